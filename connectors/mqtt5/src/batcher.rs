@@ -52,6 +52,7 @@ impl Batcher {
                 .unwrap()
                 .as_millis() as u64,
             points: self.current_batch.drain(..).collect(),
+            historical: false,
         };
 
         self.last_send = Instant::now();
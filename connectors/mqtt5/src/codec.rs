@@ -0,0 +1,84 @@
+//! 上行负载编码
+
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+
+use crate::config::{MqttMessage, PayloadCodec};
+
+impl PayloadCodec {
+    /// MQTT 5 Content Type
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json | Self::FlatJson => "application/json",
+            Self::Cbor => "application/cbor",
+            Self::MessagePack => "application/msgpack",
+        }
+    }
+
+    /// 是否每个点位单独一条消息
+    pub fn per_point(&self) -> bool {
+        matches!(self, Self::FlatJson)
+    }
+
+    /// 编码消息；`FlatJson`单点位时为裸值，多点位时为`{tag: value}`对象
+    pub fn encode(&self, message: &MqttMessage) -> Result<Vec<u8>> {
+        match self {
+            Self::Json => Ok(serde_json::to_vec(message)?),
+            Self::FlatJson => match message.points.as_slice() {
+                [point] => Ok(serde_json::to_vec(&point.value)?),
+                points => {
+                    let values: serde_json::Map<String, JsonValue> =
+                        points.iter().map(|p| (p.tag.clone(), p.value.clone())).collect();
+                    Ok(serde_json::to_vec(&values)?)
+                }
+            },
+            Self::Cbor => {
+                let mut buf = Vec::new();
+                ciborium::into_writer(message, &mut buf)?;
+                Ok(buf)
+            }
+            Self::MessagePack => Ok(rmp_serde::to_vec_named(message)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn message(points: Vec<(&str, JsonValue)>) -> MqttMessage {
+        MqttMessage {
+            device_id: "dev".to_string(),
+            timestamp: 0,
+            points: points
+                .into_iter()
+                .map(|(tag, value)| crate::config::DataPoint {
+                    tag: tag.to_string(),
+                    value,
+                    quality: 2,
+                    meta: Default::default(),
+                })
+                .collect(),
+            historical: false,
+        }
+    }
+
+    #[test]
+    fn test_msgpack_named_fields() {
+        let encoded = PayloadCodec::MessagePack.encode(&message(vec![("a.b", json!(42)), ("c", json!("on"))])).unwrap();
+        let decoded: JsonValue = rmp_serde::from_slice(&encoded).unwrap();
+        assert_eq!(decoded["device_id"], json!("dev"));
+        assert_eq!(decoded["points"][0]["tag"], json!("a.b"));
+        assert_eq!(decoded["points"][0]["value"], json!(42));
+        assert_eq!(decoded["points"][1]["value"], json!("on"));
+    }
+
+    #[test]
+    fn test_flat_json() {
+        let single = PayloadCodec::FlatJson.encode(&message(vec![("a.b", json!(42))])).unwrap();
+        assert_eq!(single, b"42");
+        let multi = PayloadCodec::FlatJson.encode(&message(vec![("a", json!(1)), ("b", json!("on"))])).unwrap();
+        assert_eq!(serde_json::from_slice::<JsonValue>(&multi).unwrap(), json!({"a": 1, "b": "on"}));
+    }
+}
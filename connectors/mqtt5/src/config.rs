@@ -55,6 +55,41 @@ pub struct MqttCfg {
     /// TLS配置
     #[serde(default)]
     pub tls: TlsCfg,
+    
+    /// 下行命令配置
+    #[serde(default)]
+    pub downlink: DownlinkCfg,
+    
+    /// 上行负载格式
+    #[serde(default)]
+    pub mode: PayloadMode,
+    
+    /// Sparkplug B配置，`mode`为`sparkplug_b`时生效
+    #[serde(default)]
+    pub sparkplug: SparkplugCfg,
+    
+    /// 断网续传配置
+    #[serde(default)]
+    pub store_forward: StoreForwardCfg,
+    
+    /// 上行路由，为空时发布到`{prefix}/data/{device}`的JSON批量消息
+    #[serde(default)]
+    pub routes: Vec<RouteCfg>,
+    
+    /// 上行点位通配模式，为空时上报全部点位
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// 上行负载格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadMode {
+    /// `MqttMessage` JSON批量消息
+    #[default]
+    Json,
+    /// Sparkplug B会话与protobuf负载
+    SparkplugB,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -68,6 +103,40 @@ pub struct BatchCfg {
     pub timeout: Duration,
 }
 
+/// 上行路由
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteCfg {
+    /// 主题模板，支持`{prefix}` `{device}` `{tag}` `{tag_path}` `{meta.<键>}`占位符
+    pub topic: String,
+    
+    /// 负载编码
+    #[serde(default)]
+    pub codec: PayloadCodec,
+    
+    /// 点位通配模式，为空时匹配全部点位
+    #[serde(default)]
+    pub tags: Vec<String>,
+    
+    /// 覆盖连接器级压缩配置
+    #[serde(default)]
+    pub compression: Option<CompressionCfg>,
+}
+
+/// 上行负载编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadCodec {
+    /// `MqttMessage` JSON
+    #[default]
+    Json,
+    /// 每个点位一条消息，负载只有JSON值
+    FlatJson,
+    /// `MqttMessage` CBOR
+    Cbor,
+    /// `MqttMessage` MessagePack
+    MessagePack,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressionCfg {
     /// 是否启用压缩
@@ -110,6 +179,83 @@ pub struct TlsCfg {
     pub verify_cert: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownlinkCfg {
+    /// 是否订阅`{topic_prefix}/{device_id}/cmd/#`下发命令
+    #[serde(default)]
+    pub enabled: bool,
+    
+    /// 命令订阅与应答发布的QoS等级
+    #[serde(default = "default_downlink_qos")]
+    pub qos: u8,
+    
+    /// 请求未携带Response Topic时的应答主题，为空时使用`{topic_prefix}/{device_id}/cmd_ack/...`
+    #[serde(default)]
+    pub response_topic: String,
+    
+    /// 命令超时后等待确认帧的宽限时间
+    #[serde(default = "default_ack_grace", with = "humantime_serde")]
+    pub ack_grace: Duration,
+    
+    /// 等待确认的命令上限
+    #[serde(default = "default_max_pending")]
+    pub max_pending: usize,
+    
+    /// 命令负载大小上限（字节）
+    #[serde(default = "default_max_payload")]
+    pub max_payload: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SparkplugCfg {
+    /// Sparkplug组ID
+    #[serde(default = "default_group_id")]
+    pub group_id: String,
+    
+    /// 边缘节点ID，为空时使用客户端ID
+    #[serde(default)]
+    pub edge_node_id: String,
+    
+    /// 用于划分Sparkplug设备的DataFrame元数据键
+    #[serde(default = "default_device_meta_key")]
+    pub device_meta_key: String,
+    
+    /// 元数据中没有设备键时归入的设备
+    #[serde(default = "default_device")]
+    pub default_device: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreForwardCfg {
+    /// 是否以WAL持久订阅作为上行数据源，收到PUBACK后才推进游标
+    #[serde(default = "default_store_forward_enabled")]
+    pub enabled: bool,
+    
+    /// 持久消费者ID，为空时使用`mqtt-{device_id}`
+    #[serde(default)]
+    pub consumer_id: String,
+    
+    /// 回放积压数据时每秒发布的消息数上限，0为不限速
+    #[serde(default = "default_catch_up_rate")]
+    pub catch_up_rate: u32,
+    
+    /// 首次启动(无游标)时从WAL中最早的帧开始回放
+    #[serde(default)]
+    pub from_earliest: bool,
+    
+    /// 同时在途、等待broker确认的上行批次上限
+    #[serde(default = "default_max_inflight")]
+    pub max_inflight: usize,
+    
+    /// 单条消息发布失败后的重试次数上限，用尽后转入死信
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+    
+    /// 死信文件路径 (JSON Lines)，为空时只记录日志
+    #[serde(default)]
+    pub dead_letter_path: String,
+}
+
 // 默认值函数
 fn default_qos() -> u8 { 2 }
 fn default_topic_prefix() -> String { "gateway".to_string() }
@@ -122,6 +268,17 @@ fn default_batch_timeout() -> Duration { Duration::from_millis(500) }
 fn default_compression_level() -> i32 { 3 }
 fn default_compression_threshold() -> usize { 1024 }
 fn default_verify_cert() -> bool { true }
+fn default_downlink_qos() -> u8 { 1 }
+fn default_ack_grace() -> Duration { Duration::from_secs(5) }
+fn default_max_pending() -> usize { 1024 }
+fn default_max_payload() -> usize { 64 * 1024 }
+fn default_group_id() -> String { "gateway".to_string() }
+fn default_device_meta_key() -> String { "device".to_string() }
+fn default_device() -> String { "default".to_string() }
+fn default_store_forward_enabled() -> bool { true }
+fn default_catch_up_rate() -> u32 { 20 }
+fn default_max_inflight() -> usize { 8 }
+fn default_max_retries() -> u32 { 5 }
 
 impl Default for MqttCfg {
     fn default() -> Self {
@@ -139,6 +296,12 @@ impl Default for MqttCfg {
             compression: CompressionCfg::default(),
             buffer_size: default_buffer_size(),
             tls: TlsCfg::default(),
+            downlink: DownlinkCfg::default(),
+            mode: PayloadMode::default(),
+            sparkplug: SparkplugCfg::default(),
+            store_forward: StoreForwardCfg::default(),
+            routes: Vec::new(),
+            tags: Vec::new(),
         }
     }
 }
@@ -175,6 +338,44 @@ impl Default for TlsCfg {
     }
 }
 
+impl Default for DownlinkCfg {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            qos: default_downlink_qos(),
+            response_topic: String::new(),
+            ack_grace: default_ack_grace(),
+            max_pending: default_max_pending(),
+            max_payload: default_max_payload(),
+        }
+    }
+}
+
+impl Default for SparkplugCfg {
+    fn default() -> Self {
+        Self {
+            group_id: default_group_id(),
+            edge_node_id: String::new(),
+            device_meta_key: default_device_meta_key(),
+            default_device: default_device(),
+        }
+    }
+}
+
+impl Default for StoreForwardCfg {
+    fn default() -> Self {
+        Self {
+            enabled: default_store_forward_enabled(),
+            consumer_id: String::new(),
+            catch_up_rate: default_catch_up_rate(),
+            from_earliest: false,
+            max_inflight: default_max_inflight(),
+            max_retries: default_max_retries(),
+            dead_letter_path: String::new(),
+        }
+    }
+}
+
 /// MQTT消息内容
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MqttMessage {
@@ -186,6 +387,10 @@ pub struct MqttMessage {
     
     /// 数据点
     pub points: Vec<DataPoint>,
+    
+    /// 是否为断网期间积压、重连后回放的历史数据
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub historical: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,8 +1,11 @@
 //! MQTT5连接器实现
 
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tokio::sync::Mutex;
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
 use tokio::time::sleep;
 use anyhow::Result;
 use serde_json::Value as JsonValue;
@@ -10,17 +13,41 @@ use uuid::Uuid;
 use base64::engine::Engine;
 
 use frame_bus::{FrameReceiver, Filter, DataFrame, Value};
-use crate::config::{MqttCfg, MqttMessage, DataPoint};
-use crate::metrics::METRICS;
-
-use rumqttc::{MqttOptions, AsyncClient, QoS, Event, Packet};
+use crate::config::{BatchCfg, MqttCfg, MqttMessage, DataPoint, PayloadMode};
+use crate::delivery::{DeliveryTracker, Receipt};
+use crate::downlink::{Downlink, Response};
+use crate::metrics::{ConnectorStats, METRICS};
+use crate::route::{Outbound, Router};
+use crate::sparkplug::{Command, Observation, SparkplugMessage, SparkplugSession};
+use crate::store_forward::{AckWindow, DeadLetter, UplinkSource};
+
+use rumqttc::Outgoing;
+use rumqttc::v5::{MqttOptions, AsyncClient, Event};
+use rumqttc::v5::mqttbytes::QoS;
+use rumqttc::v5::mqttbytes::v5::{LastWill, Packet, Publish, PublishProperties};
+
+/// 事件循环转交给start()中任务的事件
+enum Incoming {
+    Connected,
+    Disconnected,
+    Publish(Box<Publish>),
+}
 
 /// MQTT5连接器
 pub struct MqttConnector {
     cfg: MqttCfg,
     client: Option<AsyncClient>,
-    buffer: Arc<Mutex<Vec<MqttMessage>>>,
+    tracker: Arc<DeliveryTracker>,
     device_id: String,
+    downlink: Option<Arc<Downlink>>,
+    sparkplug: Option<Arc<std::sync::Mutex<SparkplugSession>>>,
+    incoming: std::sync::Mutex<Option<mpsc::UnboundedReceiver<Incoming>>>,
+    stats: Arc<ConnectorStats>,
+    /// 事件循环任务，stop()时等待DISCONNECT发出
+    eventloop: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// start()中启动的任务
+    tasks: std::sync::Mutex<Vec<tokio::task::AbortHandle>>,
+    stopping: AtomicBool,
 }
 
 impl MqttConnector {
@@ -31,11 +58,26 @@ impl MqttConnector {
             cfg.client_id.clone()
         };
 
+        let sparkplug = (cfg.mode == PayloadMode::SparkplugB)
+            .then(|| Arc::new(std::sync::Mutex::new(SparkplugSession::new(cfg.sparkplug.clone(), &device_id))));
+        if sparkplug.is_some() && cfg.downlink.enabled {
+            tracing::warn!("MQTT downlink is ignored in Sparkplug B mode, commands arrive as DCMD");
+        }
+        let downlink = (cfg.downlink.enabled && sparkplug.is_none())
+            .then(|| Arc::new(Downlink::new(&cfg.topic_prefix, &device_id, cfg.downlink.clone())));
+
         Self {
             cfg,
             client: None,
-            buffer: Arc::new(Mutex::new(Vec::new())),
+            tracker: Arc::new(DeliveryTracker::new()),
             device_id,
+            downlink,
+            sparkplug,
+            incoming: std::sync::Mutex::new(None),
+            stats: Arc::new(ConnectorStats::default()),
+            eventloop: std::sync::Mutex::new(None),
+            tasks: std::sync::Mutex::new(Vec::new()),
+            stopping: AtomicBool::new(false),
         }
     }
 
@@ -59,30 +101,112 @@ impl MqttConnector {
             self.configure_tls(&mut mqttoptions).await?;
         }
 
+        // Sparkplug B以NDEATH作为遗嘱
+        if let Some(session) = &self.sparkplug {
+            mqttoptions.set_clean_start(true);
+            mqttoptions.set_last_will(death_will(&session.lock().unwrap()));
+        }
+
         let (client, mut eventloop) = AsyncClient::new(mqttoptions, 10);
-        self.client = Some(client);
+        self.client = Some(client.clone());
+
+        // 下行命令交给start()中的任务处理，事件循环本身不阻塞
+        let mut subscriptions = Vec::new();
+        if let Some(downlink) = &self.downlink {
+            subscriptions.push((downlink.subscription(), qos_level(self.cfg.downlink.qos)));
+        }
+        if let Some(session) = &self.sparkplug {
+            let session = session.lock().unwrap();
+            subscriptions.extend(session.command_subscriptions().into_iter().map(|t| (t, QoS::AtLeastOnce)));
+        }
+        let incoming_tx = (!subscriptions.is_empty()).then(|| {
+            let (tx, rx) = mpsc::unbounded_channel();
+            *self.incoming.lock().unwrap() = Some(rx);
+            tx
+        });
+        let sparkplug = self.sparkplug.clone();
+        let tracker = self.tracker.clone();
+        let stats = self.stats.clone();
 
         // 启动事件循环处理
-        tokio::spawn(async move {
+        let eventloop_task = tokio::spawn(async move {
+            let mut online = false;
             loop {
-                match eventloop.poll().await {
+                let lost = match eventloop.poll().await {
                     Ok(Event::Incoming(Packet::ConnAck(_))) => {
                         METRICS.connect_total.inc();
                         tracing::info!("MQTT connected");
+                        online = true;
+                        tracker.on_connected();
+                        stats.set_connected(true);
+                        // 每次(重)连接后重新订阅命令主题
+                        for (filter, qos) in &subscriptions {
+                            if let Err(e) = client.try_subscribe(filter.as_str(), *qos) {
+                                tracing::error!("Failed to subscribe to {}: {}", filter, e);
+                            }
+                        }
+                        if let Some(tx) = &incoming_tx {
+                            let _ = tx.send(Incoming::Connected);
+                        }
+                        false
                     }
-                    Ok(Event::Incoming(Packet::Disconnect)) => {
+                    Ok(Event::Incoming(Packet::Publish(publish))) => {
+                        if let Some(tx) = &incoming_tx {
+                            let _ = tx.send(Incoming::Publish(Box::new(publish)));
+                        }
+                        false
+                    }
+                    Ok(Event::Outgoing(Outgoing::Publish(pkid))) => {
+                        tracker.on_outgoing(pkid);
+                        false
+                    }
+                    Ok(Event::Outgoing(Outgoing::AwaitAck(pkid))) => {
+                        tracker.on_await_ack(pkid);
+                        false
+                    }
+                    // stop()主动断开
+                    Ok(Event::Outgoing(Outgoing::Disconnect)) => break,
+                    // 拒绝的确认以连接错误上报，这里只有成功的确认
+                    Ok(Event::Incoming(Packet::PubAck(ack))) => {
+                        tracker.on_ack(ack.pkid);
+                        false
+                    }
+                    Ok(Event::Incoming(Packet::PubComp(comp))) => {
+                        tracker.on_ack(comp.pkid);
+                        false
+                    }
+                    Ok(Event::Incoming(Packet::Disconnect(_))) => {
                         METRICS.disconnect_total.inc();
                         tracing::warn!("MQTT disconnected");
+                        true
                     }
                     Err(e) => {
                         tracing::error!("MQTT connection error: {}", e);
                         METRICS.reconnect_total.inc();
                         sleep(Duration::from_secs(5)).await;
+                        true
+                    }
+                    _ => false,
+                };
+
+                if lost && online {
+                    online = false;
+                    tracker.on_disconnected();
+                    stats.set_connected(false);
+                    if let Some(tx) = &incoming_tx {
+                        let _ = tx.send(Incoming::Disconnected);
+                    }
+                    // 新会话使用递增后的bdSeq
+                    if let Some(session) = &sparkplug {
+                        let mut session = session.lock().unwrap();
+                        session.next_session();
+                        eventloop.options.set_last_will(death_will(&session));
                     }
-                    _ => {}
                 }
             }
+            stats.set_connected(false);
         });
+        *self.eventloop.lock().unwrap() = Some(eventloop_task);
 
         tracing::info!("MQTT5 connector initialized with device_id: {}", self.device_id);
         Ok(())
@@ -92,163 +216,526 @@ impl MqttConnector {
     pub async fn start(&self) -> Result<()> {
         tracing::info!("Starting MQTT5 connector");
 
-        // 订阅DataFrame
-        let rx = frame_bus::subscribe(Filter::data_only())?;
-        let buffer1 = self.buffer.clone();
-        let buffer2 = self.buffer.clone();
-        let cfg1 = self.cfg.clone();
-        let cfg2 = self.cfg.clone();
-        let device_id = self.device_id.clone();
-        let client = self.client.as_ref().unwrap().clone();
+        if let Some(session) = &self.sparkplug {
+            return self.start_sparkplug(session.clone()).await;
+        }
 
-        // 启动数据处理任务
-        let data_task = tokio::spawn(async move {
-            Self::process_frames(rx, buffer1, device_id, cfg1.batch).await
-        });
+        // 上行数据以WAL持久订阅为源，broker确认后才推进游标
+        let source = UplinkSource::open(&self.cfg.store_forward, &self.device_id, self.data_filter()?)?;
+        if source.is_durable() {
+            let consumer = crate::store_forward::consumer_id(&self.cfg.store_forward, &self.device_id);
+            tracing::info!("MQTT uplink resuming from WAL as consumer '{}'", consumer);
+        }
+        let router = Router::new(&self.cfg, &self.device_id)?;
+        let uplink_task = tokio::spawn(Self::process_uplink(
+            self.client.as_ref().unwrap().clone(),
+            self.tracker.clone(),
+            self.stats.clone(),
+            router,
+            self.cfg.clone(),
+            self.device_id.clone(),
+            source,
+        ));
+
+        // 启动下行命令任务
+        let command_task = match (&self.downlink, self.incoming.lock().unwrap().take()) {
+            (Some(downlink), Some(commands)) => {
+                let acks = frame_bus::subscribe_as(&self.device_id, Filter::cmd_ack_only())?;
+                let publisher = frame_bus::publisher(&self.device_id)?;
+                let client = self.client.as_ref().unwrap().clone();
+                let qos = qos_level(self.cfg.downlink.qos);
+                tokio::spawn(Self::process_commands(client, self.tracker.clone(), qos, downlink.clone(), publisher, commands, acks))
+            }
+            _ => tokio::spawn(std::future::pending()),
+        };
 
-        // 启动批量发送任务
-        let send_task = tokio::spawn(async move {
-            Self::batch_sender(client, buffer2, cfg2).await
-        });
+        self.tasks.lock().unwrap().extend([uplink_task.abort_handle(), command_task.abort_handle()]);
+
+        // 等待任务完成（stop()之前会一直运行）
+        let (task, result) = tokio::select! {
+            result = uplink_task => ("Uplink", result),
+            result = command_task => ("Command", result),
+        };
+        if !self.stopping.load(Ordering::SeqCst) {
+            tracing::error!("{} task ended: {:?}", task, result);
+        }
+
+        Ok(())
+    }
 
-        // 等待任务完成（实际上会一直运行）
-        tokio::select! {
-            result = data_task => {
-                tracing::error!("Data processing task ended: {:?}", result);
+    /// 停止连接器：结束上行与命令任务，发出DISCONNECT后结束事件循环
+    ///
+    /// Sparkplug B模式下先发布NDEATH，正常断开时broker不会发送遗嘱。
+    pub async fn stop(&self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        for task in self.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+
+        if let Some(client) = &self.client {
+            if let Some(session) = &self.sparkplug {
+                let death = session.lock().unwrap().death_certificate();
+                let _ = self.tracker.publish(client, death.topic, QoS::AtLeastOnce, death.payload, None).await;
             }
-            result = send_task => {
-                tracing::error!("Batch sender task ended: {:?}", result);
+            let _ = client.disconnect().await;
+        }
+
+        let eventloop = self.eventloop.lock().unwrap().take();
+        if let Some(mut eventloop) = eventloop {
+            if tokio::time::timeout(Duration::from_secs(1), &mut eventloop).await.is_err() {
+                eventloop.abort();
             }
         }
+        self.stats.set_connected(false);
+        tracing::info!("MQTT5 connector {} stopped", self.device_id);
+    }
 
-        Ok(())
+    /// 设备ID，同时作为MQTT客户端ID
+    pub fn device_id(&self) -> &str {
+        &self.device_id
     }
 
-    /// 处理接收到的DataFrame
-    async fn process_frames(
-        mut rx: FrameReceiver,
-        buffer: Arc<Mutex<Vec<MqttMessage>>>,
+    /// 本实例的统计
+    pub fn stats(&self) -> Arc<ConnectorStats> {
+        self.stats.clone()
+    }
+
+    /// 上行订阅过滤器
+    fn data_filter(&self) -> Result<Filter> {
+        if self.cfg.tags.is_empty() {
+            Ok(Filter::data_only())
+        } else {
+            Ok(Filter::And(vec![Filter::data_only(), Filter::tag_patterns(&self.cfg.tags)?]))
+        }
+    }
+
+    /// 上行任务：按批经路由发布数据帧，broker确认后推进持久游标
+    ///
+    /// 最多`max_inflight`个批次同时在途，游标推进到连续完成的批次前缀。
+    /// 回放的历史数据与实时数据不混在同一批次，历史批次按`catch_up_rate`限速。
+    async fn process_uplink(
+        client: AsyncClient,
+        tracker: Arc<DeliveryTracker>,
+        stats: Arc<ConnectorStats>,
+        router: Router,
+        cfg: MqttCfg,
         device_id: String,
-        batch_cfg: crate::config::BatchCfg,
+        mut source: UplinkSource,
+    ) {
+        let catch_up = (cfg.store_forward.catch_up_rate > 0)
+            .then(|| Duration::from_secs(1) / cfg.store_forward.catch_up_rate);
+        let max_inflight = cfg.store_forward.max_inflight.max(1);
+        let dead_letter = DeadLetter::new(&cfg.store_forward);
+        let cfg = Arc::new(cfg);
+        let mut deliveries = JoinSet::new();
+        let mut batches = HashMap::new();
+        let mut window = AckWindow::default();
+        let mut points = Vec::new();
+        let mut historical = false;
+        let mut last_seq = None;
+        let mut carry = None;
+        let mut deadline = tokio::time::Instant::now() + cfg.batch.timeout;
+
+        loop {
+            while let Some(result) = deliveries.try_join_next_with_id() {
+                Self::complete_batch(&mut window, &mut batches, &mut source, result);
+            }
+            while window.len() >= max_inflight {
+                let Some(result) = deliveries.join_next_with_id().await else { break };
+                Self::complete_batch(&mut window, &mut batches, &mut source, result);
+            }
+            METRICS.buffer_used.set(window.len() as i64);
+
+            let next = match carry.take() {
+                Some(next) => Some(next),
+                None => match tokio::time::timeout_at(deadline, source.recv()).await {
+                    Ok(Ok(next)) => Some(next),
+                    Ok(Err(e)) => {
+                        tracing::error!("MQTT uplink source failed: {}", e);
+                        break;
+                    }
+                    Err(_) => None,
+                },
+            };
+
+            if let Some((envelope, replayed)) = next {
+                if points.is_empty() || replayed == historical {
+                    historical = replayed;
+                    last_seq = Some(envelope.seq);
+                    // 解码 DataFrame；失败则跳过当前包
+                    match envelope.into_data() {
+                        Ok(frame) => points.push(Self::data_point(frame)),
+                        Err(e) => tracing::warn!("Failed to decode DataFrame from envelope: {}", e),
+                    }
+                    if points.len() < cfg.batch.size {
+                        continue;
+                    }
+                } else {
+                    carry = Some((envelope, replayed));
+                }
+            }
+
+            if !points.is_empty() {
+                let message = MqttMessage {
+                    device_id: device_id.clone(),
+                    timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+                    points: std::mem::take(&mut points),
+                    historical,
+                };
+                let batch = window.push(last_seq.take());
+                let task = deliveries.spawn(Self::deliver_batch(
+                    client.clone(),
+                    tracker.clone(),
+                    stats.clone(),
+                    router.route(&message),
+                    historical,
+                    cfg.clone(),
+                    dead_letter.clone(),
+                ));
+                batches.insert(task.id(), batch);
+
+                if historical {
+                    METRICS.historical_total.inc();
+                    if let Some(interval) = catch_up {
+                        sleep(interval).await;
+                    }
+                }
+            }
+            deadline = tokio::time::Instant::now() + cfg.batch.timeout;
+        }
+    }
+
+    /// 记录完成的批次，并把游标推进到连续完成的前缀
+    fn complete_batch(
+        window: &mut AckWindow,
+        batches: &mut HashMap<tokio::task::Id, u64>,
+        source: &mut UplinkSource,
+        result: Result<(tokio::task::Id, ()), tokio::task::JoinError>,
     ) {
-        let mut current_batch = Vec::new();
-        let mut last_send = Instant::now();
+        let task = match result {
+            Ok((task, ())) => task,
+            Err(e) => {
+                tracing::error!("MQTT uplink delivery task failed: {}", e);
+                e.id()
+            }
+        };
+        if let Some(batch) = batches.remove(&task) {
+            window.complete(batch);
+        }
+        if let Some(seq) = window.advance() {
+            if let Err(e) = source.ack(seq) {
+                tracing::error!("Failed to advance MQTT uplink cursor to {}: {}", seq, e);
+            }
+        }
+    }
 
-        while let Ok(envelope) = rx.recv().await {
-            // 解码 DataFrame；失败则跳过当前包
-            let frame = match envelope.into_data() {
-                Ok(f) => f,
+    fn data_point(frame: DataFrame) -> DataPoint {
+        DataPoint {
+            tag: frame.tag,
+            value: Self::frame_value_to_json(frame.value.as_ref().unwrap_or(&Value::int(0))),
+            quality: frame.qos as u8,
+            meta: frame.meta,
+        }
+    }
+
+    /// 并发投递一个批次路由出的全部消息，全部确认或转入死信后返回
+    async fn deliver_batch(
+        client: AsyncClient,
+        tracker: Arc<DeliveryTracker>,
+        stats: Arc<ConnectorStats>,
+        outbounds: Vec<Outbound>,
+        historical: bool,
+        cfg: Arc<MqttCfg>,
+        dead_letter: DeadLetter,
+    ) {
+        let mut publishes = JoinSet::new();
+        for outbound in outbounds {
+            let (client, tracker, stats) = (client.clone(), tracker.clone(), stats.clone());
+            let (cfg, dead_letter) = (cfg.clone(), dead_letter.clone());
+            publishes.spawn(async move {
+                Self::deliver(&client, &tracker, &stats, &outbound, historical, &cfg, &dead_letter).await
+            });
+        }
+        while let Some(result) = publishes.join_next().await {
+            if let Err(e) = result {
+                tracing::error!("MQTT publish task failed: {}", e);
+            }
+        }
+    }
+
+    /// 发布消息直至broker确认，重试`max_retries`次仍失败时转入死信
+    async fn deliver(
+        client: &AsyncClient,
+        tracker: &DeliveryTracker,
+        stats: &ConnectorStats,
+        outbound: &Outbound,
+        historical: bool,
+        cfg: &MqttCfg,
+        dead_letter: &DeadLetter,
+    ) {
+        let start = Instant::now();
+        let attempts = cfg.store_forward.max_retries.saturating_add(1);
+        for attempt in 1..=attempts {
+            let delivered = match Self::send_message(client, tracker, outbound, historical, qos_level(cfg.qos)).await {
+                Ok(receipt) => Self::await_receipt(tracker, receipt, cfg.timeout).await,
                 Err(e) => {
-                    tracing::warn!("Failed to decode DataFrame from envelope: {}", e);
-                    continue;
+                    tracing::error!("Failed to send message: {}", e);
+                    false
                 }
             };
+            if delivered {
+                METRICS.publish_total.inc();
+                METRICS.publish_latency.observe(start.elapsed().as_millis() as f64);
+                stats.record_delivery(outbound.points, historical);
+                return;
+            }
+            METRICS.publish_error_total.inc();
+            stats.record_error();
+            if attempt < attempts {
+                sleep(cfg.reconnect).await;
+            }
+        }
 
-            let timestamp = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_millis() as u64;
+        tracing::error!("MQTT message to '{}' not delivered after {} attempts, moving to dead letter", outbound.topic, attempts);
+        METRICS.dead_letter_total.inc();
+        if let Err(e) = dead_letter.write(outbound, historical, attempts) {
+            tracing::error!("Failed to write MQTT dead letter: {}", e);
+        }
+    }
 
-            let point = DataPoint {
-                tag: frame.tag,
-                value: Self::frame_value_to_json(frame.value.as_ref().unwrap_or(&Value::int(0))),
-                quality: frame.qos as u8,
-                meta: frame.meta,
-            };
+    /// 等待broker确认；离线期间由rumqttc在重连后重发，在线超时未确认则视为失败
+    async fn await_receipt(tracker: &DeliveryTracker, mut receipt: Receipt, timeout: Duration) -> bool {
+        loop {
+            match tokio::time::timeout(timeout, &mut receipt).await {
+                Ok(result) => return result.unwrap_or(false),
+                Err(_) if !tracker.is_connected() => continue,
+                Err(_) => {
+                    tracing::warn!("MQTT publish not acknowledged within {:?}, resending", timeout);
+                    return false;
+                }
+            }
+        }
+    }
 
-            current_batch.push(point);
+    /// 处理下行命令：校验后发布CmdFrame，总线确认帧回发到应答主题
+    async fn process_commands(
+        client: AsyncClient,
+        tracker: Arc<DeliveryTracker>,
+        qos: QoS,
+        downlink: Arc<Downlink>,
+        publisher: frame_bus::FramePublisher,
+        mut commands: mpsc::UnboundedReceiver<Incoming>,
+        mut acks: FrameReceiver,
+    ) {
+        let mut expiry = tokio::time::interval(Duration::from_secs(1));
 
-            // 检查是否需要发送批次
-            let should_send = current_batch.len() >= batch_cfg.size
-                || last_send.elapsed() >= batch_cfg.timeout;
+        loop {
+            let responses = tokio::select! {
+                event = commands.recv() => {
+                    let publish = match event {
+                        Some(Incoming::Publish(publish)) => publish,
+                        Some(_) => continue,
+                        None => break,
+                    };
+                    let topic = String::from_utf8_lossy(&publish.topic);
+                    match downlink.on_publish(&topic, &publish.payload, publish.properties.as_ref()) {
+                        Ok(frame) => {
+                            let cmd_id = frame.cmd_id;
+                            match publisher.send_cmd(frame) {
+                                Ok(()) => Vec::new(),
+                                Err(e) => downlink.fail(cmd_id, &e.to_string()).into_iter().collect(),
+                            }
+                        }
+                        Err(rejected) => vec![rejected],
+                    }
+                }
+                ack = acks.recv() => match ack {
+                    Ok(envelope) => match envelope.into_cmd_ack() {
+                        Ok(ack) => downlink.on_ack(&ack).into_iter().collect(),
+                        Err(e) => {
+                            tracing::warn!("Failed to decode CmdAckFrame from envelope: {}", e);
+                            Vec::new()
+                        }
+                    },
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        tracing::warn!("Command ack subscriber lagged, {} frames dropped", n);
+                        Vec::new()
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                },
+                _ = expiry.tick() => downlink.expire(Instant::now()),
+            };
 
-            if should_send && !current_batch.is_empty() {
-                let message = MqttMessage {
-                    device_id: device_id.clone(),
-                    timestamp,
-                    points: current_batch.drain(..).collect(),
-                };
+            for response in responses {
+                Self::send_response(&client, &tracker, qos, response).await;
+            }
+        }
+    }
 
-                let mut buffer_guard = buffer.lock().await;
-                buffer_guard.push(message);
-                METRICS.buffer_used.set(buffer_guard.len() as i64);
-                drop(buffer_guard);
+    /// 发布命令应答
+    async fn send_response(client: &AsyncClient, tracker: &DeliveryTracker, qos: QoS, response: Response) {
+        let properties = response.properties();
+        match tracker.publish(client, response.topic, qos, response.payload, Some(properties)).await {
+            Ok(_) => METRICS.publish_total.inc(),
+            Err(e) => {
+                tracing::error!("Failed to send command response: {}", e);
+                METRICS.publish_error_total.inc();
+            }
+        }
+    }
 
-                last_send = Instant::now();
+    /// 以Sparkplug B会话代替JSON批量上报
+    async fn start_sparkplug(&self, session: Arc<std::sync::Mutex<SparkplugSession>>) -> Result<()> {
+        // 以最新值缓存中的点位作为初始BIRTH内容，快照之后的帧无缝衔接
+        let (snapshot, rx) = frame_bus::subscribe_with_snapshot_as(&self.device_id, self.data_filter()?)?;
+        {
+            let mut session = session.lock().unwrap();
+            for (tag, value) in &snapshot.values {
+                session.observe(&value.to_frame(tag.as_str()));
             }
         }
+
+        let incoming = self.incoming.lock().unwrap().take()
+            .ok_or_else(|| anyhow::anyhow!("MQTT connector not initialized"))?;
+        let publisher = frame_bus::publisher(&self.device_id)?;
+        let client = self.client.as_ref().unwrap().clone();
+
+        let tracker = self.tracker.clone();
+        let task = tokio::spawn(Self::process_sparkplug(client, tracker, session, self.cfg.batch.clone(), publisher, incoming, rx));
+        self.tasks.lock().unwrap().push(task.abort_handle());
+        let result = task.await;
+        if !self.stopping.load(Ordering::SeqCst) {
+            tracing::error!("Sparkplug task ended: {:?}", result);
+        }
+        Ok(())
     }
 
-    /// 批量发送器
-    async fn batch_sender(
+    /// Sparkplug会话任务：连接后BIRTH，按例外报告DATA，处理NCMD/DCMD
+    async fn process_sparkplug(
         client: AsyncClient,
-        buffer: Arc<Mutex<Vec<MqttMessage>>>,
-        cfg: MqttCfg,
+        tracker: Arc<DeliveryTracker>,
+        session: Arc<std::sync::Mutex<SparkplugSession>>,
+        batch: BatchCfg,
+        publisher: frame_bus::FramePublisher,
+        mut incoming: mpsc::UnboundedReceiver<Incoming>,
+        mut rx: FrameReceiver,
     ) {
-        loop {
-            sleep(cfg.batch.timeout / 2).await;
+        let mut online = false;
+        let mut flush = tokio::time::interval(batch.timeout);
 
-            let messages = {
-                let mut buffer_guard = buffer.lock().await;
-                if buffer_guard.is_empty() {
-                    continue;
+        loop {
+            let messages = tokio::select! {
+                event = incoming.recv() => match event {
+                    Some(Incoming::Connected) => {
+                        online = true;
+                        session.lock().unwrap().births()
+                    }
+                    Some(Incoming::Disconnected) => {
+                        online = false;
+                        Vec::new()
+                    }
+                    Some(Incoming::Publish(publish)) => {
+                        let topic = String::from_utf8_lossy(&publish.topic);
+                        let command = session.lock().unwrap().on_command(&topic, &publish.payload);
+                        match command {
+                            Ok(Some(Command::Rebirth)) if online => {
+                                tracing::info!("Sparkplug rebirth requested");
+                                session.lock().unwrap().births()
+                            }
+                            Ok(Some(Command::Write(frames))) => {
+                                for frame in frames {
+                                    METRICS.command_total.inc();
+                                    if let Err(e) = publisher.send_cmd(frame) {
+                                        tracing::warn!("Failed to publish Sparkplug command: {}", e);
+                                        METRICS.command_reject_total.inc();
+                                    }
+                                }
+                                Vec::new()
+                            }
+                            Ok(_) => Vec::new(),
+                            Err(e) => {
+                                tracing::warn!("Rejected Sparkplug command on '{}': {}", topic, e);
+                                METRICS.command_reject_total.inc();
+                                Vec::new()
+                            }
+                        }
+                    }
+                    None => break,
+                },
+                frame = rx.recv() => match frame {
+                    Ok(envelope) => match envelope.into_data() {
+                        Ok(frame) => {
+                            let mut session = session.lock().unwrap();
+                            // 新点位排队到下一次刷新周期，由所属设备的DBIRTH声明
+                            match session.observe(&frame) {
+                                Observation::Changed if online && session.pending() >= batch.size => session.flush_data(),
+                                _ => Vec::new(),
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!("Failed to decode DataFrame from envelope: {}", e);
+                            Vec::new()
+                        }
+                    },
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                        // 丢帧后在下一次刷新时重新DBIRTH以同步全部点位
+                        tracing::warn!("Sparkplug subscriber lagged, {} frames dropped", n);
+                        session.lock().unwrap().rebirth_devices();
+                        Vec::new()
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                },
+                _ = flush.tick() => {
+                    // 离线期间的变化与新点位由重连后的BIRTH携带
+                    let messages = session.lock().unwrap().flush();
+                    if online { messages } else { Vec::new() }
                 }
-                let messages = buffer_guard.drain(..).collect::<Vec<_>>();
-                METRICS.buffer_used.set(0);
-                messages
             };
 
             for message in messages {
-                if let Err(e) = Self::send_message(&client, &message, &cfg).await {
-                    tracing::error!("Failed to send message: {}", e);
-                    METRICS.publish_error_total.inc();
-                    
-                    // 重新放回缓冲区
-                    let mut buffer_guard = buffer.lock().await;
-                    buffer_guard.push(message);
-                    METRICS.buffer_used.set(buffer_guard.len() as i64);
-                } else {
-                    METRICS.publish_total.inc();
-                }
+                Self::send_sparkplug(&client, &tracker, message).await;
+            }
+        }
+    }
+
+    /// 发布Sparkplug消息，BIRTH与DATA均为QoS0
+    async fn send_sparkplug(client: &AsyncClient, tracker: &DeliveryTracker, message: SparkplugMessage) {
+        METRICS.message_size.observe(message.payload.len() as f64);
+        match tracker.publish(client, message.topic, QoS::AtMostOnce, message.payload, None).await {
+            Ok(_) => METRICS.publish_total.inc(),
+            Err(e) => {
+                tracing::error!("Failed to send Sparkplug message: {}", e);
+                METRICS.publish_error_total.inc();
             }
         }
     }
 
-    /// 发送单个消息
+    /// 发送单个消息，返回broker确认回执
     async fn send_message(
         client: &AsyncClient,
-        message: &MqttMessage,
-        cfg: &MqttCfg,
-    ) -> Result<()> {
-        let start = Instant::now();
-        
-        let json_data = serde_json::to_vec(message)?;
-        let payload = if cfg.compression.enabled && json_data.len() > cfg.compression.threshold {
-            let compressed = zstd::encode_all(&json_data[..], cfg.compression.level)?;
-            let ratio = compressed.len() as f64 / json_data.len() as f64;
-            METRICS.compression_ratio.observe(ratio);
-            compressed
-        } else {
-            json_data
-        };
-
-        let topic = format!("{}/data/{}", cfg.topic_prefix, message.device_id);
-        let qos = match cfg.qos {
-            0 => QoS::AtMostOnce,
-            1 => QoS::AtLeastOnce,
-            2 => QoS::ExactlyOnce,
-            _ => QoS::ExactlyOnce,
+        tracker: &DeliveryTracker,
+        outbound: &Outbound,
+        historical: bool,
+        qos: QoS,
+    ) -> Result<Receipt> {
+        METRICS.message_size.observe(outbound.payload.len() as f64);
+        METRICS.batch_size.observe(outbound.points as f64);
+
+        // 编码、压缩与历史数据标记放在属性中，云端无需解析负载即可区分
+        let mut user_properties = Vec::new();
+        if outbound.compressed {
+            user_properties.push(("content-encoding".to_string(), "zstd".to_string()));
+        }
+        if historical {
+            user_properties.push(("historical".to_string(), "true".to_string()));
+        }
+        let properties = PublishProperties {
+            content_type: Some(outbound.codec.content_type().to_string()),
+            user_properties,
+            ..Default::default()
         };
 
-        METRICS.message_size.observe(payload.len() as f64);
-        METRICS.batch_size.observe(message.points.len() as f64);
-
-        client.publish(topic, qos, false, payload).await?;
-
-        let latency = start.elapsed().as_millis() as f64;
-        METRICS.publish_latency.observe(latency);
-
-        Ok(())
+        tracker.publish(client, outbound.topic.clone(), qos, outbound.payload.clone(), Some(properties)).await
     }
 
     /// 配置TLS
@@ -273,7 +760,7 @@ impl MqttConnector {
     }
 
     /// 转换Frame值为JSON
-    fn frame_value_to_json(value: &Value) -> JsonValue {
+    pub(crate) fn frame_value_to_json(value: &Value) -> JsonValue {
         use frame_bus::envelope::value::Value as ValueEnum;
         match &value.value {
             Some(ValueEnum::BoolV(b)) => JsonValue::Bool(*b),
@@ -290,3 +777,29 @@ impl MqttConnector {
     }
 }
 
+impl Drop for MqttConnector {
+    /// 未经stop()直接丢弃时也结束后台任务
+    fn drop(&mut self) {
+        for task in self.tasks.get_mut().unwrap().drain(..) {
+            task.abort();
+        }
+        if let Some(eventloop) = self.eventloop.get_mut().unwrap().take() {
+            eventloop.abort();
+        }
+    }
+}
+
+/// QoS等级映射，非法值按QoS2处理
+fn qos_level(level: u8) -> QoS {
+    match level {
+        0 => QoS::AtMostOnce,
+        1 => QoS::AtLeastOnce,
+        _ => QoS::ExactlyOnce,
+    }
+}
+
+/// NDEATH遗嘱，QoS1且不保留
+fn death_will(session: &SparkplugSession) -> LastWill {
+    let death = session.death_certificate();
+    LastWill::new(death.topic, death.payload, QoS::AtLeastOnce, false, None)
+}
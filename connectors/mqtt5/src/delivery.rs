@@ -0,0 +1,225 @@
+//! 发布确认追踪
+//!
+//! rumqttc的publish()只把请求放入事件循环队列，包ID在事件循环中分配，
+//! 只能从`Outgoing::Publish(pkid)`事件得知。所有发布经由`DeliveryTracker`
+//! 按调用顺序排队，事件循环发出时把队首与包ID对应起来，收到PUBACK/PUBCOMP
+//! 后完成回执。断线时在途的发布在重连后以原包ID重发，不会再次出队；
+//! 重连后没有重发的发布(被broker拒绝)在第一条新发布发出时判为失败。
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use rumqttc::v5::mqttbytes::v5::PublishProperties;
+use rumqttc::v5::mqttbytes::QoS;
+use rumqttc::v5::AsyncClient;
+use tokio::sync::oneshot;
+
+/// 发布回执，broker确认时为true；QoS0在发出时即完成
+pub type Receipt = oneshot::Receiver<bool>;
+
+/// 发布确认追踪器
+#[derive(Default)]
+pub struct DeliveryTracker {
+    /// 保证入队顺序与请求进入rumqttc队列的顺序一致
+    order: tokio::sync::Mutex<()>,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    /// 已提交、尚未被事件循环发出的发布
+    queued: VecDeque<oneshot::Sender<bool>>,
+    /// 已发出等待确认的发布
+    inflight: HashMap<u16, oneshot::Sender<bool>>,
+    /// 断线时在途、等待重连后重发的发布
+    resend: HashMap<u16, oneshot::Sender<bool>>,
+    /// 包ID冲突、等待旧发布确认后才发出的发布
+    collision: Option<(u16, oneshot::Sender<bool>)>,
+    /// 冲突发布发出时旧发布已确认，随后到达的确认不属于新发布
+    acked_early: HashSet<u16>,
+    connected: bool,
+}
+
+impl DeliveryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 发布消息并返回确认回执
+    pub async fn publish(
+        &self,
+        client: &AsyncClient,
+        topic: String,
+        qos: QoS,
+        payload: Vec<u8>,
+        properties: Option<PublishProperties>,
+    ) -> Result<Receipt> {
+        let _order = self.order.lock().await;
+        let receipt = self.enqueue();
+        let result = match properties {
+            Some(properties) => client.publish_with_properties(topic, qos, false, payload, properties).await,
+            None => client.publish(topic, qos, false, payload).await,
+        };
+        if let Err(e) = result {
+            // 请求未进入队列，撤回刚入队的回执
+            self.state.lock().unwrap().queued.pop_back();
+            return Err(e.into());
+        }
+        Ok(receipt)
+    }
+
+    fn enqueue(&self) -> Receipt {
+        let (tx, rx) = oneshot::channel();
+        self.state.lock().unwrap().queued.push_back(tx);
+        rx
+    }
+
+    /// 是否与broker保持连接
+    pub fn is_connected(&self) -> bool {
+        self.state.lock().unwrap().connected
+    }
+
+    /// 收到CONNACK
+    pub fn on_connected(&self) {
+        self.state.lock().unwrap().connected = true;
+    }
+
+    /// 连接断开，在途发布转入重发等待
+    pub fn on_disconnected(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.connected = false;
+        let inflight = std::mem::take(&mut state.inflight);
+        state.resend.extend(inflight);
+    }
+
+    /// 事件循环发出PUBLISH
+    pub fn on_outgoing(&self, pkid: u16) {
+        let mut state = self.state.lock().unwrap();
+
+        if state.collision.as_ref().is_some_and(|(id, _)| *id == pkid) {
+            let (_, tx) = state.collision.take().unwrap();
+            if let Some(old) = state.inflight.insert(pkid, tx) {
+                let _ = old.send(true);
+            }
+            state.acked_early.insert(pkid);
+            return;
+        }
+
+        if let Some(tx) = state.resend.remove(&pkid) {
+            state.inflight.insert(pkid, tx);
+            return;
+        }
+
+        // 重发总在新发布之前，此时仍未重发的已被broker拒绝
+        for (_, tx) in state.resend.drain() {
+            let _ = tx.send(false);
+        }
+
+        let Some(tx) = state.queued.pop_front() else {
+            tracing::warn!("Untracked MQTT publish with packet id {}", pkid);
+            return;
+        };
+        if pkid == 0 {
+            let _ = tx.send(true);
+        } else if let Some(stale) = state.inflight.insert(pkid, tx) {
+            let _ = stale.send(false);
+        }
+    }
+
+    /// 包ID仍在途，发布被rumqttc暂存
+    pub fn on_await_ack(&self, pkid: u16) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(tx) = state.queued.pop_front() {
+            state.collision = Some((pkid, tx));
+        }
+    }
+
+    /// 收到PUBACK(QoS1)或PUBCOMP(QoS2)
+    pub fn on_ack(&self, pkid: u16) {
+        let mut state = self.state.lock().unwrap();
+        if state.acked_early.remove(&pkid) {
+            return;
+        }
+        let tx = state.inflight.remove(&pkid).or_else(|| state.resend.remove(&pkid));
+        if let Some(tx) = tx {
+            let _ = tx.send(true);
+        }
+    }
+
+    /// 等待确认的发布数
+    pub fn pending(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.queued.len() + state.inflight.len() + state.resend.len() + usize::from(state.collision.is_some())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::oneshot::error::TryRecvError;
+
+    #[test]
+    fn test_receipts_follow_packet_ids() {
+        let tracker = DeliveryTracker::new();
+        let mut first = tracker.enqueue();
+        let mut second = tracker.enqueue();
+        let mut qos0 = tracker.enqueue();
+
+        tracker.on_outgoing(1);
+        tracker.on_outgoing(2);
+        tracker.on_outgoing(0);
+        assert_eq!(qos0.try_recv(), Ok(true));
+
+        tracker.on_ack(2);
+        assert_eq!(second.try_recv(), Ok(true));
+        assert_eq!(first.try_recv(), Err(TryRecvError::Empty));
+
+        tracker.on_ack(1);
+        assert_eq!(first.try_recv(), Ok(true));
+        assert_eq!(tracker.pending(), 0);
+    }
+
+    #[test]
+    fn test_retransmit_after_reconnect() {
+        let tracker = DeliveryTracker::new();
+        tracker.on_connected();
+        let mut resent = tracker.enqueue();
+        let mut rejected = tracker.enqueue();
+        tracker.on_outgoing(1);
+        tracker.on_outgoing(2);
+
+        // 断线期间提交的新发布排在重发之后
+        tracker.on_disconnected();
+        let mut fresh = tracker.enqueue();
+        tracker.on_connected();
+        tracker.on_outgoing(1);
+        assert_eq!(resent.try_recv(), Err(TryRecvError::Empty));
+
+        tracker.on_outgoing(3);
+        assert_eq!(rejected.try_recv(), Ok(false));
+
+        tracker.on_ack(1);
+        tracker.on_ack(3);
+        assert_eq!(resent.try_recv(), Ok(true));
+        assert_eq!(fresh.try_recv(), Ok(true));
+    }
+
+    #[test]
+    fn test_packet_id_collision() {
+        let tracker = DeliveryTracker::new();
+        let mut old = tracker.enqueue();
+        let mut new = tracker.enqueue();
+        tracker.on_outgoing(1);
+        tracker.on_await_ack(1);
+
+        // rumqttc先发出冲突的发布，再上报旧发布的PUBACK
+        tracker.on_outgoing(1);
+        tracker.on_ack(1);
+        assert_eq!(old.try_recv(), Ok(true));
+        assert_eq!(new.try_recv(), Err(TryRecvError::Empty));
+
+        tracker.on_ack(1);
+        assert_eq!(new.try_recv(), Ok(true));
+    }
+}
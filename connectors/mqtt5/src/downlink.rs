@@ -0,0 +1,394 @@
+//! MQTT下行命令
+//!
+//! 订阅`{topic_prefix}/{device_id}/cmd/#`，主题剩余层级以`.`连接作为目标点位
+//! (`gateway/dev1/cmd/plant1/valve` -> `plant1.valve`)。负载可以是JSON标量，
+//! 也可以是`{"value": .., "cmd_id": .., "priority": .., "timeout_ms": .., "meta": {..}}`。
+//!
+//! 校验通过的命令以`CmdFrame`发布到总线，总线上对应的`CmdAckFrame`回发给请求方：
+//! 请求携带MQTT 5 Response Topic时发布到该主题并带回Correlation Data，
+//! 否则发布到配置的应答主题。校验失败或超时未确认的命令同样回发失败应答。
+//!
+//! 总线命令帧使用本地生成的`cmd_id`，请求方的`cmd_id`只记录在待确认表中并写回应答，
+//! 请求方无法借`cmd_id`冒领其他命令的确认。
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+
+use frame_bus::{CmdAckFrame, CmdFrame, Value};
+use rumqttc::v5::mqttbytes::v5::PublishProperties;
+
+use crate::config::DownlinkCfg;
+use crate::connector::MqttConnector;
+use crate::metrics::METRICS;
+
+/// 总线命令帧的来源标识
+pub const COMMAND_ORIGIN: &str = "mqtt";
+
+/// 命令负载
+#[derive(Debug, Deserialize)]
+struct CommandPayload {
+    value: JsonValue,
+    #[serde(default)]
+    cmd_id: Option<u64>,
+    #[serde(default)]
+    priority: Option<i32>,
+    #[serde(default)]
+    timeout_ms: Option<u32>,
+    #[serde(default)]
+    meta: HashMap<String, String>,
+}
+
+/// 应答负载
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandResponse {
+    pub cmd_id: u64,
+    pub tag: String,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<JsonValue>,
+    #[serde(default)]
+    pub driver_id: String,
+    /// 时间戳（毫秒）
+    pub timestamp: u64,
+}
+
+/// 待发布的应答
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub correlation_data: Option<Vec<u8>>,
+}
+
+impl Response {
+    /// 应答的MQTT 5发布属性
+    pub fn properties(&self) -> PublishProperties {
+        PublishProperties {
+            correlation_data: self.correlation_data.clone().map(Into::into),
+            content_type: Some("application/json".to_string()),
+            ..Default::default()
+        }
+    }
+}
+
+/// 等待确认的命令
+struct Pending {
+    /// 请求方的命令ID
+    cmd_id: u64,
+    tag: String,
+    topic: String,
+    correlation_data: Option<Vec<u8>>,
+    deadline: Instant,
+}
+
+/// 下行命令处理器
+pub struct Downlink {
+    cfg: DownlinkCfg,
+    command_root: String,
+    ack_root: String,
+    /// 按总线命令ID索引
+    pending: Mutex<HashMap<u64, Pending>>,
+}
+
+impl Downlink {
+    pub fn new(topic_prefix: &str, device_id: &str, cfg: DownlinkCfg) -> Self {
+        Self {
+            cfg,
+            command_root: format!("{}/{}/cmd/", topic_prefix, device_id),
+            ack_root: format!("{}/{}/cmd_ack/", topic_prefix, device_id),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 命令订阅主题过滤器
+    pub fn subscription(&self) -> String {
+        format!("{}#", self.command_root)
+    }
+
+    /// 等待确认的命令数
+    pub fn pending(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// 处理下行消息：校验通过返回待发布的命令帧，否则返回拒绝应答
+    pub fn on_publish(
+        &self,
+        topic: &str,
+        payload: &[u8],
+        properties: Option<&PublishProperties>,
+    ) -> Result<CmdFrame, Response> {
+        METRICS.command_total.inc();
+
+        let suffix = topic.strip_prefix(&self.command_root).unwrap_or_default();
+        let reply_topic = properties
+            .and_then(|p| p.response_topic.clone())
+            .unwrap_or_else(|| self.default_reply_topic(suffix));
+        let correlation_data = properties
+            .and_then(|p| p.correlation_data.as_ref())
+            .map(|d| d.to_vec());
+        let tag = suffix.split('/').filter(|level| !level.is_empty()).collect::<Vec<_>>().join(".");
+
+        let reject = |cmd_id: u64, error: String| {
+            METRICS.command_reject_total.inc();
+            tracing::warn!("Rejected MQTT command on '{}': {}", topic, error);
+            Response {
+                topic: reply_topic.clone(),
+                payload: encode(&CommandResponse {
+                    cmd_id,
+                    tag: tag.clone(),
+                    success: false,
+                    error: Some(error),
+                    value: None,
+                    driver_id: String::new(),
+                    timestamp: now_ms(),
+                }),
+                correlation_data: correlation_data.clone(),
+            }
+        };
+
+        if tag.is_empty() {
+            return Err(reject(0, "command topic has no target tag".to_string()));
+        }
+        if payload.len() > self.cfg.max_payload {
+            return Err(reject(0, format!("payload of {} bytes exceeds limit", payload.len())));
+        }
+        let command = match parse_payload(payload) {
+            Ok(command) => command,
+            Err(e) => return Err(reject(0, e)),
+        };
+        let value = match json_to_value(&command.value) {
+            Ok(value) => value,
+            Err(e) => return Err(reject(command.cmd_id.unwrap_or_default(), e)),
+        };
+
+        let mut frame = CmdFrame::new(tag.clone(), value, COMMAND_ORIGIN.to_string());
+        if let Some(priority) = command.priority {
+            frame = frame.with_priority(priority);
+        }
+        if let Some(timeout_ms) = command.timeout_ms {
+            frame.timeout_ms = timeout_ms;
+        }
+        frame.meta = command.meta;
+        frame.meta.insert("ack_needed".to_string(), "true".to_string());
+
+        let mut pending = self.pending.lock().unwrap();
+        // 未指定时以总线命令ID应答
+        let cmd_id = command.cmd_id.unwrap_or(frame.cmd_id);
+        if command.cmd_id.is_some() && pending.values().any(|p| p.cmd_id == cmd_id) {
+            return Err(reject(cmd_id, "duplicate cmd_id".to_string()));
+        }
+        if pending.len() >= self.cfg.max_pending {
+            return Err(reject(cmd_id, "too many pending commands".to_string()));
+        }
+        while pending.contains_key(&frame.cmd_id) {
+            frame.cmd_id = frame.cmd_id.wrapping_add(1);
+        }
+        pending.insert(frame.cmd_id, Pending {
+            cmd_id,
+            tag,
+            topic: reply_topic.clone(),
+            correlation_data: correlation_data.clone(),
+            deadline: Instant::now() + Duration::from_millis(frame.timeout_ms as u64) + self.cfg.ack_grace,
+        });
+        Ok(frame)
+    }
+
+    /// 总线确认帧对应的应答，非本连接器下发的命令返回None
+    pub fn on_ack(&self, ack: &CmdAckFrame) -> Option<Response> {
+        let pending = self.pending.lock().unwrap().remove(&ack.cmd_id)?;
+        Some(Response {
+            topic: pending.topic,
+            payload: encode(&CommandResponse {
+                cmd_id: pending.cmd_id,
+                tag: ack.tag.clone(),
+                success: ack.success,
+                error: (!ack.success).then(|| ack.error_msg.clone()),
+                value: ack.actual_value.as_ref().map(MqttConnector::frame_value_to_json),
+                driver_id: ack.driver_id.clone(),
+                timestamp: now_ms(),
+            }),
+            correlation_data: pending.correlation_data,
+        })
+    }
+
+    /// 命令未能进入总线时的失败应答，`cmd_id`为总线命令ID
+    pub fn fail(&self, cmd_id: u64, error: &str) -> Option<Response> {
+        let pending = self.pending.lock().unwrap().remove(&cmd_id)?;
+        METRICS.command_reject_total.inc();
+        Some(failure(pending, error))
+    }
+
+    /// 超时未确认命令的失败应答
+    pub fn expire(&self, now: Instant) -> Vec<Response> {
+        let mut pending = self.pending.lock().unwrap();
+        let expired: Vec<u64> = pending.iter()
+            .filter(|(_, p)| p.deadline <= now)
+            .map(|(cmd_id, _)| *cmd_id)
+            .collect();
+        expired.into_iter()
+            .filter_map(|cmd_id| pending.remove(&cmd_id).map(|p| failure(p, "no acknowledgement before timeout")))
+            .collect()
+    }
+
+    fn default_reply_topic(&self, suffix: &str) -> String {
+        if !self.cfg.response_topic.is_empty() {
+            self.cfg.response_topic.clone()
+        } else {
+            format!("{}{}", self.ack_root, suffix)
+        }
+    }
+}
+
+fn failure(pending: Pending, error: &str) -> Response {
+    Response {
+        topic: pending.topic,
+        payload: encode(&CommandResponse {
+            cmd_id: pending.cmd_id,
+            tag: pending.tag,
+            success: false,
+            error: Some(error.to_string()),
+            value: None,
+            driver_id: String::new(),
+            timestamp: now_ms(),
+        }),
+        correlation_data: pending.correlation_data,
+    }
+}
+
+/// 解析负载：带`value`字段的对象为完整命令，其余JSON视为写入值
+fn parse_payload(payload: &[u8]) -> Result<CommandPayload, String> {
+    let json: JsonValue = serde_json::from_slice(payload).map_err(|e| format!("invalid JSON payload: {}", e))?;
+    if json.get("value").is_some() {
+        serde_json::from_value(json).map_err(|e| format!("invalid command payload: {}", e))
+    } else {
+        Ok(CommandPayload {
+            value: json,
+            cmd_id: None,
+            priority: None,
+            timeout_ms: None,
+            meta: HashMap::new(),
+        })
+    }
+}
+
+fn json_to_value(json: &JsonValue) -> Result<Value, String> {
+    match json {
+        JsonValue::Bool(b) => Ok(Value::bool(*b)),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => Ok(Value::int(i)),
+            None => n.as_f64().map(Value::float).ok_or_else(|| format!("unsupported number {}", n)),
+        },
+        JsonValue::String(s) => Ok(Value::string(s.clone())),
+        other => Err(format!("unsupported command value {}", other)),
+    }
+}
+
+fn encode(response: &CommandResponse) -> Vec<u8> {
+    serde_json::to_vec(response).unwrap_or_default()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn downlink() -> Downlink {
+        Downlink::new("site", "dev1", DownlinkCfg { enabled: true, ..Default::default() })
+    }
+
+    fn response(r: &Response) -> CommandResponse {
+        serde_json::from_slice(&r.payload).unwrap()
+    }
+
+    #[test]
+    fn test_parse_command_topic_and_payload() {
+        let downlink = downlink();
+        assert_eq!(downlink.subscription(), "site/dev1/cmd/#");
+
+        let frame = downlink.on_publish("site/dev1/cmd/plant1/valve", b"{\"value\": 42, \"cmd_id\": 7, \"priority\": 3}", None).unwrap();
+        assert_eq!(frame.tag, "plant1.valve");
+        assert_eq!(frame.priority, 3);
+        assert_eq!(frame.origin, COMMAND_ORIGIN);
+        assert_eq!(frame.value, Some(Value::int(42)));
+
+        let frame = downlink.on_publish("site/dev1/cmd/plant1.pump", b"true", None).unwrap();
+        assert_eq!(frame.tag, "plant1.pump");
+        assert_eq!(frame.value, Some(Value::bool(true)));
+        assert_eq!(downlink.pending(), 2);
+    }
+
+    #[test]
+    fn test_invalid_command_rejected() {
+        let downlink = downlink();
+        let properties = PublishProperties {
+            response_topic: Some("cloud/reply".to_string()),
+            correlation_data: Some(b"req-1".to_vec().into()),
+            ..Default::default()
+        };
+
+        let rejected = downlink.on_publish("site/dev1/cmd/valve", b"{\"value\": [1, 2]}", Some(&properties)).unwrap_err();
+        assert_eq!(rejected.topic, "cloud/reply");
+        assert_eq!(rejected.correlation_data.as_deref(), Some(&b"req-1"[..]));
+        assert!(!response(&rejected).success);
+
+        let rejected = downlink.on_publish("site/dev1/cmd/valve", b"not json", None).unwrap_err();
+        assert_eq!(rejected.topic, "site/dev1/cmd_ack/valve");
+        assert_eq!(downlink.pending(), 0);
+    }
+
+    #[test]
+    fn test_ack_and_expiry_responses() {
+        let downlink = downlink();
+        let properties = PublishProperties {
+            response_topic: Some("cloud/reply".to_string()),
+            correlation_data: Some(b"req-2".to_vec().into()),
+            ..Default::default()
+        };
+        let valve = downlink.on_publish("site/dev1/cmd/valve", b"{\"value\": 1.5, \"cmd_id\": 1}", Some(&properties)).unwrap();
+        downlink.on_publish("site/dev1/cmd/pump", b"{\"value\": 1, \"cmd_id\": 2, \"timeout_ms\": 0}", None).unwrap();
+
+        // 请求方的cmd_id不能确认命令
+        assert!(downlink.on_ack(&CmdAckFrame::success(1, "valve", "drv", None)).is_none());
+        let acked = downlink.on_ack(&CmdAckFrame::success(valve.cmd_id, "valve", "drv", Some(Value::float(1.5)))).unwrap();
+        assert_eq!(acked.topic, "cloud/reply");
+        assert_eq!(acked.correlation_data.as_deref(), Some(&b"req-2"[..]));
+        let body = response(&acked);
+        assert!(body.success);
+        assert_eq!(body.cmd_id, 1);
+        assert_eq!(body.value, Some(serde_json::json!(1.5)));
+
+        let expired = downlink.expire(Instant::now() + Duration::from_secs(6));
+        assert_eq!(expired.len(), 1);
+        assert_eq!(response(&expired[0]).cmd_id, 2);
+        assert_eq!(downlink.pending(), 0);
+    }
+
+    #[test]
+    fn test_duplicate_cmd_id_keeps_pending_entry() {
+        let downlink = downlink();
+        let first = downlink.on_publish("site/dev1/cmd/valve", b"{\"value\": 1, \"cmd_id\": 5}", None).unwrap();
+        let rejected = downlink.on_publish("site/dev1/cmd/pump", b"{\"value\": 2, \"cmd_id\": 5}", None).unwrap_err();
+        assert_eq!(response(&rejected).error.as_deref(), Some("duplicate cmd_id"));
+
+        // 请求方命令ID相同也分配不同的总线命令ID
+        let second = downlink.on_publish("site/dev1/cmd/pump", b"{\"value\": 2, \"cmd_id\": 6}", None).unwrap();
+        assert_ne!(first.cmd_id, second.cmd_id);
+        assert_eq!(downlink.pending(), 2);
+
+        let acked = downlink.on_ack(&CmdAckFrame::success(first.cmd_id, "valve", "drv", None)).unwrap();
+        assert_eq!(acked.topic, "site/dev1/cmd_ack/valve");
+        assert_eq!(response(&acked).cmd_id, 5);
+    }
+}
@@ -263,6 +263,7 @@ mod tests {
                 .unwrap()
                 .as_millis() as u64,
             points,
+            historical: false,
         }
     }
 
@@ -1,12 +1,19 @@
 //! MQTT5 Connector
 //! 
-//! QoS2上云连接器，支持批量发送和断网续传
+//! QoS2上云连接器，支持批量发送、基于WAL的断网续传、下行命令和Sparkplug B
 
 pub mod connector;
 pub mod config;
 pub mod batcher;
 pub mod inflight;
 pub mod metrics;
+pub mod downlink;
+pub mod sparkplug;
+pub mod delivery;
+pub mod store_forward;
+pub mod codec;
+pub mod route;
 
 pub use connector::MqttConnector;
-pub use config::MqttCfg;
\ No newline at end of file
+pub use config::{MqttCfg, DownlinkCfg, PayloadMode, SparkplugCfg, StoreForwardCfg, RouteCfg, PayloadCodec};
+pub use downlink::Downlink;
\ No newline at end of file
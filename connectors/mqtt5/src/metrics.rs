@@ -1,7 +1,10 @@
 //! MQTT5连接器Prometheus指标
 
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
 use prometheus::{Counter, Histogram, IntGauge, Opts, HistogramOpts};
 use once_cell::sync::Lazy;
+use serde::Serialize;
 
 pub static METRICS: Lazy<MqttMetrics> = Lazy::new(MqttMetrics::new);
 
@@ -16,6 +19,10 @@ pub struct MqttMetrics {
     pub buffer_used: IntGauge,
     pub compression_ratio: Histogram,
     pub batch_size: Histogram,
+    pub command_total: Counter,
+    pub command_reject_total: Counter,
+    pub historical_total: Counter,
+    pub dead_letter_total: Counter,
 }
 
 impl MqttMetrics {
@@ -84,6 +91,26 @@ impl MqttMetrics {
         ).unwrap();
         registry.register(Box::new(batch_size.clone())).unwrap();
 
+        let command_total = Counter::with_opts(
+            Opts::new("mqtt_command_total", "Total downlink commands received")
+        ).unwrap();
+        registry.register(Box::new(command_total.clone())).unwrap();
+
+        let command_reject_total = Counter::with_opts(
+            Opts::new("mqtt_command_reject_total", "Total downlink commands rejected")
+        ).unwrap();
+        registry.register(Box::new(command_reject_total.clone())).unwrap();
+
+        let historical_total = Counter::with_opts(
+            Opts::new("mqtt_historical_publish_total", "Total backlog messages replayed from WAL")
+        ).unwrap();
+        registry.register(Box::new(historical_total.clone())).unwrap();
+
+        let dead_letter_total = Counter::with_opts(
+            Opts::new("mqtt_dead_letter_total", "Total messages moved to dead letter after exhausting retries")
+        ).unwrap();
+        registry.register(Box::new(dead_letter_total.clone())).unwrap();
+
         Self {
             connect_total,
             disconnect_total,
@@ -95,6 +122,63 @@ impl MqttMetrics {
             buffer_used,
             compression_ratio,
             batch_size,
+            command_total,
+            command_reject_total,
+            historical_total,
+            dead_letter_total,
         }
     }
-}
\ No newline at end of file
+}
+
+/// 单个连接器实例的统计，全局指标按进程汇总，多连接器时按实例区分
+#[derive(Debug, Default)]
+pub struct ConnectorStats {
+    connected: AtomicBool,
+    messages: AtomicU64,
+    points: AtomicU64,
+    errors: AtomicU64,
+    historical: AtomicU64,
+    last_publish_ms: AtomicU64,
+}
+
+/// 连接器统计快照
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConnectorStatsSnapshot {
+    pub connected: bool,
+    pub messages_published: u64,
+    pub points_published: u64,
+    pub publish_errors: u64,
+    pub historical_messages: u64,
+    /// 最后一次确认送达的时间戳（毫秒），从未送达时为0
+    pub last_publish_ms: u64,
+}
+
+impl ConnectorStats {
+    pub(crate) fn set_connected(&self, connected: bool) {
+        self.connected.store(connected, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_delivery(&self, points: usize, historical: bool) {
+        self.messages.fetch_add(1, Ordering::Relaxed);
+        self.points.fetch_add(points as u64, Ordering::Relaxed);
+        if historical {
+            self.historical.fetch_add(1, Ordering::Relaxed);
+        }
+        self.last_publish_ms.store(chrono::Utc::now().timestamp_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> ConnectorStatsSnapshot {
+        ConnectorStatsSnapshot {
+            connected: self.connected.load(Ordering::Relaxed),
+            messages_published: self.messages.load(Ordering::Relaxed),
+            points_published: self.points.load(Ordering::Relaxed),
+            publish_errors: self.errors.load(Ordering::Relaxed),
+            historical_messages: self.historical.load(Ordering::Relaxed),
+            last_publish_ms: self.last_publish_ms.load(Ordering::Relaxed),
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! 上行路由：主题模板、点位过滤与负载编码
+//!
+//! 一批数据点按路由分发，每条路由按渲染出的主题分组后编码为消息。
+//! 没有配置路由时等价于`{prefix}/data/{device}`上的JSON批量消息。
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use frame_bus::TagPattern;
+
+use crate::config::{CompressionCfg, DataPoint, MqttCfg, MqttMessage, PayloadCodec, RouteCfg};
+use crate::metrics::METRICS;
+
+/// 未配置路由时的主题模板
+pub const DEFAULT_TOPIC: &str = "{prefix}/data/{device}";
+
+/// 模板片段
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Literal(String),
+    Tag,
+    TagPath,
+    Meta(String),
+}
+
+/// 主题模板
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicTemplate {
+    parts: Vec<Part>,
+}
+
+impl TopicTemplate {
+    /// 解析模板，`{prefix}`与`{device}`在解析时代入
+    pub fn parse(template: &str, prefix: &str, device_id: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            literal.push_str(&rest[..start]);
+            let end = rest[start..].find('}')
+                .ok_or_else(|| anyhow::anyhow!("Unclosed placeholder in topic template '{}'", template))?;
+            let name = &rest[start + 1..start + end];
+            let part = match name {
+                "prefix" => {
+                    literal.push_str(prefix);
+                    None
+                }
+                "device" => {
+                    literal.push_str(&sanitize(device_id));
+                    None
+                }
+                "tag" => Some(Part::Tag),
+                "tag_path" => Some(Part::TagPath),
+                _ => match name.strip_prefix("meta.") {
+                    Some(key) if !key.is_empty() => Some(Part::Meta(key.to_string())),
+                    _ => return Err(anyhow::anyhow!("Unknown placeholder '{{{}}}' in topic template '{}'", name, template)),
+                },
+            };
+            if let Some(part) = part {
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(part);
+            }
+            rest = &rest[start + end + 1..];
+        }
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        let template_literal: String = parts.iter().filter_map(|p| match p {
+            Part::Literal(s) => Some(s.as_str()),
+            _ => None,
+        }).collect();
+        if parts.is_empty() || template_literal.contains(['+', '#']) {
+            return Err(anyhow::anyhow!("Invalid topic template '{}'", template));
+        }
+        Ok(Self { parts })
+    }
+
+    /// 渲染点位的主题，缺少引用的元数据时返回None
+    pub fn render(&self, point: &DataPoint) -> Option<String> {
+        let mut topic = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(s) => topic.push_str(s),
+                Part::Tag => topic.push_str(&sanitize(&point.tag)),
+                Part::TagPath => topic.push_str(&sanitize(&point.tag).replace('.', "/")),
+                Part::Meta(key) => topic.push_str(&sanitize(point.meta.get(key)?)),
+            }
+        }
+        Some(topic)
+    }
+}
+
+/// 代入值中的MQTT通配符替换为`_`
+fn sanitize(value: &str) -> String {
+    value.replace(['+', '#'], "_")
+}
+
+/// 编码后待发布的消息
+#[derive(Debug, Clone)]
+pub struct Outbound {
+    pub topic: String,
+    pub payload: Vec<u8>,
+    pub codec: PayloadCodec,
+    /// 负载是否经zstd压缩
+    pub compressed: bool,
+    pub points: usize,
+}
+
+struct Route {
+    template: TopicTemplate,
+    codec: PayloadCodec,
+    patterns: Vec<TagPattern>,
+    compression: CompressionCfg,
+}
+
+impl Route {
+    fn accepts(&self, tag: &str) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|p| p.matches(tag))
+    }
+}
+
+/// 上行路由表
+pub struct Router {
+    routes: Vec<Route>,
+}
+
+impl Router {
+    /// 由连接器配置构建路由表
+    pub fn new(cfg: &MqttCfg, device_id: &str) -> Result<Self> {
+        let default_route = [RouteCfg {
+            topic: DEFAULT_TOPIC.to_string(),
+            codec: PayloadCodec::Json,
+            tags: Vec::new(),
+            compression: None,
+        }];
+        let routes = if cfg.routes.is_empty() { &default_route[..] } else { &cfg.routes[..] };
+
+        let routes = routes.iter().map(|route| {
+            Ok(Route {
+                template: TopicTemplate::parse(&route.topic, &cfg.topic_prefix, device_id)?,
+                codec: route.codec,
+                patterns: route.tags.iter().map(|p| TagPattern::parse(p)).collect::<Result<_>>()?,
+                compression: route.compression.clone().unwrap_or_else(|| cfg.compression.clone()),
+            })
+        }).collect::<Result<_>>()?;
+        Ok(Self { routes })
+    }
+
+    /// 把一批数据点分发到各路由并编码
+    pub fn route(&self, message: &MqttMessage) -> Vec<Outbound> {
+        let mut outbound = Vec::new();
+        for route in &self.routes {
+            // 按主题分组，保持首次出现的顺序
+            let mut groups: Vec<(String, Vec<DataPoint>)> = Vec::new();
+            let mut index: HashMap<String, usize> = HashMap::new();
+            for point in message.points.iter().filter(|p| route.accepts(&p.tag)) {
+                let Some(topic) = route.template.render(point) else {
+                    tracing::debug!("Point '{}' lacks metadata for topic template, skipped", point.tag);
+                    continue;
+                };
+                if route.codec.per_point() {
+                    groups.push((topic, vec![point.clone()]));
+                    continue;
+                }
+                match index.get(&topic) {
+                    Some(&i) => groups[i].1.push(point.clone()),
+                    None => {
+                        index.insert(topic.clone(), groups.len());
+                        groups.push((topic, vec![point.clone()]));
+                    }
+                }
+            }
+
+            for (topic, points) in groups {
+                let group = MqttMessage {
+                    device_id: message.device_id.clone(),
+                    timestamp: message.timestamp,
+                    points,
+                    historical: message.historical,
+                };
+                match encode(route, &group) {
+                    Ok((payload, compressed)) => outbound.push(Outbound {
+                        topic,
+                        payload,
+                        codec: route.codec,
+                        compressed,
+                        points: group.points.len(),
+                    }),
+                    Err(e) => tracing::error!("Failed to encode message for '{}': {}", topic, e),
+                }
+            }
+        }
+        outbound
+    }
+}
+
+/// 编码并按阈值压缩
+fn encode(route: &Route, message: &MqttMessage) -> Result<(Vec<u8>, bool)> {
+    let data = route.codec.encode(message)?;
+    let compression = &route.compression;
+    if compression.enabled && data.len() > compression.threshold {
+        let compressed = zstd::encode_all(&data[..], compression.level)?;
+        METRICS.compression_ratio.observe(compressed.len() as f64 / data.len() as f64);
+        Ok((compressed, true))
+    } else {
+        Ok((data, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn point(tag: &str, meta: &[(&str, &str)]) -> DataPoint {
+        DataPoint {
+            tag: tag.to_string(),
+            value: json!(1),
+            quality: 2,
+            meta: meta.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    fn message(points: Vec<DataPoint>) -> MqttMessage {
+        MqttMessage { device_id: "gw1".to_string(), timestamp: 0, points, historical: false }
+    }
+
+    #[test]
+    fn test_template_render() {
+        let template = TopicTemplate::parse("plant/{meta.line}/{device}/{tag_path}", "site", "gw1").unwrap();
+        assert_eq!(template.render(&point("pump.speed", &[("line", "L1")])).as_deref(), Some("plant/L1/gw1/pump/speed"));
+        assert_eq!(template.render(&point("pump.speed", &[])), None);
+        assert_eq!(template.render(&point("a+b", &[("line", "#")])).as_deref(), Some("plant/_/gw1/a_b"));
+
+        let fixed = TopicTemplate::parse(DEFAULT_TOPIC, "site", "gw1").unwrap();
+        assert_eq!(fixed.render(&point("x", &[])).as_deref(), Some("site/data/gw1"));
+    }
+
+    #[test]
+    fn test_template_errors() {
+        assert!(TopicTemplate::parse("plant/{tag", "p", "d").is_err());
+        assert!(TopicTemplate::parse("plant/{unknown}", "p", "d").is_err());
+        assert!(TopicTemplate::parse("plant/{meta.}", "p", "d").is_err());
+        assert!(TopicTemplate::parse("plant/+/{tag}", "p", "d").is_err());
+    }
+
+    #[test]
+    fn test_routes_group_and_filter() {
+        let cfg = MqttCfg {
+            topic_prefix: "site".to_string(),
+            routes: vec![
+                RouteCfg { topic: "plant/{meta.line}".to_string(), codec: PayloadCodec::Json, tags: Vec::new(), compression: None },
+                RouteCfg {
+                    topic: "values/{tag}".to_string(),
+                    codec: PayloadCodec::FlatJson,
+                    tags: vec!["pump.#".to_string()],
+                    compression: None,
+                },
+            ],
+            ..Default::default()
+        };
+        let router = Router::new(&cfg, "gw1").unwrap();
+        let outbound = router.route(&message(vec![
+            point("pump.speed", &[("line", "L1")]),
+            point("valve.open", &[("line", "L2")]),
+            point("pump.temp", &[("line", "L1")]),
+        ]));
+
+        let topics: Vec<(&str, usize)> = outbound.iter().map(|o| (o.topic.as_str(), o.points)).collect();
+        assert_eq!(topics, vec![("plant/L1", 2), ("plant/L2", 1), ("values/pump.speed", 1), ("values/pump.temp", 1)]);
+        assert_eq!(outbound[2].payload, b"1");
+    }
+
+    #[test]
+    fn test_route_compression_override() {
+        let mut cfg = MqttCfg::default();
+        cfg.routes.push(RouteCfg {
+            topic: "t".to_string(),
+            codec: PayloadCodec::Json,
+            tags: Vec::new(),
+            compression: Some(CompressionCfg { enabled: true, level: 3, threshold: 16 }),
+        });
+        let router = Router::new(&cfg, "gw1").unwrap();
+        let outbound = router.route(&message((0..50).map(|i| point(&format!("tag{}", i), &[])).collect()));
+        assert!(outbound[0].compressed);
+        let json = zstd::decode_all(&outbound[0].payload[..]).unwrap();
+        assert_eq!(serde_json::from_slice::<MqttMessage>(&json).unwrap().points.len(), 50);
+    }
+}
@@ -0,0 +1,467 @@
+//! Sparkplug B会话
+//!
+//! 主题：`spBv1.0/{group_id}/{NBIRTH|NDEATH|NDATA|NCMD|DBIRTH|DDATA|DCMD}/{edge_node_id}[/{device_id}]`
+//! - 连接时以携带`bdSeq`的NDEATH作为遗嘱，连上后发布NBIRTH与各设备的DBIRTH，声明全部点位及别名；
+//! - 之后只按别名发布发生变化的点位 (report by exception)，出现新点位时在下一次刷新中
+//!   只为受影响的设备重新发布DBIRTH，NBIRTH仅在新会话或主机请求时发布；
+//! - `seq`在NBIRTH时归零，此后每条BIRTH/DATA递增并在255后回绕，`bdSeq`每个会话递增；
+//! - NCMD `Node Control/Rebirth`触发重新BIRTH，DCMD写入映射为总线`CmdFrame`。
+//!
+//! 点位按DataFrame元数据中的设备键划分为Sparkplug设备，点位名中的`.`映射为指标目录`/`。
+
+pub mod payload;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use prost::Message;
+
+use frame_bus::{CmdFrame, DataFrame, Value};
+
+use crate::config::SparkplugCfg;
+use payload::{Metric, MetricValue, Payload};
+
+/// 主题命名空间
+pub const NAMESPACE: &str = "spBv1.0";
+/// NBIRTH/NDEATH中的会话序号指标
+pub const BD_SEQ_METRIC: &str = "bdSeq";
+/// 主机请求重新BIRTH的节点控制指标
+pub const REBIRTH_METRIC: &str = "Node Control/Rebirth";
+/// DCMD转换的命令帧来源标识
+pub const COMMAND_ORIGIN: &str = "sparkplug";
+
+/// Sparkplug消息类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    NBirth,
+    NDeath,
+    NData,
+    NCmd,
+    DBirth,
+    DDeath,
+    DData,
+    DCmd,
+}
+
+impl MessageType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageType::NBirth => "NBIRTH",
+            MessageType::NDeath => "NDEATH",
+            MessageType::NData => "NDATA",
+            MessageType::NCmd => "NCMD",
+            MessageType::DBirth => "DBIRTH",
+            MessageType::DDeath => "DDEATH",
+            MessageType::DData => "DDATA",
+            MessageType::DCmd => "DCMD",
+        }
+    }
+}
+
+/// 待发布的Sparkplug消息
+#[derive(Debug, Clone)]
+pub struct SparkplugMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+/// 点位观测结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Observation {
+    /// BIRTH中未声明的新点位，所属设备在下一次刷新时重新DBIRTH
+    New,
+    /// 值发生变化，等待下一次DATA
+    Changed,
+    /// 值未变化，按例外报告不发布
+    Unchanged,
+}
+
+/// 主机下发的命令
+#[derive(Debug)]
+pub enum Command {
+    Rebirth,
+    Write(Vec<CmdFrame>),
+}
+
+struct TagState {
+    device: String,
+    alias: u64,
+    value: Option<Value>,
+    timestamp_ms: u64,
+}
+
+/// 边缘节点会话状态
+pub struct SparkplugSession {
+    cfg: SparkplugCfg,
+    edge_node_id: String,
+    bd_seq: u64,
+    seq: u64,
+    tags: HashMap<String, TagState>,
+    aliases: HashMap<u64, String>,
+    pending: BTreeMap<String, Vec<Metric>>,
+    /// 有新点位、等待重新DBIRTH的设备
+    unborn: BTreeSet<String>,
+}
+
+impl SparkplugSession {
+    pub fn new(cfg: SparkplugCfg, edge_node_id: &str) -> Self {
+        let edge_node_id = if cfg.edge_node_id.is_empty() {
+            sanitize(edge_node_id)
+        } else {
+            sanitize(&cfg.edge_node_id)
+        };
+        Self {
+            cfg,
+            edge_node_id,
+            bd_seq: 0,
+            seq: 0,
+            tags: HashMap::new(),
+            aliases: HashMap::new(),
+            pending: BTreeMap::new(),
+            unborn: BTreeSet::new(),
+        }
+    }
+
+    pub fn edge_node_id(&self) -> &str {
+        &self.edge_node_id
+    }
+
+    /// 当前会话序号
+    pub fn bd_seq(&self) -> u64 {
+        self.bd_seq
+    }
+
+    /// 已声明的点位数
+    pub fn len(&self) -> usize {
+        self.tags.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tags.is_empty()
+    }
+
+    /// 等待发布的变化点位数
+    pub fn pending(&self) -> usize {
+        self.pending.values().map(Vec::len).sum()
+    }
+
+    pub fn topic(&self, kind: MessageType, device: Option<&str>) -> String {
+        match device {
+            Some(device) => format!("{}/{}/{}/{}/{}", NAMESPACE, self.cfg.group_id, kind.as_str(), self.edge_node_id, device),
+            None => format!("{}/{}/{}/{}", NAMESPACE, self.cfg.group_id, kind.as_str(), self.edge_node_id),
+        }
+    }
+
+    /// 需要订阅的NCMD/DCMD主题
+    pub fn command_subscriptions(&self) -> Vec<String> {
+        vec![
+            self.topic(MessageType::NCmd, None),
+            self.topic(MessageType::DCmd, Some("+")),
+        ]
+    }
+
+    /// 作为遗嘱的NDEATH，携带当前`bdSeq`
+    pub fn death_certificate(&self) -> SparkplugMessage {
+        let payload = Payload::new(now_ms(), None, vec![self.bd_seq_metric()]);
+        SparkplugMessage {
+            topic: self.topic(MessageType::NDeath, None),
+            payload: payload.encode_to_vec(),
+        }
+    }
+
+    /// 会话断开后递增`bdSeq`，供下一次连接的遗嘱与NBIRTH使用
+    pub fn next_session(&mut self) {
+        self.bd_seq = (self.bd_seq + 1) % 256;
+    }
+
+    /// 记录总线数据帧
+    pub fn observe(&mut self, frame: &DataFrame) -> Observation {
+        let timestamp_ms = frame.timestamp / 1_000_000;
+        if let Some(state) = self.tags.get_mut(&frame.tag) {
+            if state.value == frame.value {
+                return Observation::Unchanged;
+            }
+            state.value = frame.value.clone();
+            state.timestamp_ms = timestamp_ms;
+            let metric = Metric::from_value(frame.value.as_ref(), timestamp_ms).with_alias(state.alias);
+            self.pending.entry(state.device.clone()).or_default().push(metric);
+            return Observation::Changed;
+        }
+
+        let device = frame.meta.get(&self.cfg.device_meta_key)
+            .map(|d| sanitize(d))
+            .unwrap_or_else(|| sanitize(&self.cfg.default_device));
+        let alias = self.aliases.len() as u64 + 1;
+        self.aliases.insert(alias, frame.tag.clone());
+        self.unborn.insert(device.clone());
+        self.tags.insert(frame.tag.clone(), TagState {
+            device,
+            alias,
+            value: frame.value.clone(),
+            timestamp_ms,
+        });
+        Observation::New
+    }
+
+    /// 等待重新DBIRTH的设备数
+    pub fn unborn(&self) -> usize {
+        self.unborn.len()
+    }
+
+    /// 标记全部设备需要重新DBIRTH，用于丢帧后同步点位
+    pub fn rebirth_devices(&mut self) {
+        self.unborn.extend(self.tags.values().map(|state| state.device.clone()));
+    }
+
+    /// NBIRTH与各设备的DBIRTH，`seq`从0开始
+    pub fn births(&mut self) -> Vec<SparkplugMessage> {
+        self.pending.clear();
+        self.unborn.clear();
+        self.seq = 0;
+        let now = now_ms();
+
+        let node_metrics = vec![
+            self.bd_seq_metric(),
+            Metric::from_value(Some(&Value::bool(false)), now).with_name(REBIRTH_METRIC),
+        ];
+        let seq = self.next_seq();
+        let mut messages = vec![SparkplugMessage {
+            topic: self.topic(MessageType::NBirth, None),
+            payload: Payload::new(now, Some(seq), node_metrics).encode_to_vec(),
+        }];
+        messages.extend(self.device_births(|_| true, now));
+        messages
+    }
+
+    /// 满足`include`的设备各一条DBIRTH，声明该设备的全部点位
+    fn device_births(&mut self, include: impl Fn(&str) -> bool, now: u64) -> Vec<SparkplugMessage> {
+        let mut devices: BTreeMap<String, Vec<Metric>> = BTreeMap::new();
+        let mut tags: Vec<_> = self.tags.iter().filter(|(_, state)| include(&state.device)).collect();
+        tags.sort_by_key(|(_, state)| state.alias);
+        for (tag, state) in tags {
+            let metric = Metric::from_value(state.value.as_ref(), state.timestamp_ms)
+                .with_name(metric_name(tag))
+                .with_alias(state.alias);
+            devices.entry(state.device.clone()).or_default().push(metric);
+        }
+        devices.into_iter()
+            .map(|(device, metrics)| {
+                let seq = self.next_seq();
+                SparkplugMessage {
+                    topic: self.topic(MessageType::DBirth, Some(&device)),
+                    payload: Payload::new(now, Some(seq), metrics).encode_to_vec(),
+                }
+            })
+            .collect()
+    }
+
+    /// 刷新周期的输出：先为有新点位的设备重新DBIRTH，再发布其余设备的DDATA
+    ///
+    /// DBIRTH携带点位最新值，这些设备待发布的变化随之丢弃
+    pub fn flush(&mut self) -> Vec<SparkplugMessage> {
+        let now = now_ms();
+        let unborn = std::mem::take(&mut self.unborn);
+        self.pending.retain(|device, _| !unborn.contains(device));
+        let mut messages = self.device_births(|device| unborn.contains(device), now);
+        messages.extend(self.flush_data());
+        messages
+    }
+
+    /// 变化点位的DDATA，每个设备一条；等待DBIRTH的设备留到下一次刷新
+    pub fn flush_data(&mut self) -> Vec<SparkplugMessage> {
+        let now = now_ms();
+        let (pending, unborn): (BTreeMap<_, _>, BTreeMap<_, _>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|(device, _)| !self.unborn.contains(device));
+        self.pending = unborn;
+        pending.into_iter()
+            .map(|(device, metrics)| {
+                let seq = self.next_seq();
+                SparkplugMessage {
+                    topic: self.topic(MessageType::DData, Some(&device)),
+                    payload: Payload::new(now, Some(seq), metrics).encode_to_vec(),
+                }
+            })
+            .collect()
+    }
+
+    /// 解析NCMD/DCMD，不相关的指标忽略
+    pub fn on_command(&self, topic: &str, payload: &[u8]) -> Result<Option<Command>> {
+        let payload = Payload::decode(payload)?;
+
+        if topic == self.topic(MessageType::NCmd, None) {
+            let rebirth = payload.metrics.iter().any(|m| {
+                m.name.as_deref() == Some(REBIRTH_METRIC) && m.value == Some(MetricValue::BooleanValue(true))
+            });
+            return Ok(rebirth.then_some(Command::Rebirth));
+        }
+
+        let device = topic.strip_prefix(&self.topic(MessageType::DCmd, Some("")))
+            .ok_or_else(|| anyhow!("unexpected Sparkplug command topic {}", topic))?;
+        let mut frames = Vec::new();
+        for metric in &payload.metrics {
+            let tag = self.resolve(metric)
+                .filter(|tag| self.tags.get(*tag).is_some_and(|s| s.device == device))
+                .ok_or_else(|| anyhow!("unknown metric {:?}/{:?} for device {}", metric.name, metric.alias, device))?;
+            let value = metric.to_value()
+                .ok_or_else(|| anyhow!("metric {} has no value", tag))?;
+            frames.push(CmdFrame::new(tag.to_string(), value, COMMAND_ORIGIN.to_string()));
+        }
+        Ok((!frames.is_empty()).then_some(Command::Write(frames)))
+    }
+
+    fn resolve(&self, metric: &Metric) -> Option<&str> {
+        if let Some(alias) = metric.alias {
+            return self.aliases.get(&alias).map(String::as_str);
+        }
+        let name = metric.name.as_deref()?;
+        self.tags.keys().find(|tag| metric_name(tag) == name).map(String::as_str)
+    }
+
+    fn bd_seq_metric(&self) -> Metric {
+        Metric::from_value(Some(&Value::int(self.bd_seq as i64)), now_ms()).with_name(BD_SEQ_METRIC)
+    }
+
+    fn next_seq(&mut self) -> u64 {
+        let seq = self.seq;
+        self.seq = (self.seq + 1) % 256;
+        seq
+    }
+}
+
+/// 点位名到指标名：`plant1.temp` -> `plant1/temp`
+fn metric_name(tag: &str) -> String {
+    tag.replace('.', "/")
+}
+
+/// 主题层级中不允许出现的字符替换为`_`
+fn sanitize(id: &str) -> String {
+    id.replace(['/', '+', '#'], "_")
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> SparkplugSession {
+        SparkplugSession::new(SparkplugCfg { group_id: "plant".to_string(), ..Default::default() }, "edge1")
+    }
+
+    fn decode(message: &SparkplugMessage) -> Payload {
+        Payload::decode(&message.payload[..]).unwrap()
+    }
+
+    #[test]
+    fn test_births_declare_tags_with_aliases() {
+        let mut session = session();
+        assert_eq!(session.observe(&DataFrame::new("line1.temp", Value::float(21.5))), Observation::New);
+        assert_eq!(session.observe(&DataFrame::new("line1.run", Value::bool(true)).with_meta("device", "plc1")), Observation::New);
+
+        let births = session.births();
+        assert_eq!(births.len(), 3);
+        assert_eq!(births[0].topic, "spBv1.0/plant/NBIRTH/edge1");
+        let nbirth = decode(&births[0]);
+        assert_eq!(nbirth.seq, Some(0));
+        assert_eq!(nbirth.metrics[0].name.as_deref(), Some(BD_SEQ_METRIC));
+
+        assert_eq!(births[1].topic, "spBv1.0/plant/DBIRTH/edge1/default");
+        let dbirth = decode(&births[1]);
+        assert_eq!(dbirth.seq, Some(1));
+        assert_eq!(dbirth.metrics[0].name.as_deref(), Some("line1/temp"));
+        assert_eq!(dbirth.metrics[0].alias, Some(1));
+        assert_eq!(births[2].topic, "spBv1.0/plant/DBIRTH/edge1/plc1");
+    }
+
+    #[test]
+    fn test_report_by_exception_and_seq_wrap() {
+        let mut session = session();
+        session.observe(&DataFrame::new("line1.temp", Value::int(1)));
+        session.births();
+
+        assert_eq!(session.observe(&DataFrame::new("line1.temp", Value::int(1))), Observation::Unchanged);
+        assert!(session.flush().is_empty());
+
+        for v in 2..300 {
+            assert_eq!(session.observe(&DataFrame::new("line1.temp", Value::int(v))), Observation::Changed);
+            let data = session.flush();
+            assert_eq!(data.len(), 1);
+            let payload = decode(&data[0]);
+            assert_eq!(payload.seq, Some(v as u64 % 256));
+            assert_eq!(payload.metrics[0].alias, Some(1));
+            assert!(payload.metrics[0].name.is_none());
+        }
+    }
+
+    #[test]
+    fn test_death_certificate_tracks_bd_seq() {
+        let mut session = session();
+        session.next_session();
+        let death = session.death_certificate();
+        assert_eq!(death.topic, "spBv1.0/plant/NDEATH/edge1");
+        let payload = decode(&death);
+        assert_eq!(payload.seq, None);
+        assert_eq!(payload.metrics[0].to_value(), Some(Value::int(1)));
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut session = session();
+        session.observe(&DataFrame::new("line1.setpoint", Value::int(0)));
+
+        let rebirth = Payload::new(0, None, vec![
+            Metric::from_value(Some(&Value::bool(true)), 0).with_name(REBIRTH_METRIC),
+        ]);
+        let command = session.on_command("spBv1.0/plant/NCMD/edge1", &rebirth.encode_to_vec()).unwrap();
+        assert!(matches!(command, Some(Command::Rebirth)));
+
+        let write = Payload::new(0, None, vec![Metric::from_value(Some(&Value::int(42)), 0).with_name("line1/setpoint")]);
+        match session.on_command("spBv1.0/plant/DCMD/edge1/default", &write.encode_to_vec()).unwrap() {
+            Some(Command::Write(frames)) => {
+                assert_eq!(frames[0].tag, "line1.setpoint");
+                assert_eq!(frames[0].value, Some(Value::int(42)));
+                assert_eq!(frames[0].origin, COMMAND_ORIGIN);
+            }
+            other => panic!("unexpected command {:?}", other),
+        }
+
+        assert!(session.on_command("spBv1.0/plant/DCMD/edge1/other", &write.encode_to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_new_tag_rebirths_only_its_device() {
+        let mut session = session();
+        session.observe(&DataFrame::new("line1.temp", Value::int(1)));
+        session.observe(&DataFrame::new("line2.temp", Value::int(1)).with_meta("device", "plc2"));
+        session.births();
+
+        // 新点位排队，直到刷新时只为所属设备发布DBIRTH，seq延续
+        session.observe(&DataFrame::new("line1.temp", Value::int(2)));
+        session.observe(&DataFrame::new("line2.temp", Value::int(2)).with_meta("device", "plc2"));
+        assert_eq!(session.observe(&DataFrame::new("line2.flow", Value::int(5)).with_meta("device", "plc2")), Observation::New);
+        assert_eq!(session.observe(&DataFrame::new("line2.level", Value::int(7)).with_meta("device", "plc2")), Observation::New);
+        assert_eq!(session.unborn(), 1);
+
+        // 按批量触发的DDATA不包含等待DBIRTH的设备
+        let data = session.flush_data();
+        assert_eq!(data.len(), 1);
+        assert_eq!(data[0].topic, "spBv1.0/plant/DDATA/edge1/default");
+
+        let messages = session.flush();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].topic, "spBv1.0/plant/DBIRTH/edge1/plc2");
+        let dbirth = decode(&messages[0]);
+        assert_eq!(dbirth.seq, Some(4));
+        assert_eq!(dbirth.metrics.len(), 3);
+        assert_eq!(dbirth.metrics[0].to_value(), Some(Value::int(2)));
+        assert_eq!(session.unborn(), 0);
+        assert!(session.flush().is_empty());
+    }
+}
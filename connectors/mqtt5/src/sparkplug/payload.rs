@@ -0,0 +1,150 @@
+//! Sparkplug B负载 (sparkplug_b.proto的标量子集)
+
+use prost::{Message, Oneof};
+
+use frame_bus::envelope::value::Value as ValueEnum;
+use frame_bus::Value;
+
+/// Sparkplug B负载
+#[derive(Clone, PartialEq, Message)]
+pub struct Payload {
+    /// 时间戳 (ms)
+    #[prost(uint64, optional, tag = "1")]
+    pub timestamp: Option<u64>,
+    #[prost(message, repeated, tag = "2")]
+    pub metrics: Vec<Metric>,
+    /// 消息序号 0-255，NDEATH不携带
+    #[prost(uint64, optional, tag = "3")]
+    pub seq: Option<u64>,
+    #[prost(string, optional, tag = "4")]
+    pub uuid: Option<String>,
+    #[prost(bytes = "vec", optional, tag = "5")]
+    pub body: Option<Vec<u8>>,
+}
+
+/// 指标
+#[derive(Clone, PartialEq, Message)]
+pub struct Metric {
+    /// 指标名，BIRTH之后的DATA只携带别名
+    #[prost(string, optional, tag = "1")]
+    pub name: Option<String>,
+    #[prost(uint64, optional, tag = "2")]
+    pub alias: Option<u64>,
+    /// 时间戳 (ms)
+    #[prost(uint64, optional, tag = "3")]
+    pub timestamp: Option<u64>,
+    /// `DataType`
+    #[prost(uint32, optional, tag = "4")]
+    pub datatype: Option<u32>,
+    #[prost(bool, optional, tag = "5")]
+    pub is_historical: Option<bool>,
+    #[prost(bool, optional, tag = "6")]
+    pub is_transient: Option<bool>,
+    #[prost(bool, optional, tag = "7")]
+    pub is_null: Option<bool>,
+    #[prost(oneof = "MetricValue", tags = "10, 11, 12, 13, 14, 15, 16")]
+    pub value: Option<MetricValue>,
+}
+
+/// 指标值
+#[derive(Clone, PartialEq, Oneof)]
+pub enum MetricValue {
+    #[prost(uint32, tag = "10")]
+    IntValue(u32),
+    #[prost(uint64, tag = "11")]
+    LongValue(u64),
+    #[prost(float, tag = "12")]
+    FloatValue(f32),
+    #[prost(double, tag = "13")]
+    DoubleValue(f64),
+    #[prost(bool, tag = "14")]
+    BooleanValue(bool),
+    #[prost(string, tag = "15")]
+    StringValue(String),
+    #[prost(bytes, tag = "16")]
+    BytesValue(Vec<u8>),
+}
+
+/// Sparkplug B数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum DataType {
+    Int8 = 1,
+    Int16 = 2,
+    Int32 = 3,
+    Int64 = 4,
+    UInt8 = 5,
+    UInt16 = 6,
+    UInt32 = 7,
+    UInt64 = 8,
+    Float = 9,
+    Double = 10,
+    Boolean = 11,
+    String = 12,
+    DateTime = 13,
+    Text = 14,
+    Uuid = 15,
+    Bytes = 17,
+}
+
+impl Metric {
+    /// 由总线值构造指标，整数按Int64、浮点按Double编码
+    pub fn from_value(value: Option<&Value>, timestamp_ms: u64) -> Self {
+        let (datatype, value) = match value.and_then(|v| v.value.as_ref()) {
+            Some(ValueEnum::BoolV(b)) => (DataType::Boolean, Some(MetricValue::BooleanValue(*b))),
+            Some(ValueEnum::IntV(i)) => (DataType::Int64, Some(MetricValue::LongValue(*i as u64))),
+            Some(ValueEnum::FloatV(f)) => (DataType::Double, Some(MetricValue::DoubleValue(*f))),
+            Some(ValueEnum::StrV(s)) => (DataType::String, Some(MetricValue::StringValue(s.clone()))),
+            Some(ValueEnum::BinV(b)) => (DataType::Bytes, Some(MetricValue::BytesValue(b.clone()))),
+            None => (DataType::Double, None),
+        };
+        Self {
+            timestamp: Some(timestamp_ms),
+            datatype: Some(datatype as u32),
+            is_null: value.is_none().then_some(true),
+            value,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn with_alias(mut self, alias: u64) -> Self {
+        self.alias = Some(alias);
+        self
+    }
+
+    /// 转换为总线值，有符号整数按声明的数据类型还原
+    pub fn to_value(&self) -> Option<Value> {
+        let datatype = self.datatype.unwrap_or_default();
+        match self.value.as_ref()? {
+            MetricValue::IntValue(v) => Some(Value::int(match datatype {
+                t if t == DataType::Int8 as u32 => *v as i8 as i64,
+                t if t == DataType::Int16 as u32 => *v as i16 as i64,
+                t if t == DataType::Int32 as u32 => *v as i32 as i64,
+                _ => *v as i64,
+            })),
+            MetricValue::LongValue(v) => Some(Value::int(*v as i64)),
+            MetricValue::FloatValue(v) => Some(Value::float(*v as f64)),
+            MetricValue::DoubleValue(v) => Some(Value::float(*v)),
+            MetricValue::BooleanValue(v) => Some(Value::bool(*v)),
+            MetricValue::StringValue(v) => Some(Value::string(v.clone())),
+            MetricValue::BytesValue(v) => Some(Value::bytes(v.clone())),
+        }
+    }
+}
+
+impl Payload {
+    pub fn new(timestamp_ms: u64, seq: Option<u64>, metrics: Vec<Metric>) -> Self {
+        Self {
+            timestamp: Some(timestamp_ms),
+            metrics,
+            seq,
+            uuid: None,
+            body: None,
+        }
+    }
+}
@@ -0,0 +1,167 @@
+//! 断网续传上行数据源
+//!
+//! 上行以FrameBus WAL持久订阅作为数据源，broker确认后才推进游标，
+//! 断网或重启期间的数据留在WAL中，恢复后从游标之后按序回放。
+//! 回放出的帧标记为历史数据。
+//!
+//! 多个批次可同时在途，游标只推进到连续完成的批次前缀；
+//! 重试用尽的消息写入死信后视为完成，不阻塞后续批次。
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use base64::engine::Engine;
+use frame_bus::{DurableOptions, DurableReceiver, Filter, FrameEnvelope, FrameReceiver, StartFrom};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::config::StoreForwardCfg;
+use crate::route::Outbound;
+
+/// 上行数据源
+pub enum UplinkSource {
+    /// WAL持久订阅
+    Durable(DurableReceiver),
+    /// 实时订阅，断网期间的数据不保留
+    Live(FrameReceiver),
+}
+
+impl UplinkSource {
+    /// 打开上行数据源，未启用断网续传或WAL未初始化时退化为实时订阅
+    pub fn open(cfg: &StoreForwardCfg, device_id: &str, filter: Filter) -> Result<Self> {
+        if cfg.enabled {
+            let options = DurableOptions {
+                start: if cfg.from_earliest { StartFrom::Earliest } else { StartFrom::Latest },
+                ..Default::default()
+            };
+            match frame_bus::subscribe_durable_with(&consumer_id(cfg, device_id), filter.clone(), options) {
+                Ok(receiver) => return Ok(Self::Durable(receiver.with_subject(device_id))),
+                Err(e) => tracing::warn!("MQTT store-and-forward disabled: {}", e),
+            }
+        }
+        Ok(Self::Live(frame_bus::subscribe_as(device_id, filter)?))
+    }
+
+    /// 接收下一帧，同时返回该帧是否来自WAL回放
+    pub async fn recv(&mut self) -> Result<(FrameEnvelope, bool)> {
+        match self {
+            Self::Durable(receiver) => {
+                let envelope = receiver.recv().await?;
+                Ok((envelope, receiver.is_catching_up()))
+            }
+            Self::Live(receiver) => loop {
+                match receiver.recv().await {
+                    Ok(envelope) => return Ok((envelope, false)),
+                    Err(RecvError::Lagged(n)) => {
+                        tracing::warn!("MQTT uplink subscriber lagged, {} frames dropped", n);
+                    }
+                    Err(RecvError::Closed) => return Err(anyhow::anyhow!("FrameBus closed")),
+                }
+            },
+        }
+    }
+
+    /// 确认已送达到`seq`(含)
+    pub fn ack(&mut self, seq: u64) -> Result<()> {
+        match self {
+            Self::Durable(receiver) => receiver.ack(seq),
+            Self::Live(_) => Ok(()),
+        }
+    }
+
+    /// 是否为持久订阅
+    pub fn is_durable(&self) -> bool {
+        matches!(self, Self::Durable(_))
+    }
+}
+
+/// 持久消费者ID
+pub fn consumer_id(cfg: &StoreForwardCfg, device_id: &str) -> String {
+    if cfg.consumer_id.is_empty() {
+        format!("mqtt-{}", device_id)
+    } else {
+        cfg.consumer_id.clone()
+    }
+}
+
+/// 在途批次的确认窗口
+///
+/// 批次可以乱序完成，游标只推进到连续完成的前缀中最后一批的序列号
+#[derive(Debug, Default)]
+pub struct AckWindow {
+    next_id: u64,
+    /// (批次ID, 批次内最大序列号, 是否完成)，按提交顺序排列
+    batches: VecDeque<(u64, Option<u64>, bool)>,
+}
+
+impl AckWindow {
+    /// 登记一个在途批次，返回批次ID
+    pub fn push(&mut self, last_seq: Option<u64>) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.batches.push_back((id, last_seq, false));
+        id
+    }
+
+    /// 尚未推进游标的批次数
+    pub fn len(&self) -> usize {
+        self.batches.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.batches.is_empty()
+    }
+
+    /// 标记批次完成
+    pub fn complete(&mut self, id: u64) {
+        if let Some(batch) = self.batches.iter_mut().find(|(batch, _, _)| *batch == id) {
+            batch.2 = true;
+        }
+    }
+
+    /// 移出连续完成的前缀，返回可确认的序列号
+    pub fn advance(&mut self) -> Option<u64> {
+        let mut ack = None;
+        while self.batches.front().is_some_and(|(_, _, done)| *done) {
+            let (_, seq, _) = self.batches.pop_front().unwrap();
+            ack = seq.or(ack);
+        }
+        ack
+    }
+}
+
+/// 死信：重试用尽的上行消息以JSON Lines追加到文件，未配置路径时只记录日志
+#[derive(Debug, Clone, Default)]
+pub struct DeadLetter {
+    path: Option<PathBuf>,
+}
+
+impl DeadLetter {
+    pub fn new(cfg: &StoreForwardCfg) -> Self {
+        Self { path: (!cfg.dead_letter_path.is_empty()).then(|| PathBuf::from(&cfg.dead_letter_path)) }
+    }
+
+    /// 记录一条放弃投递的消息
+    pub fn write(&self, outbound: &Outbound, historical: bool, attempts: u32) -> Result<()> {
+        let Some(path) = &self.path else {
+            tracing::error!("MQTT message to '{}' ({} points) dropped, no dead letter path configured",
+                            outbound.topic, outbound.points);
+            return Ok(());
+        };
+        let record = serde_json::json!({
+            "timestamp": SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            "topic": outbound.topic,
+            "content_type": outbound.codec.content_type(),
+            "compressed": outbound.compressed,
+            "historical": historical,
+            "points": outbound.points,
+            "attempts": attempts,
+            "payload": base64::engine::general_purpose::STANDARD.encode(&outbound.payload),
+        });
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", record)?;
+        Ok(())
+    }
+}
@@ -47,6 +47,7 @@ impl MockBatcher {
                 .unwrap()
                 .as_millis() as u64,
             points: self.current_batch.drain(..).collect(),
+            historical: false,
         };
 
         self.last_send = Instant::now();
@@ -0,0 +1,101 @@
+//! 测试公用的MQTT 5 broker替身
+
+// 各测试文件只用到其中一部分
+#![allow(dead_code)]
+
+use bytes::BytesMut;
+use rumqttc::v5::mqttbytes::v5::{
+    ConnAck, ConnectReturnCode, Disconnect, DisconnectReasonCode, Packet, PingResp, PubAck, PubComp, PubRec, Publish, PublishProperties, SubAck,
+    SubscribeReasonCode,
+};
+use rumqttc::v5::mqttbytes::{Error as MqttError, QoS};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+
+/// 单连接broker替身：确认所有报文，客户端首次订阅后注入`commands`，收到的报文转交给测试
+pub async fn run_broker(listener: TcpListener, commands: Vec<Publish>, received: mpsc::UnboundedSender<Packet>) {
+    run_broker_with(listener, commands, received, Arc::new(AtomicBool::new(true))).await
+}
+
+/// 同`run_broker`，`ack_publishes`为false时不确认客户端的发布
+pub async fn run_broker_with(
+    listener: TcpListener,
+    commands: Vec<Publish>,
+    received: mpsc::UnboundedSender<Packet>,
+    ack_publishes: Arc<AtomicBool>,
+) {
+    let (mut stream, _) = listener.accept().await.unwrap();
+    let mut buf = BytesMut::new();
+    let mut commands = Some(commands);
+
+    loop {
+        // 无原因码的DISCONNECT只有固定报头，mqttbytes不接受空负载
+        if buf.starts_with(&[0xe0, 0x00]) {
+            let _ = buf.split_to(2);
+            let _ = received.send(Packet::Disconnect(Disconnect::new(DisconnectReasonCode::NormalDisconnection)));
+            continue;
+        }
+
+        let packet = match Packet::read(&mut buf, None) {
+            Ok(packet) => packet,
+            Err(MqttError::InsufficientBytes(_)) => {
+                if stream.read_buf(&mut buf).await.unwrap_or(0) == 0 {
+                    return;
+                }
+                continue;
+            }
+            Err(e) => panic!("broker stand-in failed to decode packet: {:?}", e),
+        };
+
+        let mut replies = Vec::new();
+        match &packet {
+            Packet::Connect(..) => {
+                replies.push(Packet::ConnAck(ConnAck {
+                    session_present: false,
+                    code: ConnectReturnCode::Success,
+                    properties: None,
+                }));
+            }
+            Packet::Subscribe(subscribe) => {
+                replies.push(Packet::SubAck(SubAck {
+                    pkid: subscribe.pkid,
+                    return_codes: vec![SubscribeReasonCode::Success(QoS::AtLeastOnce)],
+                    properties: None,
+                }));
+                for publish in commands.take().unwrap_or_default() {
+                    replies.push(Packet::Publish(publish));
+                }
+            }
+            Packet::Publish(publish) if ack_publishes.load(Ordering::SeqCst) => {
+                match publish.qos {
+                    QoS::AtLeastOnce => replies.push(Packet::PubAck(PubAck::new(publish.pkid, None))),
+                    QoS::ExactlyOnce => replies.push(Packet::PubRec(PubRec::new(publish.pkid, None))),
+                    QoS::AtMostOnce => {}
+                }
+            }
+            Packet::PubRel(pubrel) => replies.push(Packet::PubComp(PubComp::new(pubrel.pkid, None))),
+            Packet::PingReq(_) => replies.push(Packet::PingResp(PingResp)),
+            _ => {}
+        }
+
+        let _ = received.send(packet);
+        for reply in replies {
+            write_packet(&mut stream, reply).await;
+        }
+    }
+}
+
+async fn write_packet(stream: &mut TcpStream, packet: Packet) {
+    let mut out = BytesMut::new();
+    packet.write(&mut out).unwrap();
+    stream.write_all(&out).await.unwrap();
+}
+
+pub fn command(pkid: u16, topic: &str, payload: impl Into<Vec<u8>>, properties: Option<PublishProperties>) -> Publish {
+    let mut publish = Publish::new(topic, QoS::AtLeastOnce, payload.into(), properties);
+    publish.pkid = pkid;
+    publish
+}
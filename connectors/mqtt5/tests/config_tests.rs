@@ -82,6 +82,12 @@ fn test_mqtt_config_serialization() {
             ca_path: "/path/to/ca".to_string(),
             verify_cert: false,
         },
+        downlink: Default::default(),
+        mode: Default::default(),
+        sparkplug: Default::default(),
+        store_forward: Default::default(),
+        routes: Vec::new(),
+        tags: Vec::new(),
     };
 
     // 序列化为JSON
@@ -134,6 +140,7 @@ fn test_mqtt_message_creation() {
         device_id: "device-001".to_string(),
         timestamp: 1640995200000, // 2022-01-01 00:00:00 UTC
         points: vec![point.clone()],
+        historical: false,
     };
 
     assert_eq!(message.device_id, "device-001");
@@ -168,6 +175,7 @@ fn test_mqtt_message_serialization() {
         device_id: "gateway-001".to_string(),
         timestamp: 1640995200000,
         points,
+        historical: false,
     };
 
     // 序列化为JSON
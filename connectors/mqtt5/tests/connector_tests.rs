@@ -31,6 +31,12 @@ fn create_test_config() -> MqttCfg {
         },
         buffer_size: 100,
         tls: TlsCfg::default(),
+        downlink: Default::default(),
+        mode: Default::default(),
+        sparkplug: Default::default(),
+        store_forward: Default::default(),
+        routes: Vec::new(),
+        tags: Vec::new(),
     }
 }
 
@@ -200,6 +206,7 @@ fn test_message_compression_threshold() {
             quality: 2,
             meta: HashMap::new(),
         }],
+        historical: false,
     };
 
     let large_message = MqttMessage {
@@ -217,6 +224,7 @@ fn test_message_compression_threshold() {
                 meta
             },
         }).collect(),
+        historical: false,
     };
 
     // 序列化消息并检查大小
@@ -0,0 +1,91 @@
+//! MQTT下行命令测试（本地MQTT 5 broker替身）
+
+mod common;
+
+use common::{command, run_broker};
+use frame_bus::{CmdAckFrame, Filter, Value};
+use mqtt5::config::DownlinkCfg;
+use mqtt5::downlink::CommandResponse;
+use mqtt5::{MqttCfg, MqttConnector};
+use rumqttc::v5::mqttbytes::v5::{Packet, PublishProperties};
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn test_command_roundtrip_with_correlation() {
+    let wal_dir = tempdir().unwrap();
+    frame_bus::init(1024, wal_dir.path()).unwrap();
+    let mut bus_commands = frame_bus::subscribe(Filter::cmd_only()).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let properties = PublishProperties {
+        response_topic: Some("cloud/replies/42".to_string()),
+        correlation_data: Some(b"req-42".to_vec().into()),
+        ..Default::default()
+    };
+    let commands = vec![
+        command(1, "site/dl-test/cmd/plant1/valve", r#"{"value": 42, "cmd_id": 7}"#, Some(properties)),
+        command(2, "site/dl-test/cmd/plant1/valve", r#"{"value": {"open": true}}"#, None),
+    ];
+    let (published_tx, mut published) = mpsc::unbounded_channel();
+    tokio::spawn(run_broker(listener, commands, published_tx));
+
+    let cfg = MqttCfg {
+        broker: format!("tcp://127.0.0.1:{}", port),
+        client_id: "dl-test".to_string(),
+        topic_prefix: "site".to_string(),
+        downlink: DownlinkCfg { enabled: true, ..Default::default() },
+        ..Default::default()
+    };
+    let mut connector = MqttConnector::new(cfg);
+    connector.init().await.unwrap();
+    tokio::spawn(async move { connector.start().await });
+
+    // 合法命令进入总线，非法命令直接拒绝
+    let envelope = timeout(Duration::from_secs(5), bus_commands.recv()).await.unwrap().unwrap();
+    let frame = envelope.into_cmd().unwrap();
+    assert_eq!(frame.tag, "plant1.valve");
+    assert_eq!(frame.origin, "mqtt");
+    assert_eq!(frame.value, Some(Value::int(42)));
+
+    let ack = CmdAckFrame::success(frame.cmd_id, "plant1.valve", "modbus-1", Some(Value::int(42)));
+    frame_bus::publisher("modbus-1").unwrap().send_cmd_ack(ack).unwrap();
+
+    let mut acked = None;
+    let mut rejected = None;
+    while acked.is_none() || rejected.is_none() {
+        let publish = match timeout(Duration::from_secs(5), published.recv()).await.unwrap().unwrap() {
+            Packet::Publish(publish) => publish,
+            Packet::Subscribe(subscribe) => {
+                assert_eq!(subscribe.filters[0].path, "site/dl-test/cmd/#");
+                continue;
+            }
+            _ => continue,
+        };
+        let topic = String::from_utf8(publish.topic.to_vec()).unwrap();
+        let response = || serde_json::from_slice::<CommandResponse>(&publish.payload).unwrap();
+        match topic.as_str() {
+            "cloud/replies/42" => {
+                acked = Some(response());
+                let correlation = publish.properties.and_then(|p| p.correlation_data).unwrap();
+                assert_eq!(&correlation[..], b"req-42");
+            }
+            "site/dl-test/cmd_ack/plant1/valve" => rejected = Some(response()),
+            _ => {}
+        }
+    }
+
+    let acked = acked.unwrap();
+    assert!(acked.success);
+    assert_eq!(acked.cmd_id, 7);
+    assert_eq!(acked.driver_id, "modbus-1");
+    assert_eq!(acked.value, Some(serde_json::json!(42)));
+
+    let rejected = rejected.unwrap();
+    assert!(!rejected.success);
+    assert!(rejected.error.is_some());
+}
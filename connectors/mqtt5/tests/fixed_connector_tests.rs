@@ -23,6 +23,12 @@ fn create_test_config() -> MqttCfg {
         compression: CompressionCfg::default(),
         buffer_size: 1000,
         tls: TlsCfg::default(),
+        downlink: Default::default(),
+        mode: Default::default(),
+        sparkplug: Default::default(),
+        store_forward: Default::default(),
+        routes: Vec::new(),
+        tags: Vec::new(),
     }
 }
 
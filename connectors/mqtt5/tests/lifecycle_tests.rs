@@ -0,0 +1,77 @@
+//! 点位过滤与停止测试（本地MQTT 5 broker替身）
+
+mod common;
+
+use common::run_broker;
+use frame_bus::{DataFrame, Value};
+use mqtt5::config::{BatchCfg, MqttMessage};
+use mqtt5::{MqttCfg, MqttConnector};
+use rumqttc::v5::mqttbytes::v5::Packet;
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn test_tag_filter_and_stop() {
+    let wal_dir = tempdir().unwrap();
+    frame_bus::init(1024, wal_dir.path()).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (received_tx, mut received) = mpsc::unbounded_channel();
+    tokio::spawn(run_broker(listener, Vec::new(), received_tx));
+
+    let cfg = MqttCfg {
+        broker: format!("tcp://127.0.0.1:{}", port),
+        client_id: "lifecycle-test".to_string(),
+        qos: 1,
+        batch: BatchCfg { size: 2, timeout: Duration::from_millis(100) },
+        tags: vec!["keep.#".to_string()],
+        ..Default::default()
+    };
+    let mut connector = MqttConnector::new(cfg);
+    connector.init().await.unwrap();
+    let connector = Arc::new(connector);
+    let running = connector.clone();
+    let run = tokio::spawn(async move { running.start().await });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    frame_bus::publish_data(DataFrame::new("drop.a", Value::int(1))).unwrap();
+    frame_bus::publish_data(DataFrame::new("keep.b", Value::int(2))).unwrap();
+    frame_bus::publish_data(DataFrame::new("keep.c", Value::int(3))).unwrap();
+
+    let message: MqttMessage = loop {
+        let packet = timeout(Duration::from_secs(5), received.recv()).await.unwrap().unwrap();
+        if let Packet::Publish(publish) = packet {
+            break serde_json::from_slice(&publish.payload).unwrap();
+        }
+    };
+    let tags: Vec<&str> = message.points.iter().map(|p| p.tag.as_str()).collect();
+    assert_eq!(tags, vec!["keep.b", "keep.c"]);
+
+    // PUBACK后才计入统计
+    timeout(Duration::from_secs(2), async {
+        while connector.stats().snapshot().points_published < 2 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let stats = connector.stats().snapshot();
+    assert!(stats.connected);
+    assert_eq!(stats.messages_published, 1);
+
+    // stop()结束start()并发出DISCONNECT
+    connector.stop().await;
+    timeout(Duration::from_secs(2), run).await.unwrap().unwrap().unwrap();
+    loop {
+        let packet = timeout(Duration::from_secs(2), received.recv()).await.unwrap().unwrap();
+        if matches!(packet, Packet::Disconnect(_)) {
+            break;
+        }
+    }
+    assert!(!connector.stats().snapshot().connected);
+}
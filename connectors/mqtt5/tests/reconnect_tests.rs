@@ -505,5 +505,6 @@ fn create_test_message(device_id: &str, point_count: usize) -> MqttMessage {
             .unwrap()
             .as_millis() as u64,
         points,
+        historical: false,
     }
 }
\ No newline at end of file
@@ -0,0 +1,77 @@
+//! 上行路由测试（本地MQTT 5 broker替身）
+
+mod common;
+
+use common::run_broker;
+use frame_bus::{DataFrame, Value};
+use mqtt5::config::{BatchCfg, CompressionCfg, MqttMessage};
+use mqtt5::{MqttCfg, MqttConnector, PayloadCodec, RouteCfg};
+use rumqttc::v5::mqttbytes::v5::{Packet, Publish};
+use std::collections::HashMap;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn test_routes_with_templates_and_codecs() {
+    let wal_dir = tempdir().unwrap();
+    frame_bus::init(1024, wal_dir.path()).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (received_tx, mut received) = mpsc::unbounded_channel();
+    tokio::spawn(run_broker(listener, Vec::new(), received_tx));
+
+    let cfg = MqttCfg {
+        broker: format!("tcp://127.0.0.1:{}", port),
+        client_id: "route-test".to_string(),
+        qos: 1,
+        batch: BatchCfg { size: 3, timeout: Duration::from_millis(100) },
+        routes: vec![
+            RouteCfg {
+                topic: "plant/{meta.line}/{tag_path}".to_string(),
+                codec: PayloadCodec::FlatJson,
+                tags: vec!["pump.#".to_string()],
+                compression: None,
+            },
+            RouteCfg {
+                topic: "bulk/{device}".to_string(),
+                codec: PayloadCodec::Cbor,
+                tags: Vec::new(),
+                compression: Some(CompressionCfg { enabled: true, level: 3, threshold: 0 }),
+            },
+        ],
+        ..Default::default()
+    };
+    let mut connector = MqttConnector::new(cfg);
+    connector.init().await.unwrap();
+    tokio::spawn(async move { connector.start().await });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    frame_bus::publish_data(DataFrame::new("pump.speed", Value::int(1450)).with_meta("line", "L1")).unwrap();
+    frame_bus::publish_data(DataFrame::new("pump.temp", Value::float(61.5)).with_meta("line", "L2")).unwrap();
+    frame_bus::publish_data(DataFrame::new("valve.open", Value::bool(true)).with_meta("line", "L1")).unwrap();
+
+    let mut publishes: HashMap<String, Publish> = HashMap::new();
+    while publishes.len() < 3 {
+        let packet = timeout(Duration::from_secs(5), received.recv()).await.unwrap().unwrap();
+        if let Packet::Publish(publish) = packet {
+            publishes.insert(String::from_utf8(publish.topic.to_vec()).unwrap(), publish);
+        }
+    }
+
+    assert_eq!(&publishes["plant/L1/pump/speed"].payload[..], b"1450");
+    assert_eq!(&publishes["plant/L2/pump/temp"].payload[..], b"61.5");
+
+    let bulk = &publishes["bulk/route-test"];
+    let properties = bulk.properties.as_ref().unwrap();
+    assert_eq!(properties.content_type.as_deref(), Some("application/cbor"));
+    assert!(properties.user_properties.contains(&("content-encoding".to_string(), "zstd".to_string())));
+    let cbor = zstd::decode_all(&bulk.payload[..]).unwrap();
+    let message: MqttMessage = ciborium::from_reader(&cbor[..]).unwrap();
+    let tags: Vec<&str> = message.points.iter().map(|p| p.tag.as_str()).collect();
+    assert_eq!(tags, vec!["pump.speed", "pump.temp", "valve.open"]);
+    assert_eq!(message.points[2].meta["line"], "L1");
+}
@@ -0,0 +1,106 @@
+//! Sparkplug B会话测试（本地MQTT 5 broker替身）
+
+mod common;
+
+use common::{command, run_broker};
+use frame_bus::{DataFrame, Filter, Value};
+use mqtt5::config::{BatchCfg, PayloadMode, SparkplugCfg};
+use mqtt5::sparkplug::payload::{Metric, Payload};
+use mqtt5::sparkplug::{BD_SEQ_METRIC, REBIRTH_METRIC};
+use mqtt5::{MqttCfg, MqttConnector};
+use prost::Message;
+use rumqttc::v5::mqttbytes::v5::{Packet, Publish};
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+/// 等待下一条发往指定主题的PUBLISH
+async fn next_publish(received: &mut mpsc::UnboundedReceiver<Packet>, topic: &str) -> Payload {
+    loop {
+        let packet = timeout(Duration::from_secs(5), received.recv()).await.unwrap().unwrap();
+        if let Packet::Publish(Publish { topic: t, payload, .. }) = packet {
+            if t == topic.as_bytes() {
+                return Payload::decode(&payload[..]).unwrap();
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_sparkplug_session() {
+    let wal_dir = tempdir().unwrap();
+    frame_bus::init(1024, wal_dir.path()).unwrap();
+    let _keepalive = frame_bus::subscribe(Filter::All).unwrap();
+    let mut bus_commands = frame_bus::subscribe(Filter::cmd_only()).unwrap();
+
+    // BIRTH以最新值缓存中的点位为初始内容
+    frame_bus::publish_data(DataFrame::new("line1.temp", Value::float(20.0))).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let rebirth = Payload::new(0, None, vec![Metric::from_value(Some(&Value::bool(true)), 0).with_name(REBIRTH_METRIC)]);
+    let write = Payload::new(0, None, vec![Metric::from_value(Some(&Value::float(42.0)), 0).with_name("line1/temp")]);
+    let commands = vec![
+        command(1, "spBv1.0/plant/DCMD/edge1/default", write.encode_to_vec(), None),
+        command(2, "spBv1.0/plant/NCMD/edge1", rebirth.encode_to_vec(), None),
+    ];
+    let (received_tx, mut received) = mpsc::unbounded_channel();
+    tokio::spawn(run_broker(listener, commands, received_tx));
+
+    let cfg = MqttCfg {
+        broker: format!("tcp://127.0.0.1:{}", port),
+        client_id: "edge1".to_string(),
+        batch: BatchCfg { size: 100, timeout: Duration::from_millis(50) },
+        mode: PayloadMode::SparkplugB,
+        sparkplug: SparkplugCfg { group_id: "plant".to_string(), ..Default::default() },
+        ..Default::default()
+    };
+    let mut connector = MqttConnector::new(cfg);
+    connector.init().await.unwrap();
+    tokio::spawn(async move { connector.start().await });
+
+    // NDEATH遗嘱携带bdSeq
+    match timeout(Duration::from_secs(5), received.recv()).await.unwrap().unwrap() {
+        Packet::Connect(_, Some(will), _) => {
+            assert_eq!(&will.topic[..], b"spBv1.0/plant/NDEATH/edge1");
+            let death = Payload::decode(&will.message[..]).unwrap();
+            assert_eq!(death.metrics[0].name.as_deref(), Some(BD_SEQ_METRIC));
+        }
+        other => panic!("expected CONNECT with last will, got {:?}", other),
+    }
+
+    let nbirth = next_publish(&mut received, "spBv1.0/plant/NBIRTH/edge1").await;
+    assert_eq!(nbirth.seq, Some(0));
+    let dbirth = next_publish(&mut received, "spBv1.0/plant/DBIRTH/edge1/default").await;
+    let metric = &dbirth.metrics[0];
+    assert_eq!(metric.name.as_deref(), Some("line1/temp"));
+    let alias = metric.alias.unwrap();
+
+    // DCMD写入映射为CmdFrame
+    let envelope = timeout(Duration::from_secs(5), bus_commands.recv()).await.unwrap().unwrap();
+    let frame = envelope.into_cmd().unwrap();
+    assert_eq!(frame.tag, "line1.temp");
+    assert_eq!(frame.value, Some(Value::float(42.0)));
+
+    // NCMD rebirth重新发布BIRTH
+    let rebirth = next_publish(&mut received, "spBv1.0/plant/NBIRTH/edge1").await;
+    assert_eq!(rebirth.seq, Some(0));
+
+    // 仅变化的点位按别名发布
+    frame_bus::publish_data(DataFrame::new("line1.temp", Value::float(20.0))).unwrap();
+    frame_bus::publish_data(DataFrame::new("line1.temp", Value::float(21.0))).unwrap();
+    let ddata = next_publish(&mut received, "spBv1.0/plant/DDATA/edge1/default").await;
+    assert_eq!(ddata.metrics.len(), 1);
+    assert_eq!(ddata.metrics[0].alias, Some(alias));
+    assert!(ddata.metrics[0].name.is_none());
+    assert_eq!(ddata.metrics[0].to_value(), Some(Value::float(21.0)));
+
+    // 新点位只触发所属设备的DBIRTH，seq延续当前会话
+    frame_bus::publish_data(DataFrame::new("line2.flow", Value::float(3.0)).with_meta("device", "plc2")).unwrap();
+    let dbirth = next_publish(&mut received, "spBv1.0/plant/DBIRTH/edge1/plc2").await;
+    assert_eq!(dbirth.metrics.len(), 1);
+    assert_eq!(dbirth.metrics[0].name.as_deref(), Some("line2/flow"));
+    assert_ne!(dbirth.seq, Some(0));
+}
@@ -0,0 +1,238 @@
+//! 断网续传测试（本地MQTT 5 broker替身）
+
+mod common;
+
+use common::run_broker_with;
+use frame_bus::{DataFrame, Filter, Value};
+use mqtt5::config::{BatchCfg, MqttMessage, StoreForwardCfg};
+use mqtt5::store_forward::AckWindow;
+use mqtt5::{MqttCfg, MqttConnector, PayloadCodec, RouteCfg};
+use rumqttc::v5::mqttbytes::v5::Packet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::{sleep, timeout};
+
+/// 等待下一条上行数据消息及其是否带historical用户属性
+async fn next_message(received: &mut mpsc::UnboundedReceiver<Packet>) -> (MqttMessage, bool) {
+    loop {
+        let packet = timeout(Duration::from_secs(5), received.recv()).await.unwrap().unwrap();
+        if let Packet::Publish(publish) = packet {
+            if publish.topic == "site/data/sf-test".as_bytes() {
+                let flagged = publish.properties.is_some_and(|p| {
+                    p.user_properties.contains(&("historical".to_string(), "true".to_string()))
+                });
+                return (serde_json::from_slice(&publish.payload).unwrap(), flagged);
+            }
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_backlog_replayed_after_puback() {
+    let wal_dir = tempdir().unwrap();
+    frame_bus::init(1024, wal_dir.path()).unwrap();
+    let mut frames = frame_bus::subscribe(Filter::data_only()).unwrap();
+
+    // 连接器启动前积压的数据
+    let mut seqs = Vec::new();
+    for i in 0..5 {
+        frame_bus::publish_data(DataFrame::new(format!("sf.t{}", i), Value::int(i))).unwrap();
+        seqs.push(frames.recv().await.unwrap().seq);
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let ack_publishes = Arc::new(AtomicBool::new(false));
+    let (received_tx, mut received) = mpsc::unbounded_channel();
+    tokio::spawn(run_broker_with(listener, Vec::new(), received_tx, ack_publishes.clone()));
+
+    let cfg = MqttCfg {
+        broker: format!("tcp://127.0.0.1:{}", port),
+        client_id: "sf-test".to_string(),
+        topic_prefix: "site".to_string(),
+        qos: 1,
+        timeout: Duration::from_millis(500),
+        reconnect: Duration::from_millis(100),
+        batch: BatchCfg { size: 2, timeout: Duration::from_millis(100) },
+        // 单批在途，验证确认前不推进游标且按序回放
+        store_forward: StoreForwardCfg { from_earliest: true, catch_up_rate: 50, max_inflight: 1, ..Default::default() },
+        ..Default::default()
+    };
+    let mut connector = MqttConnector::new(cfg);
+    connector.init().await.unwrap();
+    tokio::spawn(async move { connector.start().await });
+
+    // 未收到PUBACK时游标停在积压之前
+    let (first, flagged) = next_message(&mut received).await;
+    assert!(first.historical && flagged);
+    sleep(Duration::from_millis(200)).await;
+    assert_eq!(frame_bus::wal::acked_seq("mqtt-sf-test").unwrap(), Some(seqs[0].saturating_sub(1)));
+
+    // 确认超时后重发，之后按序回放剩余积压
+    ack_publishes.store(true, Ordering::SeqCst);
+    let mut tags = Vec::new();
+    while tags.len() < 5 {
+        let (message, flagged) = next_message(&mut received).await;
+        assert!(message.historical && flagged);
+        if message.points[0].tag == first.points[0].tag && !tags.is_empty() {
+            continue;
+        }
+        tags.extend(message.points.into_iter().map(|p| p.tag));
+    }
+    assert_eq!(tags, vec!["sf.t0", "sf.t1", "sf.t2", "sf.t3", "sf.t4"]);
+
+    timeout(Duration::from_secs(5), async {
+        while frame_bus::wal::acked_seq("mqtt-sf-test").unwrap() != seqs.last().copied() {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    // 追平后的数据为实时数据
+    frame_bus::publish_data(DataFrame::new("sf.live", Value::int(99))).unwrap();
+    let (live, flagged) = next_message(&mut received).await;
+    assert!(!live.historical && !flagged);
+    assert_eq!(live.points[0].tag, "sf.live");
+}
+
+#[test]
+fn test_ack_window_advances_contiguous_prefix() {
+    let mut window = AckWindow::default();
+    let first = window.push(Some(10));
+    let second = window.push(Some(20));
+    let third = window.push(Some(30));
+
+    // 后提交的批次先完成时游标不前进
+    window.complete(third);
+    assert_eq!(window.advance(), None);
+    window.complete(first);
+    assert_eq!(window.advance(), Some(10));
+    assert_eq!(window.len(), 2);
+
+    window.complete(second);
+    assert_eq!(window.advance(), Some(30));
+    assert!(window.is_empty());
+}
+
+#[tokio::test]
+async fn test_unacknowledged_messages_dead_lettered() {
+    let wal_dir = tempdir().unwrap();
+    frame_bus::init(1024, wal_dir.path()).unwrap();
+    let mut frames = frame_bus::subscribe(Filter::data_only()).unwrap();
+
+    let mut seqs = Vec::new();
+    for i in 0..4 {
+        frame_bus::publish_data(DataFrame::new(format!("dl.t{}", i), Value::int(i))).unwrap();
+        seqs.push(frames.recv().await.unwrap().seq);
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (received_tx, mut received) = mpsc::unbounded_channel();
+    tokio::spawn(run_broker_with(listener, Vec::new(), received_tx, Arc::new(AtomicBool::new(false))));
+
+    let dead_letter = tempdir().unwrap().keep().join("dead.jsonl");
+    let cfg = MqttCfg {
+        broker: format!("tcp://127.0.0.1:{}", port),
+        client_id: "dl-test".to_string(),
+        // 同一进程内其他测试的帧不进入本连接器
+        tags: vec!["dl.#".to_string()],
+        topic_prefix: "site".to_string(),
+        qos: 1,
+        timeout: Duration::from_millis(100),
+        reconnect: Duration::from_millis(20),
+        batch: BatchCfg { size: 1, timeout: Duration::from_millis(50) },
+        store_forward: StoreForwardCfg {
+            from_earliest: true,
+            catch_up_rate: 0,
+            max_inflight: 4,
+            max_retries: 1,
+            dead_letter_path: dead_letter.to_string_lossy().into_owned(),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut connector = MqttConnector::new(cfg);
+    connector.init().await.unwrap();
+    tokio::spawn(async move { connector.start().await });
+
+    // 多个批次同时在途：首次确认超时前已收到不同批次
+    let mut first_round = Vec::new();
+    while first_round.len() < 2 {
+        let packet = timeout(Duration::from_secs(5), received.recv()).await.unwrap().unwrap();
+        if let Packet::Publish(publish) = packet {
+            let message: MqttMessage = serde_json::from_slice(&publish.payload).unwrap();
+            if !first_round.contains(&message.points[0].tag) {
+                first_round.push(message.points[0].tag.clone());
+            }
+        }
+    }
+
+    // 重试用尽后转入死信，游标越过放弃的批次
+    timeout(Duration::from_secs(5), async {
+        while frame_bus::wal::acked_seq("mqtt-dl-test").unwrap() != seqs.last().copied() {
+            sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    let lines: Vec<serde_json::Value> = std::fs::read_to_string(&dead_letter).unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines.iter().all(|line| line["topic"] == "site/data/dl-test" && line["attempts"] == 2));
+}
+
+#[tokio::test]
+async fn test_batch_messages_published_concurrently() {
+    let wal_dir = tempdir().unwrap();
+    frame_bus::init(1024, wal_dir.path()).unwrap();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (received_tx, mut received) = mpsc::unbounded_channel();
+    tokio::spawn(run_broker_with(listener, Vec::new(), received_tx, Arc::new(AtomicBool::new(false))));
+
+    let cfg = MqttCfg {
+        broker: format!("tcp://127.0.0.1:{}", port),
+        client_id: "cc-test".to_string(),
+        tags: vec!["cc.#".to_string()],
+        qos: 1,
+        timeout: Duration::from_secs(10),
+        batch: BatchCfg { size: 3, timeout: Duration::from_millis(50) },
+        routes: vec![RouteCfg {
+            topic: "cc/{tag_path}".to_string(),
+            codec: PayloadCodec::FlatJson,
+            tags: Vec::new(),
+            compression: None,
+        }],
+        store_forward: StoreForwardCfg { max_inflight: 1, ..Default::default() },
+        ..Default::default()
+    };
+    let mut connector = MqttConnector::new(cfg);
+    connector.init().await.unwrap();
+    tokio::spawn(async move { connector.start().await });
+    sleep(Duration::from_millis(200)).await;
+
+    for i in 0..3 {
+        frame_bus::publish_data(DataFrame::new(format!("cc.t{}", i), Value::int(i))).unwrap();
+    }
+
+    // 同一批次路由出的消息不等前一条确认即全部发出
+    let mut topics = Vec::new();
+    while topics.len() < 3 {
+        let packet = timeout(Duration::from_secs(2), received.recv()).await.unwrap().unwrap();
+        if let Packet::Publish(publish) = packet {
+            topics.push(String::from_utf8(publish.topic.to_vec()).unwrap());
+        }
+    }
+    topics.sort();
+    assert_eq!(topics, vec!["cc/cc/t0", "cc/cc/t1", "cc/cc/t2"]);
+}
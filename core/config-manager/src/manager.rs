@@ -282,6 +282,7 @@ impl ConfigManager {
             address: "40001".to_string(),
             access: Access::Read,
             scale: Some("value / 10.0".to_string()),
+            unscale: None,
             unit: "m³/h".to_string(),
             alarms: Vec::new(),
         });
@@ -169,6 +169,10 @@ pub struct VariableCfg {
     #[serde(default)]
     pub scale: Option<String>,
     
+    /// 反向缩放表达式 (写入时工程值 -> 原始值)
+    #[serde(default)]
+    pub unscale: Option<String>,
+    
     /// 单位
     #[serde(default)]
     pub unit: String,
@@ -158,6 +158,7 @@ fn test_variable_config_serialization() {
         address: "40001".to_string(),
         access: Access::ReadWrite,
         scale: Some("value / 10.0".to_string()),
+        unscale: None,
         unit: "°C".to_string(),
         alarms: vec![
             AlarmCfg {
@@ -326,6 +327,7 @@ fn test_complete_config_structure() {
         address: "40001".to_string(),
         access: Access::Read,
         scale: Some("value / 10.0".to_string()),
+        unscale: None,
         unit: "°C".to_string(),
         alarms: vec![],
     });
@@ -0,0 +1,82 @@
+//! 北向连接器抽象
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// 北向连接器
+///
+/// 每次启动由工厂新建实例，`run()`在连接器运行期间不返回。
+#[async_trait]
+pub trait NorthboundConnector: Send + Sync {
+    /// 连接并运行，直至`stop()`或出错
+    async fn run(&self) -> anyhow::Result<()>;
+
+    /// 停止运行并断开连接
+    async fn stop(&self);
+
+    /// 运行统计
+    fn metrics(&self) -> ConnectorMetrics;
+
+    /// 在WAL上注册的持久消费者ID，连接器移除时一并删除其游标
+    fn durable_consumer(&self) -> Option<String> {
+        None
+    }
+}
+
+/// 连接器运行状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectorState {
+    Stopped,
+    Running,
+    Failed,
+}
+
+/// 连接器运行统计
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ConnectorMetrics {
+    pub connected: bool,
+    pub messages_published: u64,
+    pub points_published: u64,
+    pub publish_errors: u64,
+    pub historical_messages: u64,
+    /// 最后一次确认送达的时间戳（毫秒），从未送达时为0
+    pub last_publish_ms: u64,
+}
+
+/// 连接器状态报告
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectorStatus {
+    pub name: String,
+    pub connector_type: String,
+    pub enabled: bool,
+    pub auto_connect: bool,
+    pub tag_filter: Vec<String>,
+    pub state: ConnectorState,
+    pub last_error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub metrics: ConnectorMetrics,
+}
+
+/// 连接器管理错误
+#[derive(Debug, thiserror::Error)]
+pub enum ConnectorError {
+    #[error("Connector '{0}' not found")]
+    NotFound(String),
+
+    #[error("Connector '{0}' already exists")]
+    AlreadyExists(String),
+
+    #[error("Connector '{0}' is disabled")]
+    Disabled(String),
+
+    #[error("Connector '{0}' is stopping")]
+    Stopping(String),
+
+    #[error("Unsupported connector type '{0}'")]
+    UnsupportedType(String),
+
+    #[error("Invalid connector config: {0}")]
+    InvalidConfig(String),
+}
@@ -0,0 +1,11 @@
+//! ConnectorManager - 北向连接器管理器
+//!
+//! 按名称管理多个北向连接器的生命周期，每个连接器各自的点位过滤、状态与统计
+
+pub mod connector;
+pub mod manager;
+pub mod mqtt;
+
+pub use connector::{ConnectorError, ConnectorMetrics, ConnectorState, ConnectorStatus, NorthboundConnector};
+pub use manager::{ConnectorFactory, ConnectorManager};
+pub use mqtt::MqttNorthbound;
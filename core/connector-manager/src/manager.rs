@@ -0,0 +1,363 @@
+//! 连接器管理器
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use frame_bus::TagPattern;
+use production_config::{validate_connector_type, ConnectorConfig};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use validator::Validate;
+
+use crate::connector::{ConnectorError, ConnectorState, ConnectorStatus, NorthboundConnector};
+use crate::mqtt::MqttNorthbound;
+
+/// 连接器工厂：按名称与配置新建实例
+pub type ConnectorFactory =
+    Arc<dyn Fn(&str, &ConnectorConfig) -> anyhow::Result<Arc<dyn NorthboundConnector>> + Send + Sync>;
+
+/// 停止时等待`run()`返回的时间，超时后中止任务
+const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 运行期状态，运行任务结束时回写
+struct Runtime {
+    state: ConnectorState,
+    last_error: Option<String>,
+    started_at: Option<DateTime<Utc>>,
+    /// 当前或最近一次运行的实例，停止后保留以便查询统计
+    connector: Option<Arc<dyn NorthboundConnector>>,
+    stopping: bool,
+    /// 停止请求已发出、运行任务尚未结束
+    stop_pending: bool,
+}
+
+/// 连接器条目
+struct ConnectorEntry {
+    config: ConnectorConfig,
+    /// 持久消费者ID，移除连接器时删除其游标
+    consumer: Option<String>,
+    runtime: Arc<parking_lot::Mutex<Runtime>>,
+    task_handle: Option<JoinHandle<()>>,
+}
+
+impl ConnectorEntry {
+    fn status(&self, name: &str) -> ConnectorStatus {
+        let runtime = self.runtime.lock();
+        ConnectorStatus {
+            name: name.to_string(),
+            connector_type: self.config.connector_type.clone(),
+            enabled: self.config.enabled,
+            auto_connect: self.config.auto_connect,
+            tag_filter: self.config.tag_filter.clone(),
+            state: runtime.state,
+            last_error: runtime.last_error.clone(),
+            started_at: runtime.started_at,
+            metrics: runtime.connector.as_ref().map(|c| c.metrics()).unwrap_or_default(),
+        }
+    }
+}
+
+/// 北向连接器管理器
+pub struct ConnectorManager {
+    connectors: RwLock<HashMap<String, ConnectorEntry>>,
+    factories: parking_lot::RwLock<HashMap<String, ConnectorFactory>>,
+}
+
+impl Default for ConnectorManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConnectorManager {
+    /// 创建管理器并注册内置的MQTT连接器
+    pub fn new() -> Self {
+        let manager = Self {
+            connectors: RwLock::new(HashMap::new()),
+            factories: parking_lot::RwLock::new(HashMap::new()),
+        };
+        let mqtt: ConnectorFactory = Arc::new(|name, config| Ok(Arc::new(MqttNorthbound::new(name, config)?)));
+        manager.register_factory("mqtt5", mqtt.clone());
+        manager.register_factory("mqtt", mqtt);
+        manager
+    }
+
+    /// 注册连接器类型，同名类型会被替换
+    pub fn register_factory(&self, connector_type: &str, factory: ConnectorFactory) {
+        self.factories.write().insert(connector_type.to_lowercase(), factory);
+    }
+
+    /// 批量加载配置，返回加载失败的连接器
+    pub async fn load(&self, configs: &HashMap<String, ConnectorConfig>) -> Vec<(String, ConnectorError)> {
+        let mut names: Vec<&String> = configs.keys().collect();
+        names.sort();
+
+        let mut failed = Vec::new();
+        for name in names {
+            if let Err(e) = self.add_connector(name, configs[name].clone()).await {
+                failed.push((name.clone(), e));
+            }
+        }
+        failed
+    }
+
+    /// 添加连接器，启用且自动连接时立即启动
+    pub async fn add_connector(&self, name: &str, config: ConnectorConfig) -> Result<(), ConnectorError> {
+        let consumer = self.validate(name, &config)?.durable_consumer();
+
+        let mut connectors = self.connectors.write().await;
+        if connectors.contains_key(name) {
+            return Err(ConnectorError::AlreadyExists(name.to_string()));
+        }
+
+        let auto_start = config.enabled && config.auto_connect;
+        let mut entry = ConnectorEntry {
+            config,
+            consumer,
+            runtime: Arc::new(parking_lot::Mutex::new(Runtime {
+                state: ConnectorState::Stopped,
+                last_error: None,
+                started_at: None,
+                connector: None,
+                stopping: false,
+                stop_pending: false,
+            })),
+            task_handle: None,
+        };
+        if auto_start {
+            self.start_entry(name, &mut entry)?;
+        }
+        connectors.insert(name.to_string(), entry);
+
+        tracing::info!("Added connector: {}", name);
+        Ok(())
+    }
+
+    /// 移除连接器，运行中的先停止
+    pub async fn remove_connector(&self, name: &str) -> Result<(), ConnectorError> {
+        let mut entry = self.connectors.write().await.remove(name)
+            .ok_or_else(|| ConnectorError::NotFound(name.to_string()))?;
+        if let Some(stopping) = Self::take_task(name, &mut entry) {
+            stopping.finish().await;
+        }
+        if let Some(consumer) = &entry.consumer {
+            Self::remove_consumer(name, consumer);
+        }
+
+        tracing::info!("Removed connector: {}", name);
+        Ok(())
+    }
+
+    /// 启动连接器，已在运行时不做处理
+    pub async fn start_connector(&self, name: &str) -> Result<(), ConnectorError> {
+        let mut connectors = self.connectors.write().await;
+        let entry = connectors.get_mut(name)
+            .ok_or_else(|| ConnectorError::NotFound(name.to_string()))?;
+        self.start_entry(name, entry)
+    }
+
+    /// 停止连接器，未运行时不做处理
+    pub async fn stop_connector(&self, name: &str) -> Result<(), ConnectorError> {
+        let stopping = {
+            let mut connectors = self.connectors.write().await;
+            let entry = connectors.get_mut(name)
+                .ok_or_else(|| ConnectorError::NotFound(name.to_string()))?;
+            Self::take_task(name, entry)
+        };
+        if let Some(stopping) = stopping {
+            stopping.finish().await;
+        }
+        Ok(())
+    }
+
+    /// 替换连接器配置，运行中的以新配置重启，新配置禁用时保持停止
+    pub async fn reconfigure_connector(&self, name: &str, config: ConnectorConfig) -> Result<(), ConnectorError> {
+        let consumer = self.validate(name, &config)?.durable_consumer();
+
+        let (stopping, was_running, old_consumer) = {
+            let mut connectors = self.connectors.write().await;
+            let entry = connectors.get_mut(name)
+                .ok_or_else(|| ConnectorError::NotFound(name.to_string()))?;
+
+            let was_running = entry.runtime.lock().state == ConnectorState::Running;
+            let stopping = Self::take_task(name, entry);
+            let old_consumer = entry.consumer.take().filter(|old| consumer.as_ref() != Some(old));
+            entry.config = config;
+            entry.consumer = consumer;
+            (stopping, was_running, old_consumer)
+        };
+        if let Some(stopping) = stopping {
+            stopping.finish().await;
+        }
+        // 消费者ID变更后旧游标不再推进，删除以免阻塞WAL回收
+        if let Some(old) = old_consumer {
+            Self::remove_consumer(name, &old);
+        }
+        if was_running {
+            let mut connectors = self.connectors.write().await;
+            if let Some(entry) = connectors.get_mut(name).filter(|entry| entry.config.enabled) {
+                self.start_entry(name, entry)?;
+            }
+        }
+
+        tracing::info!("Reconfigured connector: {}", name);
+        Ok(())
+    }
+
+    /// 获取连接器状态
+    pub async fn get_connector_status(&self, name: &str) -> Option<ConnectorStatus> {
+        let connectors = self.connectors.read().await;
+        connectors.get(name).map(|entry| entry.status(name))
+    }
+
+    /// 获取连接器配置
+    pub async fn get_connector_config(&self, name: &str) -> Option<ConnectorConfig> {
+        let connectors = self.connectors.read().await;
+        connectors.get(name).map(|entry| entry.config.clone())
+    }
+
+    /// 列出所有连接器，按名称排序
+    pub async fn list_connectors(&self) -> Vec<ConnectorStatus> {
+        let connectors = self.connectors.read().await;
+        let mut list: Vec<ConnectorStatus> = connectors
+            .iter()
+            .map(|(name, entry)| entry.status(name))
+            .collect();
+        list.sort_by(|a, b| a.name.cmp(&b.name));
+        list
+    }
+
+    /// 停止所有连接器
+    pub async fn shutdown(&self) {
+        let stopping: Vec<Stopping> = self.connectors.write().await
+            .iter_mut()
+            .filter_map(|(name, entry)| Self::take_task(name, entry))
+            .collect();
+        for stopping in stopping {
+            stopping.finish().await;
+        }
+    }
+
+    /// 校验名称、配置字段、类型、点位过滤，并试建实例以校验类型相关的设置，返回试建的实例
+    fn validate(&self, name: &str, config: &ConnectorConfig) -> Result<Arc<dyn NorthboundConnector>, ConnectorError> {
+        if name.trim().is_empty() {
+            return Err(ConnectorError::InvalidConfig("connector name must not be empty".to_string()));
+        }
+        config.validate()
+            .map_err(|e| ConnectorError::InvalidConfig(e.to_string()))?;
+        validate_connector_type(&config.connector_type)
+            .map_err(|_| ConnectorError::UnsupportedType(config.connector_type.clone()))?;
+        for pattern in &config.tag_filter {
+            TagPattern::parse(pattern).map_err(|e| ConnectorError::InvalidConfig(e.to_string()))?;
+        }
+        self.create(name, config)
+    }
+
+    /// 由工厂新建实例
+    fn create(&self, name: &str, config: &ConnectorConfig) -> Result<Arc<dyn NorthboundConnector>, ConnectorError> {
+        let factory = self.factories.read()
+            .get(&config.connector_type.to_lowercase())
+            .cloned()
+            .ok_or_else(|| ConnectorError::UnsupportedType(config.connector_type.clone()))?;
+        factory(name, config).map_err(|e| ConnectorError::InvalidConfig(format!("{:#}", e)))
+    }
+
+    /// 删除持久消费者游标，使WAL保留策略不再受其约束
+    fn remove_consumer(name: &str, consumer: &str) {
+        match frame_bus::remove_durable(consumer) {
+            Ok(()) => tracing::info!("Removed consumer '{}' of connector '{}'", consumer, name),
+            Err(e) => tracing::warn!("Failed to remove consumer '{}' of connector '{}': {}", consumer, name, e),
+        }
+    }
+
+    fn start_entry(&self, name: &str, entry: &mut ConnectorEntry) -> Result<(), ConnectorError> {
+        if !entry.config.enabled {
+            return Err(ConnectorError::Disabled(name.to_string()));
+        }
+        if entry.task_handle.as_ref().is_some_and(|handle| !handle.is_finished()) {
+            return Ok(());
+        }
+        if entry.runtime.lock().stop_pending {
+            return Err(ConnectorError::Stopping(name.to_string()));
+        }
+
+        let connector = self.create(name, &entry.config)?;
+        {
+            let mut runtime = entry.runtime.lock();
+            runtime.state = ConnectorState::Running;
+            runtime.last_error = None;
+            runtime.started_at = Some(Utc::now());
+            runtime.connector = Some(connector.clone());
+            runtime.stopping = false;
+        }
+
+        let runtime = entry.runtime.clone();
+        let connector_name = name.to_string();
+        entry.task_handle = Some(tokio::spawn(async move {
+            let result = connector.run().await;
+            let mut runtime = runtime.lock();
+            if runtime.stopping {
+                return;
+            }
+            let error = match result {
+                Ok(()) => "connector exited unexpectedly".to_string(),
+                Err(e) => format!("{:#}", e),
+            };
+            tracing::error!("Connector '{}' failed: {}", connector_name, error);
+            runtime.state = ConnectorState::Failed;
+            runtime.last_error = Some(error);
+        }));
+
+        tracing::info!("Started connector: {}", name);
+        Ok(())
+    }
+
+    /// 取出运行任务并标记停止，调用方释放管理器锁后再等待其结束
+    fn take_task(name: &str, entry: &mut ConnectorEntry) -> Option<Stopping> {
+        let handle = entry.task_handle.take()?;
+        let connector = {
+            let mut runtime = entry.runtime.lock();
+            runtime.stopping = true;
+            runtime.stop_pending = true;
+            runtime.connector.clone()
+        };
+        Some(Stopping {
+            name: name.to_string(),
+            runtime: entry.runtime.clone(),
+            connector,
+            handle,
+        })
+    }
+}
+
+/// 已取出的运行任务，在管理器锁外停止
+struct Stopping {
+    name: String,
+    runtime: Arc<parking_lot::Mutex<Runtime>>,
+    connector: Option<Arc<dyn NorthboundConnector>>,
+    handle: JoinHandle<()>,
+}
+
+impl Stopping {
+    /// 停止连接器并等待`run()`返回，超时后中止任务
+    async fn finish(self) {
+        let Stopping { name, runtime, connector, mut handle } = self;
+        if let Some(connector) = connector {
+            connector.stop().await;
+        }
+
+        if tokio::time::timeout(STOP_TIMEOUT, &mut handle).await.is_err() {
+            handle.abort();
+            let _ = handle.await;
+        }
+
+        let mut runtime = runtime.lock();
+        runtime.stop_pending = false;
+        if runtime.state == ConnectorState::Running {
+            runtime.state = ConnectorState::Stopped;
+        }
+        tracing::info!("Stopped connector: {}", name);
+    }
+}
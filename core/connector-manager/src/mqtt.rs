@@ -0,0 +1,95 @@
+//! MQTT5北向连接器适配
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use mqtt5::metrics::ConnectorStats;
+use mqtt5::route::Router;
+use mqtt5::{MqttCfg, MqttConnector};
+use parking_lot::Mutex;
+use production_config::ConnectorConfig;
+
+use crate::connector::{ConnectorMetrics, NorthboundConnector};
+
+/// 以`MqttConnector`实现的北向连接器
+pub struct MqttNorthbound {
+    cfg: MqttCfg,
+    connector: Mutex<Option<Arc<MqttConnector>>>,
+    /// 最近一次运行的统计，停止后仍可查询
+    stats: Mutex<Option<Arc<ConnectorStats>>>,
+}
+
+impl MqttNorthbound {
+    /// 由连接器配置构建
+    ///
+    /// `settings`按`MqttCfg`解析；超时与保活取连接器配置，
+    /// 客户端ID缺省为连接器名称，`tag_filter`非空时覆盖`settings.tags`。
+    pub fn new(name: &str, config: &ConnectorConfig) -> Result<Self> {
+        let settings = serde_json::Value::Object(config.settings.clone().into_iter().collect());
+        let mut cfg: MqttCfg = serde_json::from_value(settings).context("Invalid MQTT settings")?;
+        cfg.timeout = Duration::from_secs(config.connection_timeout_seconds as u64);
+        cfg.keep_alive = Duration::from_secs(config.keepalive_interval_seconds as u64);
+        if cfg.client_id.is_empty() {
+            cfg.client_id = name.to_string();
+        }
+        if !config.tag_filter.is_empty() {
+            cfg.tags = config.tag_filter.clone();
+        }
+
+        // 提前校验路由，避免启动后才失败
+        Router::new(&cfg, &cfg.client_id)?;
+
+        Ok(Self {
+            cfg,
+            connector: Mutex::new(None),
+            stats: Mutex::new(None),
+        })
+    }
+
+    /// 生效的MQTT配置
+    pub fn config(&self) -> &MqttCfg {
+        &self.cfg
+    }
+}
+
+#[async_trait]
+impl NorthboundConnector for MqttNorthbound {
+    async fn run(&self) -> Result<()> {
+        let mut connector = MqttConnector::new(self.cfg.clone());
+        *self.stats.lock() = Some(connector.stats());
+        connector.init().await?;
+
+        let connector = Arc::new(connector);
+        *self.connector.lock() = Some(connector.clone());
+        connector.start().await
+    }
+
+    async fn stop(&self) {
+        let connector = self.connector.lock().take();
+        if let Some(connector) = connector {
+            connector.stop().await;
+        }
+    }
+
+    fn durable_consumer(&self) -> Option<String> {
+        self.cfg.store_forward.enabled
+            .then(|| mqtt5::store_forward::consumer_id(&self.cfg.store_forward, &self.cfg.client_id))
+    }
+
+    fn metrics(&self) -> ConnectorMetrics {
+        let Some(stats) = self.stats.lock().clone() else {
+            return ConnectorMetrics::default();
+        };
+        let snapshot = stats.snapshot();
+        ConnectorMetrics {
+            connected: snapshot.connected,
+            messages_published: snapshot.messages_published,
+            points_published: snapshot.points_published,
+            publish_errors: snapshot.publish_errors,
+            historical_messages: snapshot.historical_messages,
+            last_publish_ms: snapshot.last_publish_ms,
+        }
+    }
+}
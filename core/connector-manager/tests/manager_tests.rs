@@ -0,0 +1,258 @@
+//! 连接器管理器生命周期测试
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use connector_manager::{
+    ConnectorError, ConnectorFactory, ConnectorManager, ConnectorMetrics, ConnectorState, MqttNorthbound,
+    NorthboundConnector,
+};
+use production_config::ConnectorConfig;
+use serde_json::json;
+use tokio::sync::Notify;
+
+/// 运行直至stop()的连接器，`settings.fail`为true时立即失败
+struct MockConnector {
+    stopped: Notify,
+    fail: bool,
+    tag_filter: Vec<String>,
+    consumer: Option<String>,
+    stop_delay: Duration,
+}
+
+#[async_trait]
+impl NorthboundConnector for MockConnector {
+    async fn run(&self) -> anyhow::Result<()> {
+        if self.fail {
+            return Err(anyhow::anyhow!("broker unreachable"));
+        }
+        self.stopped.notified().await;
+        Ok(())
+    }
+
+    async fn stop(&self) {
+        tokio::time::sleep(self.stop_delay).await;
+        self.stopped.notify_one();
+    }
+
+    fn metrics(&self) -> ConnectorMetrics {
+        ConnectorMetrics {
+            points_published: self.tag_filter.len() as u64,
+            ..Default::default()
+        }
+    }
+
+    fn durable_consumer(&self) -> Option<String> {
+        self.consumer.clone()
+    }
+}
+
+fn config(connector_type: &str, tag_filter: &[&str]) -> ConnectorConfig {
+    ConnectorConfig {
+        connector_type: connector_type.to_string(),
+        enabled: true,
+        auto_connect: true,
+        connection_timeout_seconds: 5,
+        keepalive_interval_seconds: 30,
+        tag_filter: tag_filter.iter().map(|t| t.to_string()).collect(),
+        settings: HashMap::new(),
+    }
+}
+
+/// 注册mock类型，返回实例创建计数
+fn manager_with_mock() -> (ConnectorManager, Arc<AtomicUsize>) {
+    let manager = ConnectorManager::new();
+    let created = Arc::new(AtomicUsize::new(0));
+    let counter = created.clone();
+    let factory: ConnectorFactory = Arc::new(move |_name, config| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Ok(Arc::new(MockConnector {
+            stopped: Notify::new(),
+            fail: config.settings.get("fail").and_then(|v| v.as_bool()).unwrap_or(false),
+            tag_filter: config.tag_filter.clone(),
+            consumer: config.settings.get("consumer").and_then(|v| v.as_str()).map(str::to_string),
+            stop_delay: Duration::from_millis(config.settings.get("stop_delay_ms").and_then(|v| v.as_u64()).unwrap_or(0)),
+        }))
+    });
+    manager.register_factory("custom", factory);
+    (manager, created)
+}
+
+#[tokio::test]
+async fn test_connector_lifecycle() {
+    let (manager, created) = manager_with_mock();
+
+    manager.add_connector("cloud", config("custom", &["pump.#"])).await.unwrap();
+    let mut manual = config("custom", &[]);
+    manual.auto_connect = false;
+    manager.add_connector("backup", manual).await.unwrap();
+
+    let list = manager.list_connectors().await;
+    assert_eq!(list.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["backup", "cloud"]);
+    assert_eq!(list[0].state, ConnectorState::Stopped);
+    assert_eq!(list[1].state, ConnectorState::Running);
+    assert_eq!(list[1].tag_filter, vec!["pump.#"]);
+    assert!(list[1].started_at.is_some());
+
+    manager.start_connector("backup").await.unwrap();
+    manager.start_connector("backup").await.unwrap();
+    assert_eq!(manager.get_connector_status("backup").await.unwrap().state, ConnectorState::Running);
+
+    manager.stop_connector("cloud").await.unwrap();
+    let status = manager.get_connector_status("cloud").await.unwrap();
+    assert_eq!(status.state, ConnectorState::Stopped);
+    assert_eq!(status.metrics.points_published, 1);
+
+    // 停止中的连接器重新配置后保持停止，运行中的以新配置重启
+    manager.reconfigure_connector("cloud", config("custom", &["a.#", "b.#"])).await.unwrap();
+    assert_eq!(manager.get_connector_status("cloud").await.unwrap().state, ConnectorState::Stopped);
+    manager.reconfigure_connector("backup", config("custom", &["a.#", "b.#"])).await.unwrap();
+    let status = manager.get_connector_status("backup").await.unwrap();
+    assert_eq!(status.state, ConnectorState::Running);
+    assert_eq!(status.metrics.points_published, 2);
+    // 每次添加/重新配置试建一次实例，每次启动新建一次
+    assert_eq!(created.load(Ordering::SeqCst), 7);
+
+    manager.remove_connector("backup").await.unwrap();
+    assert!(manager.get_connector_status("backup").await.is_none());
+    assert!(matches!(manager.stop_connector("backup").await, Err(ConnectorError::NotFound(_))));
+
+    manager.shutdown().await;
+    assert!(manager.list_connectors().await.iter().all(|s| s.state == ConnectorState::Stopped));
+}
+
+#[tokio::test]
+async fn test_failed_connector_reports_error() {
+    let (manager, _) = manager_with_mock();
+    let mut failing = config("custom", &[]);
+    failing.settings.insert("fail".to_string(), json!(true));
+    manager.add_connector("flaky", failing).await.unwrap();
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while manager.get_connector_status("flaky").await.unwrap().state != ConnectorState::Failed {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let status = manager.get_connector_status("flaky").await.unwrap();
+    assert_eq!(status.last_error.as_deref(), Some("broker unreachable"));
+
+    // 修正配置后重新配置不会自动启动失败的连接器，需显式启动
+    manager.reconfigure_connector("flaky", config("custom", &[])).await.unwrap();
+    manager.start_connector("flaky").await.unwrap();
+    let status = manager.get_connector_status("flaky").await.unwrap();
+    assert_eq!(status.state, ConnectorState::Running);
+    assert_eq!(status.last_error, None);
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_invalid_connectors_rejected() {
+    let (manager, _) = manager_with_mock();
+
+    let result = manager.add_connector("web", config("http", &[])).await;
+    assert!(matches!(result, Err(ConnectorError::UnsupportedType(_))));
+    let result = manager.add_connector("bad", config("ftp", &[])).await;
+    assert!(matches!(result, Err(ConnectorError::UnsupportedType(_))));
+    let result = manager.add_connector("bad", config("custom", &["a.#.b"])).await;
+    assert!(matches!(result, Err(ConnectorError::InvalidConfig(_))));
+    let result = manager.add_connector("", config("custom", &[])).await;
+    assert!(matches!(result, Err(ConnectorError::InvalidConfig(_))));
+
+    let mut timeout = config("custom", &[]);
+    timeout.connection_timeout_seconds = 0;
+    let result = manager.add_connector("bad", timeout).await;
+    assert!(matches!(result, Err(ConnectorError::InvalidConfig(_))));
+
+    // MQTT缺少broker
+    let result = manager.add_connector("mqtt", config("mqtt5", &[])).await;
+    assert!(matches!(result, Err(ConnectorError::InvalidConfig(_))));
+
+    manager.add_connector("dup", config("custom", &[])).await.unwrap();
+    let result = manager.add_connector("dup", config("custom", &[])).await;
+    assert!(matches!(result, Err(ConnectorError::AlreadyExists(_))));
+
+    let mut disabled = config("custom", &[]);
+    disabled.enabled = false;
+    manager.add_connector("off", disabled).await.unwrap();
+    assert!(matches!(manager.start_connector("off").await, Err(ConnectorError::Disabled(_))));
+
+    let mut configs = HashMap::new();
+    configs.insert("ok".to_string(), config("custom", &[]));
+    configs.insert("dup".to_string(), config("custom", &[]));
+    let failed = manager.load(&configs).await;
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].0, "dup");
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_slow_stop_does_not_block_queries() {
+    let (manager, _) = manager_with_mock();
+    let manager = Arc::new(manager);
+    let mut cfg = config("custom", &[]);
+    cfg.settings.insert("stop_delay_ms".to_string(), json!(1000));
+    manager.add_connector("slow", cfg).await.unwrap();
+
+    let stopping = tokio::spawn({
+        let manager = manager.clone();
+        async move { manager.stop_connector("slow").await }
+    });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // 停止期间查询不等待，重新启动被拒绝
+    let list = tokio::time::timeout(Duration::from_millis(200), manager.list_connectors()).await.unwrap();
+    assert_eq!(list[0].state, ConnectorState::Running);
+    assert!(matches!(manager.start_connector("slow").await, Err(ConnectorError::Stopping(_))));
+
+    stopping.await.unwrap().unwrap();
+    assert_eq!(manager.get_connector_status("slow").await.unwrap().state, ConnectorState::Stopped);
+    manager.start_connector("slow").await.unwrap();
+    manager.shutdown().await;
+}
+
+#[tokio::test]
+async fn test_remove_deletes_durable_consumer() {
+    let dir = tempfile::tempdir().unwrap();
+    frame_bus::init(1024, dir.path()).unwrap();
+    let (manager, _) = manager_with_mock();
+
+    let mut cfg = config("custom", &[]);
+    cfg.settings.insert("consumer".to_string(), json!("mqtt-old"));
+    manager.add_connector("cloud", cfg.clone()).await.unwrap();
+    frame_bus::ack("mqtt-old", 1).unwrap();
+
+    // 消费者ID变更时删除旧游标
+    cfg.settings.insert("consumer".to_string(), json!("mqtt-new"));
+    manager.reconfigure_connector("cloud", cfg).await.unwrap();
+    frame_bus::ack("mqtt-new", 1).unwrap();
+    assert_eq!(frame_bus::wal::acked_seq("mqtt-old").unwrap(), None);
+    assert_eq!(frame_bus::wal::acked_seq("mqtt-new").unwrap(), Some(1));
+
+    manager.remove_connector("cloud").await.unwrap();
+    assert_eq!(frame_bus::wal::acked_seq("mqtt-new").unwrap(), None);
+}
+
+#[test]
+fn test_mqtt_settings_mapping() {
+    let mut cfg = config("mqtt5", &["line1.#"]);
+    cfg.settings.insert("broker".to_string(), json!("tcp://127.0.0.1:1883"));
+    cfg.settings.insert("tags".to_string(), json!(["ignored.#"]));
+    cfg.settings.insert("routes".to_string(), json!([{ "topic": "plant/{tag_path}", "codec": "flat_json" }]));
+
+    let connector = MqttNorthbound::new("north", &cfg).unwrap();
+    let mqtt = connector.config();
+    assert_eq!(mqtt.client_id, "north");
+    assert_eq!(mqtt.tags, vec!["line1.#"]);
+    assert_eq!(mqtt.timeout, Duration::from_secs(5));
+    assert_eq!(mqtt.keep_alive, Duration::from_secs(30));
+    assert_eq!(connector.metrics(), ConnectorMetrics::default());
+    assert_eq!(connector.durable_consumer().as_deref(), Some("mqtt-north"));
+
+    cfg.settings.insert("routes".to_string(), json!([{ "topic": "plant/{unknown}" }]));
+    assert!(MqttNorthbound::new("north", &cfg).is_err());
+}
@@ -0,0 +1,385 @@
+//! 命令分发器 - CmdFrame到Driver::write的端到端路由
+//!
+//! 订阅FrameBus上的CmdFrame，按点位模式解析所属驱动实例并调用`Driver::write`，
+//! 写入后回读实际值并发布CmdAckFrame。每个设备一个工作任务，按优先级串行执行写入；
+//! 同一点位的高优先级命令会取代队列中尚未执行的低优先级命令。
+//!
+//! 读循环独占驱动主实例，写入使用单独注册的驱动实例。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use frame_bus::command::{CommandQueue, PendingCommand, POLICY_ID};
+use frame_bus::policy::{CommandPolicy, PolicyDecision};
+use frame_bus::{CmdAckFrame, CmdFrame, Filter, TagIndex, TagPattern, Value};
+use tokio::sync::{broadcast, Notify};
+use tokio::task::JoinHandle;
+
+use crate::driver::Driver;
+
+/// 确认帧出口，默认发布到FrameBus
+pub type AckSink = Arc<dyn Fn(CmdAckFrame) -> Result<()> + Send + Sync>;
+
+/// 分发器配置
+#[derive(Debug, Clone)]
+pub struct DispatcherConfig {
+    /// 写入失败后的最大重试次数
+    pub max_retries: u32,
+    /// 重试间隔
+    pub retry_backoff: Duration,
+    /// 命令未指定timeout_ms时使用的超时
+    pub default_timeout: Duration,
+    /// 订阅命令与发布确认帧使用的ACL主体
+    pub subject: String,
+}
+
+impl Default for DispatcherConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 2,
+            retry_backoff: Duration::from_millis(100),
+            default_timeout: Duration::from_secs(5),
+            subject: "command-dispatcher".to_string(),
+        }
+    }
+}
+
+/// 分发统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DispatcherStats {
+    pub dispatched: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub timeouts: u64,
+    pub retries: u64,
+    pub superseded: u64,
+    pub unroutable: u64,
+    pub rejected: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    dispatched: AtomicU64,
+    succeeded: AtomicU64,
+    failed: AtomicU64,
+    timeouts: AtomicU64,
+    retries: AtomicU64,
+    superseded: AtomicU64,
+    unroutable: AtomicU64,
+    rejected: AtomicU64,
+}
+
+/// 工作任务共享的上下文
+struct Shared {
+    config: DispatcherConfig,
+    ack_sink: AckSink,
+    counters: Counters,
+    policy: Option<Arc<CommandPolicy>>,
+}
+
+impl Shared {
+    fn ack(&self, ack: CmdAckFrame) {
+        if let Some(policy) = &self.policy {
+            policy.record_ack(&ack);
+        }
+        self.publish(ack);
+    }
+
+    fn publish(&self, ack: CmdAckFrame) {
+        let cmd_id = ack.cmd_id;
+        if let Err(e) = (self.ack_sink)(ack) {
+            tracing::warn!("Failed to publish ack for command {}: {}", cmd_id, e);
+        }
+    }
+
+    fn fail(&self, cmd: &CmdFrame, driver_id: &str, error: String) {
+        self.counters.failed.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!("Command {} to '{}' failed: {}", cmd.cmd_id, cmd.tag, error);
+        self.ack(CmdAckFrame::failure(cmd.cmd_id, cmd.tag.clone(), driver_id.to_string(), error));
+    }
+}
+
+/// 单个设备的命令队列与工作任务
+struct Device {
+    patterns: Vec<TagPattern>,
+    queue: Arc<Mutex<CommandQueue>>,
+    wake: Arc<Notify>,
+    task: JoinHandle<()>,
+}
+
+/// 命令分发器
+pub struct CommandDispatcher {
+    shared: Arc<Shared>,
+    routes: RwLock<TagIndex<String>>,
+    devices: RwLock<HashMap<String, Device>>,
+}
+
+impl CommandDispatcher {
+    pub fn new(config: DispatcherConfig) -> Self {
+        let subject = config.subject.clone();
+        let ack_sink: AckSink = Arc::new(move |ack| frame_bus::publisher(&subject)?.send_cmd_ack(ack));
+        Self {
+            shared: Arc::new(Shared { config, ack_sink, counters: Counters::default(), policy: None }),
+            routes: RwLock::new(TagIndex::new()),
+            devices: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// 替换确认帧出口
+    pub fn with_ack_sink(mut self, sink: AckSink) -> Self {
+        let shared = Arc::get_mut(&mut self.shared).expect("ack sink must be set before registering drivers");
+        shared.ack_sink = sink;
+        self
+    }
+
+    /// 写入前按命令策略校验 (限值、联锁、速率、选择-执行)
+    pub fn with_policy(mut self, policy: Arc<CommandPolicy>) -> Self {
+        let shared = Arc::get_mut(&mut self.shared).expect("policy must be set before registering drivers");
+        shared.policy = Some(policy);
+        self
+    }
+
+    /// 注册驱动写入实例，`patterns`为该驱动负责的点位模式 (`plc1.#`)
+    pub fn register_driver(&self, driver_id: &str, driver: Box<dyn Driver>, patterns: &[&str]) -> Result<()> {
+        let patterns = patterns.iter()
+            .map(|p| TagPattern::parse(p))
+            .collect::<Result<Vec<_>>>()?;
+        if patterns.is_empty() {
+            return Err(anyhow::anyhow!("Driver '{}' must own at least one tag pattern", driver_id));
+        }
+
+        let mut devices = self.devices.write().unwrap();
+        if devices.contains_key(driver_id) {
+            return Err(anyhow::anyhow!("Driver '{}' already registered", driver_id));
+        }
+
+        let queue = Arc::new(Mutex::new(CommandQueue::new()));
+        let wake = Arc::new(Notify::new());
+        let task = tokio::spawn(run_device(
+            driver_id.to_string(),
+            Arc::new(tokio::sync::Mutex::new(driver)),
+            queue.clone(),
+            wake.clone(),
+            self.shared.clone(),
+        ));
+
+        let mut routes = self.routes.write().unwrap();
+        for pattern in &patterns {
+            routes.insert(pattern, driver_id.to_string());
+        }
+        devices.insert(driver_id.to_string(), Device { patterns, queue, wake, task });
+
+        tracing::info!("Registered write target for driver {}", driver_id);
+        Ok(())
+    }
+
+    /// 注销驱动，队列中未执行的命令随工作任务一并丢弃
+    pub fn unregister_driver(&self, driver_id: &str) -> bool {
+        let Some(device) = self.devices.write().unwrap().remove(driver_id) else {
+            return false;
+        };
+        device.task.abort();
+        let mut routes = self.routes.write().unwrap();
+        for pattern in &device.patterns {
+            routes.remove(pattern, &driver_id.to_string());
+        }
+        true
+    }
+
+    /// 解析点位所属的驱动
+    pub fn resolve(&self, tag: &str) -> Option<String> {
+        self.routes.read().unwrap().matching(tag).into_iter().next()
+    }
+
+    /// 将命令排入所属设备的队列
+    pub fn dispatch(&self, frame: CmdFrame) -> Result<()> {
+        // 策略拒绝与选择确认由策略直接应答，不进入设备队列
+        if let Some(policy) = &self.shared.policy {
+            match policy.evaluate(&frame) {
+                PolicyDecision::Allow => {}
+                PolicyDecision::Armed => {
+                    self.shared.publish(CmdAckFrame::success(frame.cmd_id, frame.tag.clone(), POLICY_ID.to_string(), None));
+                    return Ok(());
+                }
+                PolicyDecision::Reject(reason) => {
+                    self.shared.counters.rejected.fetch_add(1, Ordering::Relaxed);
+                    self.shared.publish(CmdAckFrame::failure(frame.cmd_id, frame.tag.clone(), POLICY_ID.to_string(), reason.clone()));
+                    return Err(anyhow::anyhow!(reason));
+                }
+            }
+        }
+
+        let Some(driver_id) = self.resolve(&frame.tag) else {
+            self.shared.counters.unroutable.fetch_add(1, Ordering::Relaxed);
+            let error = format!("No driver owns tag '{}'", frame.tag);
+            self.shared.fail(&frame, "", error.clone());
+            return Err(anyhow::anyhow!(error));
+        };
+
+        let devices = self.devices.read().unwrap();
+        let device = devices.get(&driver_id)
+            .ok_or_else(|| anyhow::anyhow!("Driver '{}' not registered", driver_id))?;
+
+        let mut command = PendingCommand::new(frame);
+        command.max_retries = self.shared.config.max_retries;
+        if command.frame.timeout_ms == 0 {
+            command.timeout_at = command.submitted_at + self.shared.config.default_timeout;
+        }
+
+        let superseded = {
+            let mut queue = device.queue.lock().unwrap();
+            let superseded = queue.supersede(&command.frame.tag, command.priority());
+            let cmd_id = command.frame.cmd_id;
+            if let Err(e) = queue.push(command.clone()) {
+                drop(queue);
+                self.shared.fail(&command.frame, &driver_id, e.to_string());
+                return Err(e);
+            }
+            superseded.into_iter().map(|old| (old, cmd_id)).collect::<Vec<_>>()
+        };
+        for (old, by) in superseded {
+            self.shared.counters.superseded.fetch_add(1, Ordering::Relaxed);
+            self.shared.fail(&old.frame, &driver_id, format!("Superseded by command {}", by));
+        }
+
+        self.shared.counters.dispatched.fetch_add(1, Ordering::Relaxed);
+        device.wake.notify_one();
+        Ok(())
+    }
+
+    /// 订阅FrameBus上的命令帧并持续分发
+    pub fn start(self: &Arc<Self>) -> Result<JoinHandle<()>> {
+        let mut rx = frame_bus::subscribe_as(&self.shared.config.subject, Filter::cmd_only())?;
+        let dispatcher = self.clone();
+
+        Ok(tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(envelope) => match envelope.into_cmd() {
+                        Ok(frame) => {
+                            let _ = dispatcher.dispatch(frame);
+                        }
+                        Err(e) => tracing::warn!("Undecodable command frame: {}", e),
+                    },
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::error!("Command dispatcher lagged, {} commands lost", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }))
+    }
+
+    /// 分发统计
+    pub fn stats(&self) -> DispatcherStats {
+        let c = &self.shared.counters;
+        DispatcherStats {
+            dispatched: c.dispatched.load(Ordering::Relaxed),
+            succeeded: c.succeeded.load(Ordering::Relaxed),
+            failed: c.failed.load(Ordering::Relaxed),
+            timeouts: c.timeouts.load(Ordering::Relaxed),
+            retries: c.retries.load(Ordering::Relaxed),
+            superseded: c.superseded.load(Ordering::Relaxed),
+            unroutable: c.unroutable.load(Ordering::Relaxed),
+            rejected: c.rejected.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for CommandDispatcher {
+    fn drop(&mut self) {
+        for device in self.devices.read().unwrap().values() {
+            device.task.abort();
+        }
+    }
+}
+
+/// 设备工作任务：按优先级逐条执行，同一设备的写入不会并发
+async fn run_device(
+    driver_id: String,
+    driver: Arc<tokio::sync::Mutex<Box<dyn Driver>>>,
+    queue: Arc<Mutex<CommandQueue>>,
+    wake: Arc<Notify>,
+    shared: Arc<Shared>,
+) {
+    loop {
+        let next = {
+            let mut queue = queue.lock().unwrap();
+            for expired in queue.remove_timed_out() {
+                shared.counters.timeouts.fetch_add(1, Ordering::Relaxed);
+                shared.fail(&expired.frame, &driver_id, "Timed out waiting in queue".to_string());
+            }
+            queue.pop()
+        };
+
+        match next {
+            Some(command) => execute(&shared, &driver_id, &driver, command).await,
+            None => wake.notified().await,
+        }
+    }
+}
+
+/// 执行单条命令：超时内重试写入，成功后回读
+async fn execute(
+    shared: &Shared,
+    driver_id: &str,
+    driver: &tokio::sync::Mutex<Box<dyn Driver>>,
+    mut command: PendingCommand,
+) {
+    let deadline = tokio::time::Instant::from_std(command.timeout_at);
+    let backoff = shared.config.retry_backoff;
+
+    let result = loop {
+        let attempt = tokio::time::timeout_at(deadline, write_and_read_back(driver, &command.frame)).await;
+        match attempt {
+            Err(_) => break Err(None),
+            Ok(Ok(actual)) => break Ok(actual),
+            Ok(Err(e)) if command.can_retry() && Instant::now() + backoff < command.timeout_at => {
+                tracing::debug!("Retrying command {} after error: {}", command.frame.cmd_id, e);
+                command.retry();
+                shared.counters.retries.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(backoff).await;
+            }
+            Ok(Err(e)) => break Err(Some(e)),
+        }
+    };
+
+    let frame = &command.frame;
+    match result {
+        Ok(actual) => {
+            shared.counters.succeeded.fetch_add(1, Ordering::Relaxed);
+            tracing::debug!("Command {} to '{}' succeeded via {}", frame.cmd_id, frame.tag, driver_id);
+            shared.ack(CmdAckFrame::success(frame.cmd_id, frame.tag.clone(), driver_id.to_string(), actual));
+        }
+        Err(None) => {
+            shared.counters.timeouts.fetch_add(1, Ordering::Relaxed);
+            let elapsed = command.submitted_at.elapsed().as_millis();
+            shared.fail(frame, driver_id, format!("Timed out after {}ms", elapsed));
+        }
+        Err(Some(e)) => {
+            let error = if command.retry_count > 0 {
+                format!("{} (after {} retries)", e, command.retry_count)
+            } else {
+                e.to_string()
+            };
+            shared.fail(frame, driver_id, error);
+        }
+    }
+}
+
+async fn write_and_read_back(driver: &tokio::sync::Mutex<Box<dyn Driver>>, frame: &CmdFrame) -> Result<Option<Value>> {
+    let mut driver = driver.lock().await;
+    driver.write(frame.clone()).await?;
+
+    // 回读失败不影响写入结果
+    match driver.read_tag(&frame.tag).await {
+        Ok(actual) => Ok(actual),
+        Err(e) => {
+            tracing::warn!("Read back of '{}' failed: {}", frame.tag, e);
+            Ok(None)
+        }
+    }
+}
@@ -65,6 +65,11 @@ pub trait Driver: Send + Sync {
         Err(anyhow::anyhow!("Write not supported"))
     }
 
+    /// 读取单个点位的当前值，用于写入后回读确认（可选实现，不支持时返回None）
+    async fn read_tag(&mut self, _tag: &str) -> anyhow::Result<Option<frame_bus::Value>> {
+        Ok(None)
+    }
+
     /// 优雅关闭
     async fn shutdown(&mut self) -> anyhow::Result<()> {
         Ok(())
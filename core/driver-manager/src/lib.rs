@@ -9,9 +9,11 @@ pub mod supervisor;
 pub mod registry;
 pub mod dynamic;
 pub mod registry_manager;
+pub mod dispatcher;
 
 pub use driver::{Driver, DriverMeta, DriverKind, DriverState, StaticDriverEntry};
-pub use manager::DriverManager;
+pub use manager::{DriverManager, CommandRoute};
+pub use dispatcher::{CommandDispatcher, DispatcherConfig, DispatcherStats, AckSink};
 pub use registry::StaticDriverRegistry;
 pub use loader::{DynDriverLoader, WasmDriverLoader};
 pub use dynamic::{DynamicDriverLoader, DynamicDriverInfo, DynamicDriverEvent, SdkDriverWrapper};
@@ -8,7 +8,8 @@ use anyhow::Result;
 use tracing::info;
 use uuid::Uuid;
 
-use crate::driver::{DriverState, DriverMeta};
+use crate::dispatcher::CommandDispatcher;
+use crate::driver::{DriverFactory, DriverState, DriverMeta};
 use crate::registry::StaticDriverRegistry;
 use crate::supervisor::DriverSupervisor;
 use crate::dynamic::DynamicDriverLoader;
@@ -28,6 +29,16 @@ pub struct DriverInstance {
     pub state: DriverState,
     pub supervisor: DriverSupervisor,
     pub task_handle: Option<JoinHandle<()>>,
+    /// 写入路由：启动时创建独立的写入实例注册到命令分发器
+    pub command_route: Option<CommandRoute>,
+}
+
+/// 驱动写入路由，点位来自驱动配置中可写的点位表
+#[derive(Clone)]
+pub struct CommandRoute {
+    pub factory: DriverFactory,
+    pub config: serde_json::Value,
+    pub tags: Vec<String>,
 }
 
 /// 驱动管理器
@@ -36,6 +47,7 @@ pub struct DriverManager {
     static_registry: StaticDriverRegistry,
     dynamic_loader: DynamicDriverLoader,
     registry_manager: RegistryManager,
+    dispatcher: Option<Arc<CommandDispatcher>>,
 }
 
 impl DriverManager {
@@ -56,6 +68,7 @@ impl DriverManager {
             static_registry,
             dynamic_loader,
             registry_manager,
+            dispatcher: None,
         })
     }
     
@@ -72,9 +85,21 @@ impl DriverManager {
             static_registry,
             dynamic_loader,
             registry_manager,
+            dispatcher: None,
         })
     }
 
+    /// 启用命令分发：静态驱动配置中的可写点位路由到该驱动的写入实例
+    pub fn with_dispatcher(mut self, dispatcher: Arc<CommandDispatcher>) -> Self {
+        self.dispatcher = Some(dispatcher);
+        self
+    }
+
+    /// 命令分发器
+    pub fn dispatcher(&self) -> Option<&Arc<CommandDispatcher>> {
+        self.dispatcher.as_ref()
+    }
+
     /// 加载静态驱动
     pub async fn load_static_driver(
         &self,
@@ -87,16 +112,27 @@ impl DriverManager {
 
         let mut driver = factory();
         let meta = driver.meta();
+
+        // 注入实例ID，驱动以此作为总线发布主体
+        let mut config = config;
+        if let Some(obj) = config.as_object_mut() {
+            obj.entry("instance_id").or_insert_with(|| serde_json::Value::String(driver_id.clone()));
+        }
         
         // 初始化驱动
         driver.init(&config).await?;
         
+        let tags = writable_tags(&config);
+        let command_route = (self.dispatcher.is_some() && !tags.is_empty())
+            .then_some(CommandRoute { factory, config, tags });
+
         let supervisor = DriverSupervisor::new(driver_id.clone(), driver);
         let instance = DriverInstance {
             meta,
             state: DriverState::Init,
             supervisor,
             task_handle: None,
+            command_route,
         };
 
         let mut drivers = self.drivers.write().await;
@@ -115,6 +151,15 @@ impl DriverManager {
             return Err(anyhow::anyhow!("Driver '{}' already started", driver_id));
         }
 
+        // 读循环独占主实例，写入使用单独的实例
+        if let (Some(dispatcher), Some(route)) = (&self.dispatcher, &instance.command_route) {
+            let mut writer = (route.factory)();
+            writer.init(&route.config).await?;
+            writer.connect(crate::supervisor::resolve_endpoint().await?).await?;
+            let patterns: Vec<&str> = route.tags.iter().map(String::as_str).collect();
+            dispatcher.register_driver(driver_id, writer, &patterns)?;
+        }
+
         let supervisor = instance.supervisor.clone();
         let handle = tokio::spawn(async move {
             supervisor.run().await;
@@ -134,6 +179,10 @@ impl DriverManager {
             .ok_or_else(|| anyhow::anyhow!("Driver '{}' not found", driver_id))?;
 
         if let Some(handle) = instance.task_handle.take() {
+            if let Some(dispatcher) = &self.dispatcher {
+                dispatcher.unregister_driver(driver_id);
+            }
+
             // 先通知supervisor关闭
             instance.supervisor.shutdown().await;
             
@@ -186,6 +235,7 @@ impl DriverManager {
             state: DriverState::Init,
             supervisor,
             task_handle: None,
+            command_route: None,
         };
 
         let mut drivers = self.drivers.write().await;
@@ -222,6 +272,7 @@ impl DriverManager {
             state: DriverState::Init,
             supervisor,
             task_handle: None,
+            command_route: None,
         };
 
         let mut drivers = self.drivers.write().await;
@@ -445,4 +496,15 @@ impl DriverManager {
         
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// 驱动配置中可写的点位 (`points`或`tags`表中access为w/rw的条目)
+fn writable_tags(config: &serde_json::Value) -> Vec<String> {
+    ["points", "tags"].iter()
+        .filter_map(|key| config.get(*key).and_then(|v| v.as_array()))
+        .flatten()
+        .filter(|point| matches!(point.get("access").and_then(|a| a.as_str()), Some("w" | "rw")))
+        .filter_map(|point| point.get("tag").and_then(|t| t.as_str()))
+        .map(str::to_string)
+        .collect()
+}
@@ -75,12 +75,7 @@ impl DriverSupervisor {
         // 获取frame-bus sender
         let frame_tx = frame_bus::ring::get_publisher()?.clone();
         
-        // 创建一个endpoint handle，支持通过环境变量覆盖
-        let endpoint_url = std::env::var("MODBUS_ENDPOINT")
-            .unwrap_or_else(|_| "tcp://localhost:502".to_string());
-        tracing::info!("Modbus endpoint = {}", endpoint_url);
-        let endpoint_handle = endpoint_kit::from_url(&endpoint_url).await
-            .map_err(|e| anyhow::anyhow!("Failed to create endpoint handle: {}", e))?;
+        let endpoint_handle = resolve_endpoint().await?;
         
         // 连接驱动
         {
@@ -119,4 +114,13 @@ impl DriverSupervisor {
         let mut count = self.restart_count.write().await;
         *count = 0;
     }
-}
\ No newline at end of file
+}
+
+/// 创建驱动端点，支持通过环境变量覆盖
+pub(crate) async fn resolve_endpoint() -> anyhow::Result<Arc<endpoint_kit::EndpointHandle>> {
+    let endpoint_url = std::env::var("MODBUS_ENDPOINT")
+        .unwrap_or_else(|_| "tcp://localhost:502".to_string());
+    tracing::info!("Modbus endpoint = {}", endpoint_url);
+    endpoint_kit::from_url(&endpoint_url).await
+        .map_err(|e| anyhow::anyhow!("Failed to create endpoint handle: {}", e))
+}
@@ -0,0 +1,326 @@
+//! 命令分发器测试
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use driver_manager::{AckSink, CommandDispatcher, DispatcherConfig, Driver, DriverKind, DriverManager, DriverMeta};
+use frame_bus::{AuditOutcome, CmdAckFrame, CmdFrame, CommandPolicy, Filter, PolicyConfig, TagPolicy, Value};
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+/// 测试用的可写Mock驱动，点位值保存在共享表中
+#[derive(Clone, Default)]
+struct WritableDriver {
+    values: Arc<Mutex<HashMap<String, Value>>>,
+    /// 前N次写入失败
+    failures: Arc<AtomicU32>,
+    delay: Duration,
+    in_flight: Arc<AtomicU32>,
+    max_in_flight: Arc<AtomicU32>,
+    writes: Arc<Mutex<Vec<String>>>,
+}
+
+impl WritableDriver {
+    fn with_failures(self, count: u32) -> Self {
+        self.failures.store(count, Ordering::SeqCst);
+        self
+    }
+
+    fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    fn written(&self) -> Vec<String> {
+        self.writes.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Driver for WritableDriver {
+    fn meta(&self) -> DriverMeta {
+        DriverMeta {
+            name: "writable".to_string(),
+            kind: DriverKind::Static,
+            version: "1.0.0".to_string(),
+            api_version: 1,
+            description: "Writable mock driver".to_string(),
+            features: vec!["write".to_string()],
+        }
+    }
+
+    async fn init(&mut self, _cfg: &serde_json::Value) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn connect(&mut self, _pool: Arc<endpoint_kit::EndpointHandle>) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn read_loop(&mut self, _tx: frame_bus::FrameSender) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn write(&mut self, cmd: CmdFrame) -> anyhow::Result<()> {
+        let current = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max_in_flight.fetch_max(current, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        self.in_flight.fetch_sub(1, Ordering::SeqCst);
+
+        if self.failures.load(Ordering::SeqCst) > 0 {
+            self.failures.fetch_sub(1, Ordering::SeqCst);
+            return Err(anyhow::anyhow!("Device busy"));
+        }
+
+        self.writes.lock().unwrap().push(format!("{}={:?}", cmd.tag, cmd.value.as_ref().and_then(|v| v.to_i64())));
+        self.values.lock().unwrap().insert(cmd.tag, cmd.value.unwrap_or_default());
+        Ok(())
+    }
+
+    async fn read_tag(&mut self, tag: &str) -> anyhow::Result<Option<Value>> {
+        Ok(self.values.lock().unwrap().get(tag).cloned())
+    }
+}
+
+fn config() -> DispatcherConfig {
+    DispatcherConfig {
+        max_retries: 2,
+        retry_backoff: Duration::from_millis(10),
+        default_timeout: Duration::from_secs(1),
+        subject: "dispatcher-test".to_string(),
+    }
+}
+
+fn dispatcher() -> (CommandDispatcher, mpsc::UnboundedReceiver<CmdAckFrame>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let sink: AckSink = Arc::new(move |ack| {
+        tx.send(ack).map_err(|e| anyhow::anyhow!("{}", e))
+    });
+    (CommandDispatcher::new(config()).with_ack_sink(sink), rx)
+}
+
+fn cmd(id: u64, tag: &str, value: i64) -> CmdFrame {
+    CmdFrame::new(tag, Value::int(value), "test").with_cmd_id(id)
+}
+
+async fn next_ack(rx: &mut mpsc::UnboundedReceiver<CmdAckFrame>) -> CmdAckFrame {
+    timeout(Duration::from_secs(2), rx.recv()).await
+        .expect("Timed out waiting for ack")
+        .expect("Ack channel closed")
+}
+
+#[tokio::test]
+async fn test_write_acked_with_read_back_value() {
+    let (dispatcher, mut acks) = dispatcher();
+    let driver = WritableDriver::default();
+    dispatcher.register_driver("plc1", Box::new(driver.clone()), &["plant1.#"]).unwrap();
+
+    assert_eq!(dispatcher.resolve("plant1.pump.speed").as_deref(), Some("plc1"));
+    dispatcher.dispatch(cmd(1, "plant1.pump.speed", 1500)).unwrap();
+
+    let ack = next_ack(&mut acks).await;
+    assert!(ack.success, "{}", ack.error_msg);
+    assert_eq!(ack.cmd_id, 1);
+    assert_eq!(ack.driver_id, "plc1");
+    assert_eq!(ack.actual_value.and_then(|v| v.to_i64()), Some(1500));
+    assert_eq!(dispatcher.stats().succeeded, 1);
+}
+
+#[tokio::test]
+async fn test_unroutable_tag_is_nacked() {
+    let (dispatcher, mut acks) = dispatcher();
+    dispatcher.register_driver("plc1", Box::new(WritableDriver::default()), &["plant1.#"]).unwrap();
+
+    assert!(dispatcher.dispatch(cmd(7, "plant2.valve", 1)).is_err());
+
+    let ack = next_ack(&mut acks).await;
+    assert!(!ack.success);
+    assert_eq!(ack.cmd_id, 7);
+    assert!(ack.error_msg.contains("No driver"));
+    assert_eq!(dispatcher.stats().unroutable, 1);
+}
+
+#[tokio::test]
+async fn test_transient_failures_are_retried() {
+    let (dispatcher, mut acks) = dispatcher();
+    dispatcher.register_driver("plc1", Box::new(WritableDriver::default().with_failures(2)), &["plant1.#"]).unwrap();
+    dispatcher.register_driver("plc2", Box::new(WritableDriver::default().with_failures(5)), &["plant2.#"]).unwrap();
+
+    dispatcher.dispatch(cmd(1, "plant1.valve", 1)).unwrap();
+    let ack = next_ack(&mut acks).await;
+    assert!(ack.success, "{}", ack.error_msg);
+
+    dispatcher.dispatch(cmd(2, "plant2.valve", 1)).unwrap();
+    let ack = next_ack(&mut acks).await;
+    assert!(!ack.success);
+    assert!(ack.error_msg.contains("Device busy"));
+    assert!(ack.error_msg.contains("after 2 retries"));
+
+    let stats = dispatcher.stats();
+    assert_eq!(stats.retries, 4);
+    assert_eq!(stats.failed, 1);
+}
+
+#[tokio::test]
+async fn test_slow_write_times_out() {
+    let (dispatcher, mut acks) = dispatcher();
+    let driver = WritableDriver::default().with_delay(Duration::from_millis(500));
+    dispatcher.register_driver("plc1", Box::new(driver), &["plant1.#"]).unwrap();
+
+    dispatcher.dispatch(cmd(1, "plant1.valve", 1).with_timeout_ms(50)).unwrap();
+
+    let ack = next_ack(&mut acks).await;
+    assert!(!ack.success);
+    assert!(ack.error_msg.contains("Timed out"));
+    assert_eq!(dispatcher.stats().timeouts, 1);
+}
+
+#[tokio::test]
+async fn test_priority_order_and_supersede() {
+    let (dispatcher, mut acks) = dispatcher();
+    let driver = WritableDriver::default().with_delay(Duration::from_millis(50));
+    dispatcher.register_driver("plc1", Box::new(driver.clone()), &["plant1.#"]).unwrap();
+
+    // 第一条占用设备，其余在队列中等待
+    dispatcher.dispatch(cmd(1, "plant1.busy", 0)).unwrap();
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    dispatcher.dispatch(cmd(2, "plant1.valve", 10).with_priority(0)).unwrap();
+    dispatcher.dispatch(cmd(3, "plant1.pump", 20).with_priority(1)).unwrap();
+    dispatcher.dispatch(cmd(4, "plant1.valve", 30).with_priority(3)).unwrap();
+
+    let mut results = HashMap::new();
+    for _ in 0..4 {
+        let ack = next_ack(&mut acks).await;
+        results.insert(ack.cmd_id, ack);
+    }
+
+    assert!(!results[&2].success);
+    assert!(results[&2].error_msg.contains("Superseded by command 4"));
+    assert!(results[&1].success && results[&3].success && results[&4].success);
+    assert_eq!(driver.written(), vec!["plant1.busy=Some(0)", "plant1.valve=Some(30)", "plant1.pump=Some(20)"]);
+    assert_eq!(dispatcher.stats().superseded, 1);
+}
+
+#[tokio::test]
+async fn test_writes_serialized_per_device() {
+    let (dispatcher, mut acks) = dispatcher();
+    let plc1 = WritableDriver::default().with_delay(Duration::from_millis(20));
+    let plc2 = WritableDriver::default().with_delay(Duration::from_millis(20));
+    dispatcher.register_driver("plc1", Box::new(plc1.clone()), &["plant1.#"]).unwrap();
+    dispatcher.register_driver("plc2", Box::new(plc2.clone()), &["plant2.#"]).unwrap();
+
+    for i in 0..5 {
+        dispatcher.dispatch(cmd(i, &format!("plant1.tag{}", i), i as i64)).unwrap();
+        dispatcher.dispatch(cmd(100 + i, &format!("plant2.tag{}", i), i as i64)).unwrap();
+    }
+    for _ in 0..10 {
+        assert!(next_ack(&mut acks).await.success);
+    }
+
+    assert_eq!(plc1.max_in_flight.load(Ordering::SeqCst), 1);
+    assert_eq!(plc2.max_in_flight.load(Ordering::SeqCst), 1);
+    assert_eq!(plc1.written().len(), 5);
+
+    assert!(dispatcher.unregister_driver("plc2"));
+    assert!(dispatcher.resolve("plant2.tag0").is_none());
+}
+
+#[tokio::test]
+async fn test_policy_rejections_never_reach_driver() {
+    let (tx, mut acks) = mpsc::unbounded_channel();
+    let sink: AckSink = Arc::new(move |ack| tx.send(ack).map_err(|e| anyhow::anyhow!("{}", e)));
+    let policy = Arc::new(CommandPolicy::new(PolicyConfig {
+        policies: vec![TagPolicy::new("plant1.valve").with_interlock("plant1.pump.running == false")],
+        ..Default::default()
+    }).unwrap());
+    let dispatcher = CommandDispatcher::new(config()).with_ack_sink(sink).with_policy(policy.clone());
+    let driver = WritableDriver::default();
+    dispatcher.register_driver("plc1", Box::new(driver.clone()), &["plant1.#"]).unwrap();
+
+    policy.update_value("plant1.pump.running", Value::bool(true));
+    assert!(dispatcher.dispatch(cmd(1, "plant1.valve", 1)).is_err());
+    let ack = next_ack(&mut acks).await;
+    assert!(!ack.success);
+    assert!(ack.error_msg.contains("Interlock"));
+    assert!(driver.written().is_empty());
+
+    policy.update_value("plant1.pump.running", Value::bool(false));
+    dispatcher.dispatch(cmd(2, "plant1.valve", 1)).unwrap();
+    assert!(next_ack(&mut acks).await.success);
+
+    let outcomes: Vec<_> = policy.audit_log().into_iter().map(|r| (r.cmd_id, r.outcome)).collect();
+    assert!(matches!(outcomes[0], (1, AuditOutcome::Rejected(_))));
+    assert_eq!(outcomes[1..], [(2, AuditOutcome::Accepted), (2, AuditOutcome::Succeeded)]);
+    assert_eq!(dispatcher.stats().rejected, 1);
+}
+
+#[tokio::test]
+async fn test_commands_routed_from_frame_bus() {
+    let dir = tempfile::tempdir().unwrap().keep();
+    frame_bus::init(1024, &dir).expect("Failed to init FrameBus");
+    let _keepalive = frame_bus::subscribe(Filter::All).unwrap();
+    let mut acks = frame_bus::subscribe(Filter::cmd_ack_only()).unwrap();
+
+    let dispatcher = Arc::new(CommandDispatcher::new(config()));
+    let driver = WritableDriver::default();
+    dispatcher.register_driver("plc1", Box::new(driver.clone()), &["plant1.#"]).unwrap();
+    let _task = dispatcher.start().unwrap();
+
+    frame_bus::publisher("hmi").unwrap().send_cmd(cmd(42, "plant1.setpoint", 75)).unwrap();
+
+    let ack = timeout(Duration::from_secs(2), acks.recv()).await
+        .expect("Timed out waiting for ack")
+        .unwrap()
+        .into_cmd_ack()
+        .unwrap();
+    assert!(ack.success, "{}", ack.error_msg);
+    assert_eq!(ack.cmd_id, 42);
+    assert_eq!(ack.actual_value.and_then(|v| v.to_i64()), Some(75));
+}
+
+/// 通过静态注册表创建的驱动实例共享同一份点位值
+fn routed_driver() -> &'static WritableDriver {
+    static DRIVER: OnceLock<WritableDriver> = OnceLock::new();
+    DRIVER.get_or_init(WritableDriver::default)
+}
+
+fn create_routed_driver() -> Box<dyn Driver> {
+    Box::new(routed_driver().clone())
+}
+
+// 动态驱动目录监听会占用一个运行时线程
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_manager_routes_writable_tags_from_driver_config() {
+    driver_manager::registry::register_driver("routed_writable", create_routed_driver);
+    let dir = tempfile::tempdir().unwrap();
+    let (tx, mut acks) = mpsc::unbounded_channel();
+    let sink: AckSink = Arc::new(move |ack| tx.send(ack).map_err(|e| anyhow::anyhow!("{}", e)));
+    let dispatcher = Arc::new(CommandDispatcher::new(config()).with_ack_sink(sink));
+    let manager = DriverManager::with_drivers_dir(dir.path()).unwrap().with_dispatcher(dispatcher.clone());
+
+    manager.load_static_driver("plc1".to_string(), "routed_writable", serde_json::json!({
+        "points": [
+            { "tag": "plant1.setpoint", "access": "rw" },
+            { "tag": "plant1.pv" },
+        ],
+    })).await.unwrap();
+    // 驱动启动前不接收命令
+    assert_eq!(dispatcher.resolve("plant1.setpoint"), None);
+
+    manager.start_driver("plc1").await.unwrap();
+    assert_eq!(dispatcher.resolve("plant1.setpoint").as_deref(), Some("plc1"));
+    assert_eq!(dispatcher.resolve("plant1.pv"), None);
+
+    dispatcher.dispatch(cmd(7, "plant1.setpoint", 60)).unwrap();
+    let ack = next_ack(&mut acks).await;
+    assert!(ack.success, "{}", ack.error_msg);
+    assert_eq!(ack.driver_id, "plc1");
+    assert_eq!(ack.actual_value.and_then(|v| v.to_i64()), Some(60));
+    assert_eq!(routed_driver().written(), vec!["plant1.setpoint=Some(60)"]);
+
+    manager.stop_driver("plc1").await.unwrap();
+    assert_eq!(dispatcher.resolve("plant1.setpoint"), None);
+}
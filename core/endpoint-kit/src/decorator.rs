@@ -3,7 +3,7 @@
 use std::pin::Pin;
 use tokio::io::{AsyncRead, AsyncWrite};
 use async_trait::async_trait;
-use crate::{EndpointUrl, EndpointError};
+use crate::{EndpointUrl, EndpointError, tls::TlsSettings};
 
 /// 创建一个统一的IO trait
 pub trait AsyncIO: AsyncRead + AsyncWrite + Send + Sync + Unpin {}
@@ -23,9 +23,14 @@ pub struct TlsDecorator;
 
 #[async_trait]
 impl EndpointDecorator for TlsDecorator {
-    async fn decorate(&self, _stream: EndpointBox, _url: &EndpointUrl) -> Result<EndpointBox, EndpointError> {
-        // MVP-0 暂不实现TLS，留待后续版本
-        Err(EndpointError::UnsupportedScheme("TLS not implemented in MVP-0".to_string()))
+    async fn decorate(&self, stream: EndpointBox, url: &EndpointUrl) -> Result<EndpointBox, EndpointError> {
+        let settings = TlsSettings::from_url(url)?;
+        let server_name = settings.server_name(url)?;
+        let connector = tokio_rustls::TlsConnector::from(settings.client_config()?);
+
+        let tls_stream = connector.connect(server_name, stream).await
+            .map_err(|e| EndpointError::Tls(format!("TLS handshake with {} failed: {}", url.host(), e)))?;
+        Ok(Box::pin(tls_stream))
     }
 }
 
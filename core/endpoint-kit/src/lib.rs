@@ -10,6 +10,9 @@ pub mod error;
 pub mod control;
 pub mod circuitbreaker;
 pub mod backpressure;
+pub mod serial;
+pub mod udp;
+pub mod tls;
 
 pub use url::{EndpointUrl, NormalizedUrl, Scheme};
 pub use pool::{EndpointHandle, EndpointFactory, get_factory};
@@ -18,6 +21,9 @@ pub use error::EndpointError;
 pub use control::{ControlMsg, send_pause, send_resume, send_drain};
 pub use circuitbreaker::{CircuitBreaker, CircuitBreakerState, CircuitBreakerConfig, CircuitBreakerError};
 pub use backpressure::{BackpressureManager, BackpressureSignal, BackpressureConfig, QueueStatus};
+pub use serial::{SerialSettings, InterFrameStream};
+pub use udp::{UdpStream, DatagramSocket};
+pub use tls::TlsSettings;
 
 pub type Result<T> = std::result::Result<T, EndpointError>;
 
@@ -7,6 +7,7 @@ use tokio::sync::RwLock;
 use bb8::{Pool, PooledConnection};
 use async_trait::async_trait;
 use dashmap::DashMap;
+use config_manager::schemas::{EndpointCfg, Parity};
 
 use crate::{
     EndpointUrl, NormalizedUrl, Scheme, EndpointBox, EndpointError,
@@ -14,6 +15,7 @@ use crate::{
     control::{subscribe_control, ControlMsg},
     metrics::METRICS,
     circuitbreaker::{CircuitBreaker, CircuitBreakerConfig},
+    udp::DatagramSocket,
 };
 
 /// 连接制造器
@@ -43,6 +45,7 @@ impl bb8::ManageConnection for ConnMaker {
 
 /// 端点句柄
 pub struct EndpointHandle {
+    url: EndpointUrl,
     normalized_url: NormalizedUrl,
     pool: Pool<ConnMaker>,
     paused: Arc<RwLock<bool>>,
@@ -60,6 +63,53 @@ impl EndpointHandle {
         self.normalized_url.port
     }
 
+    /// 获取基础传输层scheme
+    pub fn scheme(&self) -> &Scheme {
+        self.url.scheme_stack.last().unwrap()
+    }
+
+    /// 获取原始端点URL
+    pub fn url(&self) -> &EndpointUrl {
+        &self.url
+    }
+
+    /// 打开独占连接 (不经过连接池)
+    ///
+    /// 适用于需要独占持有传输层的协议栈，如Modbus RTU客户端
+    pub async fn open(&self) -> Result<EndpointBox, EndpointError> {
+        if *self.paused.read().await {
+            return Err(EndpointError::Paused);
+        }
+
+        match self.circuit_breaker.call(build_connection_stack(&self.url)).await {
+            Ok(stream) => Ok(stream),
+            Err(crate::circuitbreaker::CircuitBreakerError::CircuitOpen) => {
+                Err(EndpointError::Pool("Circuit breaker is open".to_string()))
+            }
+            Err(crate::circuitbreaker::CircuitBreakerError::OperationFailed(e)) => Err(e),
+        }
+    }
+
+    /// 打开请求/应答式数据报套接字 (仅udp://端点)
+    pub async fn open_datagram(&self) -> Result<DatagramSocket, EndpointError> {
+        if *self.scheme() != Scheme::Udp {
+            return Err(EndpointError::UnsupportedScheme(
+                format!("Datagram socket requires udp://, got {}", self.scheme())
+            ));
+        }
+        if *self.paused.read().await {
+            return Err(EndpointError::Paused);
+        }
+
+        match self.circuit_breaker.call(DatagramSocket::connect(&self.url)).await {
+            Ok(socket) => Ok(socket),
+            Err(crate::circuitbreaker::CircuitBreakerError::CircuitOpen) => {
+                Err(EndpointError::Pool("Circuit breaker is open".to_string()))
+            }
+            Err(crate::circuitbreaker::CircuitBreakerError::OperationFailed(e)) => Err(e),
+        }
+    }
+
     /// 获取连接
     pub async fn acquire(&self) -> Result<PooledConnection<'_, ConnMaker>, EndpointError> {
         let start = Instant::now();
@@ -125,6 +175,49 @@ impl EndpointFactory {
     /// 从URL创建端点句柄
     pub async fn from_url(&self, url_str: &str) -> Result<Arc<EndpointHandle>, EndpointError> {
         let url = EndpointUrl::parse(url_str)?;
+        self.handle_for_url(url).await
+    }
+
+    /// 从endpoints.yml端点配置创建端点句柄
+    ///
+    /// `tls`/`serial`配置项转换为URL查询参数，URL中显式给出的参数优先
+    pub async fn from_config(&self, cfg: &EndpointCfg) -> Result<Arc<EndpointHandle>, EndpointError> {
+        let mut url = EndpointUrl::parse(&cfg.url)?;
+        let mut defaults: Vec<(&str, String)> = Vec::new();
+
+        if let Some(tls) = &cfg.tls {
+            defaults.push(("sni", tls.server_name.clone()));
+            defaults.push(("ca", tls.ca_path.clone()));
+            defaults.push(("cert", tls.cert_path.clone()));
+            defaults.push(("key", tls.key_path.clone()));
+            if !tls.verify_cert {
+                defaults.push(("verify", "false".to_string()));
+            }
+        }
+
+        if let Some(serial) = &cfg.serial {
+            let parity = match serial.parity {
+                Parity::None => "none",
+                Parity::Odd => "odd",
+                Parity::Even => "even",
+            };
+            defaults.push(("baud", serial.baud_rate.to_string()));
+            defaults.push(("data_bits", serial.data_bits.to_string()));
+            defaults.push(("stop_bits", serial.stop_bits.to_string()));
+            defaults.push(("parity", parity.to_string()));
+        }
+
+        for (key, value) in defaults {
+            if !value.is_empty() {
+                url.query.entry(key.to_string()).or_insert(value);
+            }
+        }
+
+        self.handle_for_url(url).await
+    }
+
+    /// 从已解析的URL创建端点句柄
+    async fn handle_for_url(&self, url: EndpointUrl) -> Result<Arc<EndpointHandle>, EndpointError> {
         let normalized = url.normalize();
         
         // 检查缓存
@@ -133,9 +226,11 @@ impl EndpointFactory {
         }
 
         // 创建新的连接池
-        let manager = ConnMaker { url };
+        // 串口同一时刻只能被打开一次
+        let max_size = if url.scheme_stack.last() == Some(&Scheme::Serial) { 1 } else { 4 };
+        let manager = ConnMaker { url: url.clone() };
         let pool = Pool::builder()
-            .max_size(4) // MVP-3要求：默认连接池大小为4
+            .max_size(max_size) // MVP-3要求：默认连接池大小为4
             .build(manager)
             .await
             .map_err(|e| EndpointError::Pool(format!("Failed to create pool: {}", e)))?;
@@ -151,6 +246,7 @@ impl EndpointFactory {
         let circuit_breaker = CircuitBreaker::new(circuit_breaker_config);
 
         let handle = Arc::new(EndpointHandle {
+            url,
             normalized_url: normalized.clone(),
             pool,
             paused: Arc::new(RwLock::new(false)),
@@ -192,8 +288,8 @@ async fn build_connection_stack(url: &EndpointUrl) -> Result<EndpointBox, Endpoi
     // 1. 创建基础传输层
     let mut stream: EndpointBox = match url.scheme_stack.last().unwrap() {
         Scheme::Serial => {
-            // 串口连接 - MVP-0暂不实现
-            return Err(EndpointError::UnsupportedScheme("Serial not implemented in MVP-0".to_string()));
+            // 串口连接，附带3.5字符帧间隔控制
+            Box::pin(crate::serial::connect(url)?)
         }
         Scheme::Tcp | Scheme::Tls | Scheme::Tsn | Scheme::Prp | Scheme::Quic => {
             // TCP连接
@@ -202,8 +298,12 @@ async fn build_connection_stack(url: &EndpointUrl) -> Result<EndpointBox, Endpoi
                 .map_err(EndpointError::Io)?;
             Box::pin(tcp_stream)
         }
-        Scheme::Udp | Scheme::Dtls => {
-            return Err(EndpointError::UnsupportedScheme("UDP not yet implemented".to_string()));
+        Scheme::Udp => {
+            // UDP数据报流，flush时发送一个数据报
+            Box::pin(crate::udp::UdpStream::connect(url).await?)
+        }
+        Scheme::Dtls => {
+            return Err(EndpointError::UnsupportedScheme("DTLS not yet implemented".to_string()));
         }
         other => {
             return Err(EndpointError::UnsupportedScheme(format!("Scheme {:?} not implemented", other)));
@@ -0,0 +1,285 @@
+//! 串口传输层
+//!
+//! 支持 `serial:///dev/ttyUSB0?baud=9600&parity=even&stop_bits=1` 形式的端点，
+//! 并在帧之间强制插入3.5字符静默间隔 (Modbus RTU帧定界要求)
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::time::{Instant, Sleep};
+use tokio_serial::SerialPortBuilderExt;
+
+use crate::{EndpointUrl, EndpointError};
+
+/// 校验位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+impl std::str::FromStr for Parity {
+    type Err = EndpointError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "n" => Ok(Parity::None),
+            "odd" | "o" => Ok(Parity::Odd),
+            "even" | "e" => Ok(Parity::Even),
+            _ => Err(EndpointError::InvalidUrl(format!("Invalid parity: {}", s))),
+        }
+    }
+}
+
+/// 串口参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct SerialSettings {
+    /// 设备路径
+    pub path: String,
+    /// 波特率
+    pub baud_rate: u32,
+    /// 数据位 (5-8)
+    pub data_bits: u8,
+    /// 校验位
+    pub parity: Parity,
+    /// 停止位 (1-2)
+    pub stop_bits: u8,
+}
+
+impl SerialSettings {
+    /// 从URL查询参数解析串口参数 (缺省为9600 8N1)
+    pub fn from_url(url: &EndpointUrl) -> Result<Self, EndpointError> {
+        let path = url.path().unwrap_or(url.host()).to_string();
+        if path.is_empty() {
+            return Err(EndpointError::InvalidUrl("Missing serial device path".to_string()));
+        }
+
+        let query = url.query_params();
+        let baud_rate = parse_param(query.get("baud"), 9600, "baud")?;
+        let data_bits = parse_param(query.get("data_bits"), 8, "data_bits")?;
+        let stop_bits = parse_param(query.get("stop_bits"), 1, "stop_bits")?;
+        let parity = match query.get("parity") {
+            Some(p) => p.parse()?,
+            None => Parity::None,
+        };
+
+        if !(5..=8).contains(&data_bits) {
+            return Err(EndpointError::InvalidUrl(format!("Invalid data_bits: {}", data_bits)));
+        }
+        if !(1..=2).contains(&stop_bits) {
+            return Err(EndpointError::InvalidUrl(format!("Invalid stop_bits: {}", stop_bits)));
+        }
+        if baud_rate == 0 {
+            return Err(EndpointError::InvalidUrl("Invalid baud: 0".to_string()));
+        }
+
+        Ok(Self { path, baud_rate, data_bits, parity, stop_bits })
+    }
+
+    /// 单个字符的传输时间 (起始位 + 数据位 + 校验位 + 停止位)
+    pub fn char_time(&self) -> Duration {
+        let parity_bits = if self.parity == Parity::None { 0 } else { 1 };
+        let bits = 1 + self.data_bits as u64 + parity_bits + self.stop_bits as u64;
+        Duration::from_nanos(bits * 1_000_000_000 / self.baud_rate as u64)
+    }
+
+    /// 帧间静默间隔 (3.5字符)
+    ///
+    /// 按Modbus串行链路规范，波特率高于19200时固定为1.75ms
+    pub fn inter_frame_delay(&self) -> Duration {
+        if self.baud_rate > 19200 {
+            Duration::from_micros(1750)
+        } else {
+            self.char_time() * 7 / 2
+        }
+    }
+
+    /// 打开串口
+    pub fn open(&self) -> Result<tokio_serial::SerialStream, EndpointError> {
+        let parity = match self.parity {
+            Parity::None => tokio_serial::Parity::None,
+            Parity::Odd => tokio_serial::Parity::Odd,
+            Parity::Even => tokio_serial::Parity::Even,
+        };
+        let data_bits = match self.data_bits {
+            5 => tokio_serial::DataBits::Five,
+            6 => tokio_serial::DataBits::Six,
+            7 => tokio_serial::DataBits::Seven,
+            _ => tokio_serial::DataBits::Eight,
+        };
+        let stop_bits = match self.stop_bits {
+            2 => tokio_serial::StopBits::Two,
+            _ => tokio_serial::StopBits::One,
+        };
+
+        tokio_serial::new(&self.path, self.baud_rate)
+            .data_bits(data_bits)
+            .parity(parity)
+            .stop_bits(stop_bits)
+            .open_native_async()
+            .map_err(|e| EndpointError::Io(e.into()))
+    }
+}
+
+fn parse_param<T: std::str::FromStr>(
+    value: Option<&String>,
+    default: T,
+    name: &str,
+) -> Result<T, EndpointError> {
+    match value {
+        Some(v) => v.parse()
+            .map_err(|_| EndpointError::InvalidUrl(format!("Invalid {}: {}", name, v))),
+        None => Ok(default),
+    }
+}
+
+/// 帧间隔流包装器
+///
+/// 一帧由若干次write加一次flush组成；新帧的第一次write会等待，
+/// 直到距上一帧发送完毕或最后一次收到数据已过去3.5字符时间
+pub struct InterFrameStream<S> {
+    inner: S,
+    char_time: Duration,
+    gap: Duration,
+    /// 线路最近一次活动结束的时间
+    idle_since: Option<Instant>,
+    /// 当前帧已写入的字节数 (flush前)
+    pending_bytes: usize,
+    delay: Option<Pin<Box<Sleep>>>,
+}
+
+impl<S> InterFrameStream<S> {
+    pub fn new(inner: S, settings: &SerialSettings) -> Self {
+        Self {
+            inner,
+            char_time: settings.char_time(),
+            gap: settings.inter_frame_delay(),
+            idle_since: None,
+            pending_bytes: 0,
+            delay: None,
+        }
+    }
+
+    /// 帧间静默间隔
+    pub fn gap(&self) -> Duration {
+        self.gap
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for InterFrameStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let before = buf.filled().len();
+        let result = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if matches!(result, Poll::Ready(Ok(()))) && buf.filled().len() > before {
+            self.idle_since = Some(Instant::now());
+        }
+        result
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for InterFrameStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, std::io::Error>> {
+        // 仅在新帧开始时等待静默间隔，帧内的连续写入不能被拉开
+        if self.pending_bytes == 0 {
+            if let Some(idle_since) = self.idle_since {
+                let ready_at = idle_since + self.gap;
+                if Instant::now() < ready_at {
+                    let delay = self.delay
+                        .get_or_insert_with(|| Box::pin(tokio::time::sleep_until(ready_at)));
+                    if delay.as_mut().poll(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                }
+            }
+            self.delay = None;
+        }
+
+        let result = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(n)) = result {
+            self.pending_bytes += n;
+        }
+        result
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        let result = Pin::new(&mut self.inner).poll_flush(cx);
+        if matches!(result, Poll::Ready(Ok(()))) && self.pending_bytes > 0 {
+            // flush只保证数据进入驱动缓冲区，按波特率估算帧在线路上发送完毕的时间
+            let tx_time = self.char_time * self.pending_bytes as u32;
+            self.idle_since = Some(Instant::now() + tx_time);
+            self.pending_bytes = 0;
+        }
+        result
+    }
+
+    fn poll_shutdown(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+/// 打开串口并应用帧间隔控制
+pub fn connect(url: &EndpointUrl) -> Result<InterFrameStream<tokio_serial::SerialStream>, EndpointError> {
+    let settings = SerialSettings::from_url(url)?;
+    let port = settings.open()?;
+    tracing::debug!(
+        "Opened serial port {} at {} baud, inter-frame gap {:?}",
+        settings.path, settings.baud_rate, settings.inter_frame_delay()
+    );
+    Ok(InterFrameStream::new(port, &settings))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_from_url() {
+        let url = EndpointUrl::parse("serial:///dev/ttyUSB0?baud=19200&parity=even&stop_bits=1").unwrap();
+        let settings = SerialSettings::from_url(&url).unwrap();
+        assert_eq!(settings.path, "/dev/ttyUSB0");
+        assert_eq!(settings.baud_rate, 19200);
+        assert_eq!(settings.data_bits, 8);
+        assert_eq!(settings.parity, Parity::Even);
+        assert_eq!(settings.stop_bits, 1);
+    }
+
+    #[test]
+    fn test_inter_frame_delay() {
+        let url = EndpointUrl::parse("serial:///dev/ttyS0?baud=9600").unwrap();
+        let settings = SerialSettings::from_url(&url).unwrap();
+        // 8N1: 10位/字符，9600波特 => 1.0417ms/字符, 3.5字符 ≈ 3.646ms
+        assert_eq!(settings.char_time(), Duration::from_nanos(1_041_666));
+        assert_eq!(settings.inter_frame_delay().as_micros(), 3645);
+
+        let url = EndpointUrl::parse("serial:///dev/ttyS0?baud=115200").unwrap();
+        let settings = SerialSettings::from_url(&url).unwrap();
+        assert_eq!(settings.inter_frame_delay(), Duration::from_micros(1750));
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let url = EndpointUrl::parse("serial:///dev/ttyS0?parity=mark").unwrap();
+        assert!(SerialSettings::from_url(&url).is_err());
+
+        let url = EndpointUrl::parse("serial:///dev/ttyS0?data_bits=9").unwrap();
+        assert!(SerialSettings::from_url(&url).is_err());
+    }
+}
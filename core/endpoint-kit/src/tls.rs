@@ -0,0 +1,198 @@
+//! TLS客户端配置
+//!
+//! 支持的URL查询参数：
+//! - `ca`: CA证书包路径 (PEM)，缺省使用系统根证书
+//! - `cert` / `key`: 客户端证书链与私钥路径 (PEM)，同时提供时启用双向TLS
+//! - `sni`: 覆盖SNI/证书校验使用的服务器名称，缺省为URL主机名
+//! - `verify`: `false`/`0` 时跳过服务器证书校验 (仅限调试)
+
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use std::time::SystemTime;
+use rustls::client::{ServerCertVerified, ServerCertVerifier};
+use rustls::{Certificate, ClientConfig, PrivateKey, RootCertStore, ServerName};
+
+use crate::{EndpointUrl, EndpointError};
+
+/// TLS连接参数
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TlsSettings {
+    /// SNI覆盖
+    pub server_name: Option<String>,
+    /// CA证书包路径
+    pub ca_path: Option<String>,
+    /// 客户端证书路径
+    pub cert_path: Option<String>,
+    /// 客户端私钥路径
+    pub key_path: Option<String>,
+    /// 是否校验服务器证书
+    pub verify_cert: bool,
+}
+
+impl TlsSettings {
+    /// 从URL查询参数解析TLS参数
+    pub fn from_url(url: &EndpointUrl) -> Result<Self, EndpointError> {
+        let query = url.query_params();
+        let non_empty = |key: &str| query.get(key).filter(|v| !v.is_empty()).cloned();
+
+        let verify_cert = match query.get("verify").map(|v| v.to_lowercase()) {
+            None => true,
+            Some(v) if v == "1" || v == "true" => true,
+            Some(v) if v == "0" || v == "false" => false,
+            Some(v) => return Err(EndpointError::InvalidUrl(format!("Invalid verify: {}", v))),
+        };
+
+        let settings = Self {
+            server_name: non_empty("sni"),
+            ca_path: non_empty("ca"),
+            cert_path: non_empty("cert"),
+            key_path: non_empty("key"),
+            verify_cert,
+        };
+
+        if settings.cert_path.is_some() != settings.key_path.is_some() {
+            return Err(EndpointError::Tls("Client certificate requires both cert and key".to_string()));
+        }
+
+        Ok(settings)
+    }
+
+    /// 证书校验与SNI使用的服务器名称
+    pub fn server_name(&self, url: &EndpointUrl) -> Result<ServerName, EndpointError> {
+        let name = self.server_name.as_deref().unwrap_or(url.host());
+        ServerName::try_from(name)
+            .map_err(|e| EndpointError::Tls(format!("Invalid server name '{}': {}", name, e)))
+    }
+
+    /// 构建rustls客户端配置
+    pub fn client_config(&self) -> Result<Arc<ClientConfig>, EndpointError> {
+        let builder = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(self.root_store()?);
+
+        let mut config = match (&self.cert_path, &self.key_path) {
+            (Some(cert), Some(key)) => builder
+                .with_client_auth_cert(load_certs(cert)?, load_private_key(key)?)
+                .map_err(|e| EndpointError::Tls(format!("Invalid client certificate: {}", e)))?,
+            _ => builder.with_no_client_auth(),
+        };
+
+        if !self.verify_cert {
+            tracing::warn!("TLS server certificate verification is disabled");
+            config.dangerous().set_certificate_verifier(Arc::new(NoVerification));
+        }
+
+        Ok(Arc::new(config))
+    }
+
+    fn root_store(&self) -> Result<RootCertStore, EndpointError> {
+        let mut roots = RootCertStore::empty();
+
+        match &self.ca_path {
+            Some(path) => {
+                for cert in load_certs(path)? {
+                    roots.add(&cert)
+                        .map_err(|e| EndpointError::Tls(format!("Invalid CA certificate in {}: {}", path, e)))?;
+                }
+            }
+            None => {
+                let native = rustls_native_certs::load_native_certs()
+                    .map_err(|e| EndpointError::Tls(format!("Failed to load native certificates: {}", e)))?;
+                for cert in native {
+                    // 个别系统证书无法解析时忽略
+                    let _ = roots.add(&Certificate(cert.0));
+                }
+            }
+        }
+
+        if roots.is_empty() && self.verify_cert {
+            return Err(EndpointError::Tls("No trusted CA certificates available".to_string()));
+        }
+
+        Ok(roots)
+    }
+}
+
+fn open_pem(path: &str) -> Result<BufReader<File>, EndpointError> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| EndpointError::Tls(format!("Failed to open {}: {}", path, e)))
+}
+
+/// 读取PEM证书链
+fn load_certs(path: &str) -> Result<Vec<Certificate>, EndpointError> {
+    let certs = rustls_pemfile::certs(&mut open_pem(path)?)
+        .map_err(|e| EndpointError::Tls(format!("Failed to parse {}: {}", path, e)))?;
+    if certs.is_empty() {
+        return Err(EndpointError::Tls(format!("No certificates found in {}", path)));
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+/// 读取PEM私钥 (PKCS#8 / PKCS#1 / SEC1)
+fn load_private_key(path: &str) -> Result<PrivateKey, EndpointError> {
+    let items = rustls_pemfile::read_all(&mut open_pem(path)?)
+        .map_err(|e| EndpointError::Tls(format!("Failed to parse {}: {}", path, e)))?;
+
+    items.into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(key)
+            | rustls_pemfile::Item::RSAKey(key)
+            | rustls_pemfile::Item::ECKey(key) => Some(PrivateKey(key)),
+            _ => None,
+        })
+        .ok_or_else(|| EndpointError::Tls(format!("No private key found in {}", path)))
+}
+
+/// 跳过服务器证书校验 (verify=false)
+struct NoVerification;
+
+impl ServerCertVerifier for NoVerification {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_from_url() {
+        let url = EndpointUrl::parse(
+            "tls+tcp://10.0.0.5:802?ca=/etc/ca.pem&cert=/etc/c.pem&key=/etc/c.key&sni=plc.local"
+        ).unwrap();
+        let settings = TlsSettings::from_url(&url).unwrap();
+        assert_eq!(settings.ca_path.as_deref(), Some("/etc/ca.pem"));
+        assert_eq!(settings.cert_path.as_deref(), Some("/etc/c.pem"));
+        assert_eq!(settings.key_path.as_deref(), Some("/etc/c.key"));
+        assert!(settings.verify_cert);
+
+        let name = settings.server_name(&url).unwrap();
+        assert_eq!(name, ServerName::try_from("plc.local").unwrap());
+    }
+
+    #[test]
+    fn test_cert_requires_key() {
+        let url = EndpointUrl::parse("tls://host:802?cert=/etc/c.pem").unwrap();
+        assert!(matches!(TlsSettings::from_url(&url), Err(EndpointError::Tls(_))));
+    }
+
+    #[test]
+    fn test_verify_flag() {
+        let url = EndpointUrl::parse("tls://host:802?verify=false").unwrap();
+        assert!(!TlsSettings::from_url(&url).unwrap().verify_cert);
+
+        let url = EndpointUrl::parse("tls://host:802?verify=maybe").unwrap();
+        assert!(TlsSettings::from_url(&url).is_err());
+    }
+}
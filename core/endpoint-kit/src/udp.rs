@@ -0,0 +1,192 @@
+//! UDP传输层
+//!
+//! 提供两种使用方式：
+//! - `UdpStream`: 保留报文边界的流适配器，一次flush发送一个数据报，可直接放入装饰器链
+//! - `DatagramSocket`: 请求/应答式接口，按调用方提供的匹配规则关联应答并支持单次请求超时
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::UdpSocket;
+use tokio::time::Instant;
+
+use crate::{EndpointUrl, EndpointError, metrics::METRICS};
+
+/// 单个数据报最大长度
+pub const MAX_DATAGRAM_SIZE: usize = 65507;
+
+/// 绑定本地端口并连接到远端地址
+async fn connect_socket(url: &EndpointUrl) -> Result<UdpSocket, EndpointError> {
+    let addr = url.socket_addr()?;
+    let local: std::net::SocketAddr = if addr.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        (std::net::Ipv6Addr::UNSPECIFIED, 0).into()
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(addr).await?;
+    Ok(socket)
+}
+
+/// 保留报文边界的UDP流
+///
+/// 写入内容在flush时作为一个数据报发送；读取时每次最多返回一个数据报的剩余部分
+pub struct UdpStream {
+    socket: UdpSocket,
+    /// 接收缓冲区，复用于每个数据报
+    read_buf: Vec<u8>,
+    /// 当前数据报长度
+    read_len: usize,
+    read_pos: usize,
+    write_buf: Vec<u8>,
+}
+
+impl UdpStream {
+    pub async fn connect(url: &EndpointUrl) -> Result<Self, EndpointError> {
+        Ok(Self {
+            socket: connect_socket(url).await?,
+            read_buf: vec![0u8; MAX_DATAGRAM_SIZE],
+            read_len: 0,
+            read_pos: 0,
+            write_buf: Vec::new(),
+        })
+    }
+}
+
+impl AsyncRead for UdpStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        let this = &mut *self;
+        if this.read_pos >= this.read_len {
+            let mut datagram_buf = ReadBuf::new(&mut this.read_buf);
+            match this.socket.poll_recv(cx, &mut datagram_buf) {
+                Poll::Ready(Ok(())) => {
+                    this.read_len = datagram_buf.filled().len();
+                    this.read_pos = 0;
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        let remaining = &this.read_buf[this.read_pos..this.read_len];
+        let n = remaining.len().min(buf.remaining());
+        buf.put_slice(&remaining[..n]);
+        this.read_pos += n;
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for UdpStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<Result<usize, std::io::Error>> {
+        if self.write_buf.len() + buf.len() > MAX_DATAGRAM_SIZE {
+            return Poll::Ready(Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "datagram too large",
+            )));
+        }
+        self.write_buf.extend_from_slice(buf);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        if self.write_buf.is_empty() {
+            return Poll::Ready(Ok(()));
+        }
+        let this = &mut *self;
+        match this.socket.poll_send(cx, &this.write_buf) {
+            Poll::Ready(Ok(_)) => {
+                this.write_buf.clear();
+                Poll::Ready(Ok(()))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn poll_shutdown(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(), std::io::Error>> {
+        self.poll_flush(cx)
+    }
+}
+
+/// 请求/应答式数据报套接字
+pub struct DatagramSocket {
+    socket: UdpSocket,
+    recv_buf: Vec<u8>,
+    /// 因不匹配而丢弃的应答数 (迟到或重复的报文)
+    discarded: u64,
+}
+
+impl DatagramSocket {
+    pub async fn connect(url: &EndpointUrl) -> Result<Self, EndpointError> {
+        Ok(Self {
+            socket: connect_socket(url).await?,
+            recv_buf: vec![0u8; MAX_DATAGRAM_SIZE],
+            discarded: 0,
+        })
+    }
+
+    /// 发送单个数据报
+    pub async fn send(&self, payload: &[u8]) -> Result<(), EndpointError> {
+        self.socket.send(payload).await?;
+        Ok(())
+    }
+
+    /// 发送请求并等待匹配的应答
+    ///
+    /// `matches`用于关联应答 (如比较事务ID)，不匹配的数据报被视为上一次超时请求的迟到应答并丢弃
+    pub async fn request<F>(
+        &mut self,
+        payload: &[u8],
+        timeout: Duration,
+        matches: F,
+    ) -> Result<Vec<u8>, EndpointError>
+    where
+        F: Fn(&[u8]) -> bool,
+    {
+        let deadline = Instant::now() + timeout;
+        self.socket.send(payload).await?;
+
+        loop {
+            let len = match tokio::time::timeout_at(deadline, self.socket.recv(&mut self.recv_buf)).await {
+                Ok(result) => result?,
+                Err(_) => {
+                    METRICS.timeout_total.inc();
+                    return Err(EndpointError::Timeout);
+                }
+            };
+
+            let datagram = &self.recv_buf[..len];
+            if matches(datagram) {
+                return Ok(datagram.to_vec());
+            }
+
+            self.discarded += 1;
+            tracing::debug!("Discarded unmatched UDP datagram ({} bytes)", len);
+        }
+    }
+
+    /// 已丢弃的不匹配应答数
+    pub fn discarded(&self) -> u64 {
+        self.discarded
+    }
+
+    /// 本地绑定地址
+    pub fn local_addr(&self) -> Result<std::net::SocketAddr, EndpointError> {
+        Ok(self.socket.local_addr()?)
+    }
+}
@@ -206,7 +206,7 @@ impl EndpointUrl {
     pub fn default_port(&self) -> Option<u16> {
         match self.scheme_stack.last()? {
             Scheme::Tcp => Some(502), // Modbus default
-            Scheme::Tls => Some(502),
+            Scheme::Tls => Some(802), // Modbus/TCP Security
             Scheme::Udp => Some(502),
             Scheme::Dtls => Some(5060),
             Scheme::Quic => Some(443),
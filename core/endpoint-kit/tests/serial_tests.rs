@@ -0,0 +1,69 @@
+//! 串口端点测试 (使用pty对模拟串口)
+
+#![cfg(unix)]
+
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::time::Instant;
+use tokio_serial::{SerialPort, SerialStream};
+use endpoint_kit::{EndpointFactory, Scheme};
+
+/// 创建pty对，返回主端和从端设备路径
+fn open_pty() -> (SerialStream, String) {
+    let (master, slave) = SerialStream::pair().expect("Failed to open pty pair");
+    let path = slave.name().expect("pty slave has no name");
+    drop(slave);
+    (master, path)
+}
+
+#[tokio::test]
+async fn test_serial_endpoint_roundtrip() {
+    let (mut master, path) = open_pty();
+    let factory = EndpointFactory::new();
+    let handle = factory.from_url(&format!("serial://{}?baud=9600", path)).await
+        .expect("Failed to create serial endpoint");
+    assert_eq!(*handle.scheme(), Scheme::Serial);
+
+    let mut port = handle.open().await.expect("Failed to open serial port");
+    port.write_all(&[0x01, 0x03, 0x00, 0x00, 0x00, 0x01]).await.unwrap();
+    port.flush().await.unwrap();
+
+    let mut buf = [0u8; 6];
+    master.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, [0x01, 0x03, 0x00, 0x00, 0x00, 0x01]);
+
+    master.write_all(&[0x01, 0x03, 0x02]).await.unwrap();
+    let mut reply = [0u8; 3];
+    port.read_exact(&mut reply).await.unwrap();
+    assert_eq!(reply, [0x01, 0x03, 0x02]);
+}
+
+#[tokio::test]
+async fn test_serial_inter_frame_gap() {
+    let (mut master, path) = open_pty();
+    let factory = EndpointFactory::new();
+    // 1200波特 8N1: 3.5字符 ≈ 29ms
+    let handle = factory.from_url(&format!("serial://{}?baud=1200", path)).await.unwrap();
+    let mut port = handle.open().await.unwrap();
+
+    // 收到对端数据后立即发送的下一帧必须延迟至少3.5字符时间
+    master.write_all(&[0xAA]).await.unwrap();
+    let mut byte = [0u8; 1];
+    port.read_exact(&mut byte).await.unwrap();
+
+    let start = Instant::now();
+    port.write_all(&[0x01, 0x02]).await.unwrap();
+    port.flush().await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(29));
+
+    let mut buf = [0u8; 2];
+    master.read_exact(&mut buf).await.unwrap();
+    assert_eq!(buf, [0x01, 0x02]);
+}
+
+#[tokio::test]
+async fn test_serial_endpoint_missing_device() {
+    let factory = EndpointFactory::new();
+    let handle = factory.from_url("serial:///dev/does-not-exist?baud=9600").await.unwrap();
+    assert!(handle.open().await.is_err());
+}
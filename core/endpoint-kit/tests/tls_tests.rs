@@ -0,0 +1,158 @@
+//! TLS装饰器测试 (使用tests/fixtures/tls下的测试CA签发的证书)
+
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use rustls::server::AllowAnyAuthenticatedClient;
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use config_manager::schemas::{EndpointCfg, PoolCfg, TlsCfg};
+use endpoint_kit::{EndpointFactory, EndpointError};
+
+fn fixture(name: &str) -> String {
+    format!("{}/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn load_certs(name: &str) -> Vec<Certificate> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(fixture(name)).unwrap());
+    rustls_pemfile::certs(&mut reader).unwrap().into_iter().map(Certificate).collect()
+}
+
+fn load_key(name: &str) -> PrivateKey {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(fixture(name)).unwrap());
+    PrivateKey(rustls_pemfile::pkcs8_private_keys(&mut reader).unwrap().remove(0))
+}
+
+/// 启动回显TLS服务器，`require_client_cert`为true时要求双向TLS
+async fn spawn_echo_server(require_client_cert: bool) -> u16 {
+    let builder = ServerConfig::builder().with_safe_defaults();
+    let config = if require_client_cert {
+        let mut roots = RootCertStore::empty();
+        for cert in load_certs("ca.pem") {
+            roots.add(&cert).unwrap();
+        }
+        builder.with_client_cert_verifier(Arc::new(AllowAnyAuthenticatedClient::new(roots)))
+    } else {
+        builder.with_no_client_auth()
+    }
+    .with_single_cert(load_certs("server.pem"), load_key("server.key"))
+    .unwrap();
+
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        loop {
+            let (tcp, _) = listener.accept().await.unwrap();
+            let acceptor = acceptor.clone();
+            tokio::spawn(async move {
+                if let Ok(mut tls) = acceptor.accept(tcp).await {
+                    let mut buf = [0u8; 64];
+                    while let Ok(n) = tls.read(&mut buf).await {
+                        if n == 0 || tls.write_all(&buf[..n]).await.is_err() {
+                            break;
+                        }
+                    }
+                }
+            });
+        }
+    });
+
+    port
+}
+
+async fn echo(conn: &mut endpoint_kit::EndpointBox) -> std::io::Result<Vec<u8>> {
+    conn.write_all(b"ping").await?;
+    conn.flush().await?;
+    let mut buf = [0u8; 4];
+    conn.read_exact(&mut buf).await?;
+    Ok(buf.to_vec())
+}
+
+#[tokio::test]
+async fn test_tls_with_custom_ca_and_sni() {
+    let port = spawn_echo_server(false).await;
+    let url = format!("tls+tcp://127.0.0.1:{}?ca={}&sni=plc.test", port, fixture("ca.pem"));
+
+    let handle = EndpointFactory::new().from_url(&url).await.unwrap();
+    let mut conn = handle.acquire().await.unwrap();
+    let reply = {
+        let stream: &mut endpoint_kit::EndpointBox = &mut conn;
+        echo(stream).await.unwrap()
+    };
+    assert_eq!(reply, b"ping");
+}
+
+#[tokio::test]
+async fn test_tls_rejects_untrusted_server() {
+    let port = spawn_echo_server(false).await;
+    // 没有提供CA时使用系统根证书，测试CA签发的证书不受信任
+    let url = format!("tls://127.0.0.1:{}?sni=localhost", port);
+
+    let handle = EndpointFactory::new().from_url(&url).await.unwrap();
+    assert!(matches!(handle.open().await, Err(EndpointError::Tls(_))));
+}
+
+#[tokio::test]
+async fn test_tls_sni_mismatch() {
+    let port = spawn_echo_server(false).await;
+    let url = format!("tls://127.0.0.1:{}?ca={}&sni=other.test", port, fixture("ca.pem"));
+
+    let handle = EndpointFactory::new().from_url(&url).await.unwrap();
+    assert!(matches!(handle.open().await, Err(EndpointError::Tls(_))));
+}
+
+#[tokio::test]
+async fn test_tls_skip_verification() {
+    let port = spawn_echo_server(false).await;
+    let url = format!("tls://127.0.0.1:{}?verify=false&sni=localhost", port);
+
+    let handle = EndpointFactory::new().from_url(&url).await.unwrap();
+    let mut conn = handle.open().await.unwrap();
+    assert_eq!(echo(&mut conn).await.unwrap(), b"ping");
+}
+
+#[tokio::test]
+async fn test_mutual_tls() {
+    let port = spawn_echo_server(true).await;
+    let factory = EndpointFactory::new();
+
+    let url = format!(
+        "tls://127.0.0.1:{}?ca={}&cert={}&key={}&sni=localhost",
+        port, fixture("ca.pem"), fixture("client.pem"), fixture("client.key")
+    );
+    let mut conn = factory.from_url(&url).await.unwrap().open().await.unwrap();
+    assert_eq!(echo(&mut conn).await.unwrap(), b"ping");
+
+    // 未提供客户端证书时服务器拒绝连接 (TLS 1.3下在首次读写时才能感知)
+    let url = format!("tls://127.0.0.1:{}?ca={}&sni=localhost", port, fixture("ca.pem"));
+    let result = match factory.from_url(&url).await.unwrap().open().await {
+        Ok(mut conn) => echo(&mut conn).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_tls_from_endpoint_config() {
+    let port = spawn_echo_server(true).await;
+    let cfg = EndpointCfg {
+        url: format!("tls://127.0.0.1:{}", port),
+        description: "secure plc".to_string(),
+        timeout: std::time::Duration::from_secs(5),
+        pool: PoolCfg::default(),
+        tls: Some(TlsCfg {
+            server_name: "plc.test".to_string(),
+            cert_path: fixture("client.pem"),
+            key_path: fixture("client.key"),
+            ca_path: fixture("ca.pem"),
+            verify_cert: true,
+        }),
+        serial: None,
+    };
+
+    let handle = EndpointFactory::new().from_config(&cfg).await.unwrap();
+    let mut conn = handle.open().await.unwrap();
+    assert_eq!(echo(&mut conn).await.unwrap(), b"ping");
+}
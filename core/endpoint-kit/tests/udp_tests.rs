@@ -0,0 +1,79 @@
+//! UDP端点测试
+
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UdpSocket;
+use endpoint_kit::{EndpointFactory, EndpointError};
+
+async fn bind_server() -> (UdpSocket, u16) {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let port = socket.local_addr().unwrap().port();
+    (socket, port)
+}
+
+#[tokio::test]
+async fn test_udp_stream_preserves_datagrams() {
+    let (server, port) = bind_server().await;
+    tokio::spawn(async move {
+        let mut buf = [0u8; 1500];
+        let (len, peer) = server.recv_from(&mut buf).await.unwrap();
+        // 两次write + 一次flush必须合并为一个数据报
+        assert_eq!(&buf[..len], b"hello world");
+        server.send_to(b"pong", peer).await.unwrap();
+    });
+
+    let factory = EndpointFactory::new();
+    let handle = factory.from_url(&format!("udp://127.0.0.1:{}", port)).await.unwrap();
+    let mut conn = handle.acquire().await.unwrap();
+    conn.write_all(b"hello ").await.unwrap();
+    conn.write_all(b"world").await.unwrap();
+    conn.flush().await.unwrap();
+
+    let mut reply = [0u8; 4];
+    tokio::time::timeout(Duration::from_secs(1), conn.read_exact(&mut reply)).await
+        .expect("reply timed out")
+        .unwrap();
+    assert_eq!(&reply, b"pong");
+}
+
+#[tokio::test]
+async fn test_datagram_request_correlation() {
+    let (server, port) = bind_server().await;
+    tokio::spawn(async move {
+        let mut buf = [0u8; 1500];
+        let (len, peer) = server.recv_from(&mut buf).await.unwrap();
+        let id = buf[0];
+        assert_eq!(len, 2);
+        // 先发送一个上次请求的迟到应答，再发送真正的应答
+        server.send_to(&[id.wrapping_sub(1), 0xEE], peer).await.unwrap();
+        server.send_to(&[id, 0x42], peer).await.unwrap();
+    });
+
+    let factory = EndpointFactory::new();
+    let handle = factory.from_url(&format!("udp://127.0.0.1:{}", port)).await.unwrap();
+    let mut socket = handle.open_datagram().await.unwrap();
+
+    let resp = socket.request(&[7, 0x01], Duration::from_secs(1), |d| d.first() == Some(&7)).await.unwrap();
+    assert_eq!(resp, vec![7, 0x42]);
+    assert_eq!(socket.discarded(), 1);
+}
+
+#[tokio::test]
+async fn test_datagram_request_timeout() {
+    // 服务器只接收不应答
+    let (_server, port) = bind_server().await;
+
+    let factory = EndpointFactory::new();
+    let handle = factory.from_url(&format!("udp://127.0.0.1:{}", port)).await.unwrap();
+    let mut socket = handle.open_datagram().await.unwrap();
+
+    let result = socket.request(&[1], Duration::from_millis(50), |_| true).await;
+    assert!(matches!(result, Err(EndpointError::Timeout)));
+}
+
+#[tokio::test]
+async fn test_datagram_requires_udp_scheme() {
+    let factory = EndpointFactory::new();
+    let handle = factory.from_url("tcp://127.0.0.1:1502").await.unwrap();
+    assert!(matches!(handle.open_datagram().await, Err(EndpointError::UnsupportedScheme(_))));
+}
@@ -0,0 +1,398 @@
+//! 最新值缓存 (Last-Value Cache)
+//!
+//! 按点位保存最近一次有效变化的值、时间戳、QoS与元数据。缓存按点位散列分片，
+//! 总线发布路径在数据帧所属分片的锁内更新缓存并广播，非数据帧不经过缓存锁；
+//! 快照持有全部分片的读锁完成订阅，因此"快照 + 订阅"之间既无缺口也无重复：
+//! 快照包含订阅前发布的全部帧，接收端只收到快照之后发布的帧。
+//!
+//! 数值点位可按模式配置死区，变化未超过死区的帧只刷新`last_seen`，不更新缓存值。
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{RwLock, RwLockReadGuard};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use prost::Message;
+
+use crate::envelope::{DataFrame, FrameEnvelope, FrameKind, Value};
+use crate::index::{TagIndex, TagPattern};
+use crate::{Filter, permissions};
+
+/// 缓存分片数，发布路径只锁定点位所属的分片
+const SHARDS: usize = 16;
+
+/// 全局最新值缓存，由总线发布路径维护
+static LAST_VALUES: Lazy<LastValueCache> = Lazy::new(LastValueCache::default);
+
+/// 全局最新值缓存
+pub fn global() -> &'static LastValueCache {
+    &LAST_VALUES
+}
+
+/// 数值死区，变化量需同时超过绝对死区与相对死区才视为变化
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Deadband {
+    /// 绝对死区
+    pub absolute: f64,
+    /// 相对死区，占上次缓存值绝对值的百分比
+    pub percent: f64,
+}
+
+impl Deadband {
+    pub fn absolute(absolute: f64) -> Self {
+        Self { absolute, percent: 0.0 }
+    }
+
+    pub fn percent(percent: f64) -> Self {
+        Self { absolute: 0.0, percent }
+    }
+
+    /// `new`相对`old`的变化是否超过死区
+    fn exceeded(&self, old: f64, new: f64) -> bool {
+        let threshold = self.absolute.max(old.abs() * self.percent / 100.0);
+        (new - old).abs() > threshold
+    }
+}
+
+/// 缓存配置
+#[derive(Debug, Clone, Default)]
+pub struct CacheConfig {
+    /// 按点位模式配置的死区 (`plant1.+.temp`、`plant1.#`)，未匹配的点位值不同即视为变化
+    pub deadbands: Vec<(String, Deadband)>,
+}
+
+impl CacheConfig {
+    pub fn with_deadband<S: Into<String>>(mut self, pattern: S, deadband: Deadband) -> Self {
+        self.deadbands.push((pattern.into(), deadband));
+        self
+    }
+}
+
+/// 点位的缓存值
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedValue {
+    pub value: Option<Value>,
+    /// 帧时间戳 (ns)
+    pub timestamp: u64,
+    pub qos: u32,
+    pub meta: HashMap<String, String>,
+    /// 最近一次有效变化的帧序列号
+    pub seq: u64,
+    /// 最近一帧的时间戳，含死区内被抑制的帧
+    pub last_seen: u64,
+}
+
+impl CachedValue {
+    fn from_frame(seq: u64, frame: DataFrame) -> Self {
+        Self {
+            value: frame.value,
+            timestamp: frame.timestamp,
+            qos: frame.qos,
+            meta: frame.meta,
+            seq,
+            last_seen: frame.timestamp,
+        }
+    }
+
+    /// 还原为数据帧
+    pub fn to_frame<S: Into<String>>(&self, tag: S) -> DataFrame {
+        DataFrame {
+            tag: tag.into(),
+            value: self.value.clone(),
+            timestamp: self.timestamp,
+            qos: self.qos,
+            meta: self.meta.clone(),
+        }
+    }
+}
+
+/// 一帧对缓存的影响
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheUpdate {
+    /// 首次出现的点位
+    New,
+    /// 值、QoS或元数据发生变化
+    Changed,
+    /// 变化在死区内或与缓存值相同
+    Unchanged,
+}
+
+/// 一致性快照
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    /// 快照生效时已应用的最大序列号，None表示尚无数据帧
+    pub seq: Option<u64>,
+    pub values: HashMap<String, CachedValue>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    values: HashMap<String, CachedValue>,
+    last_seq: Option<u64>,
+}
+
+/// 最新值缓存
+#[derive(Default)]
+pub struct LastValueCache {
+    shards: [RwLock<CacheState>; SHARDS],
+    deadbands: RwLock<TagIndex<Deadband>>,
+}
+
+impl LastValueCache {
+    pub fn new(config: &CacheConfig) -> Result<Self> {
+        let cache = Self::default();
+        cache.configure(config)?;
+        Ok(cache)
+    }
+
+    /// 替换死区配置，已缓存的值保留
+    pub fn configure(&self, config: &CacheConfig) -> Result<()> {
+        let mut index = TagIndex::new();
+        for (pattern, deadband) in &config.deadbands {
+            index.insert(&TagPattern::parse(pattern)?, *deadband);
+        }
+        *self.deadbands.write().unwrap() = index;
+        Ok(())
+    }
+
+    /// 应用一个帧封装，非数据帧或无法解码的帧返回None
+    pub fn apply(&self, envelope: &FrameEnvelope) -> Option<CacheUpdate> {
+        let frame = Self::decode(envelope)?;
+        let mut state = self.shard(&frame.tag).write().unwrap();
+        Some(self.apply_frame(&mut state, envelope.seq, frame))
+    }
+
+    /// 应用数据帧后在所属分片的锁内执行`send`，供总线发布路径保证快照与广播的一致性
+    ///
+    /// 非数据帧不进入缓存，直接执行`send`
+    pub(crate) fn apply_then<R>(&self, envelope: FrameEnvelope, send: impl FnOnce(FrameEnvelope) -> R) -> R {
+        let Some(frame) = Self::decode(&envelope) else {
+            return send(envelope);
+        };
+        let mut state = self.shard(&frame.tag).write().unwrap();
+        self.apply_frame(&mut state, envelope.seq, frame);
+        send(envelope)
+    }
+
+    fn shard(&self, tag: &str) -> &RwLock<CacheState> {
+        let mut hasher = DefaultHasher::new();
+        tag.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % SHARDS]
+    }
+
+    /// 按顺序获取全部分片的读锁
+    fn read_all(&self) -> Vec<RwLockReadGuard<'_, CacheState>> {
+        self.shards.iter().map(|shard| shard.read().unwrap()).collect()
+    }
+
+    /// 各分片中最大的已应用序列号
+    fn last_seq(shards: &[RwLockReadGuard<'_, CacheState>]) -> Option<u64> {
+        shards.iter().filter_map(|state| state.last_seq).max()
+    }
+
+    fn decode(envelope: &FrameEnvelope) -> Option<DataFrame> {
+        if envelope.kind() != FrameKind::Data {
+            return None;
+        }
+        DataFrame::decode(&envelope.payload[..]).ok()
+    }
+
+    fn apply_frame(&self, state: &mut CacheState, seq: u64, frame: DataFrame) -> CacheUpdate {
+        state.last_seq = Some(state.last_seq.map_or(seq, |last| last.max(seq)));
+
+        let Some(cached) = state.values.get_mut(&frame.tag) else {
+            state.values.insert(frame.tag.clone(), CachedValue::from_frame(seq, frame));
+            return CacheUpdate::New;
+        };
+        // 乱序到达的旧帧不覆盖缓存
+        if frame.timestamp < cached.timestamp {
+            return CacheUpdate::Unchanged;
+        }
+        cached.last_seen = frame.timestamp;
+        if !self.changed(&frame, cached) {
+            return CacheUpdate::Unchanged;
+        }
+        *cached = CachedValue::from_frame(seq, frame);
+        CacheUpdate::Changed
+    }
+
+    fn changed(&self, frame: &DataFrame, cached: &CachedValue) -> bool {
+        if frame.qos != cached.qos || frame.meta != cached.meta {
+            return true;
+        }
+        let deadbands = self.deadbands.read().unwrap().matching(&frame.tag);
+        match (numeric(&cached.value), numeric(&frame.value)) {
+            // 多条规则匹配同一点位时，超过任一死区即视为变化
+            (Some(old), Some(new)) if !deadbands.is_empty() => deadbands.iter().any(|d| d.exceeded(old, new)),
+            _ => frame.value != cached.value,
+        }
+    }
+
+    /// 点位当前值
+    pub fn get(&self, tag: &str) -> Option<CachedValue> {
+        self.shard(tag).read().unwrap().values.get(tag).cloned()
+    }
+
+    /// 已缓存的点位数
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().unwrap().values.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 全部点位的一致性快照
+    pub fn snapshot(&self) -> Snapshot {
+        let shards = self.read_all();
+        Snapshot {
+            seq: Self::last_seq(&shards),
+            values: shards.iter()
+                .flat_map(|state| state.values.iter())
+                .map(|(tag, cached)| (tag.clone(), cached.clone()))
+                .collect(),
+        }
+    }
+
+    /// 最近一次有效变化晚于`seq`的点位
+    pub fn changes_since(&self, seq: u64) -> Snapshot {
+        let shards = self.read_all();
+        Snapshot {
+            seq: Self::last_seq(&shards),
+            values: shards.iter()
+                .flat_map(|state| state.values.iter())
+                .filter(|(_, cached)| cached.seq > seq)
+                .map(|(tag, cached)| (tag.clone(), cached.clone()))
+                .collect(),
+        }
+    }
+
+    /// 持有全部分片的读锁执行`subscribe`并生成快照，快照只包含匹配过滤器且主体有读权限的点位
+    pub(crate) fn snapshot_with<R>(
+        &self,
+        filter: &Filter,
+        subject: Option<&str>,
+        subscribe: impl FnOnce() -> R,
+    ) -> (Snapshot, R) {
+        let shards = self.read_all();
+        let receiver = subscribe();
+        let values = shards.iter()
+            .flat_map(|state| state.values.iter())
+            .filter(|(tag, cached)| {
+                permissions::authorize_receive(subject, tag)
+                    && FrameEnvelope::wrap_data(cached.seq, cached.to_frame(tag.as_str()))
+                        .is_ok_and(|envelope| filter.matches(&envelope))
+            })
+            .map(|(tag, cached)| (tag.clone(), cached.clone()))
+            .collect();
+        (Snapshot { seq: Self::last_seq(&shards), values }, receiver)
+    }
+
+    /// 清空缓存
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            *shard.write().unwrap() = CacheState::default();
+        }
+    }
+}
+
+/// 数值型值 (布尔与字符串不参与死区比较)
+fn numeric(value: &Option<Value>) -> Option<f64> {
+    use crate::envelope::value::Value as V;
+
+    match value.as_ref()?.value.as_ref()? {
+        V::IntV(v) => Some(*v as f64),
+        V::FloatV(v) => Some(*v),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn data(seq: u64, tag: &str, value: Value) -> FrameEnvelope {
+        FrameEnvelope::wrap_data(seq, DataFrame::new(tag, value)).unwrap()
+    }
+
+    #[test]
+    fn test_change_of_value() {
+        let cache = LastValueCache::default();
+        assert_eq!(cache.apply(&data(1, "line1.state", Value::string("run"))), Some(CacheUpdate::New));
+        assert_eq!(cache.apply(&data(2, "line1.state", Value::string("run"))), Some(CacheUpdate::Unchanged));
+        assert_eq!(cache.apply(&data(3, "line1.state", Value::string("stop"))), Some(CacheUpdate::Changed));
+
+        let bad = DataFrame::new("line1.state", Value::string("stop")).with_qos(0);
+        assert_eq!(cache.apply(&FrameEnvelope::wrap_data(4, bad).unwrap()), Some(CacheUpdate::Changed));
+
+        let cached = cache.get("line1.state").unwrap();
+        assert_eq!(cached.seq, 4);
+        assert_eq!(cached.qos, 0);
+
+        // 命令帧不进入缓存
+        let cmd = FrameEnvelope::wrap_cmd(5, crate::CmdFrame::new("line1.state", Value::bool(true), "north")).unwrap();
+        assert_eq!(cache.apply(&cmd), None);
+        assert_eq!(cache.snapshot().seq, Some(4));
+    }
+
+    #[test]
+    fn test_deadband() {
+        let config = CacheConfig::default()
+            .with_deadband("plant1.+.temp", Deadband::absolute(0.5))
+            .with_deadband("plant1.#", Deadband::percent(10.0));
+        let cache = LastValueCache::new(&config).unwrap();
+
+        cache.apply(&data(1, "plant1.pump.speed", Value::int(100)));
+        assert_eq!(cache.apply(&data(2, "plant1.pump.speed", Value::int(108))), Some(CacheUpdate::Unchanged));
+        assert_eq!(cache.apply(&data(3, "plant1.pump.speed", Value::int(111))), Some(CacheUpdate::Changed));
+
+        // 两条规则都匹配时超过绝对死区0.5即可
+        cache.apply(&data(1, "plant1.line1.temp", Value::float(20.0)));
+        assert_eq!(cache.apply(&data(2, "plant1.line1.temp", Value::float(20.4))), Some(CacheUpdate::Unchanged));
+        assert_eq!(cache.apply(&data(4, "plant1.line1.temp", Value::float(20.8))), Some(CacheUpdate::Changed));
+
+        let cached = cache.get("plant1.line1.temp").unwrap();
+        assert_eq!(cached.value, Some(Value::float(20.8)));
+        assert_eq!(cached.seq, 4);
+        assert!(cached.last_seen >= cached.timestamp);
+
+        // 未配置死区的点位任何变化都记录
+        cache.apply(&data(5, "plant2.flow", Value::int(100)));
+        assert_eq!(cache.apply(&data(6, "plant2.flow", Value::int(101))), Some(CacheUpdate::Changed));
+    }
+
+    #[test]
+    fn test_changes_since() {
+        let cache = LastValueCache::default();
+        cache.apply(&data(1, "a", Value::int(1)));
+        cache.apply(&data(2, "b", Value::int(1)));
+        cache.apply(&data(3, "a", Value::int(2)));
+        cache.apply(&data(4, "b", Value::int(1)));
+
+        let changes = cache.changes_since(2);
+        assert_eq!(changes.seq, Some(4));
+        assert_eq!(changes.values.len(), 1);
+        assert!(changes.values.contains_key("a"));
+    }
+
+    #[test]
+    fn test_sharded_snapshot() {
+        let cache = LastValueCache::default();
+        for i in 0..100u64 {
+            cache.apply(&data(i + 1, &format!("line1.tag{}", i), Value::int(i as i64)));
+        }
+        cache.apply(&data(101, "line1.tag7", Value::int(-1)));
+
+        let snapshot = cache.snapshot();
+        assert_eq!(cache.len(), 100);
+        assert_eq!(snapshot.seq, Some(101));
+        assert_eq!(snapshot.values.len(), 100);
+        assert_eq!(snapshot.values["line1.tag7"].seq, 101);
+        assert_eq!(cache.changes_since(100).values.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(cache.snapshot().seq, None);
+    }
+}
@@ -0,0 +1,410 @@
+//! WAL记录编码：压缩与静态加密
+//!
+//! 编码记录格式：`MAGIC(2) | flags(1) | key_id(4) | nonce(12，仅加密) | body`，
+//! body为先LZ4压缩、后AES-256-GCM加密的帧数据，加密以帧序列号作附加数据，防止记录被挪用到其他序列号。
+//! 未启用加密时，不以MAGIC开头的记录视为明文，启用编码前写入的帧仍可读取；
+//! 启用加密后只接受加密记录，防止篡改者写入明文记录绕过认证；
+//! 启用加密前写入的明文记录只在迁移路径 (`decode_legacy`) 中读取，由`WalManager`重写为加密记录。
+//!
+//! 密钥文件格式与`production_config::encryption::ConfigEncryption`相同 (32字节原始密钥)。
+//! 轮换密钥时旧密钥保留为只解密密钥，存量帧由`WalManager::rekey`重写为新密钥后方可移除。
+//! 运行时轮换先将旧密钥另存为密钥文件旁的`<key_file>.<key_id>.retired`，再原子替换密钥文件，
+//! 重写中途崩溃时重启仍可解密存量帧。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use ring::aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, NONCE_LEN};
+use ring::digest;
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// 密钥长度 (AES-256)
+pub const KEY_LEN: usize = 32;
+
+const MAGIC: [u8; 2] = [0xF7, 0x57];
+const HEADER_LEN: usize = MAGIC.len() + 1 + 4;
+const FLAG_COMPRESSED: u8 = 0b01;
+const FLAG_ENCRYPTED: u8 = 0b10;
+
+/// WAL编码配置
+#[derive(Debug, Clone, Default)]
+pub struct WalCodecConfig {
+    /// 启用LZ4压缩
+    pub compression: bool,
+    /// 加密密钥文件，None表示不加密
+    pub key_file: Option<PathBuf>,
+    /// 已轮换的旧密钥文件，仅用于解密存量帧
+    pub retired_key_files: Vec<PathBuf>,
+}
+
+/// WAL记录编解码器
+#[derive(Clone)]
+pub struct WalCodec {
+    compression: bool,
+    active_key: Option<u32>,
+    keys: HashMap<u32, Arc<LessSafeKey>>,
+    rng: SystemRandom,
+}
+
+impl std::fmt::Debug for WalCodec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WalCodec")
+            .field("compression", &self.compression)
+            .field("active_key", &self.active_key)
+            .field("keys", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Default for WalCodec {
+    fn default() -> Self {
+        Self::plain()
+    }
+}
+
+impl WalCodec {
+    /// 明文编解码器，写入格式与未启用编码时一致
+    pub fn plain() -> Self {
+        Self {
+            compression: false,
+            active_key: None,
+            keys: HashMap::new(),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// 按配置加载密钥文件，运行时轮换留下的旧密钥一并加载
+    pub fn from_config(config: &WalCodecConfig) -> Result<Self> {
+        let mut codec = Self::plain().with_compression(config.compression);
+        for path in &config.retired_key_files {
+            codec = codec.with_retired_key(&load_key_file(path)?)?;
+        }
+        if let Some(path) = &config.key_file {
+            for retired in rotated_key_files(path)? {
+                codec = codec.with_retired_key(&load_key_file(&retired)?)?;
+            }
+            codec = codec.with_key(&load_key_file(path)?)?;
+        }
+        Ok(codec)
+    }
+
+    /// 启用或关闭压缩
+    pub fn with_compression(mut self, enabled: bool) -> Self {
+        self.compression = enabled;
+        self
+    }
+
+    /// 设置加密密钥，之后写入的帧使用该密钥
+    pub fn with_key(mut self, key: &[u8]) -> Result<Self> {
+        let id = self.add_key(key)?;
+        self.active_key = Some(id);
+        Ok(self)
+    }
+
+    /// 添加仅用于解密的旧密钥
+    pub fn with_retired_key(mut self, key: &[u8]) -> Result<Self> {
+        self.add_key(key)?;
+        Ok(self)
+    }
+
+    /// 轮换为新密钥，当前密钥保留用于解密
+    pub fn rotated(&self, key: &[u8]) -> Result<Self> {
+        self.clone().with_key(key)
+    }
+
+    fn add_key(&mut self, key: &[u8]) -> Result<u32> {
+        if key.len() != KEY_LEN {
+            return Err(anyhow::anyhow!("WAL key must be exactly {} bytes", KEY_LEN));
+        }
+        let unbound = UnboundKey::new(&aead::AES_256_GCM, key)
+            .map_err(|_| anyhow::anyhow!("Failed to create WAL encryption key"))?;
+        let id = key_id(key);
+        self.keys.insert(id, Arc::new(LessSafeKey::new(unbound)));
+        Ok(id)
+    }
+
+    /// 活动密钥ID
+    pub fn active_key_id(&self) -> Option<u32> {
+        self.active_key
+    }
+
+    /// 是否与明文格式一致
+    pub fn is_plain(&self) -> bool {
+        !self.compression && self.active_key.is_none()
+    }
+
+    /// 记录是否已按当前配置编码 (密钥轮换后需要重写的记录返回false)
+    pub fn is_current(&self, record: &[u8]) -> bool {
+        match self.active_key {
+            Some(active) => record_key_id(record) == Some(active),
+            None => true,
+        }
+    }
+
+    /// 编码帧数据
+    pub fn encode(&self, seq: u64, frame: &[u8]) -> Result<Vec<u8>> {
+        if self.is_plain() {
+            return Ok(frame.to_vec());
+        }
+
+        let mut flags = 0;
+        let mut body = frame.to_vec();
+        if self.compression {
+            let compressed = lz4::block::compress(frame, None, true)
+                .context("WAL frame compression failed")?;
+            // 不可压缩的帧原样存放
+            if compressed.len() < frame.len() {
+                body = compressed;
+                flags |= FLAG_COMPRESSED;
+            }
+        }
+
+        let mut nonce = None;
+        if let Some(key) = self.active_key.and_then(|id| self.keys.get(&id)) {
+            let mut bytes = [0u8; NONCE_LEN];
+            self.rng.fill(&mut bytes)
+                .map_err(|_| anyhow::anyhow!("Failed to generate WAL nonce"))?;
+            key.seal_in_place_append_tag(Nonce::assume_unique_for_key(bytes), Aad::from(seq.to_be_bytes()), &mut body)
+                .map_err(|_| anyhow::anyhow!("WAL frame encryption failed"))?;
+            flags |= FLAG_ENCRYPTED;
+            nonce = Some(bytes);
+        }
+
+        let mut record = Vec::with_capacity(HEADER_LEN + NONCE_LEN + body.len());
+        record.extend_from_slice(&MAGIC);
+        record.push(flags);
+        record.extend_from_slice(&self.active_key.unwrap_or(0).to_be_bytes());
+        if let Some(nonce) = nonce {
+            record.extend_from_slice(&nonce);
+        }
+        record.extend_from_slice(&body);
+        Ok(record)
+    }
+
+    /// 解码记录，未启用加密时明文记录原样返回
+    pub fn decode(&self, seq: u64, record: &[u8]) -> Result<Vec<u8>> {
+        self.decode_with(seq, record, self.active_key.is_some())
+    }
+
+    /// 迁移路径解码：启用加密后仍接受启用前写入的明文或仅压缩记录，用于重写为加密记录
+    pub fn decode_legacy(&self, seq: u64, record: &[u8]) -> Result<Vec<u8>> {
+        self.decode_with(seq, record, false)
+    }
+
+    fn decode_with(&self, seq: u64, record: &[u8], encryption_required: bool) -> Result<Vec<u8>> {
+        if !is_encoded(record) {
+            if encryption_required {
+                return Err(anyhow::anyhow!("Unencrypted WAL record at seq {} rejected", seq));
+            }
+            return Ok(record.to_vec());
+        }
+        if record.len() < HEADER_LEN {
+            return Err(anyhow::anyhow!("Truncated WAL record header at seq {}", seq));
+        }
+
+        let flags = record[2];
+        if encryption_required && flags & FLAG_ENCRYPTED == 0 {
+            return Err(anyhow::anyhow!("Unencrypted WAL record at seq {} rejected", seq));
+        }
+        let mut body = &record[HEADER_LEN..];
+        let mut plain;
+        if flags & FLAG_ENCRYPTED != 0 {
+            let key_id = record_key_id(record).unwrap_or_default();
+            let key = self.keys.get(&key_id)
+                .ok_or_else(|| anyhow::anyhow!("No WAL key {:08x} for frame {}", key_id, seq))?;
+            if body.len() < NONCE_LEN {
+                return Err(anyhow::anyhow!("Truncated WAL nonce at seq {}", seq));
+            }
+            let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+            let nonce = Nonce::try_assume_unique_for_key(nonce)
+                .map_err(|_| anyhow::anyhow!("Invalid WAL nonce at seq {}", seq))?;
+            plain = ciphertext.to_vec();
+            let len = key.open_in_place(nonce, Aad::from(seq.to_be_bytes()), &mut plain)
+                .map_err(|_| anyhow::anyhow!("WAL frame {} failed authentication", seq))?
+                .len();
+            plain.truncate(len);
+            body = &plain;
+        }
+
+        if flags & FLAG_COMPRESSED != 0 {
+            return lz4::block::decompress(body, None)
+                .with_context(|| format!("WAL frame {} decompression failed", seq));
+        }
+        Ok(body.to_vec())
+    }
+}
+
+/// 记录是否为编码格式
+fn is_encoded(record: &[u8]) -> bool {
+    record.starts_with(&MAGIC)
+}
+
+/// 加密记录使用的密钥ID，明文或仅压缩的记录返回None
+pub fn record_key_id(record: &[u8]) -> Option<u32> {
+    if !is_encoded(record) || record.len() < HEADER_LEN || record[2] & FLAG_ENCRYPTED == 0 {
+        return None;
+    }
+    Some(u32::from_be_bytes(record[3..7].try_into().unwrap()))
+}
+
+/// 密钥ID：密钥SHA-256摘要前4字节，不泄露密钥且跨重启稳定
+pub fn key_id(key: &[u8]) -> u32 {
+    let digest = digest::digest(&digest::SHA256, key);
+    u32::from_be_bytes(digest.as_ref()[..4].try_into().unwrap())
+}
+
+/// 运行时轮换时旧密钥的保存位置
+pub fn retired_key_path(key_file: &Path, id: u32) -> PathBuf {
+    let mut name = key_file.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{:08x}.retired", id));
+    key_file.with_file_name(name)
+}
+
+/// 密钥文件旁由运行时轮换留下的旧密钥文件
+pub fn rotated_key_files(key_file: &Path) -> Result<Vec<PathBuf>> {
+    let Some(name) = key_file.file_name().and_then(|n| n.to_str()) else {
+        return Ok(Vec::new());
+    };
+    let dir = match key_file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let prefix = format!("{}.", name);
+
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).with_context(|| format!("Failed to list WAL key directory {:?}", dir))? {
+        let path = entry?.path();
+        let matches = path.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(&prefix) && n.ends_with(".retired"));
+        if matches {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// 原子写入密钥文件：写临时文件并落盘后重命名，崩溃时不会留下半个密钥
+pub fn write_key_file(path: &Path, key: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    if key.len() != KEY_LEN {
+        return Err(anyhow::anyhow!("WAL key must be exactly {} bytes", KEY_LEN));
+    }
+    let mut tmp_name = path.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+
+    {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(&tmp)
+            .with_context(|| format!("Failed to create WAL key file: {:?}", tmp))?;
+        file.write_all(key)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&tmp, path)
+        .with_context(|| format!("Failed to replace WAL key file: {:?}", path))?;
+    // 目录项落盘后重命名才是持久的
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// 读取32字节密钥文件
+pub fn load_key_file(path: &Path) -> Result<Vec<u8>> {
+    let key = std::fs::read(path)
+        .with_context(|| format!("Failed to read WAL key file: {:?}", path))?;
+    if key.len() != KEY_LEN {
+        return Err(anyhow::anyhow!("Key file {:?} must contain exactly {} bytes", path, KEY_LEN));
+    }
+    Ok(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_A: [u8; KEY_LEN] = [7; KEY_LEN];
+    const KEY_B: [u8; KEY_LEN] = [9; KEY_LEN];
+
+    #[test]
+    fn test_plain_passthrough() {
+        let codec = WalCodec::plain();
+        let record = codec.encode(1, b"frame").unwrap();
+        assert_eq!(record, b"frame");
+        assert_eq!(codec.decode(1, &record).unwrap(), b"frame");
+    }
+
+    #[test]
+    fn test_compress_and_encrypt_roundtrip() {
+        let frame = b"plant1.line1.temperature ".repeat(40);
+        let codec = WalCodec::plain().with_compression(true).with_key(&KEY_A).unwrap();
+
+        let record = codec.encode(42, &frame).unwrap();
+        assert!(record.len() < frame.len());
+        assert_eq!(record_key_id(&record), Some(key_id(&KEY_A)));
+        assert!(!record.windows(7).any(|w| w == b"plant1."));
+        assert_eq!(codec.decode(42, &record).unwrap(), frame);
+
+        // 序列号是附加数据，记录不能挪到其他序列号
+        assert!(codec.decode(43, &record).is_err());
+        // 启用加密后不接受明文或仅压缩的记录
+        assert!(codec.decode(1, b"legacy").is_err());
+        let compressed = WalCodec::plain().with_compression(true).encode(1, &frame).unwrap();
+        assert!(codec.decode(1, &compressed).is_err());
+        // 未启用加密时明文记录仍可读取
+        let unencrypted = WalCodec::plain().with_compression(true);
+        assert_eq!(unencrypted.decode(1, b"legacy").unwrap(), b"legacy");
+        assert_eq!(unencrypted.decode(1, &compressed).unwrap(), frame);
+        // 迁移路径接受启用加密前的记录，加密记录仍须认证
+        assert_eq!(codec.decode_legacy(1, b"legacy").unwrap(), b"legacy");
+        assert_eq!(codec.decode_legacy(1, &compressed).unwrap(), frame);
+        assert!(codec.decode_legacy(43, &record).is_err());
+        assert!(!codec.is_current(b"legacy"));
+    }
+
+    #[test]
+    fn test_key_file_rotation_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_file = dir.path().join("wal.key");
+        write_key_file(&key_file, &KEY_A).unwrap();
+        assert_eq!(load_key_file(&key_file).unwrap(), KEY_A);
+        assert!(rotated_key_files(&key_file).unwrap().is_empty());
+
+        write_key_file(&retired_key_path(&key_file, key_id(&KEY_A)), &KEY_A).unwrap();
+        write_key_file(&key_file, &KEY_B).unwrap();
+        std::fs::write(dir.path().join("other.key.00000000.retired"), [0u8; KEY_LEN]).unwrap();
+
+        // 旧密钥仍可解密，活动密钥为新密钥
+        let record = WalCodec::plain().with_key(&KEY_A).unwrap().encode(1, b"frame").unwrap();
+        let codec = WalCodec::from_config(&WalCodecConfig { key_file: Some(key_file.clone()), ..Default::default() }).unwrap();
+        assert_eq!(codec.active_key_id(), Some(key_id(&KEY_B)));
+        assert_eq!(codec.decode(1, &record).unwrap(), b"frame");
+        assert_eq!(rotated_key_files(&key_file).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rotation_keeps_old_frames_readable() {
+        let old = WalCodec::plain().with_key(&KEY_A).unwrap();
+        let record = old.encode(1, b"frame").unwrap();
+
+        let rotated = old.rotated(&KEY_B).unwrap();
+        assert_eq!(rotated.active_key_id(), Some(key_id(&KEY_B)));
+        assert!(!rotated.is_current(&record));
+        assert_eq!(rotated.decode(1, &record).unwrap(), b"frame");
+
+        let rewritten = rotated.encode(1, b"frame").unwrap();
+        assert!(rotated.is_current(&rewritten));
+        assert!(WalCodec::plain().with_key(&KEY_A).unwrap().decode(1, &rewritten).is_err());
+    }
+}
@@ -13,6 +13,7 @@ use anyhow::{Context, Result};
 use tracing::{info, warn, error, debug};
 
 use crate::envelope::{CmdFrame, CmdAckFrame, FrameEnvelope, Value};
+use crate::policy::{CommandPolicy, PolicyDecision};
 use crate::ring::FrameSender;
 
 /// Command priority levels
@@ -174,6 +175,28 @@ impl CommandQueue {
         self.queues[priority as usize].len()
     }
     
+    /// Remove queued commands for `tag` below `priority`; a newer, more
+    /// urgent write to the same tag makes them obsolete
+    pub fn supersede(&mut self, tag: &str, priority: CommandPriority) -> Vec<PendingCommand> {
+        let mut superseded = Vec::new();
+
+        for queue in &mut self.queues[..priority as usize] {
+            let mut i = 0;
+            while i < queue.len() {
+                if queue[i].frame.tag == tag {
+                    if let Some(cmd) = queue.remove(i) {
+                        superseded.push(cmd);
+                        self.total_size -= 1;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+        }
+
+        superseded
+    }
+
     /// Remove timed out commands
     pub fn remove_timed_out(&mut self) -> Vec<PendingCommand> {
         let mut timed_out = Vec::new();
@@ -197,6 +220,9 @@ impl CommandQueue {
     }
 }
 
+/// Driver ID reported in acknowledgments issued by the command policy
+pub const POLICY_ID: &str = "command-policy";
+
 /// Command router and processor
 pub struct CommandProcessor {
     /// Command queue
@@ -216,6 +242,9 @@ pub struct CommandProcessor {
     
     /// Whether processor is running
     running: Arc<RwLock<bool>>,
+    
+    /// Command policy checked before queuing
+    policy: Option<Arc<CommandPolicy>>,
 }
 
 impl CommandProcessor {
@@ -228,9 +257,16 @@ impl CommandProcessor {
             frame_sender,
             stats: Arc::new(RwLock::new(CommandStats::default())),
             running: Arc::new(RwLock::new(false)),
+            policy: None,
         }
     }
     
+    /// Validate submitted commands against a command policy
+    pub fn with_policy(mut self, policy: Arc<CommandPolicy>) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+    
     /// Start the command processor
     pub async fn start(&self) -> Result<()> {
         {
@@ -310,6 +346,22 @@ impl CommandProcessor {
         // Create acknowledgment channel
         let (ack_tx, ack_rx) = mpsc::unbounded_channel();
         
+        // Rejected and armed commands are acknowledged without being queued
+        if let Some(policy) = &self.policy {
+            let ack = match policy.evaluate(&frame) {
+                PolicyDecision::Allow => None,
+                PolicyDecision::Armed => Some(CmdAckFrame::success(cmd_id, frame.tag.clone(), POLICY_ID.to_string(), None)),
+                PolicyDecision::Reject(reason) => {
+                    self.stats.write().unwrap().rejected += 1;
+                    Some(CmdAckFrame::failure(cmd_id, frame.tag.clone(), POLICY_ID.to_string(), reason))
+                }
+            };
+            if let Some(ack) = ack {
+                let _ = ack_tx.send(ack);
+                return Ok(ack_rx);
+            }
+        }
+        
         // Store acknowledgment receiver
         {
             let mut ack_receivers = self.ack_receivers.write().unwrap();
@@ -344,6 +396,10 @@ impl CommandProcessor {
         
         debug!("Received command acknowledgment: {}", cmd_id);
         
+        if let Some(policy) = &self.policy {
+            policy.record_ack(&ack);
+        }
+        
         // Update pending command
         {
             let mut pending = self.pending_commands.write().unwrap();
@@ -427,6 +483,7 @@ pub struct CommandStats {
     pub succeeded: u64,
     pub failed: u64,
     pub timeouts: u64,
+    pub rejected: u64,
 }
 
 /// Queue statistics
@@ -492,6 +549,21 @@ mod tests {
         assert!(queue.is_empty());
     }
     
+    #[test]
+    fn test_command_queue_supersede() {
+        let mut queue = CommandQueue::new();
+        for (tag, priority) in [("a", CommandPriority::Low), ("a", CommandPriority::Normal), ("b", CommandPriority::Low), ("a", CommandPriority::High)] {
+            queue.push(PendingCommand::new(CmdFrame::new(tag, Value::int(1), "test").with_priority(priority as i32))).unwrap();
+        }
+
+        let superseded = queue.supersede("a", CommandPriority::High);
+        assert_eq!(superseded.len(), 2);
+        assert!(superseded.iter().all(|cmd| cmd.frame.tag == "a" && cmd.priority() < CommandPriority::High));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(queue.size_by_priority(CommandPriority::High), 1);
+        assert_eq!(queue.size_by_priority(CommandPriority::Low), 1);
+    }
+
     #[test]
     fn test_pending_command() {
         let frame = CmdFrame::new("test.tag", Value::int(42), "test")
@@ -506,6 +578,30 @@ mod tests {
         assert_eq!(cmd.retry_count, 1);
     }
     
+    #[tokio::test]
+    async fn test_policy_rejects_before_queuing() {
+        use crate::policy::{PolicyConfig, TagPolicy};
+
+        let (tx, _rx) = tokio::sync::broadcast::channel(16);
+        let policy = CommandPolicy::new(PolicyConfig {
+            policies: vec![TagPolicy::new("plant1.#").with_range(0.0, 100.0)],
+            ..Default::default()
+        }).unwrap();
+        let processor = CommandProcessor::new(tx).with_policy(Arc::new(policy));
+
+        let mut ack_rx = processor.submit_command(CmdFrame::new("plant1.valve", Value::int(500), "test")).await.unwrap();
+        let ack = ack_rx.recv().await.unwrap();
+        assert!(!ack.success);
+        assert_eq!(ack.driver_id, POLICY_ID);
+        assert!(ack.error_msg.contains("above maximum"));
+
+        processor.submit_command(CmdFrame::new("plant1.valve", Value::int(50), "test")).await.unwrap();
+        let stats = processor.stats();
+        assert_eq!(stats.rejected, 1);
+        assert_eq!(stats.submitted, 1);
+        assert_eq!(processor.queue_stats().await.total, 1);
+    }
+    
     #[test]
     fn test_command_result() {
         let result = CommandResult {
@@ -1,10 +1,15 @@
 //! 背压控制
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
 use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
 use once_cell::sync::OnceCell;
+use tracing::{info, warn};
 
 /// 控制消息
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ControlMsg {
     /// 暂停生产者
     Pause,
@@ -45,4 +50,38 @@ pub fn send_resume() {
     if let Some(tx) = get_control_sender() {
         let _ = tx.send(ControlMsg::Resume);
     }
-}
\ No newline at end of file
+}
+
+/// Ring监控是否已启动
+static RING_MONITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 按Ring使用率发出背压信号：超过`pause_hi`时暂停，回落到`resume_lo`以下时恢复
+///
+/// 全局只启动一个监控任务，重复调用返回None
+pub fn spawn_ring_monitor(period: Duration) -> anyhow::Result<Option<JoinHandle<()>>> {
+    let instance = crate::ring::get_instance()?.clone();
+    init_control();
+    if RING_MONITOR_RUNNING.swap(true, Ordering::SeqCst) {
+        return Ok(None);
+    }
+
+    let pause_at = instance.get_config().pause_threshold();
+    let resume_at = instance.get_config().resume_threshold();
+    Ok(Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(period);
+        let mut paused = false;
+        loop {
+            ticker.tick().await;
+            let used = instance.get_sender().len();
+            if !paused && used > pause_at {
+                warn!("Ring usage {} above pause threshold {}, pausing producers", used, pause_at);
+                send_pause();
+                paused = true;
+            } else if paused && used < resume_at {
+                info!("Ring usage {} below resume threshold {}, resuming producers", used, resume_at);
+                send_resume();
+                paused = false;
+            }
+        }
+    })))
+}
@@ -0,0 +1,263 @@
+//! 持久订阅
+//!
+//! 命名消费者的游标保存在WAL的acks列族中。订阅先从游标之后回放WAL，
+//! 追平后切换到实时环形缓冲区；实时接收落后(Lagged)时回退到WAL继续追赶，
+//! 因此慢消费者与重启都不会丢帧。WAL GC以最慢的持久游标为界。
+//!
+//! 并发发布时实时帧可能乱序到达：先到的较大序列号照常投递并记入缺口集合，
+//! 投递水位只在序列号连续时推进，ACK也不会越过水位；缺口超时仍未补齐时
+//! 从WAL回放，WAL中不存在的序列号视为未发布。
+
+use std::collections::{BTreeSet, VecDeque};
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::sync::broadcast;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::{metrics::METRICS, permissions, ring, wal, Filter, FrameEnvelope};
+
+/// 实时帧缺口等待补齐的时长，超时后从WAL回放
+const GAP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// 新消费者(无游标)的起始位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StartFrom {
+    /// 从WAL中最早的帧开始
+    Earliest,
+    /// 从订阅时刻之后的新帧开始
+    #[default]
+    Latest,
+}
+
+/// 持久订阅选项
+#[derive(Debug, Clone)]
+pub struct DurableOptions {
+    /// 无游标时的起始位置
+    pub start: StartFrom,
+    /// 每次从WAL读取的帧数
+    pub replay_batch: usize,
+}
+
+impl Default for DurableOptions {
+    fn default() -> Self {
+        Self {
+            start: StartFrom::Latest,
+            replay_batch: 1024,
+        }
+    }
+}
+
+/// 持久订阅接收端
+pub struct DurableReceiver {
+    consumer_id: String,
+    filter: Filter,
+    options: DurableOptions,
+    live: broadcast::Receiver<FrameEnvelope>,
+    /// 已从WAL读出待投递的帧
+    backlog: VecDeque<FrameEnvelope>,
+    /// 投递水位：小于该值的序列号均已投递
+    next_seq: u64,
+    /// 水位之上已乱序投递的序列号
+    delivered: BTreeSet<u64>,
+    /// 缺口补齐的截止时间
+    gap_deadline: Option<Instant>,
+    /// 下一次WAL读取的起点
+    replay_from: u64,
+    catching_up: bool,
+    acked: Option<u64>,
+    /// 订阅主体，接收受总线ACL约束
+    subject: Option<String>,
+}
+
+impl DurableReceiver {
+    /// 在全局FrameBus上创建持久订阅
+    pub fn new(consumer_id: impl Into<String>, filter: Filter, options: DurableOptions) -> Result<Self> {
+        let consumer_id = consumer_id.into();
+        if !wal::is_initialized() {
+            return Err(anyhow::anyhow!("Durable subscription '{}' requires an initialized WAL", consumer_id));
+        }
+
+        // 先订阅实时通道，保证WAL回放与实时帧之间没有空隙
+        let live = ring::get_instance()?.get_sender().subscribe();
+
+        let acked = wal::acked_seq(&consumer_id)?;
+        let next_seq = match (acked, options.start) {
+            (Some(seq), _) => seq + 1,
+            (None, StartFrom::Earliest) => {
+                // 游标登记在最早保留的帧之前，使GC与保留策略不删除尚未消费的帧；
+                // 序列号0之前无法表示，此时登记在0
+                let earliest = wal::min_frame_seq()?.unwrap_or_else(ring::current_sequence);
+                wal::ack(&consumer_id, earliest.saturating_sub(1))?;
+                0
+            }
+            (None, StartFrom::Latest) => {
+                let next = ring::current_sequence();
+                // 登记游标，使GC保留订阅之后的帧
+                if next > 0 {
+                    wal::ack(&consumer_id, next - 1)?;
+                }
+                next
+            }
+        };
+
+        info!("Durable subscription '{}' resuming at seq {}", consumer_id, next_seq);
+
+        Ok(Self {
+            consumer_id,
+            filter,
+            options,
+            live,
+            backlog: VecDeque::new(),
+            next_seq,
+            delivered: BTreeSet::new(),
+            gap_deadline: None,
+            replay_from: next_seq,
+            catching_up: true,
+            acked,
+            subject: None,
+        })
+    }
+
+    /// 设置订阅主体
+    pub fn with_subject<S: Into<String>>(mut self, subject: S) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// 消费者标识
+    pub fn consumer_id(&self) -> &str {
+        &self.consumer_id
+    }
+
+    /// 最后ACK的序列号
+    pub fn acked(&self) -> Option<u64> {
+        self.acked
+    }
+
+    /// 是否正在从WAL追赶
+    pub fn is_catching_up(&self) -> bool {
+        self.catching_up || !self.backlog.is_empty()
+    }
+
+    /// 按序接收下一个匹配过滤器的帧
+    pub async fn recv(&mut self) -> Result<FrameEnvelope> {
+        loop {
+            if let Some(envelope) = self.backlog.pop_front() {
+                METRICS.durable_replay_total.inc();
+                if self.mark_replayed(envelope.seq) && self.accepts(&envelope) {
+                    return Ok(envelope);
+                }
+                continue;
+            }
+
+            if self.catching_up {
+                let frames = wal::read_from(self.replay_from, self.options.replay_batch)?;
+                match frames.last() {
+                    Some(last) => {
+                        self.replay_from = last.seq + 1;
+                        METRICS.backlog_lag.set(ring::current_sequence().saturating_sub(last.seq) as i64);
+                        self.backlog.extend(frames);
+                    }
+                    None => {
+                        // 已广播但仍在写入队列中的帧落库后再读一次，避免追赶结束时漏帧
+                        if wal::wait_flushed().await? {
+                            continue;
+                        }
+                        self.catching_up = false;
+                        METRICS.backlog_lag.set(0);
+                    }
+                }
+                continue;
+            }
+
+            let received = match self.gap_deadline {
+                Some(deadline) => tokio::select! {
+                    received = self.live.recv() => received,
+                    _ = tokio::time::sleep_until(deadline) => {
+                        warn!("Durable subscription '{}' missing seq {}, replaying gap from WAL", self.consumer_id, self.next_seq);
+                        self.gap_deadline = None;
+                        self.resume_replay();
+                        continue;
+                    }
+                },
+                None => self.live.recv().await,
+            };
+
+            match received {
+                Ok(envelope) => {
+                    if self.mark_delivered(envelope.seq) && self.accepts(&envelope) {
+                        return Ok(envelope);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("Durable subscription '{}' lagged by {} frames, replaying from WAL", self.consumer_id, skipped);
+                    METRICS.durable_lag_total.inc();
+                    self.resume_replay();
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    return Err(anyhow::anyhow!("FrameBus closed"));
+                }
+            }
+        }
+    }
+
+    /// 从投递水位开始回放WAL
+    fn resume_replay(&mut self) {
+        self.replay_from = self.next_seq;
+        self.catching_up = true;
+    }
+
+    /// 记录实时帧已投递，返回是否首次投递
+    fn mark_delivered(&mut self, seq: u64) -> bool {
+        if seq < self.next_seq || !self.delivered.insert(seq) {
+            return false;
+        }
+        self.advance();
+        true
+    }
+
+    /// 记录WAL回放的帧已投递，返回是否首次投递
+    ///
+    /// WAL按序读出，早于`seq`而WAL中不存在的序列号不再等待
+    fn mark_replayed(&mut self, seq: u64) -> bool {
+        if seq < self.next_seq {
+            return false;
+        }
+        let first = !self.delivered.contains(&seq);
+        self.delivered = self.delivered.split_off(&(seq + 1));
+        self.next_seq = seq + 1;
+        self.advance();
+        first
+    }
+
+    /// 水位越过连续已投递的序列号，并维护缺口截止时间
+    fn advance(&mut self) {
+        while self.delivered.remove(&self.next_seq) {
+            self.next_seq += 1;
+        }
+        if self.delivered.is_empty() {
+            self.gap_deadline = None;
+        } else if self.gap_deadline.is_none() {
+            self.gap_deadline = Some(Instant::now() + GAP_TIMEOUT);
+        }
+    }
+
+    fn accepts(&self, envelope: &FrameEnvelope) -> bool {
+        self.filter.matches(envelope) && permissions::authorize_receive(self.subject.as_deref(), &envelope.tag())
+    }
+
+    /// 确认已处理到`seq`(含)，重启后从其后恢复
+    ///
+    /// 游标不越过投递水位，缺口中尚未投递的帧重启后仍会回放
+    pub fn ack(&mut self, seq: u64) -> Result<()> {
+        let Some(delivered) = self.next_seq.checked_sub(1) else {
+            return Ok(());
+        };
+        let seq = seq.min(delivered);
+        wal::ack(&self.consumer_id, seq)?;
+        self.acked = Some(seq);
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 //! Frame数据结构和封装
 
 use prost::Message;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 // 引入手工编写的protobuf结构
@@ -143,6 +145,10 @@ impl FrameEnvelope {
             seq,
             kind: FrameKind::Data as i32,
             payload,
+            timestamp: frame.timestamp,
+            qos: frame.qos,
+            alarm: frame.meta.contains_key(crate::retention::ALARM_META_KEY),
+            tag: frame.tag,
         })
     }
 
@@ -153,6 +159,9 @@ impl FrameEnvelope {
             seq,
             kind: FrameKind::Cmd as i32,
             payload,
+            timestamp: frame.timestamp,
+            tag: frame.tag,
+            ..Default::default()
         })
     }
 
@@ -163,6 +172,9 @@ impl FrameEnvelope {
             seq,
             kind: FrameKind::CmdAck as i32,
             payload,
+            timestamp: frame.timestamp,
+            tag: frame.tag,
+            ..Default::default()
         })
     }
 
@@ -194,6 +206,30 @@ impl FrameEnvelope {
     pub fn kind(&self) -> FrameKind {
         FrameKind::from(self.kind)
     }
+
+    /// 获取帧点位
+    ///
+    /// 新帧的点位随封装携带；升级前写入WAL的帧没有该字段，回退为解码payload
+    pub fn tag(&self) -> Cow<'_, str> {
+        if !self.tag.is_empty() {
+            return Cow::Borrowed(&self.tag);
+        }
+        let tag = match self.kind() {
+            FrameKind::Data => DataFrame::decode(&self.payload[..]).map(|f| f.tag),
+            FrameKind::Cmd => CmdFrame::decode(&self.payload[..]).map(|f| f.tag),
+            FrameKind::CmdAck => CmdAckFrame::decode(&self.payload[..]).map(|f| f.tag),
+        };
+        Cow::Owned(tag.unwrap_or_default())
+    }
+
+    /// 获取帧元数据，需要解码payload (确认帧没有元数据)
+    pub fn meta(&self) -> Option<HashMap<String, String>> {
+        match self.kind() {
+            FrameKind::Data => DataFrame::decode(&self.payload[..]).ok().map(|f| f.meta),
+            FrameKind::Cmd => CmdFrame::decode(&self.payload[..]).ok().map(|f| f.meta),
+            FrameKind::CmdAck => None,
+        }
+    }
 }
 
 /// Value类型转换方法（构造方法在generated.rs中定义）
@@ -242,7 +278,7 @@ impl Value {
 }
 
 /// 获取当前时间戳（纳秒）
-fn current_timestamp_ns() -> u64 {
+pub(crate) fn current_timestamp_ns() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
@@ -1,8 +1,13 @@
 //! 订阅过滤器
+//!
+//! 点位类过滤器直接使用封装携带的点位，不解码payload；
+//! 元数据谓词需要解码，组合时应放在点位过滤器之后以便短路。
 
+use std::sync::Arc;
+
+use crate::index::{TagIndex, TagPattern};
 use crate::{FrameEnvelope, FrameKind};
 use regex::Regex;
-use prost::Message;
 
 /// 帧过滤器
 #[derive(Debug, Clone)]
@@ -15,6 +20,14 @@ pub enum Filter {
     TagPrefix(String),
     /// 按tag正则表达式过滤
     TagRegex(Regex),
+    /// 按MQTT风格通配模式过滤 (`plant1.+.temp`、`plant1.#`)
+    TagPattern(TagPattern),
+    /// 匹配任一模式的点位集合，大量模式时按前缀树查找
+    TagSet(Arc<TagIndex<()>>),
+    /// 元数据包含指定键
+    MetaKey(String),
+    /// 元数据键等于指定值
+    MetaEq(String, String),
     /// 组合过滤器（AND逻辑）
     And(Vec<Filter>),
     /// 组合过滤器（OR逻辑）
@@ -27,58 +40,12 @@ impl Filter {
         match self {
             Filter::All => true,
             Filter::Kind(kind) => envelope.kind() == *kind,
-            Filter::TagPrefix(prefix) => {
-                // 需要解包获取tag
-                match envelope.kind() {
-                    FrameKind::Data => {
-                        if let Ok(frame) = crate::DataFrame::decode(&envelope.payload[..]) {
-                            frame.tag.starts_with(prefix)
-                        } else {
-                            false
-                        }
-                    }
-                    FrameKind::Cmd => {
-                        if let Ok(frame) = crate::CmdFrame::decode(&envelope.payload[..]) {
-                            frame.tag.starts_with(prefix)
-                        } else {
-                            false
-                        }
-                    }
-                    FrameKind::CmdAck => {
-                        if let Ok(frame) = crate::CmdAckFrame::decode(&envelope.payload[..]) {
-                            frame.tag.starts_with(prefix)
-                        } else {
-                            false
-                        }
-                    }
-                }
-            }
-            Filter::TagRegex(regex) => {
-                // 需要解包获取tag
-                match envelope.kind() {
-                    FrameKind::Data => {
-                        if let Ok(frame) = crate::DataFrame::decode(&envelope.payload[..]) {
-                            regex.is_match(&frame.tag)
-                        } else {
-                            false
-                        }
-                    }
-                    FrameKind::Cmd => {
-                        if let Ok(frame) = crate::CmdFrame::decode(&envelope.payload[..]) {
-                            regex.is_match(&frame.tag)
-                        } else {
-                            false
-                        }
-                    }
-                    FrameKind::CmdAck => {
-                        if let Ok(frame) = crate::CmdAckFrame::decode(&envelope.payload[..]) {
-                            regex.is_match(&frame.tag)
-                        } else {
-                            false
-                        }
-                    }
-                }
-            }
+            Filter::TagPrefix(prefix) => envelope.tag().starts_with(prefix.as_str()),
+            Filter::TagRegex(regex) => regex.is_match(&envelope.tag()),
+            Filter::TagPattern(pattern) => pattern.matches(&envelope.tag()),
+            Filter::TagSet(index) => index.contains_match(&envelope.tag()),
+            Filter::MetaKey(key) => envelope.meta().is_some_and(|meta| meta.contains_key(key)),
+            Filter::MetaEq(key, value) => envelope.meta().is_some_and(|meta| meta.get(key) == Some(value)),
             Filter::And(filters) => {
                 filters.iter().all(|f| f.matches(envelope))
             }
@@ -108,6 +75,32 @@ impl Filter {
     pub fn tag_matches(pattern: &str) -> Result<Self, regex::Error> {
         Ok(Filter::TagRegex(Regex::new(pattern)?))
     }
+
+    /// MQTT风格通配模式
+    pub fn tag_pattern(pattern: &str) -> anyhow::Result<Self> {
+        Ok(Filter::TagPattern(TagPattern::parse(pattern)?))
+    }
+
+    /// 匹配任一模式的点位集合
+    pub fn tag_patterns<I, S>(patterns: I) -> anyhow::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut index = TagIndex::new();
+        for pattern in patterns {
+            index.insert(&TagPattern::parse(pattern.as_ref())?, ());
+        }
+        Ok(Filter::TagSet(Arc::new(index)))
+    }
+
+    pub fn meta_key<S: Into<String>>(key: S) -> Self {
+        Filter::MetaKey(key.into())
+    }
+
+    pub fn meta_eq<K: Into<String>, V: Into<String>>(key: K, value: V) -> Self {
+        Filter::MetaEq(key.into(), value.into())
+    }
 }
 
 impl Default for Filter {
@@ -164,4 +157,43 @@ mod tests {
 
         assert!(filter.matches(&envelope));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_wildcard_filters() {
+        let envelope = FrameEnvelope::wrap_data(1, DataFrame::new("plant1.line2.temp", Value::float(25.5))).unwrap();
+
+        assert!(Filter::tag_pattern("plant1.+.temp").unwrap().matches(&envelope));
+        assert!(Filter::tag_pattern("plant1.#").unwrap().matches(&envelope));
+        assert!(!Filter::tag_pattern("plant1.+").unwrap().matches(&envelope));
+
+        let set = Filter::tag_patterns(["plant2.#", "plant1.line2.+"]).unwrap();
+        assert!(set.matches(&envelope));
+        let set = Filter::tag_patterns(["plant2.#", "plant1.line3.+"]).unwrap();
+        assert!(!set.matches(&envelope));
+        assert!(Filter::tag_patterns(["plant1.#.temp"]).is_err());
+    }
+
+    #[test]
+    fn test_meta_filters() {
+        let frame = DataFrame::new("plant1.line2.temp", Value::float(25.5)).with_meta("unit", "celsius");
+        let envelope = FrameEnvelope::wrap_data(1, frame).unwrap();
+
+        assert!(Filter::meta_key("unit").matches(&envelope));
+        assert!(!Filter::meta_key("alarm").matches(&envelope));
+        assert!(Filter::meta_eq("unit", "celsius").matches(&envelope));
+        assert!(!Filter::meta_eq("unit", "kelvin").matches(&envelope));
+
+        let ack = FrameEnvelope::wrap_cmd_ack(2, crate::CmdAckFrame::success(1, "plant1.line2.temp", "drv", None)).unwrap();
+        assert!(!Filter::meta_key("unit").matches(&ack));
+    }
+
+    #[test]
+    fn test_legacy_envelope_without_tag() {
+        let mut envelope = FrameEnvelope::wrap_data(1, DataFrame::new("plant.temp.sensor1", Value::float(25.5))).unwrap();
+        envelope.tag.clear();
+
+        // 升级前持久化的帧回退为解码payload
+        assert!(Filter::tag_starts_with("plant.").matches(&envelope));
+        assert!(Filter::tag_pattern("plant.+.sensor1").unwrap().matches(&envelope));
+    }
+}
@@ -114,6 +114,18 @@ pub struct FrameEnvelope {
     /// DataFrame、CmdFrame或CmdAckFrame序列化
     #[prost(bytes, tag = "3")]
     pub payload: ::prost::alloc::vec::Vec<u8>,
+    /// 帧点位，过滤与路由无需解码payload
+    #[prost(string, tag = "4")]
+    pub tag: ::prost::alloc::string::String,
+    /// 帧时间戳 (ns)，保留策略无需解码payload
+    #[prost(uint64, tag = "5")]
+    pub timestamp: u64,
+    /// 数据帧质量码
+    #[prost(uint32, tag = "6")]
+    pub qos: u32,
+    /// 是否为告警数据帧
+    #[prost(bool, tag = "7")]
+    pub alarm: bool,
 }
 
 // 便利构造函数实现
@@ -0,0 +1,327 @@
+//! 点位订阅索引
+//!
+//! 点位按`.`分段，订阅模式采用MQTT风格通配符：`+`匹配单个分段，
+//! `#`匹配其后任意多个分段(含零个)且只能位于末尾，例如`plant1.+.temp`、`plant1.#`。
+//! 索引是按分段组织的前缀树，一次查找只沿点位分段下行，与模式数量无关。
+
+use std::collections::HashMap;
+use std::fmt;
+
+use anyhow::Result;
+
+/// 点位分段分隔符
+pub const TAG_SEPARATOR: char = '.';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    /// `+`
+    Single,
+    /// `#`
+    Multi,
+}
+
+/// 编译后的点位通配模式
+#[derive(Clone, PartialEq, Eq)]
+pub struct TagPattern {
+    raw: String,
+    segments: Vec<Segment>,
+}
+
+impl TagPattern {
+    /// 解析模式，`+`/`#`必须独占一个分段且`#`只能位于末尾
+    pub fn parse(pattern: &str) -> Result<Self> {
+        if pattern.is_empty() {
+            return Err(anyhow::anyhow!("Empty tag pattern"));
+        }
+
+        let parts: Vec<&str> = pattern.split(TAG_SEPARATOR).collect();
+        let mut segments = Vec::with_capacity(parts.len());
+        for (i, part) in parts.iter().enumerate() {
+            let segment = match *part {
+                "+" => Segment::Single,
+                "#" if i == parts.len() - 1 => Segment::Multi,
+                "#" => return Err(anyhow::anyhow!("'#' must be the last segment in tag pattern '{}'", pattern)),
+                _ if part.contains(['+', '#']) => {
+                    return Err(anyhow::anyhow!("Wildcard must occupy a whole segment in tag pattern '{}'", pattern));
+                }
+                _ => Segment::Literal(part.to_string()),
+            };
+            segments.push(segment);
+        }
+
+        Ok(Self { raw: pattern.to_string(), segments })
+    }
+
+    /// 原始模式文本
+    pub fn as_str(&self) -> &str {
+        &self.raw
+    }
+
+    /// 是否包含通配符
+    pub fn is_wildcard(&self) -> bool {
+        self.segments.iter().any(|s| !matches!(s, Segment::Literal(_)))
+    }
+
+    /// 检查点位是否匹配
+    pub fn matches(&self, tag: &str) -> bool {
+        let mut parts = tag.split(TAG_SEPARATOR);
+        for segment in &self.segments {
+            match segment {
+                Segment::Multi => return true,
+                Segment::Single => {
+                    if parts.next().is_none() {
+                        return false;
+                    }
+                }
+                Segment::Literal(literal) => {
+                    if parts.next() != Some(literal.as_str()) {
+                        return false;
+                    }
+                }
+            }
+        }
+        parts.next().is_none()
+    }
+}
+
+impl fmt::Debug for TagPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TagPattern({:?})", self.raw)
+    }
+}
+
+impl fmt::Display for TagPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.raw)
+    }
+}
+
+impl std::str::FromStr for TagPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    literals: HashMap<String, Node<T>>,
+    single: Option<Box<Node<T>>>,
+    /// 以`#`结尾的模式在此节点登记的值
+    multi: Vec<T>,
+    /// 在此节点结束的模式登记的值
+    terminal: Vec<T>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            literals: HashMap::new(),
+            single: None,
+            multi: Vec::new(),
+            terminal: Vec::new(),
+        }
+    }
+}
+
+impl<T> Node<T> {
+    fn is_empty(&self) -> bool {
+        self.literals.is_empty() && self.single.is_none() && self.multi.is_empty() && self.terminal.is_empty()
+    }
+}
+
+/// 点位模式索引：模式 -> 订阅值
+#[derive(Debug, Clone)]
+pub struct TagIndex<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+impl<T> Default for TagIndex<T> {
+    fn default() -> Self {
+        Self { root: Node::default(), len: 0 }
+    }
+}
+
+impl<T: Clone + PartialEq> TagIndex<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 登记的(模式, 值)数量
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 为模式登记一个值
+    pub fn insert(&mut self, pattern: &TagPattern, value: T) {
+        let mut node = &mut self.root;
+        for segment in &pattern.segments {
+            node = match segment {
+                Segment::Literal(literal) => node.literals.entry(literal.clone()).or_default(),
+                Segment::Single => node.single.get_or_insert_with(Default::default),
+                Segment::Multi => {
+                    node.multi.push(value);
+                    self.len += 1;
+                    return;
+                }
+            };
+        }
+        node.terminal.push(value);
+        self.len += 1;
+    }
+
+    /// 移除模式下登记的值，返回是否存在
+    pub fn remove(&mut self, pattern: &TagPattern, value: &T) -> bool {
+        let removed = Self::remove_in(&mut self.root, &pattern.segments, value);
+        if removed {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    fn remove_in(node: &mut Node<T>, segments: &[Segment], value: &T) -> bool {
+        let take = |values: &mut Vec<T>| match values.iter().position(|v| v == value) {
+            Some(pos) => {
+                values.swap_remove(pos);
+                true
+            }
+            None => false,
+        };
+
+        let Some((segment, rest)) = segments.split_first() else {
+            return take(&mut node.terminal);
+        };
+        match segment {
+            Segment::Multi => take(&mut node.multi),
+            Segment::Single => {
+                let Some(child) = node.single.as_mut() else { return false };
+                let removed = Self::remove_in(child, rest, value);
+                if child.is_empty() {
+                    node.single = None;
+                }
+                removed
+            }
+            Segment::Literal(literal) => {
+                let Some(child) = node.literals.get_mut(literal) else { return false };
+                let removed = Self::remove_in(child, rest, value);
+                if child.is_empty() {
+                    node.literals.remove(literal);
+                }
+                removed
+            }
+        }
+    }
+
+    /// 查找匹配点位的全部值，同一值经多个模式匹配时只返回一次
+    pub fn matching(&self, tag: &str) -> Vec<T> {
+        let parts: Vec<&str> = tag.split(TAG_SEPARATOR).collect();
+        let mut found = Vec::new();
+        Self::collect(&self.root, &parts, &mut found);
+        found
+    }
+
+    /// 是否存在匹配点位的模式
+    pub fn contains_match(&self, tag: &str) -> bool {
+        let parts: Vec<&str> = tag.split(TAG_SEPARATOR).collect();
+        Self::any(&self.root, &parts)
+    }
+
+    fn collect(node: &Node<T>, parts: &[&str], found: &mut Vec<T>) {
+        let mut push = |values: &[T]| {
+            for value in values {
+                if !found.contains(value) {
+                    found.push(value.clone());
+                }
+            }
+        };
+
+        push(&node.multi);
+        let Some((part, rest)) = parts.split_first() else {
+            push(&node.terminal);
+            return;
+        };
+        if let Some(child) = node.literals.get(*part) {
+            Self::collect(child, rest, found);
+        }
+        if let Some(child) = &node.single {
+            Self::collect(child, rest, found);
+        }
+    }
+
+    fn any(node: &Node<T>, parts: &[&str]) -> bool {
+        if !node.multi.is_empty() {
+            return true;
+        }
+        let Some((part, rest)) = parts.split_first() else {
+            return !node.terminal.is_empty();
+        };
+        node.literals.get(*part).is_some_and(|child| Self::any(child, rest))
+            || node.single.as_ref().is_some_and(|child| Self::any(child, rest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(s: &str) -> TagPattern {
+        TagPattern::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_pattern_matching() {
+        assert!(pattern("plant1.+.temp").matches("plant1.line2.temp"));
+        assert!(!pattern("plant1.+.temp").matches("plant1.temp"));
+        assert!(!pattern("plant1.+.temp").matches("plant1.line2.pump.temp"));
+
+        assert!(pattern("plant1.#").matches("plant1"));
+        assert!(pattern("plant1.#").matches("plant1.line2.pump.temp"));
+        assert!(!pattern("plant1.#").matches("plant10.line2"));
+        assert!(pattern("#").matches("anything.at.all"));
+
+        assert!(pattern("plant1.line2").matches("plant1.line2"));
+        assert!(!pattern("plant1.line2").matches("plant1.line2.temp"));
+        assert!(!pattern("plant1.line2").is_wildcard());
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        assert!(TagPattern::parse("").is_err());
+        assert!(TagPattern::parse("plant1.#.temp").is_err());
+        assert!(TagPattern::parse("plant1.line+").is_err());
+        assert!(TagPattern::parse("plant1.#x").is_err());
+    }
+
+    #[test]
+    fn test_index_lookup_and_remove() {
+        let mut index = TagIndex::new();
+        index.insert(&pattern("plant1.+.temp"), 1);
+        index.insert(&pattern("plant1.#"), 2);
+        index.insert(&pattern("plant1.line2.temp"), 3);
+        index.insert(&pattern("plant1.line2.temp"), 1);
+        index.insert(&pattern("plant2.line1.temp"), 4);
+        assert_eq!(index.len(), 5);
+
+        let mut found = index.matching("plant1.line2.temp");
+        found.sort();
+        assert_eq!(found, vec![1, 2, 3]);
+        assert_eq!(index.matching("plant1"), vec![2]);
+        assert!(index.matching("plant3.x").is_empty());
+        assert!(index.contains_match("plant2.line1.temp"));
+        assert!(!index.contains_match("plant2.line1"));
+
+        assert!(index.remove(&pattern("plant1.#"), &2));
+        assert!(!index.remove(&pattern("plant1.#"), &2));
+        assert!(index.matching("plant1").is_empty());
+        assert!(index.remove(&pattern("plant2.line1.temp"), &4));
+        assert!(!index.contains_match("plant2.line1.temp"));
+        assert_eq!(index.len(), 3);
+    }
+}
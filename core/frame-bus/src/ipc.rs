@@ -0,0 +1,523 @@
+//! 本地IPC传输 (Unix域套接字)
+//!
+//! 网关进程运行`IpcServer`，子进程中的驱动或桥接插件通过`IpcClient`接入同一条总线，
+//! 驱动崩溃只影响其所在子进程。
+//!
+//! 报文格式：`len(u32 BE) | IpcMessage(protobuf)`，帧内容即现有的`FrameEnvelope`。
+//! 客户端连接后先发送`Hello`声明主体与点位模式，服务端按对端凭据 (`SO_PEERCRED`的uid) 校验
+//! 该uid可否使用所声明的主体，通过后以该主体订阅总线并回复`Hello`，否则断开连接：
+//! - 服务端按主体ACL与点位模式转发帧，客户端本地再按`Filter`过滤，语义与进程内订阅一致；
+//! - 客户端发布的帧由服务端以该主体重新发布，序列号由网关总线统一分配，ACL拒绝的帧在服务端丢弃；
+//! - 网关的`ControlMsg`转发给所有客户端，Ring使用率由服务端启动的监控任务维护。
+//!
+//! 套接字文件默认权限为0600，只有网关所属用户 (及root) 可以连接。
+
+use std::collections::HashMap;
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use prost::Message;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::control::{self, ControlMsg};
+use crate::metrics::METRICS;
+use crate::transport::Transport;
+use crate::{ring, CmdAckFrame, CmdFrame, DataFrame, Filter, FrameEnvelope, FrameKind, FramePublisher, FrameReceiver};
+
+/// 单条报文上限
+const MAX_MESSAGE_LEN: usize = 16 * 1024 * 1024;
+/// 握手超时
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Ring使用率检查周期
+const RING_MONITOR_PERIOD: Duration = Duration::from_millis(50);
+
+/// 线上报文
+mod wire {
+    use prost::Message;
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct IpcMessage {
+        #[prost(oneof = "Body", tags = "1, 2, 3")]
+        pub body: Option<Body>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Body {
+        #[prost(message, tag = "1")]
+        Hello(Hello),
+        #[prost(message, tag = "2")]
+        Frame(crate::FrameEnvelope),
+        /// 0=Pause, 1=Resume, 2=Drain
+        #[prost(int32, tag = "3")]
+        Control(i32),
+    }
+
+    #[derive(Clone, PartialEq, Message)]
+    pub struct Hello {
+        /// 订阅与发布主体，空字符串为匿名
+        #[prost(string, tag = "1")]
+        pub subject: String,
+        /// 服务端转发的点位模式，为空时转发全部
+        #[prost(string, repeated, tag = "2")]
+        pub patterns: Vec<String>,
+    }
+
+    impl From<Body> for IpcMessage {
+        fn from(body: Body) -> Self {
+            Self { body: Some(body) }
+        }
+    }
+}
+
+use wire::{Body, Hello, IpcMessage};
+
+fn control_to_wire(msg: &ControlMsg) -> i32 {
+    match msg {
+        ControlMsg::Pause => 0,
+        ControlMsg::Resume => 1,
+        ControlMsg::Drain => 2,
+    }
+}
+
+fn control_from_wire(code: i32) -> Option<ControlMsg> {
+    match code {
+        0 => Some(ControlMsg::Pause),
+        1 => Some(ControlMsg::Resume),
+        2 => Some(ControlMsg::Drain),
+        _ => None,
+    }
+}
+
+async fn write_message<W: AsyncWrite + Unpin>(writer: &mut W, message: &IpcMessage) -> Result<()> {
+    let bytes = message.encode_to_vec();
+    writer.write_u32(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await?;
+    Ok(())
+}
+
+/// 读取一条报文，对端关闭时返回None
+async fn read_message<R: AsyncRead + Unpin>(reader: &mut R) -> Result<Option<IpcMessage>> {
+    let len = match reader.read_u32().await {
+        Ok(len) => len as usize,
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if len > MAX_MESSAGE_LEN {
+        return Err(anyhow::anyhow!("IPC message of {} bytes exceeds limit", len));
+    }
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf).await?;
+    Ok(Some(IpcMessage::decode(&buf[..])?))
+}
+
+/// IPC服务端配置
+#[derive(Debug, Clone)]
+pub struct IpcServerConfig {
+    /// 套接字文件权限
+    pub socket_mode: u32,
+    /// 按对端uid授权的主体，空字符串表示匿名
+    ///
+    /// 未配置的uid中，只有与网关同一用户的进程可以声明任意主体，其余连接被拒绝
+    pub peers: HashMap<u32, Vec<String>>,
+}
+
+impl Default for IpcServerConfig {
+    fn default() -> Self {
+        Self { socket_mode: 0o600, peers: HashMap::new() }
+    }
+}
+
+impl IpcServerConfig {
+    /// 授权uid使用指定主体
+    pub fn with_peer<S: Into<String>>(mut self, uid: u32, subject: S) -> Self {
+        self.peers.entry(uid).or_default().push(subject.into());
+        self
+    }
+
+    /// 解析`uid=主体,主体;uid=主体`格式的授权列表
+    pub fn parse_peers(mut self, spec: &str) -> Result<Self> {
+        for entry in spec.split(';').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (uid, subjects) = entry.split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Invalid IPC peer entry '{}', expected uid=subject,...", entry))?;
+            let uid = uid.trim().parse::<u32>()
+                .with_context(|| format!("Invalid uid in IPC peer entry '{}'", entry))?;
+            for subject in subjects.split(',') {
+                self = self.with_peer(uid, subject.trim());
+            }
+        }
+        Ok(self)
+    }
+
+    /// 校验对端uid能否使用声明的主体
+    fn authorize(&self, owner_uid: u32, peer_uid: u32, subject: &str) -> Result<()> {
+        let allowed = match self.peers.get(&peer_uid) {
+            Some(subjects) => subjects.iter().any(|allowed| allowed == subject),
+            None => peer_uid == owner_uid,
+        };
+        if !allowed {
+            return Err(anyhow::anyhow!("IPC peer uid {} is not allowed to use subject '{}'", peer_uid, subject));
+        }
+        Ok(())
+    }
+}
+
+/// 网关侧IPC服务端
+pub struct IpcServer {
+    listener: UnixListener,
+    path: PathBuf,
+    config: Arc<IpcServerConfig>,
+    /// 套接字文件所属用户，即网关进程的uid
+    owner_uid: u32,
+}
+
+impl IpcServer {
+    /// 以默认配置绑定套接字
+    pub fn bind<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::bind_with(path, IpcServerConfig::default())
+    }
+
+    /// 绑定套接字并设置权限，替换上次异常退出遗留的套接字文件
+    ///
+    /// 套接字先在仅属主可访问的临时目录中绑定并设置权限，再原子重命名到目标路径，
+    /// 其他用户看不到权限生效前的套接字；目标路径已存在且不是套接字时拒绝绑定
+    pub fn bind_with<P: AsRef<Path>>(path: P, config: IpcServerConfig) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+            if !metadata.file_type().is_socket() {
+                anyhow::bail!("IPC socket path {:?} exists and is not a socket", path);
+            }
+        }
+
+        let file_name = path.file_name()
+            .with_context(|| format!("Invalid IPC socket path {:?}", path))?;
+        let parent = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let staging = parent.join(format!(".{}.{}", file_name.to_string_lossy(), std::process::id()));
+        std::fs::DirBuilder::new().mode(0o700).create(&staging)
+            .with_context(|| format!("Failed to create IPC staging directory {:?}", staging))?;
+        let staged = staging.join(file_name);
+        let bound = UnixListener::bind(&staged)
+            .with_context(|| format!("Failed to bind IPC socket {:?}", path))
+            .and_then(|listener| {
+                std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(config.socket_mode))
+                    .with_context(|| format!("Failed to set permissions on IPC socket {:?}", path))?;
+                std::fs::rename(&staged, &path)
+                    .with_context(|| format!("Failed to move IPC socket into place at {:?}", path))?;
+                Ok(listener)
+            });
+        let _ = std::fs::remove_file(&staged);
+        let _ = std::fs::remove_dir(&staging);
+        let listener = bound?;
+        let owner_uid = std::fs::metadata(&path)?.uid();
+        Ok(Self { listener, path, config: Arc::new(config), owner_uid })
+    }
+
+    /// 套接字路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 在后台接受连接，任务终止时删除套接字文件
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(self.run())
+    }
+
+    /// 接受并服务客户端连接
+    pub async fn run(self) {
+        if let Err(e) = control::spawn_ring_monitor(RING_MONITOR_PERIOD) {
+            warn!("Ring backpressure monitor not started: {}", e);
+        }
+        info!("FrameBus IPC server listening on {:?}", self.path);
+
+        loop {
+            match self.listener.accept().await {
+                Ok((stream, _)) => {
+                    let config = self.config.clone();
+                    let owner_uid = self.owner_uid;
+                    tokio::spawn(async move {
+                        if let Err(e) = serve_connection(stream, &config, owner_uid).await {
+                            warn!("IPC connection closed with error: {}", e);
+                        }
+                    });
+                }
+                Err(e) => {
+                    warn!("IPC accept failed: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+    }
+}
+
+impl Drop for IpcServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// 服务一个客户端：校验对端凭据后转发总线帧与控制消息，并以客户端主体重新发布其帧
+async fn serve_connection(stream: UnixStream, config: &IpcServerConfig, owner_uid: u32) -> Result<()> {
+    let peer_uid = stream.peer_cred().context("Failed to read IPC peer credentials")?.uid();
+    let (mut reader, mut writer) = stream.into_split();
+
+    let hello = match tokio::time::timeout(HANDSHAKE_TIMEOUT, read_message(&mut reader)).await
+        .context("IPC handshake timed out")??
+    {
+        Some(IpcMessage { body: Some(Body::Hello(hello)) }) => hello,
+        _ => return Err(anyhow::anyhow!("IPC client did not start with Hello")),
+    };
+    config.authorize(owner_uid, peer_uid, &hello.subject)?;
+    let name = if hello.subject.is_empty() { "anonymous".to_string() } else { hello.subject.clone() };
+
+    let filter = if hello.patterns.is_empty() {
+        Filter::All
+    } else {
+        Filter::tag_patterns(&hello.patterns)?
+    };
+    let mut frames = ring::subscribe(filter)?;
+    let mut publisher = FramePublisher::new(ring::get_publisher()?.clone());
+    if !hello.subject.is_empty() {
+        frames = frames.with_subject(hello.subject.as_str());
+        publisher = publisher.with_subject(hello.subject.as_str());
+    }
+    control::init_control();
+    let mut control_rx = control::subscribe_control()
+        .ok_or_else(|| anyhow::anyhow!("Control channel not initialized"))?;
+
+    write_message(&mut writer, &Body::Hello(hello).into()).await?;
+    info!("IPC client '{}' connected", name);
+
+    let forward_name = name.clone();
+    let forward = tokio::spawn(async move {
+        loop {
+            let body = tokio::select! {
+                frame = frames.recv() => match frame {
+                    Ok(envelope) => Body::Frame(envelope),
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("IPC client '{}' lagged, {} frames dropped", forward_name, n);
+                        METRICS.drop_total.inc_by(n as f64);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                msg = control_rx.recv() => match msg {
+                    Ok(msg) => Body::Control(control_to_wire(&msg)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => break,
+                },
+            };
+            if write_message(&mut writer, &body.into()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let result = async {
+        while let Some(message) = read_message(&mut reader).await? {
+            match message.body {
+                Some(Body::Frame(envelope)) => {
+                    if let Err(e) = republish(&publisher, envelope) {
+                        debug!("Dropped frame from IPC client '{}': {}", name, e);
+                    }
+                }
+                _ => warn!("Unexpected IPC message from '{}'", name),
+            }
+        }
+        Ok(())
+    }.await;
+
+    forward.abort();
+    info!("IPC client '{}' disconnected", name);
+    result
+}
+
+/// 按帧类型重新发布，序列号由本进程总线分配
+fn republish(publisher: &FramePublisher, envelope: FrameEnvelope) -> Result<()> {
+    match envelope.kind() {
+        FrameKind::Data => publisher.send_data(envelope.into_data()?),
+        FrameKind::Cmd => publisher.send_cmd(envelope.into_cmd()?),
+        FrameKind::CmdAck => publisher.send_cmd_ack(envelope.into_cmd_ack()?),
+    }
+}
+
+/// IPC客户端配置
+#[derive(Debug, Clone)]
+pub struct IpcClientConfig {
+    /// 发布与订阅主体，受网关总线ACL约束
+    pub subject: String,
+    /// 服务端转发的点位模式 (`plant1.#`)，为空时转发全部
+    pub patterns: Vec<String>,
+    /// 待发送帧队列长度，满时发布返回错误
+    pub send_queue: usize,
+    /// 本地订阅缓冲区容量
+    pub receive_capacity: usize,
+}
+
+impl Default for IpcClientConfig {
+    fn default() -> Self {
+        Self {
+            subject: String::new(),
+            patterns: Vec::new(),
+            send_queue: 1024,
+            receive_capacity: 4096,
+        }
+    }
+}
+
+/// 子进程侧IPC客户端
+///
+/// 背压与进程内一致为建议式：收到`Pause`后`is_paused()`为true，生产者应暂缓发布直到`Resume`
+pub struct IpcClient {
+    outbound: mpsc::Sender<IpcMessage>,
+    frames: broadcast::Sender<FrameEnvelope>,
+    control: broadcast::Sender<ControlMsg>,
+    paused: Arc<AtomicBool>,
+    connected: Arc<AtomicBool>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl IpcClient {
+    /// 以指定主体连接，接收全部有读权限的帧
+    pub async fn connect<P: AsRef<Path>>(path: P, subject: &str) -> Result<Self> {
+        Self::connect_with(path, IpcClientConfig { subject: subject.to_string(), ..Default::default() }).await
+    }
+
+    /// 按配置连接
+    pub async fn connect_with<P: AsRef<Path>>(path: P, config: IpcClientConfig) -> Result<Self> {
+        let path = path.as_ref();
+        let stream = UnixStream::connect(path).await
+            .with_context(|| format!("Failed to connect to IPC socket {:?}", path))?;
+        let (mut reader, mut writer) = stream.into_split();
+
+        let hello = Hello { subject: config.subject.clone(), patterns: config.patterns.clone() };
+        write_message(&mut writer, &Body::Hello(hello).into()).await?;
+        match tokio::time::timeout(HANDSHAKE_TIMEOUT, read_message(&mut reader)).await
+            .context("IPC handshake timed out")??
+        {
+            Some(IpcMessage { body: Some(Body::Hello(_)) }) => {}
+            _ => return Err(anyhow::anyhow!("IPC server did not acknowledge handshake")),
+        }
+
+        let (frames, _) = broadcast::channel(config.receive_capacity);
+        let (control, _) = broadcast::channel(64);
+        let (outbound, mut outbound_rx) = mpsc::channel::<IpcMessage>(config.send_queue);
+        let paused = Arc::new(AtomicBool::new(false));
+        let connected = Arc::new(AtomicBool::new(true));
+
+        let reader_task = {
+            let frames = frames.clone();
+            let control = control.clone();
+            let paused = paused.clone();
+            let connected = connected.clone();
+            tokio::spawn(async move {
+                loop {
+                    match read_message(&mut reader).await {
+                        Ok(Some(IpcMessage { body: Some(Body::Frame(envelope)) })) => {
+                            let _ = frames.send(envelope);
+                        }
+                        Ok(Some(IpcMessage { body: Some(Body::Control(code)) })) => {
+                            let Some(msg) = control_from_wire(code) else { continue };
+                            match msg {
+                                ControlMsg::Pause => paused.store(true, Ordering::SeqCst),
+                                ControlMsg::Resume => paused.store(false, Ordering::SeqCst),
+                                ControlMsg::Drain => {}
+                            }
+                            let _ = control.send(msg);
+                        }
+                        Ok(Some(_)) => {}
+                        Ok(None) => break,
+                        Err(e) => {
+                            warn!("IPC read failed: {}", e);
+                            break;
+                        }
+                    }
+                }
+                connected.store(false, Ordering::SeqCst);
+            })
+        };
+
+        let writer_task = {
+            let connected = connected.clone();
+            tokio::spawn(async move {
+                while let Some(message) = outbound_rx.recv().await {
+                    if let Err(e) = write_message(&mut writer, &message).await {
+                        warn!("IPC write failed: {}", e);
+                        break;
+                    }
+                }
+                connected.store(false, Ordering::SeqCst);
+            })
+        };
+
+        Ok(Self {
+            outbound,
+            frames,
+            control,
+            paused,
+            connected,
+            tasks: vec![reader_task, writer_task],
+        })
+    }
+
+    /// 连接是否仍然可用
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    /// 网关是否要求生产者暂停
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+
+    fn publish(&self, envelope: Result<FrameEnvelope, prost::EncodeError>) -> Result<()> {
+        if !self.is_connected() {
+            return Err(anyhow::anyhow!("IPC connection closed"));
+        }
+        self.outbound.try_send(Body::Frame(envelope?).into()).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => {
+                METRICS.drop_total.inc();
+                anyhow::anyhow!("IPC send queue full")
+            }
+            mpsc::error::TrySendError::Closed(_) => anyhow::anyhow!("IPC connection closed"),
+        })
+    }
+}
+
+impl Transport for IpcClient {
+    fn subscribe(&self, filter: Filter) -> Result<FrameReceiver> {
+        Ok(FrameReceiver::new(self.frames.subscribe(), filter))
+    }
+
+    // 序列号由网关分配，本地填0
+    fn send_data(&self, frame: DataFrame) -> Result<()> {
+        self.publish(FrameEnvelope::wrap_data(0, frame))
+    }
+
+    fn send_cmd(&self, frame: CmdFrame) -> Result<()> {
+        self.publish(FrameEnvelope::wrap_cmd(0, frame))
+    }
+
+    fn send_cmd_ack(&self, frame: CmdAckFrame) -> Result<()> {
+        self.publish(FrameEnvelope::wrap_cmd_ack(0, frame))
+    }
+
+    fn subscribe_control(&self) -> Result<broadcast::Receiver<ControlMsg>> {
+        Ok(self.control.subscribe())
+    }
+}
+
+impl Drop for IpcClient {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
@@ -12,14 +12,34 @@ pub mod metrics;
 pub mod filter;
 pub mod command;
 pub mod permissions;
+pub mod durable;
+pub mod index;
+pub mod router;
+pub mod policy;
+pub mod codec;
+pub mod retention;
+pub mod cache;
+pub mod transport;
+#[cfg(unix)]
+pub mod ipc;
 
 pub use envelope::{DataFrame, CmdFrame, CmdAckFrame, FrameEnvelope, FrameKind, Value};
 pub use ring::{FrameSender, FrameReceiver, FramePublisher, BatchConfig, AsyncBatchPublisher};
 pub use filter::Filter;
+pub use index::{TagIndex, TagPattern};
+pub use router::RoutedReceiver;
 pub use config::{BusCfg, PerformancePresets};
-pub use wal::WAL;
+pub use wal::{WAL, WalConfig, RecoveryStats};
+pub use codec::{WalCodec, WalCodecConfig};
+pub use cache::{LastValueCache, CacheConfig, CachedValue, CacheUpdate, Deadband, Snapshot};
+pub use transport::{Transport, LocalTransport};
+#[cfg(unix)]
+pub use ipc::{IpcServer, IpcServerConfig, IpcClient, IpcClientConfig};
+pub use retention::{RetentionConfig, RetentionStats, RetentionReport, RetentionAlert, RetentionAlertSink};
 pub use command::{CommandProcessor, CommandPriority, CommandStatus, CommandResult, PendingCommand};
-pub use permissions::{PermissionManager, Permission, AccessControlEntry};
+pub use permissions::{PermissionManager, Permission, AccessControlEntry, AclMode};
+pub use durable::{DurableReceiver, DurableOptions, StartFrom};
+pub use policy::{CommandPolicy, PolicyConfig, PolicyDecision, TagPolicy, AuditRecord, AuditOutcome};
 
 use anyhow::{Result, Context};
 use std::path::{Path, PathBuf};
@@ -36,6 +56,7 @@ pub fn init<P: AsRef<Path>>(ring_size: usize, wal_dir: P) -> Result<(FrameSender
     
     metrics::init_metrics();
     wal::init(&cfg.wal_dir)?;
+    recover_from_wal()?;
     let (tx, rx) = ring::init(cfg)?;
     
     // 初始化批量发布器（使用默认配置）
@@ -58,6 +79,7 @@ pub fn init_with_batch_config<P: AsRef<Path>>(
     
     metrics::init_metrics();
     wal::init(&cfg.wal_dir)?;
+    recover_from_wal()?;
     let (tx, rx) = ring::init(cfg)?;
     
     // 初始化批量发布器（使用自定义配置）
@@ -66,6 +88,48 @@ pub fn init_with_batch_config<P: AsRef<Path>>(
     Ok((tx, rx))
 }
 
+/// 初始化FrameBus全局实例（带自定义WAL配置，如帧压缩与加密）
+pub fn init_with_wal_config<P: AsRef<Path>>(
+    ring_size: usize,
+    wal_dir: P,
+    wal_config: WalConfig,
+) -> Result<(FrameSender, FrameReceiver)> {
+    let cfg = BusCfg {
+        ring_pow: (ring_size as f64).log2() as u8,
+        wal_dir: wal_dir.as_ref().to_path_buf(),
+        ..Default::default()
+    };
+
+    metrics::init_metrics();
+    wal::init_with_config(&cfg.wal_dir, wal_config)?;
+    recover_from_wal()?;
+    let (tx, rx) = ring::init(cfg)?;
+
+    ring::init_batch_publisher(&tx, None)?;
+
+    Ok((tx, rx))
+}
+
+/// 崩溃恢复：扫描WAL并将序列号生成器推进到已持久化的最大序列号之后
+///
+/// 未ACK的帧不回灌环形缓冲区，而是由持久订阅从各自游标之后的WAL回放重投
+fn recover_from_wal() -> Result<RecoveryStats> {
+    let stats = wal::recover().context("WAL recovery scan failed")?;
+    if let Some(max_seq) = stats.max_seq {
+        ring::restore_sequence(max_seq + 1);
+    }
+    info!(
+        "WAL recovery: {} frames recovered, {} corrupt skipped, {} unacked across {} consumers, next seq {}",
+        stats.frames_recovered, stats.corrupt_skipped, stats.unacked_frames, stats.consumers, ring::current_sequence()
+    );
+    Ok(stats)
+}
+
+/// 获取启动时的WAL恢复统计
+pub fn recovery_stats() -> Option<RecoveryStats> {
+    wal::recovery_stats()
+}
+
 /// 发布DataFrame到总线（传统方式）
 pub fn publish_data(frame: DataFrame) -> Result<()> {
     let tx = ring::get_publisher()?;
@@ -116,9 +180,12 @@ pub fn init_high_performance<P: AsRef<Path>>(
         auto_compact_threshold: cfg.wal_max_bytes,
         retain_frames: 10000,
         backpressure_threshold: (cfg.async_write_queue_size as f32 * cfg.backpressure_threshold) as usize,
+        codec: WalCodecConfig::default(),
+        retention: RetentionConfig::default(),
     };
     
     wal::init_with_config(&cfg.wal_dir, wal_config)?;
+    recover_from_wal()?;
     
     // 使用新的批量配置初始化
     let batch_config = BatchConfig {
@@ -152,6 +219,7 @@ pub fn init_memory_mode<P: AsRef<Path>>(ring_size: usize, _wal_dir: P) -> Result
     
     // 初始化内存WAL
     wal::init_memory_wal()?;
+    recover_from_wal()?;
     
     // 使用内存优化配置初始化Ring
     let batch_config = BatchConfig {
@@ -219,11 +287,96 @@ pub fn subscribe(filter: Filter) -> Result<FrameReceiver> {
     ring::subscribe(filter)
 }
 
+/// 按点位模式创建路由订阅，只接收匹配模式的帧 (`plant1.+.temp`、`plant1.#`)
+///
+/// `filter`在点位匹配之后检查，可用于帧类型与元数据谓词
+pub fn subscribe_routed<I, S>(patterns: I, filter: Filter) -> Result<RoutedReceiver>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    router::subscribe(patterns, filter)
+}
+
+/// 获取带主体标识的发布器，发布受总线ACL约束
+pub fn publisher(subject: &str) -> Result<FramePublisher> {
+    let tx = ring::get_publisher()?;
+    Ok(FramePublisher::new(tx.clone()).with_subject(subject))
+}
+
+/// 获取带主体标识的批量发布器，发布受总线ACL约束
+pub fn batched_publisher(subject: &str) -> Result<FramePublisher> {
+    let tx = ring::get_publisher()?;
+    Ok(FramePublisher::new_batched(tx.clone()).with_subject(subject))
+}
+
+/// 以指定主体订阅，只接收该主体有读权限的点位
+pub fn subscribe_as(subject: &str, filter: Filter) -> Result<FrameReceiver> {
+    Ok(ring::subscribe(filter)?.with_subject(subject))
+}
+
+/// 点位当前值
+pub fn last_value(tag: &str) -> Option<CachedValue> {
+    cache::global().get(tag)
+}
+
+/// 全部点位当前值的一致性快照
+pub fn snapshot() -> Snapshot {
+    cache::global().snapshot()
+}
+
+/// 配置最新值缓存的死区
+pub fn configure_last_value_cache(config: &CacheConfig) -> Result<()> {
+    cache::global().configure(config)
+}
+
+/// 原子地生成快照并订阅：快照包含订阅前发布的全部匹配点位，接收端从快照之后的帧开始
+pub fn subscribe_with_snapshot(filter: Filter) -> Result<(Snapshot, FrameReceiver)> {
+    let (snapshot, receiver) = cache::global().snapshot_with(&filter, None, || ring::subscribe(filter.clone()));
+    Ok((snapshot, receiver?))
+}
+
+/// 以指定主体生成快照并订阅，快照与接收端均只包含该主体有读权限的点位
+pub fn subscribe_with_snapshot_as(subject: &str, filter: Filter) -> Result<(Snapshot, FrameReceiver)> {
+    let (snapshot, receiver) = cache::global().snapshot_with(&filter, Some(subject), || ring::subscribe(filter.clone()));
+    Ok((snapshot, receiver?.with_subject(subject)))
+}
+
+/// 安装总线ACL，所有发布与订阅句柄按其主体受约束
+pub fn install_acl(manager: std::sync::Arc<PermissionManager>, mode: AclMode) {
+    permissions::install_acl(manager, mode)
+}
+
+/// 轮换WAL加密密钥，返回重写为新密钥的存量帧数
+pub async fn rotate_wal_key(key: &[u8]) -> Result<u64> {
+    wal::rotate_key(key).await
+}
+
+/// 设置WAL删除未回放数据前的告警钩子
+pub fn set_wal_retention_alert_sink(sink: RetentionAlertSink) -> Result<()> {
+    wal::set_retention_alert_sink(sink)
+}
+
 /// ACK指定序列号
 pub fn ack(consumer_id: &str, seq: u64) -> Result<()> {
     wal::ack(consumer_id, seq)
 }
 
+/// 创建命名持久订阅，从该消费者最后ACK的序列号之后恢复
+pub fn subscribe_durable(consumer_id: &str, filter: Filter) -> Result<DurableReceiver> {
+    DurableReceiver::new(consumer_id, filter, DurableOptions::default())
+}
+
+/// 使用自定义选项创建命名持久订阅
+pub fn subscribe_durable_with(consumer_id: &str, filter: Filter, options: DurableOptions) -> Result<DurableReceiver> {
+    DurableReceiver::new(consumer_id, filter, options)
+}
+
+/// 删除持久订阅游标，WAL GC不再为其保留帧
+pub fn remove_durable(consumer_id: &str) -> Result<()> {
+    wal::remove_consumer(consumer_id)
+}
+
 /// 测试专用：创建独立的FrameBus实例，不使用全局状态
 pub fn init_test_instance<P: AsRef<Path>>(ring_size: usize, wal_dir: P) -> Result<(FrameSender, FrameReceiver)> {
     let cfg = BusCfg {
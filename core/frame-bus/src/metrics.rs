@@ -13,6 +13,18 @@ pub struct BusMetrics {
     pub backlog_lag: IntGauge,
     pub wal_bytes: IntGauge,
     pub wal_flush_duration: Histogram,
+    pub wal_write_error_total: Counter,
+    pub wal_recovered_frames: IntGauge,
+    pub wal_corrupt_frames: IntGauge,
+    pub wal_retention_deleted_total: Counter,
+    // ACL指标
+    pub acl_publish_denied_total: Counter,
+    pub acl_receive_denied_total: Counter,
+    // 命令策略指标
+    pub policy_rejected_total: Counter,
+    // 持久订阅指标
+    pub durable_replay_total: Counter,
+    pub durable_lag_total: Counter,
     // 批量处理指标
     pub batch_size: Histogram,
     pub batch_flush_duration: Histogram,
@@ -57,6 +69,54 @@ impl BusMetrics {
         ).unwrap();
         registry.register(Box::new(wal_flush_duration.clone())).unwrap();
 
+        let wal_write_error_total = Counter::with_opts(
+            Opts::new("framebus_wal_write_error_total", "Total frames that failed to persist to WAL")
+        ).unwrap();
+        registry.register(Box::new(wal_write_error_total.clone())).unwrap();
+
+        let wal_recovered_frames = IntGauge::with_opts(
+            Opts::new("framebus_wal_recovered_frames", "Frames found in WAL at startup")
+        ).unwrap();
+        registry.register(Box::new(wal_recovered_frames.clone())).unwrap();
+
+        let wal_corrupt_frames = IntGauge::with_opts(
+            Opts::new("framebus_wal_corrupt_frames", "Corrupt WAL records skipped at startup")
+        ).unwrap();
+        registry.register(Box::new(wal_corrupt_frames.clone())).unwrap();
+
+        let wal_retention_deleted_total = Counter::with_opts(
+            Opts::new("framebus_wal_retention_deleted_total", "WAL frames deleted by the retention policy")
+        ).unwrap();
+        registry.register(Box::new(wal_retention_deleted_total.clone())).unwrap();
+
+        // ACL指标
+        let acl_publish_denied_total = Counter::with_opts(
+            Opts::new("framebus_acl_publish_denied_total", "Publishes outside the subject's ACL")
+        ).unwrap();
+        registry.register(Box::new(acl_publish_denied_total.clone())).unwrap();
+
+        let acl_receive_denied_total = Counter::with_opts(
+            Opts::new("framebus_acl_receive_denied_total", "Frames outside the subscriber's ACL")
+        ).unwrap();
+        registry.register(Box::new(acl_receive_denied_total.clone())).unwrap();
+
+        // 命令策略指标
+        let policy_rejected_total = Counter::with_opts(
+            Opts::new("framebus_policy_rejected_total", "Commands rejected by the command policy")
+        ).unwrap();
+        registry.register(Box::new(policy_rejected_total.clone())).unwrap();
+
+        // 持久订阅指标
+        let durable_replay_total = Counter::with_opts(
+            Opts::new("framebus_durable_replay_total", "Total frames replayed from WAL to durable subscribers")
+        ).unwrap();
+        registry.register(Box::new(durable_replay_total.clone())).unwrap();
+
+        let durable_lag_total = Counter::with_opts(
+            Opts::new("framebus_durable_lag_total", "Times a durable subscriber lagged behind the ring and fell back to WAL")
+        ).unwrap();
+        registry.register(Box::new(durable_lag_total.clone())).unwrap();
+
         // 批量处理指标
         let batch_size = Histogram::with_opts(
             HistogramOpts::new(
@@ -94,6 +154,15 @@ impl BusMetrics {
             backlog_lag,
             wal_bytes,
             wal_flush_duration,
+            wal_write_error_total,
+            wal_recovered_frames,
+            wal_corrupt_frames,
+            wal_retention_deleted_total,
+            acl_publish_denied_total,
+            acl_receive_denied_total,
+            policy_rejected_total,
+            durable_replay_total,
+            durable_lag_total,
             batch_size,
             batch_flush_duration,
             batch_send_duration,
@@ -5,12 +5,15 @@ Provides fine-grained permission control for frame operations and commands.
 */
 
 use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, RwLock};
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use anyhow::{Context, Result};
-use tracing::debug;
+use tracing::{debug, warn};
 
 use crate::envelope::{CmdFrame, DataFrame};
+use crate::metrics::METRICS;
 
 /// Permission levels for data access
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -395,6 +398,93 @@ impl PermissionManager {
     }
 }
 
+/// How the bus applies the installed ACL
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AclMode {
+    /// Reject publishes and withhold frames outside the subject's ACL
+    #[default]
+    Enforce,
+    /// Let everything through but log and count what would have been denied
+    Audit,
+}
+
+/// Subject used for handles that were not given one
+pub const ANONYMOUS_SUBJECT: &str = "anonymous";
+
+struct BusAcl {
+    manager: Arc<PermissionManager>,
+    mode: AclMode,
+}
+
+/// Fast path: skip the lock entirely while no ACL is installed
+static ACL_INSTALLED: AtomicBool = AtomicBool::new(false);
+static BUS_ACL: Lazy<RwLock<Option<BusAcl>>> = Lazy::new(|| RwLock::new(None));
+
+/// Install the ACL consulted by every publisher and subscriber handle.
+///
+/// Publishing a frame requires `Write` on its tag, receiving one requires `Read`.
+pub fn install_acl(manager: Arc<PermissionManager>, mode: AclMode) {
+    *BUS_ACL.write().unwrap() = Some(BusAcl { manager, mode });
+    ACL_INSTALLED.store(true, Ordering::Release);
+}
+
+/// Remove the bus ACL; all handles are unrestricted again
+pub fn clear_acl() {
+    ACL_INSTALLED.store(false, Ordering::Release);
+    *BUS_ACL.write().unwrap() = None;
+}
+
+/// Mode of the installed ACL, if any
+pub fn acl_mode() -> Option<AclMode> {
+    BUS_ACL.read().unwrap().as_ref().map(|acl| acl.mode)
+}
+
+/// Check the bus ACL; returns `(allowed, mode)` or `None` when no ACL is installed
+fn check_bus_acl(subject: Option<&str>, tag: &str, permission: Permission) -> Option<(bool, AclMode)> {
+    if !ACL_INSTALLED.load(Ordering::Acquire) {
+        return None;
+    }
+    let acl = BUS_ACL.read().unwrap();
+    let acl = acl.as_ref()?;
+    let subject = subject.unwrap_or(ANONYMOUS_SUBJECT);
+    Some((acl.manager.check_permission(subject, tag, permission), acl.mode))
+}
+
+/// Authorize a publish of `tag` by `subject`; errors only when the ACL is enforced
+pub fn authorize_publish(subject: Option<&str>, tag: &str) -> Result<()> {
+    match check_bus_acl(subject, tag, Permission::Write) {
+        None | Some((true, _)) => Ok(()),
+        Some((false, mode)) => {
+            METRICS.acl_publish_denied_total.inc();
+            let subject = subject.unwrap_or(ANONYMOUS_SUBJECT);
+            match mode {
+                AclMode::Enforce => {
+                    warn!("ACL denied publish to '{}' by '{}'", tag, subject);
+                    Err(anyhow::anyhow!("Subject '{}' is not permitted to publish to tag '{}'", subject, tag))
+                }
+                AclMode::Audit => {
+                    warn!("ACL audit: '{}' published to '{}' without write permission", subject, tag);
+                    Ok(())
+                }
+            }
+        }
+    }
+}
+
+/// Whether `subject` may receive a frame for `tag`; always true in audit mode
+pub fn authorize_receive(subject: Option<&str>, tag: &str) -> bool {
+    match check_bus_acl(subject, tag, Permission::Read) {
+        None | Some((true, _)) => true,
+        Some((false, mode)) => {
+            METRICS.acl_receive_denied_total.inc();
+            debug!("ACL {} delivery of '{}' to '{}'",
+                   if mode == AclMode::Audit { "audited" } else { "withheld" },
+                   tag, subject.unwrap_or(ANONYMOUS_SUBJECT));
+            mode == AclMode::Audit
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,730 @@
+/*!
+# Command Policy
+
+Validates commands before they are queued or written to a driver: value limits,
+step-change limits, interlocks, per-tag write-rate limits and select-before-operate
+(SBO) for critical targets. Every decision and every command result is recorded in
+the audit trail.
+*/
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::command::CommandPriority;
+use crate::envelope::{current_timestamp_ns, value::Value as Kind, CmdAckFrame, CmdFrame, DataFrame, Value};
+use crate::index::{TagIndex, TagPattern};
+use crate::metrics::METRICS;
+
+/// Command meta key carrying the select-before-operate step
+pub const SBO_META_KEY: &str = "sbo";
+/// SBO step that arms a command
+pub const SBO_SELECT: &str = "select";
+/// SBO step that executes a previously armed command
+pub const SBO_OPERATE: &str = "operate";
+
+/// Write-rate limit for a tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Maximum accepted writes within the window
+    pub max_writes: u32,
+    /// Window length in milliseconds
+    pub window_ms: u64,
+}
+
+/// Policy applied to all tags matching a pattern
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TagPolicy {
+    /// Tag pattern, wildcards as in [`TagPattern`]
+    pub pattern: String,
+    /// Minimum accepted value
+    #[serde(default)]
+    pub min: Option<f64>,
+    /// Maximum accepted value
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// Maximum change relative to the current value
+    #[serde(default)]
+    pub max_step: Option<f64>,
+    /// Interlock expressions that must all hold, e.g. `plant1.pump.running == false`
+    #[serde(default)]
+    pub interlocks: Vec<String>,
+    /// Per-tag write-rate limit
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    /// Require select-before-operate regardless of priority
+    #[serde(default)]
+    pub select_before_operate: bool,
+}
+
+impl TagPolicy {
+    pub fn new<S: Into<String>>(pattern: S) -> Self {
+        Self { pattern: pattern.into(), ..Default::default() }
+    }
+
+    /// Set accepted value range
+    pub fn with_range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    /// Set maximum step change
+    pub fn with_max_step(mut self, max_step: f64) -> Self {
+        self.max_step = Some(max_step);
+        self
+    }
+
+    /// Add an interlock expression
+    pub fn with_interlock<S: Into<String>>(mut self, expr: S) -> Self {
+        self.interlocks.push(expr.into());
+        self
+    }
+
+    /// Set write-rate limit
+    pub fn with_rate_limit(mut self, max_writes: u32, window: Duration) -> Self {
+        self.rate_limit = Some(RateLimit { max_writes, window_ms: window.as_millis() as u64 });
+        self
+    }
+
+    /// Require select-before-operate
+    pub fn with_select_before_operate(mut self) -> Self {
+        self.select_before_operate = true;
+        self
+    }
+}
+
+/// Policy configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PolicyConfig {
+    pub policies: Vec<TagPolicy>,
+    /// How long an armed selection stays valid
+    pub sbo_window_ms: u64,
+    /// Emergency priority commands always require select-before-operate
+    pub sbo_for_emergency: bool,
+    /// Audit records kept in memory
+    pub audit_capacity: usize,
+}
+
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        Self {
+            policies: Vec::new(),
+            sbo_window_ms: 10_000,
+            sbo_for_emergency: true,
+            audit_capacity: 1000,
+        }
+    }
+}
+
+/// Interlock comparison operator
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CompareOp {
+    fn parse(op: &str) -> Option<Self> {
+        Some(match op {
+            "==" => CompareOp::Eq,
+            "!=" => CompareOp::Ne,
+            "<" => CompareOp::Lt,
+            "<=" => CompareOp::Le,
+            ">" => CompareOp::Gt,
+            ">=" => CompareOp::Ge,
+            _ => return None,
+        })
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            CompareOp::Eq => "==",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        }
+    }
+
+    fn accepts(&self, ordering: Ordering) -> bool {
+        match self {
+            CompareOp::Eq => ordering == Ordering::Equal,
+            CompareOp::Ne => ordering != Ordering::Equal,
+            CompareOp::Lt => ordering == Ordering::Less,
+            CompareOp::Le => ordering != Ordering::Greater,
+            CompareOp::Gt => ordering == Ordering::Greater,
+            CompareOp::Ge => ordering != Ordering::Less,
+        }
+    }
+}
+
+static INTERLOCK_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*([^\s=!<>]+)\s*(==|!=|<=|>=|<|>)\s*(.+?)\s*$").unwrap()
+});
+
+/// Condition on another tag's current value that must hold before a write
+#[derive(Debug, Clone, PartialEq)]
+pub struct Interlock {
+    pub tag: String,
+    pub op: CompareOp,
+    pub value: Value,
+}
+
+impl Interlock {
+    /// Parse `<tag> <op> <literal>`, literal being a bool, number or quoted string
+    pub fn parse(expr: &str) -> Result<Self> {
+        let caps = INTERLOCK_RE.captures(expr)
+            .ok_or_else(|| anyhow::anyhow!("Invalid interlock expression '{}'", expr))?;
+        let op = CompareOp::parse(&caps[2]).expect("regex only matches known operators");
+        let literal = &caps[3];
+
+        let value = if literal == "true" || literal == "false" {
+            Value::bool(literal == "true")
+        } else if let Some(s) = literal.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            Value::string(s)
+        } else if let Ok(i) = literal.parse::<i64>() {
+            Value::int(i)
+        } else if let Ok(f) = literal.parse::<f64>() {
+            Value::float(f)
+        } else {
+            return Err(anyhow::anyhow!("Invalid literal '{}' in interlock '{}'", literal, expr));
+        };
+
+        Ok(Self { tag: caps[1].to_string(), op, value })
+    }
+
+    /// Check the interlock against the referenced tag's current value
+    pub fn holds(&self, current: &Value) -> bool {
+        let ordering = match &self.value.value {
+            Some(Kind::BoolV(expected)) => current.to_bool().map(|v| v.cmp(expected)),
+            Some(Kind::StrV(expected)) => current.to_string().map(|v| v.as_str().cmp(expected)),
+            _ => match (current.to_f64(), self.value.to_f64()) {
+                (Some(v), Some(expected)) => v.partial_cmp(&expected),
+                _ => None,
+            },
+        };
+        ordering.is_some_and(|o| self.op.accepts(o))
+    }
+}
+
+impl fmt::Display for Interlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let literal = match &self.value.value {
+            Some(Kind::StrV(s)) => format!("\"{}\"", s),
+            _ => self.value.to_string().unwrap_or_default(),
+        };
+        write!(f, "{} {} {}", self.tag, self.op.as_str(), literal)
+    }
+}
+
+/// Policy decision for a command
+#[derive(Debug, Clone, PartialEq)]
+pub enum PolicyDecision {
+    /// Command may be executed
+    Allow,
+    /// SBO select accepted, awaiting operate
+    Armed,
+    /// Command rejected with reason
+    Reject(String),
+}
+
+/// Audit outcome
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum AuditOutcome {
+    Accepted,
+    Armed,
+    Rejected(String),
+    Succeeded,
+    Failed(String),
+}
+
+/// Audit trail record
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    /// ns since epoch
+    pub timestamp: u64,
+    pub cmd_id: u64,
+    pub tag: String,
+    /// Command origin, or the acknowledging driver for results
+    pub source: String,
+    pub value: Option<String>,
+    pub priority: i32,
+    pub outcome: AuditOutcome,
+}
+
+/// Audit hook invoked for every record
+pub type AuditSink = Arc<dyn Fn(&AuditRecord) + Send + Sync>;
+
+struct CompiledPolicy {
+    policy: TagPolicy,
+    interlocks: Vec<Interlock>,
+}
+
+struct Selection {
+    origin: String,
+    value: Option<Value>,
+    expires_at: Instant,
+}
+
+/// Command policy engine
+pub struct CommandPolicy {
+    config: PolicyConfig,
+    policies: Vec<CompiledPolicy>,
+    index: TagIndex<usize>,
+    /// Current values used for interlocks and step limits
+    values: RwLock<HashMap<String, Value>>,
+    /// Accepted write times per tag
+    writes: Mutex<HashMap<String, VecDeque<Instant>>>,
+    /// Armed selections per tag
+    selections: Mutex<HashMap<String, Selection>>,
+    audit: Mutex<VecDeque<AuditRecord>>,
+    audit_sink: Option<AuditSink>,
+}
+
+impl CommandPolicy {
+    /// Create policy engine, compiling patterns and interlocks
+    pub fn new(config: PolicyConfig) -> Result<Self> {
+        let mut policies = Vec::with_capacity(config.policies.len());
+        let mut index = TagIndex::new();
+        for (i, policy) in config.policies.iter().enumerate() {
+            index.insert(&TagPattern::parse(&policy.pattern)?, i);
+            let interlocks = policy.interlocks.iter()
+                .map(|expr| Interlock::parse(expr))
+                .collect::<Result<Vec<_>>>()?;
+            policies.push(CompiledPolicy { policy: policy.clone(), interlocks });
+        }
+
+        Ok(Self {
+            config,
+            policies,
+            index,
+            values: RwLock::new(HashMap::new()),
+            writes: Mutex::new(HashMap::new()),
+            selections: Mutex::new(HashMap::new()),
+            audit: Mutex::new(VecDeque::new()),
+            audit_sink: None,
+        })
+    }
+
+    /// Set audit hook (e.g. forwarding to a persistent audit log)
+    pub fn with_audit_sink(mut self, sink: AuditSink) -> Self {
+        self.audit_sink = Some(sink);
+        self
+    }
+
+    /// Update a tag's current value
+    pub fn update_value<S: Into<String>>(&self, tag: S, value: Value) {
+        self.values.write().unwrap().insert(tag.into(), value);
+    }
+
+    /// Update current values from a data frame
+    pub fn observe(&self, frame: &DataFrame) {
+        if let Some(value) = &frame.value {
+            self.update_value(frame.tag.clone(), value.clone());
+        }
+    }
+
+    /// Keep current values up to date from data frames on the global bus
+    pub fn spawn_observer(self: &Arc<Self>, subject: &str) -> Result<tokio::task::JoinHandle<()>> {
+        let mut rx = crate::subscribe_as(subject, crate::Filter::data_only())?;
+        let policy = self.clone();
+        Ok(tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(envelope) => {
+                        if let Ok(frame) = envelope.into_data() {
+                            policy.observe(&frame);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Command policy observer lagged by {} frames", skipped);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }))
+    }
+
+    /// Evaluate a command and record the decision in the audit trail
+    pub fn evaluate(&self, cmd: &CmdFrame) -> PolicyDecision {
+        let decision = self.check(cmd);
+        let outcome = match &decision {
+            PolicyDecision::Allow => AuditOutcome::Accepted,
+            PolicyDecision::Armed => AuditOutcome::Armed,
+            PolicyDecision::Reject(reason) => {
+                METRICS.policy_rejected_total.inc();
+                warn!("Command {} to '{}' rejected: {}", cmd.cmd_id, cmd.tag, reason);
+                AuditOutcome::Rejected(reason.clone())
+            }
+        };
+        self.record(AuditRecord {
+            timestamp: current_timestamp_ns(),
+            cmd_id: cmd.cmd_id,
+            tag: cmd.tag.clone(),
+            source: cmd.origin.clone(),
+            value: cmd.value.as_ref().and_then(|v| v.to_string()),
+            priority: cmd.priority,
+            outcome,
+        });
+        decision
+    }
+
+    /// Record a command result in the audit trail
+    pub fn record_ack(&self, ack: &CmdAckFrame) {
+        if ack.success {
+            if let Some(actual) = &ack.actual_value {
+                self.update_value(ack.tag.clone(), actual.clone());
+            }
+        }
+        self.record(AuditRecord {
+            timestamp: ack.timestamp,
+            cmd_id: ack.cmd_id,
+            tag: ack.tag.clone(),
+            source: ack.driver_id.clone(),
+            value: ack.actual_value.as_ref().and_then(|v| v.to_string()),
+            priority: 0,
+            outcome: if ack.success {
+                AuditOutcome::Succeeded
+            } else {
+                AuditOutcome::Failed(ack.error_msg.clone())
+            },
+        });
+    }
+
+    /// Audit records kept in memory, oldest first
+    pub fn audit_log(&self) -> Vec<AuditRecord> {
+        self.audit.lock().unwrap().iter().cloned().collect()
+    }
+
+    fn record(&self, record: AuditRecord) {
+        info!(
+            target: "frame_bus::audit",
+            cmd_id = record.cmd_id,
+            tag = %record.tag,
+            source = %record.source,
+            outcome = ?record.outcome,
+            "command audit"
+        );
+        if let Some(sink) = &self.audit_sink {
+            sink(&record);
+        }
+
+        let mut audit = self.audit.lock().unwrap();
+        if audit.len() >= self.config.audit_capacity {
+            audit.pop_front();
+        }
+        audit.push_back(record);
+    }
+
+    fn check(&self, cmd: &CmdFrame) -> PolicyDecision {
+        let policies: Vec<&CompiledPolicy> = self.index.matching(&cmd.tag)
+            .into_iter()
+            .map(|i| &self.policies[i])
+            .collect();
+
+        if let Err(reason) = self.check_limits(cmd, &policies) {
+            return PolicyDecision::Reject(reason);
+        }
+        if let Err(reason) = self.check_interlocks(&policies) {
+            return PolicyDecision::Reject(reason);
+        }
+
+        let requires_sbo = (self.config.sbo_for_emergency
+            && CommandPriority::from(cmd.priority) == CommandPriority::Emergency)
+            || policies.iter().any(|p| p.policy.select_before_operate);
+        match cmd.meta.get(SBO_META_KEY).map(String::as_str) {
+            Some(SBO_SELECT) if !requires_sbo => {
+                return PolicyDecision::Reject(format!("Tag '{}' does not use select-before-operate", cmd.tag));
+            }
+            Some(SBO_SELECT) => {
+                self.arm(cmd);
+                return PolicyDecision::Armed;
+            }
+            Some(SBO_OPERATE) => {
+                if let Err(reason) = self.take_selection(cmd) {
+                    return PolicyDecision::Reject(reason);
+                }
+            }
+            Some(other) => {
+                return PolicyDecision::Reject(format!("Unknown select-before-operate step '{}'", other));
+            }
+            None if requires_sbo => {
+                return PolicyDecision::Reject(format!("Tag '{}' requires select-before-operate", cmd.tag));
+            }
+            None => {}
+        }
+
+        if let Err(reason) = self.check_rate(&cmd.tag, &policies) {
+            return PolicyDecision::Reject(reason);
+        }
+        PolicyDecision::Allow
+    }
+
+    fn check_limits(&self, cmd: &CmdFrame, policies: &[&CompiledPolicy]) -> Result<(), String> {
+        let numeric = cmd.value.as_ref().and_then(Value::to_f64);
+        for compiled in policies {
+            let policy = &compiled.policy;
+            if policy.min.is_none() && policy.max.is_none() && policy.max_step.is_none() {
+                continue;
+            }
+            let Some(value) = numeric else {
+                return Err(format!("Non-numeric value for limited tag '{}'", cmd.tag));
+            };
+
+            if let Some(min) = policy.min.filter(|min| value < *min) {
+                return Err(format!("Value {} below minimum {}", value, min));
+            }
+            if let Some(max) = policy.max.filter(|max| value > *max) {
+                return Err(format!("Value {} above maximum {}", value, max));
+            }
+            if let Some(max_step) = policy.max_step {
+                // Without a known current value the step cannot be bounded
+                let current = self.values.read().unwrap().get(&cmd.tag).and_then(Value::to_f64)
+                    .ok_or_else(|| format!("No current value for '{}' to check step limit", cmd.tag))?;
+                if (value - current).abs() > max_step {
+                    return Err(format!("Step from {} to {} exceeds limit {}", current, value, max_step));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_interlocks(&self, policies: &[&CompiledPolicy]) -> Result<(), String> {
+        let values = self.values.read().unwrap();
+        for interlock in policies.iter().flat_map(|p| &p.interlocks) {
+            match values.get(&interlock.tag) {
+                Some(current) if interlock.holds(current) => {}
+                Some(_) => return Err(format!("Interlock '{}' not satisfied", interlock)),
+                // Unknown state never satisfies an interlock
+                None => return Err(format!("Interlock '{}' has no value for '{}'", interlock, interlock.tag)),
+            }
+        }
+        Ok(())
+    }
+
+    fn check_rate(&self, tag: &str, policies: &[&CompiledPolicy]) -> Result<(), String> {
+        let limits: Vec<RateLimit> = policies.iter().filter_map(|p| p.policy.rate_limit).collect();
+        if limits.is_empty() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut writes = self.writes.lock().unwrap();
+        let history = writes.entry(tag.to_string()).or_default();
+
+        let longest = limits.iter().map(|l| Duration::from_millis(l.window_ms)).max().unwrap_or_default();
+        while history.front().is_some_and(|t| now.duration_since(*t) >= longest) {
+            history.pop_front();
+        }
+        for limit in &limits {
+            let window = Duration::from_millis(limit.window_ms);
+            let recent = history.iter().filter(|t| now.duration_since(**t) < window).count();
+            if recent >= limit.max_writes as usize {
+                return Err(format!(
+                    "Write rate limit of {} per {}ms exceeded for '{}'",
+                    limit.max_writes, limit.window_ms, tag
+                ));
+            }
+        }
+        history.push_back(now);
+        Ok(())
+    }
+
+    fn arm(&self, cmd: &CmdFrame) {
+        let selection = Selection {
+            origin: cmd.origin.clone(),
+            value: cmd.value.clone(),
+            expires_at: Instant::now() + Duration::from_millis(self.config.sbo_window_ms),
+        };
+        self.selections.lock().unwrap().insert(cmd.tag.clone(), selection);
+    }
+
+    fn take_selection(&self, cmd: &CmdFrame) -> Result<(), String> {
+        let mut selections = self.selections.lock().unwrap();
+        let selection = selections.get(&cmd.tag)
+            .ok_or_else(|| format!("No active selection for '{}'", cmd.tag))?;
+        if selection.origin != cmd.origin {
+            return Err(format!("Tag '{}' is selected by '{}'", cmd.tag, selection.origin));
+        }
+
+        // The selection is consumed by any operate attempt from its owner
+        let selection = selections.remove(&cmd.tag).expect("selection checked above");
+        if Instant::now() > selection.expires_at {
+            return Err(format!("Selection for '{}' expired", cmd.tag));
+        }
+        if selection.value != cmd.value {
+            return Err(format!("Operate value for '{}' does not match selection", cmd.tag));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(policies: Vec<TagPolicy>) -> CommandPolicy {
+        CommandPolicy::new(PolicyConfig { policies, ..Default::default() }).unwrap()
+    }
+
+    fn cmd(tag: &str, value: Value) -> CmdFrame {
+        CmdFrame::new(tag, value, "hmi")
+    }
+
+    fn is_reject(decision: &PolicyDecision, needle: &str) -> bool {
+        matches!(decision, PolicyDecision::Reject(reason) if reason.contains(needle))
+    }
+
+    #[test]
+    fn test_interlock_parse_and_eval() {
+        let interlock = Interlock::parse("plant1.pump.running == false").unwrap();
+        assert_eq!(interlock.tag, "plant1.pump.running");
+        assert!(interlock.holds(&Value::bool(false)));
+        assert!(!interlock.holds(&Value::bool(true)));
+        assert_eq!(interlock.to_string(), "plant1.pump.running == false");
+
+        let level = Interlock::parse("tank.level<=80.5").unwrap();
+        assert!(level.holds(&Value::int(80)));
+        assert!(!level.holds(&Value::float(81.0)));
+
+        let mode = Interlock::parse("line.mode != \"manual\"").unwrap();
+        assert!(mode.holds(&Value::string("auto")));
+        assert!(!mode.holds(&Value::string("manual")));
+
+        assert!(Interlock::parse("tank.level ~ 3").is_err());
+        assert!(Interlock::parse("tank.level == maybe").is_err());
+    }
+
+    #[test]
+    fn test_limits_and_step() {
+        let policy = policy(vec![TagPolicy::new("plant1.+.speed").with_range(0.0, 1500.0).with_max_step(200.0)]);
+
+        assert!(is_reject(&policy.evaluate(&cmd("plant1.pump.speed", Value::int(100))), "No current value"));
+        policy.update_value("plant1.pump.speed", Value::int(0));
+        assert_eq!(policy.evaluate(&cmd("plant1.pump.speed", Value::int(100))), PolicyDecision::Allow);
+        assert!(is_reject(&policy.evaluate(&cmd("plant1.pump.speed", Value::int(1600))), "above maximum"));
+        assert!(is_reject(&policy.evaluate(&cmd("plant1.pump.speed", Value::int(-1))), "below minimum"));
+        assert!(is_reject(&policy.evaluate(&cmd("plant1.pump.speed", Value::string("fast"))), "Non-numeric"));
+
+        policy.update_value("plant1.pump.speed", Value::int(1000));
+        assert!(is_reject(&policy.evaluate(&cmd("plant1.pump.speed", Value::int(1300))), "exceeds limit"));
+        assert_eq!(policy.evaluate(&cmd("plant1.pump.speed", Value::int(1150))), PolicyDecision::Allow);
+
+        // Unrelated tags are unconstrained
+        assert_eq!(policy.evaluate(&cmd("plant2.valve", Value::int(9999))), PolicyDecision::Allow);
+    }
+
+    #[test]
+    fn test_partial_config_uses_defaults() {
+        let config: PolicyConfig = serde_json::from_str(r#"{"policies": [{"pattern": "plant1.#", "max": 10.0}]}"#).unwrap();
+        assert_eq!(config.policies[0].max, Some(10.0));
+        assert_eq!(config.sbo_window_ms, PolicyConfig::default().sbo_window_ms);
+        assert!(config.sbo_for_emergency);
+    }
+
+    #[test]
+    fn test_interlock_blocks_write() {
+        let policy = policy(vec![TagPolicy::new("plant1.valve.open").with_interlock("plant1.pump.running == false")]);
+
+        assert!(is_reject(&policy.evaluate(&cmd("plant1.valve.open", Value::bool(true))), "has no value"));
+        policy.observe(&DataFrame::new("plant1.pump.running", Value::bool(true)));
+        assert!(is_reject(&policy.evaluate(&cmd("plant1.valve.open", Value::bool(true))), "not satisfied"));
+        policy.observe(&DataFrame::new("plant1.pump.running", Value::bool(false)));
+        assert_eq!(policy.evaluate(&cmd("plant1.valve.open", Value::bool(true))), PolicyDecision::Allow);
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let policy = policy(vec![TagPolicy::new("plant1.#").with_rate_limit(2, Duration::from_millis(100))]);
+
+        assert_eq!(policy.evaluate(&cmd("plant1.valve", Value::int(1))), PolicyDecision::Allow);
+        assert_eq!(policy.evaluate(&cmd("plant1.valve", Value::int(2))), PolicyDecision::Allow);
+        assert!(is_reject(&policy.evaluate(&cmd("plant1.valve", Value::int(3))), "rate limit"));
+        // Limits are per tag
+        assert_eq!(policy.evaluate(&cmd("plant1.pump", Value::int(1))), PolicyDecision::Allow);
+
+        std::thread::sleep(Duration::from_millis(120));
+        assert_eq!(policy.evaluate(&cmd("plant1.valve", Value::int(4))), PolicyDecision::Allow);
+    }
+
+    #[test]
+    fn test_select_before_operate() {
+        let policy = CommandPolicy::new(PolicyConfig { sbo_window_ms: 50, ..Default::default() }).unwrap();
+        let emergency = |step: Option<&str>, value: i64| {
+            let frame = cmd("plant1.breaker", Value::int(value)).with_priority(CommandPriority::Emergency as i32);
+            match step {
+                Some(step) => frame.with_meta(SBO_META_KEY, step),
+                None => frame,
+            }
+        };
+
+        assert!(is_reject(&policy.evaluate(&emergency(None, 1)), "requires select-before-operate"));
+        assert!(is_reject(&policy.evaluate(&emergency(Some(SBO_OPERATE), 1)), "No active selection"));
+
+        assert_eq!(policy.evaluate(&emergency(Some(SBO_SELECT), 1)), PolicyDecision::Armed);
+        let other = emergency(Some(SBO_OPERATE), 1);
+        let other = CmdFrame { origin: "scada".to_string(), ..other };
+        assert!(is_reject(&policy.evaluate(&other), "selected by 'hmi'"));
+        assert_eq!(policy.evaluate(&emergency(Some(SBO_OPERATE), 1)), PolicyDecision::Allow);
+        // Selection is single-use
+        assert!(is_reject(&policy.evaluate(&emergency(Some(SBO_OPERATE), 1)), "No active selection"));
+
+        assert_eq!(policy.evaluate(&emergency(Some(SBO_SELECT), 1)), PolicyDecision::Armed);
+        assert!(is_reject(&policy.evaluate(&emergency(Some(SBO_OPERATE), 0)), "does not match"));
+
+        assert_eq!(policy.evaluate(&emergency(Some(SBO_SELECT), 1)), PolicyDecision::Armed);
+        std::thread::sleep(Duration::from_millis(70));
+        assert!(is_reject(&policy.evaluate(&emergency(Some(SBO_OPERATE), 1)), "expired"));
+
+        // Selecting a tag that does not use select-before-operate never arms it
+        let select = cmd("plant1.valve", Value::int(1)).with_meta(SBO_META_KEY, SBO_SELECT);
+        assert!(is_reject(&policy.evaluate(&select), "does not use select-before-operate"));
+    }
+
+    #[test]
+    fn test_audit_trail() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink_records = records.clone();
+        let policy = CommandPolicy::new(PolicyConfig {
+            policies: vec![TagPolicy::new("plant1.#").with_range(0.0, 10.0)],
+            audit_capacity: 2,
+            ..Default::default()
+        })
+        .unwrap()
+        .with_audit_sink(Arc::new(move |record: &AuditRecord| {
+            sink_records.lock().unwrap().push(record.outcome.clone());
+        }));
+
+        let ok = cmd("plant1.valve", Value::int(5)).with_cmd_id(1);
+        policy.evaluate(&ok);
+        policy.evaluate(&cmd("plant1.valve", Value::int(50)).with_cmd_id(2));
+        policy.record_ack(&CmdAckFrame::success(1, "plant1.valve", "plc1", Some(Value::int(5))));
+
+        let outcomes = records.lock().unwrap().clone();
+        assert_eq!(outcomes.len(), 3);
+        assert_eq!(outcomes[0], AuditOutcome::Accepted);
+        assert!(matches!(&outcomes[1], AuditOutcome::Rejected(reason) if reason.contains("above maximum")));
+        assert_eq!(outcomes[2], AuditOutcome::Succeeded);
+
+        // In-memory trail is bounded
+        let log = policy.audit_log();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[1].source, "plc1");
+        assert_eq!(log[1].value.as_deref(), Some("5"));
+    }
+}
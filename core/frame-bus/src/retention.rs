@@ -0,0 +1,909 @@
+//! WAL保留策略：磁盘配额、时间保留与按优先级卸载
+//!
+//! 上行链路长时间中断时WAL持续增长，保留策略在GC周期内按以下顺序回收空间：
+//! 1. 超过`max_age`的帧直接过期；
+//! 2. 超过配额水位线时，先删除已被最慢游标确认的帧，再对低价值帧 (`qos=0`或高频点位) 抽稀，
+//!    随后依次删除剩余低价值帧与普通数据帧；
+//! 3. 仍超过硬配额时才删除告警帧与命令帧。
+//!
+//! 删除尚未回放的帧 (序列号大于最慢游标) 之前先通过告警钩子上报，由`monitoring`转为系统告警。
+//!
+//! [`RecordIndex`]按序列号分段汇总帧数、字节数与最旧时间戳，在写入与删除时增量维护，
+//! 只在首次执行时全量扫描一次；总字节数与最旧时间戳均在配额与时间保留之内时不生成计划。
+//! 生成计划时按序列号顺序分轮扫描WAL，逐帧摘要不常驻内存。写入路径只解码封装字段，
+//! 等级与时间戳均取自封装，不解码payload。
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+use prost::Message;
+use tracing::warn;
+
+use crate::envelope::{CmdAckFrame, CmdFrame, DataFrame, FrameEnvelope, FrameKind};
+
+/// 标记告警数据帧的元数据键
+pub const ALARM_META_KEY: &str = "alarm";
+
+/// 保留策略配置
+#[derive(Debug, Clone)]
+pub struct RetentionConfig {
+    /// 帧记录字节硬配额 (按记录逻辑大小计)，None表示不限
+    pub max_bytes: Option<u64>,
+    /// 帧最长保留时间，None表示不限
+    pub max_age: Option<Duration>,
+    /// 开始卸载的配额比例，低价值与普通帧卸载至该水位以下
+    pub shed_watermark: f64,
+    /// 抽稀时每个点位每N帧保留1帧
+    pub decimation_factor: u64,
+    /// 点位在WAL中的平均频率超过该值 (帧/秒) 视为高频点位
+    pub high_rate_hz: f64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: None,
+            max_age: None,
+            shed_watermark: 0.9,
+            decimation_factor: 4,
+            high_rate_hz: 10.0,
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// 是否启用了配额或时间保留
+    pub fn is_enabled(&self) -> bool {
+        self.max_bytes.is_some() || self.max_age.is_some()
+    }
+}
+
+/// 帧的卸载等级，按卸载先后排序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ShedClass {
+    /// `qos=0`或高频点位的数据帧
+    LowValue,
+    /// 普通数据帧
+    Normal,
+    /// 告警帧、命令帧与命令确认帧
+    Protected,
+}
+
+/// 帧被删除的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShedReason {
+    /// 超过最长保留时间
+    Expired,
+    /// 已被全部消费者确认，配额不足时提前回收
+    Acked,
+    /// 低价值帧抽稀
+    Decimated,
+    /// 配额不足按等级删除
+    Quota(ShedClass),
+}
+
+impl fmt::Display for ShedReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShedReason::Expired => write!(f, "expired"),
+            ShedReason::Acked => write!(f, "acked"),
+            ShedReason::Decimated => write!(f, "decimated"),
+            ShedReason::Quota(ShedClass::LowValue) => write!(f, "quota:low-value"),
+            ShedReason::Quota(ShedClass::Normal) => write!(f, "quota:normal"),
+            ShedReason::Quota(ShedClass::Protected) => write!(f, "quota:protected"),
+        }
+    }
+}
+
+/// 参与保留决策的帧记录摘要，扫描WAL时逐帧生成
+#[derive(Debug, Clone)]
+pub struct RecordInfo {
+    pub seq: u64,
+    /// 记录在存储中的字节数 (键+值)
+    pub bytes: u64,
+    /// 帧时间戳 (ns)
+    pub timestamp_ns: u64,
+    pub tag: String,
+    /// 按帧内容确定的等级，高频点位在决策时再降为低价值
+    pub class: ShedClass,
+}
+
+/// 帧封装中参与保留决策的字段，解码时跳过payload
+#[derive(Clone, PartialEq, Message)]
+struct EnvelopeHeader {
+    #[prost(uint64, tag = "1")]
+    seq: u64,
+    #[prost(int32, tag = "2")]
+    kind: i32,
+    #[prost(string, tag = "4")]
+    tag: String,
+    #[prost(uint64, tag = "5")]
+    timestamp: u64,
+    #[prost(uint32, tag = "6")]
+    qos: u32,
+    #[prost(bool, tag = "7")]
+    alarm: bool,
+}
+
+/// 写入路径只需要时间戳，连点位也不解码
+#[derive(Clone, PartialEq, Message)]
+struct EnvelopeTimestamp {
+    #[prost(uint64, tag = "5")]
+    timestamp: u64,
+}
+
+fn classify(kind: FrameKind, qos: u32, alarm: bool) -> ShedClass {
+    match kind {
+        FrameKind::Data if alarm => ShedClass::Protected,
+        FrameKind::Data if qos == 0 => ShedClass::LowValue,
+        FrameKind::Data => ShedClass::Normal,
+        FrameKind::Cmd | FrameKind::CmdAck => ShedClass::Protected,
+    }
+}
+
+impl RecordInfo {
+    /// 从编码后的帧封装提取摘要，无法解码时返回None
+    pub fn from_payload(bytes: u64, payload: &[u8]) -> Option<Self> {
+        let header = EnvelopeHeader::decode(payload).ok()?;
+        if header.timestamp == 0 {
+            return Self::from_envelope(bytes, &FrameEnvelope::decode(payload).ok()?);
+        }
+        Some(Self {
+            seq: header.seq,
+            bytes,
+            timestamp_ns: header.timestamp,
+            class: classify(FrameKind::from(header.kind), header.qos, header.alarm),
+            tag: header.tag,
+        })
+    }
+
+    /// 从帧封装提取摘要；旧版本封装没有时间戳，回退解码payload，无法解码时返回None
+    pub fn from_envelope(bytes: u64, envelope: &FrameEnvelope) -> Option<Self> {
+        if envelope.timestamp != 0 {
+            return Some(Self {
+                seq: envelope.seq,
+                bytes,
+                timestamp_ns: envelope.timestamp,
+                tag: envelope.tag.clone(),
+                class: classify(envelope.kind(), envelope.qos, envelope.alarm),
+            });
+        }
+        let (timestamp_ns, class) = match envelope.kind() {
+            FrameKind::Data => {
+                let frame = DataFrame::decode(&envelope.payload[..]).ok()?;
+                (frame.timestamp, classify(FrameKind::Data, frame.qos, frame.meta.contains_key(ALARM_META_KEY)))
+            }
+            FrameKind::Cmd => (CmdFrame::decode(&envelope.payload[..]).ok()?.timestamp, ShedClass::Protected),
+            FrameKind::CmdAck => (CmdAckFrame::decode(&envelope.payload[..]).ok()?.timestamp, ShedClass::Protected),
+        };
+        Some(Self {
+            seq: envelope.seq,
+            bytes,
+            timestamp_ns,
+            tag: envelope.tag().into_owned(),
+            class,
+        })
+    }
+}
+
+/// 落库帧的序列号、大小与时间戳，记录索引只需要这些字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecordStamp {
+    pub seq: u64,
+    pub bytes: u64,
+    pub timestamp_ns: u64,
+}
+
+impl RecordStamp {
+    /// 从编码后的帧封装提取，不解码payload；旧版本封装没有时间戳时按写入时刻计
+    pub fn from_payload(seq: u64, bytes: u64, payload: &[u8]) -> Option<Self> {
+        let timestamp_ns = match EnvelopeTimestamp::decode(payload).ok()?.timestamp {
+            0 => crate::envelope::current_timestamp_ns(),
+            timestamp => timestamp,
+        };
+        Some(Self { seq, bytes, timestamp_ns })
+    }
+}
+
+impl From<&RecordInfo> for RecordStamp {
+    fn from(info: &RecordInfo) -> Self {
+        Self { seq: info.seq, bytes: info.bytes, timestamp_ns: info.timestamp_ns }
+    }
+}
+
+/// 一次保留决策的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    /// 决策前帧记录总字节数
+    pub bytes_before: u64,
+    /// 删除后帧记录总字节数
+    pub bytes_after: u64,
+    pub expired: u64,
+    pub acked: u64,
+    pub decimated: u64,
+    pub shed_low_value: u64,
+    pub shed_normal: u64,
+    pub shed_protected: u64,
+    /// 其中尚未被最慢游标确认的帧数
+    pub unreplayed: u64,
+}
+
+impl RetentionReport {
+    /// 删除的帧总数
+    pub fn deleted(&self) -> u64 {
+        self.expired + self.acked + self.decimated + self.shed_low_value + self.shed_normal + self.shed_protected
+    }
+
+    fn count(&mut self, reason: ShedReason) {
+        match reason {
+            ShedReason::Expired => self.expired += 1,
+            ShedReason::Acked => self.acked += 1,
+            ShedReason::Decimated => self.decimated += 1,
+            ShedReason::Quota(ShedClass::LowValue) => self.shed_low_value += 1,
+            ShedReason::Quota(ShedClass::Normal) => self.shed_normal += 1,
+            ShedReason::Quota(ShedClass::Protected) => self.shed_protected += 1,
+        }
+    }
+}
+
+/// 保留计划：待删除的帧及原因
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPlan {
+    pub deletions: Vec<(u64, ShedReason)>,
+    pub report: RetentionReport,
+    /// 待删除帧的大小与时间戳，删除后从记录索引中扣除
+    pub(crate) removed: Vec<RecordStamp>,
+}
+
+impl RetentionPlan {
+    /// 不删除任何帧的计划
+    pub fn unchanged(bytes: u64) -> Self {
+        Self {
+            report: RetentionReport { bytes_before: bytes, bytes_after: bytes, ..Default::default() },
+            ..Default::default()
+        }
+    }
+
+    /// 计划删除的未回放帧的序列号范围
+    pub fn unreplayed_range(&self, min_ack: Option<u64>) -> Option<(u64, u64)> {
+        let mut seqs = self.deletions.iter()
+            .map(|(seq, _)| *seq)
+            .filter(|seq| is_unreplayed(*seq, min_ack));
+        let first = seqs.next()?;
+        Some(seqs.fold((first, first), |(lo, hi), seq| (lo.min(seq), hi.max(seq))))
+    }
+}
+
+/// 无持久游标时没有待回放的帧
+fn is_unreplayed(seq: u64, min_ack: Option<u64>) -> bool {
+    min_ack.is_some_and(|ack| seq > ack)
+}
+
+/// 按配置为按序列号排序的记录生成删除计划
+pub fn plan(records: &[RecordInfo], config: &RetentionConfig, min_ack: Option<u64>, now_ns: u64) -> RetentionPlan {
+    let scan = |visit: &mut dyn FnMut(&RecordInfo) -> bool| {
+        for record in records {
+            if !visit(record) {
+                break;
+            }
+        }
+        Ok(())
+    };
+    plan_with(scan, config, min_ack, now_ns).expect("scanning a record slice cannot fail")
+}
+
+/// 按配置生成删除计划，`scan`按序列号顺序遍历帧记录，回调返回false时提前结束
+///
+/// 每轮删除各扫描一次，内存只随点位数与计划删除的帧数增长
+pub fn plan_with<S>(mut scan: S, config: &RetentionConfig, min_ack: Option<u64>, now_ns: u64) -> anyhow::Result<RetentionPlan>
+where
+    S: FnMut(&mut dyn FnMut(&RecordInfo) -> bool) -> anyhow::Result<()>,
+{
+    let cutoff = config.max_age.map(|max_age| now_ns.saturating_sub(max_age.as_nanos() as u64));
+    let mut planner = Planner { min_ack, ..Default::default() };
+    let mut spans: HashMap<String, TagSpan> = HashMap::new();
+    let mut bytes_before = 0;
+
+    // 首轮：统计总字节数与普通数据点位的频率，同时删除过期帧
+    scan(&mut |record| {
+        bytes_before += record.bytes;
+        planner.total += record.bytes;
+        if record.class == ShedClass::Normal {
+            match spans.get_mut(record.tag.as_str()) {
+                Some(span) => span.add(record.timestamp_ns),
+                None => {
+                    spans.insert(record.tag.clone(), TagSpan::new(record.timestamp_ns));
+                }
+            }
+        }
+        if cutoff.is_some_and(|cutoff| record.timestamp_ns < cutoff) {
+            planner.remove(record, ShedReason::Expired);
+        }
+        true
+    })?;
+    planner.plan.report.bytes_before = bytes_before;
+
+    let high_rate: HashSet<String> = spans.into_iter()
+        .filter(|(_, span)| span.rate_hz() > config.high_rate_hz)
+        .map(|(tag, _)| tag)
+        .collect();
+    let class_of = |record: &RecordInfo| match record.class {
+        ShedClass::Normal if high_rate.contains(record.tag.as_str()) => ShedClass::LowValue,
+        class => class,
+    };
+
+    if let Some(budget) = config.max_bytes {
+        let target = (budget as f64 * config.shed_watermark.clamp(0.0, 1.0)) as u64;
+
+        // 已确认帧
+        if let Some(ack) = min_ack.filter(|_| planner.total > target) {
+            scan(&mut |record| {
+                if record.seq > ack {
+                    return false;
+                }
+                if planner.is_live(record.seq) {
+                    planner.remove(record, ShedReason::Acked);
+                }
+                planner.total > target
+            })?;
+        }
+
+        // 低价值帧抽稀：每个点位按时间先后每N帧保留1帧
+        if planner.total > target {
+            let factor = config.decimation_factor.max(1);
+            let mut per_tag: HashMap<String, u64> = HashMap::new();
+            scan(&mut |record| {
+                if !planner.is_live(record.seq) || class_of(record) != ShedClass::LowValue {
+                    return true;
+                }
+                let n = match per_tag.get_mut(record.tag.as_str()) {
+                    Some(n) => n,
+                    None => per_tag.entry(record.tag.clone()).or_default(),
+                };
+                if !n.is_multiple_of(factor) {
+                    planner.remove(record, ShedReason::Decimated);
+                }
+                *n += 1;
+                planner.total > target
+            })?;
+        }
+
+        // 按等级由旧到新删除，受保护帧只在超过硬配额时删除
+        for (class, limit) in [
+            (ShedClass::LowValue, target),
+            (ShedClass::Normal, target),
+            (ShedClass::Protected, budget),
+        ] {
+            if planner.total <= limit {
+                continue;
+            }
+            scan(&mut |record| {
+                if planner.is_live(record.seq) && class_of(record) == class {
+                    planner.remove(record, ShedReason::Quota(class));
+                }
+                planner.total > limit
+            })?;
+        }
+    }
+
+    planner.plan.report.bytes_after = planner.total;
+    Ok(planner.plan)
+}
+
+/// 生成计划过程中的剩余字节数与已删除的帧
+#[derive(Default)]
+struct Planner {
+    plan: RetentionPlan,
+    total: u64,
+    deleted: HashSet<u64>,
+    min_ack: Option<u64>,
+}
+
+impl Planner {
+    fn is_live(&self, seq: u64) -> bool {
+        !self.deleted.contains(&seq)
+    }
+
+    fn remove(&mut self, record: &RecordInfo, reason: ShedReason) {
+        self.deleted.insert(record.seq);
+        self.total -= record.bytes;
+        self.plan.deletions.push((record.seq, reason));
+        self.plan.removed.push(record.into());
+        self.plan.report.count(reason);
+        if is_unreplayed(record.seq, self.min_ack) {
+            self.plan.report.unreplayed += 1;
+        }
+    }
+}
+
+/// 点位在WAL中的帧数与时间跨度
+struct TagSpan {
+    first: u64,
+    last: u64,
+    count: u64,
+}
+
+impl TagSpan {
+    fn new(timestamp_ns: u64) -> Self {
+        Self { first: timestamp_ns, last: timestamp_ns, count: 1 }
+    }
+
+    fn add(&mut self, timestamp_ns: u64) {
+        self.first = self.first.min(timestamp_ns);
+        self.last = self.last.max(timestamp_ns);
+        self.count += 1;
+    }
+
+    fn rate_hz(&self) -> f64 {
+        // 不足1秒的跨度按1秒计，避免少量帧被误判为高频
+        let secs = ((self.last - self.first) as f64 / 1e9).max(1.0);
+        self.count as f64 / secs
+    }
+}
+
+/// 记录索引每段覆盖的序列号数
+const SEGMENT_FRAMES: u64 = 1024;
+
+/// 记录索引中一段序列号的汇总
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SegmentSummary {
+    pub frames: u64,
+    pub bytes: u64,
+    /// 段内最旧的帧时间戳 (ns)
+    pub oldest_ns: u64,
+}
+
+impl SegmentSummary {
+    pub fn add(&mut self, stamp: RecordStamp) {
+        self.oldest_ns = if self.frames == 0 { stamp.timestamp_ns } else { self.oldest_ns.min(stamp.timestamp_ns) };
+        self.frames += 1;
+        self.bytes += stamp.bytes;
+    }
+}
+
+/// 帧记录汇总索引，按序列号分段维护帧数、字节数与最旧时间戳
+///
+/// 不保存逐帧摘要，内存随段数增长；段内最旧的帧被删除后由调用方重新扫描该段
+#[derive(Debug, Default)]
+pub struct RecordIndex {
+    segments: BTreeMap<u64, SegmentSummary>,
+    frames: u64,
+    total_bytes: u64,
+}
+
+impl RecordIndex {
+    /// `seq`所在段覆盖的序列号范围
+    pub fn segment_range(seq: u64) -> Range<u64> {
+        let start = seq - seq % SEGMENT_FRAMES;
+        start..start.saturating_add(SEGMENT_FRAMES)
+    }
+
+    /// 加入一条记录
+    pub fn insert(&mut self, stamp: RecordStamp) {
+        self.segments.entry(stamp.seq / SEGMENT_FRAMES).or_default().add(stamp);
+        self.frames += 1;
+        self.total_bytes += stamp.bytes;
+    }
+
+    /// 记录重写后调整大小
+    pub fn resize(&mut self, seq: u64, old_bytes: u64, new_bytes: u64) {
+        if let Some(segment) = self.segments.get_mut(&(seq / SEGMENT_FRAMES)) {
+            segment.bytes = segment.bytes.saturating_sub(old_bytes) + new_bytes;
+            self.total_bytes = self.total_bytes.saturating_sub(old_bytes) + new_bytes;
+        }
+    }
+
+    /// 移除一条记录，返回段内最旧时间戳是否需要重新扫描
+    pub fn remove(&mut self, stamp: RecordStamp) -> bool {
+        let key = stamp.seq / SEGMENT_FRAMES;
+        let Some(segment) = self.segments.get_mut(&key) else {
+            return false;
+        };
+        segment.frames = segment.frames.saturating_sub(1);
+        segment.bytes = segment.bytes.saturating_sub(stamp.bytes);
+        self.frames = self.frames.saturating_sub(1);
+        self.total_bytes = self.total_bytes.saturating_sub(stamp.bytes);
+        if segment.frames == 0 {
+            self.segments.remove(&key);
+            return false;
+        }
+        stamp.timestamp_ns <= segment.oldest_ns
+    }
+
+    /// 移除序列号小于`seq`的段，返回跨越`seq`、需要重新扫描的剩余范围
+    pub fn remove_below(&mut self, seq: u64) -> Option<Range<u64>> {
+        let kept = self.segments.split_off(&(seq / SEGMENT_FRAMES));
+        for old in std::mem::replace(&mut self.segments, kept).into_values() {
+            self.frames -= old.frames;
+            self.total_bytes -= old.bytes;
+        }
+        let range = Self::segment_range(seq);
+        (seq > range.start && self.segments.contains_key(&(seq / SEGMENT_FRAMES))).then_some(seq..range.end)
+    }
+
+    /// 以重新扫描的结果替换`seq`所在段的汇总
+    pub fn replace_segment(&mut self, seq: u64, summary: SegmentSummary) {
+        let key = seq / SEGMENT_FRAMES;
+        if let Some(old) = self.segments.remove(&key) {
+            self.frames -= old.frames;
+            self.total_bytes -= old.bytes;
+        }
+        if summary.frames > 0 {
+            self.frames += summary.frames;
+            self.total_bytes += summary.bytes;
+            self.segments.insert(key, summary);
+        }
+    }
+
+    /// 以重新扫描的最旧时间戳更新`seq`所在段，帧数与字节数仍按增量维护
+    pub fn refresh_oldest(&mut self, seq: u64, oldest_ns: Option<u64>) {
+        if let (Some(segment), Some(oldest_ns)) = (self.segments.get_mut(&(seq / SEGMENT_FRAMES)), oldest_ns) {
+            segment.oldest_ns = oldest_ns;
+        }
+    }
+
+    /// 帧数
+    pub fn len(&self) -> usize {
+        self.frames as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames == 0
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// 最旧的帧时间戳 (ns)
+    pub fn oldest_timestamp(&self) -> Option<u64> {
+        self.segments.values().map(|segment| segment.oldest_ns).min()
+    }
+
+    /// 是否超过卸载水位或存在过期帧，否则无需生成计划
+    pub fn needs_plan(&self, config: &RetentionConfig, now_ns: u64) -> bool {
+        let over_quota = config.max_bytes.is_some_and(|budget| {
+            self.total_bytes > (budget as f64 * config.shed_watermark.clamp(0.0, 1.0)) as u64
+        });
+        let expired = config.max_age.is_some_and(|max_age| {
+            let cutoff = now_ns.saturating_sub(max_age.as_nanos() as u64);
+            self.oldest_timestamp().is_some_and(|oldest| oldest < cutoff)
+        });
+        over_quota || expired
+    }
+}
+
+/// 删除未回放数据前发出的告警
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionAlert {
+    /// 将被删除的未回放帧数
+    pub frames: u64,
+    /// 未回放帧的序列号范围
+    pub first_seq: u64,
+    pub last_seq: u64,
+    /// 最慢持久游标
+    pub min_ack: Option<u64>,
+    /// 本次决策的完整结果
+    pub report: RetentionReport,
+}
+
+impl fmt::Display for RetentionAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WAL retention will delete {} unreplayed frames (seq {}..={}, slowest cursor {:?}): \
+                   expired={}, decimated={}, low-value={}, normal={}, protected={}, bytes {} -> {}",
+               self.frames, self.first_seq, self.last_seq, self.min_ack,
+               self.report.expired, self.report.decimated, self.report.shed_low_value,
+               self.report.shed_normal, self.report.shed_protected,
+               self.report.bytes_before, self.report.bytes_after)
+    }
+}
+
+/// 告警钩子，frame-bus不依赖`monitoring`，由上层注入
+pub type RetentionAlertSink = Arc<dyn Fn(&RetentionAlert) + Send + Sync>;
+
+/// 保留策略累计统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionStats {
+    /// 执行删除的决策次数
+    pub runs: u64,
+    pub expired_frames: u64,
+    pub acked_frames: u64,
+    pub decimated_frames: u64,
+    pub shed_low_value_frames: u64,
+    pub shed_normal_frames: u64,
+    pub shed_protected_frames: u64,
+    pub shed_bytes: u64,
+    pub unreplayed_dropped: u64,
+    pub alerts_raised: u64,
+    /// 最近一次删除帧的决策
+    pub last_report: Option<RetentionReport>,
+}
+
+impl RetentionStats {
+    fn record(&mut self, report: &RetentionReport) {
+        self.runs += 1;
+        self.expired_frames += report.expired;
+        self.acked_frames += report.acked;
+        self.decimated_frames += report.decimated;
+        self.shed_low_value_frames += report.shed_low_value;
+        self.shed_normal_frames += report.shed_normal;
+        self.shed_protected_frames += report.shed_protected;
+        self.shed_bytes += report.bytes_before - report.bytes_after;
+        self.unreplayed_dropped += report.unreplayed;
+        self.last_report = Some(report.clone());
+    }
+}
+
+/// WAL管理器与后台GC任务共享的保留状态
+#[derive(Default)]
+pub(crate) struct RetentionState {
+    stats: Mutex<RetentionStats>,
+    alert_sink: RwLock<Option<RetentionAlertSink>>,
+    /// 帧记录索引，首次执行保留策略时加载，此前的写入由加载扫描覆盖
+    index: Mutex<Option<RecordIndex>>,
+}
+
+impl RetentionState {
+    /// 记录已落库的帧
+    pub(crate) fn track(&self, stamps: impl IntoIterator<Item = RecordStamp>) {
+        if let Some(index) = self.index.lock().unwrap().as_mut() {
+            for stamp in stamps {
+                index.insert(stamp);
+            }
+        }
+    }
+
+    /// 记录重写后的大小变化
+    pub(crate) fn resize(&self, sizes: impl IntoIterator<Item = (u64, u64, u64)>) {
+        if let Some(index) = self.index.lock().unwrap().as_mut() {
+            for (seq, old_bytes, new_bytes) in sizes {
+                index.resize(seq, old_bytes, new_bytes);
+            }
+        }
+    }
+
+    /// 记录保留策略删除的帧，最旧帧被删除的段由`rescan`重新扫描最旧时间戳
+    pub(crate) fn forget(
+        &self,
+        removed: &[RecordStamp],
+        mut rescan: impl FnMut(Range<u64>) -> anyhow::Result<SegmentSummary>,
+    ) -> anyhow::Result<()> {
+        let mut guard = self.index.lock().unwrap();
+        let Some(index) = guard.as_mut() else {
+            return Ok(());
+        };
+        let mut stale = HashSet::new();
+        for stamp in removed {
+            if index.remove(*stamp) {
+                stale.insert(RecordIndex::segment_range(stamp.seq));
+            }
+        }
+        for range in stale {
+            let seq = range.start;
+            let summary = rescan(range)?;
+            index.refresh_oldest(seq, (summary.frames > 0).then_some(summary.oldest_ns));
+        }
+        Ok(())
+    }
+
+    /// 记录GC删除了序列号小于`seq`的帧，跨越`seq`的段由`rescan`重新扫描
+    pub(crate) fn forget_below(
+        &self,
+        seq: u64,
+        rescan: impl FnOnce(Range<u64>) -> anyhow::Result<SegmentSummary>,
+    ) -> anyhow::Result<()> {
+        let mut guard = self.index.lock().unwrap();
+        let Some(index) = guard.as_mut() else {
+            return Ok(());
+        };
+        if let Some(range) = index.remove_below(seq) {
+            index.replace_segment(seq, rescan(range)?);
+        }
+        Ok(())
+    }
+
+    /// 读取帧记录索引，索引未加载时先调用`load`全量加载
+    ///
+    /// 加载期间写入的帧在`track`中等待，加载扫描与增量记录不会遗漏
+    pub(crate) fn with_index<R>(
+        &self,
+        load: impl FnOnce() -> anyhow::Result<RecordIndex>,
+        f: impl FnOnce(&RecordIndex) -> R,
+    ) -> anyhow::Result<R> {
+        let mut index = self.index.lock().unwrap();
+        if index.is_none() {
+            *index = Some(load()?);
+        }
+        Ok(f(index.as_ref().unwrap()))
+    }
+
+    pub(crate) fn set_alert_sink(&self, sink: RetentionAlertSink) {
+        *self.alert_sink.write().unwrap() = Some(sink);
+    }
+
+    pub(crate) fn stats(&self) -> RetentionStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// 计划将删除未回放帧时告警，须在执行删除前调用
+    pub(crate) fn alert_if_unreplayed(&self, plan: &RetentionPlan, min_ack: Option<u64>) {
+        let Some((first_seq, last_seq)) = plan.unreplayed_range(min_ack) else {
+            return;
+        };
+        let alert = RetentionAlert {
+            frames: plan.report.unreplayed,
+            first_seq,
+            last_seq,
+            min_ack,
+            report: plan.report.clone(),
+        };
+        warn!("{}", alert);
+        if let Some(sink) = self.alert_sink.read().unwrap().as_ref() {
+            sink(&alert);
+        }
+        self.stats.lock().unwrap().alerts_raised += 1;
+    }
+
+    pub(crate) fn record(&self, report: &RetentionReport) {
+        self.stats.lock().unwrap().record(report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEC: u64 = 1_000_000_000;
+
+    fn record(seq: u64, tag: &str, class: ShedClass, timestamp_ns: u64) -> RecordInfo {
+        RecordInfo { seq, bytes: 100, timestamp_ns, tag: tag.to_string(), class }
+    }
+
+    fn quota(max_bytes: u64) -> RetentionConfig {
+        RetentionConfig { max_bytes: Some(max_bytes), shed_watermark: 1.0, high_rate_hz: 5.0, ..Default::default() }
+    }
+
+    #[test]
+    fn test_classify_envelopes() {
+        let bad = FrameEnvelope::wrap_data(1, DataFrame::new("a", crate::Value::int(1)).with_qos(0)).unwrap();
+        let alarm = FrameEnvelope::wrap_data(2, DataFrame::new("a", crate::Value::int(1)).with_qos(0).with_meta(ALARM_META_KEY, "high")).unwrap();
+        let cmd = FrameEnvelope::wrap_cmd(3, CmdFrame::new("a", crate::Value::int(1), "north")).unwrap();
+        let good = FrameEnvelope::wrap_data(4, DataFrame::new("a", crate::Value::int(1))).unwrap();
+
+        assert_eq!(RecordInfo::from_envelope(10, &bad).unwrap().class, ShedClass::LowValue);
+        assert_eq!(RecordInfo::from_envelope(10, &alarm).unwrap().class, ShedClass::Protected);
+        assert_eq!(RecordInfo::from_envelope(10, &cmd).unwrap().class, ShedClass::Protected);
+        assert_eq!(RecordInfo::from_envelope(10, &good).unwrap().class, ShedClass::Normal);
+    }
+
+    #[test]
+    fn test_max_age_expires_old_frames() {
+        let records = vec![
+            record(1, "a", ShedClass::Protected, 10 * SEC),
+            record(2, "a", ShedClass::Normal, 50 * SEC),
+            record(3, "a", ShedClass::Normal, 95 * SEC),
+        ];
+        let config = RetentionConfig { max_age: Some(Duration::from_secs(30)), ..Default::default() };
+
+        let plan = plan(&records, &config, Some(1), 100 * SEC);
+        assert_eq!(plan.deletions, vec![(1, ShedReason::Expired), (2, ShedReason::Expired)]);
+        assert_eq!(plan.report.unreplayed, 1);
+        assert_eq!(plan.unreplayed_range(Some(1)), Some((2, 2)));
+    }
+
+    #[test]
+    fn test_quota_sheds_low_value_before_protected() {
+        // 高频点位fast：100帧/秒；qos=0点位bad；普通点位slow；告警alarm
+        let mut records = Vec::new();
+        for i in 0..8 {
+            records.push(record(i, "fast", ShedClass::Normal, i * SEC / 100));
+        }
+        records.push(record(8, "bad", ShedClass::LowValue, 0));
+        records.push(record(9, "slow", ShedClass::Normal, 0));
+        records.push(record(10, "alarm", ShedClass::Protected, 0));
+        records.push(record(11, "slow", ShedClass::Normal, 5 * SEC));
+
+        // 12帧共1200字节，配额900字节：只需删除3帧
+        let plan = plan(&records, &quota(900), None, 10 * SEC);
+        assert_eq!(plan.report.bytes_after, 900);
+        assert_eq!(plan.report.decimated, 3);
+        assert!(plan.deletions.iter().all(|(seq, _)| *seq < 8));
+        // 每个点位首帧保留
+        assert!(!plan.deletions.iter().any(|(seq, _)| *seq == 0));
+
+        // 配额200字节：低价值帧删尽后删除普通帧，告警帧仍保留
+        let plan = super::plan(&records, &quota(200), None, 10 * SEC);
+        assert_eq!(plan.report.shed_protected, 0);
+        assert!(!plan.deletions.iter().any(|(seq, _)| *seq == 10));
+        assert_eq!(plan.report.shed_normal, 1);
+    }
+
+    #[test]
+    fn test_protected_frames_only_shed_over_hard_budget() {
+        let records = vec![
+            record(1, "cmd", ShedClass::Protected, 0),
+            record(2, "cmd", ShedClass::Protected, 0),
+            record(3, "a", ShedClass::Normal, 0),
+        ];
+        let config = RetentionConfig { max_bytes: Some(150), shed_watermark: 0.5, ..Default::default() };
+
+        let plan = plan(&records, &config, None, 0);
+        // 普通帧卸载至水位线，受保护帧只卸载至硬配额
+        assert_eq!(plan.deletions, vec![(3, ShedReason::Quota(ShedClass::Normal)), (1, ShedReason::Quota(ShedClass::Protected))]);
+        assert_eq!(plan.report.bytes_after, 100);
+    }
+
+    #[test]
+    fn test_acked_frames_reclaimed_first() {
+        let records = vec![
+            record(1, "a", ShedClass::Protected, 0),
+            record(2, "a", ShedClass::Protected, 0),
+            record(3, "b", ShedClass::LowValue, 0),
+        ];
+
+        let plan = plan(&records, &quota(200), Some(2), 0);
+        assert_eq!(plan.deletions, vec![(1, ShedReason::Acked)]);
+        assert_eq!(plan.report.unreplayed, 0);
+        assert_eq!(plan.unreplayed_range(Some(2)), None);
+    }
+
+    #[test]
+    fn test_classify_from_envelope_fields() {
+        let alarm = FrameEnvelope::wrap_data(7, DataFrame::new("a", crate::Value::int(1)).with_meta(ALARM_META_KEY, "high")).unwrap();
+        let info = RecordInfo::from_payload(10, &alarm.encode_to_vec()).unwrap();
+        assert_eq!((info.seq, info.tag.as_str(), info.class), (7, "a", ShedClass::Protected));
+
+        // 旧版本封装没有等级字段，回退解码payload
+        let mut legacy = FrameEnvelope::wrap_data(8, DataFrame::new("b", crate::Value::int(1)).with_qos(0)).unwrap();
+        let timestamp = legacy.timestamp;
+        legacy.timestamp = 0;
+        legacy.qos = 2;
+        legacy.tag.clear();
+        let info = RecordInfo::from_payload(10, &legacy.encode_to_vec()).unwrap();
+        assert_eq!((info.tag.as_str(), info.class, info.timestamp_ns), ("b", ShedClass::LowValue, timestamp));
+
+        let stamp = RecordStamp::from_payload(7, 10, &alarm.encode_to_vec()).unwrap();
+        assert_eq!(stamp.timestamp_ns, alarm.timestamp);
+        assert!(RecordStamp::from_payload(1, 10, b"not an envelope").is_none());
+    }
+
+    #[test]
+    fn test_record_index_tracks_bytes_and_oldest() {
+        let stamp = |seq, timestamp_ns| RecordStamp { seq, bytes: 100, timestamp_ns };
+        let mut index = RecordIndex::default();
+        index.insert(stamp(1, 5 * SEC));
+        index.insert(stamp(2, 2 * SEC));
+        index.insert(stamp(SEGMENT_FRAMES + 3, 9 * SEC));
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.total_bytes(), 300);
+        assert_eq!(index.oldest_timestamp(), Some(2 * SEC));
+
+        // 重写记录时调整大小
+        index.resize(2, 100, 40);
+        assert_eq!(index.total_bytes(), 240);
+
+        let config = RetentionConfig { max_age: Some(Duration::from_secs(5)), ..quota(300) };
+        assert!(index.needs_plan(&config, 8 * SEC));
+
+        // 删除段内最旧的帧后需要重新扫描该段
+        assert!(index.remove(RecordStamp { bytes: 40, ..stamp(2, 2 * SEC) }));
+        index.refresh_oldest(1, Some(5 * SEC));
+        assert_eq!(index.oldest_timestamp(), Some(5 * SEC));
+        assert!(!index.remove(stamp(1, 5 * SEC)));
+        assert_eq!(index.oldest_timestamp(), Some(9 * SEC));
+        assert!(!index.needs_plan(&config, 8 * SEC));
+        assert!(index.needs_plan(&quota(50), 0));
+
+        // GC跨越段时返回需要重新扫描的剩余范围
+        index.insert(stamp(SEGMENT_FRAMES + 10, 9 * SEC));
+        let rest = index.remove_below(SEGMENT_FRAMES + 5).unwrap();
+        assert_eq!(rest, SEGMENT_FRAMES + 5..2 * SEGMENT_FRAMES);
+        index.replace_segment(rest.start, SegmentSummary { frames: 1, bytes: 100, oldest_ns: 9 * SEC });
+        assert_eq!((index.len(), index.total_bytes()), (1, 100));
+        assert_eq!(index.remove_below(2 * SEGMENT_FRAMES), None);
+        assert!(index.is_empty());
+    }
+}
@@ -11,7 +11,7 @@ use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tokio::time::interval;
 
-use crate::{FrameEnvelope, DataFrame, CmdFrame, Filter, BusCfg, metrics::METRICS};
+use crate::{FrameEnvelope, DataFrame, CmdFrame, CmdAckFrame, Filter, BusCfg, cache, metrics::METRICS, permissions, wal};
 
 /// 帧发送端
 pub type FrameSender = broadcast::Sender<FrameEnvelope>;
@@ -20,18 +20,33 @@ pub type FrameSender = broadcast::Sender<FrameEnvelope>;
 pub struct FrameReceiver {
     inner: broadcast::Receiver<FrameEnvelope>,
     filter: Filter,
+    /// 订阅主体 (驱动、连接器、桥接ID)，接收受总线ACL约束
+    subject: Option<String>,
 }
 
 impl FrameReceiver {
     /// 创建新的FrameReceiver实例
     pub fn new(inner: broadcast::Receiver<FrameEnvelope>, filter: Filter) -> Self {
-        Self { inner, filter }
+        Self { inner, filter, subject: None }
+    }
+
+    /// 设置订阅主体
+    pub fn with_subject<S: Into<String>>(mut self, subject: S) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// 订阅主体
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
     }
 
     pub async fn recv(&mut self) -> Result<FrameEnvelope, broadcast::error::RecvError> {
         loop {
             let envelope = self.inner.recv().await?;
-            if self.filter.matches(&envelope) {
+            if self.filter.matches(&envelope)
+                && permissions::authorize_receive(self.subject.as_deref(), &envelope.tag())
+            {
                 return Ok(envelope);
             }
             // 如果不匹配过滤器，继续接收下一个
@@ -42,6 +57,29 @@ impl FrameReceiver {
 /// 全局序列号生成器 (线程安全)
 static SEQ_GENERATOR: AtomicU64 = AtomicU64::new(0);
 
+/// 下一个待分配的序列号
+pub fn current_sequence() -> u64 {
+    SEQ_GENERATOR.load(Ordering::SeqCst)
+}
+
+/// 恢复序列号生成器，保证新帧不覆盖WAL中已持久化的序列号
+pub fn restore_sequence(next: u64) {
+    SEQ_GENERATOR.fetch_max(next, Ordering::SeqCst);
+}
+
+/// 广播前放入WAL批量写入队列，供持久订阅回放；未初始化WAL时跳过
+fn persist(envelope: &FrameEnvelope) {
+    use prost::Message;
+
+    if !wal::is_initialized() {
+        return;
+    }
+    if let Err(e) = wal::enqueue_frame(envelope.seq, &envelope.encode_to_vec()) {
+        METRICS.wal_write_error_total.inc();
+        tracing::error!("WAL write for frame {} failed: {}", envelope.seq, e);
+    }
+}
+
 /// FrameBus实例管理器 (替代全局状态)
 #[derive(Clone)]
 pub struct FrameBusInstance {
@@ -97,6 +135,7 @@ pub fn init(cfg: BusCfg) -> Result<(FrameSender, FrameReceiver)> {
         let receiver = FrameReceiver {
             inner: existing_instance.sender.subscribe(),
             filter: Filter::All,
+            subject: None,
         };
         return Ok((existing_instance.sender.clone(), receiver));
     }
@@ -107,6 +146,7 @@ pub fn init(cfg: BusCfg) -> Result<(FrameSender, FrameReceiver)> {
     let receiver = FrameReceiver {
         inner: rx,
         filter: Filter::All,
+        subject: None,
     };
     
     // 创建实例管理器
@@ -137,6 +177,7 @@ pub fn subscribe(filter: Filter) -> Result<FrameReceiver> {
     Ok(FrameReceiver {
         inner: rx,
         filter,
+        subject: None,
     })
 }
 
@@ -147,6 +188,7 @@ pub fn subscribe_with_instance(filter: Filter) -> Result<(FrameReceiver, Arc<Fra
     let receiver = FrameReceiver {
         inner: rx,
         filter,
+        subject: None,
     };
     Ok((receiver, instance))
 }
@@ -166,6 +208,7 @@ pub fn force_reinit_for_test(cfg: BusCfg) -> Result<(FrameSender, FrameReceiver)
     let receiver = FrameReceiver {
         inner: rx,
         filter: Filter::All,
+        subject: None,
     };
 
     // 对于测试，我们创建一个新的通道并返回，不依赖全局状态
@@ -240,7 +283,8 @@ impl BatchProcessor {
 
         // 批量发送，避免阻塞
         for envelope in self.buffer.drain(..) {
-            match self.tx.send(envelope) {
+            persist(&envelope);
+            match cache::global().apply_then(envelope, |envelope| self.tx.send(envelope)) {
                 Ok(_) => success_count += 1,
                 Err(_) => drop_count += 1,
             }
@@ -319,7 +363,9 @@ impl AsyncBatchPublisher {
     }
 
     /// 异步发送帧
-    pub fn send_envelope(&self, envelope: FrameEnvelope) -> Result<()> {
+    ///
+    /// 不做ACL校验，仅供已完成`authorize_publish`的`FramePublisher`调用
+    pub(crate) fn send_envelope(&self, envelope: FrameEnvelope) -> Result<()> {
         self.tx.send(envelope)
             .map_err(|_| anyhow::anyhow!("批量发布器通道已关闭"))
     }
@@ -359,6 +405,7 @@ pub fn init_with_batch_config(
     let receiver = FrameReceiver {
         inner: rx,
         filter: Filter::All,
+        subject: None,
     };
     
     // 创建带批量发布器的实例
@@ -379,16 +426,29 @@ pub fn init_with_batch_config(
 pub struct FramePublisher {
     tx: FrameSender,
     batch_mode: bool,
+    /// 发布主体 (驱动、连接器、桥接ID)，发布受总线ACL约束
+    subject: Option<String>,
 }
 
 impl FramePublisher {
     pub fn new(tx: FrameSender) -> Self {
-        Self { tx, batch_mode: false }
+        Self { tx, batch_mode: false, subject: None }
     }
 
     /// 创建启用批量模式的发布器
     pub fn new_batched(tx: FrameSender) -> Self {
-        Self { tx, batch_mode: true }
+        Self { tx, batch_mode: true, subject: None }
+    }
+
+    /// 设置发布主体
+    pub fn with_subject<S: Into<String>>(mut self, subject: S) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// 发布主体
+    pub fn subject(&self) -> Option<&str> {
+        self.subject.as_deref()
     }
 
     /// 批量发送多个数据帧
@@ -407,6 +467,10 @@ impl FramePublisher {
                 let frame_count = frames.len();
                 
                 for frame in frames {
+                    if permissions::authorize_publish(self.subject.as_deref(), &frame.tag).is_err() {
+                        error_count += 1;
+                        continue;
+                    }
                     let seq = SEQ_GENERATOR.fetch_add(1, Ordering::SeqCst);
                     match FrameEnvelope::wrap_data(seq, frame) {
                         Ok(envelope) => {
@@ -450,6 +514,7 @@ impl FramePublisher {
     }
 
     pub fn send_data(&self, frame: DataFrame) -> Result<()> {
+        permissions::authorize_publish(self.subject.as_deref(), &frame.tag)?;
         let seq = SEQ_GENERATOR.fetch_add(1, Ordering::SeqCst);
         let envelope = FrameEnvelope::wrap_data(seq, frame)?;
         
@@ -462,7 +527,8 @@ impl FramePublisher {
         }
         
         // 传统直接发送
-        match self.tx.send(envelope) {
+        persist(&envelope);
+        match cache::global().apply_then(envelope, |envelope| self.tx.send(envelope)) {
             Ok(_) => {
                 METRICS.publish_total.inc();
                 // 更新ring使用率  
@@ -492,10 +558,24 @@ impl FramePublisher {
     }
 
     pub fn send_cmd(&self, frame: CmdFrame) -> Result<()> {
+        permissions::authorize_publish(self.subject.as_deref(), &frame.tag)?;
         let seq = SEQ_GENERATOR.fetch_add(1, Ordering::SeqCst);
         let envelope = FrameEnvelope::wrap_cmd(seq, frame)?;
-        
-        match self.tx.send(envelope) {
+        self.broadcast(envelope)
+    }
+
+    /// 发送命令确认帧
+    pub fn send_cmd_ack(&self, frame: CmdAckFrame) -> Result<()> {
+        permissions::authorize_publish(self.subject.as_deref(), &frame.tag)?;
+        let seq = SEQ_GENERATOR.fetch_add(1, Ordering::SeqCst);
+        let envelope = FrameEnvelope::wrap_cmd_ack(seq, frame)?;
+        self.broadcast(envelope)
+    }
+
+    /// 持久化后广播
+    fn broadcast(&self, envelope: FrameEnvelope) -> Result<()> {
+        persist(&envelope);
+        match cache::global().apply_then(envelope, |envelope| self.tx.send(envelope)) {
             Ok(_) => {
                 METRICS.publish_total.inc();
                 let len = self.tx.len();
@@ -0,0 +1,188 @@
+//! 按点位索引路由的订阅
+//!
+//! 普通订阅每个接收端都要检查每一帧，代价随订阅者数量线性增长。路由订阅由单个路由线程
+//! 消费广播通道，按点位前缀树查出匹配的订阅者，只把帧投递到它们各自的队列。
+//! 订阅者队列满时丢弃并在下次接收时以`Lagged`报告，与广播通道语义一致。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{info, warn};
+
+use crate::index::{TagIndex, TagPattern};
+use crate::{metrics::METRICS, permissions, ring, Filter, FrameEnvelope};
+
+struct Route {
+    tx: mpsc::Sender<FrameEnvelope>,
+    /// 点位匹配之后再检查的过滤器 (帧类型、元数据等)
+    filter: Filter,
+    dropped: Arc<AtomicU64>,
+}
+
+/// 点位路由器
+pub struct TagRouter {
+    index: RwLock<TagIndex<u64>>,
+    routes: RwLock<HashMap<u64, Route>>,
+    next_id: AtomicU64,
+    capacity: usize,
+}
+
+impl TagRouter {
+    /// 创建路由器并启动路由线程消费广播通道
+    pub fn start(rx: broadcast::Receiver<FrameEnvelope>, capacity: usize) -> Result<Arc<Self>> {
+        let router = Arc::new(Self {
+            index: RwLock::new(TagIndex::new()),
+            routes: RwLock::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+            capacity,
+        });
+
+        let worker = router.clone();
+        std::thread::Builder::new()
+            .name("frame-bus-router".into())
+            .spawn(move || worker.run(rx))?;
+
+        info!("FrameBus tag router started, queue capacity {}", capacity);
+        Ok(router)
+    }
+
+    fn run(&self, mut rx: broadcast::Receiver<FrameEnvelope>) {
+        loop {
+            match rx.blocking_recv() {
+                Ok(envelope) => self.route(envelope),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    // 路由线程自身落后，所有订阅者都可能缺帧
+                    warn!("FrameBus tag router lagged by {} frames", skipped);
+                    for route in self.routes.read().unwrap().values() {
+                        route.dropped.fetch_add(skipped, Ordering::Relaxed);
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+
+    /// 投递到匹配的订阅者
+    pub fn route(&self, envelope: FrameEnvelope) {
+        let ids = self.index.read().unwrap().matching(&envelope.tag());
+        if ids.is_empty() {
+            return;
+        }
+
+        let routes = self.routes.read().unwrap();
+        for id in ids {
+            let Some(route) = routes.get(&id) else { continue };
+            if !route.filter.matches(&envelope) {
+                continue;
+            }
+            if let Err(mpsc::error::TrySendError::Full(_)) = route.tx.try_send(envelope.clone()) {
+                route.dropped.fetch_add(1, Ordering::Relaxed);
+                METRICS.drop_total.inc();
+            }
+        }
+    }
+
+    /// 按模式登记订阅者
+    pub fn subscribe(self: &Arc<Self>, patterns: Vec<TagPattern>, filter: Filter) -> RoutedReceiver {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = mpsc::channel(self.capacity);
+        let dropped = Arc::new(AtomicU64::new(0));
+
+        // 先登记路由再加入索引，路由线程查到的订阅者一定有队列
+        self.routes.write().unwrap().insert(id, Route { tx, filter, dropped: dropped.clone() });
+        let mut index = self.index.write().unwrap();
+        for pattern in &patterns {
+            index.insert(pattern, id);
+        }
+
+        RoutedReceiver { id, patterns, rx, dropped, router: self.clone(), subject: None }
+    }
+
+    fn unsubscribe(&self, id: u64, patterns: &[TagPattern]) {
+        let mut index = self.index.write().unwrap();
+        for pattern in patterns {
+            index.remove(pattern, &id);
+        }
+        drop(index);
+        self.routes.write().unwrap().remove(&id);
+    }
+
+    /// 当前路由订阅者数量
+    pub fn subscriber_count(&self) -> usize {
+        self.routes.read().unwrap().len()
+    }
+}
+
+/// 路由订阅接收端，丢弃时自动注销
+pub struct RoutedReceiver {
+    id: u64,
+    patterns: Vec<TagPattern>,
+    rx: mpsc::Receiver<FrameEnvelope>,
+    dropped: Arc<AtomicU64>,
+    router: Arc<TagRouter>,
+    /// 订阅主体，接收受总线ACL约束
+    subject: Option<String>,
+}
+
+impl RoutedReceiver {
+    /// 设置订阅主体
+    pub fn with_subject<S: Into<String>>(mut self, subject: S) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    /// 订阅的点位模式
+    pub fn patterns(&self) -> &[TagPattern] {
+        &self.patterns
+    }
+
+    /// 接收下一个匹配帧，队列溢出后先返回`Lagged`
+    pub async fn recv(&mut self) -> Result<FrameEnvelope, broadcast::error::RecvError> {
+        loop {
+            let dropped = self.dropped.swap(0, Ordering::Relaxed);
+            if dropped > 0 {
+                return Err(broadcast::error::RecvError::Lagged(dropped));
+            }
+            let envelope = self.rx.recv().await.ok_or(broadcast::error::RecvError::Closed)?;
+            if permissions::authorize_receive(self.subject.as_deref(), &envelope.tag()) {
+                return Ok(envelope);
+            }
+        }
+    }
+}
+
+impl Drop for RoutedReceiver {
+    fn drop(&mut self) {
+        self.router.unsubscribe(self.id, &self.patterns);
+    }
+}
+
+/// 全局路由器 (首次路由订阅时启动)
+static GLOBAL_ROUTER: OnceCell<Arc<TagRouter>> = OnceCell::new();
+
+/// 获取全局路由器
+pub fn get_router() -> Result<&'static Arc<TagRouter>> {
+    GLOBAL_ROUTER.get_or_try_init(|| {
+        let instance = ring::get_instance()?;
+        TagRouter::start(instance.get_sender().subscribe(), instance.get_config().ring_capacity())
+    })
+}
+
+/// 在全局FrameBus上按点位模式创建路由订阅
+pub fn subscribe<I, S>(patterns: I, filter: Filter) -> Result<RoutedReceiver>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let patterns = patterns.into_iter()
+        .map(|p| TagPattern::parse(p.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+    if patterns.is_empty() {
+        return Err(anyhow::anyhow!("Routed subscription requires at least one tag pattern"));
+    }
+    Ok(get_router()?.subscribe(patterns, filter))
+}
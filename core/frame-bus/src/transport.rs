@@ -0,0 +1,73 @@
+//! 总线传输抽象
+//!
+//! 驱动与桥接插件只依赖`Transport`，同一份代码既可在网关进程内直接使用全局总线 (`LocalTransport`)，
+//! 也可在子进程中经本地IPC连接网关总线 (`ipc::IpcClient`)。两种传输的订阅端都是`FrameReceiver`，
+//! 过滤语义一致；背压通过`ControlMsg::Pause/Resume`通知生产者。
+
+use anyhow::Result;
+use tokio::sync::broadcast;
+
+use crate::control::{self, ControlMsg};
+use crate::{ring, CmdAckFrame, CmdFrame, DataFrame, Filter, FramePublisher, FrameReceiver};
+
+/// 总线传输
+pub trait Transport: Send + Sync {
+    /// 订阅匹配过滤器的帧
+    fn subscribe(&self, filter: Filter) -> Result<FrameReceiver>;
+
+    /// 发布数据帧
+    fn send_data(&self, frame: DataFrame) -> Result<()>;
+
+    /// 发布命令帧
+    fn send_cmd(&self, frame: CmdFrame) -> Result<()>;
+
+    /// 发布命令确认帧
+    fn send_cmd_ack(&self, frame: CmdAckFrame) -> Result<()>;
+
+    /// 订阅背压控制消息
+    fn subscribe_control(&self) -> Result<broadcast::Receiver<ControlMsg>>;
+}
+
+/// 进程内传输，直接使用全局总线
+pub struct LocalTransport {
+    publisher: FramePublisher,
+}
+
+impl LocalTransport {
+    /// 匿名主体的进程内传输
+    pub fn new() -> Result<Self> {
+        Ok(Self { publisher: FramePublisher::new(ring::get_publisher()?.clone()) })
+    }
+
+    /// 以指定主体发布与订阅，受总线ACL约束
+    pub fn with_subject(subject: &str) -> Result<Self> {
+        Ok(Self { publisher: FramePublisher::new(ring::get_publisher()?.clone()).with_subject(subject) })
+    }
+}
+
+impl Transport for LocalTransport {
+    fn subscribe(&self, filter: Filter) -> Result<FrameReceiver> {
+        let receiver = ring::subscribe(filter)?;
+        Ok(match self.publisher.subject() {
+            Some(subject) => receiver.with_subject(subject),
+            None => receiver,
+        })
+    }
+
+    fn send_data(&self, frame: DataFrame) -> Result<()> {
+        self.publisher.send_data(frame)
+    }
+
+    fn send_cmd(&self, frame: CmdFrame) -> Result<()> {
+        self.publisher.send_cmd(frame)
+    }
+
+    fn send_cmd_ack(&self, frame: CmdAckFrame) -> Result<()> {
+        self.publisher.send_cmd_ack(frame)
+    }
+
+    fn subscribe_control(&self) -> Result<broadcast::Receiver<ControlMsg>> {
+        control::init_control();
+        control::subscribe_control().ok_or_else(|| anyhow::anyhow!("Control channel not initialized"))
+    }
+}
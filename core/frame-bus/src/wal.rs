@@ -2,6 +2,7 @@
 //! MVP-3增强：后台GC线程、自动压缩、批量写入优化
 
 use rocksdb::{DB, Options, ColumnFamilyDescriptor};
+use std::ops::Range;
 use std::path::Path;
 use once_cell::sync::OnceCell;
 use std::sync::Arc;
@@ -9,8 +10,10 @@ use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 use anyhow::Result;
 use tokio::sync::mpsc;
-use tracing::{info, warn, debug};
+use tracing::{info, warn, debug, error};
 
+use crate::codec::{WalCodec, WalCodecConfig};
+use crate::retention::{self, RecordIndex, RecordInfo, RecordStamp, SegmentSummary, RetentionAlertSink, RetentionConfig, RetentionPlan, RetentionReport, RetentionState, RetentionStats};
 use crate::metrics::METRICS;
 
 /// 全局WAL实例
@@ -19,11 +22,46 @@ static GLOBAL_WAL: OnceCell<Arc<WalManager>> = OnceCell::new();
 /// 全局内存WAL实例 (WSL2兼容性备选方案)
 static GLOBAL_MEMORY_WAL: OnceCell<Arc<InMemoryWalManager>> = OnceCell::new();
 
-/// 批量写入条目
+/// 启动恢复统计 (首次初始化时记录)
+static RECOVERY_STATS: OnceCell<RecoveryStats> = OnceCell::new();
+
+/// 内存WAL每写入多少帧执行一次GC
+const MEMORY_GC_EVERY_WRITES: u64 = 1024;
+
+/// 轮换密钥时等待写入队列落库的时间
+const ROTATE_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 计算GC下界：小于返回值的帧可删除
+///
+/// 存在持久游标时保留最慢游标之前`retain`个帧，否则只保留最近`retain`个帧
+fn gc_floor(min_ack: Option<u64>, max_seq: Option<u64>, retain: u64) -> Option<u64> {
+    let anchor = min_ack.or(max_seq)?;
+    (anchor > retain).then(|| anchor - retain)
+}
+
+/// 批量写入条目 (payload为已编码的WAL记录)
 #[derive(Debug)]
 struct BatchEntry {
     seq: u64,
     payload: Vec<u8>,
+    /// 保留策略使用的记录大小与时间戳，落库后加入记录索引
+    info: Option<RecordStamp>,
+}
+
+impl BatchEntry {
+    /// 编码帧并从明文封装字段提取记录大小与时间戳
+    fn encode(codec: &WalCodec, seq: u64, payload: &[u8]) -> Result<Self> {
+        let record = codec.encode(seq, payload)?;
+        let info = RecordStamp::from_payload(seq, (8 + record.len()) as u64, payload);
+        Ok(Self { seq, payload: record, info })
+    }
+}
+
+/// 写入队列进度：已入队与已落库的条目数
+#[derive(Debug, Default)]
+struct WriteProgress {
+    enqueued: AtomicU64,
+    flushed: AtomicU64,
 }
 
 /// WAL配置
@@ -49,6 +87,10 @@ pub struct WalConfig {
     pub retain_frames: u64,
     /// 背压阈值
     pub backpressure_threshold: usize,
+    /// 帧记录压缩与加密 (仅持久化WAL)
+    pub codec: WalCodecConfig,
+    /// 磁盘配额与时间保留 (仅持久化WAL)
+    pub retention: RetentionConfig,
 }
 
 impl Default for WalConfig {
@@ -64,6 +106,8 @@ impl Default for WalConfig {
             auto_compact_threshold: 4 * 1024 * 1024 * 1024, // 4GiB
             retain_frames: 10000,
             backpressure_threshold: 40000, // 队列80%时触发背压
+            codec: WalCodecConfig::default(),
+            retention: RetentionConfig::default(),
         }
     }
 }
@@ -72,8 +116,13 @@ impl Default for WalConfig {
 pub struct WalManager {
     db: Arc<DB>,
     config: WalConfig,
+    // 帧记录编解码 (密钥轮换时整体替换)
+    codec: Arc<std::sync::RwLock<Arc<WalCodec>>>,
+    // 保留策略统计与告警钩子
+    retention: Arc<RetentionState>,
     // 高性能写入队列 (有界，支持背压)
     write_queue: mpsc::Sender<BatchEntry>,
+    write_progress: Arc<WriteProgress>,
     // 异步同步通道
     sync_sender: mpsc::UnboundedSender<()>,
     // 后台任务状态
@@ -177,6 +226,51 @@ impl WAL {
     pub async fn gc(&self, keep_seq: u64) -> Result<()> {
         self.manager.gc(keep_seq)
     }
+
+    /// 记录消费者游标
+    pub async fn ack(&self, consumer_id: &str, seq: u64) -> Result<()> {
+        self.manager.ack(consumer_id, seq)
+    }
+
+    /// 获取消费者游标
+    pub async fn acked_seq(&self, consumer_id: &str) -> Result<Option<u64>> {
+        self.manager.acked_seq(consumer_id)
+    }
+
+    /// 从指定序列号开始读取帧
+    pub async fn read_from(&self, from_seq: u64, limit: usize) -> Result<Vec<crate::FrameEnvelope>> {
+        self.manager.read_from(from_seq, limit)
+    }
+
+    /// 按最慢的持久游标执行垃圾回收
+    pub async fn gc_to_cursors(&self) -> Result<()> {
+        self.manager.gc_to_cursors()
+    }
+
+    /// 扫描恢复统计
+    pub async fn recovery_scan(&self) -> Result<RecoveryStats> {
+        self.manager.recovery_scan()
+    }
+
+    /// 轮换加密密钥并重写存量帧
+    pub async fn rotate_key(&self, key: &[u8]) -> Result<u64> {
+        self.manager.rotate_key(key).await
+    }
+
+    /// 将存量帧重写为当前编码
+    pub async fn rekey(&self) -> Result<u64> {
+        self.manager.rekey()
+    }
+
+    /// 立即执行一次保留策略
+    pub async fn enforce_retention(&self) -> Result<RetentionReport> {
+        self.manager.enforce_retention()
+    }
+
+    /// 设置删除未回放数据前的告警钩子
+    pub fn set_retention_alert_sink(&self, sink: RetentionAlertSink) {
+        self.manager.set_retention_alert_sink(sink)
+    }
 }
 
 impl WalManager {
@@ -210,6 +304,11 @@ impl WalManager {
         let acks_cf = ColumnFamilyDescriptor::new("acks", Options::default());
 
         let db = DB::open_cf_descriptors(&opts, path, vec![frames_cf, acks_cf])?;
+        let codec = WalCodec::from_config(&config.codec)?;
+        if !codec.is_plain() {
+            info!("WAL frame codec: compression={}, encryption key={:?}",
+                  config.codec.compression, codec.active_key_id().map(|id| format!("{:08x}", id)));
+        }
         
         // 创建有界写入队列以支持背压
         let (write_sender, write_receiver) = mpsc::channel(config.write_queue_capacity);
@@ -218,7 +317,10 @@ impl WalManager {
         let manager = Self {
             db: Arc::new(db),
             config: config.clone(),
+            codec: Arc::new(std::sync::RwLock::new(Arc::new(codec))),
+            retention: Arc::new(RetentionState::default()),
             write_queue: write_sender,
+            write_progress: Arc::new(WriteProgress::default()),
             sync_sender,
             background_running: Arc::new(AtomicBool::new(true)),
             total_writes: AtomicU64::new(0),
@@ -230,6 +332,14 @@ impl WalManager {
             last_write_latency: Arc::new(std::sync::Mutex::new(Duration::from_nanos(0))),
         };
         
+        // 启用加密前写入的明文帧重写为加密记录，否则之后无法读取
+        if manager.codec().active_key_id().is_some() {
+            let (rewritten, failed) = manager.rekey_frames(true)?;
+            if rewritten > 0 || failed > 0 {
+                info!("WAL migration to current encoding: {} frames rewritten, {} failed", rewritten, failed);
+            }
+        }
+
         // 启动后台任务
         manager.start_background_tasks(write_receiver, sync_receiver)?;
         
@@ -275,10 +385,19 @@ impl WalManager {
         let async_sync_enabled = config.async_sync;
         let sync_config = config.clone(); // 为异步同步任务克隆配置
         
-        // 批量写入分发任务
-        tokio::spawn(async move {
-            Self::write_dispatcher_task(db.clone(), config.clone(), write_receiver, batch_writes).await;
-        });
+        // 批量写入分发任务：独立线程运行，不依赖创建WAL的运行时存活
+        let write_progress = self.write_progress.clone();
+        let write_retention = self.retention.clone();
+        let dispatcher_runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+        std::thread::Builder::new()
+            .name("wal-writer".to_string())
+            .spawn(move || {
+                dispatcher_runtime.block_on(Self::write_dispatcher_task(
+                    db, config, write_receiver, batch_writes, write_progress, write_retention,
+                ));
+            })?;
         
         // 异步同步任务
         if async_sync_enabled {
@@ -290,12 +409,16 @@ impl WalManager {
             });
         }
         
-        // GC和压缩任务
+        // GC、保留策略和压缩任务
         let db_clone = self.db.clone();
         let config_clone = self.config.clone();
+        let codec = self.codec.clone();
+        let retention = self.retention.clone();
         let background_running_clone = self.background_running.clone();
         tokio::spawn(async move {
-            Self::gc_and_compact_task(db_clone, config_clone, background_running_clone, gc_runs, compactions).await;
+            Self::gc_and_compact_task(
+                db_clone, config_clone, codec, retention, background_running_clone, gc_runs, compactions
+            ).await;
         });
         
         info!("WAL高性能后台任务启动完成: {} 写入工作线程, 异步同步: {}", 
@@ -323,7 +446,7 @@ impl WalManager {
                 if let Ok(batch_entries) = local_rx.recv_timeout(Duration::from_millis(1)) {
                     let start = Instant::now();
                     
-                    if let Err(e) = Self::flush_batch_optimized(&db, batch_entries, &batch_writes) {
+                    if let Err(e) = Self::flush_batch_optimized(&db, &batch_entries, &batch_writes) {
                         warn!("高性能写入失败: {}", e);
                     } else {
                         let latency = start.elapsed();
@@ -346,59 +469,61 @@ impl WalManager {
         debug!("高性能写入工作线程初始化完成");
     }
     
-    /// 批量写入分发任务
+    /// 批量写入分发任务：攒批写入，达到批量上限或超时即刷新
     async fn write_dispatcher_task(
-        _db: Arc<DB>,
+        db: Arc<DB>,
         config: WalConfig,
         mut receiver: mpsc::Receiver<BatchEntry>,
-        _batch_writes: Arc<AtomicU64>,
+        batch_writes: Arc<AtomicU64>,
+        progress: Arc<WriteProgress>,
+        retention: Arc<RetentionState>,
     ) {
         let mut batch = Vec::with_capacity(config.batch_size_limit);
-        let mut last_flush = Instant::now();
         
         loop {
-            let timeout = tokio::time::sleep(config.batch_timeout);
-            tokio::pin!(timeout);
-            
-            tokio::select! {
+            let flush_now = tokio::select! {
                 entry = receiver.recv() => {
                     match entry {
                         Some(entry) => {
                             batch.push(entry);
-                            
-                            // 检查是否需要立即刷新
-                            if batch.len() >= config.batch_size_limit {
-                                // TODO: 发送给工作线程处理
-                                last_flush = Instant::now();
-                            }
+                            batch.len() >= config.batch_size_limit
                         }
                         None => {
                             // 通道关闭，处理剩余批次并退出
-                            if !batch.is_empty() {
-                                // TODO: 发送给工作线程处理
-                            }
+                            Self::dispatch_batch(&db, &mut batch, &batch_writes, &progress, &retention);
                             break;
                         }
                     }
                 }
-                _ = timeout => {
-                    // 超时，处理当前批次
-                    if !batch.is_empty() && last_flush.elapsed() >= config.batch_timeout {
-                        // TODO: 发送给工作线程处理
-                        last_flush = Instant::now();
-                    }
-                }
+                _ = tokio::time::sleep(config.batch_timeout), if !batch.is_empty() => true,
+            };
+
+            if flush_now {
+                Self::dispatch_batch(&db, &mut batch, &batch_writes, &progress, &retention);
             }
         }
         
         debug!("批量写入分发任务终止");
     }
 
+    /// 写入一个批次并推进落库进度
+    fn dispatch_batch(
+        db: &Arc<DB>,
+        batch: &mut Vec<BatchEntry>,
+        batch_writes: &AtomicU64,
+        progress: &WriteProgress,
+        retention: &RetentionState,
+    ) {
+        let count = batch.len() as u64;
+        Self::flush_batch(db, batch, batch_writes, retention);
+        progress.flushed.fetch_add(count, Ordering::Release);
+    }
+
     /// 优化的批量刷新到数据库 (零拷贝 + 并行优化)
     fn flush_batch_optimized(
         db: &Arc<DB>, 
-        batch_entries: Vec<BatchEntry>, 
-        batch_writes: &Arc<AtomicU64>
+        batch_entries: &[BatchEntry], 
+        batch_writes: &AtomicU64
     ) -> Result<()> {
         if batch_entries.is_empty() {
             return Ok(());
@@ -443,14 +568,19 @@ impl WalManager {
         Ok(())
     }
     
-    /// 传统批量刷新 (兼容性保留)
-    fn flush_batch(db: &Arc<DB>, batch: &mut Vec<BatchEntry>, batch_writes: &Arc<AtomicU64>) {
+    /// 批量刷新，失败的批次计入写入错误，成功的批次加入记录索引
+    fn flush_batch(db: &Arc<DB>, batch: &mut Vec<BatchEntry>, batch_writes: &AtomicU64, retention: &RetentionState) {
         if batch.is_empty() {
             return;
         }
         
-        if let Err(e) = Self::flush_batch_optimized(db, batch.drain(..).collect(), batch_writes) {
-            warn!("传统批量刷新失败: {}", e);
+        let entries = std::mem::take(batch);
+        match Self::flush_batch_optimized(db, &entries, batch_writes) {
+            Ok(()) => retention.track(entries.into_iter().filter_map(|entry| entry.info)),
+            Err(e) => {
+                METRICS.wal_write_error_total.inc_by(entries.len() as f64);
+                error!("WAL批量写入失败 ({} 帧, 起始序列号 {}): {}", entries.len(), entries[0].seq, e);
+            }
         }
     }
 
@@ -496,10 +626,12 @@ impl WalManager {
         debug!("异步同步任务终止");
     }
     
-    /// GC和自动压缩任务
+    /// GC、保留策略和自动压缩任务
     async fn gc_and_compact_task(
         db: Arc<DB>,
         config: WalConfig,
+        codec: Arc<std::sync::RwLock<Arc<WalCodec>>>,
+        retention: Arc<RetentionState>,
         background_running: Arc<AtomicBool>,
         gc_runs: Arc<AtomicU64>,
         compactions: Arc<AtomicU64>,
@@ -510,11 +642,18 @@ impl WalManager {
             interval.tick().await;
             
             // 执行GC
-            if let Err(e) = Self::perform_gc(&db, &config) {
+            if let Err(e) = Self::perform_gc(&db, &config, &codec.read().unwrap().clone(), &retention) {
                 warn!("GC failed: {}", e);
             } else {
                 gc_runs.fetch_add(1, Ordering::Relaxed);
             }
+
+            if config.retention.is_enabled() {
+                let codec = codec.read().unwrap().clone();
+                if let Err(e) = Self::enforce_retention_in(&db, &codec, &config.retention, &retention) {
+                    warn!("WAL retention failed: {}", e);
+                }
+            }
             
             // 检查是否需要自动压缩
             let current_size = db.property_int_value("rocksdb.estimate-live-data-size")
@@ -532,98 +671,219 @@ impl WalManager {
         debug!("GC and compact task terminated");
     }
 
-    /// 执行垃圾回收
-    fn perform_gc(db: &Arc<DB>, config: &WalConfig) -> Result<()> {
-        let acks_cf = db.cf_handle("acks")
-            .ok_or_else(|| anyhow::anyhow!("acks CF not found"))?;
+    /// 执行垃圾回收：删除最慢持久游标之前超出保留窗口的帧
+    fn perform_gc(db: &Arc<DB>, config: &WalConfig, codec: &WalCodec, retention: &RetentionState) -> Result<()> {
         let frames_cf = db.cf_handle("frames")
             .ok_or_else(|| anyhow::anyhow!("frames CF not found"))?;
-        
-        // 找到所有消费者的最小ACK序列号
-        let iter = db.iterator_cf(acks_cf, rocksdb::IteratorMode::Start);
-        let mut min_ack_seq = None;
-        
-        for item in iter {
-            let (_, value) = item?;
-            if value.len() >= 8 {
-                let seq = u64::from_le_bytes(value[0..8].try_into().unwrap());
-                min_ack_seq = Some(min_ack_seq.map_or(seq, |min: u64| min.min(seq)));
-            }
+
+        let min_ack = Self::min_ack_seq_in(db)?;
+        let max_seq = Self::max_frame_seq_in(db)?;
+
+        if let Some(gc_seq) = gc_floor(min_ack, max_seq, config.retain_frames) {
+            let start_key = 0u64.to_be_bytes();
+            let end_key = gc_seq.to_be_bytes();
+
+            db.delete_range_cf(frames_cf, start_key, end_key)?;
+            retention.forget_below(gc_seq, |range| Self::scan_segment(db, codec, range))?;
+            debug!("WAL GC: deleted frames with seq < {}", gc_seq);
         }
         
-        if let Some(min_seq) = min_ack_seq {
-            // 保留指定数量的帧
-            if min_seq > config.retain_frames {
-                let gc_seq = min_seq - config.retain_frames;
-                let start_key = 0u64.to_be_bytes();
-                let end_key = gc_seq.to_be_bytes();
-                
-                db.delete_range_cf(frames_cf, start_key, end_key)?;
-                debug!("WAL GC: deleted frames with seq < {}", gc_seq);
+        Ok(())
+    }
+
+    /// 执行保留策略：在记录索引上按配额与时间保留生成删除计划
+    ///
+    /// 计划包含未回放的帧时先告警再删除；未超出水位且无过期帧时直接返回
+    fn enforce_retention_in(
+        db: &DB,
+        codec: &WalCodec,
+        config: &RetentionConfig,
+        state: &RetentionState,
+    ) -> Result<RetentionReport> {
+        let frames_cf = db.cf_handle("frames")
+            .ok_or_else(|| anyhow::anyhow!("frames CF not found"))?;
+        let min_ack = Self::min_ack_seq_in(db)?;
+        let now_ns = crate::envelope::current_timestamp_ns();
+
+        let (needs_plan, total_bytes) = state.with_index(
+            || Self::load_record_index(db, codec),
+            |index| (index.needs_plan(config, now_ns), index.total_bytes()),
+        )?;
+        if !needs_plan {
+            return Ok(RetentionPlan::unchanged(total_bytes).report);
+        }
+
+        // 生成计划时不持有索引锁，写入路径照常记录新帧
+        let scan = |visit: &mut dyn FnMut(&RecordInfo) -> bool| {
+            Self::scan_records(db, codec, 0..u64::MAX, visit).map(|_| ())
+        };
+        let plan = retention::plan_with(scan, config, min_ack, now_ns)?;
+        if plan.deletions.is_empty() {
+            return Ok(plan.report);
+        }
+
+        state.alert_if_unreplayed(&plan, min_ack);
+
+        let mut batch = rocksdb::WriteBatch::default();
+        for (seq, _) in &plan.deletions {
+            batch.delete_cf(frames_cf, seq.to_be_bytes());
+        }
+        db.write(batch)?;
+        state.forget(&plan.removed, |range| Self::scan_segment(db, codec, range))?;
+
+        state.record(&plan.report);
+        METRICS.wal_retention_deleted_total.inc_by(plan.report.deleted() as f64);
+        METRICS.wal_bytes.set(plan.report.bytes_after as i64);
+        info!("WAL retention: deleted {} frames ({} unreplayed), {} -> {} bytes",
+              plan.report.deleted(), plan.report.unreplayed, plan.report.bytes_before, plan.report.bytes_after);
+        Ok(plan.report)
+    }
+
+    /// 全量扫描帧记录建立索引
+    ///
+    /// 无法解码的记录无法判断时间与等级，不纳入索引，也不会被保留策略删除
+    fn load_record_index(db: &DB, codec: &WalCodec) -> Result<RecordIndex> {
+        let mut index = RecordIndex::default();
+        let skipped = Self::scan_records(db, codec, 0..u64::MAX, &mut |info| {
+            index.insert(info.into());
+            true
+        })?;
+        if skipped > 0 {
+            warn!("WAL retention: {} undecodable frames skipped", skipped);
+        }
+        debug!("WAL retention index loaded: {} frames, {} bytes", index.len(), index.total_bytes());
+        Ok(index)
+    }
+
+    /// 按序列号顺序扫描`seqs`范围内的帧记录摘要，`visit`返回false时停止，返回无法解码的记录数
+    fn scan_records(
+        db: &DB,
+        codec: &WalCodec,
+        seqs: Range<u64>,
+        visit: &mut dyn FnMut(&RecordInfo) -> bool,
+    ) -> Result<u64> {
+        let frames_cf = db.cf_handle("frames")
+            .ok_or_else(|| anyhow::anyhow!("frames CF not found"))?;
+
+        let start_key = seqs.start.to_be_bytes();
+        let mut skipped = 0;
+        for item in db.iterator_cf(frames_cf, rocksdb::IteratorMode::From(&start_key, rocksdb::Direction::Forward)) {
+            let (key, value) = item?;
+            let Some(seq) = key.get(0..8).map(|k| u64::from_be_bytes(k.try_into().unwrap())) else {
+                continue;
+            };
+            if seq >= seqs.end {
+                break;
+            }
+            let bytes = (key.len() + value.len()) as u64;
+            match codec.decode(seq, &value).ok().and_then(|payload| RecordInfo::from_payload(bytes, &payload)) {
+                Some(info) => if !visit(&info) {
+                    break;
+                },
+                None => skipped += 1,
             }
         }
-        
-        Ok(())
+        Ok(skipped)
+    }
+
+    /// 重新扫描一段序列号，汇总帧数、字节数与最旧时间戳
+    fn scan_segment(db: &DB, codec: &WalCodec, seqs: Range<u64>) -> Result<SegmentSummary> {
+        let mut summary = SegmentSummary::default();
+        Self::scan_records(db, codec, seqs, &mut |info| {
+            summary.add(info.into());
+            true
+        })?;
+        Ok(summary)
+    }
+
+    /// 立即执行一次保留策略
+    pub fn enforce_retention(&self) -> Result<RetentionReport> {
+        Self::enforce_retention_in(&self.db, &self.codec(), &self.config.retention, &self.retention)
     }
 
-    /// 高性能写入帧到WAL (支持背压控制)
+    /// 设置删除未回放数据前的告警钩子
+    pub fn set_retention_alert_sink(&self, sink: RetentionAlertSink) {
+        self.retention.set_alert_sink(sink);
+    }
+
+    /// 写入帧到WAL (支持背压控制)
     pub async fn write_frame(&self, seq: u64, payload: &[u8]) -> Result<()> {
-        let start = Instant::now();
-        
         // 检查背压状态
         if self.backpressure_active.load(Ordering::Relaxed) {
             // 在背压状态下，稍微延迟以减少负荷
             tokio::time::sleep(Duration::from_micros(100)).await;
         }
-        
-        let entry = BatchEntry {
-            seq,
-            payload: payload.to_vec(),
+
+        self.write_frame_sync(seq, payload)
+    }
+    
+    /// 同步写入帧
+    ///
+    /// 写穿到frames列族，返回后即可被持久订阅回放；刷盘由异步同步任务负责
+    pub fn write_frame_sync(&self, seq: u64, payload: &[u8]) -> Result<()> {
+        let start = Instant::now();
+        // 持有编解码器读锁直至写入完成，轮换密钥切换编解码器时不会漏掉以旧密钥写入的帧
+        let codec = self.codec.read().unwrap();
+        let entry = BatchEntry::encode(&codec, seq, payload)?;
+        Self::flush_batch_optimized(&self.db, std::slice::from_ref(&entry), &self.batch_writes)?;
+        drop(codec);
+        self.retention.track(entry.info);
+
+        self.total_writes.fetch_add(1, Ordering::Relaxed);
+        if let Ok(mut last_latency) = self.last_write_latency.lock() {
+            *last_latency = start.elapsed();
+        }
+        Ok(())
+    }
+
+    /// 编码后放入批量写入队列，不阻塞调用方
+    ///
+    /// 队列已满时退化为同步写入，保证帧不丢失
+    pub fn enqueue_frame(&self, seq: u64, payload: &[u8]) -> Result<()> {
+        // 先计入入队数再取编解码器，轮换密钥后等待落库的目标必然包含以旧密钥编码的帧
+        self.write_progress.enqueued.fetch_add(1, Ordering::AcqRel);
+        let entry = match BatchEntry::encode(&self.codec(), seq, payload) {
+            Ok(entry) => entry,
+            Err(e) => {
+                self.write_progress.enqueued.fetch_sub(1, Ordering::AcqRel);
+                return Err(e);
+            }
         };
-        
-        // 使用有界通道，在队列满时等待
-        match tokio::time::timeout(Duration::from_millis(10), self.write_queue.send(entry)).await {
-            Ok(result) => {
-                result.map_err(|_| anyhow::anyhow!("Write queue closed"))?;
+        match self.write_queue.try_send(entry) {
+            Ok(()) => {
+                self.total_writes.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Full(entry)) => {
+                self.write_progress.enqueued.fetch_sub(1, Ordering::AcqRel);
+                warn!("WAL write queue full, writing frame {} synchronously", seq);
+                Self::flush_batch_optimized(&self.db, std::slice::from_ref(&entry), &self.batch_writes)?;
+                self.retention.track(entry.info);
                 self.total_writes.fetch_add(1, Ordering::Relaxed);
-                
-                // 记录写入延迟
-                let write_latency = start.elapsed();
-                if write_latency.as_millis() > 1 {
-                    warn!("写入延迟超过1ms: {:?}", write_latency);
-                }
-                
                 Ok(())
-            },
-            Err(_) => {
-                // 写入超时，触发背压
-                self.backpressure_active.store(true, Ordering::Relaxed);
-                Err(anyhow::anyhow!("Write timeout - backpressure activated"))
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.write_progress.enqueued.fetch_sub(1, Ordering::AcqRel);
+                Err(anyhow::anyhow!("WAL write queue closed"))
             }
         }
     }
-    
-    /// 传统同步写入接口 (兼容性保留)
-    pub fn write_frame_sync(&self, seq: u64, payload: &[u8]) -> Result<()> {
-        let entry = BatchEntry {
-            seq,
-            payload: payload.to_vec(),
-        };
-        
-        // 非阻塞式发送，在队列满时返回错误
-        self.write_queue.try_send(entry)
-            .map_err(|e| match e {
-                mpsc::error::TrySendError::Full(_) => {
-                    self.backpressure_active.store(true, Ordering::Relaxed);
-                    anyhow::anyhow!("Write queue full - backpressure activated")
-                },
-                mpsc::error::TrySendError::Closed(_) => {
-                    anyhow::anyhow!("Write queue closed")
-                }
-            })?;
-        
-        self.total_writes.fetch_add(1, Ordering::Relaxed);
-        Ok(())
+
+    /// 等待调用时已入队的帧全部落库，返回是否有需要等待的帧
+    pub async fn wait_flushed(&self, timeout: Duration) -> bool {
+        let target = self.write_progress.enqueued.load(Ordering::Acquire);
+        if self.write_progress.flushed.load(Ordering::Acquire) >= target {
+            return false;
+        }
+        let deadline = Instant::now() + timeout;
+        while self.write_progress.flushed.load(Ordering::Acquire) < target {
+            if Instant::now() >= deadline {
+                warn!("Timed out waiting for queued WAL writes to flush");
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        true
     }
 
     /// 直接写入帧到WAL (绕过批量系统，用于紧急情况)
@@ -631,8 +891,9 @@ impl WalManager {
         let frames_cf = self.db.cf_handle("frames")
             .ok_or_else(|| anyhow::anyhow!("frames CF not found"))?;
         
-        let key = seq.to_be_bytes();
-        self.db.put_cf(frames_cf, key, payload)?;
+        let entry = BatchEntry::encode(&self.codec(), seq, payload)?;
+        self.db.put_cf(frames_cf, seq.to_be_bytes(), &entry.payload)?;
+        self.retention.track(entry.info);
         
         // 更新指标
         let size = self.estimate_size();
@@ -648,24 +909,23 @@ impl WalManager {
         
         let key = seq.to_be_bytes();
         match self.db.get_cf(frames_cf, key)? {
-            Some(data) => Ok(Some(data)),
+            Some(record) => Ok(Some(self.codec().decode(seq, &record)?)),
             None => Ok(None),
         }
     }
 
     /// 恢复所有帧
     pub fn recover_all(&self) -> Result<Vec<crate::FrameEnvelope>> {
-        use prost::Message;
-        
         let frames_cf = self.db.cf_handle("frames")
             .ok_or_else(|| anyhow::anyhow!("frames CF not found"))?;
         
+        let codec = self.codec();
         let mut frames = Vec::new();
         let iter = self.db.iterator_cf(frames_cf, rocksdb::IteratorMode::Start);
         
         for item in iter {
-            let (_, value) = item?;
-            if let Ok(envelope) = crate::FrameEnvelope::decode(&value[..]) {
+            let (key, value) = item?;
+            if let Some(envelope) = Self::decode_record(&codec, &key, &value) {
                 frames.push(envelope);
             }
         }
@@ -676,6 +936,34 @@ impl WalManager {
         Ok(frames)
     }
 
+    /// 扫描全部帧，统计可恢复与损坏的记录
+    pub fn recovery_scan(&self) -> Result<RecoveryStats> {
+        let frames_cf = self.db.cf_handle("frames")
+            .ok_or_else(|| anyhow::anyhow!("frames CF not found"))?;
+        let acks_cf = self.db.cf_handle("acks")
+            .ok_or_else(|| anyhow::anyhow!("acks CF not found"))?;
+
+        let min_ack = self.min_ack_seq()?;
+        let mut stats = RecoveryStats {
+            consumers: self.db.iterator_cf(acks_cf, rocksdb::IteratorMode::Start).count(),
+            ..Default::default()
+        };
+
+        let codec = self.codec();
+        for item in self.db.iterator_cf(frames_cf, rocksdb::IteratorMode::Start) {
+            let (key, value) = item?;
+            if key.len() < 8 {
+                stats.corrupt_skipped += 1;
+                continue;
+            }
+            let seq = u64::from_be_bytes(key[0..8].try_into().unwrap());
+            let decoded = Self::decode_record(&codec, &key, &value).is_some_and(|envelope| envelope.seq == seq);
+            stats.record(seq, decoded, min_ack);
+        }
+
+        Ok(stats)
+    }
+
     /// 记录消费者ACK
     pub fn ack(&self, consumer_id: &str, seq: u64) -> Result<()> {
         let acks_cf = self.db.cf_handle("acks")
@@ -707,10 +995,14 @@ impl WalManager {
 
     /// 获取最小ACK序列号（用于GC）
     pub fn min_ack_seq(&self) -> Result<Option<u64>> {
-        let acks_cf = self.db.cf_handle("acks")
+        Self::min_ack_seq_in(&self.db)
+    }
+
+    fn min_ack_seq_in(db: &DB) -> Result<Option<u64>> {
+        let acks_cf = db.cf_handle("acks")
             .ok_or_else(|| anyhow::anyhow!("acks CF not found"))?;
         
-        let iter = self.db.iterator_cf(acks_cf, rocksdb::IteratorMode::Start);
+        let iter = db.iterator_cf(acks_cf, rocksdb::IteratorMode::Start);
         let mut min_seq = None;
         
         for item in iter {
@@ -724,6 +1016,188 @@ impl WalManager {
         Ok(min_seq)
     }
 
+    /// 获取最大frames序列号
+    pub fn max_frame_seq(&self) -> Result<Option<u64>> {
+        Self::max_frame_seq_in(&self.db)
+    }
+
+    fn max_frame_seq_in(db: &DB) -> Result<Option<u64>> {
+        let frames_cf = db.cf_handle("frames")
+            .ok_or_else(|| anyhow::anyhow!("frames CF not found"))?;
+
+        if let Some(item) = db.iterator_cf(frames_cf, rocksdb::IteratorMode::End).next() {
+            let (key, _) = item?;
+            if key.len() >= 8 {
+                return Ok(Some(u64::from_be_bytes(key[0..8].try_into().unwrap())));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 获取消费者已ACK的序列号
+    pub fn acked_seq(&self, consumer_id: &str) -> Result<Option<u64>> {
+        let acks_cf = self.db.cf_handle("acks")
+            .ok_or_else(|| anyhow::anyhow!("acks CF not found"))?;
+
+        Ok(self.db.get_cf(acks_cf, consumer_id.as_bytes())?
+            .filter(|value| value.len() >= 8)
+            .map(|value| u64::from_le_bytes(value[0..8].try_into().unwrap())))
+    }
+
+    /// 删除消费者游标，不再阻止GC
+    pub fn remove_consumer(&self, consumer_id: &str) -> Result<()> {
+        let acks_cf = self.db.cf_handle("acks")
+            .ok_or_else(|| anyhow::anyhow!("acks CF not found"))?;
+
+        self.db.delete_cf(acks_cf, consumer_id.as_bytes())?;
+        Ok(())
+    }
+
+    /// 从指定序列号开始按序读取最多`limit`个帧
+    pub fn read_from(&self, from_seq: u64, limit: usize) -> Result<Vec<crate::FrameEnvelope>> {
+        let frames_cf = self.db.cf_handle("frames")
+            .ok_or_else(|| anyhow::anyhow!("frames CF not found"))?;
+
+        let start_key = from_seq.to_be_bytes();
+        let iter = self.db.iterator_cf(
+            frames_cf,
+            rocksdb::IteratorMode::From(&start_key, rocksdb::Direction::Forward),
+        );
+
+        let codec = self.codec();
+        let mut frames = Vec::new();
+        for item in iter {
+            if frames.len() >= limit {
+                break;
+            }
+            let (key, value) = item?;
+            if let Some(envelope) = Self::decode_record(&codec, &key, &value) {
+                frames.push(envelope);
+            }
+        }
+
+        Ok(frames)
+    }
+
+    /// 当前帧记录编解码器
+    fn codec(&self) -> Arc<WalCodec> {
+        self.codec.read().unwrap().clone()
+    }
+
+    /// 解码一条帧记录，无法解密或解析时跳过
+    fn decode_record(codec: &WalCodec, key: &[u8], record: &[u8]) -> Option<crate::FrameEnvelope> {
+        use prost::Message;
+
+        let seq = u64::from_be_bytes(key.get(0..8)?.try_into().ok()?);
+        let result = codec.decode(seq, record)
+            .and_then(|payload| crate::FrameEnvelope::decode(&payload[..]).map_err(Into::into));
+        match result {
+            Ok(envelope) => Some(envelope),
+            Err(e) => {
+                warn!("Skipping undecodable WAL frame {}: {}", seq, e);
+                None
+            }
+        }
+    }
+
+    /// 轮换加密密钥：新帧立即使用新密钥，存量帧随后重写，旧密钥在此期间仍可解密
+    ///
+    /// 新密钥在重写存量帧前写入密钥文件，旧密钥另存于密钥文件旁；
+    /// 切换编解码器后先等待队列中以旧密钥编码的帧落库再重写，
+    /// 全部存量帧重写成功后才删除旧密钥，任一步骤崩溃重启后帧均可解密
+    pub async fn rotate_key(&self, key: &[u8]) -> Result<u64> {
+        let key_file = self.config.codec.key_file.as_ref()
+            .ok_or_else(|| anyhow::anyhow!("WAL key rotation requires a configured key file"))?;
+        let rotated = self.codec().rotated(key)?;
+
+        let current = crate::codec::load_key_file(key_file)?;
+        let retired = crate::codec::retired_key_path(key_file, crate::codec::key_id(&current));
+        crate::codec::write_key_file(&retired, &current)?;
+        crate::codec::write_key_file(key_file, key)?;
+
+        *self.codec.write().unwrap() = Arc::new(rotated);
+        info!("WAL encryption key rotated");
+
+        let target = self.write_progress.enqueued.load(Ordering::Acquire);
+        self.wait_flushed(ROTATE_DRAIN_TIMEOUT).await;
+        let drained = self.write_progress.flushed.load(Ordering::Acquire) >= target;
+
+        let (rewritten, failed) = self.rekey_frames(false)?;
+        if !drained {
+            warn!("Queued WAL frames not flushed before rekey, keeping retired key files");
+        } else if failed == 0 {
+            for path in crate::codec::rotated_key_files(key_file)? {
+                if let Err(e) = std::fs::remove_file(&path) {
+                    warn!("Failed to remove retired WAL key {:?}: {}", path, e);
+                }
+            }
+        } else {
+            warn!("{} WAL frames still use a retired key, keeping retired key files", failed);
+        }
+        Ok(rewritten)
+    }
+
+    /// 将未按当前密钥编码的存量帧重写为当前编码，返回重写的帧数
+    ///
+    /// 完成后旧密钥即可从`retired_key_files`中移除
+    pub fn rekey(&self) -> Result<u64> {
+        Ok(self.rekey_frames(false)?.0)
+    }
+
+    /// 重写存量帧，返回重写与无法重写的帧数；`unencrypted_only`时只重写启用加密前的明文帧
+    fn rekey_frames(&self, unencrypted_only: bool) -> Result<(u64, u64)> {
+        let frames_cf = self.db.cf_handle("frames")
+            .ok_or_else(|| anyhow::anyhow!("frames CF not found"))?;
+        let codec = self.codec();
+
+        let mut rewritten = 0;
+        let mut failed = 0;
+        let mut batch = rocksdb::WriteBatch::default();
+        let mut resized = Vec::new();
+        for item in self.db.iterator_cf(frames_cf, rocksdb::IteratorMode::Start) {
+            let (key, record) = item?;
+            if key.len() < 8 || codec.is_current(&record) {
+                continue;
+            }
+            if unencrypted_only && crate::codec::record_key_id(&record).is_some() {
+                continue;
+            }
+            let seq = u64::from_be_bytes(key[0..8].try_into().unwrap());
+            // 启用加密前的明文帧一并加密；无法解密的帧保持原样，不因轮换丢失
+            match codec.decode_legacy(seq, &record).and_then(|payload| BatchEntry::encode(&codec, seq, &payload)) {
+                Ok(entry) => {
+                    batch.put_cf(frames_cf, &key, &entry.payload);
+                    resized.push((seq, (key.len() + record.len()) as u64, (key.len() + entry.payload.len()) as u64));
+                    rewritten += 1;
+                }
+                Err(e) => {
+                    warn!("Cannot re-encode WAL frame {}: {}", seq, e);
+                    failed += 1;
+                }
+            }
+            if batch.len() >= self.config.batch_size_limit {
+                self.db.write(std::mem::take(&mut batch))?;
+                self.retention.resize(resized.drain(..));
+            }
+        }
+        if !batch.is_empty() {
+            self.db.write(batch)?;
+            self.retention.resize(resized);
+        }
+
+        info!("WAL rekey: {} frames re-encoded", rewritten);
+        Ok((rewritten, failed))
+    }
+
+    /// 按持久游标执行GC
+    pub fn gc_to_cursors(&self) -> Result<()> {
+        if let Some(gc_seq) = gc_floor(self.min_ack_seq()?, self.max_frame_seq()?, self.config.retain_frames) {
+            self.gc(gc_seq)?;
+        }
+        Ok(())
+    }
+
     /// 垃圾回收旧的帧
     pub fn gc(&self, keep_seq: u64) -> Result<()> {
         let frames_cf = self.db.cf_handle("frames")
@@ -734,6 +1208,8 @@ impl WalManager {
         let end_key = keep_seq.to_be_bytes();
         
         self.db.delete_range_cf(frames_cf, start_key, end_key)?;
+        let codec = self.codec();
+        self.retention.forget_below(keep_seq, |range| Self::scan_segment(&self.db, &codec, range))?;
         
         tracing::info!("WAL GC: deleted frames with seq < {}", keep_seq);
         
@@ -782,6 +1258,7 @@ impl WalManager {
             backpressure_active: self.backpressure_active.load(Ordering::Relaxed),
             last_write_latency_ms: last_latency_ms,
             queue_usage_percent: queue_usage,
+            retention: self.retention.stats(),
         }
     }
     
@@ -821,6 +1298,41 @@ pub struct WalStats {
     pub backpressure_active: bool,
     pub last_write_latency_ms: f64,
     pub queue_usage_percent: f64,
+    /// 保留策略卸载决策统计
+    pub retention: RetentionStats,
+}
+
+/// 启动恢复统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecoveryStats {
+    /// 可解码的帧数
+    pub frames_recovered: u64,
+    /// 无法解码而跳过的记录数
+    pub corrupt_skipped: u64,
+    /// WAL中最小序列号
+    pub min_seq: Option<u64>,
+    /// WAL中最大序列号 (含损坏记录)，序列号生成器从其后继续
+    pub max_seq: Option<u64>,
+    /// 持久消费者数量
+    pub consumers: usize,
+    /// 最慢游标之后待重投的帧数
+    pub unacked_frames: u64,
+}
+
+impl RecoveryStats {
+    /// 按序列号扫描的结果累计统计
+    fn record(&mut self, seq: u64, decoded: bool, min_ack: Option<u64>) {
+        self.min_seq = Some(self.min_seq.map_or(seq, |min| min.min(seq)));
+        self.max_seq = Some(self.max_seq.map_or(seq, |max| max.max(seq)));
+        if !decoded {
+            self.corrupt_skipped += 1;
+            return;
+        }
+        self.frames_recovered += 1;
+        if min_ack.is_some_and(|ack| seq > ack) {
+            self.unacked_frames += 1;
+        }
+    }
 }
 
 /// 内存WAL管理器 (WSL2兼容性备选方案)
@@ -853,24 +1365,13 @@ impl InMemoryWalManager {
 
     /// 高性能写入帧到内存
     pub async fn write_frame(&self, seq: u64, payload: &[u8]) -> Result<()> {
-        let start = Instant::now();
-        
-        {
-            let mut frames = self.frames.write()
-                .map_err(|_| anyhow::anyhow!("Failed to acquire write lock"))?;
-            frames.insert(seq, payload.to_vec());
-        }
-        
-        self.total_writes.fetch_add(1, Ordering::Relaxed);
-        
-        let write_latency = start.elapsed();
-        if let Ok(mut last_latency) = self.last_write_latency.lock() {
-            *last_latency = write_latency;
-        }
+        self.write_frame_sync(seq, payload)?;
         
         // 内存操作应该非常快，记录异常延迟
-        if write_latency.as_millis() > 1 {
-            warn!("内存写入延迟异常: {:?}", write_latency);
+        if let Ok(last_latency) = self.last_write_latency.lock() {
+            if last_latency.as_millis() > 1 {
+                warn!("内存写入延迟异常: {:?}", *last_latency);
+            }
         }
         
         Ok(())
@@ -886,12 +1387,17 @@ impl InMemoryWalManager {
             frames.insert(seq, payload.to_vec());
         }
         
-        self.total_writes.fetch_add(1, Ordering::Relaxed);
+        let writes = self.total_writes.fetch_add(1, Ordering::Relaxed) + 1;
         
         let write_latency = start.elapsed();
         if let Ok(mut last_latency) = self.last_write_latency.lock() {
             *last_latency = write_latency;
         }
+
+        // 内存模式无后台GC任务，按写入次数触发
+        if writes.is_multiple_of(MEMORY_GC_EVERY_WRITES) {
+            self.gc_to_cursors()?;
+        }
         
         Ok(())
     }
@@ -927,6 +1433,26 @@ impl InMemoryWalManager {
         Ok(envelopes)
     }
 
+    /// 扫描全部帧，统计可恢复与损坏的记录
+    pub fn recovery_scan(&self) -> Result<RecoveryStats> {
+        use prost::Message;
+
+        let min_ack = self.min_ack_seq()?;
+        let consumers = self.acks.read()
+            .map_err(|_| anyhow::anyhow!("Failed to acquire read lock"))?
+            .len();
+        let frames = self.frames.read()
+            .map_err(|_| anyhow::anyhow!("Failed to acquire read lock"))?;
+
+        let mut stats = RecoveryStats { consumers, ..Default::default() };
+        for (&seq, data) in frames.iter() {
+            let decoded = crate::FrameEnvelope::decode(&data[..]).is_ok_and(|envelope| envelope.seq == seq);
+            stats.record(seq, decoded, min_ack);
+        }
+
+        Ok(stats)
+    }
+
     /// 记录消费者ACK
     pub fn ack(&self, consumer_id: &str, seq: u64) -> Result<()> {
         let mut acks = self.acks.write()
@@ -949,6 +1475,52 @@ impl InMemoryWalManager {
         Ok(acks.values().min().copied())
     }
 
+    /// 获取最大frames序列号
+    pub fn max_frame_seq(&self) -> Result<Option<u64>> {
+        let frames = self.frames.read()
+            .map_err(|_| anyhow::anyhow!("Failed to acquire read lock"))?;
+        Ok(frames.keys().max().copied())
+    }
+
+    /// 获取消费者已ACK的序列号
+    pub fn acked_seq(&self, consumer_id: &str) -> Result<Option<u64>> {
+        let acks = self.acks.read()
+            .map_err(|_| anyhow::anyhow!("Failed to acquire read lock"))?;
+        Ok(acks.get(consumer_id).copied())
+    }
+
+    /// 删除消费者游标，不再阻止GC
+    pub fn remove_consumer(&self, consumer_id: &str) -> Result<()> {
+        let mut acks = self.acks.write()
+            .map_err(|_| anyhow::anyhow!("Failed to acquire write lock"))?;
+        acks.remove(consumer_id);
+        Ok(())
+    }
+
+    /// 从指定序列号开始按序读取最多`limit`个帧
+    pub fn read_from(&self, from_seq: u64, limit: usize) -> Result<Vec<crate::FrameEnvelope>> {
+        use prost::Message;
+
+        let frames = self.frames.read()
+            .map_err(|_| anyhow::anyhow!("Failed to acquire read lock"))?;
+
+        let mut seqs: Vec<u64> = frames.keys().copied().filter(|&seq| seq >= from_seq).collect();
+        seqs.sort_unstable();
+
+        Ok(seqs.into_iter()
+            .take(limit)
+            .filter_map(|seq| crate::FrameEnvelope::decode(&frames[&seq][..]).ok())
+            .collect())
+    }
+
+    /// 按持久游标执行GC
+    pub fn gc_to_cursors(&self) -> Result<()> {
+        if let Some(gc_seq) = gc_floor(self.min_ack_seq()?, self.max_frame_seq()?, self.config.retain_frames) {
+            self.gc(gc_seq)?;
+        }
+        Ok(())
+    }
+
     /// 垃圾回收
     pub fn gc(&self, keep_seq: u64) -> Result<()> {
         let mut frames = self.frames.write()
@@ -1006,6 +1578,7 @@ impl InMemoryWalManager {
             backpressure_active: false, // 内存模式下无背压
             last_write_latency_ms: last_latency_ms,
             queue_usage_percent: 0.0, // 内存模式下无队列
+            retention: RetentionStats::default(), // 内存模式下不执行保留策略
         }
     }
 
@@ -1157,6 +1730,8 @@ pub fn init_memory_wal() -> Result<()> {
         auto_compact_threshold: 1024 * 1024 * 1024, // 1GB内存限制
         retain_frames: 50000, // 保留更多帧在内存中
         backpressure_threshold: 8000,
+        codec: WalCodecConfig::default(),
+        retention: RetentionConfig::default(),
     };
     
     let manager = InMemoryWalManager::new(config);
@@ -1221,6 +1796,22 @@ pub fn write_frame(seq: u64, payload: &[u8]) -> Result<()> {
     }
 }
 
+/// 放入批量写入队列 (支持降级)，内存WAL直接写入
+pub fn enqueue_frame(seq: u64, payload: &[u8]) -> Result<()> {
+    match get_available_wal()? {
+        WalInstance::Persistent(wal) => wal.enqueue_frame(seq, payload),
+        WalInstance::Memory(memory_wal) => memory_wal.write_frame_sync(seq, payload),
+    }
+}
+
+/// 等待已入队的帧落库，返回是否有需要等待的帧
+pub async fn wait_flushed() -> Result<bool> {
+    match get_available_wal()? {
+        WalInstance::Persistent(wal) => Ok(wal.wait_flushed(Duration::from_secs(1)).await),
+        WalInstance::Memory(_) => Ok(false),
+    }
+}
+
 /// 异步高性能写入帧 (支持降级)
 pub async fn write_frame_async(seq: u64, payload: &[u8]) -> Result<()> {
     match get_available_wal()? {
@@ -1240,24 +1831,87 @@ pub fn ack(consumer_id: &str, seq: u64) -> Result<()> {
 /// 执行垃圾回收 (支持降级)
 pub fn gc_if_needed() -> Result<()> {
     match get_available_wal()? {
-        WalInstance::Persistent(wal) => {
-            if let Some(min_seq) = wal.min_ack_seq()? {
-                // 保留最近1000个帧
-                if min_seq > 1000 {
-                    wal.gc(min_seq - 1000)?;
-                }
-            }
-        },
-        WalInstance::Memory(memory_wal) => {
-            if let Some(min_seq) = memory_wal.min_ack_seq()? {
-                // 内存模式下保留更多帧以提供更好的性能
-                if min_seq > 5000 {
-                    memory_wal.gc(min_seq - 5000)?;
-                }
-            }
-        },
+        WalInstance::Persistent(wal) => wal.gc_to_cursors(),
+        WalInstance::Memory(memory_wal) => memory_wal.gc_to_cursors(),
+    }
+}
+
+/// 扫描WAL恢复状态，仅首次调用生效，返回启动时的统计 (支持降级)
+pub fn recover() -> Result<RecoveryStats> {
+    if let Some(stats) = RECOVERY_STATS.get() {
+        return Ok(stats.clone());
+    }
+
+    let stats = match get_available_wal()? {
+        WalInstance::Persistent(wal) => wal.recovery_scan()?,
+        WalInstance::Memory(memory_wal) => memory_wal.recovery_scan()?,
+    };
+
+    METRICS.wal_recovered_frames.set(stats.frames_recovered as i64);
+    METRICS.wal_corrupt_frames.set(stats.corrupt_skipped as i64);
+    if stats.corrupt_skipped > 0 {
+        warn!("WAL recovery skipped {} corrupt records", stats.corrupt_skipped);
+    }
+
+    Ok(RECOVERY_STATS.get_or_init(|| stats).clone())
+}
+
+/// 获取启动恢复统计
+pub fn recovery_stats() -> Option<RecoveryStats> {
+    RECOVERY_STATS.get().cloned()
+}
+
+/// 是否已初始化WAL (持久化或内存)
+pub fn is_initialized() -> bool {
+    get_available_wal().is_ok()
+}
+
+/// 获取消费者已ACK的序列号 (支持降级)
+pub fn acked_seq(consumer_id: &str) -> Result<Option<u64>> {
+    match get_available_wal()? {
+        WalInstance::Persistent(wal) => wal.acked_seq(consumer_id),
+        WalInstance::Memory(memory_wal) => memory_wal.acked_seq(consumer_id),
+    }
+}
+
+/// 删除消费者游标 (支持降级)
+pub fn remove_consumer(consumer_id: &str) -> Result<()> {
+    match get_available_wal()? {
+        WalInstance::Persistent(wal) => wal.remove_consumer(consumer_id),
+        WalInstance::Memory(memory_wal) => memory_wal.remove_consumer(consumer_id),
+    }
+}
+
+/// 获取WAL中最早保留的序列号 (支持降级)
+pub fn min_frame_seq() -> Result<Option<u64>> {
+    match get_available_wal()? {
+        WalInstance::Persistent(wal) => wal.min_frame_seq(),
+        WalInstance::Memory(memory_wal) => memory_wal.min_frame_seq(),
+    }
+}
+
+/// 从指定序列号开始读取帧 (支持降级)
+pub fn read_from(from_seq: u64, limit: usize) -> Result<Vec<crate::FrameEnvelope>> {
+    match get_available_wal()? {
+        WalInstance::Persistent(wal) => wal.read_from(from_seq, limit),
+        WalInstance::Memory(memory_wal) => memory_wal.read_from(from_seq, limit),
+    }
+}
+
+/// 轮换WAL加密密钥并重写存量帧 (内存WAL不落盘，无需加密)
+pub async fn rotate_key(key: &[u8]) -> Result<u64> {
+    match get_available_wal()? {
+        WalInstance::Persistent(wal) => wal.rotate_key(key).await,
+        WalInstance::Memory(_) => Err(anyhow::anyhow!("In-memory WAL does not support encryption")),
+    }
+}
+
+/// 设置WAL保留策略告警钩子 (内存WAL不执行保留策略，忽略)
+pub fn set_retention_alert_sink(sink: RetentionAlertSink) -> Result<()> {
+    match get_available_wal()? {
+        WalInstance::Persistent(wal) => wal.set_retention_alert_sink(sink),
+        WalInstance::Memory(_) => debug!("In-memory WAL has no retention policy, alert sink ignored"),
     }
-    
     Ok(())
 }
 
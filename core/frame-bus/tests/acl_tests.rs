@@ -0,0 +1,88 @@
+//! 总线ACL测试
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use frame_bus::metrics::METRICS;
+use frame_bus::permissions::{clear_acl, ANONYMOUS_SUBJECT};
+use frame_bus::{AccessControlEntry, AclMode, CmdFrame, DataFrame, Filter, Permission, PermissionManager, Value};
+use tempfile::tempdir;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+
+/// ACL与FrameBus均为全局状态，串行执行
+static BUS_LOCK: Mutex<()> = Mutex::const_new(());
+
+fn perms(list: &[Permission]) -> HashSet<Permission> {
+    list.iter().copied().collect()
+}
+
+/// 驱动可写全部点位；桥接只能写plant1且不能写安全点位；HMI只能读plant1
+fn acl() -> Arc<PermissionManager> {
+    let manager = PermissionManager::new();
+    manager.add_ace(AccessControlEntry::allow("driver", "*", perms(&[Permission::Read, Permission::Write])));
+    manager.add_ace(AccessControlEntry::allow("bridge", "plant1.*", perms(&[Permission::Write])));
+    manager.add_ace(AccessControlEntry::deny("bridge", "plant1.safety.*", perms(&[Permission::Write])).with_priority(200));
+    manager.add_ace(AccessControlEntry::allow("hmi", "plant1.*", perms(&[Permission::Read])));
+    Arc::new(manager)
+}
+
+fn init_bus() -> frame_bus::FrameReceiver {
+    static WAL_DIR: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+    let dir = WAL_DIR.get_or_init(|| tempdir().unwrap().keep());
+    frame_bus::init(1024, dir).expect("Failed to init FrameBus");
+    frame_bus::subscribe(Filter::All).unwrap()
+}
+
+#[tokio::test]
+async fn test_enforced_acl_rejects_publish_and_withholds_frames() {
+    let _guard = BUS_LOCK.lock().await;
+    let _live = init_bus();
+    frame_bus::install_acl(acl(), AclMode::Enforce);
+
+    let bridge = frame_bus::publisher("bridge").unwrap();
+    let denied = METRICS.acl_publish_denied_total.get();
+
+    bridge.send_cmd(CmdFrame::new("plant1.valve", Value::bool(true), "bridge")).unwrap();
+    assert!(bridge.send_cmd(CmdFrame::new("plant1.safety.estop", Value::bool(false), "bridge")).is_err());
+    assert!(bridge.send_cmd(CmdFrame::new("plant2.valve", Value::bool(true), "bridge")).is_err());
+    // 未声明主体的句柄按匿名主体检查
+    assert!(frame_bus::publish_data(DataFrame::new("plant1.temp", Value::float(1.0))).is_err());
+    assert_eq!(METRICS.acl_publish_denied_total.get(), denied + 3.0);
+
+    let mut hmi = frame_bus::subscribe_as("hmi", Filter::data_only()).unwrap();
+    assert_eq!(hmi.subject(), Some("hmi"));
+    let driver = frame_bus::publisher("driver").unwrap();
+    let withheld = METRICS.acl_receive_denied_total.get();
+    driver.send_data(DataFrame::new("plant2.temp", Value::float(2.0))).unwrap();
+    driver.send_data(DataFrame::new("plant1.temp", Value::float(3.0))).unwrap();
+
+    let envelope = timeout(Duration::from_secs(5), hmi.recv()).await.unwrap().unwrap();
+    assert_eq!(envelope.tag(), "plant1.temp");
+    assert_eq!(METRICS.acl_receive_denied_total.get(), withheld + 1.0);
+
+    clear_acl();
+}
+
+#[tokio::test]
+async fn test_audit_mode_counts_but_allows() {
+    let _guard = BUS_LOCK.lock().await;
+    let _live = init_bus();
+    frame_bus::install_acl(acl(), AclMode::Audit);
+    assert_eq!(frame_bus::permissions::acl_mode(), Some(AclMode::Audit));
+
+    let denied = METRICS.acl_publish_denied_total.get();
+    frame_bus::publisher("bridge").unwrap()
+        .send_cmd(CmdFrame::new("plant1.safety.estop", Value::bool(false), "bridge"))
+        .unwrap();
+    frame_bus::publisher(ANONYMOUS_SUBJECT).unwrap()
+        .send_data_batch(vec![DataFrame::new("plant3.temp", Value::float(1.0))])
+        .unwrap();
+    assert_eq!(METRICS.acl_publish_denied_total.get(), denied + 2.0);
+
+    clear_acl();
+    assert_eq!(frame_bus::permissions::acl_mode(), None);
+    frame_bus::publish_data(DataFrame::new("plant1.safety.estop", Value::float(1.0))).unwrap();
+    assert_eq!(METRICS.acl_publish_denied_total.get(), denied + 2.0);
+}
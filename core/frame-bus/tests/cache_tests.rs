@@ -0,0 +1,93 @@
+//! 最新值缓存与快照订阅测试
+
+use frame_bus::{DataFrame, Filter, Value};
+use std::collections::HashMap;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::time::timeout;
+
+fn init_bus() {
+    static WAL_DIR: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+    let dir = WAL_DIR.get_or_init(|| tempdir().unwrap().keep());
+    frame_bus::init(4096, dir).expect("Failed to init FrameBus");
+}
+
+fn int_value(value: &Option<Value>) -> i64 {
+    match value.as_ref().and_then(|v| v.value.as_ref()) {
+        Some(frame_bus::envelope::value::Value::IntV(v)) => *v,
+        other => panic!("unexpected value {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_last_value_and_snapshot_filter() {
+    init_bus();
+    let _keepalive = frame_bus::subscribe(Filter::All).unwrap();
+
+    frame_bus::publish_data(DataFrame::new("lvc.plant1.temp", Value::int(20)).with_meta("unit", "celsius")).unwrap();
+    frame_bus::publish_data(DataFrame::new("lvc.plant1.temp", Value::int(21)).with_meta("unit", "celsius")).unwrap();
+    frame_bus::publish_data(DataFrame::new("lvc.plant2.flow", Value::int(7)).with_qos(1)).unwrap();
+
+    let temp = frame_bus::last_value("lvc.plant1.temp").expect("temp not cached");
+    assert_eq!(int_value(&temp.value), 21);
+    assert_eq!(temp.meta.get("unit").map(String::as_str), Some("celsius"));
+    assert_eq!(frame_bus::last_value("lvc.plant2.flow").unwrap().qos, 1);
+    assert!(frame_bus::last_value("lvc.unknown").is_none());
+
+    let (snapshot, _rx) = frame_bus::subscribe_with_snapshot(Filter::tag_pattern("lvc.plant1.#").unwrap()).unwrap();
+    assert!(snapshot.values.contains_key("lvc.plant1.temp"));
+    assert!(!snapshot.values.contains_key("lvc.plant2.flow"));
+    assert!(snapshot.seq >= Some(temp.seq));
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_snapshot_then_tail_has_no_gaps() {
+    const TAGS: usize = 4;
+    const FRAMES: i64 = 500;
+
+    init_bus();
+    let _keepalive = frame_bus::subscribe(Filter::All).unwrap();
+    let filter = Filter::tag_starts_with("gapless.");
+
+    // 每个点位由单独线程按0..FRAMES递增发布
+    let publishers: Vec<_> = (0..TAGS)
+        .map(|i| std::thread::spawn(move || {
+            for v in 0..FRAMES {
+                frame_bus::publish_data(DataFrame::new(format!("gapless.tag{}", i), Value::int(v))).unwrap();
+            }
+        }))
+        .collect();
+
+    std::thread::sleep(Duration::from_millis(2));
+    let (snapshot, mut rx) = frame_bus::subscribe_with_snapshot(filter).unwrap();
+    for publisher in publishers {
+        publisher.join().unwrap();
+    }
+
+    // 快照值之后的第一帧必须紧接快照值，之后逐一递增
+    let mut next: HashMap<String, i64> = (0..TAGS)
+        .map(|i| format!("gapless.tag{}", i))
+        .map(|tag| {
+            let start = snapshot.values.get(&tag).map_or(0, |cached| int_value(&cached.value) + 1);
+            (tag, start)
+        })
+        .collect();
+    let mut remaining: i64 = next.values().map(|start| FRAMES - start).sum();
+
+    while remaining > 0 {
+        let envelope = timeout(Duration::from_secs(5), rx.recv()).await
+            .expect("Timed out waiting for tail frame")
+            .expect("Receive failed");
+        let frame = envelope.into_data().unwrap();
+        let expected = next.get_mut(&frame.tag).unwrap();
+        assert_eq!(int_value(&frame.value), *expected, "gap or duplicate on {}", frame.tag);
+        *expected += 1;
+        remaining -= 1;
+    }
+    assert!(next.values().all(|v| *v == FRAMES));
+
+    for i in 0..TAGS {
+        let cached = frame_bus::last_value(&format!("gapless.tag{}", i)).unwrap();
+        assert_eq!(int_value(&cached.value), FRAMES - 1);
+    }
+}
@@ -0,0 +1,199 @@
+//! FrameBus持久订阅测试
+
+use frame_bus::wal::WalConfig;
+use frame_bus::{DataFrame, DurableOptions, DurableReceiver, Filter, FrameEnvelope, StartFrom, Value, WAL};
+use prost::Message;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+
+/// 全局FrameBus在测试间共享，串行执行避免相互干扰
+static BUS_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// 初始化小容量环形缓冲区的全局FrameBus，便于触发Lagged
+///
+/// 返回的普通订阅保持总线上始终有接收端，持久消费者离线时发布不会失败
+fn init_bus() -> frame_bus::FrameReceiver {
+    static WAL_DIR: OnceLock<PathBuf> = OnceLock::new();
+    let dir = WAL_DIR.get_or_init(|| tempdir().unwrap().keep());
+    frame_bus::init(16, dir).expect("Failed to init FrameBus");
+    frame_bus::subscribe(Filter::All).unwrap()
+}
+
+fn publish(prefix: &str, range: std::ops::Range<i64>) {
+    for i in range {
+        frame_bus::publish_data(DataFrame::new(format!("{}.{}", prefix, i), Value::int(i))).unwrap();
+    }
+}
+
+async fn recv_value(rx: &mut DurableReceiver) -> (FrameEnvelope, i64) {
+    let envelope = timeout(Duration::from_secs(5), rx.recv()).await
+        .expect("Timed out waiting for frame")
+        .expect("Durable receive failed");
+    let frame = DataFrame::decode(&envelope.payload[..]).unwrap();
+    let value = frame.value.unwrap().to_i64().unwrap();
+    (envelope, value)
+}
+
+#[tokio::test]
+async fn test_lagging_consumer_catches_up_from_wal() {
+    let _guard = BUS_LOCK.lock().await;
+    let _live = init_bus();
+
+    let mut rx = frame_bus::subscribe_durable("lag-consumer", Filter::TagPrefix("lag.".into())).unwrap();
+
+    // 远超环形缓冲区容量，实时接收必然Lagged
+    publish("lag", 0..100);
+
+    let mut last_seq = None;
+    for expected in 0..100 {
+        let (envelope, value) = recv_value(&mut rx).await;
+        assert_eq!(value, expected);
+        assert!(last_seq.is_none_or(|seq| envelope.seq > seq));
+        last_seq = Some(envelope.seq);
+    }
+
+    // 追平后继续接收实时帧
+    publish("lag", 100..101);
+    assert_eq!(recv_value(&mut rx).await.1, 100);
+}
+
+#[tokio::test]
+async fn test_resume_from_acked_cursor() {
+    let _guard = BUS_LOCK.lock().await;
+    let _live = init_bus();
+
+    let filter = Filter::TagPrefix("resume.".into());
+    let mut rx = frame_bus::subscribe_durable("resume-consumer", filter.clone()).unwrap();
+    publish("resume", 0..10);
+
+    let mut acked = 0;
+    for expected in 0..5 {
+        let (envelope, value) = recv_value(&mut rx).await;
+        assert_eq!(value, expected);
+        acked = envelope.seq;
+    }
+    rx.ack(acked).unwrap();
+    drop(rx);
+
+    // 消费者离线期间继续发布
+    publish("resume", 10..15);
+
+    let mut rx = frame_bus::subscribe_durable("resume-consumer", filter).unwrap();
+    assert_eq!(rx.acked(), Some(acked));
+    for expected in 5..15 {
+        assert_eq!(recv_value(&mut rx).await.1, expected);
+    }
+
+    frame_bus::remove_durable("resume-consumer").unwrap();
+    assert_eq!(frame_bus::wal::acked_seq("resume-consumer").unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_new_consumer_start_position() {
+    let _guard = BUS_LOCK.lock().await;
+    let _live = init_bus();
+
+    publish("start", 0..3);
+
+    let filter = Filter::TagPrefix("start.".into());
+    let mut latest = frame_bus::subscribe_durable("start-latest", filter.clone()).unwrap();
+    let mut earliest = frame_bus::subscribe_durable_with(
+        "start-earliest",
+        filter,
+        DurableOptions { start: StartFrom::Earliest, ..Default::default() },
+    ).unwrap();
+
+    // 从最早位置开始的消费者同样登记游标，保留其尚未消费的帧
+    let registered = frame_bus::wal::acked_seq("start-earliest").unwrap().expect("earliest cursor must be registered");
+    assert!(registered <= frame_bus::wal::min_frame_seq().unwrap().unwrap_or(0));
+
+    publish("start", 3..4);
+
+    assert_eq!(recv_value(&mut latest).await.1, 3);
+    for expected in 0..4 {
+        assert_eq!(recv_value(&mut earliest).await.1, expected);
+    }
+}
+
+#[tokio::test]
+async fn test_out_of_order_live_frames_fill_gap() {
+    let _guard = BUS_LOCK.lock().await;
+    let _live = init_bus();
+
+    let mut rx = frame_bus::subscribe_durable("gap-consumer", Filter::TagPrefix("gap.".into())).unwrap();
+    let mut drained = frame_bus::subscribe_durable("gap-drain", Filter::TagPrefix("gap.".into())).unwrap();
+
+    // 预留三个序列号，首尾两帧乱序到达，中间一帧稍后才落库
+    let base = frame_bus::ring::current_sequence();
+    frame_bus::ring::restore_sequence(base + 3);
+    let envelopes: Vec<_> = (0..3)
+        .map(|i| FrameEnvelope::wrap_data(base + i, DataFrame::new(format!("gap.{}", i), Value::int(i as i64))).unwrap())
+        .collect();
+    let sender = frame_bus::ring::get_instance().unwrap().get_sender();
+    sender.send(envelopes[2].clone()).unwrap();
+    sender.send(envelopes[0].clone()).unwrap();
+
+    assert_eq!(recv_value(&mut rx).await.1, 2);
+    assert_eq!(recv_value(&mut rx).await.1, 0);
+    for envelope in &envelopes {
+        frame_bus::wal::write_frame(envelope.seq, &envelope.encode_to_vec()).unwrap();
+    }
+
+    // 缺口未补齐前ACK不越过投递水位
+    rx.ack(base + 2).unwrap();
+    assert_eq!(frame_bus::wal::acked_seq("gap-consumer").unwrap(), Some(base));
+
+    // 缺口超时后从WAL补齐，已投递的帧不重复
+    assert_eq!(recv_value(&mut rx).await.1, 1);
+    rx.ack(base + 2).unwrap();
+    assert_eq!(frame_bus::wal::acked_seq("gap-consumer").unwrap(), Some(base + 2));
+
+    publish("gap", 3..4);
+    assert_eq!(recv_value(&mut rx).await.1, 3);
+    // 稍后才接收的消费者从WAL按序回放，实时通道中缓冲的同一批帧不重复投递
+    for expected in 0..4 {
+        assert_eq!(recv_value(&mut drained).await.1, expected);
+    }
+
+    frame_bus::remove_durable("gap-consumer").unwrap();
+    frame_bus::remove_durable("gap-drain").unwrap();
+}
+
+#[tokio::test]
+async fn test_wal_gc_driven_by_slowest_cursor() {
+    let temp_dir = tempdir().unwrap();
+    let config = WalConfig { retain_frames: 10, ..Default::default() };
+    let wal = WAL::with_config(temp_dir.path(), config).await.unwrap();
+
+    for seq in 0..100u64 {
+        let envelope = FrameEnvelope::wrap_data(seq, DataFrame::new("gc.test", Value::int(seq as i64))).unwrap();
+        wal.append(&envelope).await.unwrap();
+    }
+
+    // 无游标时只保留最近的帧
+    wal.gc_to_cursors().await.unwrap();
+    assert_eq!(wal.get_min_sequence().await.unwrap(), Some(89));
+
+    let wal_dir = tempdir().unwrap();
+    let wal = WAL::with_config(wal_dir.path(), WalConfig { retain_frames: 10, ..Default::default() }).await.unwrap();
+    for seq in 0..100u64 {
+        let envelope = FrameEnvelope::wrap_data(seq, DataFrame::new("gc.test", Value::int(seq as i64))).unwrap();
+        wal.append(&envelope).await.unwrap();
+    }
+    wal.ack("fast", 90).await.unwrap();
+    wal.ack("slow", 40).await.unwrap();
+
+    // 最慢的游标决定GC下界
+    wal.gc_to_cursors().await.unwrap();
+    assert_eq!(wal.get_min_sequence().await.unwrap(), Some(30));
+    assert_eq!(wal.read_from(30, 5).await.unwrap().iter().map(|e| e.seq).collect::<Vec<_>>(), vec![30, 31, 32, 33, 34]);
+
+    wal.ack("slow", 95).await.unwrap();
+    wal.gc_to_cursors().await.unwrap();
+    assert_eq!(wal.get_min_sequence().await.unwrap(), Some(80));
+    assert_eq!(wal.acked_seq("slow").await.unwrap(), Some(95));
+}
@@ -0,0 +1,113 @@
+//! 本地IPC传输测试
+#![cfg(unix)]
+
+use frame_bus::control::{self, ControlMsg};
+use frame_bus::{DataFrame, Filter, IpcClient, IpcClientConfig, IpcServer, IpcServerConfig, Transport, Value};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::time::timeout;
+
+fn init_bus() {
+    static WAL_DIR: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+    let dir = WAL_DIR.get_or_init(|| tempdir().unwrap().keep());
+    frame_bus::init(4096, dir).expect("Failed to init FrameBus");
+}
+
+#[tokio::test]
+async fn test_frames_cross_process_boundary() {
+    init_bus();
+    let _keepalive = frame_bus::subscribe(Filter::All).unwrap();
+    let dir = tempdir().unwrap();
+    let server = IpcServer::bind(dir.path().join("bus.sock")).unwrap();
+    let socket = server.path().to_path_buf();
+    let _server = server.spawn();
+
+    let client = IpcClient::connect_with(&socket, IpcClientConfig {
+        patterns: vec!["ipc.xfer.#".to_string()],
+        ..Default::default()
+    }).await.unwrap();
+    let mut remote_rx = client.subscribe(Filter::tag_starts_with("ipc.xfer.plant1.")).unwrap();
+
+    // 网关 -> 子进程：服务端按模式转发，客户端本地再按Filter过滤
+    frame_bus::publish_data(DataFrame::new("ipc.other.temp", Value::int(1))).unwrap();
+    frame_bus::publish_data(DataFrame::new("ipc.xfer.plant2.temp", Value::int(2))).unwrap();
+    frame_bus::publish_data(DataFrame::new("ipc.xfer.plant1.temp", Value::int(3))).unwrap();
+    let envelope = timeout(Duration::from_secs(2), remote_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(envelope.tag(), "ipc.xfer.plant1.temp");
+
+    // 子进程 -> 网关：由网关总线分配序列号
+    let mut local_rx = frame_bus::subscribe(Filter::tag_starts_with("ipc.up.")).unwrap();
+    client.send_data(DataFrame::new("ipc.up.flow", Value::int(42))).unwrap();
+    let envelope = timeout(Duration::from_secs(2), local_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(envelope.tag(), "ipc.up.flow");
+    assert!(envelope.seq > 0);
+    assert!(client.is_connected());
+}
+
+#[tokio::test]
+async fn test_control_messages_forwarded() {
+    init_bus();
+    let _keepalive = frame_bus::subscribe(Filter::All).unwrap();
+    let dir = tempdir().unwrap();
+    let server = IpcServer::bind(dir.path().join("ctl.sock")).unwrap();
+    let socket = server.path().to_path_buf();
+    let _server = server.spawn();
+
+    let client = IpcClient::connect(&socket, "").await.unwrap();
+    let mut control_rx = client.subscribe_control().unwrap();
+
+    control::init_control();
+    control::send_pause();
+    let msg = timeout(Duration::from_secs(2), control_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(msg, ControlMsg::Pause);
+    assert!(client.is_paused());
+
+    control::send_resume();
+    let msg = timeout(Duration::from_secs(2), control_rx.recv()).await.unwrap().unwrap();
+    assert_eq!(msg, ControlMsg::Resume);
+    assert!(!client.is_paused());
+}
+
+#[tokio::test]
+async fn test_stale_socket_replaced() {
+    init_bus();
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("stale.sock");
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    let server = IpcServer::bind(&path).unwrap();
+    drop(server);
+    assert!(!path.exists());
+    // 只留下目标套接字，不残留临时目录
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[tokio::test]
+async fn test_non_socket_path_not_removed() {
+    init_bus();
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("config.yaml");
+    std::fs::write(&path, b"keep").unwrap();
+
+    assert!(IpcServer::bind(&path).is_err());
+    assert_eq!(std::fs::read(&path).unwrap(), b"keep");
+}
+
+#[tokio::test]
+async fn test_peer_credentials_restrict_subject() {
+    init_bus();
+    let dir = tempdir().unwrap();
+    let uid = std::fs::metadata(dir.path()).unwrap().uid();
+    let config = IpcServerConfig::default().parse_peers(&format!("{}=ipc.driver.a", uid)).unwrap();
+    let server = IpcServer::bind_with(dir.path().join("auth.sock"), config).unwrap();
+    let socket = server.path().to_path_buf();
+    assert_eq!(std::fs::metadata(&socket).unwrap().permissions().mode() & 0o777, 0o600);
+    let _server = server.spawn();
+
+    // 只允许对端uid声明已授权的主体
+    assert!(IpcClient::connect(&socket, "ipc.driver.a").await.is_ok());
+    assert!(IpcClient::connect(&socket, "ipc.driver.b").await.is_err());
+    assert!(IpcClient::connect(&socket, "").await.is_err());
+}
@@ -0,0 +1,86 @@
+//! FrameBus崩溃恢复测试
+//!
+//! 测试进程以子进程方式重新执行自身：子进程发布、部分ACK后直接abort，
+//! 父进程在同一WAL目录上重新初始化总线并验证恢复结果。
+
+use frame_bus::{DataFrame, Filter, Value};
+use prost::Message;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use tokio::time::timeout;
+
+/// 子进程模式的WAL目录环境变量
+const CRASH_CHILD_ENV: &str = "FRAMEBUS_CRASH_CHILD_WAL_DIR";
+const CONSUMER: &str = "recovery-consumer";
+const PUBLISHED: i64 = 20;
+const ACKED: i64 = 8;
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap()
+}
+
+/// 子进程：发布帧、消费并ACK前一部分，写入一条损坏记录后模拟崩溃
+fn crash_child(wal_dir: &Path) -> ! {
+    runtime().block_on(async {
+        frame_bus::init(1024, wal_dir).unwrap();
+        let _live = frame_bus::subscribe(Filter::All).unwrap();
+        let mut rx = frame_bus::subscribe_durable(CONSUMER, Filter::TagPrefix("crash.".into())).unwrap();
+
+        for i in 0..PUBLISHED {
+            frame_bus::publish_data(DataFrame::new(format!("crash.{}", i), Value::int(i))).unwrap();
+        }
+        for _ in 0..ACKED {
+            let envelope = rx.recv().await.unwrap();
+            rx.ack(envelope.seq).unwrap();
+        }
+
+        // 写入一半的记录
+        frame_bus::wal::write_frame(frame_bus::ring::current_sequence(), b"\xff\xfftruncated").unwrap();
+    });
+    std::process::abort()
+}
+
+#[test]
+fn test_crash_recovery_redelivers_unacked_frames() {
+    if let Ok(dir) = std::env::var(CRASH_CHILD_ENV) {
+        crash_child(Path::new(&dir));
+    }
+
+    let wal_dir = tempfile::tempdir().unwrap();
+    let status = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "test_crash_recovery_redelivers_unacked_frames", "--nocapture"])
+        .env(CRASH_CHILD_ENV, wal_dir.path())
+        .status()
+        .unwrap();
+    assert!(!status.success(), "child process should have aborted");
+
+    runtime().block_on(async {
+        frame_bus::init(1024, wal_dir.path()).unwrap();
+
+        let stats = frame_bus::recovery_stats().expect("recovery stats recorded at init");
+        assert_eq!(stats.frames_recovered, PUBLISHED as u64);
+        assert_eq!(stats.corrupt_skipped, 1);
+        assert_eq!(stats.min_seq, Some(0));
+        assert_eq!(stats.max_seq, Some(PUBLISHED as u64));
+        assert_eq!(stats.consumers, 1);
+        assert_eq!(stats.unacked_frames, (PUBLISHED - ACKED) as u64);
+
+        // 序列号从损坏记录之后继续，不覆盖已持久化的帧
+        assert_eq!(frame_bus::ring::current_sequence(), PUBLISHED as u64 + 1);
+
+        let mut live = frame_bus::subscribe(Filter::All).unwrap();
+        let mut rx = frame_bus::subscribe_durable(CONSUMER, Filter::TagPrefix("crash.".into())).unwrap();
+        assert_eq!(rx.acked(), Some(ACKED as u64 - 1));
+
+        frame_bus::publish_data(DataFrame::new("crash.restarted", Value::int(PUBLISHED))).unwrap();
+        assert_eq!(live.recv().await.unwrap().seq, PUBLISHED as u64 + 1);
+
+        // 未ACK的帧按序重投，随后是重启后的新帧
+        for expected in ACKED..=PUBLISHED {
+            let envelope = timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+            let frame = DataFrame::decode(&envelope.payload[..]).unwrap();
+            assert_eq!(frame.value.unwrap().to_i64(), Some(expected));
+        }
+    });
+}
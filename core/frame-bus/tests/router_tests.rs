@@ -0,0 +1,84 @@
+//! 点位路由订阅测试
+
+use frame_bus::{DataFrame, Filter, Value};
+use std::time::Duration;
+use tempfile::tempdir;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+
+/// 全局FrameBus在测试间共享，串行执行
+static BUS_LOCK: Mutex<()> = Mutex::const_new(());
+
+fn init_bus() {
+    static WAL_DIR: std::sync::OnceLock<std::path::PathBuf> = std::sync::OnceLock::new();
+    let dir = WAL_DIR.get_or_init(|| tempdir().unwrap().keep());
+    frame_bus::init(16, dir).expect("Failed to init FrameBus");
+}
+
+async fn recv_tag(rx: &mut frame_bus::RoutedReceiver) -> String {
+    let envelope = timeout(Duration::from_secs(5), rx.recv()).await
+        .expect("Timed out waiting for frame")
+        .expect("Routed receive failed");
+    envelope.tag().into_owned()
+}
+
+#[tokio::test]
+async fn test_routes_only_matching_frames() {
+    let _guard = BUS_LOCK.lock().await;
+    init_bus();
+
+    let mut temps = frame_bus::subscribe_routed(["plant1.+.temp"], Filter::All).unwrap();
+    let mut celsius = frame_bus::subscribe_routed(["plant1.#", "plant2.line1.temp"], Filter::meta_eq("unit", "celsius")).unwrap();
+    assert!(frame_bus::subscribe_routed(["plant1.#.temp"], Filter::All).is_err());
+
+    for (tag, unit) in [
+        ("plant1.line1.temp", "celsius"),
+        ("plant1.line1.pressure", "bar"),
+        ("plant1.line2.temp", "kelvin"),
+        ("plant2.line1.temp", "celsius"),
+        ("plant3.line1.temp", "celsius"),
+        ("plant1", "celsius"),
+    ] {
+        frame_bus::publish_data(DataFrame::new(tag, Value::float(1.0)).with_meta("unit", unit)).unwrap();
+    }
+
+    assert_eq!(recv_tag(&mut temps).await, "plant1.line1.temp");
+    assert_eq!(recv_tag(&mut temps).await, "plant1.line2.temp");
+    assert_eq!(recv_tag(&mut celsius).await, "plant1.line1.temp");
+    assert_eq!(recv_tag(&mut celsius).await, "plant2.line1.temp");
+    assert_eq!(recv_tag(&mut celsius).await, "plant1");
+
+    // 不匹配的帧从未投递
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(timeout(Duration::from_millis(50), temps.recv()).await.is_err());
+}
+
+#[tokio::test]
+async fn test_slow_subscriber_reports_lag_and_unsubscribes_on_drop() {
+    let _guard = BUS_LOCK.lock().await;
+    init_bus();
+
+    let router = frame_bus::router::get_router().unwrap();
+    let before = router.subscriber_count();
+    let mut rx = frame_bus::subscribe_routed(["lag.#"], Filter::All).unwrap();
+    assert_eq!(router.subscriber_count(), before + 1);
+
+    // 远超订阅队列容量
+    for i in 0..64 {
+        frame_bus::publish_data(DataFrame::new(format!("lag.{}", i), Value::int(i))).unwrap();
+        if i % 8 == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    match rx.recv().await {
+        Err(RecvError::Lagged(skipped)) => assert!(skipped > 0),
+        other => panic!("expected lag, got {:?}", other.map(|e| e.seq)),
+    }
+    assert!(recv_tag(&mut rx).await.starts_with("lag."));
+
+    drop(rx);
+    assert_eq!(router.subscriber_count(), before);
+}
@@ -338,4 +338,269 @@ async fn test_wal_corruption_recovery() {
             }
         }
     }
-}
\ No newline at end of file
+}
+/// 递归检查目录下的文件是否包含指定字节序列
+fn dir_contains(dir: &std::path::Path, needle: &[u8]) -> bool {
+    std::fs::read_dir(dir).unwrap().flatten().any(|entry| {
+        let path = entry.path();
+        if path.is_dir() {
+            dir_contains(&path, needle)
+        } else {
+            std::fs::read(&path).is_ok_and(|data| data.windows(needle.len()).any(|w| w == needle))
+        }
+    })
+}
+
+fn codec_config(key_file: &std::path::Path, retired: &[&std::path::Path]) -> frame_bus::WalConfig {
+    frame_bus::WalConfig {
+        codec: frame_bus::WalCodecConfig {
+            compression: true,
+            key_file: Some(key_file.to_path_buf()),
+            retired_key_files: retired.iter().map(|p| p.to_path_buf()).collect(),
+        },
+        ..Default::default()
+    }
+}
+
+async fn append_secret_frames(wal: &WAL, range: std::ops::RangeInclusive<u64>) {
+    for i in range {
+        let frame = DataFrame::new(format!("secret.reactor.core_temperature.{}", i), Value::int(i as i64));
+        let envelope = FrameEnvelope::wrap_data(i, frame).expect("Failed to wrap frame");
+        wal.append(&envelope).await.expect("Failed to append to WAL");
+    }
+    wal.force_sync().await.expect("Failed to flush WAL");
+}
+
+#[tokio::test]
+async fn test_wal_encrypted_at_rest() {
+    let needle = b"secret.reactor.core_temperature";
+    let key_dir = tempdir().unwrap();
+    let key_file = key_dir.path().join("wal.key");
+    std::fs::write(&key_file, [0x5a; 32]).unwrap();
+
+    // 对照组：明文WAL中点位名可见，说明扫描能看到落盘数据
+    let plain_dir = tempdir().unwrap();
+    let plain = WAL::new(plain_dir.path()).await.unwrap();
+    append_secret_frames(&plain, 1..=10).await;
+    assert!(dir_contains(plain_dir.path(), needle));
+
+    let wal_dir = tempdir().unwrap();
+    let wal = WAL::with_config(wal_dir.path(), codec_config(&key_file, &[])).await.unwrap();
+    append_secret_frames(&wal, 1..=10).await;
+    assert!(!dir_contains(wal_dir.path(), needle), "plaintext tag found in encrypted WAL");
+
+    // 读取与恢复透明解密
+    let payload = wal.read_frame(3).await.unwrap().expect("frame 3 missing");
+    let envelope = FrameEnvelope::decode(&payload[..]).unwrap();
+    assert_eq!(envelope.tag(), "secret.reactor.core_temperature.3");
+
+    let recovered = wal.recover_all().await.unwrap();
+    assert_eq!(recovered.len(), 10);
+    assert!(recovered.iter().enumerate().all(|(i, e)| e.seq == i as u64 + 1));
+
+    // 错误的密钥无法读出任何帧
+    drop(wal);
+    let wrong_key = key_dir.path().join("wrong.key");
+    std::fs::write(&wrong_key, [0x11; 32]).unwrap();
+    let wal = WAL::with_config(wal_dir.path(), codec_config(&wrong_key, &[])).await.unwrap();
+    assert!(wal.recover_all().await.unwrap().is_empty());
+    assert!(wal.read_frame(3).await.is_err());
+}
+
+#[tokio::test]
+async fn test_wal_key_rotation_keeps_unreplayed_frames() {
+    let key_dir = tempdir().unwrap();
+    let old_key = key_dir.path().join("old.key");
+    let new_key = key_dir.path().join("new.key");
+    std::fs::write(&old_key, [0x01; 32]).unwrap();
+    std::fs::write(&new_key, [0x02; 32]).unwrap();
+
+    let wal_dir = tempdir().unwrap();
+    {
+        let wal = WAL::with_config(wal_dir.path(), codec_config(&old_key, &[])).await.unwrap();
+        append_secret_frames(&wal, 1..=5).await;
+    }
+
+    // 新密钥为活动密钥、旧密钥保留解密：存量帧可读，新帧用新密钥
+    {
+        let wal = WAL::with_config(wal_dir.path(), codec_config(&new_key, &[&old_key])).await.unwrap();
+        assert_eq!(wal.recover_all().await.unwrap().len(), 5);
+        append_secret_frames(&wal, 6..=8).await;
+        assert_eq!(wal.rekey().await.unwrap(), 5);
+        assert_eq!(wal.rekey().await.unwrap(), 0);
+        wal.force_sync().await.unwrap();
+    }
+
+    // 重写完成后旧密钥可以移除
+    let wal = WAL::with_config(wal_dir.path(), codec_config(&new_key, &[])).await.unwrap();
+    assert_eq!(wal.recover_all().await.unwrap().len(), 8);
+
+    // 运行时轮换：新密钥写入密钥文件，重写完成后不保留旧密钥
+    let newest = [0x03; 32];
+    assert_eq!(wal.rotate_key(&newest).await.unwrap(), 8);
+    assert_eq!(std::fs::read(&new_key).unwrap(), newest);
+    assert!(frame_bus::codec::rotated_key_files(&new_key).unwrap().is_empty());
+    drop(wal);
+    let wal = WAL::with_config(wal_dir.path(), codec_config(&new_key, &[])).await.unwrap();
+    assert_eq!(wal.recover_all().await.unwrap().len(), 8);
+}
+
+#[tokio::test]
+async fn test_wal_encryption_migrates_plaintext_frames() {
+    let key_dir = tempdir().unwrap();
+    let key_file = key_dir.path().join("wal.key");
+    std::fs::write(&key_file, [0x04; 32]).unwrap();
+
+    let wal_dir = tempdir().unwrap();
+    {
+        let wal = WAL::new(wal_dir.path()).await.unwrap();
+        append_secret_frames(&wal, 1..=5).await;
+    }
+
+    // 启用加密后存量明文帧在打开时重写为加密记录，仍可读取
+    {
+        let wal = WAL::with_config(wal_dir.path(), codec_config(&key_file, &[])).await.unwrap();
+        assert_eq!(wal.rekey().await.unwrap(), 0);
+        append_secret_frames(&wal, 6..=8).await;
+        assert_eq!(wal.recover_all().await.unwrap().len(), 8);
+    }
+
+    // 运行时轮换后只凭新密钥即可读出全部帧
+    let wal = WAL::with_config(wal_dir.path(), codec_config(&key_file, &[])).await.unwrap();
+    assert_eq!(wal.rotate_key(&[0x05; 32]).await.unwrap(), 8);
+    drop(wal);
+    let wal = WAL::with_config(wal_dir.path(), codec_config(&key_file, &[])).await.unwrap();
+    assert_eq!(wal.recover_all().await.unwrap().len(), 8);
+}
+
+#[tokio::test]
+async fn test_wal_key_rotation_survives_crash_before_rekey() {
+    use frame_bus::codec::{key_id, retired_key_path, write_key_file};
+
+    let key_dir = tempdir().unwrap();
+    let key_file = key_dir.path().join("wal.key");
+    std::fs::write(&key_file, [0x01; 32]).unwrap();
+
+    let wal_dir = tempdir().unwrap();
+    {
+        let wal = WAL::with_config(wal_dir.path(), codec_config(&key_file, &[])).await.unwrap();
+        append_secret_frames(&wal, 1..=5).await;
+    }
+
+    // 模拟轮换在密钥文件替换后、存量帧重写前崩溃
+    write_key_file(&retired_key_path(&key_file, key_id(&[0x01; 32])), &[0x01; 32]).unwrap();
+    write_key_file(&key_file, &[0x02; 32]).unwrap();
+
+    let wal = WAL::with_config(wal_dir.path(), codec_config(&key_file, &[])).await.unwrap();
+    assert_eq!(wal.recover_all().await.unwrap().len(), 5);
+    assert_eq!(wal.rekey().await.unwrap(), 5);
+}
+
+#[tokio::test]
+async fn test_wal_retention_sheds_low_value_frames_first() {
+    use frame_bus::retention::{RetentionAlert, ALARM_META_KEY};
+    use frame_bus::{CmdFrame, RetentionConfig};
+    use std::sync::{Arc, Mutex};
+
+    let mut envelopes = Vec::new();
+    // 1..=40：qos=0的高频噪声点位
+    for i in 1..=40u64 {
+        let frame = DataFrame::new("line1.vibration", Value::int(i as i64)).with_qos(0);
+        envelopes.push(FrameEnvelope::wrap_data(i, frame).unwrap());
+    }
+    // 41..=50：普通点位，每个点位一帧
+    for i in 41..=50u64 {
+        let frame = DataFrame::new(format!("line1.temperature.{}", i), Value::float(20.0));
+        envelopes.push(FrameEnvelope::wrap_data(i, frame).unwrap());
+    }
+    // 51..=55：告警帧；56：命令帧
+    for i in 51..=55u64 {
+        let frame = DataFrame::new("line1.pressure", Value::float(9.9)).with_meta(ALARM_META_KEY, "high");
+        envelopes.push(FrameEnvelope::wrap_data(i, frame).unwrap());
+    }
+    envelopes.push(FrameEnvelope::wrap_cmd(56, CmdFrame::new("line1.valve", Value::bool(false), "north")).unwrap());
+
+    // 配额只容得下全部普通/受保护帧和四分之一的噪声帧
+    let record_bytes = |e: &FrameEnvelope| e.encoded_len() as u64 + 8;
+    let kept: u64 = envelopes[40..].iter().map(record_bytes).sum();
+    let noise: u64 = envelopes[..40].iter().map(record_bytes).sum();
+    let config = frame_bus::WalConfig {
+        retention: RetentionConfig {
+            max_bytes: Some(kept + noise / 4 + 16),
+            shed_watermark: 1.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    let wal_dir = tempdir().unwrap();
+    let wal = WAL::with_config(wal_dir.path(), config).await.unwrap();
+    let alerts: Arc<Mutex<Vec<RetentionAlert>>> = Arc::default();
+    let sink = alerts.clone();
+    wal.set_retention_alert_sink(Arc::new(move |alert: &RetentionAlert| sink.lock().unwrap().push(alert.clone())));
+
+    wal.append_batch(&envelopes).await.unwrap();
+    // 上行消费者只确认到第1帧，其余均未回放
+    wal.ack("mqtt-uplink", 1).await.unwrap();
+
+    let report = wal.enforce_retention().await.unwrap();
+    assert!(report.bytes_after <= kept + noise / 4 + 16);
+    assert_eq!(report.acked, 1);
+    assert!(report.decimated >= 29);
+    assert_eq!(report.shed_normal, 0);
+    assert_eq!(report.shed_protected, 0);
+
+    // 告警、命令与普通帧全部保留，噪声点位只剩抽稀后的样本
+    let remaining = wal.recover_all().await.unwrap();
+    for seq in 41..=56 {
+        assert!(remaining.iter().any(|e| e.seq == seq), "frame {} was shed", seq);
+    }
+    let noise_left = remaining.iter().filter(|e| e.seq <= 40).count() as u64;
+    assert_eq!(noise_left, 40 - report.deleted());
+
+    // 删除未回放数据前发出告警
+    {
+        let alerts = alerts.lock().unwrap();
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].frames, report.unreplayed);
+        assert_eq!(alerts[0].min_ack, Some(1));
+    }
+
+    let stats = wal.get_performance_stats().retention;
+    assert_eq!(stats.runs, 1);
+    assert_eq!(stats.alerts_raised, 1);
+    assert_eq!(stats.decimated_frames, report.decimated);
+    assert_eq!(stats.last_report, Some(report));
+
+    // 已在配额内，再次执行不删除任何帧
+    assert_eq!(wal.enforce_retention().await.unwrap().deleted(), 0);
+}
+
+#[tokio::test]
+async fn test_wal_retention_keeps_undecodable_frames() {
+    use frame_bus::RetentionConfig;
+
+    let config = frame_bus::WalConfig {
+        retention: RetentionConfig { max_age: Some(std::time::Duration::from_secs(60)), ..Default::default() },
+        ..Default::default()
+    };
+    let wal_dir = tempdir().unwrap();
+    let wal = WAL::with_config(wal_dir.path(), config).await.unwrap();
+
+    // 无法解码的记录：时间未知，不能按过期删除
+    wal.write_frame_sync(1, b"not an envelope").unwrap();
+    let fresh = FrameEnvelope::wrap_data(2, DataFrame::new("line1.temperature", Value::float(20.0))).unwrap();
+    wal.append(&fresh).await.unwrap();
+    assert_eq!(wal.enforce_retention().await.unwrap().deleted(), 0);
+
+    // 索引加载后写入的过期帧通过增量记录参与保留决策
+    let mut stale = DataFrame::new("line1.temperature", Value::float(19.0));
+    stale.timestamp = 1;
+    wal.append(&FrameEnvelope::wrap_data(3, stale).unwrap()).await.unwrap();
+
+    let report = wal.enforce_retention().await.unwrap();
+    assert_eq!(report.expired, 1);
+    assert!(wal.read_frame(1).await.unwrap().is_some());
+    assert!(wal.read_frame(2).await.unwrap().is_some());
+    assert!(wal.read_frame(3).await.unwrap().is_none());
+}
@@ -127,6 +127,19 @@ impl Alert {
     }
 }
 
+impl From<&frame_bus::RetentionAlert> for Alert {
+    /// Unreplayed WAL frames are about to be deleted; losing alarms or commands is critical
+    fn from(alert: &frame_bus::RetentionAlert) -> Self {
+        let severity = if alert.report.shed_protected > 0 {
+            AlertSeverity::Critical
+        } else {
+            AlertSeverity::Warning
+        };
+        Alert::new(severity, "WAL retention dropping unreplayed data".to_string(), alert.to_string())
+            .with_tags(vec!["wal".to_string(), "retention".to_string()])
+    }
+}
+
 /// Alert filter for querying alerts
 #[derive(Debug, Clone, Default)]
 pub struct AlertFilter {
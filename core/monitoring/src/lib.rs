@@ -12,7 +12,7 @@ use prometheus::{Counter, Gauge, Registry, Encoder, TextEncoder};
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
 use tokio::time::interval;
-use tracing::{info, debug};
+use tracing::{info, debug, warn};
 
 pub mod health;
 pub mod alerts;
@@ -246,11 +246,34 @@ impl AlertManager {
 
     /// Send alert
     pub async fn send_alert(&self, alert: Alert) -> Result<()> {
-        let handlers = self.handlers.read().await;
+        Self::dispatch(&self.handlers, &alert).await
+    }
+
+    /// Sink for frame-bus WAL retention alerts, raised before unreplayed frames are deleted
+    pub fn wal_retention_sink(&self) -> frame_bus::RetentionAlertSink {
+        let handlers = self.handlers.clone();
+        Arc::new(move |retention: &frame_bus::RetentionAlert| {
+            let alert = Alert::from(retention);
+            let handlers = handlers.clone();
+            match tokio::runtime::Handle::try_current() {
+                Ok(runtime) => {
+                    runtime.spawn(async move {
+                        if let Err(e) = Self::dispatch(&handlers, &alert).await {
+                            warn!("Failed to send WAL retention alert: {}", e);
+                        }
+                    });
+                }
+                Err(_) => warn!("No runtime to send WAL retention alert: {}", alert.message),
+            }
+        })
+    }
+
+    async fn dispatch(handlers: &RwLock<Vec<Box<dyn AlertHandler>>>, alert: &Alert) -> Result<()> {
+        let handlers = handlers.read().await;
         
         for handler in handlers.iter() {
-            if handler.should_handle(&alert) {
-                handler.handle_alert(&alert).await?;
+            if handler.should_handle(alert) {
+                handler.handle_alert(alert).await?;
             }
         }
         
@@ -312,4 +335,50 @@ mod tests {
         
         assert!(manager.send_alert(alert).await.is_ok());
     }
-}
\ No newline at end of file
+
+    struct RecordingHandler(Arc<std::sync::Mutex<Vec<Alert>>>);
+
+    #[async_trait::async_trait]
+    impl AlertHandler for RecordingHandler {
+        async fn handle_alert(&self, alert: &Alert) -> Result<()> {
+            self.0.lock().unwrap().push(alert.clone());
+            Ok(())
+        }
+
+        fn should_handle(&self, _alert: &Alert) -> bool {
+            true
+        }
+
+        fn name(&self) -> &str {
+            "recording"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wal_retention_alert_sink() {
+        let manager = AlertManager::new().unwrap();
+        let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+        manager.add_handler(Box::new(RecordingHandler(received.clone()))).await;
+
+        let retention = frame_bus::RetentionAlert {
+            frames: 12,
+            first_seq: 100,
+            last_seq: 140,
+            min_ack: Some(99),
+            report: frame_bus::RetentionReport { decimated: 12, ..Default::default() },
+        };
+        (manager.wal_retention_sink())(&retention);
+
+        for _ in 0..50 {
+            if !received.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].severity, AlertSeverity::Warning);
+        assert!(received[0].message.contains("12 unreplayed frames"));
+        assert!(received[0].tags.contains(&"wal".to_string()));
+    }
+}
@@ -232,9 +232,16 @@ pub struct StorageConfig {
     pub enable_compression: bool,
     pub enable_encryption: bool,
     pub encryption_key_file: Option<PathBuf>,
+    /// Previous key files kept for decrypting WAL frames written before a key rotation
+    #[serde(default)]
+    pub retired_encryption_key_files: Vec<PathBuf>,
 
     #[validate(range(min = 1, max = 365))]
     pub data_retention_days: u32,
+
+    /// Hard byte budget for the WAL; low-value frames are shed first when it is approached
+    #[serde(default)]
+    pub wal_max_bytes: Option<u64>,
 }
 
 impl Default for StorageConfig {
@@ -249,7 +256,9 @@ impl Default for StorageConfig {
             enable_compression: true,
             enable_encryption: false,
             encryption_key_file: None,
+            retired_encryption_key_files: Vec::new(),
             data_retention_days: 30,
+            wal_max_bytes: None,
         }
     }
 }
@@ -352,6 +361,10 @@ pub struct ConnectorConfig {
     #[validate(range(min = 1, max = 3600))]
     pub keepalive_interval_seconds: u32,
 
+    /// Tag patterns forwarded by this connector, empty forwards all tags
+    #[serde(default)]
+    pub tag_filter: Vec<String>,
+
     pub settings: HashMap<String, serde_json::Value>,
 }
 
@@ -6,7 +6,9 @@
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use frame_bus::CmdFrame;
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::SystemTime;
 
 use crate::{BridgeError, Result};
@@ -191,6 +193,14 @@ pub enum Quality {
     Bad,
 }
 
+/// 上位系统写入的命令出口，默认发布到FrameBus
+pub type CommandSink = Arc<dyn Fn(CmdFrame) -> anyhow::Result<()> + Send + Sync>;
+
+/// 以桥接名称为主体发布到FrameBus的命令出口，受总线ACL约束
+pub fn bus_command_sink(subject: String) -> CommandSink {
+    Arc::new(move |cmd| frame_bus::publisher(&subject)?.send_cmd(cmd))
+}
+
 /// 协议桥接抽象接口
 #[async_trait]
 pub trait ProtocolBridge {
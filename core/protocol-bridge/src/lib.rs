@@ -15,7 +15,7 @@
 
 ## 架构设计
 
-```
+```text
 ┌─────────────────┐    ┌─────────────────┐    ┌─────────────────┐
 │   Gateway Core  │    │ Protocol Bridge │    │    Plugins      │
 │                 │◄──►│                 │◄──►│  ┌──────────┐   │
@@ -33,6 +33,7 @@
 pub mod bridge;
 pub mod plugin;
 pub mod runtime;
+#[cfg(feature = "opcua")]
 pub mod opcua;
 pub mod modbus;
 pub mod abi;
@@ -41,7 +42,8 @@ pub mod error;
 pub use bridge::{ProtocolBridge, BridgeConfig, BridgeType};
 pub use plugin::{Plugin, PluginManager, PluginMetadata, PluginState};
 pub use runtime::{PluginRuntime, RuntimeConfig};
-pub use opcua::{OpcUaBridge, OpcUaConfig};
+#[cfg(feature = "opcua")]
+pub use opcua::{OpcUaBridge, OpcUaConfig, OpcUaUser};
 pub use modbus::{ModbusBridge, ModbusConfig};
 pub use abi::{ABIv1, PluginContext, DataExchange};
 pub use error::BridgeError;
@@ -141,6 +143,7 @@ pub async fn init() -> Result<Arc<BridgeManager>> {
     let manager = Arc::new(BridgeManager::new()?);
     
     // 注册内置桥接
+    #[cfg(feature = "opcua")]
     manager.register_bridge(
         "opcua-server".to_string(),
         OpcUaBridge::new(OpcUaConfig::default())?,
@@ -159,6 +162,7 @@ pub async fn init() -> Result<Arc<BridgeManager>> {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "opcua")]
     #[tokio::test]
     async fn test_bridge_manager() {
         let manager = BridgeManager::new().unwrap();
@@ -177,6 +181,7 @@ mod tests {
         let manager = init().await.unwrap();
         
         // 验证内置桥接已注册
+        #[cfg(feature = "opcua")]
         assert!(manager.get_bridge("opcua-server").await.is_some());
         assert!(manager.get_bridge("modbus-slave").await.is_some());
     }
@@ -2,19 +2,25 @@
 # Modbus Slave Bridge
 
 Modbus Slave桥接实现，将内部数据暴露为Modbus从站
+
+- 线圈、离散输入、保持寄存器、输入寄存器四个区域 (`ModbusRegion`) 映射到FrameBus tag
+- FrameBus上的DataFrame刷新从站存储区，上位SCADA按PLC的方式读取
+- SCADA写线圈/保持寄存器时更新存储区，并为受影响的tag发布`CmdFrame`
+- 寄存器编码遵循配置的`ByteOrder`/`WordOrder`
 */
 
 use std::collections::HashMap;
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
-use std::time::SystemTime;
+use std::time::{Duration, Instant, SystemTime};
 
 use async_trait::async_trait;
+use frame_bus::{CmdFrame, DataFrame, Filter};
 use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use tokio::net::TcpListener;
 use tokio::sync::{Mutex, broadcast};
-// Modbus support temporarily disabled due to version conflicts
-// use tokio_modbus::prelude::*;
 
 use crate::bridge::*;
 use crate::{BridgeError, Result};
@@ -48,6 +54,22 @@ pub struct ModbusConfig {
     pub byte_order: ByteOrder,
     /// 字顺序
     pub word_order: WordOrder,
+    /// 静态tag映射
+    #[serde(default)]
+    pub mappings: Vec<ModbusTagMapping>,
+}
+
+/// FrameBus tag到Modbus地址的静态映射
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModbusTagMapping {
+    /// FrameBus tag
+    pub tag: String,
+    /// Modbus区域
+    pub region: ModbusRegion,
+    /// 协议地址 (需位于区域的起始地址与数量范围内)
+    pub address: u16,
+    /// 数据类型，线圈与离散输入固定为Boolean
+    pub data_type: DataType,
 }
 
 /// Modbus传输模式
@@ -100,12 +122,33 @@ impl Default for ModbusConfig {
             input_registers_count: 1000,
             byte_order: ByteOrder::BigEndian,
             word_order: WordOrder::HighFirst,
+            mappings: Vec::new(),
         }
     }
 }
 
+impl ModbusConfig {
+    /// 区域的起始地址与数量
+    pub fn region_range(&self, region: ModbusRegion) -> (u16, u16) {
+        match region {
+            ModbusRegion::Coils => (self.coils_start, self.coils_count),
+            ModbusRegion::DiscreteInputs => (self.discrete_inputs_start, self.discrete_inputs_count),
+            ModbusRegion::HoldingRegisters => (self.holding_registers_start, self.holding_registers_count),
+            ModbusRegion::InputRegisters => (self.input_registers_start, self.input_registers_count),
+        }
+    }
+
+    /// 协议地址转换为区域内偏移，范围越界或超出16位地址空间时返回None
+    fn region_offset(&self, region: ModbusRegion, address: u16, count: u16) -> Option<u16> {
+        let (start, size) = self.region_range(region);
+        let offset = address.checked_sub(start)?;
+        (register_end(address, count) <= ADDRESS_SPACE && offset as u32 + count as u32 <= size as u32).then_some(offset)
+    }
+}
+
 /// Modbus数据区域
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum ModbusRegion {
     /// 线圈（输出线圈）
     Coils,
@@ -254,6 +297,27 @@ impl ModbusStorage {
     }
 }
 
+/// MBAP报文头长度
+const MBAP_HEADER_LEN: usize = 7;
+/// 单次读取线圈/离散输入的最大数量
+const MAX_READ_BITS: u16 = 2000;
+/// 单次读取寄存器的最大数量
+const MAX_READ_REGISTERS: u16 = 125;
+/// 单次写入线圈的最大数量
+const MAX_WRITE_BITS: u16 = 1968;
+/// 单次写入寄存器的最大数量
+const MAX_WRITE_REGISTERS: u16 = 123;
+/// 16位协议地址空间大小
+const ADDRESS_SPACE: u32 = 1 << 16;
+
+/// Modbus异常码
+mod exception {
+    pub const ILLEGAL_FUNCTION: u8 = 0x01;
+    pub const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+    pub const ILLEGAL_DATA_VALUE: u8 = 0x03;
+    pub const GATEWAY_TARGET_FAILED: u8 = 0x0B;
+}
+
 /// Modbus Slave桥接实现
 pub struct ModbusBridge {
     config: ModbusConfig,
@@ -261,22 +325,113 @@ pub struct ModbusBridge {
     stats: Arc<RwLock<BridgeStats>>,
     data_points: Arc<RwLock<HashMap<String, ModbusDataPoint>>>,
     storage: Arc<Mutex<ModbusStorage>>,
+    command_sink: CommandSink,
     shutdown_sender: Arc<Mutex<Option<broadcast::Sender<()>>>>,
 }
 
 impl ModbusBridge {
     /// 创建新的Modbus桥接
     pub fn new(config: ModbusConfig) -> Result<Self> {
+        if !matches!(config.transport, ModbusTransport::Tcp) {
+            return Err(BridgeError::config(format!(
+                "Modbus {:?} transport is not supported, only TCP is available", config.transport
+            )));
+        }
         let storage = ModbusStorage::new(&config);
+        let command_sink = bus_command_sink(config.base.name.clone());
         
-        Ok(Self {
+        let bridge = Self {
             config,
             state: Arc::new(RwLock::new(BridgeState::Stopped)),
             stats: Arc::new(RwLock::new(BridgeStats::default())),
             data_points: Arc::new(RwLock::new(HashMap::new())),
             storage: Arc::new(Mutex::new(storage)),
+            command_sink,
             shutdown_sender: Arc::new(Mutex::new(None)),
-        })
+        };
+
+        for mapping in &bridge.config.mappings {
+            bridge.register_mapping(mapping)?;
+        }
+
+        Ok(bridge)
+    }
+
+    /// 替换CmdFrame出口
+    pub fn with_command_sink(mut self, sink: CommandSink) -> Self {
+        self.command_sink = sink;
+        self
+    }
+
+    /// 注册静态tag映射
+    fn register_mapping(&self, mapping: &ModbusTagMapping) -> Result<()> {
+        let (data_type, access) = match mapping.region {
+            ModbusRegion::Coils => (DataType::Boolean, AccessLevel::ReadWrite),
+            ModbusRegion::DiscreteInputs => (DataType::Boolean, AccessLevel::ReadOnly),
+            ModbusRegion::HoldingRegisters => (mapping.data_type.clone(), AccessLevel::ReadWrite),
+            ModbusRegion::InputRegisters => (mapping.data_type.clone(), AccessLevel::ReadOnly),
+        };
+        let length = match mapping.region {
+            ModbusRegion::Coils | ModbusRegion::DiscreteInputs => 1,
+            _ => self.get_register_length(&data_type),
+        };
+
+        if self.config.region_offset(mapping.region, mapping.address, length).is_none() {
+            return Err(BridgeError::config(format!(
+                "Tag '{}' mapped outside {:?} range at {}", mapping.tag, mapping.region, mapping.address
+            )));
+        }
+
+        let mut data_points = self.data_points.write().unwrap();
+        let overlap = data_points.values().find(|dp| {
+            dp.region == mapping.region
+                && (mapping.address as u32) < register_end(dp.address, dp.length)
+                && (dp.address as u32) < register_end(mapping.address, length)
+        });
+        if let Some(dp) = overlap {
+            return Err(BridgeError::config(format!(
+                "Tag '{}' overlaps '{}' in {:?}", mapping.tag, dp.data_point.id, mapping.region
+            )));
+        }
+
+        data_points.insert(mapping.tag.clone(), ModbusDataPoint {
+            data_point: DataPoint {
+                id: mapping.tag.clone(),
+                name: mapping.tag.clone(),
+                data_type,
+                access,
+                value: None,
+                last_updated: None,
+                quality: Quality::Uncertain,
+            },
+            region: mapping.region,
+            address: mapping.address,
+            length,
+        });
+        Ok(())
+    }
+
+    /// 连接处理任务共享的从站上下文
+    fn context(&self) -> SlaveContext {
+        SlaveContext {
+            config: Arc::new(self.config.clone()),
+            stats: self.stats.clone(),
+            data_points: self.data_points.clone(),
+            storage: self.storage.clone(),
+            command_sink: self.command_sink.clone(),
+        }
+    }
+
+    /// 处理一个Modbus请求PDU，返回应答PDU
+    ///
+    /// 单元ID不匹配时返回网关异常，广播 (单元ID 0) 的写请求不应答
+    pub async fn handle_pdu(&self, unit_id: u8, pdu: &[u8]) -> Option<Vec<u8>> {
+        self.context().handle_pdu(unit_id, pdu).await
+    }
+
+    /// 用FrameBus上的DataFrame刷新映射的tag，返回tag是否已映射
+    pub async fn update_from_frame(&self, frame: &DataFrame) -> Result<bool> {
+        self.context().update_from_frame(frame).await
     }
 
     /// 确定数据点的Modbus映射
@@ -316,38 +471,31 @@ impl ModbusBridge {
 
     /// 获取数据类型对应的寄存器长度
     fn get_register_length(&self, data_type: &DataType) -> u16 {
-        match data_type {
-            DataType::Boolean => 1,
-            DataType::Int16 | DataType::UInt16 => 1,
-            DataType::Int32 | DataType::UInt32 | DataType::Float => 2,
-            DataType::Int64 | DataType::UInt64 | DataType::Double => 4,
-            DataType::String | DataType::ByteArray => 10, // 默认10个寄存器
-            DataType::DateTime => 4,
-        }
+        register_length(data_type)
     }
 
     /// 查找可用的地址
     fn find_available_address(&self, region: ModbusRegion, length: u16) -> Result<u16> {
         let data_points = self.data_points.read().unwrap();
         
-        let (start, count) = match region {
-            ModbusRegion::Coils => (self.config.coils_start, self.config.coils_count),
-            ModbusRegion::DiscreteInputs => (self.config.discrete_inputs_start, self.config.discrete_inputs_count),
-            ModbusRegion::HoldingRegisters => (self.config.holding_registers_start, self.config.holding_registers_count),
-            ModbusRegion::InputRegisters => (self.config.input_registers_start, self.config.input_registers_count),
-        };
+        let (start, count) = self.config.region_range(region);
 
         // 简单的线性搜索，实际实现中可以优化
-        for addr in start..(start + count - length + 1) {
+        let end = register_end(start, count).min(ADDRESS_SPACE);
+        for addr in start as u32..end.saturating_sub(length as u32) + 1 {
+            if addr + length as u32 > end {
+                break;
+            }
+            let addr = addr as u16;
             let mut available = true;
             
             for dp in data_points.values() {
                 if dp.region == region {
-                    let dp_end = dp.address + dp.length;
-                    let check_end = addr + length;
+                    let dp_end = register_end(dp.address, dp.length);
+                    let check_end = register_end(addr, length);
                     
                     // 检查地址范围是否重叠
-                    if !(check_end <= dp.address || addr >= dp_end) {
+                    if !(check_end <= dp.address as u32 || addr as u32 >= dp_end) {
                         available = false;
                         break;
                     }
@@ -364,179 +512,19 @@ impl ModbusBridge {
 
     /// 将内部数据值转换为Modbus存储
     async fn data_value_to_modbus(&self, value: &DataValue, mapping: &ModbusDataPoint) -> Result<()> {
-        let mut storage = self.storage.lock().await;
-        
-        match &mapping.region {
-            ModbusRegion::Coils => {
-                if let DataValue::Boolean(b) = value {
-                    storage.write_coils(mapping.address, &[*b])?;
-                } else {
-                    return Err(BridgeError::modbus("Invalid value type for coil"));
-                }
-            }
-            ModbusRegion::DiscreteInputs => {
-                if let DataValue::Boolean(b) = value {
-                    storage.write_discrete_inputs(mapping.address, &[*b])?;
-                } else {
-                    return Err(BridgeError::modbus("Invalid value type for discrete input"));
-                }
-            }
-            ModbusRegion::HoldingRegisters | ModbusRegion::InputRegisters => {
-                let registers = self.value_to_registers(value, mapping.length)?;
-                
-                match mapping.region {
-                    ModbusRegion::HoldingRegisters => {
-                        storage.write_holding_registers(mapping.address, &registers)?;
-                    }
-                    ModbusRegion::InputRegisters => {
-                        storage.write_input_registers(mapping.address, &registers)?;
-                    }
-                    _ => unreachable!(),
-                }
-            }
-        }
-        
-        Ok(())
+        self.context().store_value(value, mapping).await
     }
 
     /// 将数据值转换为寄存器数组
+    #[cfg(test)]
     fn value_to_registers(&self, value: &DataValue, length: u16) -> Result<Vec<u16>> {
-        let mut registers = vec![0u16; length as usize];
-        
-        match value {
-            DataValue::Int16(v) => {
-                registers[0] = *v as u16;
-            }
-            DataValue::UInt16(v) => {
-                registers[0] = *v;
-            }
-            DataValue::Int32(v) => {
-                let bytes = v.to_be_bytes();
-                registers[0] = u16::from_be_bytes([bytes[0], bytes[1]]);
-                registers[1] = u16::from_be_bytes([bytes[2], bytes[3]]);
-            }
-            DataValue::UInt32(v) => {
-                let bytes = v.to_be_bytes();
-                registers[0] = u16::from_be_bytes([bytes[0], bytes[1]]);
-                registers[1] = u16::from_be_bytes([bytes[2], bytes[3]]);
-            }
-            DataValue::Float(v) => {
-                let bytes = v.to_be_bytes();
-                registers[0] = u16::from_be_bytes([bytes[0], bytes[1]]);
-                registers[1] = u16::from_be_bytes([bytes[2], bytes[3]]);
-            }
-            DataValue::Int64(v) => {
-                let bytes = v.to_be_bytes();
-                registers[0] = u16::from_be_bytes([bytes[0], bytes[1]]);
-                registers[1] = u16::from_be_bytes([bytes[2], bytes[3]]);
-                registers[2] = u16::from_be_bytes([bytes[4], bytes[5]]);
-                registers[3] = u16::from_be_bytes([bytes[6], bytes[7]]);
-            }
-            DataValue::UInt64(v) => {
-                let bytes = v.to_be_bytes();
-                registers[0] = u16::from_be_bytes([bytes[0], bytes[1]]);
-                registers[1] = u16::from_be_bytes([bytes[2], bytes[3]]);
-                registers[2] = u16::from_be_bytes([bytes[4], bytes[5]]);
-                registers[3] = u16::from_be_bytes([bytes[6], bytes[7]]);
-            }
-            DataValue::Double(v) => {
-                let bytes = v.to_be_bytes();
-                registers[0] = u16::from_be_bytes([bytes[0], bytes[1]]);
-                registers[1] = u16::from_be_bytes([bytes[2], bytes[3]]);
-                registers[2] = u16::from_be_bytes([bytes[4], bytes[5]]);
-                registers[3] = u16::from_be_bytes([bytes[6], bytes[7]]);
-            }
-            DataValue::String(s) => {
-                let bytes = s.as_bytes();
-                for (i, chunk) in bytes.chunks(2).enumerate() {
-                    if i >= registers.len() {
-                        break;
-                    }
-                    
-                    let high = chunk.get(0).copied().unwrap_or(0);
-                    let low = chunk.get(1).copied().unwrap_or(0);
-                    registers[i] = u16::from_be_bytes([high, low]);
-                }
-            }
-            DataValue::ByteArray(bytes) => {
-                for (i, chunk) in bytes.chunks(2).enumerate() {
-                    if i >= registers.len() {
-                        break;
-                    }
-                    
-                    let high = chunk.get(0).copied().unwrap_or(0);
-                    let low = chunk.get(1).copied().unwrap_or(0);
-                    registers[i] = u16::from_be_bytes([high, low]);
-                }
-            }
-            DataValue::DateTime(dt) => {
-                let timestamp = dt.duration_since(SystemTime::UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs();
-                
-                let bytes = timestamp.to_be_bytes();
-                registers[0] = u16::from_be_bytes([bytes[0], bytes[1]]);
-                registers[1] = u16::from_be_bytes([bytes[2], bytes[3]]);
-                registers[2] = u16::from_be_bytes([bytes[4], bytes[5]]);
-                registers[3] = u16::from_be_bytes([bytes[6], bytes[7]]);
-            }
-            _ => {
-                return Err(BridgeError::modbus("Unsupported value type"));
-            }
-        }
-        
-        Ok(registers)
+        value_to_registers(value, length, &self.config.byte_order, &self.config.word_order)
     }
 
     /// 从寄存器数组转换为数据值
+    #[cfg(test)]
     fn registers_to_value(&self, registers: &[u16], data_type: &DataType) -> Result<DataValue> {
-        match data_type {
-            DataType::Int16 => {
-                if registers.is_empty() {
-                    return Err(BridgeError::modbus("Not enough registers for Int16"));
-                }
-                Ok(DataValue::Int16(registers[0] as i16))
-            }
-            DataType::UInt16 => {
-                if registers.is_empty() {
-                    return Err(BridgeError::modbus("Not enough registers for UInt16"));
-                }
-                Ok(DataValue::UInt16(registers[0]))
-            }
-            DataType::Int32 => {
-                if registers.len() < 2 {
-                    return Err(BridgeError::modbus("Not enough registers for Int32"));
-                }
-                let bytes = [
-                    (registers[0] >> 8) as u8, (registers[0] & 0xFF) as u8,
-                    (registers[1] >> 8) as u8, (registers[1] & 0xFF) as u8,
-                ];
-                Ok(DataValue::Int32(i32::from_be_bytes(bytes)))
-            }
-            DataType::UInt32 => {
-                if registers.len() < 2 {
-                    return Err(BridgeError::modbus("Not enough registers for UInt32"));
-                }
-                let bytes = [
-                    (registers[0] >> 8) as u8, (registers[0] & 0xFF) as u8,
-                    (registers[1] >> 8) as u8, (registers[1] & 0xFF) as u8,
-                ];
-                Ok(DataValue::UInt32(u32::from_be_bytes(bytes)))
-            }
-            DataType::Float => {
-                if registers.len() < 2 {
-                    return Err(BridgeError::modbus("Not enough registers for Float"));
-                }
-                let bytes = [
-                    (registers[0] >> 8) as u8, (registers[0] & 0xFF) as u8,
-                    (registers[1] >> 8) as u8, (registers[1] & 0xFF) as u8,
-                ];
-                Ok(DataValue::Float(f32::from_be_bytes(bytes)))
-            }
-            _ => {
-                Err(BridgeError::modbus("Unsupported data type for register conversion"))
-            }
-        }
+        registers_to_value(registers, data_type, &self.config.byte_order, &self.config.word_order)
     }
 
     /// 启动Modbus TCP服务器
@@ -551,11 +539,11 @@ impl ModbusBridge {
         let (shutdown_tx, mut shutdown_rx) = broadcast::channel(1);
         {
             let mut sender = self.shutdown_sender.lock().await;
-            *sender = Some(shutdown_tx);
+            *sender = Some(shutdown_tx.clone());
         }
 
-        let storage = self.storage.clone();
-        let stats = self.stats.clone();
+        let context = self.context();
+        let active = Arc::new(AtomicUsize::new(0));
 
         tokio::spawn(async move {
             tracing::info!("Modbus TCP server listening on {}", addr);
@@ -565,21 +553,28 @@ impl ModbusBridge {
                     result = listener.accept() => {
                         match result {
                             Ok((stream, client_addr)) => {
+                                if active.load(Ordering::SeqCst) >= context.config.base.max_connections {
+                                    tracing::warn!("Rejecting Modbus client {}: connection limit reached", client_addr);
+                                    continue;
+                                }
                                 tracing::debug!("New Modbus client connected: {}", client_addr);
                                 
-                                // 更新统计信息
-                                {
-                                    let mut stats = stats.write().unwrap();
-                                    stats.connections += 1;
-                                    stats.last_activity = Some(SystemTime::now());
-                                }
+                                active.fetch_add(1, Ordering::SeqCst);
+                                context.connection_opened();
 
-                                // 在实际实现中，这里应该处理Modbus协议
-                                // 由于tokio-modbus主要是客户端库，这里简化实现
+                                let context = context.clone();
+                                let active = active.clone();
+                                let mut shutdown_rx = shutdown_tx.subscribe();
                                 tokio::spawn(async move {
-                                    // 处理客户端连接
-                                    tracing::debug!("Handling Modbus client: {}", client_addr);
-                                    // TODO: 实现Modbus协议处理
+                                    tokio::select! {
+                                        result = context.serve_connection(stream) => {
+                                            if let Err(e) = result {
+                                                tracing::debug!("Modbus client {} closed: {}", client_addr, e);
+                                            }
+                                        }
+                                        _ = shutdown_rx.recv() => {}
+                                    }
+                                    active.fetch_sub(1, Ordering::SeqCst);
                                 });
                             }
                             Err(e) => {
@@ -597,6 +592,543 @@ impl ModbusBridge {
 
         Ok(())
     }
+
+    /// 订阅FrameBus，将映射tag的最新值写入存储区
+    async fn start_frame_subscription(&self) -> Result<()> {
+        let mut rx = match frame_bus::subscribe_as(&self.config.base.name, Filter::data_only()) {
+            Ok(rx) => rx,
+            Err(e) => {
+                tracing::warn!("FrameBus unavailable, Modbus slave serves static values only: {}", e);
+                return Ok(());
+            }
+        };
+
+        let mut shutdown_rx = self.shutdown_sender.lock().await
+            .as_ref()
+            .map(|sender| sender.subscribe())
+            .ok_or_else(|| BridgeError::BridgeState("Modbus server not started".to_string()))?;
+        let context = self.context();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    result = rx.recv() => {
+                        let Ok(envelope) = result else { break };
+                        let Ok(frame) = envelope.into_data() else { continue };
+                        if let Err(e) = context.update_from_frame(&frame).await {
+                            tracing::warn!("Failed to map tag {} to Modbus: {}", frame.tag, e);
+                        }
+                    }
+                    _ = shutdown_rx.recv() => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// 从站上下文：连接处理任务与FrameBus订阅任务共享
+#[derive(Clone)]
+struct SlaveContext {
+    config: Arc<ModbusConfig>,
+    stats: Arc<RwLock<BridgeStats>>,
+    data_points: Arc<RwLock<HashMap<String, ModbusDataPoint>>>,
+    storage: Arc<Mutex<ModbusStorage>>,
+    command_sink: CommandSink,
+}
+
+impl SlaveContext {
+    fn connection_opened(&self) {
+        let mut stats = self.stats.write().unwrap();
+        stats.connections += 1;
+        stats.last_activity = Some(SystemTime::now());
+    }
+
+    /// 处理单个TCP连接上的MBAP报文，空闲超过`connection_timeout`断开
+    async fn serve_connection<S>(&self, mut stream: S) -> Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let idle = Duration::from_secs(self.config.base.connection_timeout.max(1));
+
+        loop {
+            let mut header = [0u8; MBAP_HEADER_LEN];
+            match tokio::time::timeout(idle, stream.read_exact(&mut header)).await {
+                Err(_) => return Err(BridgeError::Timeout),
+                Ok(Err(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Ok(result) => { result?; }
+            }
+
+            let transaction_id = u16::from_be_bytes([header[0], header[1]]);
+            let protocol_id = u16::from_be_bytes([header[2], header[3]]);
+            let length = u16::from_be_bytes([header[4], header[5]]) as usize;
+            let unit_id = header[6];
+
+            if protocol_id != 0 || !(2..=254).contains(&length) {
+                return Err(BridgeError::modbus(format!(
+                    "Invalid MBAP header: protocol {} length {}", protocol_id, length
+                )));
+            }
+
+            let mut pdu = vec![0u8; length - 1];
+            stream.read_exact(&mut pdu).await?;
+
+            let started = Instant::now();
+            let Some(response) = self.handle_pdu(unit_id, &pdu).await else {
+                continue;
+            };
+            self.record_request(response[0] & 0x80 == 0, started.elapsed());
+
+            let mut frame = Vec::with_capacity(MBAP_HEADER_LEN + response.len());
+            frame.extend_from_slice(&transaction_id.to_be_bytes());
+            frame.extend_from_slice(&0u16.to_be_bytes());
+            frame.extend_from_slice(&(response.len() as u16 + 1).to_be_bytes());
+            frame.push(unit_id);
+            frame.extend_from_slice(&response);
+            stream.write_all(&frame).await?;
+        }
+    }
+
+    fn record_request(&self, success: bool, elapsed: Duration) {
+        let mut stats = self.stats.write().unwrap();
+        stats.total_requests += 1;
+        if success {
+            stats.successful_requests += 1;
+        } else {
+            stats.failed_requests += 1;
+        }
+        let elapsed_ms = elapsed.as_secs_f64() * 1000.0;
+        stats.avg_response_time += (elapsed_ms - stats.avg_response_time) / stats.total_requests as f64;
+        stats.last_activity = Some(SystemTime::now());
+    }
+
+    async fn handle_pdu(&self, unit_id: u8, pdu: &[u8]) -> Option<Vec<u8>> {
+        let function = *pdu.first()?;
+        let broadcast = unit_id == 0;
+
+        if !broadcast && unit_id != self.config.slave_id && unit_id != 0xFF {
+            return Some(vec![function | 0x80, exception::GATEWAY_TARGET_FAILED]);
+        }
+
+        let response = match self.execute(function, &pdu[1..]).await {
+            Ok(body) => {
+                let mut response = Vec::with_capacity(body.len() + 1);
+                response.push(function);
+                response.extend_from_slice(&body);
+                response
+            }
+            Err(code) => {
+                tracing::debug!("Modbus request 0x{:02X} rejected with exception 0x{:02X}", function, code);
+                vec![function | 0x80, code]
+            }
+        };
+
+        (!broadcast).then_some(response)
+    }
+
+    /// 执行请求，返回应答数据 (不含功能码) 或异常码
+    async fn execute(&self, function: u8, data: &[u8]) -> std::result::Result<Vec<u8>, u8> {
+        let word = |index: usize| -> std::result::Result<u16, u8> {
+            data.get(index..index + 2)
+                .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                .ok_or(exception::ILLEGAL_DATA_VALUE)
+        };
+
+        match function {
+            0x01 | 0x02 => {
+                let region = if function == 0x01 { ModbusRegion::Coils } else { ModbusRegion::DiscreteInputs };
+                let (address, count) = (word(0)?, word(2)?);
+                if count == 0 || count > MAX_READ_BITS {
+                    return Err(exception::ILLEGAL_DATA_VALUE);
+                }
+                let offset = self.offset(region, address, count)?;
+
+                let storage = self.storage.lock().await;
+                let bits = match region {
+                    ModbusRegion::Coils => storage.read_coils(offset, count),
+                    _ => storage.read_discrete_inputs(offset, count),
+                }.map_err(|_| exception::ILLEGAL_DATA_ADDRESS)?;
+
+                let packed = pack_bits(&bits);
+                let mut body = vec![packed.len() as u8];
+                body.extend_from_slice(&packed);
+                Ok(body)
+            }
+            0x03 | 0x04 => {
+                let region = if function == 0x03 { ModbusRegion::HoldingRegisters } else { ModbusRegion::InputRegisters };
+                let (address, count) = (word(0)?, word(2)?);
+                if count == 0 || count > MAX_READ_REGISTERS {
+                    return Err(exception::ILLEGAL_DATA_VALUE);
+                }
+                let offset = self.offset(region, address, count)?;
+
+                let storage = self.storage.lock().await;
+                let registers = match region {
+                    ModbusRegion::HoldingRegisters => storage.read_holding_registers(offset, count),
+                    _ => storage.read_input_registers(offset, count),
+                }.map_err(|_| exception::ILLEGAL_DATA_ADDRESS)?;
+
+                let mut body = vec![(registers.len() * 2) as u8];
+                body.extend(registers.iter().flat_map(|r| r.to_be_bytes()));
+                Ok(body)
+            }
+            0x05 => {
+                let (address, raw) = (word(0)?, word(2)?);
+                let on = match raw {
+                    0xFF00 => true,
+                    0x0000 => false,
+                    _ => return Err(exception::ILLEGAL_DATA_VALUE),
+                };
+                let offset = self.offset(ModbusRegion::Coils, address, 1)?;
+                self.storage.lock().await.write_coils(offset, &[on])
+                    .map_err(|_| exception::ILLEGAL_DATA_ADDRESS)?;
+
+                self.emit_commands(ModbusRegion::Coils, address, 1).await;
+                Ok(data[..4].to_vec())
+            }
+            0x06 => {
+                let (address, value) = (word(0)?, word(2)?);
+                let offset = self.offset(ModbusRegion::HoldingRegisters, address, 1)?;
+                self.storage.lock().await.write_holding_registers(offset, &[value])
+                    .map_err(|_| exception::ILLEGAL_DATA_ADDRESS)?;
+
+                self.emit_commands(ModbusRegion::HoldingRegisters, address, 1).await;
+                Ok(data[..4].to_vec())
+            }
+            0x0F => {
+                let (address, count) = (word(0)?, word(2)?);
+                let byte_count = *data.get(4).ok_or(exception::ILLEGAL_DATA_VALUE)? as usize;
+                if count == 0 || count > MAX_WRITE_BITS
+                    || byte_count != (count as usize).div_ceil(8)
+                    || data.len() != 5 + byte_count
+                {
+                    return Err(exception::ILLEGAL_DATA_VALUE);
+                }
+                let offset = self.offset(ModbusRegion::Coils, address, count)?;
+
+                let bits: Vec<bool> = (0..count as usize)
+                    .map(|i| data[5 + i / 8] & (1 << (i % 8)) != 0)
+                    .collect();
+                self.storage.lock().await.write_coils(offset, &bits)
+                    .map_err(|_| exception::ILLEGAL_DATA_ADDRESS)?;
+
+                self.emit_commands(ModbusRegion::Coils, address, count).await;
+                Ok(data[..4].to_vec())
+            }
+            0x10 => {
+                let (address, count) = (word(0)?, word(2)?);
+                let byte_count = *data.get(4).ok_or(exception::ILLEGAL_DATA_VALUE)? as usize;
+                if count == 0 || count > MAX_WRITE_REGISTERS
+                    || byte_count != count as usize * 2
+                    || data.len() != 5 + byte_count
+                {
+                    return Err(exception::ILLEGAL_DATA_VALUE);
+                }
+                let offset = self.offset(ModbusRegion::HoldingRegisters, address, count)?;
+
+                let registers: Vec<u16> = data[5..].chunks_exact(2)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]]))
+                    .collect();
+                self.storage.lock().await.write_holding_registers(offset, &registers)
+                    .map_err(|_| exception::ILLEGAL_DATA_ADDRESS)?;
+
+                self.emit_commands(ModbusRegion::HoldingRegisters, address, count).await;
+                Ok(data[..4].to_vec())
+            }
+            _ => Err(exception::ILLEGAL_FUNCTION),
+        }
+    }
+
+    fn offset(&self, region: ModbusRegion, address: u16, count: u16) -> std::result::Result<u16, u8> {
+        self.config.region_offset(region, address, count)
+            .ok_or(exception::ILLEGAL_DATA_ADDRESS)
+    }
+
+    /// 为被写入范围覆盖的tag发布CmdFrame
+    ///
+    /// 只写入多寄存器tag的一部分时，其余寄存器取存储区中的现值
+    async fn emit_commands(&self, region: ModbusRegion, address: u16, count: u16) {
+        let affected: Vec<ModbusDataPoint> = {
+            let data_points = self.data_points.read().unwrap();
+            data_points.values()
+                .filter(|dp| dp.region == region && dp.data_point.access != AccessLevel::ReadOnly)
+                .filter(|dp| {
+                    (address as u32) < dp.address as u32 + dp.length as u32
+                        && (dp.address as u32) < address as u32 + count as u32
+                })
+                .cloned()
+                .collect()
+        };
+
+        for dp in affected {
+            let value = match self.load_value(&dp).await {
+                Ok(value) => value,
+                Err(e) => {
+                    tracing::warn!("Failed to decode Modbus write for {}: {}", dp.data_point.id, e);
+                    continue;
+                }
+            };
+
+            let cmd = CmdFrame::new(dp.data_point.id.as_str(), data_value_to_frame(&value), "modbus-slave");
+            if let Err(e) = (self.command_sink)(cmd) {
+                tracing::error!("Failed to publish command for {}: {}", dp.data_point.id, e);
+                continue;
+            }
+
+            let mut data_points = self.data_points.write().unwrap();
+            if let Some(dp) = data_points.get_mut(&dp.data_point.id) {
+                dp.data_point.value = Some(value);
+                dp.data_point.last_updated = Some(SystemTime::now());
+            }
+        }
+    }
+
+    /// 从存储区解码数据点当前值
+    async fn load_value(&self, mapping: &ModbusDataPoint) -> Result<DataValue> {
+        let offset = self.config.region_offset(mapping.region, mapping.address, mapping.length)
+            .ok_or_else(|| BridgeError::modbus("Data point address out of range"))?;
+        let storage = self.storage.lock().await;
+
+        match mapping.region {
+            ModbusRegion::Coils => Ok(DataValue::Boolean(storage.read_coils(offset, 1)?[0])),
+            ModbusRegion::DiscreteInputs => Ok(DataValue::Boolean(storage.read_discrete_inputs(offset, 1)?[0])),
+            ModbusRegion::HoldingRegisters => {
+                let registers = storage.read_holding_registers(offset, mapping.length)?;
+                registers_to_value(&registers, &mapping.data_point.data_type, &self.config.byte_order, &self.config.word_order)
+            }
+            ModbusRegion::InputRegisters => {
+                let registers = storage.read_input_registers(offset, mapping.length)?;
+                registers_to_value(&registers, &mapping.data_point.data_type, &self.config.byte_order, &self.config.word_order)
+            }
+        }
+    }
+
+    /// 将数据值写入存储区
+    async fn store_value(&self, value: &DataValue, mapping: &ModbusDataPoint) -> Result<()> {
+        let offset = self.config.region_offset(mapping.region, mapping.address, mapping.length)
+            .ok_or_else(|| BridgeError::modbus("Data point address out of range"))?;
+        let mut storage = self.storage.lock().await;
+        
+        match &mapping.region {
+            ModbusRegion::Coils => {
+                if let DataValue::Boolean(b) = value {
+                    storage.write_coils(offset, &[*b])?;
+                } else {
+                    return Err(BridgeError::modbus("Invalid value type for coil"));
+                }
+            }
+            ModbusRegion::DiscreteInputs => {
+                if let DataValue::Boolean(b) = value {
+                    storage.write_discrete_inputs(offset, &[*b])?;
+                } else {
+                    return Err(BridgeError::modbus("Invalid value type for discrete input"));
+                }
+            }
+            ModbusRegion::HoldingRegisters | ModbusRegion::InputRegisters => {
+                let registers = value_to_registers(value, mapping.length, &self.config.byte_order, &self.config.word_order)?;
+                
+                match mapping.region {
+                    ModbusRegion::HoldingRegisters => {
+                        storage.write_holding_registers(offset, &registers)?;
+                    }
+                    ModbusRegion::InputRegisters => {
+                        storage.write_input_registers(offset, &registers)?;
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        }
+        
+        Ok(())
+    }
+
+    async fn update_from_frame(&self, frame: &DataFrame) -> Result<bool> {
+        let Some(mapping) = self.data_points.read().unwrap().get(&frame.tag).cloned() else {
+            return Ok(false);
+        };
+        let value = frame.value.as_ref()
+            .and_then(|v| frame_to_data_value(v, &mapping.data_point.data_type))
+            .ok_or_else(|| BridgeError::modbus(format!(
+                "Value of {} not convertible to {:?}", frame.tag, mapping.data_point.data_type
+            )))?;
+
+        self.store_value(&value, &mapping).await?;
+
+        let mut data_points = self.data_points.write().unwrap();
+        if let Some(dp) = data_points.get_mut(&frame.tag) {
+            dp.data_point.value = Some(value);
+            dp.data_point.last_updated = Some(SystemTime::now());
+            dp.data_point.quality = if frame.qos >= 2 { Quality::Good } else { Quality::Bad };
+        }
+        Ok(true)
+    }
+}
+
+/// 打包线圈状态，首个线圈位于首字节最低位
+fn pack_bits(bits: &[bool]) -> Vec<u8> {
+    let mut packed = vec![0u8; bits.len().div_ceil(8)];
+    for (i, bit) in bits.iter().enumerate() {
+        if *bit {
+            packed[i / 8] |= 1 << (i % 8);
+        }
+    }
+    packed
+}
+
+/// 地址范围的结束地址（不含），以u32计算避免越过0xFFFF时溢出
+fn register_end(address: u16, length: u16) -> u32 {
+    address as u32 + length as u32
+}
+
+/// 数据类型对应的寄存器长度
+fn register_length(data_type: &DataType) -> u16 {
+    match data_type {
+        DataType::Boolean => 1,
+        DataType::Int16 | DataType::UInt16 => 1,
+        DataType::Int32 | DataType::UInt32 | DataType::Float => 2,
+        DataType::Int64 | DataType::UInt64 | DataType::Double => 4,
+        DataType::String | DataType::ByteArray => 10, // 默认10个寄存器
+        DataType::DateTime => 4,
+    }
+}
+
+/// 按配置的字节序与字顺序调整大端寄存器序列 (变换自逆，编解码共用)
+fn apply_order(registers: &mut [u16], byte_order: &ByteOrder, word_order: &WordOrder) {
+    if *word_order == WordOrder::LowFirst {
+        registers.reverse();
+    }
+    if *byte_order == ByteOrder::LittleEndian {
+        registers.iter_mut().for_each(|r| *r = r.swap_bytes());
+    }
+}
+
+/// 大端字节序列转为寄存器
+fn bytes_to_registers(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]))
+        .collect()
+}
+
+/// 将数据值转换为寄存器数组
+fn value_to_registers(value: &DataValue, length: u16, byte_order: &ByteOrder, word_order: &WordOrder) -> Result<Vec<u16>> {
+    let numeric = match value {
+        DataValue::Boolean(v) => vec![*v as u16],
+        DataValue::Int16(v) => vec![*v as u16],
+        DataValue::UInt16(v) => vec![*v],
+        DataValue::Int32(v) => bytes_to_registers(&v.to_be_bytes()),
+        DataValue::UInt32(v) => bytes_to_registers(&v.to_be_bytes()),
+        DataValue::Float(v) => bytes_to_registers(&v.to_be_bytes()),
+        DataValue::Int64(v) => bytes_to_registers(&v.to_be_bytes()),
+        DataValue::UInt64(v) => bytes_to_registers(&v.to_be_bytes()),
+        DataValue::Double(v) => bytes_to_registers(&v.to_be_bytes()),
+        DataValue::DateTime(dt) => {
+            let timestamp = dt.duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            bytes_to_registers(&timestamp.to_be_bytes())
+        }
+        DataValue::String(s) => return Ok(text_registers(s.as_bytes(), length)),
+        DataValue::ByteArray(bytes) => return Ok(text_registers(bytes, length)),
+    };
+
+    if numeric.len() > length as usize {
+        return Err(BridgeError::modbus(format!(
+            "Value needs {} registers but only {} mapped", numeric.len(), length
+        )));
+    }
+
+    let mut registers = numeric;
+    apply_order(&mut registers, byte_order, word_order);
+    registers.resize(length as usize, 0);
+    Ok(registers)
+}
+
+/// 字符串/字节数组按字节顺序写入寄存器，超长截断，不足补零
+fn text_registers(bytes: &[u8], length: u16) -> Vec<u16> {
+    let mut registers = bytes_to_registers(bytes);
+    registers.resize(length as usize, 0);
+    registers
+}
+
+/// 从寄存器数组转换为数据值
+fn registers_to_value(registers: &[u16], data_type: &DataType, byte_order: &ByteOrder, word_order: &WordOrder) -> Result<DataValue> {
+    let needed = match data_type {
+        DataType::String | DataType::ByteArray => 0,
+        other => register_length(other) as usize,
+    };
+    if registers.len() < needed {
+        return Err(BridgeError::modbus(format!("Not enough registers for {:?}", data_type)));
+    }
+
+    let mut ordered = registers[..needed].to_vec();
+    apply_order(&mut ordered, byte_order, word_order);
+    let bytes: Vec<u8> = ordered.iter().flat_map(|r| r.to_be_bytes()).collect();
+    let raw: Vec<u8> = registers.iter().flat_map(|r| r.to_be_bytes()).collect();
+
+    Ok(match data_type {
+        DataType::Boolean => DataValue::Boolean(ordered[0] != 0),
+        DataType::Int16 => DataValue::Int16(ordered[0] as i16),
+        DataType::UInt16 => DataValue::UInt16(ordered[0]),
+        DataType::Int32 => DataValue::Int32(i32::from_be_bytes(bytes[..4].try_into().unwrap())),
+        DataType::UInt32 => DataValue::UInt32(u32::from_be_bytes(bytes[..4].try_into().unwrap())),
+        DataType::Float => DataValue::Float(f32::from_be_bytes(bytes[..4].try_into().unwrap())),
+        DataType::Int64 => DataValue::Int64(i64::from_be_bytes(bytes[..8].try_into().unwrap())),
+        DataType::UInt64 => DataValue::UInt64(u64::from_be_bytes(bytes[..8].try_into().unwrap())),
+        DataType::Double => DataValue::Double(f64::from_be_bytes(bytes[..8].try_into().unwrap())),
+        DataType::DateTime => {
+            let secs = u64::from_be_bytes(bytes[..8].try_into().unwrap());
+            DataValue::DateTime(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+        }
+        DataType::String => {
+            let end = raw.iter().position(|b| *b == 0).unwrap_or(raw.len());
+            DataValue::String(String::from_utf8_lossy(&raw[..end]).into_owned())
+        }
+        DataType::ByteArray => DataValue::ByteArray(raw),
+    })
+}
+
+/// FrameBus值转换为数据点类型
+pub(crate) fn frame_to_data_value(value: &frame_bus::Value, data_type: &DataType) -> Option<DataValue> {
+    Some(match data_type {
+        DataType::Boolean => DataValue::Boolean(value.to_bool()?),
+        DataType::Int16 => DataValue::Int16(value.to_i64()? as i16),
+        DataType::UInt16 => DataValue::UInt16(value.to_i64()? as u16),
+        DataType::Int32 => DataValue::Int32(value.to_i64()? as i32),
+        DataType::UInt32 => DataValue::UInt32(value.to_i64()? as u32),
+        DataType::Int64 => DataValue::Int64(value.to_i64()?),
+        DataType::UInt64 => DataValue::UInt64(value.to_i64()? as u64),
+        DataType::Float => DataValue::Float(value.to_f64()? as f32),
+        DataType::Double => DataValue::Double(value.to_f64()?),
+        DataType::String => DataValue::String(value.to_string()?),
+        DataType::ByteArray => match &value.value {
+            Some(frame_bus::envelope::value::Value::BinV(bytes)) => DataValue::ByteArray(bytes.clone()),
+            _ => return None,
+        },
+        DataType::DateTime => {
+            DataValue::DateTime(SystemTime::UNIX_EPOCH + Duration::from_secs(value.to_i64()?.max(0) as u64))
+        }
+    })
+}
+
+/// 数据值转换为FrameBus值
+pub(crate) fn data_value_to_frame(value: &DataValue) -> frame_bus::Value {
+    match value {
+        DataValue::Boolean(v) => frame_bus::Value::bool(*v),
+        DataValue::Int16(v) => frame_bus::Value::int(*v as i64),
+        DataValue::Int32(v) => frame_bus::Value::int(*v as i64),
+        DataValue::Int64(v) => frame_bus::Value::int(*v),
+        DataValue::UInt16(v) => frame_bus::Value::int(*v as i64),
+        DataValue::UInt32(v) => frame_bus::Value::int(*v as i64),
+        DataValue::UInt64(v) => frame_bus::Value::int(*v as i64),
+        DataValue::Float(v) => frame_bus::Value::float(*v as f64),
+        DataValue::Double(v) => frame_bus::Value::float(*v),
+        DataValue::String(v) => frame_bus::Value::string(v.clone()),
+        DataValue::ByteArray(v) => frame_bus::Value::bytes(v.clone()),
+        DataValue::DateTime(dt) => frame_bus::Value::int(
+            dt.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default().as_secs() as i64
+        ),
+    }
 }
 
 #[async_trait]
@@ -626,10 +1158,12 @@ impl ProtocolBridge for ModbusBridge {
         match self.config.transport {
             ModbusTransport::Tcp => {
                 self.start_tcp_server().await?;
+                self.start_frame_subscription().await?;
             }
             ModbusTransport::Rtu | ModbusTransport::Ascii => {
-                // TODO: 实现串口支持
-                return Err(BridgeError::modbus("Serial transport not yet implemented"));
+                return Err(BridgeError::config(format!(
+                    "Modbus {:?} transport is not supported", self.config.transport
+                )));
             }
         }
 
@@ -809,7 +1343,15 @@ mod tests {
         let bridge = ModbusBridge::new(config).unwrap();
         
         assert_eq!(bridge.state().await, BridgeState::Stopped);
-        assert!(bridge.health_check().await.unwrap());
+        assert!(!bridge.health_check().await.unwrap());
+    }
+
+    #[test]
+    fn test_serial_transports_rejected() {
+        for transport in [ModbusTransport::Rtu, ModbusTransport::Ascii] {
+            let config = ModbusConfig { transport, ..ModbusConfig::default() };
+            assert!(ModbusBridge::new(config).is_err());
+        }
     }
 
     #[tokio::test]
@@ -861,4 +1403,183 @@ mod tests {
         let registers = storage.read_holding_registers(0, 2).unwrap();
         assert_eq!(registers, vec![0x1234, 0x5678]);
     }
+    /// 带静态映射并捕获CmdFrame的测试桥接
+    fn mapped_bridge(config: ModbusConfig) -> (ModbusBridge, Arc<std::sync::Mutex<Vec<CmdFrame>>>) {
+        let commands = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = commands.clone();
+        let bridge = ModbusBridge::new(config).unwrap()
+            .with_command_sink(Arc::new(move |cmd| {
+                sink.lock().unwrap().push(cmd);
+                Ok(())
+            }));
+        (bridge, commands)
+    }
+
+    fn test_config() -> ModbusConfig {
+        let mapping = |tag: &str, region, address, data_type| ModbusTagMapping {
+            tag: tag.to_string(),
+            region,
+            address,
+            data_type,
+        };
+
+        ModbusConfig {
+            mappings: vec![
+                mapping("pump.run", ModbusRegion::Coils, 0, DataType::Boolean),
+                mapping("pump.alarm", ModbusRegion::DiscreteInputs, 10000, DataType::Boolean),
+                mapping("tank.setpoint", ModbusRegion::HoldingRegisters, 40000, DataType::Float),
+                mapping("tank.level", ModbusRegion::InputRegisters, 30002, DataType::UInt16),
+            ],
+            ..ModbusConfig::default()
+        }
+    }
+
+    fn data_frame(tag: &str, value: frame_bus::Value) -> DataFrame {
+        DataFrame::new(tag, value).with_qos(2)
+    }
+
+    #[tokio::test]
+    async fn test_read_mapped_tags() {
+        let (bridge, _) = mapped_bridge(test_config());
+        assert!(bridge.update_from_frame(&data_frame("tank.level", frame_bus::Value::int(1234))).await.unwrap());
+        assert!(bridge.update_from_frame(&data_frame("tank.setpoint", frame_bus::Value::float(1.5))).await.unwrap());
+        assert!(bridge.update_from_frame(&data_frame("pump.alarm", frame_bus::Value::bool(true))).await.unwrap());
+        assert!(!bridge.update_from_frame(&data_frame("other.tag", frame_bus::Value::int(1))).await.unwrap());
+
+        // FC04 读取输入寄存器30000..30002
+        let response = bridge.handle_pdu(1, &[0x04, 0x75, 0x30, 0x00, 0x03]).await.unwrap();
+        assert_eq!(response, vec![0x04, 6, 0, 0, 0, 0, 0x04, 0xD2]);
+
+        // FC03 读取Float32 1.5 = 0x3FC00000
+        let response = bridge.handle_pdu(1, &[0x03, 0x9C, 0x40, 0x00, 0x02]).await.unwrap();
+        assert_eq!(response, vec![0x03, 4, 0x3F, 0xC0, 0x00, 0x00]);
+
+        // FC02 读取离散输入
+        let response = bridge.handle_pdu(1, &[0x02, 0x27, 0x10, 0x00, 0x09]).await.unwrap();
+        assert_eq!(response, vec![0x02, 2, 0x01, 0x00]);
+    }
+
+    #[tokio::test]
+    async fn test_exceptions() {
+        let (bridge, _) = mapped_bridge(test_config());
+
+        // 区域外地址
+        assert_eq!(bridge.handle_pdu(1, &[0x03, 0x00, 0x00, 0x00, 0x01]).await.unwrap(), vec![0x83, 0x02]);
+        assert_eq!(bridge.handle_pdu(1, &[0x03, 0xA0, 0x27, 0x00, 0x02]).await.unwrap(), vec![0x83, 0x02]);
+        // 数量非法
+        assert_eq!(bridge.handle_pdu(1, &[0x03, 0x9C, 0x40, 0x00, 0x00]).await.unwrap(), vec![0x83, 0x03]);
+        assert_eq!(bridge.handle_pdu(1, &[0x05, 0x00, 0x00, 0x12, 0x34]).await.unwrap(), vec![0x85, 0x03]);
+        // 不支持的功能码与截断请求
+        assert_eq!(bridge.handle_pdu(1, &[0x2B, 0x0E]).await.unwrap(), vec![0xAB, 0x01]);
+        assert_eq!(bridge.handle_pdu(1, &[0x03, 0x9C]).await.unwrap(), vec![0x83, 0x03]);
+        // 其他从站
+        assert_eq!(bridge.handle_pdu(7, &[0x03, 0x9C, 0x40, 0x00, 0x01]).await.unwrap(), vec![0x83, 0x0B]);
+    }
+
+    #[tokio::test]
+    async fn test_writes_become_commands() {
+        let (bridge, commands) = mapped_bridge(test_config());
+
+        // FC05 写线圈
+        let request = [0x05, 0x00, 0x00, 0xFF, 0x00];
+        assert_eq!(bridge.handle_pdu(1, &request).await.unwrap(), request.to_vec());
+
+        // FC16 写Float32设定值 2.5 = 0x40200000
+        let request = [0x10, 0x9C, 0x40, 0x00, 0x02, 0x04, 0x40, 0x20, 0x00, 0x00];
+        assert_eq!(bridge.handle_pdu(1, &request).await.unwrap(), vec![0x10, 0x9C, 0x40, 0x00, 0x02]);
+
+        // 未映射的保持寄存器写入只更新存储区
+        assert_eq!(bridge.handle_pdu(1, &[0x06, 0x9C, 0x50, 0x00, 0x07]).await.unwrap(), vec![0x06, 0x9C, 0x50, 0x00, 0x07]);
+
+        // 写入值可被读回
+        let response = bridge.handle_pdu(1, &[0x01, 0x00, 0x00, 0x00, 0x01]).await.unwrap();
+        assert_eq!(response, vec![0x01, 1, 0x01]);
+
+        let commands = commands.lock().unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[0].tag, "pump.run");
+        assert_eq!(commands[0].origin, "modbus-slave");
+        assert_eq!(commands[0].value.as_ref().unwrap().to_bool(), Some(true));
+        assert_eq!(commands[1].tag, "tank.setpoint");
+        assert_eq!(commands[1].value.as_ref().unwrap().to_f64(), Some(2.5));
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_write_has_no_response() {
+        let (bridge, commands) = mapped_bridge(test_config());
+        assert!(bridge.handle_pdu(0, &[0x0F, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01]).await.is_none());
+        assert_eq!(commands.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_byte_and_word_order() {
+        let value = DataValue::UInt32(0x1234_5678);
+        let cases = [
+            (ByteOrder::BigEndian, WordOrder::HighFirst, vec![0x1234, 0x5678]),
+            (ByteOrder::BigEndian, WordOrder::LowFirst, vec![0x5678, 0x1234]),
+            (ByteOrder::LittleEndian, WordOrder::HighFirst, vec![0x3412, 0x7856]),
+            (ByteOrder::LittleEndian, WordOrder::LowFirst, vec![0x7856, 0x3412]),
+        ];
+
+        for (byte_order, word_order, expected) in cases {
+            let registers = value_to_registers(&value, 2, &byte_order, &word_order).unwrap();
+            assert_eq!(registers, expected, "{:?} {:?}", byte_order, word_order);
+            let decoded = registers_to_value(&registers, &DataType::UInt32, &byte_order, &word_order).unwrap();
+            assert!(matches!(decoded, DataValue::UInt32(0x1234_5678)));
+        }
+    }
+
+    #[test]
+    fn test_invalid_mappings_rejected() {
+        let mut config = test_config();
+        config.mappings.push(ModbusTagMapping {
+            tag: "tank.overlap".to_string(),
+            region: ModbusRegion::HoldingRegisters,
+            address: 40001,
+            data_type: DataType::UInt16,
+        });
+        assert!(ModbusBridge::new(config).is_err());
+
+        let mut config = test_config();
+        config.mappings.push(ModbusTagMapping {
+            tag: "tank.outside".to_string(),
+            region: ModbusRegion::InputRegisters,
+            address: 100,
+            data_type: DataType::UInt16,
+        });
+        assert!(ModbusBridge::new(config).is_err());
+
+        // 跨越0xFFFF的范围拒绝而不是回绕
+        let mut config = test_config();
+        config.holding_registers_start = 0xFFF0;
+        config.holding_registers_count = 0x100;
+        config.mappings = vec![ModbusTagMapping {
+            tag: "tank.wrap".to_string(),
+            region: ModbusRegion::HoldingRegisters,
+            address: 0xFFFF,
+            data_type: DataType::Float,
+        }];
+        assert!(ModbusBridge::new(config.clone()).is_err());
+        config.mappings[0].address = 0xFFFE;
+        assert!(ModbusBridge::new(config).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_mbap_framing() {
+        let (bridge, _) = mapped_bridge(test_config());
+        bridge.update_from_frame(&data_frame("tank.level", frame_bus::Value::int(42))).await.unwrap();
+
+        let (mut client, server) = tokio::io::duplex(256);
+        let context = bridge.context();
+        let task = tokio::spawn(async move { context.serve_connection(server).await });
+
+        client.write_all(&[0x00, 0x2A, 0x00, 0x00, 0x00, 0x06, 0x01, 0x04, 0x75, 0x32, 0x00, 0x01]).await.unwrap();
+        let mut response = [0u8; 11];
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(response, [0x00, 0x2A, 0x00, 0x00, 0x00, 0x05, 0x01, 0x04, 0x02, 0x00, 0x2A]);
+
+        drop(client);
+        assert!(task.await.unwrap().is_ok());
+        assert_eq!(bridge.stats().await.successful_requests, 1);
+    }
 }
\ No newline at end of file
@@ -2,17 +2,43 @@
 # OPC-UA Server Bridge
 
 OPC-UA Server桥接实现，将内部数据暴露为OPC-UA服务器
+
+- 地址空间由设备/点位模型生成：每个设备一个文件夹，每个点位一个变量
+- 点位单位作为EngineeringUnits属性，质量映射为StatusCode
+- FrameBus数据帧更新变量值，客户端订阅由服务器采样触发
+- 客户端写入转换为`CmdFrame`发布，变量值等待设备回读后刷新
+- 写入缺省关闭；开启后只提供Basic256Sha256加密端点，并要求用户名密码登录
 */
 
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
 
+use ::opcua::core::config::Config;
+use ::opcua::server::address_space::address_space::AddressSpace;
+use ::opcua::server::prelude::{
+    AttrFnSetter, DataTypeId, DateTime, EUInformation, ExtensionObject, LocalizedText, NodeId,
+    ObjectId, Server, ServerBuilder, ServerEndpoint, StatusCode, UAString, VariableBuilder,
+    ServerUserToken, VariableTypeId, Variant, ANONYMOUS_USER_TOKEN_ID,
+};
+use ::opcua::server::address_space::{AccessLevel as UaAccessLevel, UserAccessLevel};
+use ::opcua::sync::RwLock as UaRwLock;
 use async_trait::async_trait;
+use config_manager::{Access, VariablesConfig};
+use frame_bus::{CmdFrame, DataFrame, Filter};
 use serde::{Deserialize, Serialize};
+use tokio::sync::{Mutex, broadcast};
 
 use crate::bridge::*;
+use crate::modbus::{data_value_to_frame, frame_to_data_value};
 use crate::{BridgeError, Result};
 
+/// 写入命令的来源标识
+const COMMAND_ORIGIN: &str = "opcua-server";
+/// UNECE单位命名空间
+const UNECE_NAMESPACE: &str = "http://www.opcfoundation.org/UA/units/un/cefact";
+
 /// OPC-UA桥接配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpcUaConfig {
@@ -26,96 +52,702 @@ pub struct OpcUaConfig {
     pub endpoint_path: String,
     /// 发布间隔（毫秒）
     pub publishing_interval: f64,
+    /// 证书目录 (own/private/trusted/rejected)
+    pub pki_dir: PathBuf,
+    /// 服务器证书，相对`pki_dir`，缺省为`own/cert.der`
+    #[serde(default)]
+    pub certificate_path: Option<PathBuf>,
+    /// 服务器私钥，相对`pki_dir`，缺省为`private/private.pem`
+    #[serde(default)]
+    pub private_key_path: Option<PathBuf>,
+    /// 证书缺失时生成自签名证书，仅用于调试
+    #[serde(default)]
+    pub create_sample_keypair: bool,
+    /// 允许客户端写入点位（转换为设备命令），缺省只读
+    #[serde(default)]
+    pub enable_writes: bool,
+    /// 加密端点的登录用户，开启写入时至少一个
+    #[serde(default)]
+    pub users: Vec<OpcUaUser>,
+}
+
+/// OPC-UA登录用户
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpcUaUser {
+    pub username: String,
+    pub password: String,
 }
 
 impl Default for OpcUaConfig {
     fn default() -> Self {
+        let base = BridgeConfig {
+            name: "opcua-server".to_string(),
+            bridge_type: BridgeType::OpcUaServer,
+            port: 4840,
+            ..BridgeConfig::default()
+        };
+
         Self {
-            base: BridgeConfig::default(),
+            base,
             server_name: "Industrial Gateway OPC-UA Server".to_string(),
             application_uri: "urn:gateway:opcua:server".to_string(),
             endpoint_path: "/UA/Server".to_string(),
             publishing_interval: 1000.0,
+            pki_dir: PathBuf::from("pki"),
+            certificate_path: None,
+            private_key_path: None,
+            create_sample_keypair: false,
+            enable_writes: false,
+            users: Vec::new(),
         }
     }
 }
 
+/// 地址空间中的点位
+#[derive(Debug, Clone)]
+struct TagNode {
+    data_point: DataPoint,
+    /// 所属设备（文件夹）
+    device: String,
+    /// 工程单位
+    unit: String,
+    description: String,
+}
+
+/// 运行中的服务器
+#[derive(Clone)]
+struct RunningServer {
+    server: Arc<UaRwLock<Server>>,
+    namespace: u16,
+}
+
+/// 地址空间上下文：桥接接口与FrameBus订阅任务共享
+#[derive(Clone)]
+struct ServerContext {
+    stats: Arc<RwLock<BridgeStats>>,
+    tags: Arc<RwLock<HashMap<String, TagNode>>>,
+    server: Arc<RwLock<Option<RunningServer>>>,
+    command_sink: CommandSink,
+    /// 是否接受客户端写入
+    writable: bool,
+}
+
 /// OPC-UA桥接实现
 pub struct OpcUaBridge {
     config: OpcUaConfig,
-    data_store: Arc<RwLock<HashMap<String, DataPoint>>>,
-    is_running: Arc<RwLock<bool>>,
+    state: Arc<RwLock<BridgeState>>,
+    context: ServerContext,
+    shutdown_sender: Arc<Mutex<Option<broadcast::Sender<()>>>>,
 }
 
 impl OpcUaBridge {
-    pub fn new(config: OpcUaConfig) -> Self {
-        Self {
+    /// 创建新的OPC-UA桥接
+    pub fn new(config: OpcUaConfig) -> Result<Self> {
+        if config.publishing_interval <= 0.0 {
+            return Err(BridgeError::config("publishing_interval must be positive"));
+        }
+        if !config.endpoint_path.starts_with('/') {
+            return Err(BridgeError::config(format!(
+                "endpoint_path must start with '/': {}", config.endpoint_path
+            )));
+        }
+        if config.enable_writes && config.users.is_empty() {
+            return Err(BridgeError::config("enable_writes requires at least one user"));
+        }
+
+        let command_sink = bus_command_sink(config.base.name.clone());
+        let writable = config.enable_writes;
+        Ok(Self {
             config,
-            data_store: Arc::new(RwLock::new(HashMap::new())),
-            is_running: Arc::new(RwLock::new(false)),
+            state: Arc::new(RwLock::new(BridgeState::Stopped)),
+            context: ServerContext {
+                stats: Arc::new(RwLock::new(BridgeStats::default())),
+                tags: Arc::new(RwLock::new(HashMap::new())),
+                server: Arc::new(RwLock::new(None)),
+                command_sink,
+                writable,
+            },
+            shutdown_sender: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// 替换CmdFrame出口
+    pub fn with_command_sink(mut self, sink: CommandSink) -> Self {
+        self.context.command_sink = sink;
+        self
+    }
+
+    /// 端点URL
+    pub fn endpoint_url(&self) -> String {
+        format!(
+            "opc.tcp://{}:{}{}",
+            self.config.base.bind_address, self.config.base.port, self.config.endpoint_path
+        )
+    }
+
+    /// 从变量配置加载点位模型，设备文件夹取自变量所属驱动
+    pub fn load_variables(&self, variables: &VariablesConfig) -> Result<()> {
+        for (tag, cfg) in &variables.variables {
+            let data_point = DataPoint {
+                id: tag.clone(),
+                name: tag.strip_prefix(&format!("{}.", cfg.driver)).unwrap_or(tag).to_string(),
+                data_type: map_config_type(&cfg.data_type),
+                access: match cfg.access {
+                    Access::Read => AccessLevel::ReadOnly,
+                    Access::Write => AccessLevel::WriteOnly,
+                    Access::ReadWrite => AccessLevel::ReadWrite,
+                },
+                value: None,
+                last_updated: None,
+                quality: Quality::Bad,
+            };
+
+            self.context.insert_tag(TagNode {
+                data_point,
+                device: cfg.driver.clone(),
+                unit: cfg.unit.clone(),
+                description: cfg.description.clone(),
+            })?;
+        }
+        Ok(())
+    }
+
+    /// 用FrameBus数据帧更新点位，返回是否命中点位
+    pub fn update_from_frame(&self, frame: &DataFrame) -> Result<bool> {
+        self.context.update_from_frame(frame)
+    }
+
+    /// 订阅FrameBus，将点位最新值写入地址空间
+    async fn start_frame_subscription(&self) -> Result<()> {
+        let mut rx = match frame_bus::subscribe_as(&self.config.base.name, Filter::data_only()) {
+            Ok(rx) => rx,
+            Err(e) => {
+                tracing::warn!("FrameBus unavailable, OPC-UA server serves static values only: {}", e);
+                return Ok(());
+            }
+        };
+
+        let mut shutdown_rx = self.shutdown_sender.lock().await
+            .as_ref()
+            .map(|sender| sender.subscribe())
+            .ok_or_else(|| BridgeError::BridgeState("OPC-UA server not started".to_string()))?;
+        let context = self.context.clone();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    result = rx.recv() => {
+                        let Ok(envelope) = result else { break };
+                        let Ok(frame) = envelope.into_data() else { continue };
+                        if let Err(e) = context.update_from_frame(&frame) {
+                            tracing::warn!("Failed to map tag {} to OPC-UA: {}", frame.tag, e);
+                        }
+                    }
+                    _ = shutdown_rx.recv() => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// 生成服务器实例
+    fn build_server(&self) -> Result<Server> {
+        let base = &self.config.base;
+
+        let mut builder = ServerBuilder::new()
+            .application_name(self.config.server_name.clone())
+            .application_uri(self.config.application_uri.clone())
+            .product_uri(self.config.application_uri.clone())
+            .create_sample_keypair(self.config.create_sample_keypair)
+            .pki_dir(self.config.pki_dir.clone())
+            .host_and_port(base.bind_address.clone(), base.port)
+            .discovery_urls(vec![self.config.endpoint_path.clone()])
+            .discovery_server_url(None);
+        // 服务器不区分会话权限，开启写入时不再提供匿名明文端点
+        if self.config.enable_writes {
+            let mut user_token_ids = Vec::with_capacity(self.config.users.len());
+            for user in &self.config.users {
+                let id = format!("user:{}", user.username);
+                builder = builder.user_token(id.clone(), ServerUserToken::user_pass(user.username.as_str(), user.password.as_str()));
+                user_token_ids.push(id);
+            }
+            builder = builder.endpoint(
+                "basic256sha256_sign_encrypt",
+                ServerEndpoint::new_basic256sha256_sign_encrypt(self.config.endpoint_path.clone(), &user_token_ids),
+            );
+        } else {
+            let user_token_ids = vec![ANONYMOUS_USER_TOKEN_ID.to_string()];
+            builder = builder.endpoint("none", ServerEndpoint::new_none(self.config.endpoint_path.clone(), &user_token_ids));
+        }
+        if let Some(path) = &self.config.certificate_path {
+            builder = builder.certificate_path(path.clone());
+        }
+        if let Some(path) = &self.config.private_key_path {
+            builder = builder.private_key_path(path.clone());
+        }
+
+        let mut server_config = builder.config();
+        // 配置以毫秒计，服务器限制以秒计
+        server_config.limits.min_publishing_interval = self.config.publishing_interval / 1000.0;
+        server_config.limits.min_sampling_interval = self.config.publishing_interval / 1000.0;
+        if !server_config.is_valid() {
+            return Err(BridgeError::config("Invalid OPC-UA server configuration"));
+        }
+
+        Ok(Server::new(server_config))
+    }
+}
+
+impl ServerContext {
+    fn running(&self) -> Option<RunningServer> {
+        self.server.read().unwrap().clone()
+    }
+
+    /// 登记点位，服务器运行中则同步加入地址空间
+    fn insert_tag(&self, tag: TagNode) -> Result<()> {
+        if let Some(running) = self.running() {
+            let address_space = running.server.read().address_space();
+            let mut address_space = address_space.write();
+            let node_id = NodeId::new(running.namespace, tag.data_point.id.clone());
+            if address_space.node_exists(&node_id) {
+                delete_tag_node(&mut address_space, running.namespace, &tag.data_point.id);
+            }
+            self.add_tag_node(&mut address_space, running.namespace, &tag)?;
+        }
+
+        self.tags.write().unwrap().insert(tag.data_point.id.clone(), tag);
+        Ok(())
+    }
+
+    /// 在地址空间中建立设备文件夹与点位变量
+    fn add_tag_node(&self, address_space: &mut AddressSpace, namespace: u16, tag: &TagNode) -> Result<()> {
+        let folder_id = NodeId::new(namespace, tag.device.clone());
+        if !address_space.node_exists(&folder_id)
+            && !address_space.add_folder_with_id(
+                &folder_id,
+                tag.device.as_str(),
+                tag.device.as_str(),
+                &NodeId::objects_folder_id(),
+            )
+        {
+            return Err(BridgeError::opcua(format!("Failed to create folder for device {}", tag.device)));
+        }
+
+        let data_point = &tag.data_point;
+        let node_id = NodeId::new(namespace, data_point.id.clone());
+        let mut builder = VariableBuilder::new(&node_id, data_point.name.as_str(), data_point.name.as_str())
+            .data_type(ua_data_type(&data_point.data_type))
+            .value(Variant::Empty)
+            .organized_by(folder_id);
+
+        if !tag.description.is_empty() {
+            builder = builder.description(tag.description.as_str());
+        }
+
+        // 未开启写入时所有点位只读
+        let access = if self.writable { data_point.access.clone() } else { AccessLevel::ReadOnly };
+        builder = match access {
+            AccessLevel::ReadOnly => builder,
+            AccessLevel::ReadWrite => builder.writable(),
+            AccessLevel::WriteOnly => builder
+                .access_level(UaAccessLevel::CURRENT_WRITE)
+                .user_access_level(UserAccessLevel::CURRENT_WRITE),
+        };
+
+        if access != AccessLevel::ReadOnly {
+            let tag_id = data_point.id.clone();
+            let data_type = data_point.data_type.clone();
+            let sink = self.command_sink.clone();
+            let stats = self.stats.clone();
+            builder = builder.value_setter(AttrFnSetter::new_boxed(move |_, _, _, data_value| {
+                let value = data_value.value.as_ref()
+                    .and_then(|variant| variant_to_data_value(variant, &data_type))
+                    .ok_or(StatusCode::BadTypeMismatch)?;
+
+                let cmd = CmdFrame::new(tag_id.as_str(), data_value_to_frame(&value), COMMAND_ORIGIN);
+                let result = sink(cmd);
+                record_request(&stats, result.is_ok());
+                result.map_err(|e| {
+                    tracing::warn!("Failed to publish command for {}: {}", tag_id, e);
+                    StatusCode::BadCommunicationError
+                })
+            }));
+        }
+
+        if !builder.insert(address_space) {
+            return Err(BridgeError::opcua(format!("Failed to create variable {}", data_point.id)));
         }
+
+        if !tag.unit.is_empty() {
+            let eu = EUInformation {
+                namespace_uri: UAString::from(UNECE_NAMESPACE),
+                unit_id: -1,
+                display_name: LocalizedText::from(tag.unit.as_str()),
+                description: LocalizedText::from(tag.unit.as_str()),
+            };
+            VariableBuilder::new(&eu_node_id(namespace, &data_point.id), "EngineeringUnits", "EngineeringUnits")
+                .property_of(node_id.clone())
+                .has_type_definition(VariableTypeId::PropertyType)
+                .data_type(DataTypeId::EUInformation)
+                .value(ExtensionObject::from_encodable(ObjectId::EUInformation_Encoding_DefaultBinary, &eu))
+                .insert(address_space);
+        }
+
+        publish_to_node(address_space, &node_id, data_point);
+        Ok(())
+    }
+
+    /// 用FrameBus数据帧更新点位
+    fn update_from_frame(&self, frame: &DataFrame) -> Result<bool> {
+        let data_point = {
+            let mut tags = self.tags.write().unwrap();
+            let Some(tag) = tags.get_mut(&frame.tag) else {
+                return Ok(false);
+            };
+
+            let data_point = &mut tag.data_point;
+            let value = frame.value.as_ref()
+                .and_then(|v| frame_to_data_value(v, &data_point.data_type));
+            let quality = match (frame.qos, &value) {
+                (2, Some(_)) => Quality::Good,
+                (1, Some(_)) => Quality::Uncertain,
+                _ => Quality::Bad,
+            };
+            // 坏质量只更新状态，保留最后有效值
+            if quality != Quality::Bad {
+                data_point.value = value;
+            }
+            data_point.quality = quality;
+            data_point.last_updated = Some(if frame.timestamp > 0 {
+                SystemTime::UNIX_EPOCH + Duration::from_nanos(frame.timestamp)
+            } else {
+                SystemTime::now()
+            });
+            data_point.clone()
+        };
+
+        self.publish(&data_point);
+        Ok(true)
+    }
+
+    /// 将点位当前值同步到地址空间
+    fn publish(&self, data_point: &DataPoint) {
+        if let Some(running) = self.running() {
+            let address_space = running.server.read().address_space();
+            let mut address_space = address_space.write();
+            let node_id = NodeId::new(running.namespace, data_point.id.clone());
+            publish_to_node(&mut address_space, &node_id, data_point);
+        }
+
+        self.stats.write().unwrap().last_activity = Some(SystemTime::now());
+    }
+}
+
+/// 写入变量值、质量与源时间戳
+fn publish_to_node(address_space: &mut AddressSpace, node_id: &NodeId, data_point: &DataPoint) {
+    let Some(variable) = address_space.find_variable_mut(node_id) else {
+        return;
+    };
+
+    let (variant, status) = match &data_point.value {
+        Some(value) => (data_value_to_variant(value), quality_status(&data_point.quality)),
+        None => (Variant::Empty, StatusCode::BadWaitingForInitialData),
+    };
+    let now = DateTime::now();
+    let source = data_point.last_updated
+        .map(|t| DateTime::from(chrono::DateTime::<chrono::Utc>::from(t)))
+        .unwrap_or(now);
+
+    if let Err(status) = variable.set_value_direct(variant, status, &now, &source) {
+        tracing::warn!("Failed to update OPC-UA variable {}: {}", data_point.id, status);
     }
 }
 
+fn delete_tag_node(address_space: &mut AddressSpace, namespace: u16, tag_id: &str) {
+    address_space.delete(&eu_node_id(namespace, tag_id), true);
+    address_space.delete(&NodeId::new(namespace, tag_id.to_string()), true);
+}
+
+fn eu_node_id(namespace: u16, tag_id: &str) -> NodeId {
+    NodeId::new(namespace, format!("{}.EngineeringUnits", tag_id))
+}
+
+fn record_request(stats: &RwLock<BridgeStats>, success: bool) {
+    let mut stats = stats.write().unwrap();
+    stats.total_requests += 1;
+    if success {
+        stats.successful_requests += 1;
+    } else {
+        stats.failed_requests += 1;
+    }
+    stats.last_activity = Some(SystemTime::now());
+}
+
+/// 质量映射为StatusCode
+fn quality_status(quality: &Quality) -> StatusCode {
+    match quality {
+        Quality::Good => StatusCode::Good,
+        Quality::Uncertain => StatusCode::UncertainLastUsableValue,
+        Quality::Bad => StatusCode::BadNoCommunication,
+    }
+}
+
+/// 配置数据类型映射为桥接数据类型
+fn map_config_type(data_type: &config_manager::DataType) -> DataType {
+    use config_manager::DataType as Cfg;
+    match data_type {
+        Cfg::Bool => DataType::Boolean,
+        Cfg::Int8 | Cfg::Int16 => DataType::Int16,
+        Cfg::Uint8 | Cfg::Uint16 => DataType::UInt16,
+        Cfg::Int32 => DataType::Int32,
+        Cfg::Uint32 => DataType::UInt32,
+        Cfg::Int64 => DataType::Int64,
+        Cfg::Uint64 => DataType::UInt64,
+        Cfg::Float32 => DataType::Float,
+        Cfg::Float64 => DataType::Double,
+        Cfg::String => DataType::String,
+        Cfg::Binary => DataType::ByteArray,
+    }
+}
+
+fn ua_data_type(data_type: &DataType) -> DataTypeId {
+    match data_type {
+        DataType::Boolean => DataTypeId::Boolean,
+        DataType::Int16 => DataTypeId::Int16,
+        DataType::Int32 => DataTypeId::Int32,
+        DataType::Int64 => DataTypeId::Int64,
+        DataType::UInt16 => DataTypeId::UInt16,
+        DataType::UInt32 => DataTypeId::UInt32,
+        DataType::UInt64 => DataTypeId::UInt64,
+        DataType::Float => DataTypeId::Float,
+        DataType::Double => DataTypeId::Double,
+        DataType::String => DataTypeId::String,
+        DataType::ByteArray => DataTypeId::ByteString,
+        DataType::DateTime => DataTypeId::DateTime,
+    }
+}
+
+fn data_value_to_variant(value: &DataValue) -> Variant {
+    match value {
+        DataValue::Boolean(v) => Variant::from(*v),
+        DataValue::Int16(v) => Variant::from(*v),
+        DataValue::Int32(v) => Variant::from(*v),
+        DataValue::Int64(v) => Variant::from(*v),
+        DataValue::UInt16(v) => Variant::from(*v),
+        DataValue::UInt32(v) => Variant::from(*v),
+        DataValue::UInt64(v) => Variant::from(*v),
+        DataValue::Float(v) => Variant::from(*v),
+        DataValue::Double(v) => Variant::from(*v),
+        DataValue::String(v) => Variant::from(v.as_str()),
+        DataValue::ByteArray(v) => Variant::from(v.clone()),
+        DataValue::DateTime(t) => Variant::from(DateTime::from(chrono::DateTime::<chrono::Utc>::from(*t))),
+    }
+}
+
+/// 客户端写入值按点位类型转换，类型不兼容时返回None
+fn variant_to_data_value(variant: &Variant, data_type: &DataType) -> Option<DataValue> {
+    let value = match variant {
+        Variant::Boolean(v) => frame_bus::Value::bool(*v),
+        Variant::SByte(v) => frame_bus::Value::int(*v as i64),
+        Variant::Byte(v) => frame_bus::Value::int(*v as i64),
+        Variant::Int16(v) => frame_bus::Value::int(*v as i64),
+        Variant::UInt16(v) => frame_bus::Value::int(*v as i64),
+        Variant::Int32(v) => frame_bus::Value::int(*v as i64),
+        Variant::UInt32(v) => frame_bus::Value::int(*v as i64),
+        Variant::Int64(v) => frame_bus::Value::int(*v),
+        Variant::UInt64(v) => frame_bus::Value::int(*v as i64),
+        Variant::Float(v) => frame_bus::Value::float(*v as f64),
+        Variant::Double(v) => frame_bus::Value::float(*v),
+        Variant::String(v) => frame_bus::Value::string(v.value().clone()?),
+        Variant::ByteString(v) => frame_bus::Value::bytes(v.value.clone()?),
+        Variant::DateTime(v) => frame_bus::Value::int(v.as_chrono().timestamp()),
+        _ => return None,
+    };
+    frame_to_data_value(&value, data_type)
+}
+
 #[async_trait]
 impl ProtocolBridge for OpcUaBridge {
-    async fn start(&mut self) -> Result<()> {
-        tracing::info!("Starting OPC-UA bridge on {}", self.config.endpoint_path);
-        
-        // 简化实现：设置运行状态
-        *self.is_running.write().unwrap() = true;
-        
-        tracing::info!("OPC-UA bridge started successfully");
+    fn config(&self) -> &BridgeConfig {
+        &self.config.base
+    }
+
+    async fn state(&self) -> BridgeState {
+        self.state.read().unwrap().clone()
+    }
+
+    async fn stats(&self) -> BridgeStats {
+        self.context.stats.read().unwrap().clone()
+    }
+
+    async fn start(&self) -> Result<()> {
+        {
+            let mut state = self.state.write().unwrap();
+            if *state == BridgeState::Running {
+                return Ok(());
+            }
+            *state = BridgeState::Starting;
+        }
+
+        // 服务器任务绑定失败会直接panic，先行检查端口
+        let addr = format!("{}:{}", self.config.base.bind_address, self.config.base.port);
+        if let Err(e) = std::net::TcpListener::bind(&addr) {
+            *self.state.write().unwrap() = BridgeState::Error(e.to_string());
+            return Err(BridgeError::opcua(format!("Cannot bind {}: {}", addr, e)));
+        }
+
+        let server = self.build_server()?;
+        let namespace = {
+            let address_space = server.address_space();
+            let mut address_space = address_space.write();
+            let namespace = address_space.register_namespace(&self.config.application_uri)
+                .map_err(|_| BridgeError::opcua("Failed to register namespace"))?;
+
+            let tags = self.context.tags.read().unwrap();
+            for tag in tags.values() {
+                self.context.add_tag_node(&mut address_space, namespace, tag)?;
+            }
+            namespace
+        };
+
+        let server = Arc::new(UaRwLock::new(server));
+        tokio::spawn(Server::new_server_task(server.clone()));
+        *self.context.server.write().unwrap() = Some(RunningServer { server, namespace });
+
+        let (shutdown_tx, _) = broadcast::channel(1);
+        *self.shutdown_sender.lock().await = Some(shutdown_tx);
+        self.start_frame_subscription().await?;
+
+        *self.state.write().unwrap() = BridgeState::Running;
+        self.context.stats.write().unwrap().start_time = Some(SystemTime::now());
+
+        tracing::info!("OPC-UA bridge started on {}", self.endpoint_url());
         Ok(())
     }
 
-    async fn stop(&mut self) -> Result<()> {
-        tracing::info!("Stopping OPC-UA bridge");
-        
-        *self.is_running.write().unwrap() = false;
-        
+    async fn stop(&self) -> Result<()> {
+        {
+            let mut state = self.state.write().unwrap();
+            if *state == BridgeState::Stopped {
+                return Ok(());
+            }
+            *state = BridgeState::Stopping;
+        }
+
+        if let Some(sender) = self.shutdown_sender.lock().await.take() {
+            let _ = sender.send(());
+        }
+        if let Some(running) = self.context.server.write().unwrap().take() {
+            running.server.write().abort();
+        }
+
+        *self.state.write().unwrap() = BridgeState::Stopped;
+
         tracing::info!("OPC-UA bridge stopped");
         Ok(())
     }
 
-    async fn write_value(&self, id: &str, value: DataValue) -> Result<()> {
-        let data_point = DataPoint {
-            id: id.to_string(),
-            name: id.to_string(),
-            data_type: DataType::String,
-            access: AccessLevel::ReadWrite,
-            value: Some(value),
-            last_updated: Some(std::time::SystemTime::now()),
-            quality: Quality::Good,
-        };
-        
-        self.data_store.write().unwrap().insert(id.to_string(), data_point);
+    async fn add_data_point(&self, data_point: DataPoint) -> Result<()> {
+        // 点位ID前缀作为设备名
+        let device = data_point.id.split_once('.')
+            .map(|(device, _)| device.to_string())
+            .unwrap_or_else(|| self.config.base.name.clone());
+
+        tracing::debug!("Added OPC-UA data point: {} -> {}", data_point.id, device);
+        self.context.insert_tag(TagNode {
+            data_point,
+            device,
+            unit: String::new(),
+            description: String::new(),
+        })
+    }
+
+    async fn remove_data_point(&self, data_point_id: &str) -> Result<()> {
+        if self.context.tags.write().unwrap().remove(data_point_id).is_none() {
+            return Err(BridgeError::BridgeNotFound(format!("Data point not found: {}", data_point_id)));
+        }
+
+        if let Some(running) = self.context.running() {
+            let address_space = running.server.read().address_space();
+            delete_tag_node(&mut address_space.write(), running.namespace, data_point_id);
+        }
+
+        tracing::debug!("Removed OPC-UA data point: {}", data_point_id);
         Ok(())
     }
 
+    async fn get_data_point(&self, data_point_id: &str) -> Result<Option<DataPoint>> {
+        let tags = self.context.tags.read().unwrap();
+        Ok(tags.get(data_point_id).map(|tag| tag.data_point.clone()))
+    }
+
+    async fn list_data_points(&self) -> Result<Vec<DataPoint>> {
+        let tags = self.context.tags.read().unwrap();
+        Ok(tags.values().map(|tag| tag.data_point.clone()).collect())
+    }
+
     async fn read_value(&self, data_point_id: &str) -> Result<Option<DataValue>> {
-        Ok(self.data_store.read().unwrap().get(data_point_id).and_then(|dp| dp.value.clone()))
+        let tags = self.context.tags.read().unwrap();
+        Ok(tags.get(data_point_id).and_then(|tag| tag.data_point.value.clone()))
     }
 
-    async fn list_data_points(&self) -> Result<Vec<String>> {
-        Ok(self.data_store.read().unwrap().keys().cloned().collect())
+    async fn write_value(&self, data_point_id: &str, value: DataValue) -> Result<()> {
+        let data_point = {
+            let mut tags = self.context.tags.write().unwrap();
+            let tag = tags.get_mut(data_point_id)
+                .ok_or_else(|| BridgeError::BridgeNotFound(format!("Data point not found: {}", data_point_id)))?;
+            tag.data_point.value = Some(value);
+            tag.data_point.quality = Quality::Good;
+            tag.data_point.last_updated = Some(SystemTime::now());
+            tag.data_point.clone()
+        };
+
+        self.context.publish(&data_point);
+        Ok(())
     }
 
-    async fn stats(&self) -> BridgeStats {
-        let data_store = self.data_store.read().unwrap();
-        BridgeStats {
-            connections: if *self.is_running.read().unwrap() { 1 } else { 0 },
-            total_requests: 0,
-            successful_requests: 0,
-            failed_requests: 0,
-            avg_response_time: 0.0,
-            start_time: None,
-            last_activity: None,
+    async fn read_multiple(&self, data_point_ids: &[String]) -> Result<HashMap<String, Option<DataValue>>> {
+        let mut result = HashMap::new();
+        for id in data_point_ids {
+            let value = self.read_value(id).await?;
+            result.insert(id.clone(), value);
+        }
+        Ok(result)
+    }
+
+    async fn write_multiple(&self, values: HashMap<String, DataValue>) -> Result<()> {
+        for (id, value) in values {
+            self.write_value(&id, value).await?;
         }
+        Ok(())
+    }
+
+    async fn subscribe(&self, _data_point_ids: &[String]) -> Result<String> {
+        // 订阅由OPC-UA客户端在会话内通过监控项建立，服务端不提供桥接级订阅
+        Err(BridgeError::UnsupportedProtocol(
+            "OPC-UA server subscriptions are created by clients via monitored items".to_string(),
+        ))
+    }
+
+    async fn unsubscribe(&self, _subscription_id: &str) -> Result<()> {
+        Err(BridgeError::UnsupportedProtocol(
+            "OPC-UA server subscriptions are created by clients via monitored items".to_string(),
+        ))
     }
 
     async fn health_check(&self) -> Result<bool> {
-        Ok(*self.is_running.read().unwrap())
+        Ok(matches!(self.state().await, BridgeState::Running))
+    }
+
+    async fn info(&self) -> Result<HashMap<String, serde_json::Value>> {
+        let mut info = HashMap::new();
+        info.insert("bridge_type".to_string(), serde_json::Value::String("opcua-server".to_string()));
+        info.insert("endpoint_url".to_string(), serde_json::Value::String(self.endpoint_url()));
+        info.insert("application_uri".to_string(), serde_json::Value::String(self.config.application_uri.clone()));
+        info.insert("data_points_count".to_string(), serde_json::Value::Number(self.context.tags.read().unwrap().len().into()));
+
+        Ok(info)
     }
 }
 
@@ -123,34 +755,126 @@ impl ProtocolBridge for OpcUaBridge {
 mod tests {
     use super::*;
 
+    fn test_point(id: &str, access: AccessLevel) -> DataPoint {
+        DataPoint {
+            id: id.to_string(),
+            name: id.to_string(),
+            data_type: DataType::Int32,
+            access,
+            value: None,
+            last_updated: None,
+            quality: Quality::Bad,
+        }
+    }
+
     #[tokio::test]
     async fn test_opcua_bridge_creation() {
         let config = OpcUaConfig::default();
-        let bridge = OpcUaBridge::new(config);
-        
-        assert!(!bridge.is_healthy());
+        let bridge = OpcUaBridge::new(config).unwrap();
+
+        assert_eq!(bridge.state().await, BridgeState::Stopped);
+        assert!(!bridge.health_check().await.unwrap());
+        assert!(bridge.subscribe(&["plc1.value".to_string()]).await.is_err());
+        assert_eq!(bridge.endpoint_url(), "opc.tcp://0.0.0.0:4840/UA/Server");
+
+        let config = OpcUaConfig { endpoint_path: "UA".to_string(), ..OpcUaConfig::default() };
+        assert!(OpcUaBridge::new(config).is_err());
     }
 
     #[tokio::test]
     async fn test_opcua_bridge_lifecycle() {
-        let config = OpcUaConfig::default();
-        let mut bridge = OpcUaBridge::new(config);
-        
+        let pki = tempfile::tempdir().unwrap();
+        let mut config = OpcUaConfig::default();
+        config.base.bind_address = "127.0.0.1".to_string();
+        config.base.port = free_port();
+        config.pki_dir = pki.path().to_path_buf();
+        let bridge = OpcUaBridge::new(config).unwrap();
+
+        bridge.add_data_point(test_point("plc1.value", AccessLevel::ReadWrite)).await.unwrap();
         assert!(bridge.start().await.is_ok());
-        assert!(bridge.is_healthy());
-        
+        assert_eq!(bridge.state().await, BridgeState::Running);
+        assert!(bridge.health_check().await.unwrap());
+
         // 测试数据点操作
-        let value = DataValue::Integer(42);
-        assert!(bridge.write_data_point("test.value", value).await.is_ok());
-        
-        let data_point = bridge.read_data_point("test.value").await.unwrap();
-        assert!(data_point.is_some());
-        
-        let stats = bridge.get_stats();
-        assert_eq!(stats.connections, 1);
-        assert_eq!(stats.data_points, 1);
-        
+        assert!(bridge.write_value("test.value", DataValue::Int32(42)).await.is_err());
+        bridge.write_value("plc1.value", DataValue::Int32(42)).await.unwrap();
+        assert!(matches!(bridge.read_value("plc1.value").await.unwrap(), Some(DataValue::Int32(42))));
+
+        let running = bridge.context.running().unwrap();
+        let node_id = NodeId::new(running.namespace, "plc1.value");
+        let address_space = running.server.read().address_space();
+        {
+            let address_space = address_space.read();
+            assert!(address_space.node_exists(&NodeId::new(running.namespace, "plc1")));
+            assert!(address_space.node_exists(&node_id));
+        }
+
+        // 运行中增删点位同步到地址空间
+        bridge.add_data_point(test_point("plc2.level", AccessLevel::ReadOnly)).await.unwrap();
+        assert!(address_space.read().node_exists(&NodeId::new(running.namespace, "plc2.level")));
+        bridge.remove_data_point("plc1.value").await.unwrap();
+        assert!(!address_space.read().node_exists(&node_id));
+
         assert!(bridge.stop().await.is_ok());
-        assert!(!bridge.is_healthy());
+        assert_eq!(bridge.state().await, BridgeState::Stopped);
+        assert!(!bridge.health_check().await.unwrap());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_start_fails_when_port_in_use() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = OpcUaConfig::default();
+        config.base.bind_address = "127.0.0.1".to_string();
+        config.base.port = listener.local_addr().unwrap().port();
+        let bridge = OpcUaBridge::new(config).unwrap();
+
+        assert!(bridge.start().await.is_err());
+        assert!(!bridge.health_check().await.unwrap());
+    }
+
+    #[test]
+    fn test_frame_quality_mapping() {
+        let bridge = OpcUaBridge::new(OpcUaConfig::default()).unwrap();
+        bridge.context.insert_tag(TagNode {
+            data_point: test_point("plc1.speed", AccessLevel::ReadOnly),
+            device: "plc1".to_string(),
+            unit: "rpm".to_string(),
+            description: String::new(),
+        }).unwrap();
+
+        let frame = DataFrame::new("plc1.speed", frame_bus::Value::int(1500));
+        assert!(bridge.update_from_frame(&frame).unwrap());
+        let point = bridge.context.tags.read().unwrap()["plc1.speed"].data_point.clone();
+        assert!(matches!(point.value, Some(DataValue::Int32(1500))));
+        assert_eq!(quality_status(&point.quality), StatusCode::Good);
+
+        // 坏质量保留最后值
+        let frame = DataFrame::new("plc1.speed", frame_bus::Value::int(0)).with_qos(0);
+        assert!(bridge.update_from_frame(&frame).unwrap());
+        let point = bridge.context.tags.read().unwrap()["plc1.speed"].data_point.clone();
+        assert!(matches!(point.value, Some(DataValue::Int32(1500))));
+        assert_eq!(quality_status(&point.quality), StatusCode::BadNoCommunication);
+
+        let frame = DataFrame::new("plc1.unknown", frame_bus::Value::int(1));
+        assert!(!bridge.update_from_frame(&frame).unwrap());
+    }
+
+    #[test]
+    fn test_variant_conversion() {
+        let value = variant_to_data_value(&Variant::from(12.5f64), &DataType::Float).unwrap();
+        assert!(matches!(value, DataValue::Float(v) if v == 12.5));
+
+        let value = variant_to_data_value(&Variant::from(true), &DataType::Boolean).unwrap();
+        assert!(matches!(value, DataValue::Boolean(true)));
+
+        let value = variant_to_data_value(&Variant::from("on"), &DataType::String).unwrap();
+        assert!(matches!(value, DataValue::String(ref s) if s == "on"));
+
+        assert!(variant_to_data_value(&Variant::Empty, &DataType::Int32).is_none());
+        assert_eq!(data_value_to_variant(&DataValue::UInt16(7)), Variant::UInt16(7));
+    }
+
+    fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+}
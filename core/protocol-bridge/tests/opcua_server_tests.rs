@@ -0,0 +1,271 @@
+//! OPC-UA服务器端到端测试：进程内客户端验证浏览/读取/订阅/写入及端点安全
+#![cfg(feature = "opcua")]
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, mpsc};
+use std::time::Duration;
+
+use config_manager::{Access, DataType, VariableCfg, VariablesConfig};
+use frame_bus::{CmdFrame, DataFrame};
+use opcua::client::prelude::*;
+use protocol_bridge::{OpcUaBridge, OpcUaConfig, OpcUaUser, ProtocolBridge};
+
+fn variable(driver: &str, data_type: DataType, access: Access, unit: &str) -> VariableCfg {
+    VariableCfg {
+        description: String::new(),
+        driver: driver.to_string(),
+        data_type,
+        address: "40001".to_string(),
+        access,
+        scale: None,
+        unscale: None,
+        unit: unit.to_string(),
+        alarms: vec![],
+    }
+}
+
+fn browse(session: &Session, node_id: NodeId, reference_type: ReferenceTypeId) -> Vec<(String, NodeId)> {
+    let description = BrowseDescription {
+        node_id,
+        browse_direction: BrowseDirection::Forward,
+        reference_type_id: reference_type.into(),
+        include_subtypes: true,
+        node_class_mask: 0,
+        result_mask: BrowseResultMask::All as u32,
+    };
+    let results = session.browse(&[description]).unwrap().unwrap();
+    results[0].references.clone().unwrap_or_default()
+        .into_iter()
+        .map(|r| (r.browse_name.name.to_string(), r.node_id.node_id))
+        .collect()
+}
+
+fn read(session: &Session, node_id: &NodeId) -> opcua::types::DataValue {
+    let values = session.read(&[node_id.clone().into()], TimestampsToReturn::Both, 0.0).unwrap();
+    values.into_iter().next().unwrap()
+}
+
+#[test]
+fn test_browse_read_subscribe_write() {
+    let pki = tempfile::tempdir().unwrap();
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+    let mut config = OpcUaConfig::default();
+    config.base.bind_address = "127.0.0.1".to_string();
+    config.base.port = port;
+    config.publishing_interval = 100.0;
+    config.pki_dir = pki.path().join("server");
+
+    let commands: Arc<Mutex<Vec<CmdFrame>>> = Arc::new(Mutex::new(Vec::new()));
+    let captured = commands.clone();
+    let bridge = OpcUaBridge::new(config).unwrap()
+        .with_command_sink(Arc::new(move |cmd| {
+            captured.lock().unwrap().push(cmd);
+            Ok(())
+        }));
+
+    let mut variables = HashMap::new();
+    variables.insert("plc1.temperature".to_string(), variable("plc1", DataType::Float64, Access::Read, "°C"));
+    variables.insert("plc1.setpoint".to_string(), variable("plc1", DataType::Int32, Access::ReadWrite, ""));
+    bridge.load_variables(&VariablesConfig { variables }).unwrap();
+
+    // 服务器运行在独立运行时，客户端为阻塞API
+    let rt = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+    rt.block_on(bridge.start()).unwrap();
+    bridge.update_from_frame(&DataFrame::new("plc1.temperature", frame_bus::Value::float(21.5))).unwrap();
+
+    let mut client = ClientBuilder::new()
+        .application_name("gateway-test-client")
+        .application_uri("urn:gateway:test:client")
+        .create_sample_keypair(true)
+        .trust_server_certs(true)
+        .pki_dir(pki.path().join("client"))
+        .session_retry_limit(3)
+        .client()
+        .unwrap();
+    let url = format!("opc.tcp://127.0.0.1:{}/UA/Server", port);
+    let session = client.connect_to_endpoint(
+        (url.as_str(), SecurityPolicy::None.to_str(), MessageSecurityMode::None, UserTokenPolicy::anonymous()),
+        IdentityToken::Anonymous,
+    ).unwrap();
+    let session_cmd = Session::run_async(session.clone());
+
+    let namespace = {
+        let session = session.read();
+        let namespaces = read(&session, &VariableId::Server_NamespaceArray.into());
+        let Some(Variant::Array(array)) = namespaces.value else { panic!("namespace array missing") };
+        array.values.iter()
+            .position(|v| *v == Variant::from("urn:gateway:opcua:server"))
+            .unwrap() as u16
+    };
+    let temperature = NodeId::new(namespace, "plc1.temperature");
+    let setpoint = NodeId::new(namespace, "plc1.setpoint");
+
+    {
+        let session = session.read();
+
+        // 设备文件夹与点位变量
+        let devices = browse(&session, ObjectId::ObjectsFolder.into(), ReferenceTypeId::Organizes);
+        assert!(devices.contains(&("plc1".to_string(), NodeId::new(namespace, "plc1"))));
+        let mut tags: Vec<String> = browse(&session, NodeId::new(namespace, "plc1"), ReferenceTypeId::Organizes)
+            .into_iter().map(|(name, _)| name).collect();
+        tags.sort();
+        assert_eq!(tags, vec!["setpoint".to_string(), "temperature".to_string()]);
+
+        let properties = browse(&session, temperature.clone(), ReferenceTypeId::HasProperty);
+        assert!(properties.iter().any(|(name, _)| name == "EngineeringUnits"));
+
+        // 值与质量
+        let value = read(&session, &temperature);
+        assert_eq!(value.value, Some(Variant::Double(21.5)));
+        assert_eq!(value.status, Some(StatusCode::Good));
+        assert!(value.source_timestamp.is_some());
+
+        let value = read(&session, &setpoint);
+        assert_eq!(value.status, Some(StatusCode::BadWaitingForInitialData));
+    }
+
+    // 订阅：FrameBus更新触发数据变化通知
+    let (tx, rx) = mpsc::channel();
+    let tx = Mutex::new(tx);
+    {
+        let session = session.read();
+        let subscription_id = session.create_subscription(
+            100.0, 30, 10, 0, 0, true,
+            DataChangeCallback::new(move |items| {
+                for item in items {
+                    let _ = tx.lock().unwrap().send(item.last_value().clone());
+                }
+            }),
+        ).unwrap();
+        let request = MonitoredItemCreateRequest::new(
+            temperature.clone().into(),
+            MonitoringMode::Reporting,
+            MonitoringParameters { sampling_interval: 100.0, ..Default::default() },
+        );
+        let results = session.create_monitored_items(subscription_id, TimestampsToReturn::Both, &[request]).unwrap();
+        assert_eq!(results[0].status_code, StatusCode::Good);
+    }
+
+    bridge.update_from_frame(&DataFrame::new("plc1.temperature", frame_bus::Value::float(22.75))).unwrap();
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    loop {
+        let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+        let value = rx.recv_timeout(remaining).expect("no data change notification");
+        if value.value == Some(Variant::Double(22.75)) {
+            break;
+        }
+    }
+
+    // 写入：未开启写入时全部拒绝
+    {
+        let session = session.read();
+        let write = |node_id: &NodeId, value: Variant| WriteValue {
+            node_id: node_id.clone(),
+            attribute_id: AttributeId::Value as u32,
+            index_range: UAString::null(),
+            value: value.into(),
+        };
+        let results = session.write(&[write(&setpoint, Variant::Int32(55)), write(&temperature, Variant::Double(1.0))]).unwrap();
+        assert!(results.iter().all(|status| status.is_bad()));
+    }
+    assert!(commands.lock().unwrap().is_empty());
+
+    let _ = session_cmd.send(SessionCommand::Stop);
+    session.read().disconnect();
+    rt.block_on(bridge.stop()).unwrap();
+}
+
+#[test]
+fn test_writes_require_secured_endpoint() {
+    let pki = tempfile::tempdir().unwrap();
+    let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+
+    let mut config = OpcUaConfig::default();
+    config.base.bind_address = "127.0.0.1".to_string();
+    config.base.port = port;
+    config.pki_dir = pki.path().join("server");
+    config.create_sample_keypair = true;
+    config.enable_writes = true;
+    assert!(OpcUaBridge::new(config.clone()).is_err(), "writes without users must be rejected");
+    config.users = vec![OpcUaUser { username: "operator".to_string(), password: "secret".to_string() }];
+
+    let commands: Arc<Mutex<Vec<CmdFrame>>> = Arc::new(Mutex::new(Vec::new()));
+    let captured = commands.clone();
+    let bridge = OpcUaBridge::new(config).unwrap()
+        .with_command_sink(Arc::new(move |cmd| {
+            captured.lock().unwrap().push(cmd);
+            Ok(())
+        }));
+
+    let mut variables = HashMap::new();
+    variables.insert("plc1.temperature".to_string(), variable("plc1", DataType::Float64, Access::Read, ""));
+    variables.insert("plc1.setpoint".to_string(), variable("plc1", DataType::Int32, Access::ReadWrite, ""));
+    bridge.load_variables(&VariablesConfig { variables }).unwrap();
+
+    let mut client = ClientBuilder::new()
+        .application_name("gateway-test-client")
+        .application_uri("urn:gateway:test:client")
+        .create_sample_keypair(true)
+        .trust_server_certs(true)
+        .pki_dir(pki.path().join("client"))
+        .session_retry_limit(0)
+        .client()
+        .unwrap();
+
+    // 服务器信任客户端证书
+    let client_cert = opcua::crypto::CertificateStore::read_cert(&pki.path().join("client/own/cert.der")).unwrap();
+    let trusted = pki.path().join("server/trusted");
+    std::fs::create_dir_all(&trusted).unwrap();
+    std::fs::write(
+        trusted.join(opcua::crypto::CertificateStore::cert_file_name(&client_cert)),
+        client_cert.to_der().unwrap(),
+    ).unwrap();
+
+    let rt = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+    rt.block_on(bridge.start()).unwrap();
+    let url = format!("opc.tcp://127.0.0.1:{}/UA/Server", port);
+
+    // 不再提供匿名明文端点
+    let anonymous = client.connect_to_endpoint(
+        (url.as_str(), SecurityPolicy::None.to_str(), MessageSecurityMode::None, UserTokenPolicy::anonymous()),
+        IdentityToken::Anonymous,
+    );
+    assert!(anonymous.is_err());
+
+    let session = client.connect_to_endpoint(
+        (url.as_str(), SecurityPolicy::Basic256Sha256.to_str(), MessageSecurityMode::SignAndEncrypt, UserTokenPolicy::anonymous()),
+        IdentityToken::UserName("operator".to_string(), "secret".to_string()),
+    ).unwrap();
+    let session_cmd = Session::run_async(session.clone());
+
+    {
+        let session = session.read();
+        let namespaces = read(&session, &VariableId::Server_NamespaceArray.into());
+        let Some(Variant::Array(array)) = namespaces.value else { panic!("namespace array missing") };
+        let namespace = array.values.iter()
+            .position(|v| *v == Variant::from("urn:gateway:opcua:server"))
+            .unwrap() as u16;
+
+        // 写入：可写点位转换为CmdFrame，只读点位拒绝
+        let write = |id: &str, value: Variant| WriteValue {
+            node_id: NodeId::new(namespace, id.to_string()),
+            attribute_id: AttributeId::Value as u32,
+            index_range: UAString::null(),
+            value: value.into(),
+        };
+        let results = session.write(&[write("plc1.setpoint", Variant::Int32(55)), write("plc1.temperature", Variant::Double(1.0))]).unwrap();
+        assert_eq!(results[0], StatusCode::Good);
+        assert!(results[1].is_bad());
+    }
+
+    let commands = commands.lock().unwrap().clone();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].tag, "plc1.setpoint");
+    assert_eq!(commands[0].origin, "opcua-server");
+    assert_eq!(commands[0].value.as_ref().and_then(|v| v.to_i64()), Some(55));
+
+    let _ = session_cmd.send(SessionCommand::Stop);
+    session.read().disconnect();
+    rt.block_on(bridge.stop()).unwrap();
+}
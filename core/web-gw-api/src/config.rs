@@ -225,7 +225,7 @@ impl Default for ConnectionRetryConfig {
     }
 }
 
-fn redact_dsn(dsn: &str) -> String {
+pub(crate) fn redact_dsn(dsn: &str) -> String {
     if let Ok(url) = url::Url::parse(dsn) {
         let mut redacted = url.clone();
         if url.password().is_some() {
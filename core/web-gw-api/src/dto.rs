@@ -885,4 +885,89 @@ pub struct DriverConfigStatusVO {
     pub driver_state: Option<String>, // Debug格式的DriverState
     pub status_message: String,
     pub last_checked: DateTime<Utc>,
-}
\ No newline at end of file
+}
+// ========== 北向连接器相关 DTO ==========
+
+/// 北向连接器配置
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ConnectorConfigReq {
+    pub connector_type: String, // "mqtt5", "mqtt"
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub auto_connect: bool,
+    #[serde(default = "default_connection_timeout_seconds")]
+    pub connection_timeout_seconds: u32,
+    #[serde(default = "default_keepalive_interval_seconds")]
+    pub keepalive_interval_seconds: u32,
+    /// 点位通配模式，为空时转发全部点位
+    #[serde(default)]
+    pub tag_filter: Vec<String>,
+    /// 连接器类型相关设置，MQTT连接器按MqttCfg解析
+    #[serde(default)]
+    #[schema(value_type = Object)]
+    pub settings: std::collections::HashMap<String, serde_json::Value>,
+}
+
+fn default_connection_timeout_seconds() -> u32 {
+    30
+}
+
+fn default_keepalive_interval_seconds() -> u32 {
+    60
+}
+
+/// 创建北向连接器请求
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConnectorCreateReq {
+    pub name: String,
+    #[serde(flatten)]
+    pub config: ConnectorConfigReq,
+}
+
+/// 北向连接器运行统计
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConnectorMetricsVO {
+    pub connected: bool,
+    pub messages_published: u64,
+    pub points_published: u64,
+    pub publish_errors: u64,
+    pub historical_messages: u64,
+    pub last_publish_ms: u64,
+}
+
+/// 北向连接器状态值对象
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConnectorStatusVO {
+    pub name: String,
+    pub connector_type: String,
+    pub enabled: bool,
+    pub auto_connect: bool,
+    pub tag_filter: Vec<String>,
+    pub state: String, // "stopped", "running", "failed"
+    pub last_error: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub metrics: ConnectorMetricsVO,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConnectorResponse {
+    pub connector: ConnectorStatusVO,
+    pub config: ConnectorConfigReq,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConnectorListResponse {
+    pub connectors: Vec<ConnectorStatusVO>,
+    pub total: usize,
+}
+
+/// 北向连接器生命周期操作响应
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConnectorLifecycleResponse {
+    pub name: String,
+    pub operation: String, // "start", "stop", "restart"
+    pub success: bool,
+    pub message: String,
+    pub current_status: Option<ConnectorStatusVO>,
+}
@@ -12,6 +12,7 @@ pub mod services;
 pub mod openapi;
 pub mod middleware;
 pub mod response;
+pub mod server;
 
 // 重新导出常用类型和函数
 pub use bootstrap::{init_state, AppState};
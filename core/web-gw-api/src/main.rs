@@ -10,11 +10,9 @@
 //! 更新历史：
 //! - 2025-01-27  Claude  初版
 
-use actix_web::{middleware::Logger, App, HttpResponse, HttpServer, web};
 use anyhow::Context;
 use metrics_exporter_prometheus::PrometheusBuilder;
 use tracing::info;
-use utoipa::OpenApi;
 
 // 使用同包内的库 `web_gw_api` 暴露的模块与函数
 use web_gw_api as api;
@@ -60,30 +58,10 @@ async fn main() -> anyhow::Result<()> {
     info!("API Documentation available on {}/docs/swagger-ui/", config.http_addr);
     info!("OpenAPI JSON schema: {}/docs/openapi.json", config.http_addr);
     
-    // 启动HTTP服务器
-    HttpServer::new(move || {
-        App::new()
-            .app_data(state.clone())
-            .wrap(Logger::default())
-            .wrap(state.cors())
-            .wrap(prometheus_middleware())
-            .configure(api::routes::configure)
-            // 直接暴露关键路由，确保调试阶段可用
-            .route("/docs/openapi.json", web::get().to(|| async {
-                let openapi = api::openapi::ApiDoc::openapi();
-                HttpResponse::Ok().json(openapi)
-            }))
-            .route("/system/health", web::get().to(api::routes::system::get_system_health))
-            .route("/api/v1/system/health", web::get().to(api::routes::system::get_system_health))
-            .route("/hello", web::get().to(|| async { HttpResponse::Ok().body("hi") }))
-            // OpenAPI & Swagger UI 路由
-            .configure(api::openapi::configure)
-    })
-    .bind(config.http_addr)
-    .context("Failed to bind HTTP server")?
-    .run()
-    .await
-    .context("HTTP server error")?;
+    // 启动HTTP服务器，独立运行时没有网关的连接器管理器
+    api::server::build(&config, state, None)?
+        .await
+        .context("HTTP server error")?;
     
     Ok(())
 }
@@ -106,10 +84,3 @@ fn init_logging() -> anyhow::Result<()> {
     
     Ok(())
 }
-
-/// 创建Prometheus指标中间件
-fn prometheus_middleware() -> actix_web::middleware::DefaultHeaders {
-    actix_web::middleware::DefaultHeaders::new()
-        .add(("X-Service", "web-gw-api"))
-        .add(("X-Version", env!("CARGO_PKG_VERSION")))
-}
\ No newline at end of file
@@ -1,6 +1,10 @@
-//! middleware.rs —— Actix 自定义中间件占位模块
+//! middleware.rs —— Actix 自定义中间件
 //!
-//! 迁移阶段保留空模块以避免编译错误；如需自定义全局中间件，可在此添加。
+//! 迁移阶段仅提供默认响应头；如需自定义全局中间件，可在此添加。
 
-// 目前中间件由 main.rs 内部的 `prometheus_middleware()` 提供默认头部，
-// 这里暂不导出额外内容。
+/// 创建Prometheus指标中间件
+pub fn prometheus_middleware() -> actix_web::middleware::DefaultHeaders {
+    actix_web::middleware::DefaultHeaders::new()
+        .add(("X-Service", "web-gw-api"))
+        .add(("X-Version", env!("CARGO_PKG_VERSION")))
+}
@@ -65,6 +65,17 @@ use tracing::info;
         crate::routes::drivers::reload_driver,
         crate::routes::drivers::unload_driver,
         
+        // 北向连接器管理
+        crate::routes::connectors::create_connector,
+        crate::routes::connectors::list_connectors,
+        crate::routes::connectors::get_connector,
+        crate::routes::connectors::update_connector,
+        crate::routes::connectors::delete_connector,
+        crate::routes::connectors::start_connector,
+        crate::routes::connectors::stop_connector,
+        crate::routes::connectors::restart_connector,
+        crate::routes::connectors::get_connector_status,
+        
         // 历史数据查询
         crate::routes::history::query_points,
         crate::routes::history::query_stats,
@@ -89,6 +100,10 @@ use tracing::info;
             // 驱动相关类型
             DriverInfo, DriverReloadRequest,
             
+            // 北向连接器相关类型
+            ApiErrorResponse, ConnectorConfigReq, ConnectorCreateReq, ConnectorMetricsVO, ConnectorStatusVO,
+            ConnectorResponse, ConnectorListResponse, ConnectorLifecycleResponse,
+            
             // 历史数据相关类型
             HistoryQuery, HistoryPointVO, HistoryStatsVO, HistoryExportRequest,
         )
@@ -98,6 +113,7 @@ use tracing::info;
         (name = "Devices", description = "设备管理 - 创建、查询、更新、删除设备"),
         (name = "Tags", description = "点位管理 - 创建、查询、更新、删除数据点位"),
         (name = "Drivers", description = "驱动管理 - 上传、重载、卸载协议驱动"),
+        (name = "connectors", description = "北向连接器 - 创建、配置、启停MQTT等北向连接器"),
         (name = "History", description = "历史数据 - 时间序列查询、聚合分析、数据导出"),
         (name = "Alerts", description = "报警管理 - 规则配置、事件查询、通知设置（代理到alert-engine）"),
     ),
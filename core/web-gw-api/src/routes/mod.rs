@@ -6,6 +6,7 @@
 //! - tags: 点位管理  
 //! - drivers: 驱动管理
 //! - driver_configs: 驱动配置管理
+//! - connectors: 北向连接器管理（使用edge-gateway注入的连接器管理器）
 //! - history: 历史数据查询
 //! - alerts: 报警管理
 //! - telemetry_ws: WebSocket实时数据
@@ -19,6 +20,7 @@ pub mod devices;
 pub mod tags;
 pub mod drivers;
 pub mod driver_configs;
+pub mod connectors;
 pub mod history;
 pub mod websocket;
 pub mod alerts;
@@ -44,6 +46,7 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
                 .service(tags::scope_as_datapoints()) // datapoints别名指向tags
                 .service(drivers::scope())
                 .service(driver_configs::scope())
+                .service(connectors::scope())
                 // 暴露与根路径一致的 system 路由，兼容旧前端对 /api/v1/system/* 的访问
                 .service(system::scope())
                 .configure(history::configure)
@@ -0,0 +1,55 @@
+//! server.rs —— HTTP服务构建
+//!
+//! 独立运行的web-gw-api与嵌入edge-gateway运行时共用同一套中间件与路由；
+//! 北向连接器路由使用调用方注入的`ConnectorManager`，未注入时不可用。
+
+use std::sync::Arc;
+
+use actix_web::{dev::Server, middleware::Logger, web, App, HttpResponse, HttpServer};
+use anyhow::Context;
+use connector_manager::ConnectorManager;
+use tracing::{info, warn};
+use utoipa::OpenApi;
+
+use crate::bootstrap::AppState;
+use crate::config::ApiConfig;
+
+/// 构建并绑定HTTP服务，返回的`Server`需由调用方驱动
+pub fn build(
+    config: &ApiConfig,
+    state: web::Data<AppState>,
+    connector_manager: Option<Arc<ConnectorManager>>,
+) -> anyhow::Result<Server> {
+    let connector_manager = connector_manager.map(web::Data::from);
+    if connector_manager.is_none() {
+        warn!("No connector manager provided, /api/v1/connectors is unavailable outside edge-gateway");
+    }
+
+    let server = HttpServer::new(move || {
+        let mut app = App::new().app_data(state.clone());
+        if let Some(manager) = &connector_manager {
+            app = app.app_data(manager.clone());
+        }
+        app
+            .wrap(Logger::default())
+            .wrap(state.cors())
+            .wrap(crate::middleware::prometheus_middleware())
+            .configure(crate::routes::configure)
+            // 直接暴露关键路由，确保调试阶段可用
+            .route("/docs/openapi.json", web::get().to(|| async {
+                let openapi = crate::openapi::ApiDoc::openapi();
+                HttpResponse::Ok().json(openapi)
+            }))
+            .route("/system/health", web::get().to(crate::routes::system::get_system_health))
+            .route("/api/v1/system/health", web::get().to(crate::routes::system::get_system_health))
+            .route("/hello", web::get().to(|| async { HttpResponse::Ok().body("hi") }))
+            // OpenAPI & Swagger UI 路由
+            .configure(crate::openapi::configure)
+    })
+    .bind(config.http_addr)
+    .context("Failed to bind HTTP server")?
+    .run();
+
+    info!("HTTP server bound on {}", config.http_addr);
+    Ok(server)
+}
@@ -1,6 +1,7 @@
 //! Modbus数据编解码
 
 use crate::config::{DataType, Endian, RegPoint};
+use crate::expr::{Expr, NoTags, TagValues};
 use frame_bus::Value;
 use anyhow::Result;
 
@@ -11,7 +12,10 @@ pub fn decode_registers(
     start_addr: u16,
     endian: &Endian,
 ) -> Result<Value> {
-    let offset = (point.addr.saturating_sub(start_addr)) as usize;
+    if point.addr < start_addr {
+        return Err(anyhow::anyhow!("Point address {} precedes batch start {}", point.addr, start_addr));
+    }
+    let offset = (point.addr - start_addr) as usize;
     
     if offset >= regs.len() {
         return Err(anyhow::anyhow!("Register offset {} out of bounds", offset));
@@ -23,91 +27,71 @@ pub fn decode_registers(
             Value::bool(reg != 0)
         }
         DataType::Uint16 => {
-            let reg = regs[offset];
-            let val = match endian {
-                Endian::Big => reg,
-                Endian::Little => reg.swap_bytes(),
-            };
+            let val = swap16(regs[offset], endian);
             Value::int(val as i64)
         }
         DataType::Int16 => {
-            let reg = regs[offset];
-            let val = match endian {
-                Endian::Big => reg as i16,
-                Endian::Little => (reg.swap_bytes()) as i16,
-            };
+            let val = swap16(regs[offset], endian) as i16;
             Value::int(val as i64)
         }
         DataType::Uint32 => {
-            if offset + 1 >= regs.len() {
-                return Err(anyhow::anyhow!("Not enough registers for uint32"));
-            }
-            let val = match endian {
-                Endian::Big => {
-                    ((regs[offset] as u32) << 16) | (regs[offset + 1] as u32)
-                }
-                Endian::Little => {
-                    ((regs[offset + 1] as u32) << 16) | (regs[offset] as u32)
-                }
-            };
+            let val = combine_words(words(regs, offset, 2, "uint32")?, endian) as u32;
             Value::int(val as i64)
         }
         DataType::Int32 => {
-            if offset + 1 >= regs.len() {
-                return Err(anyhow::anyhow!("Not enough registers for int32"));
-            }
-            let val = match endian {
-                Endian::Big => {
-                    ((regs[offset] as u32) << 16) | (regs[offset + 1] as u32)
-                }
-                Endian::Little => {
-                    ((regs[offset + 1] as u32) << 16) | (regs[offset] as u32)
-                }
-            } as i32;
+            let val = combine_words(words(regs, offset, 2, "int32")?, endian) as u32 as i32;
             Value::int(val as i64)
         }
-        DataType::Float32 => {
-            if offset + 1 >= regs.len() {
-                return Err(anyhow::anyhow!("Not enough registers for float32"));
+        DataType::Uint64 => {
+            let val = combine_words(words(regs, offset, 4, "uint64")?, endian);
+            // 超出i64范围的值只能以浮点数表示
+            match i64::try_from(val) {
+                Ok(val) => Value::int(val),
+                Err(_) => Value::float(val as f64),
             }
-            let bits = match endian {
-                Endian::Big => {
-                    ((regs[offset] as u32) << 16) | (regs[offset + 1] as u32)
-                }
-                Endian::Little => {
-                    ((regs[offset + 1] as u32) << 16) | (regs[offset] as u32)
-                }
-            };
+        }
+        DataType::Int64 => {
+            let val = combine_words(words(regs, offset, 4, "int64")?, endian) as i64;
+            Value::int(val)
+        }
+        DataType::Float32 => {
+            let bits = combine_words(words(regs, offset, 2, "float32")?, endian) as u32;
             let val = f32::from_bits(bits);
             Value::float(val as f64)
         }
         DataType::Float64 => {
-            if offset + 3 >= regs.len() {
-                return Err(anyhow::anyhow!("Not enough registers for float64"));
-            }
-            let bits = match endian {
-                Endian::Big => {
-                    ((regs[offset] as u64) << 48)
-                        | ((regs[offset + 1] as u64) << 32)
-                        | ((regs[offset + 2] as u64) << 16)
-                        | (regs[offset + 3] as u64)
-                }
-                Endian::Little => {
-                    ((regs[offset + 3] as u64) << 48)
-                        | ((regs[offset + 2] as u64) << 32)
-                        | ((regs[offset + 1] as u64) << 16)
-                        | (regs[offset] as u64)
-                }
-            };
+            let bits = combine_words(words(regs, offset, 4, "float64")?, endian);
             let val = f64::from_bits(bits);
             Value::float(val)
         }
+        DataType::String => {
+            let regs = words(regs, offset, point.len.max(1) as usize, "string")?;
+            Value::string(decode_ascii(regs, endian)?)
+        }
+        DataType::Bcd16 => {
+            let val = combine_words(words(regs, offset, 1, "bcd16")?, endian);
+            Value::int(decode_bcd(val, 4)? as i64)
+        }
+        DataType::Bcd32 => {
+            let val = combine_words(words(regs, offset, 2, "bcd32")?, endian);
+            Value::int(decode_bcd(val, 8)? as i64)
+        }
+        DataType::Bit(bit) => {
+            if bit > 15 {
+                return Err(anyhow::anyhow!("Bit {} out of range 0-15", bit));
+            }
+            let reg = words(regs, offset, 1, "bit")?[0];
+            Value::bool(reg & (1 << bit) != 0)
+        }
     };
 
     Ok(value)
 }
 
 /// 编码值为Modbus寄存器（用于写入）
+///
+/// 字符串编码为最少的寄存器数，按点位长度补齐见[`encode_point`]；
+/// 位点位需要读-改-写，见[`set_bit`]
 pub fn encode_value(
     value: &Value,
     datatype: &DataType,
@@ -120,111 +104,261 @@ pub fn encode_value(
         }
         DataType::Uint16 => {
             let val = value.to_i64().unwrap_or(0) as u16;
-            let reg = match endian {
-                Endian::Big => val,
-                Endian::Little => val.swap_bytes(),
-            };
-            Ok(vec![reg])
+            Ok(vec![swap16(val, endian)])
         }
         DataType::Int16 => {
             let val = value.to_i64().unwrap_or(0) as i16 as u16;
-            let reg = match endian {
-                Endian::Big => val,
-                Endian::Little => val.swap_bytes(),
-            };
-            Ok(vec![reg])
+            Ok(vec![swap16(val, endian)])
         }
         DataType::Uint32 => {
             let val = value.to_i64().unwrap_or(0) as u32;
-            match endian {
-                Endian::Big => Ok(vec![(val >> 16) as u16, val as u16]),
-                Endian::Little => Ok(vec![val as u16, (val >> 16) as u16]),
-            }
+            Ok(split_words(val as u64, 2, endian))
         }
         DataType::Int32 => {
             let val = value.to_i64().unwrap_or(0) as i32 as u32;
-            match endian {
-                Endian::Big => Ok(vec![(val >> 16) as u16, val as u16]),
-                Endian::Little => Ok(vec![val as u16, (val >> 16) as u16]),
-            }
+            Ok(split_words(val as u64, 2, endian))
+        }
+        DataType::Uint64 => {
+            let val = match value.to_i64() {
+                Some(v) if v >= 0 => v as u64,
+                _ => value.to_f64().filter(|v| *v >= 0.0).map(|v| v as u64)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid uint64 value: {:?}", value))?,
+            };
+            Ok(split_words(val, 4, endian))
+        }
+        DataType::Int64 => {
+            let val = value.to_i64().unwrap_or(0);
+            Ok(split_words(val as u64, 4, endian))
         }
         DataType::Float32 => {
             let val = value.to_f64().unwrap_or(0.0) as f32;
-            let bits = val.to_bits();
-            match endian {
-                Endian::Big => Ok(vec![(bits >> 16) as u16, bits as u16]),
-                Endian::Little => Ok(vec![bits as u16, (bits >> 16) as u16]),
-            }
+            Ok(split_words(val.to_bits() as u64, 2, endian))
         }
         DataType::Float64 => {
             let val = value.to_f64().unwrap_or(0.0);
-            let bits = val.to_bits();
-            match endian {
-                Endian::Big => Ok(vec![
-                    (bits >> 48) as u16,
-                    (bits >> 32) as u16,
-                    (bits >> 16) as u16,
-                    bits as u16,
-                ]),
-                Endian::Little => Ok(vec![
-                    bits as u16,
-                    (bits >> 16) as u16,
-                    (bits >> 32) as u16,
-                    (bits >> 48) as u16,
-                ]),
-            }
+            Ok(split_words(val.to_bits(), 4, endian))
+        }
+        DataType::String => {
+            let text = value.to_string()
+                .ok_or_else(|| anyhow::anyhow!("Invalid string value: {:?}", value))?;
+            encode_ascii(&text, endian)
+        }
+        DataType::Bcd16 => {
+            let val = encode_bcd(value, 4)?;
+            Ok(split_words(val, 1, endian))
+        }
+        DataType::Bcd32 => {
+            let val = encode_bcd(value, 8)?;
+            Ok(split_words(val, 2, endian))
+        }
+        DataType::Bit(_) => {
+            Err(anyhow::anyhow!("Bit points must be written with read-modify-write"))
+        }
+    }
+}
+
+/// 按点位编码写入值，字符串以NUL补齐到点位长度
+pub fn encode_point(value: &Value, point: &RegPoint, endian: &Endian) -> Result<Vec<u16>> {
+    let mut regs = encode_value(value, &point.datatype, endian)?;
+
+    if let DataType::String = point.datatype {
+        let len = point.len.max(1) as usize;
+        if regs.len() > len {
+            return Err(anyhow::anyhow!(
+                "String of {} registers does not fit point {} ({} registers)", regs.len(), point.tag, len
+            ));
+        }
+        regs.resize(len, 0);
+    }
+
+    Ok(regs)
+}
+
+/// 设置寄存器中的单个位，返回新的寄存器值
+pub fn set_bit(current: u16, bit: u8, on: bool) -> Result<u16> {
+    if bit > 15 {
+        return Err(anyhow::anyhow!("Bit {} out of range 0-15", bit));
+    }
+    Ok(if on { current | (1 << bit) } else { current & !(1 << bit) })
+}
+
+/// 单寄存器值的字节序转换
+fn swap16(reg: u16, endian: &Endian) -> u16 {
+    match endian {
+        Endian::Big => reg,
+        Endian::Little | Endian::BigSwap | Endian::LittleSwap => reg.swap_bytes(),
+    }
+}
+
+/// 多寄存器值是否在寄存器内交换字节
+fn swaps_bytes(endian: &Endian) -> bool {
+    matches!(endian, Endian::BigSwap | Endian::LittleSwap)
+}
+
+/// 取出从`offset`开始的`count`个寄存器
+fn words<'a>(regs: &'a [u16], offset: usize, count: usize, name: &str) -> Result<&'a [u16]> {
+    regs.get(offset..offset + count)
+        .ok_or_else(|| anyhow::anyhow!("Not enough registers for {}", name))
+}
+
+/// 按字序和字节序将寄存器组合为整数 (最多4个寄存器)
+fn combine_words(regs: &[u16], endian: &Endian) -> u64 {
+    let ordered: Vec<u16> = match endian {
+        Endian::Big | Endian::BigSwap => regs.to_vec(),
+        Endian::Little | Endian::LittleSwap => regs.iter().rev().copied().collect(),
+    };
+
+    ordered.into_iter().fold(0u64, |acc, reg| {
+        let reg = if swaps_bytes(endian) { reg.swap_bytes() } else { reg };
+        (acc << 16) | reg as u64
+    })
+}
+
+/// 按字序和字节序将整数拆分为`count`个寄存器
+fn split_words(value: u64, count: usize, endian: &Endian) -> Vec<u16> {
+    let mut regs: Vec<u16> = (0..count)
+        .rev()
+        .map(|i| (value >> (16 * i)) as u16)
+        .map(|reg| if swaps_bytes(endian) { reg.swap_bytes() } else { reg })
+        .collect();
+
+    if matches!(endian, Endian::Little | Endian::LittleSwap) {
+        regs.reverse();
+    }
+    regs
+}
+
+/// 解码ASCII字符串 (每个寄存器高字节在前，遇NUL结束)
+fn decode_ascii(regs: &[u16], endian: &Endian) -> Result<String> {
+    let bytes: Vec<u8> = regs.iter()
+        .flat_map(|reg| {
+            let reg = if swaps_bytes(endian) { reg.swap_bytes() } else { *reg };
+            reg.to_be_bytes()
+        })
+        .take_while(|b| *b != 0)
+        .collect();
+
+    if !bytes.is_ascii() {
+        return Err(anyhow::anyhow!("String contains non-ASCII bytes"));
+    }
+    Ok(String::from_utf8(bytes)?.trim_end().to_string())
+}
+
+/// 编码ASCII字符串，奇数长度以NUL补齐
+fn encode_ascii(text: &str, endian: &Endian) -> Result<Vec<u16>> {
+    if !text.is_ascii() {
+        return Err(anyhow::anyhow!("String '{}' is not ASCII", text));
+    }
+
+    Ok(text.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let reg = u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)]);
+            if swaps_bytes(endian) { reg.swap_bytes() } else { reg }
+        })
+        .collect())
+}
+
+/// 解码压缩BCD
+fn decode_bcd(raw: u64, digits: u32) -> Result<u64> {
+    (0..digits).rev().try_fold(0u64, |acc, i| {
+        let digit = (raw >> (4 * i)) & 0xF;
+        if digit > 9 {
+            return Err(anyhow::anyhow!("Invalid BCD digit 0x{:X} in 0x{:X}", digit, raw));
         }
+        Ok(acc * 10 + digit)
+    })
+}
+
+/// 编码压缩BCD
+fn encode_bcd(value: &Value, digits: u32) -> Result<u64> {
+    let val = value.to_i64()
+        .ok_or_else(|| anyhow::anyhow!("Invalid BCD value: {:?}", value))?;
+    if val < 0 || val >= 10i64.pow(digits) {
+        return Err(anyhow::anyhow!("Value {} out of range for {}-digit BCD", val, digits));
+    }
+
+    let mut val = val as u64;
+    let mut raw = 0u64;
+    for i in 0..digits {
+        raw |= (val % 10) << (4 * i);
+        val /= 10;
     }
+    Ok(raw)
 }
 
 /// 应用缩放表达式
+///
+/// 每次调用都会重新编译，仅适合一次性转换；轮询路径使用`init`时编译好的[`PointScale`]
 pub fn apply_scale(value: Value, scale_expr: Option<&str>) -> Result<Value> {
     match scale_expr {
-        Some(expr) => {
-            // 简单实现：支持基本的数学表达式
-            // 完整实现应该使用Lua解释器
-            if let Some(raw_val) = value.to_f64() {
-                let result = eval_simple_expression(expr, raw_val)?;
-                Ok(Value::float(result))
-            } else {
-                Ok(value)
-            }
-        }
+        Some(expr) => PointScale::compile(Some(expr), None)?.scale(value, &NoTags),
         None => Ok(value),
     }
 }
 
-/// 简单表达式求值器（基础实现）
-fn eval_simple_expression(expr: &str, value: f64) -> Result<f64> {
-    // 支持形如 "value / 10.0", "value * 0.1", "value + 100" 的表达式
-    let expr = expr.trim().replace("value", &value.to_string());
-    
-    // 非常简单的解析器，仅用于演示
-    if let Some(pos) = expr.find('/') {
-        let left: f64 = expr[..pos].trim().parse()?;
-        let right: f64 = expr[pos + 1..].trim().parse()?;
-        
-        // 检查除零错误
-        if right == 0.0 {
-            return Err(anyhow::anyhow!("Division by zero in scale expression"));
+/// 点位的已编译缩放与反向缩放表达式
+#[derive(Debug, Clone, Default)]
+pub struct PointScale {
+    scale: Option<Expr>,
+    unscale: Option<Expr>,
+}
+
+impl PointScale {
+    /// 编译表达式，未配置`unscale`且`scale`为仿射变换时自动推导反向变换
+    pub fn compile(scale: Option<&str>, unscale: Option<&str>) -> Result<Self> {
+        let scale = scale.map(Expr::compile).transpose()?;
+        let unscale = unscale.map(Expr::compile).transpose()?;
+        Ok(Self { scale, unscale })
+    }
+
+    /// 表达式引用的其他点位
+    pub fn tag_refs(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self.scale.iter()
+            .chain(self.unscale.iter())
+            .flat_map(Expr::tag_refs)
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// 原始值 -> 工程值，非数值类型原样返回
+    pub fn scale(&self, value: Value, tags: &dyn TagValues) -> Result<Value> {
+        match (&self.scale, value.to_f64()) {
+            (Some(expr), Some(raw)) => Ok(Value::float(expr.eval(raw, tags)?)),
+            _ => Ok(value),
         }
-        
-        Ok(left / right)
-    } else if let Some(pos) = expr.find('*') {
-        let left: f64 = expr[..pos].trim().parse()?;
-        let right: f64 = expr[pos + 1..].trim().parse()?;
-        Ok(left * right)
-    } else if let Some(pos) = expr.find('+') {
-        let left: f64 = expr[..pos].trim().parse()?;
-        let right: f64 = expr[pos + 1..].trim().parse()?;
-        Ok(left + right)
-    } else if let Some(pos) = expr.find('-') {
-        let left: f64 = expr[..pos].trim().parse()?;
-        let right: f64 = expr[pos + 1..].trim().parse()?;
-        Ok(left - right)
-    } else {
-        // 直接数值
-        Ok(expr.parse()?)
+    }
+
+    /// 工程值 -> 原始值，整数类型四舍五入
+    ///
+    /// 优先使用`unscale`表达式，未配置时由仿射的`scale`推导；两者都未配置时原样返回
+    pub fn unscale(&self, value: Value, datatype: &DataType, tags: &dyn TagValues) -> Result<Value> {
+        if self.scale.is_none() && self.unscale.is_none() {
+            return Ok(value);
+        }
+        if matches!(datatype, DataType::Bool | DataType::Bit(_) | DataType::String) {
+            return Ok(value);
+        }
+
+        let engineering = value.to_f64()
+            .ok_or_else(|| anyhow::anyhow!("Cannot unscale non-numeric value for {:?}", datatype))?;
+        let raw = match (&self.unscale, &self.scale) {
+            (Some(expr), _) => expr.eval(engineering, tags)?,
+            (None, Some(scale)) => {
+                let (a, b) = scale.affine_inverse().ok_or_else(|| anyhow::anyhow!(
+                    "Scale expression '{}' is not invertible, configure an unscale expression", scale.source()
+                ))?;
+                a * engineering + b
+            }
+            (None, None) => unreachable!(),
+        };
+
+        Ok(match datatype {
+            DataType::Float32 | DataType::Float64 => Value::float(raw),
+            _ => Value::int(raw.round() as i64),
+        })
     }
 }
 
@@ -242,7 +376,9 @@ mod tests {
             len: 1,
             datatype: DataType::Uint16,
             scale: None,
+            unscale: None,
             access: crate::config::Access::R,
+            scan: crate::config::ScanClass::Normal,
         };
         
         let value = decode_registers(&regs, &point, 0, &Endian::Big).unwrap();
@@ -251,7 +387,7 @@ mod tests {
 
     #[test]
     fn test_simple_scale_expression() {
-        let result = eval_simple_expression("123.0 / 10.0", 123.0).unwrap();
+        let result = Expr::compile("123.0 / 10.0").unwrap().eval(123.0, &NoTags).unwrap();
         assert_eq!(result, 12.3);
     }
 }
\ No newline at end of file
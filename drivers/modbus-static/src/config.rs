@@ -6,14 +6,23 @@ use std::time::Duration;
 /// Modbus驱动配置
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ModbusCfg {
+    /// 驱动实例ID，作为发布主体接受总线ACL约束 (由DriverManager注入)
+    #[serde(default = "ModbusCfg::default_instance_id")]
+    pub instance_id: String,
     /// Modbus单元ID (1-247)
     pub unit_id: u8,
-    /// 轮询间隔
-    #[serde(with = "humantime_serde")]
+    /// 轮询间隔 (normal扫描等级)
+    #[serde(with = "positive_duration")]
     pub polling: Duration,
+    /// fast/slow扫描等级的轮询间隔
+    #[serde(default)]
+    pub scan_rates: ScanRates,
     /// 每次请求最大寄存器数量
     #[serde(default = "ModbusCfg::default_max_regs")]
     pub max_regs_per_req: u16,
+    /// 合并相邻块时允许跨越的最大空隙寄存器数
+    #[serde(default = "ModbusCfg::default_max_gap")]
+    pub max_gap_regs: u16,
     /// 重试次数
     #[serde(default = "ModbusCfg::default_retry")]
     pub retry: u8,
@@ -23,50 +32,335 @@ pub struct ModbusCfg {
     /// 是否启用写入
     #[serde(default)]
     pub enable_write: bool,
+    /// 单次请求超时
+    #[serde(default = "ModbusCfg::default_timeout", with = "humantime_serde")]
+    pub timeout: Duration,
+    /// 点位表，加载时逐项校验
+    #[serde(default)]
+    pub points: Vec<RegPoint>,
 }
 
 /// 字节序枚举
+///
+/// 多寄存器数值按字序 (word order) 与寄存器内字节序组合，以32位值`0xAABBCCDD`为例：
+/// `big`=ABCD, `little`=CDAB, `big_swap`=BADC, `little_swap`=DCBA
 #[derive(Debug, Clone, Default, Deserialize, Serialize)]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "snake_case")]
 pub enum Endian {
     #[default]
+    #[serde(alias = "abcd")]
     Big,
+    #[serde(alias = "cdab")]
     Little,
+    /// 高字在前，寄存器内字节交换
+    #[serde(alias = "badc")]
+    BigSwap,
+    /// 低字在前，寄存器内字节交换
+    #[serde(alias = "dcba")]
+    LittleSwap,
+}
+
+/// 扫描等级轮询间隔
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ScanRates {
+    #[serde(default = "ScanRates::default_fast", with = "positive_duration")]
+    pub fast: Duration,
+    #[serde(default = "ScanRates::default_slow", with = "positive_duration")]
+    pub slow: Duration,
+}
+
+/// 轮询间隔的序列化，为0的间隔会让定时器panic或空转，加载时拒绝
+mod positive_duration {
+    use serde::{de::Error, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        humantime_serde::serialize(duration, serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let duration: Duration = humantime_serde::deserialize(deserializer)?;
+        if duration.is_zero() {
+            return Err(D::Error::custom("polling interval must be greater than zero"));
+        }
+        Ok(duration)
+    }
+}
+
+impl ScanRates {
+    fn default_fast() -> Duration {
+        Duration::from_millis(100)
+    }
+
+    fn default_slow() -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+impl Default for ScanRates {
+    fn default() -> Self {
+        Self {
+            fast: Self::default_fast(),
+            slow: Self::default_slow(),
+        }
+    }
 }
 
 impl ModbusCfg {
+    /// 扫描等级对应的轮询间隔
+    pub fn scan_interval(&self, class: &ScanClass) -> Duration {
+        match class {
+            ScanClass::Fast => self.scan_rates.fast,
+            ScanClass::Normal => self.polling,
+            ScanClass::Slow => self.scan_rates.slow,
+            ScanClass::Interval(interval) => *interval,
+        }
+    }
+
+    fn default_instance_id() -> String {
+        "modbus-tcp".to_string()
+    }
+
     fn default_max_regs() -> u16 {
         120 // 低于Modbus 125限制，留安全余量
     }
 
+    fn default_max_gap() -> u16 {
+        10 // 空隙读取开销约等于一次额外请求的报文头开销
+    }
+
     fn default_retry() -> u8 {
         3
     }
+
+    fn default_timeout() -> Duration {
+        Duration::from_secs(1)
+    }
 }
 
 impl Default for ModbusCfg {
     fn default() -> Self {
         Self {
+            instance_id: Self::default_instance_id(),
             unit_id: 1,
             polling: Duration::from_secs(1),
+            scan_rates: ScanRates::default(),
             max_regs_per_req: Self::default_max_regs(),
+            max_gap_regs: Self::default_max_gap(),
             retry: Self::default_retry(),
             endian: Endian::Big,
             enable_write: false,
+            timeout: Self::default_timeout(),
+            points: Vec::new(),
         }
     }
 }
 
 /// 点位描述
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(try_from = "PointCfg", into = "PointCfg")]
 pub struct RegPoint {
     pub tag: String,
     pub func: tokio_modbus::FunctionCode,
     pub addr: u16,
     pub len: u16,
     pub datatype: DataType,
-    pub scale: Option<String>, // Lua表达式
+    /// 缩放表达式：原始值 -> 工程值
+    pub scale: Option<String>,
+    /// 反向缩放表达式：工程值 -> 原始值，仿射的`scale`可省略
+    pub unscale: Option<String>,
+    pub access: Access,
+    pub scan: ScanClass,
+}
+
+/// 点位配置项 (驱动配置`points`中的一项)
+///
+/// `address`为寄存器号或`addr.bit`；寄存器数由数据类型决定，仅`string`需要给出`len`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PointCfg {
+    pub tag: String,
+    /// 寄存器区：`holding`或`input`
+    #[serde(default)]
+    pub register: RegisterKind,
+    #[serde(deserialize_with = "de_address")]
+    pub address: String,
+    /// 数据类型，缺省为`uint16`，位地址缺省为`bit`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub datatype: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub len: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unscale: Option<String>,
+    #[serde(default)]
     pub access: Access,
+    #[serde(default)]
+    pub scan: ScanClass,
+}
+
+/// 寄存器区
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RegisterKind {
+    #[default]
+    #[serde(alias = "holding_register")]
+    Holding,
+    #[serde(alias = "input_register")]
+    Input,
+}
+
+/// 地址可写作数字或字符串
+fn de_address<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Register(u16),
+        Text(String),
+    }
+    Ok(match Raw::deserialize(deserializer)? {
+        Raw::Register(addr) => addr.to_string(),
+        Raw::Text(text) => text,
+    })
+}
+
+impl TryFrom<PointCfg> for RegPoint {
+    type Error = anyhow::Error;
+
+    fn try_from(cfg: PointCfg) -> anyhow::Result<Self> {
+        if cfg.tag.trim().is_empty() {
+            return Err(anyhow::anyhow!("Point tag must not be empty"));
+        }
+        let (addr, bit) = parse_address(&cfg.address)
+            .map_err(|e| anyhow::anyhow!("Point '{}': {}", cfg.tag, e))?;
+
+        let datatype = match (cfg.datatype.as_deref(), bit) {
+            (None | Some("bit") | Some("bool"), Some(bit)) => DataType::Bit(bit),
+            (Some(name), Some(_)) => {
+                return Err(anyhow::anyhow!("Point '{}': bit address requires datatype 'bit', got '{}'", cfg.tag, name));
+            }
+            (None, None) => DataType::Uint16,
+            (Some(name), None) => DataType::from_name(name)
+                .ok_or_else(|| anyhow::anyhow!("Point '{}': unknown datatype '{}'", cfg.tag, name))?,
+        };
+
+        let len = match (&datatype, cfg.len) {
+            (DataType::String, Some(len)) if len > 0 => len,
+            (DataType::String, _) => {
+                return Err(anyhow::anyhow!("Point '{}': string datatype requires len > 0", cfg.tag));
+            }
+            (datatype, Some(len)) if len != datatype.register_count() => {
+                return Err(anyhow::anyhow!(
+                    "Point '{}': len {} does not match {} ({} registers)",
+                    cfg.tag, len, datatype.name(), datatype.register_count()
+                ));
+            }
+            (datatype, _) => datatype.register_count(),
+        };
+        if addr as u32 + len as u32 > 0x10000 {
+            return Err(anyhow::anyhow!("Point '{}': registers {}+{} exceed address space", cfg.tag, addr, len));
+        }
+
+        if cfg.register == RegisterKind::Input && !matches!(cfg.access, Access::R) {
+            return Err(anyhow::anyhow!("Point '{}': input registers are read-only", cfg.tag));
+        }
+        let func = match cfg.register {
+            RegisterKind::Holding => tokio_modbus::FunctionCode::ReadHoldingRegisters,
+            RegisterKind::Input => tokio_modbus::FunctionCode::ReadInputRegisters,
+        };
+
+        Ok(RegPoint {
+            tag: cfg.tag,
+            func,
+            addr,
+            len,
+            datatype,
+            scale: cfg.scale,
+            unscale: cfg.unscale,
+            access: cfg.access,
+            scan: cfg.scan,
+        })
+    }
+}
+
+impl From<RegPoint> for PointCfg {
+    fn from(point: RegPoint) -> Self {
+        let (address, len) = match point.datatype {
+            DataType::Bit(bit) => (format!("{}.{}", point.addr, bit), None),
+            DataType::String => (point.addr.to_string(), Some(point.len)),
+            _ => (point.addr.to_string(), None),
+        };
+        let register = match point.func {
+            tokio_modbus::FunctionCode::ReadInputRegisters => RegisterKind::Input,
+            _ => RegisterKind::Holding,
+        };
+        PointCfg {
+            tag: point.tag,
+            register,
+            address,
+            datatype: Some(point.datatype.name().to_string()),
+            len,
+            scale: point.scale,
+            unscale: point.unscale,
+            access: point.access,
+            scan: point.scan,
+        }
+    }
+}
+
+/// 点位扫描等级
+///
+/// 配置中写作`fast`、`normal`、`slow`或轮询间隔 (如`500ms`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum ScanClass {
+    /// 快速变化的过程值
+    Fast,
+    /// 按`polling`轮询
+    #[default]
+    Normal,
+    /// 慢变化的设定值
+    Slow,
+    /// 显式指定的轮询间隔
+    Interval(Duration),
+}
+
+impl ScanClass {
+    /// 指标标签
+    pub fn label(&self) -> String {
+        match self {
+            ScanClass::Fast => "fast".to_string(),
+            ScanClass::Normal => "normal".to_string(),
+            ScanClass::Slow => "slow".to_string(),
+            ScanClass::Interval(interval) => humantime_serde::re::humantime::format_duration(*interval).to_string(),
+        }
+    }
+}
+
+impl TryFrom<String> for ScanClass {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        match value.as_str() {
+            "fast" => Ok(ScanClass::Fast),
+            "normal" => Ok(ScanClass::Normal),
+            "slow" => Ok(ScanClass::Slow),
+            other => {
+                let interval = humantime_serde::re::humantime::parse_duration(other)
+                    .map_err(|e| anyhow::anyhow!("Invalid scan class '{}': {}", other, e))?;
+                if interval.is_zero() {
+                    return Err(anyhow::anyhow!("Scan interval must be greater than zero"));
+                }
+                Ok(ScanClass::Interval(interval))
+            }
+        }
+    }
+}
+
+impl From<ScanClass> for String {
+    fn from(class: ScanClass) -> Self {
+        class.label()
+    }
 }
 
 /// 数据类型
@@ -77,13 +371,100 @@ pub enum DataType {
     Int16,
     Uint32,
     Int32,
+    Uint64,
+    Int64,
     Float32,
     Float64,
+    /// ASCII字符串，占用点位`len`个寄存器 (每个寄存器2个字符)
+    String,
+    /// 4位压缩BCD (1个寄存器，0-9999)
+    Bcd16,
+    /// 8位压缩BCD (2个寄存器，0-99999999)
+    Bcd32,
+    /// 保持寄存器中的单个位 (0-15)，地址写作`addr.bit`
+    Bit(u8),
+}
+
+impl DataType {
+    /// 按配置名称解析 (位类型由地址给出，不在此列)
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "bool" => DataType::Bool,
+            "uint16" => DataType::Uint16,
+            "int16" => DataType::Int16,
+            "uint32" => DataType::Uint32,
+            "int32" => DataType::Int32,
+            "uint64" => DataType::Uint64,
+            "int64" => DataType::Int64,
+            "float32" => DataType::Float32,
+            "float64" => DataType::Float64,
+            "string" => DataType::String,
+            "bcd16" => DataType::Bcd16,
+            "bcd32" => DataType::Bcd32,
+            _ => return None,
+        })
+    }
+
+    /// 配置名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataType::Bool => "bool",
+            DataType::Uint16 => "uint16",
+            DataType::Int16 => "int16",
+            DataType::Uint32 => "uint32",
+            DataType::Int32 => "int32",
+            DataType::Uint64 => "uint64",
+            DataType::Int64 => "int64",
+            DataType::Float32 => "float32",
+            DataType::Float64 => "float64",
+            DataType::String => "string",
+            DataType::Bcd16 => "bcd16",
+            DataType::Bcd32 => "bcd32",
+            DataType::Bit(_) => "bit",
+        }
+    }
+
+    /// 定长类型占用的寄存器数，字符串按最小1个计
+    pub fn register_count(&self) -> u16 {
+        match self {
+            DataType::Uint32 | DataType::Int32 | DataType::Float32 | DataType::Bcd32 => 2,
+            DataType::Uint64 | DataType::Int64 | DataType::Float64 => 4,
+            _ => 1,
+        }
+    }
+}
+
+/// 解析点位地址
+///
+/// 支持`.bit`后缀指定寄存器内的位，例如`100.3`表示寄存器100的第3位 (0为最低位)
+pub fn parse_address(address: &str) -> anyhow::Result<(u16, Option<u8>)> {
+    let (reg, bit) = match address.trim().split_once('.') {
+        Some((reg, bit)) => (reg, Some(bit)),
+        None => (address.trim(), None),
+    };
+
+    let reg = reg.parse::<u16>()
+        .map_err(|e| anyhow::anyhow!("Invalid register address '{}': {}", address, e))?;
+    let bit = match bit {
+        Some(bit) => {
+            let bit = bit.parse::<u8>()
+                .map_err(|e| anyhow::anyhow!("Invalid bit in address '{}': {}", address, e))?;
+            if bit > 15 {
+                return Err(anyhow::anyhow!("Bit {} out of range 0-15 in address '{}'", bit, address));
+            }
+            Some(bit)
+        }
+        None => None,
+    };
+
+    Ok((reg, bit))
 }
 
 /// 访问权限
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Access {
+    #[default]
     R,  // 只读
     W,  // 只写
     RW, // 读写
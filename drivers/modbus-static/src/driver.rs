@@ -1,7 +1,8 @@
 //! Modbus-TCP驱动实现
 
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU16, Ordering};
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use async_trait::async_trait;
@@ -12,67 +13,237 @@ use tokio_modbus::prelude::{Reader, Writer};
 
 use driver_manager::{Driver, DriverMeta, DriverKind};
 use frame_bus::{DataFrame, CmdFrame, FrameSender};
-use endpoint_kit::EndpointHandle;
+use endpoint_kit::{EndpointHandle, EndpointBox, DatagramSocket, Scheme};
 
 use crate::config::{ModbusCfg, RegPoint, PollBatch, DataType, Access};
-use crate::codec::{decode_registers, encode_value, apply_scale};
+use crate::codec::{decode_registers, encode_point, set_bit, PointScale};
+use crate::mbap::{self, ModbusException};
+use crate::planner::BlockPlanner;
+use crate::scan::ScanScheduler;
 use crate::metrics::METRICS;
 
+/// endpoint-kit传输层包装 (tokio-modbus要求传输层实现Debug)
+struct EndpointTransport(EndpointBox);
+
+impl std::fmt::Debug for EndpointTransport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EndpointTransport")
+    }
+}
+
+impl tokio::io::AsyncRead for EndpointTransport {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.0.as_mut().poll_read(cx, buf)
+    }
+}
+
+impl tokio::io::AsyncWrite for EndpointTransport {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.0.as_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.0.as_mut().poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.0.as_mut().poll_shutdown(cx)
+    }
+}
+
+/// 按端点建立Modbus客户端
+///
+/// `serial://`使用RTU帧格式；`tls://`与`tls+tcp://`在endpoint-kit的TLS流上使用
+/// Modbus/TCP Security (默认端口802)，握手失败即返回错误，不会退回明文。
+pub async fn connect(endpoint: &EndpointHandle, unit_id: u8) -> anyhow::Result<tokio_modbus::client::Context> {
+    let slave = tokio_modbus::Slave(unit_id);
+
+    // 串口端点使用RTU帧格式 (CRC由RTU编解码器处理，帧间隔由endpoint-kit保证)
+    if *endpoint.scheme() == Scheme::Serial {
+        let transport = endpoint.open().await?;
+        return Ok(tokio_modbus::client::rtu::attach_slave(EndpointTransport(transport), slave));
+    }
+
+    if endpoint.url().scheme_stack.contains(&Scheme::Tls) {
+        let transport = endpoint.open().await?;
+        return Ok(tokio_modbus::client::tcp::attach_slave(EndpointTransport(transport), slave));
+    }
+
+    // 从endpoint中提取主机和端口
+    let host = endpoint.host();
+    let port = endpoint.port().unwrap_or(502); // Modbus TCP default port
+
+    // 直接创建TCP连接
+    let socket_addr: std::net::SocketAddr = format!("{}:{}", host, port).parse()?;
+    let ctx = tokio_modbus::client::tcp::connect_slave(socket_addr, slave).await?;
+    Ok(ctx)
+}
+
 /// Modbus驱动
 pub struct ModbusDriver {
     cfg: ModbusCfg,
     endpoint: Option<Arc<EndpointHandle>>,
     points: Vec<RegPoint>,
-    batches: Vec<PollBatch>,
+    /// 按扫描等级分组的轮询批次
+    scheduler: ScanScheduler,
+    /// 读请求规划器 (记录不可读地址)
+    planner: BlockPlanner,
     tag_map: HashMap<String, RegPoint>,
+    /// 在`init`时编译的缩放表达式
+    scales: HashMap<String, PointScale>,
+    /// 各点位最新工程值，供表达式中的`tag("...")`引用
+    last_values: HashMap<String, f64>,
+    /// 流式连接 (TCP/TLS/串口) 跨轮询复用，超时或传输错误后丢弃，下次请求重新打开
+    client: tokio::sync::Mutex<Option<tokio_modbus::client::Context>>,
+    /// Modbus/UDP数据报套接字 (跨请求复用，以便丢弃超时请求的迟到应答)
+    udp_socket: tokio::sync::Mutex<Option<DatagramSocket>>,
+    /// Modbus/UDP事务ID
+    transaction_id: AtomicU16,
 }
 
 impl ModbusDriver {
     pub fn new() -> Self {
+        let cfg = ModbusCfg::default();
         Self {
-            cfg: ModbusCfg::default(),
+            planner: BlockPlanner::new(cfg.max_regs_per_req, cfg.max_gap_regs),
+            cfg,
             endpoint: None,
             points: Vec::new(),
-            batches: Vec::new(),
+            scheduler: ScanScheduler::default(),
             tag_map: HashMap::new(),
+            scales: HashMap::new(),
+            last_values: HashMap::new(),
+            client: tokio::sync::Mutex::new(None),
+            udp_socket: tokio::sync::Mutex::new(None),
+            transaction_id: AtomicU16::new(0),
         }
     }
 
-    /// 创建Modbus客户端连接
-    async fn make_client(&self) -> anyhow::Result<tokio_modbus::client::Context> {
+    /// 端点是否为Modbus/UDP
+    fn is_udp(&self) -> bool {
+        self.endpoint.as_ref().is_some_and(|e| *e.scheme() == Scheme::Udp)
+    }
+
+    /// 发送Modbus/UDP请求并返回应答PDU
+    ///
+    /// 按事务ID关联应答；超时保留套接字，迟到的旧应答按事务ID丢弃；
+    /// 其他错误时丢弃套接字，下次请求重新建立
+    async fn udp_request(&self, function: u8, pdu: &[u8]) -> anyhow::Result<Vec<u8>> {
         let endpoint = self.endpoint.as_ref()
             .ok_or_else(|| anyhow::anyhow!("Endpoint not set"))?;
 
-        // 从endpoint中提取主机和端口
-        let host = endpoint.host();
-        let port = endpoint.port().unwrap_or(502); // Modbus TCP default port
+        let mut socket = self.udp_socket.lock().await;
+        if socket.is_none() {
+            *socket = Some(endpoint.open_datagram().await?);
+        }
 
-        // 直接创建TCP连接
-        let socket_addr: std::net::SocketAddr = format!("{}:{}", host, port).parse()?;
-        let ctx = tokio_modbus::client::tcp::connect_slave(socket_addr, tokio_modbus::Slave(self.cfg.unit_id)).await?;
-        Ok(ctx)
+        let txn = self.transaction_id.fetch_add(1, Ordering::Relaxed);
+        let adu = mbap::encode_adu(txn, self.cfg.unit_id, pdu);
+        let result = socket.as_mut().unwrap()
+            .request(&adu, self.cfg.timeout, |d| mbap::is_response_to(d, txn))
+            .await;
+
+        let datagram = match result {
+            Ok(datagram) => datagram,
+            Err(endpoint_kit::EndpointError::Timeout) => {
+                return Err(anyhow::anyhow!("Modbus request timed out after {:?}", self.cfg.timeout));
+            }
+            Err(e) => {
+                *socket = None;
+                return Err(e.into());
+            }
+        };
+
+        Ok(mbap::decode_response(&datagram, self.cfg.unit_id, function)?.to_vec())
+    }
+
+    /// 通过Modbus/UDP读取单个批次
+    async fn read_batch_udp(&self, batch: &PollBatch) -> anyhow::Result<Vec<u16>> {
+        let function = match batch.func {
+            tokio_modbus::FunctionCode::ReadHoldingRegisters => 0x03,
+            tokio_modbus::FunctionCode::ReadInputRegisters => 0x04,
+            _ => return Err(anyhow::anyhow!("Unsupported function code: {:?}", batch.func)),
+        };
+
+        let pdu = mbap::read_registers_pdu(function, batch.start, batch.qty);
+        let resp = self.udp_request(function, &pdu).await?;
+        mbap::parse_registers(&resp, batch.qty)
+    }
+
+    /// 取得复用的Modbus客户端连接，尚未连接时建立
+    async fn client(&self) -> anyhow::Result<tokio::sync::MutexGuard<'_, Option<tokio_modbus::client::Context>>> {
+        let mut client = self.client.lock().await;
+        if client.is_none() {
+            let endpoint = self.endpoint.as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Endpoint not set"))?;
+            *client = Some(connect(endpoint, self.cfg.unit_id).await?);
+        }
+        Ok(client)
     }
 
     /// 读取单个批次
     async fn read_batch(&self, batch: &PollBatch) -> anyhow::Result<Vec<u16>> {
-        let mut ctx = self.make_client().await?;
+        if self.is_udp() {
+            let start = Instant::now();
+            let regs = self.read_batch_udp(batch).await.inspect_err(|_| {
+                METRICS.exception_total.inc();
+            })?;
+            METRICS.pdu_total.inc();
+            METRICS.point_latency.observe(start.elapsed().as_millis() as f64);
+            return Ok(regs);
+        }
+
+        let function = match batch.func {
+            tokio_modbus::FunctionCode::ReadHoldingRegisters => 0x03,
+            tokio_modbus::FunctionCode::ReadInputRegisters => 0x04,
+            _ => return Err(anyhow::anyhow!("Unsupported function code: {:?}", batch.func)),
+        };
+
+        let mut client = self.client().await?;
+        let ctx = client.as_mut().expect("client connected");
         let start = Instant::now();
         
-        let result = match batch.func {
-            tokio_modbus::FunctionCode::ReadHoldingRegisters => {
-                ctx.read_holding_registers(batch.start, batch.qty).await
-            }
-            tokio_modbus::FunctionCode::ReadInputRegisters => {
-                ctx.read_input_registers(batch.start, batch.qty).await
+        let request = async {
+            match batch.func {
+                tokio_modbus::FunctionCode::ReadHoldingRegisters => {
+                    Ok(ctx.read_holding_registers(batch.start, batch.qty).await)
+                }
+                tokio_modbus::FunctionCode::ReadInputRegisters => {
+                    Ok(ctx.read_input_registers(batch.start, batch.qty).await)
+                }
+                _ => Err(anyhow::anyhow!("Unsupported function code: {:?}", batch.func)),
             }
-            _ => {
-                return Err(anyhow::anyhow!("Unsupported function code: {:?}", batch.func));
+        };
+
+        // 串行链路上从站无响应时不会断开连接，必须依靠超时结束请求；
+        // 超时后迟到的应答会与下一次请求错位，因此连同传输错误一并丢弃连接
+        let result = match tokio::time::timeout(self.cfg.timeout, request).await {
+            Ok(result) => result?,
+            Err(_) => {
+                *client = None;
+                METRICS.exception_total.inc();
+                return Err(anyhow::anyhow!("Modbus request timed out after {:?}", self.cfg.timeout));
             }
         };
 
         let regs = match result {
             Ok(regs) => regs,
             Err(e) => {
+                *client = None;
                 METRICS.exception_total.inc();
                 return Err(anyhow::anyhow!("Modbus read error: {:?}", e));
             }
@@ -81,12 +252,78 @@ impl ModbusDriver {
         METRICS.pdu_total.inc();
         let latency = start.elapsed().as_millis() as f64;
         METRICS.point_latency.observe(latency);
-        Ok(regs?)
+        regs.map_err(|e| ModbusException { function, code: e.into() }.into())
+    }
+
+    /// 读取单个保持寄存器 (用于位点位的读-改-写)
+    async fn read_holding_register(&self, addr: u16) -> anyhow::Result<u16> {
+        let batch = PollBatch {
+            func: tokio_modbus::FunctionCode::ReadHoldingRegisters,
+            start: addr,
+            qty: 1,
+            points: Vec::new(),
+        };
+        self.read_batch(&batch).await?
+            .first()
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("Empty response reading register {}", addr))
+    }
+
+    /// 读取并发布单个批次，返回因非法地址异常拆分出的子批次
+    async fn poll_batch(&mut self, batch: &PollBatch, tx: &FrameSender) -> Vec<PollBatch> {
+        let mut retry_count = 0;
+
+        loop {
+            match self.read_batch(batch).await {
+                Ok(regs) => {
+                    self.planner.record_success(batch);
+                    if let Err(e) = self.decode_and_publish(regs, batch, tx).await {
+                        tracing::error!("Failed to publish batch: {}", e);
+                    }
+                    return Vec::new();
+                }
+                Err(e) if is_illegal_address(&e) => {
+                    // 非法地址异常重试无意义，拆分批次定位不可读地址
+                    tracing::debug!("Batch {:?}@{}+{} rejected: {}, splitting", batch.func, batch.start, batch.qty, e);
+                    METRICS.exception_total.inc();
+                    let halves = self.planner.split(batch);
+                    if halves.is_empty() {
+                        self.publish_bad_quality(&batch.points, &e.to_string());
+                    }
+                    return halves;
+                }
+                Err(e) => {
+                    retry_count += 1;
+                    if retry_count <= self.cfg.retry {
+                        tracing::warn!("Batch read failed (attempt {}): {}", retry_count, e);
+                        sleep(Duration::from_millis(100)).await;
+                    } else {
+                        tracing::error!("Batch read failed after {} retries: {}", retry_count, e);
+                        METRICS.exception_total.inc();
+                        self.publish_bad_quality(&batch.points, &e.to_string());
+                        return Vec::new();
+                    }
+                }
+            }
+        }
+    }
+
+    /// 为未能读取的点位发布坏质量帧，点位不会因读取失败而从北向消失
+    fn publish_bad_quality(&self, points: &[RegPoint], error: &str) {
+        if points.is_empty() {
+            return;
+        }
+        let frames = points.iter().map(|point| bad_quality_frame(point, error)).collect();
+        let published = frame_bus::batched_publisher(&self.cfg.instance_id)
+            .and_then(|publisher| publisher.send_data_batch(frames));
+        if let Err(e) = published {
+            tracing::error!("Failed to publish bad-quality frames: {}", e);
+        }
     }
 
     /// 解码并发布帧（批量优化版本）
     async fn decode_and_publish(
-        &self,
+        &mut self,
         regs: Vec<u16>,
         batch: &PollBatch,
         _tx: &FrameSender,
@@ -95,11 +332,18 @@ impl ModbusDriver {
         
         // 批量解码所有点位
         for point in &batch.points {
-            let frame = match decode_registers(&regs, point, batch.start, &self.cfg.endian) {
-                Ok(value) => {
-                    // 应用缩放
-                    let scaled_value = apply_scale(value, point.scale.as_deref())?;
-                    
+            let scaled = decode_registers(&regs, point, batch.start, &self.cfg.endian)
+                .and_then(|value| match self.scales.get(&point.tag) {
+                    Some(scale) => scale.scale(value, &self.last_values),
+                    None => Ok(value),
+                });
+
+            let frame = match scaled {
+                Ok(scaled_value) => {
+                    if let Some(v) = scaled_value.to_f64() {
+                        self.last_values.insert(point.tag.clone(), v);
+                    }
+
                     // 创建DataFrame
                     DataFrame::new(&point.tag, scaled_value)
                         .with_qos(2) // Good quality
@@ -109,12 +353,7 @@ impl ModbusDriver {
                 }
                 Err(e) => {
                     tracing::warn!("Failed to decode point {}: {}", point.tag, e);
-                    
-                    // 创建错误质量的帧
-                    DataFrame::new(&point.tag, frame_bus::Value::int(0))
-                        .with_qos(0) // Bad quality
-                        .with_meta("error", e.to_string())
-                        .with_meta("driver", "modbus-tcp")
+                    bad_quality_frame(point, &e.to_string())
                 }
             };
             
@@ -123,7 +362,7 @@ impl ModbusDriver {
 
         // 批量发布所有帧（使用高性能批量API）
         if !frames.is_empty() {
-            frame_bus::publish_data_batch(frames)?;
+            frame_bus::batched_publisher(&self.cfg.instance_id)?.send_data_batch(frames)?;
             METRICS.point_total.inc_by(batch.points.len() as f64);
             
             // 记录批量发布指标
@@ -133,81 +372,20 @@ impl ModbusDriver {
         
         Ok(())
     }
+}
 
-    /// 分组点位为批次
-    fn group_points_to_batches(&self) -> Vec<PollBatch> {
-        let mut batches = Vec::new();
-        
-        // 按功能码分组 - 使用u8作为key避免Hash问题
-        let mut grouped: HashMap<u8, Vec<&RegPoint>> = HashMap::new();
-
-        // 按功能码分组
-        for point in &self.points {
-            let func_code = match point.func {
-                tokio_modbus::FunctionCode::ReadHoldingRegisters => 3,
-                tokio_modbus::FunctionCode::ReadInputRegisters => 4,
-                _ => continue, // 跳过不支持的功能码
-            };
-            grouped.entry(func_code).or_default().push(point);
-        }
-
-        for (func_code, mut points) in grouped {
-            // 按地址排序
-            points.sort_by_key(|p| p.addr);
-
-            // 将u8转换回FunctionCode
-            let func = match func_code {
-                3 => tokio_modbus::FunctionCode::ReadHoldingRegisters,
-                4 => tokio_modbus::FunctionCode::ReadInputRegisters,
-                _ => continue,
-            };
-
-            let mut current_batch = Vec::new();
-            let mut current_start = 0u16;
-            let mut current_end = 0u16;
-
-            for point in points {
-                let point_start = point.addr;
-                let point_end = point.addr + point.len - 1;
-
-                if current_batch.is_empty() {
-                    // 第一个点
-                    current_batch.push(point.clone());
-                    current_start = point_start;
-                    current_end = point_end;
-                } else if point_start <= current_end + 1 && 
-                         point_end - current_start + 1 <= self.cfg.max_regs_per_req {
-                    // 可以合并到当前批次
-                    current_batch.push(point.clone());
-                    current_end = current_end.max(point_end);
-                } else {
-                    // 需要新的批次
-                    batches.push(PollBatch {
-                        func,
-                        start: current_start,
-                        qty: current_end - current_start + 1,
-                        points: current_batch,
-                    });
-
-                    current_batch = vec![point.clone()];
-                    current_start = point_start;
-                    current_end = point_end;
-                }
-            }
-
-            // 添加最后的批次
-            if !current_batch.is_empty() {
-                batches.push(PollBatch {
-                    func,
-                    start: current_start,
-                    qty: current_end - current_start + 1,
-                    points: current_batch,
-                });
-            }
-        }
+/// 读取失败点位的坏质量帧
+fn bad_quality_frame(point: &RegPoint, error: &str) -> DataFrame {
+    DataFrame::new(&point.tag, frame_bus::Value::int(0))
+        .with_qos(0) // Bad quality
+        .with_meta("error", error)
+        .with_meta("driver", "modbus-tcp")
+}
 
-        batches
-    }
+/// 是否为非法数据地址异常 (0x02)
+fn is_illegal_address(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<ModbusException>()
+        .is_some_and(|e| e.is_illegal_address())
 }
 
 #[async_trait]
@@ -218,99 +396,62 @@ impl Driver for ModbusDriver {
             kind: DriverKind::Static,
             version: "0.1.0".to_string(),
             api_version: 1,
-            description: "Static Modbus-TCP driver".to_string(),
-            features: vec!["read".to_string()],
+            description: "Static Modbus-TCP/RTU/UDP driver".to_string(),
+            features: vec!["read".to_string(), "rtu".to_string(), "udp".to_string()],
         }
     }
 
     async fn init(&mut self, cfg: &Value) -> anyhow::Result<()> {
         self.cfg = serde_json::from_value(cfg.clone())?;
         
-        // 配置40001-40011的6个浮点型点位（实际地址0-10）
-        self.points = vec![
-            // 注意：实际测试中，我们的Mock服务器只返回16位整数值
-            // 所以我们将使用Uint16类型并应用缩放，而不是Float32
-            RegPoint {
-                tag: "sensor.temp1".to_string(),
-                func: tokio_modbus::FunctionCode::ReadHoldingRegisters,
-                addr: 0,  // 40001对应地址0
-                len: 1,
-                datatype: DataType::Uint16,  // 使用16位整数
-                scale: Some("value / 10.0".to_string()),  // 温度传感器1: 值/10.0
-                access: Access::R,
-            },
-            // 采集保持寄存器40001的Float32(CDAB字序时需全局Little)
-            RegPoint {
-                tag: "sensor.float40001".to_string(),
-                func: tokio_modbus::FunctionCode::ReadHoldingRegisters,
-                addr: 0,  // 40001对应地址0
-                len: 2,   // Float32占用两个寄存器
-                datatype: DataType::Float32,
-                scale: None,
-                access: Access::R,
-            },
-            RegPoint {
-                tag: "sensor.pressure1".to_string(),
-                func: tokio_modbus::FunctionCode::ReadHoldingRegisters,
-                addr: 1,  // 40002对应地址1
-                len: 1,
-                datatype: DataType::Uint16,
-                scale: Some("value / 100.0".to_string()),  // 压力传感器1: 值/100.0
-                access: Access::R,
-            },
-            RegPoint {
-                tag: "sensor.flow1".to_string(),
-                func: tokio_modbus::FunctionCode::ReadHoldingRegisters,
-                addr: 2,  // 40003对应地址2
-                len: 1,
-                datatype: DataType::Uint16,
-                scale: Some("value / 10.0".to_string()),   // 流量传感器1: 值/10.0
-                access: Access::R,
-            },
-            RegPoint {
-                tag: "sensor.temp2".to_string(),
-                func: tokio_modbus::FunctionCode::ReadHoldingRegisters,
-                addr: 3,  // 40004对应地址3
-                len: 1,
-                datatype: DataType::Uint16,
-                scale: Some("value / 10.0".to_string()),   // 温度传感器2: 值/10.0
-                access: Access::R,
-            },
-            RegPoint {
-                tag: "sensor.pressure2".to_string(),
-                func: tokio_modbus::FunctionCode::ReadHoldingRegisters,
-                addr: 4,  // 40005对应地址4
-                len: 1,
-                datatype: DataType::Uint16,
-                scale: Some("value / 100.0".to_string()),  // 压力传感器2: 值/100.0
-                access: Access::R,
-            },
-            RegPoint {
-                tag: "sensor.flow2".to_string(),
-                func: tokio_modbus::FunctionCode::ReadHoldingRegisters,
-                addr: 5,  // 40006对应地址5
-                len: 1,
-                datatype: DataType::Uint16,
-                scale: Some("value / 10.0".to_string()),   // 流量传感器2: 值/10.0
-                access: Access::R,
-            },
-        ];
+        // 点位表来自驱动配置，逐项校验已在反序列化时完成
+        self.points = self.cfg.points.clone();
 
         // 构建tag映射
+        self.tag_map.clear();
         for point in &self.points {
-            self.tag_map.insert(point.tag.clone(), point.clone());
+            if self.tag_map.insert(point.tag.clone(), point.clone()).is_some() {
+                return Err(anyhow::anyhow!("Duplicate point tag '{}'", point.tag));
+            }
+        }
+
+        // 编译缩放表达式，配置错误在初始化阶段暴露
+        self.scales.clear();
+        self.last_values.clear();
+        for point in &self.points {
+            if point.scale.is_none() && point.unscale.is_none() {
+                continue;
+            }
+            let scale = PointScale::compile(point.scale.as_deref(), point.unscale.as_deref())
+                .map_err(|e| anyhow::anyhow!("Invalid scale expression for tag '{}': {}", point.tag, e))?;
+            if let Some(unknown) = scale.tag_refs().into_iter().find(|t| !self.tag_map.contains_key(t)) {
+                return Err(anyhow::anyhow!("Scale expression for tag '{}' references unknown tag '{}'", point.tag, unknown));
+            }
+            self.scales.insert(point.tag.clone(), scale);
+        }
+
+        // 单个点位必须能在一次请求内读完，否则规划出的批次会被从站拒绝
+        if let Some(point) = self.points.iter().find(|p| p.len > self.cfg.max_regs_per_req) {
+            return Err(anyhow::anyhow!(
+                "Point '{}': {} registers exceed max_regs_per_req {}",
+                point.tag, point.len, self.cfg.max_regs_per_req
+            ));
         }
 
         // 生成批次
-        self.batches = self.group_points_to_batches();
+        self.planner = BlockPlanner::new(self.cfg.max_regs_per_req, self.cfg.max_gap_regs);
+        self.scheduler = ScanScheduler::new(&self.cfg, &mut self.planner, &self.points, Instant::now());
 
-        tracing::info!("Modbus driver initialized with {} points in {} batches", 
-                      self.points.len(), self.batches.len());
+        let batch_count: usize = self.scheduler.groups().iter().map(|g| g.batches.len()).sum();
+        tracing::info!("Modbus driver initialized with {} points in {} batches ({} scan classes)", 
+                      self.points.len(), batch_count, self.scheduler.groups().len());
         Ok(())
     }
 
     async fn connect(&mut self, endpoint: std::sync::Arc<EndpointHandle>) -> anyhow::Result<()> {
         self.endpoint = Some(endpoint);
+        *self.client.get_mut() = None;
+        *self.udp_socket.get_mut() = None;
         tracing::info!("Modbus driver connected to endpoint");
         Ok(())
     }
@@ -319,38 +460,39 @@ impl Driver for ModbusDriver {
         tracing::info!("Starting Modbus read loop");
         
         loop {
-            let cycle_start = Instant::now();
-            
-            for batch in &self.batches {
-                let mut retry_count = 0;
-                
-                loop {
-                    match self.read_batch(batch).await {
-                        Ok(regs) => {
-                            if let Err(e) = self.decode_and_publish(regs, batch, &tx).await {
-                                tracing::error!("Failed to publish batch: {}", e);
-                            }
-                            break;
-                        }
-                        Err(e) => {
-                            retry_count += 1;
-                            if retry_count <= self.cfg.retry {
-                                tracing::warn!("Batch read failed (attempt {}): {}", retry_count, e);
-                                sleep(Duration::from_millis(100)).await;
-                            } else {
-                                tracing::error!("Batch read failed after {} retries: {}", retry_count, e);
-                                METRICS.exception_total.inc();
-                                break;
-                            }
-                        }
-                    }
-                }
+            let Some((index, due)) = self.scheduler.next_due() else {
+                // 没有可轮询的点位
+                sleep(self.cfg.polling).await;
+                continue;
+            };
+            tokio::time::sleep_until(due.into()).await;
+
+            let skipped_before = self.planner.skipped_count();
+            let batches = std::mem::take(&mut self.scheduler.group_mut(index).batches);
+            let mut pending: VecDeque<PollBatch> = VecDeque::new();
+
+            for batch in &batches {
+                pending.extend(self.poll_batch(batch, &tx).await);
+            }
+
+            // 拆分出的子批次在本次扫描内继续读取
+            while let Some(batch) = pending.pop_front() {
+                pending.extend(self.poll_batch(&batch, &tx).await);
+            }
+            self.publish_bad_quality(&self.scheduler.groups()[index].skipped, "address not readable");
+
+            self.scheduler.group_mut(index).batches = batches;
+            if self.scheduler.complete(index, Instant::now()) {
+                let group = &self.scheduler.groups()[index];
+                let class = group.class.label();
+                tracing::warn!("Modbus scan class {} overran its {:?} interval", class, group.interval);
+                METRICS.scan_overrun_total.with_label_values(&[&class]).inc();
             }
 
-            // 等待下一个轮询周期
-            let cycle_duration = cycle_start.elapsed();
-            if cycle_duration < self.cfg.polling {
-                sleep(self.cfg.polling - cycle_duration).await;
+            // 发现新的不可读地址后重新规划，避免下次扫描再次拆分
+            if self.planner.skipped_count() != skipped_before {
+                self.scheduler.replan(&self.cfg, &mut self.planner, &self.points, Instant::now());
+                tracing::info!("Modbus poll plan rebuilt, {} addresses skipped", self.planner.skipped_count());
             }
         }
     }
@@ -361,29 +503,80 @@ impl Driver for ModbusDriver {
         }
 
         let point = self.tag_map.get(&cmd.tag)
-            .ok_or_else(|| anyhow::anyhow!("Tag '{}' not found", cmd.tag))?;
+            .ok_or_else(|| anyhow::anyhow!("Tag '{}' not found", cmd.tag))?
+            .clone();
 
         if !matches!(point.access, Access::W | Access::RW) {
             return Err(anyhow::anyhow!("Tag '{}' is not writable", cmd.tag));
         }
 
         let value = cmd.value.ok_or_else(|| anyhow::anyhow!("No value in command"))?;
-        let regs = encode_value(&value, &point.datatype, &self.cfg.endian)?;
+        // 工程值反算为原始值
+        let value = match self.scales.get(&point.tag) {
+            Some(scale) => scale.unscale(value, &point.datatype, &self.last_values)?,
+            None => value,
+        };
+        let regs = match point.datatype {
+            DataType::Bit(bit) => {
+                // 位点位：读取寄存器当前值，只修改目标位后写回
+                let on = value.to_bool()
+                    .ok_or_else(|| anyhow::anyhow!("Invalid bit value: {:?}", value))?;
+                let current = self.read_holding_register(point.addr).await?;
+                vec![set_bit(current, bit, on)?]
+            }
+            _ => encode_point(&value, &point, &self.cfg.endian)?,
+        };
+
+        if self.is_udp() {
+            if regs.len() == 1 {
+                self.udp_request(0x06, &mbap::write_single_register_pdu(point.addr, regs[0])).await?;
+            } else {
+                self.udp_request(0x10, &mbap::write_multiple_registers_pdu(point.addr, &regs)).await?;
+            }
+            tracing::info!("Wrote value to tag {}: {:?}", cmd.tag, value);
+            return Ok(());
+        }
 
-        let mut ctx = self.make_client().await?;
+        let mut client = self.client().await?;
+        let ctx = client.as_mut().expect("client connected");
         
-        if regs.len() == 1 {
-            let _ = ctx.write_single_register(point.addr, regs[0]).await?;
+        // 从站异常应答须作为写入失败返回，否则命令会被确认为成功
+        let (function, result) = if regs.len() == 1 {
+            (0x06, ctx.write_single_register(point.addr, regs[0]).await)
         } else {
-            let _ = ctx.write_multiple_registers(point.addr, &regs).await?;
-        }
+            (0x10, ctx.write_multiple_registers(point.addr, &regs).await)
+        };
+        let result = result.inspect_err(|_| *client = None)?;
+        result.map_err(|e| ModbusException { function, code: e.into() })?;
 
         tracing::info!("Wrote value to tag {}: {:?}", cmd.tag, value);
         Ok(())
     }
 
+    async fn read_tag(&mut self, tag: &str) -> anyhow::Result<Option<frame_bus::Value>> {
+        let point = self.tag_map.get(tag)
+            .ok_or_else(|| anyhow::anyhow!("Tag '{}' not found", tag))?
+            .clone();
+
+        let batch = PollBatch {
+            func: point.func,
+            start: point.addr,
+            qty: point.len,
+            points: vec![point.clone()],
+        };
+        let regs = self.read_batch(&batch).await?;
+        let value = decode_registers(&regs, &point, batch.start, &self.cfg.endian)?;
+        let value = match self.scales.get(&point.tag) {
+            Some(scale) => scale.scale(value, &self.last_values)?,
+            None => value,
+        };
+        Ok(Some(value))
+    }
+
     async fn shutdown(&mut self) -> anyhow::Result<()> {
         tracing::info!("Modbus driver shutting down");
+        *self.client.get_mut() = None;
+        *self.udp_socket.get_mut() = None;
         Ok(())
     }
 }
\ No newline at end of file
@@ -0,0 +1,575 @@
+//! 点位缩放表达式
+//!
+//! 沙箱化的表达式语言，在`init`时编译为语法树，轮询时只做求值：
+//! - 变量`value` (原始值或写入时的工程值)，常量`pi`、`e`、`true`、`false`
+//! - 运算符`+ - * / % ^`、比较`< <= > >= == !=`、逻辑`&& || !`、条件`cond ? a : b`
+//! - 数学函数`abs sqrt exp ln log10 sin cos tan floor ceil round min max pow clamp`
+//! - 插值`linear(x, in_lo, in_hi, out_lo, out_hi)`与分段线性表`interp(x, x0, y0, x1, y1, ...)`
+//! - 其他点位的最新值`tag("sensor.temp1")`
+//!
+//! 表达式不能访问除上述以外的任何状态，嵌套深度与长度受限。
+
+use anyhow::Result;
+
+/// 表达式最大长度
+const MAX_EXPR_LEN: usize = 4096;
+/// 最大嵌套深度
+const MAX_DEPTH: usize = 64;
+
+/// 其他点位最新值的查询接口
+pub trait TagValues {
+    fn tag_value(&self, tag: &str) -> Option<f64>;
+}
+
+impl TagValues for std::collections::HashMap<String, f64> {
+    fn tag_value(&self, tag: &str) -> Option<f64> {
+        self.get(tag).copied()
+    }
+}
+
+/// 不提供任何点位值
+pub struct NoTags;
+
+impl TagValues for NoTags {
+    fn tag_value(&self, _tag: &str) -> Option<f64> {
+        None
+    }
+}
+
+/// 已编译的表达式
+#[derive(Debug, Clone)]
+pub struct Expr {
+    source: String,
+    root: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Num(f64),
+    Value,
+    Tag(String),
+    Neg(Box<Node>),
+    Not(Box<Node>),
+    Binary(BinOp, Box<Node>, Box<Node>),
+    Cond(Box<Node>, Box<Node>, Box<Node>),
+    Call(Func, Vec<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BinOp {
+    Add, Sub, Mul, Div, Rem, Pow,
+    Lt, Le, Gt, Ge, Eq, Ne,
+    And, Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Func {
+    Abs, Sqrt, Exp, Ln, Log10, Sin, Cos, Tan, Floor, Ceil, Round,
+    Min, Max, Pow, Clamp, Linear, Interp,
+}
+
+impl Func {
+    fn lookup(name: &str) -> Option<Self> {
+        Some(match name {
+            "abs" => Func::Abs,
+            "sqrt" => Func::Sqrt,
+            "exp" => Func::Exp,
+            "ln" => Func::Ln,
+            "log10" => Func::Log10,
+            "sin" => Func::Sin,
+            "cos" => Func::Cos,
+            "tan" => Func::Tan,
+            "floor" => Func::Floor,
+            "ceil" => Func::Ceil,
+            "round" => Func::Round,
+            "min" => Func::Min,
+            "max" => Func::Max,
+            "pow" => Func::Pow,
+            "clamp" => Func::Clamp,
+            "linear" => Func::Linear,
+            "interp" => Func::Interp,
+            _ => return None,
+        })
+    }
+
+    /// 检查参数个数
+    fn check_arity(&self, name: &str, argc: usize) -> Result<()> {
+        let ok = match self {
+            Func::Min | Func::Max => argc >= 2,
+            Func::Pow => argc == 2,
+            Func::Clamp => argc == 3,
+            Func::Linear => argc == 5,
+            // x加上至少两个(x, y)点
+            Func::Interp => argc >= 5 && argc % 2 == 1,
+            _ => argc == 1,
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Wrong number of arguments ({}) for {}()", argc, name))
+        }
+    }
+}
+
+impl Expr {
+    /// 编译表达式
+    pub fn compile(source: &str) -> Result<Self> {
+        if source.len() > MAX_EXPR_LEN {
+            return Err(anyhow::anyhow!("Expression longer than {} characters", MAX_EXPR_LEN));
+        }
+
+        let tokens = tokenize(source)?;
+        let mut parser = Parser { tokens, pos: 0, depth: 0 };
+        let root = parser.expr()?;
+        if let Some(token) = parser.peek() {
+            return Err(anyhow::anyhow!("Unexpected {:?} in expression '{}'", token, source));
+        }
+
+        Ok(Self { source: source.to_string(), root })
+    }
+
+    /// 表达式原文
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 表达式引用的点位
+    pub fn tag_refs(&self) -> Vec<String> {
+        let mut tags = Vec::new();
+        collect_tags(&self.root, &mut tags);
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    /// 求值
+    pub fn eval(&self, value: f64, tags: &dyn TagValues) -> Result<f64> {
+        let result = eval(&self.root, value, tags)?;
+        if !result.is_finite() {
+            return Err(anyhow::anyhow!("Expression '{}' produced non-finite result", self.source));
+        }
+        Ok(result)
+    }
+
+    /// 若表达式对`value`是仿射的 (`a * value + b`)，返回其反函数的系数
+    ///
+    /// 用于未配置反向表达式的写入，例如`value / 10.0`可自动反算为`value * 10.0`
+    pub fn affine_inverse(&self) -> Option<(f64, f64)> {
+        if !self.tag_refs().is_empty() {
+            return None;
+        }
+
+        let samples = [0.0, 1.0, 1000.0, -1000.0];
+        let outputs: Vec<f64> = samples.iter()
+            .map(|x| self.eval(*x, &NoTags).ok())
+            .collect::<Option<_>>()?;
+
+        let b = outputs[0];
+        let a = outputs[1] - b;
+        if a == 0.0 || !a.is_finite() {
+            return None;
+        }
+
+        let affine = samples.iter().zip(&outputs)
+            .all(|(x, y)| (a * x + b - y).abs() <= 1e-9 * y.abs().max(1.0));
+        affine.then(|| (1.0 / a, -b / a))
+    }
+}
+
+fn collect_tags(node: &Node, tags: &mut Vec<String>) {
+    match node {
+        Node::Tag(tag) => tags.push(tag.clone()),
+        Node::Neg(inner) | Node::Not(inner) => collect_tags(inner, tags),
+        Node::Binary(_, lhs, rhs) => {
+            collect_tags(lhs, tags);
+            collect_tags(rhs, tags);
+        }
+        Node::Cond(cond, then, other) => {
+            collect_tags(cond, tags);
+            collect_tags(then, tags);
+            collect_tags(other, tags);
+        }
+        Node::Call(_, args) => args.iter().for_each(|arg| collect_tags(arg, tags)),
+        Node::Num(_) | Node::Value => {}
+    }
+}
+
+fn truthy(v: f64) -> bool {
+    v != 0.0
+}
+
+fn bool_num(b: bool) -> f64 {
+    if b { 1.0 } else { 0.0 }
+}
+
+fn eval(node: &Node, value: f64, tags: &dyn TagValues) -> Result<f64> {
+    Ok(match node {
+        Node::Num(n) => *n,
+        Node::Value => value,
+        Node::Tag(tag) => tags.tag_value(tag)
+            .ok_or_else(|| anyhow::anyhow!("No value available for tag '{}'", tag))?,
+        Node::Neg(inner) => -eval(inner, value, tags)?,
+        Node::Not(inner) => bool_num(!truthy(eval(inner, value, tags)?)),
+        Node::Cond(cond, then, other) => {
+            if truthy(eval(cond, value, tags)?) {
+                eval(then, value, tags)?
+            } else {
+                eval(other, value, tags)?
+            }
+        }
+        Node::Binary(BinOp::And, lhs, rhs) => {
+            bool_num(truthy(eval(lhs, value, tags)?) && truthy(eval(rhs, value, tags)?))
+        }
+        Node::Binary(BinOp::Or, lhs, rhs) => {
+            bool_num(truthy(eval(lhs, value, tags)?) || truthy(eval(rhs, value, tags)?))
+        }
+        Node::Binary(op, lhs, rhs) => {
+            let l = eval(lhs, value, tags)?;
+            let r = eval(rhs, value, tags)?;
+            match op {
+                BinOp::Add => l + r,
+                BinOp::Sub => l - r,
+                BinOp::Mul => l * r,
+                BinOp::Div => {
+                    if r == 0.0 {
+                        return Err(anyhow::anyhow!("Division by zero in scale expression"));
+                    }
+                    l / r
+                }
+                BinOp::Rem => {
+                    if r == 0.0 {
+                        return Err(anyhow::anyhow!("Modulo by zero in scale expression"));
+                    }
+                    l % r
+                }
+                BinOp::Pow => l.powf(r),
+                BinOp::Lt => bool_num(l < r),
+                BinOp::Le => bool_num(l <= r),
+                BinOp::Gt => bool_num(l > r),
+                BinOp::Ge => bool_num(l >= r),
+                BinOp::Eq => bool_num(l == r),
+                BinOp::Ne => bool_num(l != r),
+                BinOp::And | BinOp::Or => unreachable!(),
+            }
+        }
+        Node::Call(func, args) => {
+            let args = args.iter()
+                .map(|arg| eval(arg, value, tags))
+                .collect::<Result<Vec<f64>>>()?;
+            call(*func, &args)?
+        }
+    })
+}
+
+fn call(func: Func, args: &[f64]) -> Result<f64> {
+    Ok(match func {
+        Func::Abs => args[0].abs(),
+        Func::Sqrt => args[0].sqrt(),
+        Func::Exp => args[0].exp(),
+        Func::Ln => args[0].ln(),
+        Func::Log10 => args[0].log10(),
+        Func::Sin => args[0].sin(),
+        Func::Cos => args[0].cos(),
+        Func::Tan => args[0].tan(),
+        Func::Floor => args[0].floor(),
+        Func::Ceil => args[0].ceil(),
+        Func::Round => args[0].round(),
+        Func::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+        Func::Max => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        Func::Pow => args[0].powf(args[1]),
+        Func::Clamp => {
+            if args[1] > args[2] {
+                return Err(anyhow::anyhow!("clamp() lower bound {} above upper bound {}", args[1], args[2]));
+            }
+            args[0].clamp(args[1], args[2])
+        }
+        Func::Linear => {
+            let (x, in_lo, in_hi, out_lo, out_hi) = (args[0], args[1], args[2], args[3], args[4]);
+            if in_hi == in_lo {
+                return Err(anyhow::anyhow!("linear() input range is empty"));
+            }
+            out_lo + (x - in_lo) * (out_hi - out_lo) / (in_hi - in_lo)
+        }
+        Func::Interp => interpolate(args[0], &args[1..])?,
+    })
+}
+
+/// 分段线性插值，超出表范围时取端点值
+fn interpolate(x: f64, table: &[f64]) -> Result<f64> {
+    let points: Vec<(f64, f64)> = table.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+    if points.windows(2).any(|w| w[1].0 <= w[0].0) {
+        return Err(anyhow::anyhow!("interp() table x values must be strictly increasing"));
+    }
+
+    let (first, last) = (points[0], points[points.len() - 1]);
+    if x <= first.0 {
+        return Ok(first.1);
+    }
+    if x >= last.0 {
+        return Ok(last.1);
+    }
+
+    let segment = points.windows(2)
+        .find(|w| x <= w[1].0)
+        .expect("x lies within table range");
+    let ((x0, y0), (x1, y1)) = (segment[0], segment[1]);
+    Ok(y0 + (x - x0) * (y1 - y0) / (x1 - x0))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Str(String),
+    Op(&'static str),
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>> {
+    const OPERATORS: [&str; 21] = [
+        "<=", ">=", "==", "!=", "&&", "||",
+        "+", "-", "*", "/", "%", "^", "(", ")", ",", "?", ":", "<", ">", "!", "=",
+    ];
+
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            // 科学计数法
+            if i < chars.len() && (chars[i] == 'e' || chars[i] == 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && (chars[j] == '+' || chars[j] == '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let num = text.parse::<f64>()
+                .map_err(|_| anyhow::anyhow!("Invalid number '{}'", text))?;
+            tokens.push(Token::Num(num));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..].iter().position(|ch| *ch == c)
+                .ok_or_else(|| anyhow::anyhow!("Unterminated string in expression '{}'", source))?;
+            tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS.iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| anyhow::anyhow!("Unexpected character '{}' in expression '{}'", c, source))?;
+            if *op == "=" {
+                return Err(anyhow::anyhow!("Assignment is not supported, use '==' in expression '{}'", source));
+            }
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// 递归下降解析器
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Op(o)) if *o == op) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, op: &str) -> Result<()> {
+        if self.eat(op) {
+            Ok(())
+        } else {
+            Err(anyhow::anyhow!("Expected '{}' but found {:?}", op, self.peek()))
+        }
+    }
+
+    fn enter(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(anyhow::anyhow!("Expression nested deeper than {}", MAX_DEPTH));
+        }
+        Ok(())
+    }
+
+    /// expr := or ('?' expr ':' expr)?
+    fn expr(&mut self) -> Result<Node> {
+        self.enter()?;
+        let cond = self.binary(0)?;
+        let node = if self.eat("?") {
+            let then = self.expr()?;
+            self.expect(":")?;
+            let other = self.expr()?;
+            Node::Cond(Box::new(cond), Box::new(then), Box::new(other))
+        } else {
+            cond
+        };
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    /// 按优先级解析左结合二元运算
+    fn binary(&mut self, level: usize) -> Result<Node> {
+        const LEVELS: [&[(&str, BinOp)]; 5] = [
+            &[("||", BinOp::Or)],
+            &[("&&", BinOp::And)],
+            &[("==", BinOp::Eq), ("!=", BinOp::Ne)],
+            &[("<=", BinOp::Le), (">=", BinOp::Ge), ("<", BinOp::Lt), (">", BinOp::Gt)],
+            &[("+", BinOp::Add), ("-", BinOp::Sub)],
+        ];
+
+        if level == LEVELS.len() {
+            return self.term();
+        }
+
+        let mut lhs = self.binary(level + 1)?;
+        'outer: loop {
+            for (op, bin) in LEVELS[level] {
+                if self.eat(op) {
+                    let rhs = self.binary(level + 1)?;
+                    lhs = Node::Binary(*bin, Box::new(lhs), Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    /// term := unary (('*' | '/' | '%') unary)*
+    fn term(&mut self) -> Result<Node> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat("*") {
+                BinOp::Mul
+            } else if self.eat("/") {
+                BinOp::Div
+            } else if self.eat("%") {
+                BinOp::Rem
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.unary()?;
+            lhs = Node::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+    }
+
+    /// unary := ('-' | '!') unary | power
+    fn unary(&mut self) -> Result<Node> {
+        if self.eat("-") {
+            self.enter()?;
+            let node = Node::Neg(Box::new(self.unary()?));
+            self.depth -= 1;
+            Ok(node)
+        } else if self.eat("!") {
+            self.enter()?;
+            let node = Node::Not(Box::new(self.unary()?));
+            self.depth -= 1;
+            Ok(node)
+        } else {
+            self.power()
+        }
+    }
+
+    /// power := primary ('^' unary)?  (右结合)
+    fn power(&mut self) -> Result<Node> {
+        let base = self.primary()?;
+        if self.eat("^") {
+            self.enter()?;
+            let exp = self.unary()?;
+            self.depth -= 1;
+            return Ok(Node::Binary(BinOp::Pow, Box::new(base), Box::new(exp)));
+        }
+        Ok(base)
+    }
+
+    fn primary(&mut self) -> Result<Node> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Node::Num(n)),
+            Some(Token::Op("(")) => {
+                let node = self.expr()?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            Some(Token::Ident(name)) => self.ident(name),
+            Some(token) => Err(anyhow::anyhow!("Unexpected {:?}", token)),
+            None => Err(anyhow::anyhow!("Unexpected end of expression")),
+        }
+    }
+
+    fn ident(&mut self, name: String) -> Result<Node> {
+        if !self.eat("(") {
+            return match name.as_str() {
+                "value" => Ok(Node::Value),
+                "pi" => Ok(Node::Num(std::f64::consts::PI)),
+                "e" => Ok(Node::Num(std::f64::consts::E)),
+                "true" => Ok(Node::Num(1.0)),
+                "false" => Ok(Node::Num(0.0)),
+                _ => Err(anyhow::anyhow!("Unknown identifier '{}'", name)),
+            };
+        }
+
+        if name == "tag" {
+            let tag = match self.next() {
+                Some(Token::Str(tag)) if !tag.is_empty() => tag,
+                other => return Err(anyhow::anyhow!("tag() expects a tag name string, found {:?}", other)),
+            };
+            self.expect(")")?;
+            return Ok(Node::Tag(tag));
+        }
+
+        let func = Func::lookup(&name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown function '{}'", name))?;
+
+        let mut args = Vec::new();
+        if !self.eat(")") {
+            loop {
+                args.push(self.expr()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+
+        func.check_arity(&name, args.len())?;
+        Ok(Node::Call(func, args))
+    }
+}
@@ -1,14 +1,19 @@
-//! Modbus-TCP静态驱动
+//! Modbus-TCP/RTU静态驱动
 //! 
-//! 基于tokio-modbus实现的高性能Modbus-TCP驱动
+//! 基于tokio-modbus实现的高性能Modbus驱动，`serial://`端点自动使用RTU帧格式，`udp://`端点使用Modbus/UDP
 
 pub mod driver;
 pub mod config;
 pub mod codec;
+pub mod expr;
+pub mod mbap;
+pub mod planner;
+pub mod scan;
 pub mod metrics;
 
 pub use driver::ModbusDriver;
 pub use config::ModbusCfg;
+pub use planner::BlockPlanner;
 
 use driver_manager::{DriverMeta, DriverKind, Driver, register_static_driver};
 
@@ -27,7 +32,7 @@ pub fn meta() -> DriverMeta {
         kind: DriverKind::Static,
         version: "0.1.0".to_string(),
         api_version: 1,
-        description: "Static Modbus-TCP driver based on tokio-modbus".to_string(),
-        features: vec!["read".to_string()], // MVP-0 only read
+        description: "Static Modbus-TCP/RTU/UDP driver based on tokio-modbus".to_string(),
+        features: vec!["read".to_string(), "rtu".to_string(), "udp".to_string()], // MVP-0 only read
     }
 }
\ No newline at end of file
@@ -0,0 +1,155 @@
+//! Modbus/UDP 帧编解码
+//!
+//! Modbus/UDP 与 Modbus/TCP 使用相同的MBAP报文头，每个数据报承载一个完整ADU：
+//! `事务ID(2) | 协议ID(2)=0 | 长度(2) | 单元ID(1) | PDU`
+
+use anyhow::Result;
+
+/// MBAP报文头长度
+pub const MBAP_HEADER_LEN: usize = 7;
+
+/// Modbus异常应答
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModbusException {
+    /// 请求功能码
+    pub function: u8,
+    /// 异常码
+    pub code: u8,
+}
+
+impl ModbusException {
+    /// 非法数据地址
+    pub const ILLEGAL_DATA_ADDRESS: u8 = 0x02;
+
+    /// 是否为非法数据地址异常
+    pub fn is_illegal_address(&self) -> bool {
+        self.code == Self::ILLEGAL_DATA_ADDRESS
+    }
+}
+
+impl std::fmt::Display for ModbusException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Modbus exception 0x{:02X} for function 0x{:02X}", self.code, self.function)
+    }
+}
+
+impl std::error::Error for ModbusException {}
+
+/// 封装ADU
+pub fn encode_adu(transaction_id: u16, unit_id: u8, pdu: &[u8]) -> Vec<u8> {
+    let mut adu = Vec::with_capacity(MBAP_HEADER_LEN + pdu.len());
+    adu.extend_from_slice(&transaction_id.to_be_bytes());
+    adu.extend_from_slice(&0u16.to_be_bytes());
+    adu.extend_from_slice(&((pdu.len() + 1) as u16).to_be_bytes());
+    adu.push(unit_id);
+    adu.extend_from_slice(pdu);
+    adu
+}
+
+/// 读寄存器请求PDU (功能码0x03/0x04)
+pub fn read_registers_pdu(function: u8, start: u16, qty: u16) -> Vec<u8> {
+    let mut pdu = vec![function];
+    pdu.extend_from_slice(&start.to_be_bytes());
+    pdu.extend_from_slice(&qty.to_be_bytes());
+    pdu
+}
+
+/// 写单个寄存器请求PDU (功能码0x06)
+pub fn write_single_register_pdu(addr: u16, value: u16) -> Vec<u8> {
+    let mut pdu = vec![0x06];
+    pdu.extend_from_slice(&addr.to_be_bytes());
+    pdu.extend_from_slice(&value.to_be_bytes());
+    pdu
+}
+
+/// 写多个寄存器请求PDU (功能码0x10)
+pub fn write_multiple_registers_pdu(addr: u16, values: &[u16]) -> Vec<u8> {
+    let mut pdu = vec![0x10];
+    pdu.extend_from_slice(&addr.to_be_bytes());
+    pdu.extend_from_slice(&(values.len() as u16).to_be_bytes());
+    pdu.push((values.len() * 2) as u8);
+    for value in values {
+        pdu.extend_from_slice(&value.to_be_bytes());
+    }
+    pdu
+}
+
+/// 判断数据报是否为指定事务的应答
+pub fn is_response_to(datagram: &[u8], transaction_id: u16) -> bool {
+    datagram.len() >= MBAP_HEADER_LEN
+        && datagram[0..2] == transaction_id.to_be_bytes()
+        && datagram[2..4] == [0, 0]
+}
+
+/// 校验MBAP报文头并返回应答PDU
+///
+/// 异常应答 (功能码最高位置1) 转换为[`ModbusException`]错误
+pub fn decode_response(datagram: &[u8], unit_id: u8, function: u8) -> Result<&[u8]> {
+    if datagram.len() < MBAP_HEADER_LEN + 1 {
+        return Err(anyhow::anyhow!("Modbus/UDP response too short: {} bytes", datagram.len()));
+    }
+
+    let length = u16::from_be_bytes([datagram[4], datagram[5]]) as usize;
+    if length + 6 != datagram.len() {
+        return Err(anyhow::anyhow!(
+            "Modbus/UDP length mismatch: header {} actual {}", length, datagram.len() - 6
+        ));
+    }
+    if datagram[6] != unit_id {
+        return Err(anyhow::anyhow!("Unexpected unit id {} (expected {})", datagram[6], unit_id));
+    }
+
+    let pdu = &datagram[MBAP_HEADER_LEN..];
+    if pdu[0] == function | 0x80 {
+        let code = pdu.get(1).copied().unwrap_or(0);
+        return Err(ModbusException { function, code }.into());
+    }
+    if pdu[0] != function {
+        return Err(anyhow::anyhow!("Unexpected function code 0x{:02X} (expected 0x{:02X})", pdu[0], function));
+    }
+
+    Ok(pdu)
+}
+
+/// 解析读寄存器应答PDU
+pub fn parse_registers(pdu: &[u8], qty: u16) -> Result<Vec<u16>> {
+    let byte_count = *pdu.get(1)
+        .ok_or_else(|| anyhow::anyhow!("Missing byte count"))? as usize;
+    if byte_count != qty as usize * 2 || pdu.len() < 2 + byte_count {
+        return Err(anyhow::anyhow!("Invalid register payload: {} bytes for {} registers", byte_count, qty));
+    }
+
+    Ok(pdu[2..2 + byte_count]
+        .chunks_exact(2)
+        .map(|c| u16::from_be_bytes([c[0], c[1]]))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_read_request() {
+        let adu = encode_adu(0x0102, 1, &read_registers_pdu(0x03, 0x0010, 2));
+        assert_eq!(adu, vec![0x01, 0x02, 0x00, 0x00, 0x00, 0x06, 0x01, 0x03, 0x00, 0x10, 0x00, 0x02]);
+    }
+
+    #[test]
+    fn test_decode_read_response() {
+        let resp = [0x01, 0x02, 0x00, 0x00, 0x00, 0x07, 0x01, 0x03, 0x04, 0x12, 0x34, 0x56, 0x78];
+        assert!(is_response_to(&resp, 0x0102));
+        assert!(!is_response_to(&resp, 0x0103));
+
+        let pdu = decode_response(&resp, 1, 0x03).unwrap();
+        assert_eq!(parse_registers(pdu, 2).unwrap(), vec![0x1234, 0x5678]);
+    }
+
+    #[test]
+    fn test_decode_exception() {
+        let resp = [0x00, 0x01, 0x00, 0x00, 0x00, 0x03, 0x01, 0x83, 0x02];
+        let err = decode_response(&resp, 1, 0x03).unwrap_err();
+        assert!(err.to_string().contains("exception 0x02"));
+        assert!(err.downcast_ref::<ModbusException>().unwrap().is_illegal_address());
+    }
+}
@@ -1,6 +1,6 @@
 //! Modbus驱动Prometheus指标
 
-use prometheus::{Counter, Histogram, Opts, HistogramOpts};
+use prometheus::{Counter, CounterVec, Histogram, Opts, HistogramOpts};
 use once_cell::sync::Lazy;
 
 pub static METRICS: Lazy<ModbusMetrics> = Lazy::new(ModbusMetrics::new);
@@ -11,6 +11,7 @@ pub struct ModbusMetrics {
     pub point_latency: Histogram,
     pub reconnect_total: Counter,
     pub exception_total: Counter,
+    pub scan_overrun_total: CounterVec,
 }
 
 impl ModbusMetrics {
@@ -45,12 +46,19 @@ impl ModbusMetrics {
         ).unwrap();
         registry.register(Box::new(exception_total.clone())).unwrap();
 
+        let scan_overrun_total = CounterVec::new(
+            Opts::new("modbus_scan_overrun_total", "Scans that did not finish within their scan interval"),
+            &["class"]
+        ).unwrap();
+        registry.register(Box::new(scan_overrun_total.clone())).unwrap();
+
         Self {
             pdu_total,
             point_total,
             point_latency,
             reconnect_total,
             exception_total,
+            scan_overrun_total,
         }
     }
 }
\ No newline at end of file
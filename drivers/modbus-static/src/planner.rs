@@ -0,0 +1,247 @@
+//! 读请求规划器
+//!
+//! 将点位合并为尽量少的读请求：相邻块之间的空隙不超过`max_gap`时合并
+//! (多读几个寄存器比多一次往返更便宜)，单个请求不超过`max_regs`。
+//!
+//! 从站对整块返回非法数据地址异常 (0x02) 时，按点位二分拆分该块，
+//! 逐步定位不可读的地址并记入跳过集合，之后的规划不再读取这些地址。
+//! 拆分后两半都读取成功时，说明问题出在两半之间的空隙，该空隙同样记入跳过集合。
+
+use std::collections::{BTreeSet, HashMap};
+use tokio_modbus::FunctionCode;
+
+use crate::config::{PollBatch, RegPoint};
+
+/// 读请求规划器
+#[derive(Debug, Clone)]
+pub struct BlockPlanner {
+    /// 单次请求最大寄存器数
+    max_regs: u16,
+    /// 允许跨越的最大空隙寄存器数
+    max_gap: u16,
+    /// 按功能码记录的不可读地址
+    skip: HashMap<u8, BTreeSet<u16>>,
+    /// 待确认的可疑空隙
+    suspects: Vec<SuspectGap>,
+}
+
+/// 拆分产生的可疑空隙，两侧子批次都读取成功后确认为不可读
+#[derive(Debug, Clone)]
+struct SuspectGap {
+    code: u8,
+    gap: (u32, u32),
+    sides: [(u16, u16); 2],
+    confirmed: [bool; 2],
+}
+
+impl BlockPlanner {
+    pub fn new(max_regs: u16, max_gap: u16) -> Self {
+        Self {
+            max_regs: max_regs.max(1),
+            max_gap,
+            skip: HashMap::new(),
+            suspects: Vec::new(),
+        }
+    }
+
+    /// 规划读请求批次
+    ///
+    /// 覆盖跳过地址的点位不参与规划，批次也不会跨越跳过地址
+    pub fn plan(&mut self, points: &[RegPoint]) -> Vec<PollBatch> {
+        self.suspects.clear();
+
+        let mut grouped: HashMap<u8, Vec<&RegPoint>> = HashMap::new();
+        for point in points {
+            if let Some(code) = read_function_code(&point.func) {
+                grouped.entry(code).or_default().push(point);
+            }
+        }
+
+        let mut codes: Vec<u8> = grouped.keys().copied().collect();
+        codes.sort_unstable();
+
+        let mut batches = Vec::new();
+        for code in codes {
+            let mut points = grouped.remove(&code).unwrap_or_default();
+            points.sort_by_key(|p| p.addr);
+
+            let mut current: Option<PollBatch> = None;
+            for point in points {
+                if self.is_skipped(code, point_start(point), point_end(point)) {
+                    continue;
+                }
+
+                if let Some(batch) = current.as_mut() {
+                    if self.can_extend(code, batch, point) {
+                        let end = batch_end(batch).max(point_end(point));
+                        batch.qty = (end - batch.start as u32 + 1) as u16;
+                        batch.points.push(point.clone());
+                        continue;
+                    }
+                    batches.extend(current.take());
+                }
+                current = Some(PollBatch {
+                    func: point.func,
+                    start: point.addr,
+                    qty: point.len.max(1),
+                    points: vec![point.clone()],
+                });
+            }
+            batches.extend(current);
+        }
+
+        batches
+    }
+
+    /// 处理非法数据地址异常，返回需要重新读取的子批次
+    ///
+    /// 多点位批次按点位对半拆分，两半之间的空隙记为可疑；
+    /// 单点位批次无法再拆分，将其地址记为跳过并返回空列表
+    pub fn split(&mut self, batch: &PollBatch) -> Vec<PollBatch> {
+        let Some(code) = read_function_code(&batch.func) else {
+            return Vec::new();
+        };
+
+        if batch.points.len() <= 1 {
+            for point in &batch.points {
+                tracing::warn!(
+                    "Modbus address {}..={} (function 0x{:02X}) is not readable, point {} disabled",
+                    point_start(point), point_end(point), code, point.tag
+                );
+                self.mark_skipped(code, point_start(point), point_end(point));
+            }
+            return Vec::new();
+        }
+
+        let mut points = batch.points.clone();
+        points.sort_by_key(|p| p.addr);
+        let right = points.split_off(points.len() / 2);
+        let left = points;
+
+        let left_end = left.iter().map(point_end).max().unwrap_or(0);
+        let right_start = right.iter().map(point_start).min().unwrap_or(0);
+        let halves: Vec<PollBatch> = [left, right].into_iter()
+            .map(|points| tighten(batch.func, points))
+            .collect();
+
+        if right_start > left_end + 1 {
+            self.suspects.push(SuspectGap {
+                code,
+                gap: (left_end + 1, right_start - 1),
+                sides: [(halves[0].start, halves[0].qty), (halves[1].start, halves[1].qty)],
+                confirmed: [false; 2],
+            });
+        }
+
+        halves
+    }
+
+    /// 记录批次读取成功，用于确认拆分产生的可疑空隙
+    pub fn record_success(&mut self, batch: &PollBatch) {
+        if self.suspects.is_empty() {
+            return;
+        }
+        let Some(code) = read_function_code(&batch.func) else {
+            return;
+        };
+
+        let span = (batch.start, batch.qty);
+        let mut confirmed_gaps = Vec::new();
+        self.suspects.retain_mut(|suspect| {
+            if suspect.code != code {
+                return true;
+            }
+            for (side, confirmed) in suspect.sides.iter().zip(suspect.confirmed.iter_mut()) {
+                if *side == span {
+                    *confirmed = true;
+                }
+            }
+            if suspect.confirmed.iter().all(|c| *c) {
+                confirmed_gaps.push(suspect.gap);
+                return false;
+            }
+            true
+        });
+
+        for (start, end) in confirmed_gaps {
+            tracing::warn!(
+                "Modbus address {}..={} (function 0x{:02X}) is not readable, no longer merged",
+                start, end, code
+            );
+            self.mark_skipped(code, start, end);
+        }
+    }
+
+    /// 指定地址是否已被跳过
+    pub fn is_address_skipped(&self, func: &FunctionCode, addr: u16) -> bool {
+        read_function_code(func)
+            .and_then(|code| self.skip.get(&code))
+            .is_some_and(|set| set.contains(&addr))
+    }
+
+    /// 跳过地址总数
+    pub fn skipped_count(&self) -> usize {
+        self.skip.values().map(BTreeSet::len).sum()
+    }
+
+    fn can_extend(&self, code: u8, batch: &PollBatch, point: &RegPoint) -> bool {
+        let end = batch_end(batch);
+        let gap_start = end + 1;
+        let start = point_start(point);
+
+        if start > gap_start && start - gap_start > self.max_gap as u32 {
+            return false;
+        }
+        if point_end(point).max(end) - batch.start as u32 + 1 > self.max_regs as u32 {
+            return false;
+        }
+        start <= gap_start || !self.is_skipped(code, gap_start, start - 1)
+    }
+
+    fn is_skipped(&self, code: u8, start: u32, end: u32) -> bool {
+        self.skip.get(&code).is_some_and(|set| {
+            set.range(start.min(u16::MAX as u32) as u16..=end.min(u16::MAX as u32) as u16)
+                .next()
+                .is_some()
+        })
+    }
+
+    fn mark_skipped(&mut self, code: u8, start: u32, end: u32) {
+        let set = self.skip.entry(code).or_default();
+        for addr in start..=end.min(u16::MAX as u32) {
+            set.insert(addr as u16);
+        }
+    }
+}
+
+/// 以点位实际覆盖的地址范围重建批次
+fn tighten(func: FunctionCode, points: Vec<RegPoint>) -> PollBatch {
+    let start = points.iter().map(|p| p.addr).min().unwrap_or(0);
+    let end = points.iter().map(point_end).max().unwrap_or(start as u32);
+    PollBatch {
+        func,
+        start,
+        qty: (end - start as u32 + 1) as u16,
+        points,
+    }
+}
+
+fn read_function_code(func: &FunctionCode) -> Option<u8> {
+    match func {
+        FunctionCode::ReadHoldingRegisters => Some(0x03),
+        FunctionCode::ReadInputRegisters => Some(0x04),
+        _ => None,
+    }
+}
+
+fn point_start(point: &RegPoint) -> u32 {
+    point.addr as u32
+}
+
+fn point_end(point: &RegPoint) -> u32 {
+    point.addr as u32 + point.len.max(1) as u32 - 1
+}
+
+fn batch_end(batch: &PollBatch) -> u32 {
+    batch.start as u32 + batch.qty.max(1) as u32 - 1
+}
@@ -0,0 +1,94 @@
+//! 扫描等级调度
+//!
+//! 每个扫描等级拥有独立的批次与截止时间，读循环总是先处理最早到期的等级。
+//! 一次扫描结束时下一个截止时间已经过去即为超限 (overrun)，此时跳过错过的周期立即重新调度。
+
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::config::{ModbusCfg, PollBatch, RegPoint, ScanClass};
+use crate::planner::BlockPlanner;
+
+/// 单个扫描等级的轮询组
+#[derive(Debug, Clone)]
+pub struct ScanGroup {
+    pub class: ScanClass,
+    pub interval: Duration,
+    pub batches: Vec<PollBatch>,
+    /// 覆盖不可读地址、未参与规划的点位，每次扫描发布坏质量帧
+    pub skipped: Vec<RegPoint>,
+    pub next_due: Instant,
+}
+
+/// 扫描等级调度器
+#[derive(Debug, Clone, Default)]
+pub struct ScanScheduler {
+    groups: Vec<ScanGroup>,
+}
+
+impl ScanScheduler {
+    /// 按扫描等级分组点位并规划批次，所有等级立即到期
+    pub fn new(cfg: &ModbusCfg, planner: &mut BlockPlanner, points: &[RegPoint], now: Instant) -> Self {
+        let mut scheduler = Self::default();
+        scheduler.replan(cfg, planner, points, now);
+        scheduler
+    }
+
+    /// 重新规划批次，保留已有等级的截止时间
+    pub fn replan(&mut self, cfg: &ModbusCfg, planner: &mut BlockPlanner, points: &[RegPoint], now: Instant) {
+        let mut by_class: BTreeMap<ScanClass, Vec<RegPoint>> = BTreeMap::new();
+        for point in points {
+            by_class.entry(point.scan.clone()).or_default().push(point.clone());
+        }
+
+        let previous = std::mem::take(&mut self.groups);
+        self.groups = by_class.into_iter()
+            .map(|(class, points)| {
+                let next_due = previous.iter()
+                    .find(|g| g.class == class)
+                    .map_or(now, |g| g.next_due);
+                let batches = planner.plan(&points);
+                let skipped = points.into_iter()
+                    .filter(|p| !batches.iter().any(|b| b.points.iter().any(|bp| bp.tag == p.tag)))
+                    .collect();
+                ScanGroup {
+                    interval: cfg.scan_interval(&class),
+                    batches,
+                    skipped,
+                    class,
+                    next_due,
+                }
+            })
+            .filter(|g| !g.batches.is_empty() || !g.skipped.is_empty())
+            .collect();
+    }
+
+    /// 最早到期的轮询组
+    pub fn next_due(&self) -> Option<(usize, Instant)> {
+        self.groups.iter()
+            .enumerate()
+            .min_by_key(|(_, g)| g.next_due)
+            .map(|(index, g)| (index, g.next_due))
+    }
+
+    pub fn groups(&self) -> &[ScanGroup] {
+        &self.groups
+    }
+
+    pub fn group_mut(&mut self, index: usize) -> &mut ScanGroup {
+        &mut self.groups[index]
+    }
+
+    /// 记录一次扫描完成并安排下一次，返回是否超限
+    pub fn complete(&mut self, index: usize, now: Instant) -> bool {
+        let group = &mut self.groups[index];
+        let next_due = group.next_due + group.interval;
+        if now > next_due {
+            group.next_due = now;
+            true
+        } else {
+            group.next_due = next_due;
+            false
+        }
+    }
+}
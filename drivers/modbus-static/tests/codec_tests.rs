@@ -1,7 +1,7 @@
 //! Modbus编解码测试
 
-use modbus_static::codec::{decode_registers, encode_value, apply_scale};
-use modbus_static::config::{DataType, Endian, RegPoint, Access};
+use modbus_static::codec::{decode_registers, encode_value, encode_point, set_bit, apply_scale};
+use modbus_static::config::{DataType, Endian, RegPoint, Access, ScanClass, parse_address};
 use frame_bus::Value;
 use tokio_modbus::FunctionCode;
 
@@ -14,7 +14,9 @@ fn create_test_point(addr: u16, datatype: DataType, len: u16) -> RegPoint {
         len,
         datatype,
         scale: None,
+        unscale: None,
         access: Access::R,
+        scan: ScanClass::Normal,
     }
 }
 
@@ -289,6 +291,134 @@ fn test_invalid_scale_expressions() {
     assert!(result2.is_err(), "Should fail with division by zero");
 }
 
+#[test]
+fn test_int64_word_and_byte_orders() {
+    let value: i64 = 0x0102_0304_0506_0708;
+    let point = create_test_point(0, DataType::Int64, 4);
+    let cases = vec![
+        (Endian::Big, vec![0x0102, 0x0304, 0x0506, 0x0708]),
+        (Endian::Little, vec![0x0708, 0x0506, 0x0304, 0x0102]),
+        (Endian::BigSwap, vec![0x0201, 0x0403, 0x0605, 0x0807]),
+        (Endian::LittleSwap, vec![0x0807, 0x0605, 0x0403, 0x0201]),
+    ];
+
+    for (endian, regs) in cases {
+        assert_eq!(encode_value(&Value::int(value), &DataType::Int64, &endian).unwrap(), regs, "{:?}", endian);
+        assert_eq!(decode_registers(&regs, &point, 0, &endian).unwrap().to_i64(), Some(value), "{:?}", endian);
+    }
+
+    let negative = encode_value(&Value::int(-2), &DataType::Int64, &Endian::Big).unwrap();
+    assert_eq!(negative, vec![0xFFFF, 0xFFFF, 0xFFFF, 0xFFFE]);
+    assert_eq!(decode_registers(&negative, &point, 0, &Endian::Big).unwrap().to_i64(), Some(-2));
+}
+
+#[test]
+fn test_uint64_decoding() {
+    let point = create_test_point(0, DataType::Uint64, 4);
+
+    let value = decode_registers(&[0, 0, 0x0001, 0x0000], &point, 0, &Endian::Big).unwrap();
+    assert_eq!(value.to_i64(), Some(0x10000));
+
+    // 超出i64范围时以浮点数表示
+    let value = decode_registers(&[0xFFFF, 0xFFFF, 0xFFFF, 0xFFFF], &point, 0, &Endian::Big).unwrap();
+    assert_eq!(value.to_f64(), Some(u64::MAX as f64));
+
+    assert!(encode_value(&Value::int(-1), &DataType::Uint64, &Endian::Big).is_err());
+    assert_eq!(
+        encode_value(&Value::int(0x0001_0002_0003_0004), &DataType::Uint64, &Endian::Little).unwrap(),
+        vec![0x0004, 0x0003, 0x0002, 0x0001]
+    );
+}
+
+#[test]
+fn test_endian_config_names() {
+    let parse = |name: &str| serde_json::from_value::<Endian>(serde_json::json!(name)).unwrap();
+    assert!(matches!(parse("big"), Endian::Big));
+    assert!(matches!(parse("cdab"), Endian::Little));
+    assert!(matches!(parse("big_swap"), Endian::BigSwap));
+    assert!(matches!(parse("dcba"), Endian::LittleSwap));
+}
+
+#[test]
+fn test_string_decoding() {
+    // "PUMP-01" 占4个寄存器，末尾NUL填充
+    let regs = vec![0x5055, 0x4D50, 0x2D30, 0x3100];
+    let point = create_test_point(0, DataType::String, 4);
+
+    let value = decode_registers(&regs, &point, 0, &Endian::Big).unwrap();
+    assert_eq!(value.to_string(), Some("PUMP-01".to_string()));
+
+    // 寄存器内字节交换
+    let swapped: Vec<u16> = regs.iter().map(|r| r.swap_bytes()).collect();
+    let value = decode_registers(&swapped, &point, 0, &Endian::BigSwap).unwrap();
+    assert_eq!(value.to_string(), Some("PUMP-01".to_string()));
+
+    // 寄存器不足
+    assert!(decode_registers(&regs[..2], &point, 0, &Endian::Big).is_err());
+    // 非ASCII字节
+    assert!(decode_registers(&[0xC3A9, 0, 0, 0], &point, 0, &Endian::Big).is_err());
+}
+
+#[test]
+fn test_string_encoding() {
+    let point = create_test_point(10, DataType::String, 4);
+
+    let regs = encode_point(&Value::string("AB1"), &point, &Endian::Big).unwrap();
+    assert_eq!(regs, vec![0x4142, 0x3100, 0x0000, 0x0000]);
+
+    let decoded = decode_registers(&regs, &point, 10, &Endian::Big).unwrap();
+    assert_eq!(decoded.to_string(), Some("AB1".to_string()));
+
+    assert!(encode_point(&Value::string("TOO LONG TEXT"), &point, &Endian::Big).is_err());
+    assert!(encode_value(&Value::string("温度"), &DataType::String, &Endian::Big).is_err());
+}
+
+#[test]
+fn test_bcd_roundtrip() {
+    let point16 = create_test_point(0, DataType::Bcd16, 1);
+    assert_eq!(decode_registers(&[0x1234], &point16, 0, &Endian::Big).unwrap().to_i64(), Some(1234));
+    assert_eq!(encode_value(&Value::int(9876), &DataType::Bcd16, &Endian::Big).unwrap(), vec![0x9876]);
+
+    let point32 = create_test_point(0, DataType::Bcd32, 2);
+    assert_eq!(decode_registers(&[0x1234, 0x5678], &point32, 0, &Endian::Big).unwrap().to_i64(), Some(12345678));
+    assert_eq!(decode_registers(&[0x5678, 0x1234], &point32, 0, &Endian::Little).unwrap().to_i64(), Some(12345678));
+    assert_eq!(encode_value(&Value::int(42), &DataType::Bcd32, &Endian::Big).unwrap(), vec![0x0000, 0x0042]);
+
+    // 非法BCD数字与越界值
+    assert!(decode_registers(&[0x12A4], &point16, 0, &Endian::Big).is_err());
+    assert!(encode_value(&Value::int(10000), &DataType::Bcd16, &Endian::Big).is_err());
+    assert!(encode_value(&Value::int(-1), &DataType::Bcd16, &Endian::Big).is_err());
+}
+
+#[test]
+fn test_bit_in_register() {
+    let regs = vec![0x0000, 0b1000_0000_0000_1000];
+    let bit3 = create_test_point(1, DataType::Bit(3), 1);
+    let bit15 = create_test_point(1, DataType::Bit(15), 1);
+    let bit0 = create_test_point(1, DataType::Bit(0), 1);
+
+    assert_eq!(decode_registers(&regs, &bit3, 0, &Endian::Big).unwrap().to_bool(), Some(true));
+    assert_eq!(decode_registers(&regs, &bit15, 0, &Endian::Big).unwrap().to_bool(), Some(true));
+    assert_eq!(decode_registers(&regs, &bit0, 0, &Endian::Big).unwrap().to_bool(), Some(false));
+
+    // 位写入需要读-改-写，只改变目标位
+    assert!(encode_value(&Value::bool(true), &DataType::Bit(3), &Endian::Big).is_err());
+    assert_eq!(set_bit(0x00F0, 0, true).unwrap(), 0x00F1);
+    assert_eq!(set_bit(0x00F0, 4, false).unwrap(), 0x00E0);
+    assert_eq!(set_bit(0x00F0, 4, true).unwrap(), 0x00F0);
+    assert!(set_bit(0, 16, true).is_err());
+}
+
+#[test]
+fn test_parse_address_with_bit_suffix() {
+    assert_eq!(parse_address("100").unwrap(), (100, None));
+    assert_eq!(parse_address(" 100.15 ").unwrap(), (100, Some(15)));
+    assert_eq!(parse_address("0.0").unwrap(), (0, Some(0)));
+    assert!(parse_address("100.16").is_err());
+    assert!(parse_address("100.x").is_err());
+    assert!(parse_address("70000").is_err());
+}
+
 // 辅助函数
 fn decode_float32_from_regs(regs: &[u16], endian: &Endian) -> Result<f32, anyhow::Error> {
     if regs.len() < 2 {
@@ -298,6 +428,7 @@ fn decode_float32_from_regs(regs: &[u16], endian: &Endian) -> Result<f32, anyhow
     let bits = match endian {
         Endian::Big => ((regs[0] as u32) << 16) | (regs[1] as u32),
         Endian::Little => ((regs[1] as u32) << 16) | (regs[0] as u32),
+        _ => return Err(anyhow::anyhow!("Byte-swapped orders are not used by this helper")),
     };
     
     Ok(f32::from_bits(bits))
@@ -0,0 +1,85 @@
+//! 驱动配置点位表解析与校验测试
+
+use std::time::Duration;
+use modbus_static::codec::decode_registers;
+use modbus_static::config::{DataType, Endian, RegPoint, ScanClass};
+use modbus_static::ModbusCfg;
+use serde_json::json;
+
+fn parse_points(points: serde_json::Value) -> anyhow::Result<Vec<RegPoint>> {
+    let cfg: ModbusCfg = serde_json::from_value(json!({
+        "unit_id": 1,
+        "polling": "1s",
+        "points": points,
+    }))?;
+    Ok(cfg.points)
+}
+
+#[test]
+fn test_points_from_driver_config() {
+    let points = parse_points(json!([
+        { "tag": "temp", "address": 0, "scale": "value / 10.0", "scan": "fast" },
+        { "tag": "flow", "address": "10", "register": "input", "datatype": "float32", "scan": "250ms" },
+        { "tag": "alarm", "address": "20.15", "access": "rw" },
+        { "tag": "name", "address": 30, "datatype": "string", "len": 8 },
+    ])).unwrap();
+
+    assert_eq!(points.len(), 4);
+    assert!(matches!(points[0].datatype, DataType::Uint16));
+    assert_eq!(points[0].scan, ScanClass::Fast);
+    assert_eq!(points[1].func, tokio_modbus::FunctionCode::ReadInputRegisters);
+    assert_eq!(points[1].len, 2);
+    assert_eq!(points[1].scan, ScanClass::Interval(Duration::from_millis(250)));
+    assert!(matches!(points[2].datatype, DataType::Bit(15)));
+    assert_eq!((points[2].addr, points[2].len), (20, 1));
+    assert_eq!(points[3].len, 8);
+
+    // 序列化后可原样加载
+    let reloaded = parse_points(serde_json::to_value(&points).unwrap()).unwrap();
+    assert!(matches!(reloaded[2].datatype, DataType::Bit(15)));
+    assert_eq!(reloaded[1].scan, points[1].scan);
+}
+
+#[test]
+fn test_invalid_points_rejected_at_load() {
+    let invalid = [
+        json!({ "tag": "bit", "address": "20.16" }),
+        json!({ "tag": "bit", "address": "20.3", "datatype": "float32" }),
+        json!({ "tag": "bit", "address": 20, "datatype": "bit" }),
+        json!({ "tag": "text", "address": 0, "datatype": "string" }),
+        json!({ "tag": "len", "address": 0, "datatype": "uint32", "len": 1 }),
+        json!({ "tag": "end", "address": 65534, "datatype": "float64" }),
+        json!({ "tag": "ro", "address": 0, "register": "input", "access": "rw" }),
+        json!({ "tag": "scan", "address": 0, "scan": "0s" }),
+        json!({ "tag": "", "address": 0 }),
+    ];
+    for point in invalid {
+        assert!(parse_points(json!([point.clone()])).is_err(), "{} should be rejected", point);
+    }
+}
+
+#[test]
+fn test_bit_decode_bounds() {
+    let mut point: RegPoint = parse_points(json!([{ "tag": "bit", "address": "5.3" }])).unwrap().remove(0);
+
+    assert_eq!(decode_registers(&[0, 0x0008], &point, 4, &Endian::Big).unwrap().to_bool(), Some(true));
+    // 点位不在批次寄存器范围内
+    assert!(decode_registers(&[0x0008], &point, 6, &Endian::Big).is_err());
+    assert!(decode_registers(&[0x0008], &point, 0, &Endian::Big).is_err());
+
+    point.datatype = DataType::Bit(16);
+    assert!(decode_registers(&[0xFFFF], &point, 5, &Endian::Big).is_err());
+}
+
+#[test]
+fn test_instance_id_from_driver_config() {
+    let cfg: ModbusCfg = serde_json::from_value(json!({ "unit_id": 1, "polling": "1s" })).unwrap();
+    assert_eq!(cfg.instance_id, "modbus-tcp");
+
+    let cfg: ModbusCfg = serde_json::from_value(json!({
+        "instance_id": "modbus_driver_1",
+        "unit_id": 1,
+        "polling": "1s",
+    })).unwrap();
+    assert_eq!(cfg.instance_id, "modbus_driver_1");
+}
@@ -0,0 +1,155 @@
+//! 缩放表达式测试
+
+use std::collections::HashMap;
+use modbus_static::codec::PointScale;
+use modbus_static::config::DataType;
+use modbus_static::expr::{Expr, NoTags};
+use frame_bus::Value;
+
+fn eval(source: &str, value: f64) -> f64 {
+    Expr::compile(source).unwrap().eval(value, &NoTags).unwrap()
+}
+
+#[test]
+fn test_operator_precedence() {
+    assert_eq!(eval("1 + 2 * 3", 0.0), 7.0);
+    assert_eq!(eval("(1 + 2) * 3", 0.0), 9.0);
+    assert_eq!(eval("2 ^ 3 ^ 2", 0.0), 512.0);
+    assert_eq!(eval("-2 ^ 2", 0.0), -4.0);
+    assert_eq!(eval("value % 7", 23.0), 2.0);
+    assert_eq!(eval("1.5e3 + .5", 0.0), 1500.5);
+}
+
+#[test]
+fn test_math_functions() {
+    assert_eq!(eval("abs(value)", -3.5), 3.5);
+    assert_eq!(eval("sqrt(value)", 16.0), 4.0);
+    assert_eq!(eval("round(value / 3)", 10.0), 3.0);
+    assert_eq!(eval("min(value, 5, 7)", 9.0), 5.0);
+    assert_eq!(eval("max(value, 5, 7)", 9.0), 9.0);
+    assert_eq!(eval("clamp(value, 0, 100)", 140.0), 100.0);
+    assert_eq!(eval("pow(value, 2)", 3.0), 9.0);
+    assert!((eval("sin(pi / 2)", 0.0) - 1.0).abs() < 1e-12);
+    assert!((eval("ln(e)", 0.0) - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_conditionals() {
+    let expr = Expr::compile("value > 32767 ? value - 65536 : value").unwrap();
+    assert_eq!(expr.eval(65535.0, &NoTags).unwrap(), -1.0);
+    assert_eq!(expr.eval(100.0, &NoTags).unwrap(), 100.0);
+
+    assert_eq!(eval("value >= 10 && value <= 20", 15.0), 1.0);
+    assert_eq!(eval("value < 10 || !(value == 15)", 15.0), 0.0);
+    assert_eq!(eval("value < 0 ? 0 : value > 100 ? 100 : value", 120.0), 100.0);
+}
+
+#[test]
+fn test_interpolation() {
+    // 4-20mA (0-27648) -> 0-10 bar
+    assert_eq!(eval("linear(value, 0, 27648, 0, 10)", 13824.0), 5.0);
+
+    // 罐容积表，超出范围取端点值
+    let table = "interp(value, 0, 0, 100, 50, 200, 150)";
+    assert_eq!(eval(table, 50.0), 25.0);
+    assert_eq!(eval(table, 150.0), 100.0);
+    assert_eq!(eval(table, -10.0), 0.0);
+    assert_eq!(eval(table, 500.0), 150.0);
+
+    let unordered = Expr::compile("interp(value, 10, 0, 5, 1)").unwrap();
+    assert!(unordered.eval(1.0, &NoTags).is_err());
+}
+
+#[test]
+fn test_tag_references() {
+    let expr = Expr::compile("value * tag(\"plc.gain\") + tag('plc.offset')").unwrap();
+    assert_eq!(expr.tag_refs(), vec!["plc.gain".to_string(), "plc.offset".to_string()]);
+
+    let mut tags = HashMap::new();
+    tags.insert("plc.gain".to_string(), 2.0);
+    // 引用的点位尚无值时报错
+    assert!(expr.eval(10.0, &tags).is_err());
+
+    tags.insert("plc.offset".to_string(), 1.0);
+    assert_eq!(expr.eval(10.0, &tags).unwrap(), 21.0);
+}
+
+#[test]
+fn test_compile_errors() {
+    for source in [
+        "",
+        "value +",
+        "unknown",
+        "foo(value)",
+        "sqrt(1, 2)",
+        "interp(value, 0, 0, 1)",
+        "value = 1",
+        "tag(value)",
+        "(value",
+        "value $ 2",
+        "'unterminated",
+    ] {
+        assert!(Expr::compile(source).is_err(), "'{}' should not compile", source);
+    }
+
+    let deep = format!("{}value{}", "(".repeat(100), ")".repeat(100));
+    assert!(Expr::compile(&deep).is_err());
+
+    // 右结合的乘方链同样受嵌套深度限制
+    let tower = format!("value{}", "^2".repeat(10_000));
+    assert!(Expr::compile(&tower).is_err());
+    assert!(Expr::compile("value ^ 2 ^ 3").is_ok());
+}
+
+#[test]
+fn test_runtime_errors() {
+    assert!(Expr::compile("value % 0").unwrap().eval(1.0, &NoTags).is_err());
+    assert!(Expr::compile("sqrt(value)").unwrap().eval(-1.0, &NoTags).is_err());
+    assert!(Expr::compile("clamp(value, 10, 0)").unwrap().eval(1.0, &NoTags).is_err());
+}
+
+#[test]
+fn test_affine_inverse() {
+    let (a, b) = Expr::compile("value / 10.0").unwrap().affine_inverse().unwrap();
+    assert_eq!((a, b), (10.0, 0.0));
+
+    let (a, b) = Expr::compile("linear(value, 0, 27648, 0, 10)").unwrap().affine_inverse().unwrap();
+    assert!((a * 5.0 + b - 13824.0).abs() < 1e-6);
+
+    assert!(Expr::compile("sqrt(abs(value))").unwrap().affine_inverse().is_none());
+    assert!(Expr::compile("value * 0").unwrap().affine_inverse().is_none());
+    assert!(Expr::compile("value * tag(\"plc.gain\")").unwrap().affine_inverse().is_none());
+}
+
+#[test]
+fn test_point_scale_round_trip() {
+    let scale = PointScale::compile(Some("value / 10.0"), None).unwrap();
+    let engineering = scale.scale(Value::int(123), &NoTags).unwrap();
+    assert_eq!(engineering.to_f64(), Some(12.3));
+
+    // 整数类型四舍五入，避免12.3 * 10截断为122
+    let raw = scale.unscale(engineering, &DataType::Uint16, &NoTags).unwrap();
+    assert_eq!(raw.to_i64(), Some(123));
+
+    let raw = scale.unscale(Value::float(2.5), &DataType::Float32, &NoTags).unwrap();
+    assert_eq!(raw.to_f64(), Some(25.0));
+}
+
+#[test]
+fn test_point_scale_explicit_unscale() {
+    let scale = PointScale::compile(Some("sqrt(value)"), None).unwrap();
+    assert!(scale.unscale(Value::float(3.0), &DataType::Uint16, &NoTags).is_err());
+
+    let scale = PointScale::compile(Some("sqrt(value)"), Some("value ^ 2")).unwrap();
+    let raw = scale.unscale(Value::float(3.0), &DataType::Uint16, &NoTags).unwrap();
+    assert_eq!(raw.to_i64(), Some(9));
+
+    // 只配置unscale时同样生效
+    let scale = PointScale::compile(None, Some("value * 100")).unwrap();
+    let raw = scale.unscale(Value::float(1.5), &DataType::Uint16, &NoTags).unwrap();
+    assert_eq!(raw.to_i64(), Some(150));
+
+    // 位与字符串点位不做缩放
+    let raw = scale.unscale(Value::bool(true), &DataType::Bit(3), &NoTags).unwrap();
+    assert_eq!(raw.to_bool(), Some(true));
+}
@@ -0,0 +1,156 @@
+//! 读请求规划器测试
+
+use std::collections::{BTreeSet, VecDeque};
+use modbus_static::BlockPlanner;
+use modbus_static::config::{Access, DataType, PollBatch, RegPoint, ScanClass};
+use tokio_modbus::FunctionCode;
+
+fn point(addr: u16, len: u16) -> RegPoint {
+    point_with_func(FunctionCode::ReadHoldingRegisters, addr, len)
+}
+
+fn point_with_func(func: FunctionCode, addr: u16, len: u16) -> RegPoint {
+    RegPoint {
+        tag: format!("plc.{}", addr),
+        func,
+        addr,
+        len,
+        datatype: if len == 1 { DataType::Uint16 } else { DataType::Float32 },
+        scale: None,
+        unscale: None,
+        access: Access::R,
+        scan: ScanClass::Normal,
+    }
+}
+
+/// 模拟从站寄存器表：读取范围内存在未映射地址时返回非法地址异常
+struct FakeDevice {
+    mapped: BTreeSet<u16>,
+    requests: usize,
+}
+
+impl FakeDevice {
+    fn new(mapped: impl IntoIterator<Item = u16>) -> Self {
+        Self { mapped: mapped.into_iter().collect(), requests: 0 }
+    }
+
+    fn read(&mut self, batch: &PollBatch) -> bool {
+        self.requests += 1;
+        (batch.start..batch.start + batch.qty).all(|addr| self.mapped.contains(&addr))
+    }
+
+    /// 按驱动的方式执行一个轮询周期，返回成功读取的点位
+    fn poll_cycle(&mut self, planner: &mut BlockPlanner, batches: &[PollBatch]) -> BTreeSet<String> {
+        let mut read = BTreeSet::new();
+        let mut pending: VecDeque<PollBatch> = batches.iter().cloned().collect();
+        while let Some(batch) = pending.pop_front() {
+            if self.read(&batch) {
+                planner.record_success(&batch);
+                read.extend(batch.points.iter().map(|p| p.tag.clone()));
+            } else {
+                pending.extend(planner.split(&batch));
+            }
+        }
+        read
+    }
+}
+
+#[test]
+fn test_merge_across_small_gap() {
+    let mut planner = BlockPlanner::new(120, 10);
+    let batches = planner.plan(&[point(0, 1), point(5, 2), point(8, 1)]);
+
+    assert_eq!(batches.len(), 1);
+    assert_eq!(batches[0].start, 0);
+    assert_eq!(batches[0].qty, 9);
+    assert_eq!(batches[0].points.len(), 3);
+}
+
+#[test]
+fn test_large_gap_starts_new_block() {
+    let mut planner = BlockPlanner::new(120, 10);
+    let batches = planner.plan(&[point(0, 1), point(11, 1), point(30, 2)]);
+
+    let spans: Vec<(u16, u16)> = batches.iter().map(|b| (b.start, b.qty)).collect();
+    assert_eq!(spans, vec![(0, 12), (30, 2)]);
+}
+
+#[test]
+fn test_respects_max_regs_per_request() {
+    let mut planner = BlockPlanner::new(10, 10);
+    let points: Vec<RegPoint> = (0..25).map(|addr| point(addr, 1)).collect();
+    let batches = planner.plan(&points);
+
+    assert_eq!(batches.len(), 3);
+    assert!(batches.iter().all(|b| b.qty <= 10));
+    assert_eq!(batches.iter().map(|b| b.points.len()).sum::<usize>(), 25);
+
+    // 跨越边界的多寄存器点位不会被截断
+    let batches = planner.plan(&[point(0, 2), point(8, 4)]);
+    assert_eq!(batches.len(), 2);
+    assert_eq!((batches[1].start, batches[1].qty), (8, 4));
+}
+
+#[test]
+fn test_function_codes_are_planned_separately() {
+    let mut planner = BlockPlanner::new(120, 10);
+    let batches = planner.plan(&[
+        point_with_func(FunctionCode::ReadInputRegisters, 0, 1),
+        point(0, 1),
+        point_with_func(FunctionCode::ReadInputRegisters, 1, 1),
+        point_with_func(FunctionCode::WriteSingleRegister, 2, 1),
+    ]);
+
+    assert_eq!(batches.len(), 2);
+    assert_eq!(batches[0].func, FunctionCode::ReadHoldingRegisters);
+    assert_eq!(batches[1].func, FunctionCode::ReadInputRegisters);
+    assert_eq!(batches[1].qty, 2);
+}
+
+#[test]
+fn test_bad_point_does_not_poison_neighbours() {
+    // 地址7未映射，且点位7本身不可读
+    let mapped = (0..20).filter(|addr| *addr != 7);
+    let mut device = FakeDevice::new(mapped);
+    let mut planner = BlockPlanner::new(120, 10);
+    let points = vec![point(0, 1), point(2, 2), point(7, 1), point(10, 1), point(12, 2)];
+
+    let batches = planner.plan(&points);
+    assert_eq!(batches.len(), 1);
+
+    let read = device.poll_cycle(&mut planner, &batches);
+    assert_eq!(read.len(), 4);
+    assert!(!read.contains("plc.7"));
+    assert!(planner.is_address_skipped(&FunctionCode::ReadHoldingRegisters, 7));
+
+    // 重新规划后不再读取坏地址，也不会跨越它合并
+    let batches = planner.plan(&points);
+    assert!(batches.iter().all(|b| b.points.iter().all(|p| p.addr != 7)));
+    device.requests = 0;
+    let read = device.poll_cycle(&mut planner, &batches);
+    assert_eq!(read.len(), 4);
+    assert_eq!(device.requests, batches.len());
+    assert!(batches.len() < 4);
+}
+
+#[test]
+fn test_unmapped_gap_is_not_bridged_again() {
+    // 点位本身都可读，但空隙中的地址5未映射
+    let mapped = [0u16, 1, 2, 8, 9];
+    let mut device = FakeDevice::new(mapped);
+    let mut planner = BlockPlanner::new(120, 10);
+    let points = vec![point(0, 1), point(2, 1), point(8, 2)];
+
+    let batches = planner.plan(&points);
+    assert_eq!(batches.len(), 1);
+    let read = device.poll_cycle(&mut planner, &batches);
+    assert_eq!(read.len(), 3);
+
+    let batches = planner.plan(&points);
+    let spans: Vec<(u16, u16)> = batches.iter().map(|b| (b.start, b.qty)).collect();
+    assert_eq!(spans, vec![(0, 3), (8, 2)]);
+
+    device.requests = 0;
+    assert_eq!(device.poll_cycle(&mut planner, &batches).len(), 3);
+    assert_eq!(device.requests, 2);
+}
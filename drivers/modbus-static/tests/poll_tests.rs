@@ -0,0 +1,110 @@
+//! Modbus/TCP 轮询质量码测试
+
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::time::timeout;
+use driver_manager::Driver;
+use endpoint_kit::EndpointFactory;
+use frame_bus::Filter;
+use modbus_static::{mbap, ModbusDriver};
+
+/// 不可读的寄存器地址
+const UNREADABLE: u16 = 5;
+
+/// 模拟TCP从站：读取范围覆盖`UNREADABLE`时返回非法数据地址异常，否则返回地址本身
+async fn spawn_device() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (mut tcp, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut header = [0u8; mbap::MBAP_HEADER_LEN];
+                while tcp.read_exact(&mut header).await.is_ok() {
+                    let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+                    let mut pdu = vec![0u8; len - 1];
+                    if tcp.read_exact(&mut pdu).await.is_err() {
+                        break;
+                    }
+                    let txn = u16::from_be_bytes([header[0], header[1]]);
+                    let addr = u16::from_be_bytes([pdu[1], pdu[2]]);
+                    let qty = u16::from_be_bytes([pdu[3], pdu[4]]);
+                    let resp = if (addr..addr + qty).contains(&UNREADABLE) {
+                        vec![pdu[0] | 0x80, mbap::ModbusException::ILLEGAL_DATA_ADDRESS]
+                    } else {
+                        let mut resp = vec![pdu[0], (qty * 2) as u8];
+                        for reg in addr..addr + qty {
+                            resp.extend_from_slice(&reg.to_be_bytes());
+                        }
+                        resp
+                    };
+                    if tcp.write_all(&mbap::encode_adu(txn, header[6], &resp)).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn test_oversized_point_rejected_at_init() {
+    let mut driver = ModbusDriver::new();
+    let err = driver.init(&serde_json::json!({
+        "unit_id": 1,
+        "polling": "1s",
+        "max_regs_per_req": 8,
+        "points": [{ "tag": "name", "address": 0, "datatype": "string", "len": 16 }],
+    })).await.unwrap_err();
+    assert!(err.to_string().contains("max_regs_per_req"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_unreadable_point_publishes_bad_quality() {
+    let wal_dir = std::env::temp_dir().join(format!("modbus-poll-tests-{}", std::process::id()));
+    let (tx, _rx) = frame_bus::init(1024, &wal_dir).unwrap();
+    let mut frames = frame_bus::subscribe(Filter::TagPrefix("poll.".into())).unwrap();
+
+    let port = spawn_device().await;
+    let handle = EndpointFactory::new()
+        .from_url(&format!("tcp://127.0.0.1:{}", port)).await
+        .unwrap();
+
+    let mut driver = ModbusDriver::new();
+    driver.init(&serde_json::json!({
+        "unit_id": 1,
+        "polling": "100ms",
+        "points": [
+            { "tag": "poll.good", "address": 0 },
+            { "tag": "poll.bad", "address": UNREADABLE },
+        ],
+    })).await.unwrap();
+    driver.connect(handle).await.unwrap();
+    let read_loop = tokio::spawn(async move { driver.read_loop(tx).await });
+
+    // 拆分定位到不可读地址的扫描与之后跳过该地址的扫描都发布坏质量帧
+    let mut good = 0;
+    let mut bad = 0;
+    timeout(Duration::from_secs(5), async {
+        while bad < 2 || good < 2 {
+            let frame = frames.recv().await.unwrap().into_data().unwrap();
+            match frame.tag.as_str() {
+                "poll.good" => {
+                    assert_eq!(frame.qos, 2);
+                    good += 1;
+                }
+                "poll.bad" => {
+                    assert_eq!(frame.qos, 0);
+                    assert!(frame.meta.contains_key("error"));
+                    bad += 1;
+                }
+                other => panic!("unexpected tag {}", other),
+            }
+        }
+    }).await.unwrap_or_else(|_| panic!("got {} good and {} bad frames", good, bad));
+
+    read_loop.abort();
+    let _ = std::fs::remove_dir_all(&wal_dir);
+}
@@ -0,0 +1,221 @@
+//! Modbus RTU串口链路与驱动端到端测试 (使用pty对模拟RS-485从站)
+
+#![cfg(unix)]
+
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_modbus::prelude::Reader;
+use tokio_serial::{SerialPort, SerialStream};
+use driver_manager::Driver;
+use endpoint_kit::EndpointFactory;
+use frame_bus::{DataFrame, Filter, FrameReceiver};
+use modbus_static::ModbusDriver;
+
+/// Modbus CRC16 (多项式0xA001，初值0xFFFF)
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xA001 } else { crc >> 1 };
+        }
+    }
+    crc
+}
+
+fn with_crc(mut frame: Vec<u8>) -> Vec<u8> {
+    let crc = crc16(&frame);
+    frame.extend_from_slice(&crc.to_le_bytes());
+    frame
+}
+
+/// 模拟从站：应答一次读保持寄存器请求
+async fn serve_once(port: &mut SerialStream, regs: &[u16], corrupt: bool) {
+    let mut req = [0u8; 8];
+    port.read_exact(&mut req).await.unwrap();
+    assert_eq!(crc16(&req[..6]).to_le_bytes(), [req[6], req[7]], "request CRC mismatch");
+    assert_eq!(req[1], 0x03);
+
+    let mut resp = vec![req[0], 0x03, (regs.len() * 2) as u8];
+    for reg in regs {
+        resp.extend_from_slice(&reg.to_be_bytes());
+    }
+    let mut resp = with_crc(resp);
+    if corrupt {
+        let last = resp.len() - 1;
+        resp[last] ^= 0xFF;
+    }
+    port.write_all(&resp).await.unwrap();
+}
+
+fn open_pty() -> (SerialStream, String) {
+    let (master, slave) = SerialStream::pair().expect("Failed to open pty pair");
+    let path = slave.name().expect("pty slave has no name");
+    drop(slave);
+    (master, path)
+}
+
+#[tokio::test]
+async fn test_rtu_read_holding_registers() {
+    let (mut slave, path) = open_pty();
+    let server = tokio::spawn(async move {
+        serve_once(&mut slave, &[0x1234, 0x5678], false).await;
+        // 保持主端打开，关闭pty主端会丢弃未读数据
+        slave
+    });
+
+    let handle = EndpointFactory::new()
+        .from_url(&format!("serial://{}?baud=9600&parity=even", path)).await
+        .unwrap();
+    let transport = handle.open().await.unwrap();
+    let mut ctx = tokio_modbus::client::rtu::attach_slave(
+        DebugBox(transport),
+        tokio_modbus::Slave(7),
+    );
+
+    let regs = ctx.read_holding_registers(0, 2).await.unwrap().unwrap();
+    assert_eq!(regs, vec![0x1234, 0x5678]);
+    let _slave = server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_rtu_rejects_bad_crc() {
+    let (mut slave, path) = open_pty();
+    let server = tokio::spawn(async move {
+        serve_once(&mut slave, &[0x0001], true).await;
+        slave
+    });
+
+    let handle = EndpointFactory::new()
+        .from_url(&format!("serial://{}?baud=9600", path)).await
+        .unwrap();
+    let transport = handle.open().await.unwrap();
+    let mut ctx = tokio_modbus::client::rtu::attach_slave(
+        DebugBox(transport),
+        tokio_modbus::Slave(1),
+    );
+
+    // CRC错误的应答帧必须被丢弃，请求只能超时或报错
+    let result = tokio::time::timeout(Duration::from_millis(300), ctx.read_holding_registers(0, 1)).await;
+    assert!(!matches!(result, Ok(Ok(Ok(_)))));
+    let _slave = server.await.unwrap();
+}
+
+/// 本进程打开`path`的文件描述符数
+#[cfg(target_os = "linux")]
+fn open_fds(path: &str) -> usize {
+    std::fs::read_dir("/proc/self/fd").unwrap()
+        .filter_map(|entry| std::fs::read_link(entry.ok()?.path()).ok())
+        .filter(|target| target.as_os_str() == path)
+        .count()
+}
+
+async fn next_frame(frames: &mut FrameReceiver) -> DataFrame {
+    tokio::time::timeout(Duration::from_secs(5), frames.recv()).await
+        .expect("no frame from driver").unwrap()
+        .into_data().unwrap()
+}
+
+#[tokio::test]
+async fn test_driver_polls_over_rtu() {
+    let wal_dir = std::env::temp_dir().join(format!("modbus-rtu-tests-{}", std::process::id()));
+    let (tx, _rx) = frame_bus::init(1024, &wal_dir).unwrap();
+    let mut frames = frame_bus::subscribe(Filter::TagPrefix("rtu.".into())).unwrap();
+
+    // 模拟从站：第二个请求不应答，其余请求返回地址本身
+    let (mut slave, path) = open_pty();
+    tokio::spawn(async move {
+        let mut served = 0;
+        loop {
+            let mut req = [0u8; 8];
+            if slave.read_exact(&mut req).await.is_err() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                continue;
+            }
+            assert_eq!(crc16(&req[..6]).to_le_bytes(), [req[6], req[7]], "request CRC mismatch");
+            served += 1;
+            if served == 2 {
+                continue;
+            }
+            let addr = u16::from_be_bytes([req[2], req[3]]);
+            let mut resp = vec![req[0], req[1], 2];
+            resp.extend_from_slice(&addr.to_be_bytes());
+            slave.write_all(&with_crc(resp)).await.unwrap();
+        }
+    });
+
+    let handle = EndpointFactory::new()
+        .from_url(&format!("serial://{}?baud=9600", path)).await
+        .unwrap();
+    let mut driver = ModbusDriver::new();
+    driver.init(&serde_json::json!({
+        "unit_id": 3,
+        "polling": "100ms",
+        "timeout": "200ms",
+        "retry": 0,
+        "points": [{ "tag": "rtu.level", "address": 7 }],
+    })).await.unwrap();
+    driver.connect(handle).await.unwrap();
+    let read_loop = tokio::spawn(async move { driver.read_loop(tx).await });
+
+    let frame = next_frame(&mut frames).await;
+    assert_eq!(frame.qos, 2);
+    assert_eq!(frame.value.and_then(|v| v.to_i64()), Some(7));
+    // 两次轮询之间串口保持打开
+    #[cfg(target_os = "linux")]
+    assert_eq!(open_fds(&path), 1);
+
+    // 从站无应答：发布坏质量帧，下次轮询重新打开串口后恢复
+    let frame = next_frame(&mut frames).await;
+    assert_eq!(frame.qos, 0);
+    let frame = next_frame(&mut frames).await;
+    assert_eq!(frame.qos, 2);
+    #[cfg(target_os = "linux")]
+    assert_eq!(open_fds(&path), 1);
+
+    read_loop.abort();
+    let _ = std::fs::remove_dir_all(&wal_dir);
+}
+
+/// tokio-modbus要求传输层实现Debug
+struct DebugBox(endpoint_kit::EndpointBox);
+
+impl std::fmt::Debug for DebugBox {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DebugBox")
+    }
+}
+
+impl tokio::io::AsyncRead for DebugBox {
+    fn poll_read(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.0.as_mut().poll_read(cx, buf)
+    }
+}
+
+impl tokio::io::AsyncWrite for DebugBox {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.0.as_mut().poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.0.as_mut().poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        self.0.as_mut().poll_shutdown(cx)
+    }
+}
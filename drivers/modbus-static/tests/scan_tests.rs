@@ -0,0 +1,113 @@
+//! 扫描等级调度测试
+
+use std::time::{Duration, Instant};
+use modbus_static::{BlockPlanner, ModbusCfg};
+use modbus_static::config::{Access, DataType, RegPoint, ScanClass};
+use modbus_static::scan::ScanScheduler;
+use tokio_modbus::FunctionCode;
+
+fn point(addr: u16, scan: ScanClass) -> RegPoint {
+    RegPoint {
+        tag: format!("plc.{}", addr),
+        func: FunctionCode::ReadHoldingRegisters,
+        addr,
+        len: 1,
+        datatype: DataType::Uint16,
+        scale: None,
+        unscale: None,
+        access: Access::R,
+        scan,
+    }
+}
+
+fn scheduler(points: &[RegPoint], now: Instant) -> ScanScheduler {
+    let cfg = ModbusCfg::default();
+    let mut planner = BlockPlanner::new(cfg.max_regs_per_req, cfg.max_gap_regs);
+    ScanScheduler::new(&cfg, &mut planner, points, now)
+}
+
+#[test]
+fn test_scan_rates_from_config() {
+    let cfg: ModbusCfg = serde_json::from_value(serde_json::json!({
+        "unit_id": 1,
+        "polling": "1s",
+        "scan_rates": { "fast": "200ms" }
+    })).unwrap();
+
+    assert_eq!(cfg.scan_interval(&ScanClass::Fast), Duration::from_millis(200));
+    assert_eq!(cfg.scan_interval(&ScanClass::Normal), Duration::from_secs(1));
+    assert_eq!(cfg.scan_interval(&ScanClass::Slow), Duration::from_secs(10));
+    assert_eq!(cfg.scan_interval(&ScanClass::Interval(Duration::from_secs(3))), Duration::from_secs(3));
+    assert_eq!(ScanClass::Interval(Duration::from_millis(1500)).label(), "1s 500ms");
+}
+
+#[test]
+fn test_zero_polling_rejected() {
+    for cfg in [
+        serde_json::json!({ "unit_id": 1, "polling": "0s" }),
+        serde_json::json!({ "unit_id": 1, "polling": "1s", "scan_rates": { "fast": "0ms" } }),
+        serde_json::json!({ "unit_id": 1, "polling": "1s", "scan_rates": { "slow": "0s" } }),
+    ] {
+        let err = serde_json::from_value::<ModbusCfg>(cfg).unwrap_err();
+        assert!(err.to_string().contains("greater than zero"), "{}", err);
+    }
+}
+
+#[test]
+fn test_points_grouped_by_scan_class() {
+    // 相邻地址属于不同扫描等级时不会合并到同一批次
+    let points = vec![
+        point(0, ScanClass::Fast),
+        point(1, ScanClass::Slow),
+        point(2, ScanClass::Fast),
+        point(3, ScanClass::Interval(Duration::from_secs(5))),
+    ];
+    let scheduler = scheduler(&points, Instant::now());
+
+    let groups = scheduler.groups();
+    assert_eq!(groups.len(), 3);
+    let fast = groups.iter().find(|g| g.class == ScanClass::Fast).unwrap();
+    assert_eq!(fast.batches.len(), 1);
+    assert_eq!((fast.batches[0].start, fast.batches[0].qty), (0, 3));
+    assert_eq!(fast.batches[0].points.len(), 2);
+}
+
+#[test]
+fn test_each_class_runs_on_its_own_timer() {
+    let start = Instant::now();
+    let points = vec![point(0, ScanClass::Fast), point(10, ScanClass::Normal), point(20, ScanClass::Slow)];
+    let mut scheduler = scheduler(&points, start);
+
+    // 模拟2秒：每次扫描耗时10ms
+    let mut now = start;
+    let mut runs = std::collections::HashMap::new();
+    while let Some((index, due)) = scheduler.next_due() {
+        now = now.max(due);
+        if now >= start + Duration::from_secs(2) {
+            break;
+        }
+        now += Duration::from_millis(10);
+        assert!(!scheduler.complete(index, now));
+        *runs.entry(scheduler.groups()[index].class.clone()).or_insert(0) += 1;
+    }
+
+    assert_eq!(runs[&ScanClass::Fast], 20);
+    assert_eq!(runs[&ScanClass::Normal], 2);
+    assert_eq!(runs[&ScanClass::Slow], 1);
+}
+
+#[test]
+fn test_overrun_detection() {
+    let start = Instant::now();
+    let mut scheduler = scheduler(&[point(0, ScanClass::Fast)], start);
+    let (index, _) = scheduler.next_due().unwrap();
+
+    // 扫描耗时超过100ms间隔：记为超限并立即重新调度
+    let finished = start + Duration::from_millis(250);
+    assert!(scheduler.complete(index, finished));
+    assert_eq!(scheduler.next_due().unwrap().1, finished);
+
+    // 按时完成：截止时间按固定间隔推进
+    assert!(!scheduler.complete(index, finished + Duration::from_millis(20)));
+    assert_eq!(scheduler.next_due().unwrap().1, finished + Duration::from_millis(100));
+}
@@ -0,0 +1,96 @@
+//! Modbus/TCP Security 联调测试 (使用endpoint-kit测试CA签发的证书)
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_modbus::prelude::*;
+use tokio_rustls::TlsAcceptor;
+use rustls::{Certificate, PrivateKey, ServerConfig};
+use endpoint_kit::EndpointFactory;
+use modbus_static::{driver, mbap};
+
+fn fixture(name: &str) -> String {
+    format!("{}/../../core/endpoint-kit/tests/fixtures/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+fn load_certs(name: &str) -> Vec<Certificate> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(fixture(name)).unwrap());
+    rustls_pemfile::certs(&mut reader).unwrap().into_iter().map(Certificate).collect()
+}
+
+fn load_key(name: &str) -> PrivateKey {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(fixture(name)).unwrap());
+    PrivateKey(rustls_pemfile::pkcs8_private_keys(&mut reader).unwrap().remove(0))
+}
+
+/// 模拟TLS从站：应答读保持寄存器请求
+async fn spawn_tls_device(regs: Vec<u16>) -> u16 {
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(load_certs("server.pem"), load_key("server.key"))
+        .unwrap();
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        let (tcp, _) = listener.accept().await.unwrap();
+        let mut tls = acceptor.accept(tcp).await.unwrap();
+        let mut req = [0u8; 12];
+        while tls.read_exact(&mut req).await.is_ok() {
+            let txn = u16::from_be_bytes([req[0], req[1]]);
+            let qty = u16::from_be_bytes([req[10], req[11]]) as usize;
+            let mut pdu = vec![req[7], (qty * 2) as u8];
+            for reg in regs.iter().take(qty) {
+                pdu.extend_from_slice(&reg.to_be_bytes());
+            }
+            if tls.write_all(&mbap::encode_adu(txn, req[6], &pdu)).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    port
+}
+
+#[tokio::test]
+async fn test_tls_endpoint_reads_over_tls() {
+    let port = spawn_tls_device(vec![0x1234, 0x5678]).await;
+    let url = format!("tls://127.0.0.1:{}?ca={}&sni=plc.test", port, fixture("ca.pem"));
+    let endpoint = EndpointFactory::new().from_url(&url).await.unwrap();
+
+    let mut ctx = driver::connect(&endpoint, 1).await.unwrap();
+    let regs = tokio::time::timeout(Duration::from_secs(5), ctx.read_holding_registers(0, 2))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(regs.unwrap(), vec![0x1234, 0x5678]);
+}
+
+#[tokio::test]
+async fn test_tls_endpoint_fails_against_plaintext_listener() {
+    // 明文从站：收到的首个报文原样记录，并回送一个明文Modbus应答
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let (first_tx, first_rx) = tokio::sync::oneshot::channel();
+    tokio::spawn(async move {
+        let (mut tcp, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 512];
+        let n = tcp.read(&mut buf).await.unwrap_or(0);
+        let _ = first_tx.send(buf[..n].to_vec());
+        let _ = tcp.write_all(&mbap::encode_adu(1, 1, &[0x03, 0x02, 0x00, 0x01])).await;
+    });
+
+    let url = format!("tls://127.0.0.1:{}?ca={}&sni=plc.test", port, fixture("ca.pem"));
+    let endpoint = EndpointFactory::new().from_url(&url).await.unwrap();
+    let result = tokio::time::timeout(Duration::from_secs(5), driver::connect(&endpoint, 1))
+        .await
+        .unwrap();
+    assert!(result.is_err(), "handshake against plaintext listener must fail");
+
+    // 对端收到的只能是TLS ClientHello，不能是明文Modbus请求
+    let first = first_rx.await.unwrap();
+    assert_eq!(first.first(), Some(&0x16));
+}
@@ -0,0 +1,70 @@
+//! Modbus/UDP 帧与端点联调测试
+
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use endpoint_kit::EndpointFactory;
+use driver_manager::Driver;
+use modbus_static::{mbap, ModbusDriver};
+
+/// 模拟Modbus/UDP从站：先回放一个过期事务的应答，再返回正确应答
+async fn spawn_device(regs: Vec<u16>) -> u16 {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let port = socket.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let mut buf = [0u8; 260];
+        loop {
+            let (len, peer) = socket.recv_from(&mut buf).await.unwrap();
+            let req = &buf[..len];
+            let txn = u16::from_be_bytes([req[0], req[1]]);
+            let qty = u16::from_be_bytes([req[10], req[11]]) as usize;
+
+            let mut pdu = vec![req[7], (qty * 2) as u8];
+            for reg in regs.iter().take(qty) {
+                pdu.extend_from_slice(&reg.to_be_bytes());
+            }
+            let stale = mbap::encode_adu(txn.wrapping_sub(1), req[6], &pdu);
+            socket.send_to(&stale, peer).await.unwrap();
+            let resp = mbap::encode_adu(txn, req[6], &pdu);
+            socket.send_to(&resp, peer).await.unwrap();
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn test_modbus_udp_read_registers() {
+    let port = spawn_device(vec![0x0011, 0x0022, 0x0033]).await;
+    let handle = EndpointFactory::new()
+        .from_url(&format!("udp://127.0.0.1:{}", port)).await
+        .unwrap();
+    let mut socket = handle.open_datagram().await.unwrap();
+
+    for txn in 100..103u16 {
+        let adu = mbap::encode_adu(txn, 1, &mbap::read_registers_pdu(0x03, 0, 3));
+        let resp = socket.request(&adu, Duration::from_secs(1), |d| mbap::is_response_to(d, txn)).await.unwrap();
+        let pdu = mbap::decode_response(&resp, 1, 0x03).unwrap();
+        assert_eq!(mbap::parse_registers(pdu, 3).unwrap(), vec![0x0011, 0x0022, 0x0033]);
+    }
+    // 每次请求都收到一个过期事务的应答，且均被丢弃
+    assert!(socket.discarded() >= 2);
+}
+
+#[tokio::test]
+async fn test_read_tag_returns_scaled_value() {
+    let port = spawn_device(vec![215]).await;
+    let handle = EndpointFactory::new()
+        .from_url(&format!("udp://127.0.0.1:{}", port)).await
+        .unwrap();
+
+    let mut driver = ModbusDriver::new();
+    driver.init(&serde_json::json!({
+        "unit_id": 1,
+        "polling": "1s",
+        "points": [{ "tag": "temp", "address": 0, "scale": "value / 10.0" }],
+    })).await.unwrap();
+    driver.connect(handle).await.unwrap();
+
+    let value = driver.read_tag("temp").await.unwrap().unwrap();
+    assert_eq!(value.to_f64(), Some(21.5));
+    assert!(driver.read_tag("missing").await.is_err());
+}
@@ -0,0 +1,69 @@
+//! Modbus/TCP 写入联调测试
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use driver_manager::Driver;
+use endpoint_kit::EndpointFactory;
+use frame_bus::{CmdFrame, Value};
+use modbus_static::{mbap, ModbusDriver};
+
+/// 模拟TCP从站：地址0的写入返回非法数据地址异常，其余写入回显确认
+async fn spawn_device() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (mut tcp, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut header = [0u8; mbap::MBAP_HEADER_LEN];
+                while tcp.read_exact(&mut header).await.is_ok() {
+                    let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+                    let mut pdu = vec![0u8; len - 1];
+                    if tcp.read_exact(&mut pdu).await.is_err() {
+                        break;
+                    }
+                    let txn = u16::from_be_bytes([header[0], header[1]]);
+                    let addr = u16::from_be_bytes([pdu[1], pdu[2]]);
+                    let resp = if addr == 0 {
+                        vec![pdu[0] | 0x80, mbap::ModbusException::ILLEGAL_DATA_ADDRESS]
+                    } else {
+                        pdu[..5].to_vec()
+                    };
+                    if tcp.write_all(&mbap::encode_adu(txn, header[6], &resp)).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn test_write_exception_fails_command() {
+    let port = spawn_device().await;
+    let handle = EndpointFactory::new()
+        .from_url(&format!("tcp://127.0.0.1:{}", port)).await
+        .unwrap();
+
+    let mut driver = ModbusDriver::new();
+    driver.init(&serde_json::json!({
+        "unit_id": 1,
+        "polling": "1s",
+        "enable_write": true,
+        "points": [
+            { "tag": "missing", "address": 0, "access": "rw" },
+            { "tag": "setpoint", "address": 1, "access": "rw" },
+            { "tag": "total", "address": 2, "datatype": "float32", "access": "rw" },
+        ],
+    })).await.unwrap();
+    driver.connect(handle).await.unwrap();
+
+    let err = driver.write(CmdFrame::new("missing", Value::int(1), "test")).await.unwrap_err();
+    let exception = err.downcast_ref::<mbap::ModbusException>().expect("exception response must fail the write");
+    assert!(exception.is_illegal_address());
+    assert_eq!(exception.function, 0x06);
+
+    driver.write(CmdFrame::new("setpoint", Value::int(42), "test")).await.unwrap();
+    driver.write(CmdFrame::new("total", Value::float(1.5), "test")).await.unwrap();
+}
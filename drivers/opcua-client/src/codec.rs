@@ -0,0 +1,89 @@
+//! OPC-UA值与FrameBus值之间的转换
+
+use opcua::types::{DataValue, StatusCode, Variant, VariantTypeId};
+use frame_bus::envelope::value::Value as RawValue;
+use frame_bus::{DataFrame, Value};
+
+/// OPC-UA状态码映射为帧质量：0=bad, 1=uncertain, 2=good
+pub fn status_to_qos(status: StatusCode) -> u32 {
+    if status.is_good() {
+        2
+    } else if status.is_uncertain() {
+        1
+    } else {
+        0
+    }
+}
+
+/// 标量Variant转换为FrameBus值，空值与不支持的类型返回None
+pub fn variant_to_value(variant: &Variant) -> Option<Value> {
+    Some(match variant {
+        Variant::Boolean(v) => Value::bool(*v),
+        Variant::SByte(v) => Value::int(*v as i64),
+        Variant::Byte(v) => Value::int(*v as i64),
+        Variant::Int16(v) => Value::int(*v as i64),
+        Variant::UInt16(v) => Value::int(*v as i64),
+        Variant::Int32(v) => Value::int(*v as i64),
+        Variant::UInt32(v) => Value::int(*v as i64),
+        Variant::Int64(v) => Value::int(*v),
+        Variant::UInt64(v) => Value::int(i64::try_from(*v).ok()?),
+        Variant::Float(v) => Value::float(*v as f64),
+        Variant::Double(v) => Value::float(*v),
+        Variant::String(v) => Value::string(v.value().clone()?),
+        Variant::LocalizedText(v) => Value::string(v.text.value().clone()?),
+        Variant::ByteString(v) => Value::bytes(v.value.clone()?),
+        // 与协议桥接一致，时间以Unix秒表示
+        Variant::DateTime(v) => Value::int(v.as_chrono().timestamp()),
+        Variant::StatusCode(v) => Value::int(v.bits() as i64),
+        Variant::Empty => return None,
+        other => Value::string(other.to_string()),
+    })
+}
+
+/// FrameBus值转换为写入节点的Variant
+///
+/// `target`为节点DataType对应的类型，按OPC-UA转换规则显式转换，无法转换时报错
+pub fn value_to_variant(value: &Value, target: Option<VariantTypeId>) -> anyhow::Result<Variant> {
+    let variant = match &value.value {
+        Some(RawValue::BoolV(v)) => Variant::Boolean(*v),
+        Some(RawValue::IntV(v)) => Variant::Int64(*v),
+        Some(RawValue::FloatV(v)) => Variant::Double(*v),
+        Some(RawValue::StrV(v)) => Variant::from(v.as_str()),
+        Some(RawValue::BinV(v)) => Variant::from(v.clone()),
+        None => return Err(anyhow::anyhow!("Empty value")),
+    };
+
+    let Some(target) = target else {
+        return Ok(variant);
+    };
+
+    let cast = variant.cast(target);
+    if cast == Variant::Empty {
+        return Err(anyhow::anyhow!("Cannot convert {:?} to {:?}", variant, target));
+    }
+    Ok(cast)
+}
+
+/// 监控项数据变化转换为数据帧，使用源时间戳
+pub fn data_value_to_frame(tag: &str, data_value: &DataValue) -> DataFrame {
+    let status = data_value.status.unwrap_or(StatusCode::Good);
+    let value = data_value.value.as_ref().and_then(variant_to_value);
+
+    let mut frame = match value {
+        Some(value) => DataFrame::new(tag, value).with_qos(status_to_qos(status)),
+        None => DataFrame::new(tag, Value::int(0)).with_qos(0),
+    };
+    if !status.is_good() {
+        frame = frame.with_meta("status", status.to_string());
+    }
+
+    let timestamp = data_value.source_timestamp.as_ref()
+        .or(data_value.server_timestamp.as_ref())
+        .and_then(|t| t.as_chrono().timestamp_nanos_opt())
+        .and_then(|ns| u64::try_from(ns).ok());
+    if let Some(timestamp) = timestamp {
+        frame.timestamp = timestamp;
+    }
+
+    frame.with_meta("driver", "opcua-client")
+}
@@ -0,0 +1,208 @@
+//! OPC-UA客户端驱动配置
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// OPC-UA客户端驱动配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct OpcUaCfg {
+    /// 完整端点URL (`opc.tcp://host:port/path`)，为空时由端点主机/端口与`endpoint_path`拼接
+    #[serde(default)]
+    pub endpoint_url: Option<String>,
+    /// 端点路径
+    #[serde(default)]
+    pub endpoint_path: String,
+    /// 安全策略 (None, Basic256Sha256, Aes128Sha256RsaOaep...)
+    #[serde(default = "OpcUaCfg::default_security_policy")]
+    pub security_policy: String,
+    /// 消息安全模式
+    #[serde(default)]
+    pub security_mode: SecurityMode,
+    /// 用户名认证，为空时匿名登录
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+    /// 客户端证书目录
+    #[serde(default = "OpcUaCfg::default_pki_dir")]
+    pub pki_dir: PathBuf,
+    /// 受信任的服务器证书 (DER或PEM)，连接前装入`pki_dir/trusted`
+    #[serde(default)]
+    pub trusted_certs: Vec<PathBuf>,
+    /// 信任任意服务器证书，仅用于调试；默认拒绝不在信任列表中的证书
+    #[serde(default)]
+    pub trust_unknown_certs: bool,
+    /// 客户端证书，相对`pki_dir`，缺省为`own/cert.der`
+    #[serde(default)]
+    pub certificate_path: Option<PathBuf>,
+    /// 客户端私钥，相对`pki_dir`，缺省为`private/private.pem`
+    #[serde(default)]
+    pub private_key_path: Option<PathBuf>,
+    /// 证书缺失时生成自签名客户端证书
+    #[serde(default)]
+    pub create_sample_keypair: bool,
+    /// 默认发布间隔
+    #[serde(default = "OpcUaCfg::default_publishing_interval", with = "humantime_serde")]
+    pub publishing_interval: Duration,
+    /// 默认采样间隔
+    #[serde(default = "OpcUaCfg::default_sampling_interval", with = "humantime_serde")]
+    pub sampling_interval: Duration,
+    /// 默认监控项队列长度
+    #[serde(default = "OpcUaCfg::default_queue_size")]
+    pub queue_size: u32,
+    /// 会话重连间隔
+    #[serde(default = "OpcUaCfg::default_reconnect_interval", with = "humantime_serde")]
+    pub reconnect_interval: Duration,
+    /// 会话内重连次数，超过后重建会话 (重新浏览与订阅)
+    #[serde(default = "OpcUaCfg::default_retry")]
+    pub retry: u32,
+    /// 是否启用写入
+    #[serde(default)]
+    pub enable_write: bool,
+    /// 显式配置的点位
+    #[serde(default)]
+    pub tags: Vec<TagCfg>,
+    /// 浏览发现点位
+    #[serde(default)]
+    pub browse: Option<BrowseCfg>,
+}
+
+/// 消息安全模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SecurityMode {
+    #[default]
+    None,
+    Sign,
+    SignAndEncrypt,
+}
+
+/// 点位配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TagCfg {
+    /// 网关内点位名
+    pub tag: String,
+    /// 节点ID，例如`ns=2;s=Boiler.Temperature`
+    pub node_id: String,
+    /// 访问权限
+    #[serde(default)]
+    pub access: Access,
+    /// 采样间隔，缺省使用驱动默认值
+    #[serde(default, with = "humantime_serde")]
+    pub sampling_interval: Option<Duration>,
+    /// 发布间隔，相同发布间隔的点位共用一个订阅
+    #[serde(default, with = "humantime_serde")]
+    pub publishing_interval: Option<Duration>,
+    /// 监控项队列长度
+    #[serde(default)]
+    pub queue_size: Option<u32>,
+}
+
+/// 浏览发现配置
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BrowseCfg {
+    /// 浏览起点，默认Objects文件夹
+    #[serde(default = "BrowseCfg::default_root")]
+    pub root: String,
+    /// 点位名前缀，点位名为前缀加上相对浏览路径 (以`.`连接)
+    #[serde(default)]
+    pub tag_prefix: String,
+    /// 最大浏览深度
+    #[serde(default = "BrowseCfg::default_max_depth")]
+    pub max_depth: usize,
+    /// 最多发现的变量数
+    #[serde(default = "BrowseCfg::default_max_tags")]
+    pub max_tags: usize,
+}
+
+/// 访问权限
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Access {
+    #[default]
+    R,  // 只读
+    W,  // 只写
+    RW, // 读写
+}
+
+impl Access {
+    pub fn is_readable(&self) -> bool {
+        matches!(self, Access::R | Access::RW)
+    }
+
+    pub fn is_writable(&self) -> bool {
+        matches!(self, Access::W | Access::RW)
+    }
+}
+
+impl OpcUaCfg {
+    fn default_security_policy() -> String {
+        "None".to_string()
+    }
+
+    fn default_pki_dir() -> PathBuf {
+        PathBuf::from("./pki/opcua-client")
+    }
+
+    fn default_publishing_interval() -> Duration {
+        Duration::from_secs(1)
+    }
+
+    fn default_sampling_interval() -> Duration {
+        Duration::from_millis(500)
+    }
+
+    fn default_queue_size() -> u32 {
+        1
+    }
+
+    fn default_reconnect_interval() -> Duration {
+        Duration::from_secs(5)
+    }
+
+    fn default_retry() -> u32 {
+        3
+    }
+}
+
+impl Default for OpcUaCfg {
+    fn default() -> Self {
+        Self {
+            endpoint_url: None,
+            endpoint_path: String::new(),
+            security_policy: Self::default_security_policy(),
+            security_mode: SecurityMode::None,
+            username: None,
+            password: None,
+            pki_dir: Self::default_pki_dir(),
+            trusted_certs: Vec::new(),
+            trust_unknown_certs: false,
+            certificate_path: None,
+            private_key_path: None,
+            create_sample_keypair: false,
+            publishing_interval: Self::default_publishing_interval(),
+            sampling_interval: Self::default_sampling_interval(),
+            queue_size: Self::default_queue_size(),
+            reconnect_interval: Self::default_reconnect_interval(),
+            retry: Self::default_retry(),
+            enable_write: false,
+            tags: Vec::new(),
+            browse: None,
+        }
+    }
+}
+
+impl BrowseCfg {
+    fn default_root() -> String {
+        "i=85".to_string()
+    }
+
+    fn default_max_depth() -> usize {
+        4
+    }
+
+    fn default_max_tags() -> usize {
+        10_000
+    }
+}
@@ -0,0 +1,666 @@
+//! OPC-UA客户端驱动实现
+//!
+//! opcua客户端为阻塞API并自带运行时，所有会话调用都放在`spawn_blocking`中执行
+
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use opcua::client::prelude::{
+    AttributeId, BrowseDescription, BrowseDirection, BrowseResultMask, Client, ClientBuilder,
+    ConnectionStatusCallback, DataChangeCallback, ExtensionObject, IdentityToken,
+    MessageSecurityMode, MonitoredItemCreateRequest, MonitoringMode, MonitoringParameters,
+    NodeClass, NodeId, QualifiedName, ReadValueId, ReferenceTypeId, SecurityPolicy, Session,
+    SessionCommand, StatusCode, TimestampsToReturn, UAString, Variant, VariantTypeId, WriteValue,
+};
+use opcua::client::prelude::{AttributeService, MonitoredItemService, SubscriptionService, ViewService};
+use opcua::crypto::CertificateStore;
+use opcua::sync::RwLock;
+use serde_json::Value;
+use tokio::sync::oneshot;
+use tokio::time::sleep;
+
+use driver_manager::{Driver, DriverMeta};
+use endpoint_kit::EndpointHandle;
+use frame_bus::{CmdFrame, DataFrame, FramePublisher, FrameSender};
+
+use crate::codec::{data_value_to_frame, value_to_variant, variant_to_value};
+use crate::config::{Access, BrowseCfg, OpcUaCfg, SecurityMode, TagCfg};
+use crate::metrics::METRICS;
+
+/// 驱动名称，也是发布到FrameBus时的ACL主体
+pub const DRIVER_NAME: &str = "opcua-client";
+
+/// OPC-UA默认端口
+const DEFAULT_PORT: u16 = 4840;
+/// 会话轮询循环空闲时的休眠间隔 (毫秒)
+const SESSION_POLL_MS: u64 = 10;
+/// AccessLevel属性位
+const ACCESS_LEVEL_READ: u8 = 0x01;
+const ACCESS_LEVEL_WRITE: u8 = 0x02;
+
+/// 解析后的点位
+#[derive(Debug, Clone)]
+pub struct OpcTag {
+    pub tag: String,
+    pub node_id: NodeId,
+    pub access: Access,
+    pub sampling_interval: Duration,
+    pub publishing_interval: Duration,
+    pub queue_size: u32,
+}
+
+impl OpcTag {
+    /// 按驱动默认值补全点位配置
+    pub fn from_cfg(tag: &TagCfg, cfg: &OpcUaCfg) -> anyhow::Result<Self> {
+        let node_id = NodeId::from_str(&tag.node_id)
+            .map_err(|_| anyhow::anyhow!("Invalid node id '{}' for tag '{}'", tag.node_id, tag.tag))?;
+
+        Ok(Self {
+            tag: tag.tag.clone(),
+            node_id,
+            access: tag.access,
+            sampling_interval: tag.sampling_interval.unwrap_or(cfg.sampling_interval),
+            publishing_interval: tag.publishing_interval.unwrap_or(cfg.publishing_interval),
+            queue_size: tag.queue_size.unwrap_or(cfg.queue_size).max(1),
+        })
+    }
+}
+
+/// 按发布间隔分组可读点位，每组对应一个订阅
+pub fn plan_subscriptions(tags: &[OpcTag]) -> BTreeMap<Duration, Vec<&OpcTag>> {
+    let mut groups: BTreeMap<Duration, Vec<&OpcTag>> = BTreeMap::new();
+    for tag in tags.iter().filter(|t| t.access.is_readable()) {
+        groups.entry(tag.publishing_interval).or_default().push(tag);
+    }
+    groups
+}
+
+/// 已建立的会话
+struct ActiveSession {
+    /// 保留客户端实例，会话使用其证书存储
+    _client: Client,
+    session: Arc<RwLock<Session>>,
+    /// 会话内的点位 (配置 + 浏览发现)
+    tags: Vec<OpcTag>,
+    /// 写入目标类型，来自节点DataType属性
+    write_types: HashMap<String, VariantTypeId>,
+}
+
+/// OPC-UA客户端驱动
+pub struct OpcUaDriver {
+    cfg: OpcUaCfg,
+    endpoint_url: Option<String>,
+    /// 显式配置的点位
+    configured: Vec<OpcTag>,
+    /// 当前点位 (会话建立后包含浏览发现的点位)
+    tags: Vec<OpcTag>,
+    tag_map: HashMap<String, usize>,
+    write_types: HashMap<String, VariantTypeId>,
+    session: Option<Arc<RwLock<Session>>>,
+    client: Option<Client>,
+    /// 会话轮询循环的停止信号
+    session_cmd: Option<oneshot::Sender<SessionCommand>>,
+}
+
+impl OpcUaDriver {
+    pub fn new() -> Self {
+        Self {
+            cfg: OpcUaCfg::default(),
+            endpoint_url: None,
+            configured: Vec::new(),
+            tags: Vec::new(),
+            tag_map: HashMap::new(),
+            write_types: HashMap::new(),
+            session: None,
+            client: None,
+            session_cmd: None,
+        }
+    }
+
+    /// 当前点位
+    pub fn tags(&self) -> &[OpcTag] {
+        &self.tags
+    }
+
+    fn set_tags(&mut self, tags: Vec<OpcTag>) {
+        self.tag_map = tags.iter().enumerate().map(|(i, t)| (t.tag.clone(), i)).collect();
+        self.tags = tags;
+    }
+
+    /// 建立会话：连接、浏览发现点位并读取写入类型
+    async fn establish(&mut self) -> anyhow::Result<Arc<RwLock<Session>>> {
+        self.close_session().await;
+
+        let url = self.endpoint_url.clone()
+            .ok_or_else(|| anyhow::anyhow!("Endpoint not set"))?;
+        let cfg = self.cfg.clone();
+        let configured = self.configured.clone();
+
+        let active = tokio::task::spawn_blocking(move || open_session(&cfg, &url, configured))
+            .await??;
+
+        let session = active.session.clone();
+        self.set_tags(active.tags);
+        self.write_types = active.write_types;
+        self.session = Some(session.clone());
+        self.client = Some(active._client);
+        Ok(session)
+    }
+
+    /// 停止会话轮询并断开连接
+    async fn close_session(&mut self) {
+        if let Some(cmd) = self.session_cmd.take() {
+            let _ = cmd.send(SessionCommand::Stop);
+        }
+        if let Some(session) = self.session.take() {
+            let _ = tokio::task::spawn_blocking(move || session.read().disconnect()).await;
+        }
+        self.client = None;
+        METRICS.monitored_items.set(0);
+    }
+}
+
+impl Default for OpcUaDriver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 解析安全策略名称，如"None"、"Basic256Sha256"
+fn security_policy(name: &str) -> anyhow::Result<SecurityPolicy> {
+    match SecurityPolicy::from_str(name) {
+        Ok(SecurityPolicy::Unknown) | Err(_) => Err(anyhow::anyhow!("Unknown security policy '{}'", name)),
+        Ok(policy) => Ok(policy),
+    }
+}
+
+/// 将配置的受信任服务器证书装入PKI目录的trusted列表
+fn install_trusted_certs(cfg: &OpcUaCfg) -> anyhow::Result<()> {
+    if cfg.trust_unknown_certs {
+        tracing::warn!("OPC-UA client trusts unknown server certificates, do not use in production");
+    }
+    if cfg.trusted_certs.is_empty() {
+        return Ok(());
+    }
+
+    let store = CertificateStore::new(&cfg.pki_dir);
+    store.ensure_pki_path()
+        .map_err(|e| anyhow::anyhow!("Failed to create PKI directory {}: {}", cfg.pki_dir.display(), e))?;
+    for path in &cfg.trusted_certs {
+        let cert = CertificateStore::read_cert(path)
+            .map_err(|e| anyhow::anyhow!("Invalid trusted certificate {}: {}", path.display(), e))?;
+        let der = cert.to_der()
+            .map_err(|e| anyhow::anyhow!("Invalid trusted certificate {}: {}", path.display(), e))?;
+        let file_name = CertificateStore::cert_file_name(&cert);
+        let target = store.trusted_certs_dir().join(&file_name);
+        std::fs::write(&target, der)
+            .map_err(|e| anyhow::anyhow!("Failed to install trusted certificate {}: {}", target.display(), e))?;
+        // 之前被拒绝的证书留在rejected目录会继续被视为不受信任
+        let rejected = store.rejected_certs_dir().join(&file_name);
+        if rejected.exists() {
+            std::fs::remove_file(&rejected)
+                .map_err(|e| anyhow::anyhow!("Failed to clear rejected certificate {}: {}", rejected.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// 连接端点并完成点位发现 (阻塞)
+fn open_session(cfg: &OpcUaCfg, url: &str, configured: Vec<OpcTag>) -> anyhow::Result<ActiveSession> {
+    let policy = security_policy(&cfg.security_policy)?;
+    let mode = match cfg.security_mode {
+        SecurityMode::None => MessageSecurityMode::None,
+        SecurityMode::Sign => MessageSecurityMode::Sign,
+        SecurityMode::SignAndEncrypt => MessageSecurityMode::SignAndEncrypt,
+    };
+    let identity = match &cfg.username {
+        Some(user) => IdentityToken::UserName(user.clone(), cfg.password.clone().unwrap_or_default()),
+        None => IdentityToken::Anonymous,
+    };
+
+    let mut builder = ClientBuilder::new()
+        .application_name("Industrial Gateway OPC-UA Client")
+        .application_uri("urn:gateway:opcua:client")
+        .product_uri("urn:gateway:opcua:client")
+        .create_sample_keypair(cfg.create_sample_keypair)
+        .trust_server_certs(cfg.trust_unknown_certs)
+        .pki_dir(cfg.pki_dir.clone())
+        .session_retry_limit(cfg.retry as i32)
+        .session_retry_interval(cfg.reconnect_interval.as_millis() as u32);
+    if let Some(path) = &cfg.certificate_path {
+        builder = builder.certificate_path(path.clone());
+    }
+    if let Some(path) = &cfg.private_key_path {
+        builder = builder.private_key_path(path.clone());
+    }
+    let mut client = builder.client()
+        .ok_or_else(|| anyhow::anyhow!("Invalid OPC-UA client configuration"))?;
+
+    let session = client.connect_to_endpoint((url, policy.to_str(), mode), identity)
+        .map_err(|status| anyhow::anyhow!("OPC-UA connect to {} failed: {}", url, status))?;
+
+    let (tags, write_types) = {
+        let session = session.read();
+        let mut tags = configured;
+        if let Some(browse) = &cfg.browse {
+            let discovered = browse_tags(&session, browse, cfg)?;
+            let known: HashSet<NodeId> = tags.iter().map(|t| t.node_id.clone()).collect();
+            let names: HashSet<String> = tags.iter().map(|t| t.tag.clone()).collect();
+            tags.extend(discovered.into_iter()
+                .filter(|t| !known.contains(&t.node_id) && !names.contains(&t.tag)));
+        }
+        let write_types = read_write_types(&session, &tags)?;
+        (tags, write_types)
+    };
+
+    tracing::info!("OPC-UA session established to {} with {} tags", url, tags.len());
+    Ok(ActiveSession { _client: client, session, tags, write_types })
+}
+
+/// 从浏览起点按层级引用发现变量节点 (阻塞)
+fn browse_tags(session: &Session, browse: &BrowseCfg, cfg: &OpcUaCfg) -> anyhow::Result<Vec<OpcTag>> {
+    let root = NodeId::from_str(&browse.root)
+        .map_err(|_| anyhow::anyhow!("Invalid browse root '{}'", browse.root))?;
+
+    let mut variables: Vec<(Vec<String>, NodeId)> = Vec::new();
+    let mut visited: HashSet<NodeId> = HashSet::from([root.clone()]);
+    let mut queue: VecDeque<(NodeId, Vec<String>)> = VecDeque::from([(root, Vec::new())]);
+
+    while let Some((node_id, path)) = queue.pop_front() {
+        for (name, child, class) in browse_children(session, node_id)? {
+            if !visited.insert(child.clone()) {
+                continue;
+            }
+            let mut child_path = path.clone();
+            child_path.push(name);
+
+            match class {
+                NodeClass::Variable => {
+                    if variables.len() >= browse.max_tags {
+                        tracing::warn!("OPC-UA browse stopped after {} tags", browse.max_tags);
+                        queue.clear();
+                        break;
+                    }
+                    variables.push((child_path, child));
+                }
+                NodeClass::Object if child_path.len() < browse.max_depth => {
+                    queue.push_back((child, child_path));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // 由AccessLevel属性确定访问权限
+    let reads: Vec<ReadValueId> = variables.iter()
+        .map(|(_, node_id)| read_value_id(node_id, AttributeId::AccessLevel))
+        .collect();
+    let levels = if reads.is_empty() {
+        Vec::new()
+    } else {
+        session.read(&reads, TimestampsToReturn::Neither, 0.0)
+            .map_err(|status| anyhow::anyhow!("Failed to read access levels: {}", status))?
+    };
+
+    let tags = variables.into_iter().zip(levels)
+        .filter_map(|((path, node_id), level)| {
+            let level = match level.value {
+                Some(Variant::Byte(level)) => level,
+                _ => ACCESS_LEVEL_READ,
+            };
+            let access = match (level & ACCESS_LEVEL_READ != 0, level & ACCESS_LEVEL_WRITE != 0) {
+                (true, true) => Access::RW,
+                (true, false) => Access::R,
+                (false, true) => Access::W,
+                (false, false) => return None,
+            };
+            Some(OpcTag {
+                tag: format!("{}{}", browse.tag_prefix, path.join(".")),
+                node_id,
+                access,
+                sampling_interval: cfg.sampling_interval,
+                publishing_interval: cfg.publishing_interval,
+                queue_size: cfg.queue_size.max(1),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    tracing::info!("OPC-UA browse of {} discovered {} tags", browse.root, tags.len());
+    Ok(tags)
+}
+
+/// 浏览单个节点的层级子节点，处理续传点
+fn browse_children(session: &Session, node_id: NodeId) -> anyhow::Result<Vec<(String, NodeId, NodeClass)>> {
+    let description = BrowseDescription {
+        node_id: node_id.clone(),
+        browse_direction: BrowseDirection::Forward,
+        reference_type_id: ReferenceTypeId::HierarchicalReferences.into(),
+        include_subtypes: true,
+        node_class_mask: NodeClass::Object as u32 | NodeClass::Variable as u32,
+        result_mask: BrowseResultMask::All as u32,
+    };
+
+    let mut children = Vec::new();
+    let mut results = session.browse(&[description])
+        .map_err(|status| anyhow::anyhow!("Browse of {} failed: {}", node_id, status))?
+        .unwrap_or_default();
+
+    while let Some(result) = results.pop() {
+        if result.status_code.is_bad() {
+            return Err(anyhow::anyhow!("Browse of {} failed: {}", node_id, result.status_code));
+        }
+        for reference in result.references.unwrap_or_default() {
+            // 忽略指向其他服务器的引用
+            if reference.node_id.server_index != 0 {
+                continue;
+            }
+            children.push((reference.browse_name.name.to_string(), reference.node_id.node_id, reference.node_class));
+        }
+        if !result.continuation_point.is_null() {
+            results = session.browse_next(false, &[result.continuation_point])
+                .map_err(|status| anyhow::anyhow!("Browse of {} failed: {}", node_id, status))?
+                .unwrap_or_default();
+        }
+    }
+
+    Ok(children)
+}
+
+/// 读取可写点位的DataType属性 (阻塞)
+fn read_write_types(session: &Session, tags: &[OpcTag]) -> anyhow::Result<HashMap<String, VariantTypeId>> {
+    let writable: Vec<&OpcTag> = tags.iter().filter(|t| t.access.is_writable()).collect();
+    if writable.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let reads: Vec<ReadValueId> = writable.iter()
+        .map(|t| read_value_id(&t.node_id, AttributeId::DataType))
+        .collect();
+    let values = session.read(&reads, TimestampsToReturn::Neither, 0.0)
+        .map_err(|status| anyhow::anyhow!("Failed to read data types: {}", status))?;
+
+    Ok(writable.into_iter().zip(values)
+        .filter_map(|(tag, value)| match value.value {
+            Some(Variant::NodeId(data_type)) => VariantTypeId::try_from(data_type.as_ref())
+                .ok()
+                .map(|t| (tag.tag.clone(), t)),
+            _ => None,
+        })
+        .collect())
+}
+
+fn read_value_id(node_id: &NodeId, attribute: AttributeId) -> ReadValueId {
+    ReadValueId {
+        node_id: node_id.clone(),
+        attribute_id: attribute as u32,
+        index_range: UAString::null(),
+        data_encoding: QualifiedName::null(),
+    }
+}
+
+/// 为每个发布间隔创建订阅并添加监控项 (阻塞)
+fn subscribe(session: &Session, tags: &[OpcTag], tx: &FrameSender) -> anyhow::Result<usize> {
+    let mut monitored = 0;
+
+    for (interval, group) in plan_subscriptions(tags) {
+        let mut node_tags: HashMap<NodeId, Vec<String>> = HashMap::new();
+        for tag in &group {
+            node_tags.entry(tag.node_id.clone()).or_default().push(tag.tag.clone());
+        }
+
+        let publisher = FramePublisher::new(tx.clone()).with_subject(DRIVER_NAME);
+        let callback = DataChangeCallback::new(move |items| {
+            let mut frames = Vec::new();
+            for item in items {
+                let Some(tags) = node_tags.get(&item.item_to_monitor().node_id) else { continue };
+                for value in item.values() {
+                    frames.extend(tags.iter().map(|tag| data_value_to_frame(tag, value)));
+                }
+            }
+            METRICS.data_change_total.inc_by(frames.len() as f64);
+            if let Err(e) = publisher.send_data_batch(frames) {
+                tracing::error!("Failed to publish OPC-UA data changes: {}", e);
+            }
+        });
+
+        let interval_ms = interval.as_secs_f64() * 1000.0;
+        let subscription_id = session.create_subscription(interval_ms, 30, 10, 0, 0, true, callback)
+            .map_err(|status| anyhow::anyhow!("Failed to create subscription ({:?}): {}", interval, status))?;
+
+        let requests: Vec<MonitoredItemCreateRequest> = group.iter()
+            .map(|tag| MonitoredItemCreateRequest::new(
+                read_value_id(&tag.node_id, AttributeId::Value),
+                MonitoringMode::Reporting,
+                MonitoringParameters {
+                    client_handle: 0,
+                    sampling_interval: tag.sampling_interval.as_secs_f64() * 1000.0,
+                    filter: ExtensionObject::null(),
+                    queue_size: tag.queue_size,
+                    discard_oldest: true,
+                },
+            ))
+            .collect();
+        let results = session.create_monitored_items(subscription_id, TimestampsToReturn::Both, &requests)
+            .map_err(|status| anyhow::anyhow!("Failed to create monitored items: {}", status))?;
+
+        for (tag, result) in group.iter().zip(&results) {
+            if result.status_code.is_good() {
+                monitored += 1;
+            } else {
+                tracing::warn!("Cannot monitor tag {} ({}): {}", tag.tag, tag.node_id, result.status_code);
+            }
+        }
+    }
+
+    Ok(monitored)
+}
+
+/// 连接断开时发布坏质量帧，恢复后由订阅重新推送当前值
+fn publish_disconnected(tx: &FrameSender, tags: &[OpcTag]) {
+    let frames: Vec<DataFrame> = tags.iter()
+        .filter(|t| t.access.is_readable())
+        .map(|t| DataFrame::new(&t.tag, frame_bus::Value::int(0))
+            .with_qos(0)
+            .with_meta("error", "disconnected")
+            .with_meta("driver", DRIVER_NAME))
+        .collect();
+    if let Err(e) = FramePublisher::new(tx.clone()).with_subject(DRIVER_NAME).send_data_batch(frames) {
+        tracing::error!("Failed to publish OPC-UA disconnect frames: {}", e);
+    }
+}
+
+#[async_trait]
+impl Driver for OpcUaDriver {
+    fn meta(&self) -> DriverMeta {
+        crate::meta()
+    }
+
+    async fn init(&mut self, cfg: &Value) -> anyhow::Result<()> {
+        self.cfg = serde_json::from_value(cfg.clone())?;
+
+        if self.cfg.tags.is_empty() && self.cfg.browse.is_none() {
+            return Err(anyhow::anyhow!("OPC-UA driver needs 'tags' or 'browse'"));
+        }
+        security_policy(&self.cfg.security_policy)?;
+        install_trusted_certs(&self.cfg)?;
+        if let Some(url) = &self.cfg.endpoint_url {
+            if !url.starts_with("opc.tcp://") {
+                return Err(anyhow::anyhow!("Invalid OPC-UA endpoint url '{}'", url));
+            }
+        }
+
+        let mut configured = Vec::with_capacity(self.cfg.tags.len());
+        let mut names = HashSet::new();
+        for tag in &self.cfg.tags {
+            if !names.insert(tag.tag.clone()) {
+                return Err(anyhow::anyhow!("Duplicate tag '{}'", tag.tag));
+            }
+            configured.push(OpcTag::from_cfg(tag, &self.cfg)?);
+        }
+
+        self.endpoint_url = self.cfg.endpoint_url.clone();
+        self.configured = configured.clone();
+        self.set_tags(configured);
+
+        tracing::info!("OPC-UA driver initialized with {} configured tags (browse: {})",
+                      self.configured.len(), self.cfg.browse.is_some());
+        Ok(())
+    }
+
+    async fn connect(&mut self, endpoint: Arc<EndpointHandle>) -> anyhow::Result<()> {
+        // 显式URL优先，否则由端点主机/端口拼接
+        if self.cfg.endpoint_url.is_none() {
+            self.endpoint_url = Some(format!(
+                "opc.tcp://{}:{}{}",
+                endpoint.host(),
+                endpoint.port().unwrap_or(DEFAULT_PORT),
+                self.cfg.endpoint_path
+            ));
+        }
+        tracing::info!("OPC-UA driver using endpoint {}", self.endpoint_url.as_deref().unwrap_or_default());
+        Ok(())
+    }
+
+    async fn read_loop(&mut self, tx: FrameSender) -> anyhow::Result<()> {
+        tracing::info!("Starting OPC-UA read loop");
+        let mut first = true;
+
+        loop {
+            if !first {
+                METRICS.reconnect_total.inc();
+            }
+            first = false;
+
+            let session = match self.establish().await {
+                Ok(session) => session,
+                Err(e) => {
+                    tracing::warn!("OPC-UA session failed: {}", e);
+                    METRICS.session_error_total.inc();
+                    sleep(self.cfg.reconnect_interval).await;
+                    continue;
+                }
+            };
+
+            let subscribed = {
+                let session = session.clone();
+                let tags = self.tags.clone();
+                let tx = tx.clone();
+                tokio::task::spawn_blocking(move || {
+                    let disconnected_tags = tags.clone();
+                    let disconnected_tx = tx.clone();
+                    session.write().set_connection_status_callback(ConnectionStatusCallback::new(move |connected| {
+                        if connected {
+                            tracing::info!("OPC-UA session reconnected");
+                            METRICS.reconnect_total.inc();
+                        } else {
+                            tracing::warn!("OPC-UA connection lost");
+                            publish_disconnected(&disconnected_tx, &disconnected_tags);
+                        }
+                    }));
+                    subscribe(&session.read(), &tags, &tx)
+                }).await?
+            };
+
+            match subscribed {
+                Ok(monitored) => {
+                    METRICS.monitored_items.set(monitored as i64);
+                    tracing::info!("OPC-UA monitoring {} items", monitored);
+
+                    // 会话轮询循环负责发布响应处理、断线重连与订阅转移，放弃重连后才返回
+                    let (cmd_tx, cmd_rx) = oneshot::channel();
+                    self.session_cmd = Some(cmd_tx);
+                    let run = tokio::task::spawn_blocking(move || {
+                        Session::run_loop(session, SESSION_POLL_MS, cmd_rx)
+                    });
+                    let _ = run.await;
+                    tracing::warn!("OPC-UA session gave up reconnecting, rebuilding session");
+                }
+                Err(e) => {
+                    tracing::warn!("OPC-UA subscription failed: {}", e);
+                    METRICS.session_error_total.inc();
+                }
+            }
+
+            publish_disconnected(&tx, &self.tags);
+            self.close_session().await;
+            sleep(self.cfg.reconnect_interval).await;
+        }
+    }
+
+    async fn write(&mut self, cmd: CmdFrame) -> anyhow::Result<()> {
+        if !self.cfg.enable_write {
+            return Err(anyhow::anyhow!("Write not enabled"));
+        }
+
+        // 读循环未运行时按需建立会话
+        let session = match &self.session {
+            Some(session) => session.clone(),
+            None => self.establish().await?,
+        };
+
+        let tag = self.tag_map.get(&cmd.tag)
+            .map(|&i| self.tags[i].clone())
+            .ok_or_else(|| anyhow::anyhow!("Tag '{}' not found", cmd.tag))?;
+        if !tag.access.is_writable() {
+            return Err(anyhow::anyhow!("Tag '{}' is not writable", cmd.tag));
+        }
+
+        let value = cmd.value.ok_or_else(|| anyhow::anyhow!("No value in command"))?;
+        let variant = value_to_variant(&value, self.write_types.get(&tag.tag).copied())?;
+
+        let node_id = tag.node_id.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            session.read().write(&[WriteValue {
+                node_id,
+                attribute_id: AttributeId::Value as u32,
+                index_range: UAString::null(),
+                value: variant.into(),
+            }])
+        }).await?;
+
+        let status = result
+            .map(|statuses| statuses.first().copied().unwrap_or(StatusCode::BadUnexpectedError))
+            .unwrap_or_else(|status| status);
+        if !status.is_good() {
+            METRICS.write_error_total.inc();
+            return Err(anyhow::anyhow!("OPC-UA write to tag '{}' rejected: {}", cmd.tag, status));
+        }
+
+        METRICS.write_total.inc();
+        tracing::info!("Wrote value to tag {}: {:?}", cmd.tag, value);
+        Ok(())
+    }
+
+    async fn read_tag(&mut self, tag: &str) -> anyhow::Result<Option<frame_bus::Value>> {
+        let session = match &self.session {
+            Some(session) => session.clone(),
+            None => self.establish().await?,
+        };
+
+        let node_id = self.tag_map.get(tag)
+            .map(|&i| self.tags[i].node_id.clone())
+            .ok_or_else(|| anyhow::anyhow!("Tag '{}' not found", tag))?;
+
+        let values = tokio::task::spawn_blocking(move || {
+            session.read().read(&[read_value_id(&node_id, AttributeId::Value)], TimestampsToReturn::Neither, 0.0)
+        }).await?
+            .map_err(|status| anyhow::anyhow!("OPC-UA read of tag '{}' failed: {}", tag, status))?;
+
+        let data_value = values.into_iter().next()
+            .ok_or_else(|| anyhow::anyhow!("OPC-UA read of tag '{}' returned no value", tag))?;
+        let status = data_value.status.unwrap_or(StatusCode::Good);
+        if !status.is_good() {
+            return Err(anyhow::anyhow!("OPC-UA read of tag '{}' returned {}", tag, status));
+        }
+        Ok(data_value.value.as_ref().and_then(variant_to_value))
+    }
+
+    async fn shutdown(&mut self) -> anyhow::Result<()> {
+        tracing::info!("OPC-UA driver shutting down");
+        self.close_session().await;
+        Ok(())
+    }
+}
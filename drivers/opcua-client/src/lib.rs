@@ -0,0 +1,34 @@
+//! OPC-UA客户端静态驱动
+//!
+//! 基于opcua crate实现，支持浏览发现点位、监控项订阅 (按点位的采样/发布间隔)、
+//! 断线重连与订阅转移，以及通过`Driver::write`写入
+
+pub mod driver;
+pub mod config;
+pub mod codec;
+pub mod metrics;
+
+pub use driver::{OpcUaDriver, OpcTag};
+pub use config::OpcUaCfg;
+
+use driver_manager::{DriverMeta, DriverKind, Driver, register_static_driver};
+
+/// 创建OPC-UA驱动实例的工厂函数
+fn create_opcua_driver() -> Box<dyn Driver> {
+    Box::new(OpcUaDriver::new())
+}
+
+// 注册OPC-UA客户端驱动到静态驱动注册表
+register_static_driver!("opcua-client", create_opcua_driver);
+
+/// 获取驱动元信息
+pub fn meta() -> DriverMeta {
+    DriverMeta {
+        name: "opcua-client".to_string(),
+        kind: DriverKind::Static,
+        version: "0.1.0".to_string(),
+        api_version: 1,
+        description: "Static OPC-UA client driver with browse discovery and subscriptions".to_string(),
+        features: vec!["read".to_string(), "write".to_string(), "subscribe".to_string(), "browse".to_string()],
+    }
+}
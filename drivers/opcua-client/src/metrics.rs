@@ -0,0 +1,60 @@
+//! OPC-UA客户端驱动Prometheus指标
+
+use prometheus::{Counter, IntGauge, Opts};
+use once_cell::sync::Lazy;
+
+pub static METRICS: Lazy<OpcUaMetrics> = Lazy::new(OpcUaMetrics::new);
+
+pub struct OpcUaMetrics {
+    pub data_change_total: Counter,
+    pub write_total: Counter,
+    pub write_error_total: Counter,
+    pub reconnect_total: Counter,
+    pub session_error_total: Counter,
+    pub monitored_items: IntGauge,
+}
+
+impl OpcUaMetrics {
+    fn new() -> Self {
+        let registry = prometheus::default_registry();
+
+        let data_change_total = Counter::with_opts(
+            Opts::new("opcua_data_change_total", "Total data change notifications received")
+        ).unwrap();
+        registry.register(Box::new(data_change_total.clone())).unwrap();
+
+        let write_total = Counter::with_opts(
+            Opts::new("opcua_write_total", "Total successful OPC-UA writes")
+        ).unwrap();
+        registry.register(Box::new(write_total.clone())).unwrap();
+
+        let write_error_total = Counter::with_opts(
+            Opts::new("opcua_write_error_total", "Total failed or rejected OPC-UA writes")
+        ).unwrap();
+        registry.register(Box::new(write_error_total.clone())).unwrap();
+
+        let reconnect_total = Counter::with_opts(
+            Opts::new("opcua_reconnect_total", "Total OPC-UA session reconnections")
+        ).unwrap();
+        registry.register(Box::new(reconnect_total.clone())).unwrap();
+
+        let session_error_total = Counter::with_opts(
+            Opts::new("opcua_session_error_total", "Total failed attempts to establish an OPC-UA session")
+        ).unwrap();
+        registry.register(Box::new(session_error_total.clone())).unwrap();
+
+        let monitored_items = IntGauge::with_opts(
+            Opts::new("opcua_monitored_items", "Monitored items in the current OPC-UA session")
+        ).unwrap();
+        registry.register(Box::new(monitored_items.clone())).unwrap();
+
+        Self {
+            data_change_total,
+            write_total,
+            write_error_total,
+            reconnect_total,
+            session_error_total,
+            monitored_items,
+        }
+    }
+}
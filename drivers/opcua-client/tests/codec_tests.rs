@@ -0,0 +1,65 @@
+//! OPC-UA值转换测试
+
+use frame_bus::Value;
+use opcua::types::{DataValue, DateTime, LocalizedText, StatusCode, Variant, VariantTypeId};
+use opcua_client::codec::{data_value_to_frame, status_to_qos, value_to_variant, variant_to_value};
+
+#[test]
+fn test_status_to_qos() {
+    assert_eq!(status_to_qos(StatusCode::Good), 2);
+    assert_eq!(status_to_qos(StatusCode::UncertainLastUsableValue), 1);
+    assert_eq!(status_to_qos(StatusCode::BadNoCommunication), 0);
+    assert_eq!(status_to_qos(StatusCode::BadWaitingForInitialData), 0);
+}
+
+#[test]
+fn test_variant_to_value() {
+    assert_eq!(variant_to_value(&Variant::Boolean(true)).and_then(|v| v.to_bool()), Some(true));
+    assert_eq!(variant_to_value(&Variant::Int16(-5)).and_then(|v| v.to_i64()), Some(-5));
+    assert_eq!(variant_to_value(&Variant::UInt32(70000)).and_then(|v| v.to_i64()), Some(70000));
+    assert_eq!(variant_to_value(&Variant::Float(1.5)).and_then(|v| v.to_f64()), Some(1.5));
+    assert_eq!(variant_to_value(&Variant::from("run")).and_then(|v| v.to_string()), Some("run".to_string()));
+    assert_eq!(
+        variant_to_value(&Variant::LocalizedText(Box::new(LocalizedText::new("en", "open")))).and_then(|v| v.to_string()),
+        Some("open".to_string())
+    );
+    assert!(variant_to_value(&Variant::Empty).is_none());
+    // 超出i64范围的无符号值无法表示
+    assert!(variant_to_value(&Variant::UInt64(u64::MAX)).is_none());
+}
+
+#[test]
+fn test_value_to_variant_casts_to_node_type() {
+    assert_eq!(value_to_variant(&Value::int(42), Some(VariantTypeId::Int32)).unwrap(), Variant::Int32(42));
+    assert_eq!(value_to_variant(&Value::int(1), Some(VariantTypeId::Boolean)).unwrap(), Variant::Boolean(true));
+    assert_eq!(value_to_variant(&Value::float(2.5), Some(VariantTypeId::Float)).unwrap(), Variant::Float(2.5));
+    assert_eq!(value_to_variant(&Value::float(2.5), None).unwrap(), Variant::Double(2.5));
+
+    // 超出目标类型范围
+    assert!(value_to_variant(&Value::int(70000), Some(VariantTypeId::Int16)).is_err());
+}
+
+#[test]
+fn test_data_value_to_frame() {
+    let source = DateTime::now();
+    let mut data_value = DataValue::value_only(Variant::Double(21.5));
+    data_value.status = Some(StatusCode::Good);
+    data_value.source_timestamp = Some(source);
+
+    let frame = data_value_to_frame("line1.temperature", &data_value);
+    assert_eq!(frame.tag, "line1.temperature");
+    assert_eq!(frame.value.as_ref().and_then(|v| v.to_f64()), Some(21.5));
+    assert_eq!(frame.qos, 2);
+    assert_eq!(frame.timestamp, source.as_chrono().timestamp_nanos_opt().unwrap() as u64);
+    assert_eq!(frame.meta.get("driver").map(String::as_str), Some("opcua-client"));
+    assert!(!frame.meta.contains_key("status"));
+
+    data_value.status = Some(StatusCode::UncertainLastUsableValue);
+    let frame = data_value_to_frame("line1.temperature", &data_value);
+    assert_eq!(frame.qos, 1);
+    assert!(frame.meta.contains_key("status"));
+
+    // 无值的数据变化发布为坏质量
+    let frame = data_value_to_frame("line1.temperature", &DataValue::null());
+    assert_eq!(frame.qos, 0);
+}
@@ -0,0 +1,287 @@
+//! OPC-UA驱动端到端测试：进程内服务器验证浏览发现、订阅与写入
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use driver_manager::Driver;
+use frame_bus::{CmdFrame, DataFrame, FrameEnvelope, FrameKind, Value};
+use opcua::server::prelude::*;
+use opcua::sync::RwLock;
+use opcua_client::{OpcUaDriver, driver::plan_subscriptions};
+use serde_json::json;
+use tokio::sync::broadcast;
+
+/// 测试服务器：Line1文件夹下的只读温度、可写设定值与Pump子文件夹
+struct TestServer {
+    server: Arc<RwLock<Server>>,
+    namespace: u16,
+    url: String,
+}
+
+impl TestServer {
+    fn start(pki: &Path) -> Self {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let user_token_ids = vec![ANONYMOUS_USER_TOKEN_ID.to_string()];
+        let mut config = ServerBuilder::new()
+            .application_name("opcua-client-test-server")
+            .application_uri("urn:opcua-client:test:server")
+            .create_sample_keypair(true)
+            .pki_dir(pki)
+            .host_and_port("127.0.0.1", port)
+            .discovery_urls(vec!["/".to_string()])
+            .endpoint("none", ServerEndpoint::new_none("/", &user_token_ids))
+            .endpoint("secure", ServerEndpoint::new_basic256sha256_sign_encrypt("/", &user_token_ids))
+            .trust_client_certs()
+            .config();
+        config.limits.min_publishing_interval = 0.05;
+        config.limits.min_sampling_interval = 0.05;
+        let server = Server::new(config);
+
+        let namespace = {
+            let address_space = server.address_space();
+            let mut address_space = address_space.write();
+            let namespace = address_space.register_namespace("urn:opcua-client:test").unwrap();
+
+            let line = NodeId::new(namespace, "Line1");
+            let pump = NodeId::new(namespace, "Line1.Pump");
+            address_space.add_folder_with_id(&line, "Line1", "Line1", &NodeId::objects_folder_id());
+            address_space.add_folder_with_id(&pump, "Pump", "Pump", &line);
+
+            VariableBuilder::new(&NodeId::new(namespace, "Line1.Temperature"), "Temperature", "Temperature")
+                .data_type(DataTypeId::Double)
+                .value(21.5)
+                .organized_by(&line)
+                .insert(&mut address_space);
+            VariableBuilder::new(&NodeId::new(namespace, "Line1.Setpoint"), "Setpoint", "Setpoint")
+                .data_type(DataTypeId::Int32)
+                .value(10i32)
+                .writable()
+                .organized_by(&line)
+                .insert(&mut address_space);
+            VariableBuilder::new(&NodeId::new(namespace, "Line1.Pump.Speed"), "Speed", "Speed")
+                .data_type(DataTypeId::Float)
+                .value(1450.0f32)
+                .organized_by(&pump)
+                .insert(&mut address_space);
+            namespace
+        };
+
+        let server = Arc::new(RwLock::new(server));
+        tokio::spawn(Server::new_server_task(server.clone()));
+
+        Self { server, namespace, url: format!("opc.tcp://127.0.0.1:{}/", port) }
+    }
+
+    fn node(&self, id: &str) -> NodeId {
+        NodeId::new(self.namespace, id.to_string())
+    }
+
+    fn set(&self, id: &str, value: Variant) {
+        let address_space = self.server.read().address_space();
+        let mut address_space = address_space.write();
+        let now = DateTime::now();
+        address_space.find_variable_mut(self.node(id)).unwrap()
+            .set_value_direct(value, StatusCode::Good, &now, &now)
+            .unwrap();
+    }
+
+    fn get(&self, id: &str) -> Option<Variant> {
+        let address_space = self.server.read().address_space();
+        let address_space = address_space.read();
+        address_space.find_variable(self.node(id)).unwrap()
+            .value(TimestampsToReturn::Neither, NumericRange::None, &QualifiedName::null(), 0.0)
+            .value
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        self.server.write().abort();
+    }
+}
+
+fn driver_cfg(server: &TestServer, pki: &Path, enable_write: bool) -> serde_json::Value {
+    json!({
+        "endpoint_url": server.url,
+        "pki_dir": pki.join("client"),
+        "publishing_interval": "100ms",
+        "sampling_interval": "50ms",
+        "reconnect_interval": "200ms",
+        "enable_write": enable_write,
+        "browse": {
+            "root": server.node("Line1").to_string(),
+            "tag_prefix": "line1."
+        }
+    })
+}
+
+/// 等待指定点位满足条件的数据帧
+async fn wait_for(
+    rx: &mut broadcast::Receiver<FrameEnvelope>,
+    tag: &str,
+    predicate: impl Fn(&DataFrame) -> bool,
+) -> DataFrame {
+    tokio::time::timeout(Duration::from_secs(15), async {
+        loop {
+            let envelope = rx.recv().await.unwrap();
+            if envelope.kind() != FrameKind::Data {
+                continue;
+            }
+            let frame = envelope.into_data().unwrap();
+            if frame.tag == tag && predicate(&frame) {
+                return frame;
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("no matching frame for {}", tag))
+}
+
+/// 等待多个点位各自的首个数据帧，订阅初始值到达顺序不固定
+async fn wait_for_each(rx: &mut broadcast::Receiver<FrameEnvelope>, tags: &[&str]) -> HashMap<String, DataFrame> {
+    let mut found = HashMap::new();
+    tokio::time::timeout(Duration::from_secs(15), async {
+        while found.len() < tags.len() {
+            let envelope = rx.recv().await.unwrap();
+            if envelope.kind() != FrameKind::Data {
+                continue;
+            }
+            let frame = envelope.into_data().unwrap();
+            if tags.contains(&frame.tag.as_str()) {
+                found.entry(frame.tag.clone()).or_insert(frame);
+            }
+        }
+    })
+    .await
+    .unwrap_or_else(|_| panic!("missing initial frames, got {:?}", found.keys().collect::<Vec<_>>()));
+    found
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_browse_and_subscribe() {
+    let pki = tempfile::tempdir().unwrap();
+    let server = TestServer::start(&pki.path().join("server"));
+
+    let mut driver = OpcUaDriver::new();
+    driver.init(&driver_cfg(&server, pki.path(), false)).await.unwrap();
+
+    let (tx, mut rx) = broadcast::channel(1024);
+    let read_loop = tokio::spawn(async move { driver.read_loop(tx).await });
+
+    // 订阅建立后推送初始值，浏览路径构成点位名
+    let initial = wait_for_each(&mut rx, &["line1.Temperature", "line1.Pump.Speed"]).await;
+    let frame = &initial["line1.Temperature"];
+    assert_eq!(frame.value.as_ref().and_then(|v| v.to_f64()), Some(21.5));
+    assert_eq!(frame.qos, 2);
+    let frame = &initial["line1.Pump.Speed"];
+    assert_eq!(frame.value.as_ref().and_then(|v| v.to_f64()), Some(1450.0));
+
+    // 服务器值变化通过监控项推送
+    server.set("Line1.Temperature", Variant::Double(22.75));
+    let frame = wait_for(&mut rx, "line1.Temperature", |f| f.value.as_ref().and_then(|v| v.to_f64()) == Some(22.75)).await;
+    assert_eq!(frame.qos, 2);
+
+    read_loop.abort();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_write() {
+    let pki = tempfile::tempdir().unwrap();
+    let server = TestServer::start(&pki.path().join("server"));
+
+    let mut driver = OpcUaDriver::new();
+    driver.init(&driver_cfg(&server, pki.path(), true)).await.unwrap();
+
+    // 写入按节点DataType转换为Int32
+    driver.write(CmdFrame::new("line1.Setpoint", Value::int(42), "test")).await.unwrap();
+    assert_eq!(server.get("Line1.Setpoint"), Some(Variant::Int32(42)));
+    // 写入后回读
+    assert_eq!(driver.read_tag("line1.Setpoint").await.unwrap().and_then(|v| v.to_i64()), Some(42));
+    assert_eq!(driver.read_tag("line1.Temperature").await.unwrap().and_then(|v| v.to_f64()), Some(21.5));
+    assert!(driver.read_tag("line1.Unknown").await.is_err());
+
+    // 浏览发现的只读点位与未知点位拒绝写入
+    assert!(driver.write(CmdFrame::new("line1.Temperature", Value::float(1.0), "test")).await.is_err());
+    assert!(driver.write(CmdFrame::new("line1.Unknown", Value::int(1), "test")).await.is_err());
+    assert_eq!(server.get("Line1.Temperature"), Some(Variant::Double(21.5)));
+    driver.shutdown().await.unwrap();
+
+    // 未启用写入
+    let mut driver = OpcUaDriver::new();
+    driver.init(&driver_cfg(&server, pki.path(), false)).await.unwrap();
+    assert!(driver.write(CmdFrame::new("line1.Setpoint", Value::int(7), "test")).await.is_err());
+    assert_eq!(server.get("Line1.Setpoint"), Some(Variant::Int32(42)));
+}
+
+/// 加密端点的驱动配置，客户端生成自签名证书
+fn secure_cfg(server: &TestServer, pki: &Path, trusted_certs: Vec<std::path::PathBuf>) -> serde_json::Value {
+    let mut cfg = driver_cfg(server, pki, true);
+    cfg["security_policy"] = json!("Basic256Sha256");
+    cfg["security_mode"] = json!("sign_and_encrypt");
+    cfg["create_sample_keypair"] = json!(true);
+    cfg["trusted_certs"] = json!(trusted_certs);
+    cfg
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_server_certificate_trust_list() {
+    let pki = tempfile::tempdir().unwrap();
+    let server = TestServer::start(&pki.path().join("server"));
+
+    // 不在信任列表中的服务器证书被拒绝，不会自动信任
+    let mut driver = OpcUaDriver::new();
+    driver.init(&secure_cfg(&server, pki.path(), Vec::new())).await.unwrap();
+    assert!(driver.write(CmdFrame::new("line1.Setpoint", Value::int(1), "test")).await.is_err());
+    let rejected = std::fs::read_dir(pki.path().join("client/rejected")).unwrap().count();
+    assert_eq!(rejected, 1);
+    assert_eq!(std::fs::read_dir(pki.path().join("client/trusted")).unwrap().count(), 0);
+    driver.shutdown().await.unwrap();
+
+    // 配置信任列表后建立加密会话
+    let cfg = secure_cfg(&server, pki.path(), vec![pki.path().join("server/own/cert.der")]);
+    let mut driver = OpcUaDriver::new();
+    driver.init(&cfg).await.unwrap();
+    driver.write(CmdFrame::new("line1.Setpoint", Value::int(7), "test")).await.unwrap();
+    assert_eq!(server.get("Line1.Setpoint"), Some(Variant::Int32(7)));
+    driver.shutdown().await.unwrap();
+
+    // 信任列表中的证书无法读取时初始化失败
+    let missing = secure_cfg(&server, pki.path(), vec![pki.path().join("missing.der")]);
+    assert!(OpcUaDriver::new().init(&missing).await.is_err());
+}
+
+#[tokio::test]
+async fn test_init_validation() {
+    let mut driver = OpcUaDriver::new();
+
+    // 既无点位也无浏览
+    assert!(driver.init(&json!({})).await.is_err());
+    assert!(driver.init(&json!({"tags": [{"tag": "a", "node_id": "not a node"}]})).await.is_err());
+    assert!(driver.init(&json!({
+        "tags": [{"tag": "a", "node_id": "ns=2;i=1"}, {"tag": "a", "node_id": "ns=2;i=2"}]
+    })).await.is_err());
+    assert!(driver.init(&json!({"endpoint_url": "http://plc:4840", "tags": [{"tag": "a", "node_id": "ns=2;i=1"}]})).await.is_err());
+    assert!(driver.init(&json!({"security_policy": "Rot13", "tags": [{"tag": "a", "node_id": "ns=2;i=1"}]})).await.is_err());
+
+    driver.init(&json!({
+        "publishing_interval": "1s",
+        "tags": [
+            {"tag": "a", "node_id": "ns=2;s=A"},
+            {"tag": "b", "node_id": "ns=2;s=B", "publishing_interval": "200ms", "sampling_interval": "100ms", "queue_size": 5},
+            {"tag": "c", "node_id": "ns=2;s=C", "access": "w"}
+        ]
+    })).await.unwrap();
+
+    let tags = driver.tags();
+    assert_eq!(tags.len(), 3);
+    assert_eq!(tags[1].sampling_interval, Duration::from_millis(100));
+    assert_eq!(tags[1].queue_size, 5);
+
+    // 每个发布间隔一个订阅，只写点位不订阅
+    let plan = plan_subscriptions(tags);
+    assert_eq!(plan.len(), 2);
+    assert_eq!(plan[&Duration::from_millis(200)].len(), 1);
+    assert_eq!(plan[&Duration::from_secs(1)].iter().map(|t| t.tag.as_str()).collect::<Vec<_>>(), vec!["a"]);
+}
@@ -4,6 +4,7 @@
 Industrial IoT Edge Gateway - MVP-1 & MVP-2 Implementation
 */
 
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -14,21 +15,23 @@ use tracing::{info, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 // Core modules
-use frame_bus::{FrameSender, FrameReceiver, init as init_frame_bus};
+use frame_bus::{CommandPolicy, FrameSender, FrameReceiver, PolicyConfig, RetentionConfig, WalCodecConfig, WalConfig};
 use driver_manager::manager::DriverManager;
+use driver_manager::{CommandDispatcher, DispatcherConfig};
 use serde_json::json;
 
 // MQTT5 connector
 use mqtt5::config::MqttCfg;
-use mqtt5::connector::MqttConnector;
+use connector_manager::ConnectorManager;
 use dynamic_driver::DynamicDriverRegistry;
 use rest_api::ApiServer;
 use web_server::WebServer;
 use monitoring::{MetricsCollector, HealthMonitor, AlertManager};
-use production_config::{ProductionConfigManager, EnvironmentDetector};
+use production_config::{ConnectorConfig, ProductionConfigManager, EnvironmentDetector};
 use metrics_server::{MetricsServerConfig, start_background_server};
 // Force-link static drivers so their registration ctor runs
 use modbus_static as _;
+use opcua_client as _;
 // use actix_web::{App as ActixApp, HttpServer as ActixHttpServer, middleware::Logger as ActixLogger};
 // use advanced_features::AdvancedFeaturesManager;  // 暂时禁用
 
@@ -68,6 +71,10 @@ struct Gateway {
     _frame_receiver: FrameReceiver,
     dynamic_registry: DynamicDriverRegistry,
     driver_manager: DriverManager,
+    _command_dispatch: tokio::task::JoinHandle<()>,
+    _policy_observer: tokio::task::JoinHandle<()>,
+    connector_manager: Arc<ConnectorManager>,
+    web_api: Option<actix_web::dev::ServerHandle>,
     rest_api: ApiServer,
     web_server: WebServer,
     metrics_collector: MetricsCollector,
@@ -103,7 +110,7 @@ impl Gateway {
                 }
             });
 
-        // Actix web-gw-api 嵌入式服务暂不启用，保留Warp REST作为主API
+        // Actix web-gw-api 按WEBGW_EMBEDDED嵌入运行 (见下方北向连接器)，Warp REST仍为主API
         
         info!("Using WAL directory: {}", wal_dir);
         
@@ -113,11 +120,34 @@ impl Gateway {
             info!("Attempting to use in-memory Frame Bus as fallback");
         }
 
-        let (frame_sender, frame_receiver) = match init_frame_bus(1024, &wal_dir) {
+        // WAL帧按存储配置压缩与加密
+        let storage = &config_manager.config().storage;
+        if storage.enable_encryption && storage.encryption_key_file.is_none() {
+            return Err(anyhow::anyhow!("storage.enable_encryption requires storage.encryption_key_file"));
+        }
+        let wal_config = WalConfig {
+            codec: WalCodecConfig {
+                compression: storage.enable_compression,
+                key_file: storage.encryption_key_file.clone().filter(|_| storage.enable_encryption),
+                retired_key_files: storage.retired_encryption_key_files.clone(),
+            },
+            retention: RetentionConfig {
+                max_bytes: storage.wal_max_bytes,
+                max_age: Some(std::time::Duration::from_secs(storage.data_retention_days as u64 * 24 * 3600)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let (frame_sender, frame_receiver) = match frame_bus::init_with_wal_config(1024, &wal_dir, wal_config) {
             Ok((tx, rx)) => {
                 info!("Frame Bus initialized successfully with persistent WAL");
                 (tx, rx)
             },
+            // 内存降级路径不加密，启用加密时不能静默写入明文WAL
+            Err(e) if storage.enable_encryption => {
+                return Err(e.context("Failed to initialize encrypted WAL, refusing to fall back to an unencrypted Frame Bus"));
+            }
             Err(e) => {
                 warn!("Failed to initialize persistent Frame Bus: {}", e);
                 info!("Falling back to high-performance memory-only Frame Bus");
@@ -128,6 +158,21 @@ impl Gateway {
             }
         };
 
+        // 子进程驱动/插件通过本地套接字接入同一条总线
+        #[cfg(unix)]
+        if let Ok(socket_path) = std::env::var("FRAME_BUS_IPC_SOCKET") {
+            // FRAME_BUS_IPC_PEERS: `uid=主体,主体;uid=主体`，未配置时只接受网关同一用户的进程
+            let ipc_config = frame_bus::IpcServerConfig::default()
+                .parse_peers(&std::env::var("FRAME_BUS_IPC_PEERS").unwrap_or_default());
+            match ipc_config.and_then(|config| frame_bus::IpcServer::bind_with(&socket_path, config)) {
+                Ok(server) => {
+                    info!("Frame Bus IPC transport listening on {}", socket_path);
+                    server.spawn();
+                }
+                Err(e) => warn!("Failed to start Frame Bus IPC transport: {}", e),
+            }
+        }
+
         // Initialize dynamic driver registry
         let dynamic_registry = DynamicDriverRegistry::new()
             .context("Failed to initialize dynamic driver registry")?;
@@ -176,6 +221,9 @@ impl Gateway {
             .context("Failed to initialize health monitor")?;
         let alert_manager = AlertManager::new()
             .context("Failed to initialize alert manager")?;
+        if let Err(e) = frame_bus::set_wal_retention_alert_sink(alert_manager.wal_retention_sink()) {
+            warn!("Failed to register WAL retention alerts: {}", e);
+        }
 
         // Start metrics HTTP server (Prometheus) at :9090
         let metrics_server_config = MetricsServerConfig {
@@ -191,10 +239,23 @@ impl Gateway {
         //     .context("Failed to initialize advanced features")?;
 
         // Initialize Driver Manager and load Modbus static driver
-        let driver_manager = DriverManager::new().context("Failed to create DriverManager")?;
-
-        // Touch the modbus-static crate to ensure it's linked
+        // 写入前按命令策略校验 (限值、联锁、速率、选择-执行)，联锁与步长所需的当前值来自总线数据帧
+        let command_policy = Arc::new(load_command_policy()?);
+        let policy_observer = command_policy.spawn_observer("command-policy")
+            .context("Failed to start command policy observer")?;
+
+        // 总线上的命令帧按驱动配置中的可写点位路由到对应驱动实例
+        let command_dispatcher = Arc::new(
+            CommandDispatcher::new(DispatcherConfig::default()).with_policy(command_policy)
+        );
+        let command_dispatch = command_dispatcher.start()
+            .context("Failed to start command dispatcher")?;
+        let driver_manager = DriverManager::new().context("Failed to create DriverManager")?
+            .with_dispatcher(command_dispatcher);
+
+        // Touch the static driver crates to ensure they are linked
         let _ = modbus_static::meta();
+        let _ = opcua_client::meta();
 
         // Build Modbus driver config from environment
         let unit_id: u8 = std::env::var("MODBUS_UNIT_ID").ok().and_then(|v| v.parse().ok()).unwrap_or(1);
@@ -206,7 +267,17 @@ impl Gateway {
             "max_regs_per_req": 100u16,
             "retry": 3u8,
             "endian": "little",
-            "enable_write": false
+            "enable_write": false,
+            // 40001-40006对应地址0-5，Mock从站返回16位整数，按缩放换算为工程值
+            "points": [
+                { "tag": "sensor.temp1", "address": 0, "scale": "value / 10.0" },
+                { "tag": "sensor.float40001", "address": 0, "datatype": "float32" },
+                { "tag": "sensor.pressure1", "address": 1, "scale": "value / 100.0" },
+                { "tag": "sensor.flow1", "address": 2, "scale": "value / 10.0" },
+                { "tag": "sensor.temp2", "address": 3, "scale": "value / 10.0" },
+                { "tag": "sensor.pressure2", "address": 4, "scale": "value / 100.0" },
+                { "tag": "sensor.flow2", "address": 5, "scale": "value / 10.0" }
+            ]
         });
 
         let driver_id = "modbus_driver_1".to_string();
@@ -217,7 +288,7 @@ impl Gateway {
         // Start the driver read loop (supervisor will connect using MODBUS_ENDPOINT env)
         driver_manager.start_driver(&driver_id).await.context("Failed to start modbus-tcp driver")?;
 
-        // Initialize and start MQTT connector in background
+        // 北向连接器：按生产配置的connectors启动，未配置时由环境变量构建名为default的MQTT连接器
         let mqtt_broker = std::env::var("MQTT_BROKER").unwrap_or_else(|_| "tcp://emqx:1883".to_string());
         let mqtt_client_id = std::env::var("MQTT_CLIENT_ID").unwrap_or_default();
         let mqtt_username = std::env::var("MQTT_USERNAME").unwrap_or_default();
@@ -225,24 +296,66 @@ impl Gateway {
         let mqtt_topic_prefix = std::env::var("MQTT_TOPIC_PREFIX").unwrap_or_else(|_| "gateway".to_string());
 
         let mut mqtt_cfg = MqttCfg { broker: mqtt_broker, client_id: mqtt_client_id, username: mqtt_username, password: mqtt_password, topic_prefix: mqtt_topic_prefix, ..Default::default() };
+        // 云端下发命令经MQTT进入总线，默认关闭
+        mqtt_cfg.downlink.enabled = std::env::var("MQTT_DOWNLINK_ENABLED").map(|v| v == "true" || v == "1").unwrap_or(false);
+        // SCADA需要Sparkplug B时切换上行格式
+        if std::env::var("MQTT_PAYLOAD_MODE").as_deref() == Ok("sparkplug_b") {
+            mqtt_cfg.mode = mqtt5::config::PayloadMode::SparkplugB;
+            if let Ok(group_id) = std::env::var("MQTT_SPARKPLUG_GROUP") {
+                mqtt_cfg.sparkplug.group_id = group_id;
+            }
+        }
 
-        // Spawn connector tasks
-        tokio::spawn(async move {
-            let mut connector = MqttConnector::new(mqtt_cfg);
-            if let Err(e) = connector.init().await {
-                tracing::error!("MQTT connector init failed: {}", e);
-                return;
+        let mut connectors = config_manager.config().connectors.clone();
+        if connectors.is_empty() {
+            if mqtt_cfg.client_id.is_empty() {
+                mqtt_cfg.client_id = config_manager.config().general.instance_id.clone();
             }
-            if let Err(e) = connector.start().await {
-                tracing::error!("MQTT connector start terminated: {}", e);
+            let settings = match serde_json::to_value(&mqtt_cfg).context("Failed to encode MQTT settings")? {
+                serde_json::Value::Object(map) => map.into_iter().collect(),
+                _ => HashMap::new(),
+            };
+            connectors.insert("default".to_string(), ConnectorConfig {
+                connector_type: "mqtt5".to_string(),
+                enabled: true,
+                auto_connect: true,
+                connection_timeout_seconds: mqtt_cfg.timeout.as_secs() as u32,
+                keepalive_interval_seconds: mqtt_cfg.keep_alive.as_secs() as u32,
+                tag_filter: Vec::new(),
+                settings,
+            });
+        }
+
+        let connector_manager = Arc::new(ConnectorManager::new());
+        for (name, e) in connector_manager.load(&connectors).await {
+            warn!("Failed to load connector '{}': {}", name, e);
+        }
+        info!("Loaded {} northbound connector(s)", connector_manager.list_connectors().await.len());
+
+        // web-gw-api嵌入网关进程，/api/v1/connectors直接操作上面的管理器；
+        // 需要Postgres/InfluxDB，且WEBGW_HTTP_ADDR不能与Warp REST的8080端口冲突
+        let embedded = std::env::var("WEBGW_EMBEDDED").map(|v| v == "true" || v == "1").unwrap_or(false);
+        let web_api = if embedded {
+            match start_web_api(connector_manager.clone()).await {
+                Ok(handle) => Some(handle),
+                Err(e) => {
+                    warn!("Failed to start embedded web-gw-api: {:#}", e);
+                    None
+                }
             }
-        });
+        } else {
+            None
+        };
 
         Ok(Self {
             _frame_sender: frame_sender,
             _frame_receiver: frame_receiver,
             dynamic_registry,
             driver_manager,
+            _command_dispatch: command_dispatch,
+            _policy_observer: policy_observer,
+            connector_manager,
+            web_api,
             rest_api,
             web_server,
             metrics_collector,
@@ -319,6 +432,10 @@ impl Gateway {
         let _ = self.alert_manager.stop().await;
         let _ = self.health_monitor.stop().await;
         let _ = self.metrics_collector.stop().await;
+        if let Some(handle) = &self.web_api {
+            handle.stop(true).await;
+        }
+        self.connector_manager.shutdown().await;
         // Web server doesn't have stop method - simplified for compilation
         // REST API server doesn't have stop method - simplified for compilation
 
@@ -357,6 +474,35 @@ impl Gateway {
     }
 }
 
+/// 启动嵌入的web-gw-api，沿用其中间件与路由，北向连接器路由使用网关的管理器
+async fn start_web_api(connector_manager: Arc<ConnectorManager>) -> Result<actix_web::dev::ServerHandle> {
+    let config = web_gw_api::load_config().context("Failed to load web-gw-api configuration")?;
+    let state = web_gw_api::init_state(&config).await
+        .context("Failed to initialize web-gw-api state")?;
+    let server = web_gw_api::server::build(&config, state, Some(connector_manager))?;
+    info!("Embedded web-gw-api listening on {}", config.http_addr);
+
+    let handle = server.handle();
+    tokio::spawn(server);
+    Ok(handle)
+}
+
+/// 加载命令策略：`COMMAND_POLICY_FILE`指定的YAML文件，未指定时使用默认策略 (紧急命令要求选择-执行)
+fn load_command_policy() -> Result<CommandPolicy> {
+    let config = match std::env::var("COMMAND_POLICY_FILE") {
+        Ok(path) => {
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read command policy file {}", path))?;
+            let config: PolicyConfig = serde_yaml::from_str(&content)
+                .with_context(|| format!("Invalid command policy file {}", path))?;
+            info!("Loaded {} command policies from {}", config.policies.len(), path);
+            config
+        }
+        Err(_) => PolicyConfig::default(),
+    };
+    CommandPolicy::new(config).context("Invalid command policy")
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();